@@ -0,0 +1,291 @@
+use crate::{BTreeMap, FxHashMap};
+
+use std::borrow::{Borrow};
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::hash::{BuildHasher, Hash};
+use std::rc::{Rc};
+
+pub const COW_HASH_CHUNKS: usize = 64;
+
+// A copy-on-write hash map, split into a fixed number of chunks (by key
+// hash) that are shared between clones; a write only copies the one
+// chunk that it touches, and only if that chunk is still shared.
+pub struct CowMap<K, V> {
+  // NB: chunks are allocated lazily, upon the first insert.
+  chunks: Vec<Rc<FxHashMap<K, V>>>,
+  len:    usize,
+}
+
+impl<K, V> Default for CowMap<K, V> {
+  fn default() -> CowMap<K, V> {
+    CowMap{
+      chunks: Vec::new(),
+      len:    0,
+    }
+  }
+}
+
+impl<K, V> Clone for CowMap<K, V> {
+  fn clone(&self) -> CowMap<K, V> {
+    CowMap{
+      chunks: self.chunks.clone(),
+      len:    self.len,
+    }
+  }
+}
+
+impl<K: Hash + Eq + Debug, V: Debug> Debug for CowMap<K, V> {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    f.debug_map().entries(self.iter()).finish()
+  }
+}
+
+impl<K: Hash + Eq, V> CowMap<K, V> {
+  #[inline]
+  fn _chunk<Q: ?Sized + Hash>(&self, k: &Q) -> usize {
+    let h = self.chunks[0].hasher().hash_one(k);
+    // NB: use the high bits, since the low bits also pick the bucket.
+    (h >> 58) as usize % COW_HASH_CHUNKS
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn contains_key<Q: ?Sized + Hash + Eq>(&self, k: &Q) -> bool where K: Borrow<Q> {
+    self.get(k).is_some()
+  }
+
+  pub fn get<Q: ?Sized + Hash + Eq>(&self, k: &Q) -> Option<&V> where K: Borrow<Q> {
+    if self.chunks.is_empty() {
+      return None;
+    }
+    self.chunks[self._chunk(k)].get(k)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item=(&K, &V)> {
+    self.chunks.iter().flat_map(|chunk| chunk.iter())
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item=&K> {
+    self.iter().map(|(k, _)| k)
+  }
+
+  pub fn values(&self) -> impl Iterator<Item=&V> {
+    self.iter().map(|(_, v)| v)
+  }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> CowMap<K, V> {
+  pub fn get_mut<Q: ?Sized + Hash + Eq>(&mut self, k: &Q) -> Option<&mut V> where K: Borrow<Q> {
+    if !self.contains_key(k) {
+      return None;
+    }
+    let c = self._chunk(k);
+    Rc::make_mut(&mut self.chunks[c]).get_mut(k)
+  }
+
+  pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+    if self.chunks.is_empty() {
+      self.chunks.resize_with(COW_HASH_CHUNKS, Default::default);
+    }
+    let c = self._chunk(&k);
+    let prev_v = Rc::make_mut(&mut self.chunks[c]).insert(k, v);
+    if prev_v.is_none() {
+      self.len += 1;
+    }
+    prev_v
+  }
+
+  pub fn remove<Q: ?Sized + Hash + Eq>(&mut self, k: &Q) -> Option<V> where K: Borrow<Q> {
+    if !self.contains_key(k) {
+      return None;
+    }
+    let c = self._chunk(k);
+    let prev_v = Rc::make_mut(&mut self.chunks[c]).remove(k);
+    if prev_v.is_some() {
+      self.len -= 1;
+    }
+    prev_v
+  }
+
+  // NB: the reservation is spread evenly over the chunks, so it is only
+  // a hint if the keys do not hash evenly.
+  pub fn reserve(&mut self, additional: usize) {
+    if self.chunks.is_empty() {
+      self.chunks.resize_with(COW_HASH_CHUNKS, Default::default);
+    }
+    let chunk_additional = (additional + COW_HASH_CHUNKS - 1) / COW_HASH_CHUNKS;
+    for chunk in self.chunks.iter_mut() {
+      Rc::make_mut(chunk).reserve(chunk_additional);
+    }
+  }
+
+  pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+    let mut len = 0;
+    for chunk in self.chunks.iter_mut() {
+      if !chunk.is_empty() {
+        Rc::make_mut(chunk).retain(|k, v| f(k, v));
+      }
+      len += chunk.len();
+    }
+    self.len = len;
+  }
+}
+
+// A key whose chunk index is monotone in the key order, so that a
+// `CowOrdMap` can be iterated in key order chunk by chunk.
+pub trait CowChunkKey: Ord {
+  fn cow_chunk(&self) -> u64;
+}
+
+// A copy-on-write ordered map, split into chunks of adjacent keys that
+// are shared between clones (see `CowMap`).
+pub struct CowOrdMap<K, V> {
+  chunks: BTreeMap<u64, Rc<BTreeMap<K, V>>>,
+  len:    usize,
+}
+
+impl<K, V> Default for CowOrdMap<K, V> {
+  fn default() -> CowOrdMap<K, V> {
+    CowOrdMap{
+      chunks: BTreeMap::new(),
+      len:    0,
+    }
+  }
+}
+
+impl<K, V> Clone for CowOrdMap<K, V> {
+  fn clone(&self) -> CowOrdMap<K, V> {
+    CowOrdMap{
+      chunks: self.chunks.clone(),
+      len:    self.len,
+    }
+  }
+}
+
+impl<K: Debug, V: Debug> Debug for CowOrdMap<K, V> {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    f.debug_map().entries(self.chunks.values().flat_map(|chunk| chunk.iter())).finish()
+  }
+}
+
+impl<K: CowChunkKey, V> CowOrdMap<K, V> {
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn contains_key(&self, k: &K) -> bool {
+    self.get(k).is_some()
+  }
+
+  pub fn get(&self, k: &K) -> Option<&V> {
+    self.chunks.get(&k.cow_chunk()).and_then(|chunk| chunk.get(k))
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item=(&K, &V)> {
+    self.chunks.values().flat_map(|chunk| chunk.iter())
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item=&K> {
+    self.iter().map(|(k, _)| k)
+  }
+
+  pub fn values(&self) -> impl Iterator<Item=&V> {
+    self.iter().map(|(_, v)| v)
+  }
+}
+
+impl<K: CowChunkKey + Clone, V: Clone> CowOrdMap<K, V> {
+  pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+    if !self.contains_key(k) {
+      return None;
+    }
+    let chunk = self.chunks.get_mut(&k.cow_chunk()).unwrap();
+    Rc::make_mut(chunk).get_mut(k)
+  }
+
+  pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+    let chunk = self.chunks.entry(k.cow_chunk()).or_default();
+    let prev_v = Rc::make_mut(chunk).insert(k, v);
+    if prev_v.is_none() {
+      self.len += 1;
+    }
+    prev_v
+  }
+
+  pub fn remove(&mut self, k: &K) -> Option<V> {
+    if !self.contains_key(k) {
+      return None;
+    }
+    let c = k.cow_chunk();
+    let chunk = self.chunks.get_mut(&c).unwrap();
+    let prev_v = Rc::make_mut(chunk).remove(k);
+    if chunk.is_empty() {
+      self.chunks.remove(&c);
+    }
+    if prev_v.is_some() {
+      self.len -= 1;
+    }
+    prev_v
+  }
+}
+
+// A copy-on-write ordered set (see `CowOrdMap`).
+pub struct CowOrdSet<K> {
+  inner: CowOrdMap<K, ()>,
+}
+
+impl<K> Default for CowOrdSet<K> {
+  fn default() -> CowOrdSet<K> {
+    CowOrdSet{inner: CowOrdMap::default()}
+  }
+}
+
+impl<K> Clone for CowOrdSet<K> {
+  fn clone(&self) -> CowOrdSet<K> {
+    CowOrdSet{inner: self.inner.clone()}
+  }
+}
+
+impl<K: Debug> Debug for CowOrdSet<K> {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    f.debug_set().entries(self.inner.chunks.values().flat_map(|chunk| chunk.keys())).finish()
+  }
+}
+
+impl<K: CowChunkKey> CowOrdSet<K> {
+  pub fn len(&self) -> usize {
+    self.inner.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.inner.is_empty()
+  }
+
+  pub fn contains(&self, k: &K) -> bool {
+    self.inner.contains_key(k)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item=&K> {
+    self.inner.keys()
+  }
+}
+
+impl<K: CowChunkKey + Clone> CowOrdSet<K> {
+  pub fn insert(&mut self, k: K) -> bool {
+    self.inner.insert(k, ()).is_none()
+  }
+
+  pub fn remove(&mut self, k: &K) -> bool {
+    self.inner.remove(k).is_some()
+  }
+}
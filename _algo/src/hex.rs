@@ -43,6 +43,21 @@ pub fn encode_rev_upper(bytes: &[u8]) -> String {
   s
 }
 
+#[inline]
+pub fn decode_with(chars: &[u8], s: &str) -> Option<Vec<u8>> {
+  let s = s.as_bytes();
+  if s.len() % 2 != 0 {
+    return None;
+  }
+  let mut buf = Vec::with_capacity(s.len() / 2);
+  for p in (0 .. s.len()).step_by(2) {
+    let hi = chars.iter().position(|&c| c == s[p])?;
+    let lo = chars.iter().position(|&c| c == s[p + 1])?;
+    buf.push(((hi << 4) | lo) as u8);
+  }
+  Some(buf)
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 #[repr(u8)]
 pub enum HexFormat {
@@ -110,4 +125,22 @@ impl HexFormat {
       }
     }
   }
+
+  #[inline]
+  pub fn from_str(self, s: &str) -> Option<Vec<u8>> {
+    match self {
+      HexFormat::Lower => {
+        decode_with(LOWER_CHARS, s)
+      }
+      HexFormat::Upper => {
+        decode_with(UPPER_CHARS, s)
+      }
+      HexFormat::RevLower => {
+        decode_with(REV_LOWER_CHARS, s)
+      }
+      HexFormat::RevUpper => {
+        decode_with(REV_UPPER_CHARS, s)
+      }
+    }
+  }
 }
@@ -19,6 +19,7 @@ pub mod arc;
 pub mod blake2s;
 pub mod base64;
 pub mod cell;
+pub mod cow;
 pub mod extract;
 pub mod hex;
 pub mod json;
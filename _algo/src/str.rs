@@ -273,9 +273,9 @@ impl<'this, S: ?Sized + AsRef<str>> StrParser<'this, S> {
       /*assert_eq!(self.pos, self.start);*/
       let next_pos = self.start + d_len;
       if buf.len() >= next_pos {
-        if buf.get(self.pos .. next_pos).unwrap() ==
-           self.cfg.delim
-        {
+        // NB: `next_pos` may not be a char boundary (e.g. a multibyte
+        // char following a shorter delim).
+        if buf.get(self.pos .. next_pos) == Some(self.cfg.delim) {
           self.pos = next_pos;
           /*assert_eq!(self.pos, self.start + d_len);*/
         } else {
@@ -299,6 +299,7 @@ impl<'this, S: ?Sized + AsRef<str>> StrParser<'this, S> {
       if escape {
         match c {
           Some('"') => res.push('"'),
+          Some('\'') => res.push('\''),
           Some('\\') => res.push('\\'),
           Some('/') => res.push('/'),
           Some('b') => res.push('\x08'),
@@ -343,9 +344,7 @@ impl<'this, S: ?Sized + AsRef<str>> StrParser<'this, S> {
           let buf = self.buf.as_ref();
           let next_pos = self.pos + d_len - d_off;
           if buf.len() >= next_pos {
-            if buf.get(self.pos .. next_pos).unwrap() ==
-               self.cfg.delim.get(d_off .. ).unwrap()
-            {
+            if buf.get(self.pos .. next_pos) == self.cfg.delim.get(d_off .. ) {
               self.pos = next_pos;
               return Ok(res);
             }
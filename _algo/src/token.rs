@@ -3,7 +3,7 @@ use crate::str::{len_utf8};
 
 use serde::{Deserialize};
 
-use std::collections::{BTreeMap};
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum TokenCategory {
@@ -21,7 +21,10 @@ pub enum TokenCategory {
   Particle,
 }
 
-#[derive(Default)]
+// A char trie map. A node is keyed by the pair of (1) the id of the
+// node's parent prefix (0 for the empty prefix) and (2) the node's last
+// char; `next` maps a node to the ids of its children.
+#[derive(Clone, Default, Debug)]
 pub struct TokenCharTrieMap<V> {
   next: BTreeMap<(u32, char), BTreeMap<char, u32>>,
   map:  BTreeMap<(u32, char), V>,
@@ -35,98 +38,81 @@ impl<V> TokenCharTrieMap<V> {
     x
   }
 
-  /*pub fn insert<K: AsRef<str>>(&mut self, key: K, value: V) {
+  fn _find<K: AsRef<str>>(&self, key: K) -> Option<(u32, char)> {
     let key = key.as_ref();
-    assert!(key.len() > 0);
-    let prev_x = 0;
-    let prev_c = Err(());
-    let cur_x = 0;
-    let cur = key.chars().next().unwrap();
-    self._insert(prev_x, prev_c, cur_x, cur, key.get(len_utf8(cur as _) .. ).unwrap(), value)
-  }
-
-  pub fn _insert<K: AsRef<str>>(&mut self, prev_x: u32, prev_c: Result<char, ()>, cur_x: u32, cur_c: char, rest: &str, value: V) {
-    if let Ok(prev_c) = prev_c {
-      match self.next.get_mut(&(prev_x, prev_c)) {
-        None => {
-          let mut next_set = BTreeMap::new();
-          next_set.insert(cur_c, cur_x);
-          self.next.insert((prev_x, prev_c), next_set);
-        }
-        Some(next_set) => {
-          next_set.insert(cur_c, cur_x);
+    let mut prev_c: Result<char, ()> = Err(());
+    let mut cur = 0;
+    for c in key.chars() {
+      if let Ok(prev_c) = prev_c {
+        match self.next.get(&(cur, prev_c)).and_then(|next_set| next_set.get(&c)) {
+          Some(&next_cur) => {
+            cur = next_cur;
+          }
+          None => return None
         }
       }
+      prev_c = Ok(c);
     }
-    if rest.is_empty() {
-      self.map.insert((cur_x, value));
-    } else {
-      let next = rest.get(len_utf8(cur_c as _) .. ).unwrap();
-      let c = next.chars().next().unwrap();
-      let x = self._fresh();
-      self._insert(cur_x, Ok(cur_c), x, c, next, value)
-    }
-  }*/
+    prev_c.ok().map(|c| (cur, c))
+  }
 
-  pub fn insert<K: AsRef<str>>(&mut self, key: K, value: V) {
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  pub fn insert<K: AsRef<str>>(&mut self, key: K, value: V) -> Option<V> {
     let key = key.as_ref();
-    assert!(key.len() > 0);
-    let mut prev_cur = 0;
+    // NB: the empty key has no node (see `_find`), so its insert is
+    // ignored.
+    if key.is_empty() {
+      return None;
+    }
     let mut prev_c: Result<char, ()> = Err(());
     let mut cur = 0;
-    for (pos, c) in key.char_indices() {
-      if pos > 0 {
-        match self.next.get_mut(&(prev_cur, prev_c.unwrap())) {
-          Some(next_set) => {
-            match next_set.get(&c) {
-              Some(&next_cur) => {
-                prev_cur = cur;
-                cur = next_cur;
-              }
-              None => {
-                prev_cur = cur;
-                /*cur = self._fresh();*/
-                cur = self.ctr + 1;
-                self.ctr = cur;
-                next_set.insert(c, cur);
-              }
-            }
-          }
+    for c in key.chars() {
+      if let Ok(prev_c) = prev_c {
+        let next_cur = match self.next.get(&(cur, prev_c)).and_then(|next_set| next_set.get(&c)) {
+          Some(&next_cur) => next_cur,
           None => {
-            let mut next_set = BTreeMap::new();
-            prev_cur = cur;
-            /*cur = self._fresh();*/
-            cur = self.ctr + 1;
-            self.ctr = cur;
-            next_set.insert(c, cur);
-            self.next.insert((prev_cur, prev_c.unwrap()), next_set);
+            let next_cur = self._fresh();
+            self.next.entry((cur, prev_c)).or_default().insert(c, next_cur);
+            next_cur
           }
-        }
+        };
+        cur = next_cur;
       }
       prev_c = Ok(c);
     }
-    self.map.insert((cur, prev_c.unwrap()), value);
+    self.map.insert((cur, prev_c.unwrap()), value)
+  }
+
+  pub fn get<K: AsRef<str>>(&self, key: K) -> Option<&V> {
+    self._find(key).and_then(|node| self.map.get(&node))
+  }
+
+  // NB: the trie nodes of a removed key are retained.
+  pub fn remove<K: AsRef<str>>(&mut self, key: K) -> Option<V> {
+    self._find(key).and_then(|node| self.map.remove(&node))
   }
 }
 
 impl<V: Clone> TokenCharTrieMap<V> {
+  // All the keys that are prefixes of `pat`, as pairs of the prefix
+  // length (in bytes) and the value, in order of increasing length.
   pub fn prefix_match<P: AsRef<str>>(&self, pat: P) -> Vec<(usize, V)> {
     let mut mats = Vec::new();
     let pat = pat.as_ref();
-    let mut prev_cur = 0;
     let mut prev_c: Result<char, ()> = Err(());
     let mut cur = 0;
     for (pos, c) in pat.char_indices() {
-      if pos > 0 {
-        match self.next.get(&(prev_cur, prev_c.unwrap())) {
-          Some(next_set) => {
-            match next_set.get(&c) {
-              Some(&next_cur) => {
-                prev_cur = cur;
-                cur = next_cur;
-              }
-              None => break
-            }
+      if let Ok(prev_c) = prev_c {
+        match self.next.get(&(cur, prev_c)).and_then(|next_set| next_set.get(&c)) {
+          Some(&next_cur) => {
+            cur = next_cur;
           }
           None => break
         }
@@ -138,4 +124,46 @@ impl<V: Clone> TokenCharTrieMap<V> {
     }
     mats
   }
+
+  pub fn longest_prefix<P: AsRef<str>>(&self, pat: P) -> Option<(usize, V)> {
+    self.prefix_match(pat).pop()
+  }
+
+  // All the keys that have `pat` as a prefix (incl. `pat` itself), w/
+  // their values, in key order.
+  pub fn iter_prefix<P: AsRef<str>>(&self, pat: P) -> Vec<(String, V)> {
+    let pat = pat.as_ref();
+    let mut mats = Vec::new();
+    if pat.is_empty() {
+      // NB: the roots are the nodes w/ the empty prefix (id 0).
+      let roots: BTreeSet<char> = self.map.keys().chain(self.next.keys())
+        .filter(|&&(x, _)| x == 0)
+        .map(|&(_, c)| c)
+        .collect();
+      for c in roots.into_iter() {
+        let mut key = String::new();
+        key.push(c);
+        self._iter_node((0, c), &mut key, &mut mats);
+      }
+      return mats;
+    }
+    if let Some(node) = self._find(pat) {
+      let mut key = pat.to_string();
+      self._iter_node(node, &mut key, &mut mats);
+    }
+    mats
+  }
+
+  fn _iter_node(&self, node: (u32, char), key: &mut String, mats: &mut Vec<(String, V)>) {
+    if let Some(v) = self.map.get(&node) {
+      mats.push((key.clone(), v.clone()));
+    }
+    if let Some(next_set) = self.next.get(&node) {
+      for (&c, &next_cur) in next_set.iter() {
+        key.push(c);
+        self._iter_node((next_cur, c), key, mats);
+        key.pop();
+      }
+    }
+  }
 }
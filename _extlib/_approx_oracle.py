@@ -704,6 +704,7 @@ class ApproxOracleInterface:
             self.worker = ApproxOracleWorker(self.concurrency)
         self._work_set = set()
         self._done_set = set()
+        self._work_query = dict()
 
     def __len__(self) -> int:
         return len(self._work_set)
@@ -719,6 +720,21 @@ class ApproxOracleInterface:
         work_item = _ApproxOracleWorkItem(item)
         w = self.worker._poolexec.submit(_try_query, work_item, key=item.key)
         self._work_set.add(w)
+        self._work_query[w] = item.query
+
+    def cancel(self, query) -> bool:
+        # NB: only a work item that has not yet started may be cancelled;
+        # try the most recently submitted first, as it is the least likely
+        # to have started.
+        for w, q in reversed(list(self._work_query.items())):
+            if w not in self._work_set or q != query:
+                continue
+            if w.cancel():
+                print(f"DEBUG: ApproxOracleInterface.cancel: cancelled")
+                self._work_set.discard(w)
+                del self._work_query[w]
+                return True
+        return False
 
     def poll(self, timeout=None) -> ApproxOracleItem:
         print(f"DEBUG: ApproxOracleInterface.poll")
@@ -739,6 +755,7 @@ class ApproxOracleInterface:
             return None
         print(f"DEBUG: ApproxOracleInterface.poll: completed")
         w = self._done_set.pop()
+        self._work_query.pop(w, None)
         work_item = w.result()
         if False:
             item = work_item.item
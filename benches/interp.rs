@@ -0,0 +1,34 @@
+extern crate pythia;
+
+use pythia::bench::*;
+use pythia::interp::bench::*;
+use pythia::test_data::*;
+
+// The interp hot-path benchmarks; see `pythia::bench` for how to filter
+// the benchmarks and compare against a saved baseline.
+fn main() {
+  let mut runner = BenchRunner::from_args();
+  for depth in [16, 256, 4096] {
+    runner.bench_function(&format!("unifier/find_cold/{}", depth), |b| bench_unifier_find_cold(b, depth));
+    runner.bench_function(&format!("unifier/find_warm/{}", depth), |b| bench_unifier_find_warm(b, depth));
+    runner.bench_function(&format!("unifier/unify_chain/{}", depth), |b| bench_unifier_unify_chain(b, depth));
+  }
+  for len in [2, 4, 8] {
+    runner.bench_function(&format!("tuple/alloc/{}", len), |b| bench_tuple_alloc(b, len));
+    runner.bench_function(&format!("tuple/cons_hit/{}", len), |b| bench_tuple_cons_hit(b, len));
+  }
+  for (width, depth) in [(2, 8), (16, 2), (64, 1)] {
+    let src = backtrack_churn_src(width, depth);
+    runner.bench_function(&format!("backtrack/churn/{}x{}", width, depth), |b| bench_resume(b, &src));
+  }
+  // NB: the interp test modules that fail to cold start are skipped.
+  let test_data_cfg = TestDataConfig::interp_tests();
+  for item in test_data_cfg.iter_interp_tests() {
+    let mut interp = pythia::interp::FastInterp::default();
+    if interp.pre_init().is_err() || interp.cold_start(&item.src).is_err() {
+      continue;
+    }
+    runner.bench_function(&format!("resume/{}", item.key), |b| bench_resume(b, &item.src));
+  }
+  runner.finish().unwrap();
+}
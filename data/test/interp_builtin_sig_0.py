@@ -0,0 +1,6 @@
+x = add(1, 2)
+expect x == 3
+y = max(3, 1, 2)
+expect y == 3
+z = random_int(1, 1)
+expect z == 1
@@ -0,0 +1,7 @@
+cases:
+    case:
+        b = True
+        failure()
+    case:
+        b = False
+expect b == False
@@ -0,0 +1,23 @@
+k = choice(2)
+if k = 0:
+    b = True
+    defproc f(x):
+        pass
+    f(None)
+    s = "abc"
+    l = [1, 2]
+    print = None
+    t = TokenTrie()
+    failure()
+else:
+    f = None
+    s = 1
+    l = 3
+    t = 2
+    b = False
+print(b)
+b = False
+assert s = 1
+assert l = 3
+assert t = 2
+assert f = None
@@ -0,0 +1,14 @@
+defproc f(x, y):
+    x = y
+defproc g(x):
+    f(x, abc)
+    x = abc
+defproc h(n):
+    if n = 0:
+        pass
+    else:
+        h(0)
+x = "xyz"
+g("abc")
+expect x == "xyz"
+h(1)
@@ -0,0 +1,6 @@
+defproc f(y):
+    fresh x
+    x = y
+x = "abc"
+f("xyz")
+expect x == "abc"
@@ -0,0 +1,4 @@
+global print
+print = 1
+print(2)
+expect print == 1
@@ -0,0 +1,5 @@
+t = TokenTrie()
+t.insert("a", 1)
+expect t.lookup("a") == 1
+expect t.lookup("b") == None
+t.root
@@ -0,0 +1,6 @@
+foo.bar = 1
+foo.bar = 1
+foo.baz = foo.bar
+print(foo.baz)
+expect foo.bar == 1
+expect foo.baz == 1
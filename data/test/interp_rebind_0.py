@@ -0,0 +1,10 @@
+x = 1
+x .= 2
+print(x)
+3 =. y
+print(y)
+z = w :(w := 4)
+print(z)
+expect x == 2
+expect y == 3
+expect z == 4
@@ -0,0 +1 @@
+x = f(x)
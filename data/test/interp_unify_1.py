@@ -0,0 +1,9 @@
+-- pragma: unify = occurs_check
+cases:
+    case:
+        x = f(x)
+        b = True
+    case:
+        x = f(y)
+        b = False
+expect b == False
@@ -0,0 +1,11 @@
+-- pragma: unify = ground
+cases:
+    case:
+        x = y
+        b = True
+    case:
+        x = 1
+        y = x
+        b = False
+expect b == False
+expect y == 1
@@ -0,0 +1,4 @@
+x = 1
+assert x = 2
+expect x == 3
+expect x == 1
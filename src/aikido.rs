@@ -5,18 +5,27 @@ use crate::algo::json::{JsonFormat};
 use crate::clock::{Timestamp};
 
 use getrandom::{getrandom};
-use serde::{Serialize};
+use serde::{Serialize, Deserialize};
+use serde::de::{Deserializer, Error as DError};
 use serde::ser::{Serializer, SerializeStruct};
 
 use std::fmt::{Display, Debug, Formatter, Result as FmtResult};
-use std::fs::{OpenOptions, create_dir};
-use std::io::{BufWriter, Write};
+use std::fs::{File, OpenOptions, create_dir, create_dir_all, read, rename, write};
+use std::io::{BufRead, BufReader, BufWriter, Error as IoError, Write};
 use std::mem::{replace};
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 
 pub const HASH_SIZE: usize = 32;
 pub const SHORT_HASH_SIZE: usize = 16;
 
+fn _deserialize_hex<'d, D: Deserializer<'d>>(fmt: HexFormat, len: usize, deserializer: D) -> Result<Box<[u8]>, D::Error> {
+  let s = String::deserialize(deserializer)?;
+  match fmt.from_str(&s) {
+    Some(buf) if buf.len() == len => Ok(buf.into()),
+    _ => Err(<D::Error as DError>::custom(format!("invalid hash: {:?}", s)))
+  }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FrameId {
   inner: Box<[u8]>,
@@ -40,6 +49,13 @@ impl Serialize for FrameId {
   }
 }
 
+impl<'d> Deserialize<'d> for FrameId {
+  fn deserialize<D: Deserializer<'d>>(deserializer: D) -> Result<FrameId, D::Error> {
+    let inner = _deserialize_hex(HexFormat::default().lower().rev(), SHORT_HASH_SIZE, deserializer)?;
+    Ok(FrameId{inner})
+  }
+}
+
 /*impl Default for FrameId {
 }*/
 
@@ -91,6 +107,13 @@ impl Serialize for SnapshotHash {
   }
 }
 
+impl<'d> Deserialize<'d> for SnapshotHash {
+  fn deserialize<D: Deserializer<'d>>(deserializer: D) -> Result<SnapshotHash, D::Error> {
+    let inner = _deserialize_hex(HexFormat::default().lower(), HASH_SIZE, deserializer)?;
+    Ok(SnapshotHash{inner})
+  }
+}
+
 /*impl Default for SnapshotHash {
 }*/
 
@@ -144,6 +167,13 @@ impl Serialize for ContentHash {
   }
 }
 
+impl<'d> Deserialize<'d> for ContentHash {
+  fn deserialize<D: Deserializer<'d>>(deserializer: D) -> Result<ContentHash, D::Error> {
+    let inner = _deserialize_hex(HexFormat::default().lower(), HASH_SIZE, deserializer)?;
+    Ok(ContentHash{inner})
+  }
+}
+
 impl From<[u8; 32]> for ContentHash {
   fn from(buf: [u8; 32]) -> ContentHash {
     ContentHash{
@@ -170,7 +200,7 @@ impl ContentHash {
   }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct FramePointer {
   init: SnapshotHash,
   last: SnapshotHash,
@@ -183,9 +213,17 @@ impl FramePointer {
       last: SnapshotHash::root(),
     }
   }
+
+  pub fn init(&self) -> &SnapshotHash {
+    &self.init
+  }
+
+  pub fn last(&self) -> &SnapshotHash {
+    &self.last
+  }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
 pub enum SnapshotMarker {
   FreshFrame,
   Merge,
@@ -194,7 +232,7 @@ pub enum SnapshotMarker {
 }
 
 // TODO
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct SnapshotMetadata {
   frame: FrameId,
   prev: Vec<SnapshotHash>,
@@ -231,13 +269,7 @@ impl Snapshot {
   pub fn force_rehash(&mut self) {
     // TODO: other data...
     self.hashdata.rehash();
-    let mut merkle_buf = Vec::new();
-    merkle_buf.extend(&*self.metadata.frame.inner);
-    merkle_buf.extend(&*self.metadata.prev[0].inner);
-    merkle_buf.extend(&*self.hashdata.hash.inner);
-    let mut h = Blake2s::new_hash();
-    h.hash_bytes(&merkle_buf);
-    self.hash = SnapshotHash::from(h.finalize());
+    self.hash = _snapshot_hash(&self.metadata, &self.hashdata.hash);
     self.rehash = false;
   }
 }
@@ -453,6 +485,30 @@ impl Object {
     }
   }
 
+  pub fn tree_insert(&mut self, k: &str, v: Object) {
+    self.rehash = true;
+    match &mut self.data {
+      &mut ObjectData::Tree(ref mut kvs) => {
+        kvs.insert(k.to_owned(), v);
+      }
+      _ => panic!("bug")
+    }
+  }
+
+  pub fn tree_get(&self, k: &str) -> Option<&Object> {
+    match &self.data {
+      &ObjectData::Tree(ref kvs) => kvs.get(k),
+      _ => None
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match &self.data {
+      &ObjectData::String(ref s) => Some(s),
+      _ => None
+    }
+  }
+
   pub fn rehash(&mut self, /*store: &mut Store*/) {
     if !self.rehash {
       return
@@ -461,6 +517,11 @@ impl Object {
     self.rehash = false;
   }
 
+  pub fn content_hash(&mut self) -> ContentHash {
+    self.rehash();
+    self.hash.clone()
+  }
+
   pub fn mut_string(&mut self) -> &mut String {
     self.rehash = true;
     match &self.data {
@@ -479,19 +540,374 @@ impl Object {
 
 pub type Repo = Store;
 
+/// Strings at least this large are spilled out of the journal into
+/// separate blob files under `objects/`.
+pub const BLOB_SPILL_SIZE: usize = 0x10000;
+
+fn _tree_hash<'a, I: Iterator<Item=(&'a String, &'a ContentHash)>>(kvs: I) -> ContentHash {
+  let mut htree = Blake2s::new_hash();
+  for (k, hv) in kvs {
+    let mut h = Blake2s::new_hash();
+    h.hash_bytes(k.as_bytes());
+    let hk = ContentHash::from(h.finalize());
+    htree.hash_bytes(hk._as_data_bytes());
+    htree.hash_bytes(hv._as_data_bytes());
+  }
+  ContentHash::from(htree.finalize())
+}
+
+fn _snapshot_hash(metadata: &SnapshotMetadata, data: &ContentHash) -> SnapshotHash {
+  assert!(metadata.prev.len() >= 1);
+  let mut merkle_buf = Vec::new();
+  merkle_buf.extend(&*metadata.frame.inner);
+  // NB: with a single prev, this coincides with the original
+  // (frame, prev, data) merkle layout.
+  for prev in metadata.prev.iter() {
+    merkle_buf.extend(&*prev.inner);
+  }
+  merkle_buf.extend(&*data.inner);
+  let mut h = Blake2s::new_hash();
+  h.hash_bytes(&merkle_buf);
+  SnapshotHash::from(h.finalize())
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+  IO,
+  Parse(usize),
+  Hash(usize),
+  Missing(usize),
+}
+
+// NB: the on-disk journal (`_log.jsonl`) is append-only; every record
+// refers only to records that precede it, so the store is reconstructed
+// by a single forward replay.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "_type")]
+enum _LogRecord {
+  #[serde(rename = "frame")]
+  Frame {
+    id: FrameId,
+    init: SnapshotHash,
+    last: SnapshotHash,
+  },
+  #[serde(rename = "snapshot")]
+  Snapshot {
+    hash: SnapshotHash,
+    metadata: SnapshotMetadata,
+    data: ContentHash,
+  },
+  #[serde(rename = "object/string")]
+  String {
+    hash: ContentHash,
+    content: String,
+  },
+  #[serde(rename = "object/blob")]
+  Blob {
+    hash: ContentHash,
+    len: usize,
+  },
+  #[serde(rename = "object/tree")]
+  Tree {
+    hash: ContentHash,
+    up: ContentHash,
+    key: String,
+  },
+}
+
+// NB: objects are stored shallowly, keyed by content hash; trees refer
+// to their children only by hash, so identical subtrees are shared
+// between all snapshots that contain them.
+#[derive(Clone, Debug)]
+enum _StoredObject {
+  String(String),
+  Blob(usize),
+  Tree(BTreeMap<String, ContentHash>),
+}
+
+pub type TreePath = Vec<String>;
+
+#[derive(Clone, Default, Debug)]
+pub struct TreeDiff {
+  pub added: Vec<TreePath>,
+  pub removed: Vec<TreePath>,
+  pub changed: Vec<TreePath>,
+}
+
+impl TreeDiff {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() &&
+    self.removed.is_empty() &&
+    self.changed.is_empty()
+  }
+}
+
+#[derive(Clone, Debug)]
+struct _StoredSnapshot {
+  metadata: SnapshotMetadata,
+  data: ContentHash,
+}
+
 pub struct Store {
+  dir: Option<PathBuf>,
   frames: BTreeMap<FrameId, FramePointer>,
-  snapshots: BTreeMap<SnapshotHash, Snapshot>,
-  objects: BTreeMap<ContentHash, Object>,
+  snapshots: BTreeMap<SnapshotHash, _StoredSnapshot>,
+  objects: BTreeMap<ContentHash, _StoredObject>,
+  log_file: Option<BufWriter<File>>,
 }
 
 impl Store {
   pub fn root() -> Store {
     Store{
+      dir: None,
       snapshots: BTreeMap::new(),
       frames: BTreeMap::new(),
       objects: BTreeMap::new(),
+      log_file: None,
+    }
+  }
+
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<Store, StoreError> {
+    let save_dir = path.as_ref();
+    create_dir_all(save_dir).map_err(|_| StoreError::IO)?;
+    let mut log_path = save_dir.to_owned();
+    log_path.push("_log.jsonl");
+    let mut store = Store::root();
+    store.dir = Some(save_dir.to_owned());
+    let mut edges: BTreeMap<ContentHash, BTreeMap<String, ContentHash>> = BTreeMap::new();
+    let mut valid_len = None;
+    if log_path.exists() {
+      let log_file = File::open(&log_path).map_err(|_| StoreError::IO)?;
+      let mut reader = BufReader::new(log_file);
+      let mut line = String::new();
+      let mut line_nr = 0;
+      let mut off = 0;
+      loop {
+        line.clear();
+        let n = reader.read_line(&mut line).map_err(|_| StoreError::IO)?;
+        if n == 0 {
+          break;
+        }
+        // NB: an unterminated last line is the torn tail of an append
+        // that did not complete (e.g. upon a crash); it is dropped, and
+        // truncated so that later appends start on a fresh line.
+        if !line.ends_with('\n') {
+          valid_len = Some(off);
+          break;
+        }
+        let rec = line.trim_end();
+        if !rec.is_empty() {
+          let rec: _LogRecord = serde_json::from_str(rec)
+            .map_err(|_| StoreError::Parse(line_nr))?;
+          store._replay(line_nr, rec, &mut edges)?;
+        }
+        line_nr += 1;
+        off += n as u64;
+      }
+    }
+    let log_file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&log_path).map_err(|_| StoreError::IO)?;
+    if let Some(len) = valid_len {
+      log_file.set_len(len).map_err(|_| StoreError::IO)?;
+    }
+    store.log_file = Some(BufWriter::new(log_file));
+    Ok(store)
+  }
+
+  fn _replay(&mut self, line_nr: usize, rec: _LogRecord, edges: &mut BTreeMap<ContentHash, BTreeMap<String, ContentHash>>) -> Result<(), StoreError> {
+    match rec {
+      _LogRecord::Frame{id, init, last} => {
+        if !init.is_root() && !self.snapshots.contains_key(&init) {
+          return Err(StoreError::Missing(line_nr));
+        }
+        if !last.is_root() && !self.snapshots.contains_key(&last) {
+          return Err(StoreError::Missing(line_nr));
+        }
+        self.frames.insert(id, FramePointer{init, last});
+      }
+      _LogRecord::Snapshot{hash, metadata, data} => {
+        if metadata.prev.is_empty() {
+          return Err(StoreError::Parse(line_nr));
+        }
+        if !self._resolve_object(&data, edges) {
+          return Err(StoreError::Missing(line_nr));
+        }
+        if _snapshot_hash(&metadata, &data) != hash {
+          return Err(StoreError::Hash(line_nr));
+        }
+        self.snapshots.insert(hash, _StoredSnapshot{metadata, data});
+      }
+      _LogRecord::String{hash, content} => {
+        let mut h = Blake2s::new_hash();
+        h.hash_bytes(content.as_bytes());
+        if ContentHash::from(h.finalize()) != hash {
+          return Err(StoreError::Hash(line_nr));
+        }
+        self.objects.insert(hash, _StoredObject::String(content));
+      }
+      _LogRecord::Blob{hash, len} => {
+        let buf = match self._read_blob(&hash) {
+          None => return Err(StoreError::Missing(line_nr)),
+          Some(buf) => buf
+        };
+        let mut h = Blake2s::new_hash();
+        h.hash_bytes(&buf);
+        if buf.len() != len || ContentHash::from(h.finalize()) != hash {
+          return Err(StoreError::Hash(line_nr));
+        }
+        self.objects.insert(hash, _StoredObject::Blob(len));
+      }
+      _LogRecord::Tree{hash, up, key} => {
+        edges.entry(up).or_insert_with(BTreeMap::new).insert(key, hash);
+      }
     }
+    Ok(())
+  }
+
+  fn _resolve_object(&mut self, hash: &ContentHash, edges: &mut BTreeMap<ContentHash, BTreeMap<String, ContentHash>>) -> bool {
+    if hash == &ContentHash::empty() || self.objects.contains_key(hash) {
+      return true;
+    }
+    // NB: an empty tree has no edges, but still has a nonzero hash.
+    let kvs = edges.remove(hash).unwrap_or_default();
+    for h in kvs.values() {
+      if !self._resolve_object(h, edges) {
+        return false;
+      }
+    }
+    if &_tree_hash(kvs.iter()) != hash {
+      return false;
+    }
+    self.objects.insert(hash.clone(), _StoredObject::Tree(kvs));
+    true
+  }
+
+  fn _blob_path(&self, hash: &ContentHash) -> Option<PathBuf> {
+    let mut blob_path = self.dir.clone()?;
+    blob_path.push("objects");
+    blob_path.push(hash.to_string());
+    Some(blob_path)
+  }
+
+  fn _read_blob(&self, hash: &ContentHash) -> Option<Vec<u8>> {
+    read(self._blob_path(hash)?).ok()
+  }
+
+  fn _write_blob(&self, hash: &ContentHash, buf: &[u8]) -> Result<(), IoError> {
+    let blob_path = match self._blob_path(hash) {
+      None => return Ok(()),
+      Some(path) => path
+    };
+    if blob_path.exists() {
+      return Ok(());
+    }
+    if let Some(dir) = blob_path.parent() {
+      create_dir_all(dir)?;
+    }
+    let tmp_path = blob_path.with_extension("tmp");
+    write(&tmp_path, buf)?;
+    rename(&tmp_path, &blob_path)?;
+    Ok(())
+  }
+
+  // NB: the interned objects are only staged, together w/ their log
+  // records; the caller commits them to `objects` once the records are
+  // appended (see `_append_log`).
+  fn _intern_object(&self, obj: &Object, staged: &mut BTreeMap<ContentHash, _StoredObject>, recs: &mut Vec<_LogRecord>) -> Result<(), IoError> {
+    assert!(!obj.rehash);
+    if self.objects.contains_key(&obj.hash) || staged.contains_key(&obj.hash) {
+      return Ok(());
+    }
+    let stored = match &obj.data {
+      &ObjectData::Empty => {
+        return Ok(());
+      }
+      &ObjectData::String(ref s) => {
+        if s.len() >= BLOB_SPILL_SIZE && self.dir.is_some() {
+          self._write_blob(&obj.hash, s.as_bytes())?;
+          recs.push(_LogRecord::Blob{
+            hash: obj.hash.clone(),
+            len: s.len(),
+          });
+          _StoredObject::Blob(s.len())
+        } else {
+          recs.push(_LogRecord::String{
+            hash: obj.hash.clone(),
+            content: s.clone(),
+          });
+          _StoredObject::String(s.clone())
+        }
+      }
+      &ObjectData::Tree(ref kvs) => {
+        let mut hkvs = BTreeMap::new();
+        for (k, v) in kvs.iter() {
+          self._intern_object(v, staged, recs)?;
+          recs.push(_LogRecord::Tree{
+            hash: v.hash.clone(),
+            up: obj.hash.clone(),
+            key: k.clone(),
+          });
+          hkvs.insert(k.clone(), v.hash.clone());
+        }
+        _StoredObject::Tree(hkvs)
+      }
+    };
+    staged.insert(obj.hash.clone(), stored);
+    Ok(())
+  }
+
+  pub fn load_object(&self, hash: &ContentHash) -> Object {
+    if hash == &ContentHash::empty() {
+      return Object::empty();
+    }
+    let data = match self.objects.get(hash) {
+      None => panic!("bug"),
+      Some(&_StoredObject::String(ref s)) => {
+        ObjectData::String(s.clone())
+      }
+      Some(&_StoredObject::Blob(_)) => {
+        let buf = match self._read_blob(hash) {
+          None => panic!("bug"),
+          Some(buf) => buf
+        };
+        match String::from_utf8(buf) {
+          Err(_) => panic!("bug"),
+          Ok(s) => ObjectData::String(s)
+        }
+      }
+      Some(&_StoredObject::Tree(ref hkvs)) => {
+        let mut kvs = BTreeMap::new();
+        for (k, h) in hkvs.iter() {
+          kvs.insert(k.clone(), self.load_object(h));
+        }
+        ObjectData::Tree(kvs)
+      }
+    };
+    Object{
+      hash: hash.clone(),
+      rehash: false,
+      data,
+    }
+  }
+
+  fn _append_log(&mut self, recs: Vec<_LogRecord>) -> Result<(), IoError> {
+    let log_file = match self.log_file.as_mut() {
+      None => return Ok(()),
+      Some(f) => f
+    };
+    let json_fmt = JsonFormat::new()
+        .ascii(true)
+        .comma(", ").unwrap()
+        .colon(": ").unwrap()
+    ;
+    for rec in recs.iter() {
+      let s = json_fmt.to_string(rec).unwrap();
+      writeln!(log_file, "{}", s)?;
+    }
+    log_file.flush()?;
+    Ok(())
   }
 
   pub fn debug_print_digest(&self) {
@@ -538,14 +954,11 @@ impl Store {
       data: ContentHash,
     }
     for (hash, snapshot) in self.snapshots.iter() {
-      assert_eq!(hash, &snapshot.hash);
-      assert!(!snapshot.rehash);
-      assert!(!snapshot.hashdata.rehash);
       let m = _Snapshot{
         _type: Default::default(),
         hash: hash.clone(),
         metadata: snapshot.metadata.clone(),
-        data: snapshot.hashdata.hash.clone(),
+        data: snapshot.data.clone(),
       };
       let json_fmt = JsonFormat::new()
           .ascii(true)
@@ -554,7 +967,7 @@ impl Store {
       ;
       let s = json_fmt.to_string(&m).unwrap();
       writeln!(&mut log_file, "{}", s).unwrap();
-      self._save_object(&snapshot.hashdata.data, &mut log_file, );
+      self._save_object(&self.load_object(&snapshot.data), &mut log_file, );
     }
   }
 
@@ -609,7 +1022,7 @@ impl Store {
     }
   }
 
-  pub fn fresh_frame(&mut self) -> FrameId {
+  fn _fresh_frame_id(&self) -> FrameId {
     let mut inner = Vec::with_capacity(SHORT_HASH_SIZE);
     inner.resize(SHORT_HASH_SIZE, 0);
     match getrandom(&mut inner) {
@@ -618,47 +1031,295 @@ impl Store {
     }
     let inner = inner.into();
     let frame_id = FrameId{inner};
-    match self.frames.insert(frame_id.clone(), FramePointer::fresh()) {
-      None => {}
-      Some(_) => panic!("bug")
+    if self.frames.contains_key(&frame_id) {
+      panic!("bug");
     }
     frame_id
   }
 
+  pub fn fresh_frame(&mut self) -> Result<FrameId, IoError> {
+    let frame_id = self._fresh_frame_id();
+    self._append_log(vec![_LogRecord::Frame{
+      id: frame_id.clone(),
+      init: SnapshotHash::root(),
+      last: SnapshotHash::root(),
+    }])?;
+    self.frames.insert(frame_id.clone(), FramePointer::fresh());
+    Ok(frame_id)
+  }
+
+  pub fn list_frames(&self) -> Vec<(FrameId, FramePointer)> {
+    self.frames.iter()
+      .map(|(frame_id, frameptr)| (frame_id.clone(), frameptr.clone()))
+      .collect()
+  }
+
+  /// Returns the snapshot chain of a frame, newest first, from its last
+  /// snapshot back to its init snapshot. Merge snapshots are walked
+  /// through their first prev, i.e. the side that was merged into.
+  /// Returns `None` if the frame is not in the store.
+  pub fn frame_history(&self, frame_id: &FrameId) -> Option<Vec<SnapshotHash>> {
+    let frameptr = self.frames.get(frame_id)?;
+    let mut history = Vec::new();
+    let mut hash = frameptr.last.clone();
+    while !hash.is_root() {
+      let snapshot = match self.snapshots.get(&hash) {
+        None => panic!("bug"),
+        Some(snapshot) => snapshot
+      };
+      if &snapshot.metadata.frame != frame_id {
+        break;
+      }
+      history.push(hash.clone());
+      if hash == frameptr.init {
+        break;
+      }
+      hash = snapshot.metadata.prev[0].clone();
+    }
+    Some(history)
+  }
+
   pub fn get_snapshot(&self, hash: SnapshotHash) -> Option<Snapshot> {
     if hash.is_root() {
       return None;
     }
-    match self.snapshots.get(&hash) {
+    let snapshot = match self.snapshots.get(&hash) {
       None => panic!("bug"),
-      Some(v) => Some(v.clone())
-    }
+      Some(v) => v
+    };
+    Some(Snapshot{
+      hash,
+      rehash: false,
+      metadata: snapshot.metadata.clone(),
+      hashdata: SnapshotData{
+        hash: snapshot.data.clone(),
+        rehash: false,
+        data: self.load_object(&snapshot.data),
+      },
+    })
   }
 
-  pub fn commit_snapshot(&mut self, snapshot: Snapshot) {
+  pub fn commit_snapshot(&mut self, snapshot: Snapshot) -> Result<(), IoError> {
     if snapshot.rehash {
       panic!("bug");
     }
     let frame_id = snapshot.metadata.frame.clone();
     if frame_id.is_root() {
       // TODO
-      return;
+      return Ok(());
+    }
+    // NB: the snapshot hash excludes the timestamp, so that committing
+    // the same state twice (or repeating a merge) finds the existing
+    // snapshot, which is kept as is.
+    if self.snapshots.contains_key(&snapshot.hash) {
+      return Ok(());
     }
-    match self.frames.get_mut(&frame_id) {
+    let frameptr = match self.frames.get(&frame_id) {
       None => panic!("bug"),
-      Some(frameptr) => {
-        if frameptr.init.is_root() {
-          frameptr.init = snapshot.hash.clone();
+      Some(frameptr) => frameptr.clone()
+    };
+    let mut staged = BTreeMap::new();
+    let mut recs = Vec::new();
+    self._intern_object(&snapshot.hashdata.data, &mut staged, &mut recs)?;
+    self._commit(snapshot.hash, snapshot.metadata, snapshot.hashdata.hash, frameptr, staged, recs)
+  }
+
+  // Appends the staged objects (and their log records) along w/ the
+  // snapshot and its frame pointer, then applies them to the store.
+  fn _commit(&mut self, hash: SnapshotHash, metadata: SnapshotMetadata, data: ContentHash, mut frameptr: FramePointer, staged: BTreeMap<ContentHash, _StoredObject>, mut recs: Vec<_LogRecord>) -> Result<(), IoError> {
+    let frame_id = metadata.frame.clone();
+    if frameptr.init.is_root() {
+      frameptr.init = hash.clone();
+    }
+    frameptr.last = hash.clone();
+    println!("DEBUG: Store::commit_snapshot: metadata = {:?}", &metadata);
+    println!("DEBUG: Store::commit_snapshot: frameptr = {:?}", &frameptr);
+    recs.push(_LogRecord::Snapshot{
+      hash: hash.clone(),
+      metadata: metadata.clone(),
+      data: data.clone(),
+    });
+    recs.push(_LogRecord::Frame{
+      id: frame_id.clone(),
+      init: frameptr.init.clone(),
+      last: frameptr.last.clone(),
+    });
+    // NB: the store is only updated once the records are appended, so
+    // that a failed append leaves it as it was.
+    self._append_log(recs)?;
+    self.objects.extend(staged);
+    self.snapshots.insert(hash, _StoredSnapshot{metadata, data});
+    self.frames.insert(frame_id, frameptr);
+    Ok(())
+  }
+
+  fn _snapshot_data(&self, hash: &SnapshotHash) -> ContentHash {
+    if hash.is_root() {
+      return ContentHash::empty();
+    }
+    match self.snapshots.get(hash) {
+      None => panic!("bug"),
+      Some(snapshot) => snapshot.data.clone()
+    }
+  }
+
+  fn _tree_kvs(&self, hash: &ContentHash) -> Option<BTreeMap<String, ContentHash>> {
+    // NB: the root snapshot has empty data, which diffs and merges as
+    // an empty tree.
+    if hash == &ContentHash::empty() {
+      return Some(BTreeMap::new());
+    }
+    match self.objects.get(hash) {
+      Some(&_StoredObject::Tree(ref kvs)) => Some(kvs.clone()),
+      _ => None
+    }
+  }
+
+  pub fn diff(&self, a: SnapshotHash, b: SnapshotHash) -> TreeDiff {
+    let ha = self._snapshot_data(&a);
+    let hb = self._snapshot_data(&b);
+    let mut diff = TreeDiff::default();
+    let mut path = Vec::new();
+    self._diff_object(&mut path, &ha, &hb, &mut diff);
+    diff
+  }
+
+  fn _diff_object(&self, path: &mut TreePath, ha: &ContentHash, hb: &ContentHash, diff: &mut TreeDiff) {
+    // NB: shared subtrees have equal hashes, so they are skipped
+    // without being traversed.
+    if ha == hb {
+      return;
+    }
+    let (kvs_a, kvs_b) = match (self._tree_kvs(ha), self._tree_kvs(hb)) {
+      (Some(kvs_a), Some(kvs_b)) => (kvs_a, kvs_b),
+      _ => {
+        diff.changed.push(path.clone());
+        return;
+      }
+    };
+    for (k, h) in kvs_a.iter() {
+      path.push(k.clone());
+      match kvs_b.get(k) {
+        None => diff.removed.push(path.clone()),
+        Some(h2) => self._diff_object(path, h, h2, diff)
+      }
+      path.pop();
+    }
+    for k in kvs_b.keys() {
+      if !kvs_a.contains_key(k) {
+        path.push(k.clone());
+        diff.added.push(path.clone());
+        path.pop();
+      }
+    }
+  }
+
+  /// Three-way merge of `b` into `a`, relative to their common ancestor
+  /// `base`. The merged snapshot is committed to the frame of `a` (or to
+  /// a fresh frame if `a` is the root), with both `a` and `b` as its
+  /// prevs. Conflicting paths keep the contents of `a`, are marked with
+  /// `MergeConflict`, and are returned to the caller for review.
+  pub fn merge(&mut self, base: SnapshotHash, a: SnapshotHash, b: SnapshotHash) -> Result<(SnapshotHash, Vec<TreePath>), IoError> {
+    let hbase = self._snapshot_data(&base);
+    let ha = self._snapshot_data(&a);
+    let hb = self._snapshot_data(&b);
+    let mut staged = BTreeMap::new();
+    let mut recs = Vec::new();
+    let mut path = Vec::new();
+    let mut conflicts = Vec::new();
+    let data = self._merge_object(&mut path, Some(&hbase), &ha, &hb, &mut staged, &mut recs, &mut conflicts);
+    // NB: a merge into the root gets a fresh frame, which (like the
+    // merged objects) is only added to the store by the commit below.
+    let (frame_id, frameptr) = if a.is_root() {
+      (self._fresh_frame_id(), FramePointer::fresh())
+    } else {
+      let frame_id = match self.snapshots.get(&a) {
+        None => panic!("bug"),
+        Some(snapshot) => snapshot.metadata.frame.clone()
+      };
+      let frameptr = match self.frames.get(&frame_id) {
+        None => panic!("bug"),
+        Some(frameptr) => frameptr.clone()
+      };
+      (frame_id, frameptr)
+    };
+    let mut mark = vec![SnapshotMarker::Merge];
+    if !conflicts.is_empty() {
+      mark.push(SnapshotMarker::MergeConflict);
+    }
+    let metadata = SnapshotMetadata{
+      frame: frame_id,
+      prev: vec![a, b],
+      mark,
+      timestamp: Timestamp::fresh(),
+    };
+    let hash = _snapshot_hash(&metadata, &data);
+    if self.snapshots.contains_key(&hash) {
+      return Ok((hash, conflicts));
+    }
+    self._commit(hash.clone(), metadata, data, frameptr, staged, recs)?;
+    Ok((hash, conflicts))
+  }
+
+  fn _merge_object(&self, path: &mut TreePath, hbase: Option<&ContentHash>, ha: &ContentHash, hb: &ContentHash, staged: &mut BTreeMap<ContentHash, _StoredObject>, recs: &mut Vec<_LogRecord>, conflicts: &mut Vec<TreePath>) -> ContentHash {
+    if ha == hb || hbase == Some(hb) {
+      return ha.clone();
+    }
+    if hbase == Some(ha) {
+      return hb.clone();
+    }
+    let kvs_base = match hbase {
+      None => Some(BTreeMap::new()),
+      Some(hbase) => self._tree_kvs(hbase)
+    };
+    let (kvs_base, kvs_a, kvs_b) = match (kvs_base, self._tree_kvs(ha), self._tree_kvs(hb)) {
+      (Some(kvs_base), Some(kvs_a), Some(kvs_b)) => {
+        (kvs_base, kvs_a, kvs_b)
+      }
+      _ => {
+        conflicts.push(path.clone());
+        return ha.clone();
+      }
+    };
+    let mut keys: Vec<String> = kvs_a.keys().cloned().collect();
+    keys.extend(kvs_b.keys().filter(|&k| !kvs_a.contains_key(k)).cloned());
+    keys.extend(kvs_base.keys().filter(|&k| !kvs_a.contains_key(k) && !kvs_b.contains_key(k)).cloned());
+    let mut kvs = BTreeMap::new();
+    for k in keys.into_iter() {
+      path.push(k.clone());
+      let (kbase, ka, kb) = (kvs_base.get(&k), kvs_a.get(&k), kvs_b.get(&k));
+      let h = if ka == kb || kbase == kb {
+        ka.cloned()
+      } else if kbase == ka {
+        kb.cloned()
+      } else {
+        match (ka, kb) {
+          (Some(ka), Some(kb)) => {
+            Some(self._merge_object(path, kbase, ka, kb, staged, recs, conflicts))
+          }
+          _ => {
+            conflicts.push(path.clone());
+            ka.cloned()
+          }
         }
-        frameptr.last = snapshot.hash.clone();
-        println!("DEBUG: Store::commit_snapshot: metadata = {:?}", &snapshot.metadata);
-        println!("DEBUG: Store::commit_snapshot: frameptr = {:?}", &frameptr);
+      };
+      path.pop();
+      if let Some(h) = h {
+        kvs.insert(k, h);
       }
     }
-    match self.snapshots.insert(snapshot.hash.clone(), snapshot) {
-      None => {}
-      Some(_) => panic!("bug")
+    let hash = _tree_hash(kvs.iter());
+    if !self.objects.contains_key(&hash) && !staged.contains_key(&hash) {
+      for (k, h) in kvs.iter() {
+        recs.push(_LogRecord::Tree{
+          hash: h.clone(),
+          up: hash.clone(),
+          key: k.clone(),
+        });
+      }
+      staged.insert(hash.clone(), _StoredObject::Tree(kvs));
     }
+    hash
   }
 }
 
@@ -677,14 +1338,8 @@ impl Frame {
     }
   }
 
-  pub fn _new(metadata: SnapshotMetadata, data: SnapshotData, store: &mut Store) -> Frame {
-    let mut merkle_buf = Vec::new();
-    merkle_buf.extend(&*metadata.frame.inner);
-    merkle_buf.extend(&*metadata.prev[0].inner);
-    merkle_buf.extend(&*data.hash.inner);
-    let mut h = Blake2s::new_hash();
-    h.hash_bytes(&merkle_buf);
-    let hash = SnapshotHash::from(h.finalize());
+  pub fn _new(metadata: SnapshotMetadata, data: SnapshotData, store: &mut Store) -> Result<Frame, IoError> {
+    let hash = _snapshot_hash(&metadata, &data.hash);
     let snapshot = Snapshot{
       hash,
       rehash: false,
@@ -693,14 +1348,31 @@ impl Frame {
       hashdata: data.clone(),
     };
     let snapshot_hash = snapshot.hash.clone();
-    store.commit_snapshot(snapshot);
-    Frame{
+    store.commit_snapshot(snapshot)?;
+    Ok(Frame{
       snapshot: snapshot_hash,
       modified: false,
       hashdata: data,
+    })
+  }
+
+  pub fn checkout(store: &Store, hash: SnapshotHash) -> Frame {
+    match store.get_snapshot(hash.clone()) {
+      None => Frame::root(),
+      Some(snapshot) => {
+        Frame{
+          snapshot: hash,
+          modified: false,
+          hashdata: snapshot.hashdata,
+        }
+      }
     }
   }
 
+  pub fn snapshot(&self) -> &SnapshotHash {
+    &self.snapshot
+  }
+
   pub fn debug_print_status(&self, store: &Store) {
     match store.get_snapshot(self.snapshot.clone()) {
       None => {
@@ -720,10 +1392,10 @@ impl Frame {
     }
   }
 
-  pub fn import(&self, import_data: Object, store: &mut Store) -> Frame {
+  pub fn import(&self, import_data: Object, store: &mut Store) -> Result<Frame, IoError> {
     println!("DEBUG: Frame::import: ...");
     let timestamp = Timestamp::fresh();
-    let frame_id = store.fresh_frame();
+    let frame_id = store.fresh_frame()?;
     let metadata = SnapshotMetadata {
       frame: frame_id,
       prev: vec![self.snapshot.clone()],
@@ -733,14 +1405,14 @@ impl Frame {
     let mut hashdata = self.hashdata.clone();
     let _ = replace(hashdata.mut_data(), import_data);
     hashdata.rehash();
-    let frame = Frame::_new(metadata, hashdata, store);
+    let frame = Frame::_new(metadata, hashdata, store)?;
     println!("DEBUG: Frame::import: done");
-    frame
+    Ok(frame)
   }
 
-  pub fn fresh(&self, store: &mut Store) -> Frame {
+  pub fn fresh(&self, store: &mut Store) -> Result<Frame, IoError> {
     let timestamp = Timestamp::fresh();
-    let frame_id = store.fresh_frame();
+    let frame_id = store.fresh_frame()?;
     let metadata = SnapshotMetadata {
       frame: frame_id,
       prev: vec![self.snapshot.clone()],
@@ -753,9 +1425,9 @@ impl Frame {
     Frame::_new(metadata, hashdata, store)
   }
 
-  pub fn commit(&mut self, store: &mut Store) {
+  pub fn commit(&mut self, store: &mut Store) -> Result<(), IoError> {
     if !self.modified {
-      return;
+      return Ok(());
     }
     let old_snapshot = self.snapshot.clone();
     let old_hash = self.hashdata.hash.clone();
@@ -763,11 +1435,11 @@ impl Frame {
     let new_hash = self.hashdata.hash.clone();
     if old_hash == new_hash {
       self.modified = false;
-      return;
+      return Ok(());
     }
     if self.snapshot.is_root() {
       let hashdata = replace(&mut self.hashdata, SnapshotData::empty());
-      *self = self.fresh(store);
+      *self = self.fresh(store)?;
       let _ = replace(&mut self.hashdata, hashdata);
     }
     let mut snapshot = match store.get_snapshot(old_snapshot.clone()) {
@@ -781,9 +1453,10 @@ impl Frame {
     snapshot.hashdata = self.hashdata.clone();
     snapshot.force_rehash();
     let snapshot_hash = snapshot.hash.clone();
-    store.commit_snapshot(snapshot);
+    store.commit_snapshot(snapshot)?;
     self.snapshot = snapshot_hash;
     self.modified = false;
+    Ok(())
   }
 
   pub fn view(&self) -> &SnapshotData {
@@ -795,3 +1468,290 @@ impl Frame {
     &mut self.hashdata
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::fs::{read_to_string, remove_dir_all, remove_file};
+
+  fn _test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pythia-test-aikido-{}-{}", name, std::process::id()));
+    let _ = remove_dir_all(&dir);
+    dir
+  }
+
+  fn _tree(kvs: &[(&str, &str)]) -> Object {
+    let mut obj = Object::tree();
+    for &(k, v) in kvs.iter() {
+      obj.tree_insert_str(k, v);
+    }
+    obj
+  }
+
+  fn _commit(frame: &mut Frame, store: &mut Store, data: Object) -> SnapshotHash {
+    *frame.modify().mut_data() = data;
+    frame.commit(store).unwrap();
+    frame.snapshot().clone()
+  }
+
+  fn _get_str(store: &Store, hash: &SnapshotHash, path: &[&str]) -> Option<String> {
+    let snapshot = store.get_snapshot(hash.clone())?;
+    let mut obj = snapshot.hashdata.data();
+    for &k in path.iter() {
+      obj = obj.tree_get(k)?;
+    }
+    obj.as_str().map(|s| s.to_owned())
+  }
+
+  #[test]
+  fn test_store_open_replay() {
+    let dir = _test_dir("replay");
+    let (s1, s2) = {
+      let mut store = Store::open(&dir).unwrap();
+      let mut frame = Frame::root().fresh(&mut store).unwrap();
+      let mut data = _tree(&[("a", "1")]);
+      data.tree_insert("t", _tree(&[("x", "2")]));
+      let s1 = _commit(&mut frame, &mut store, data);
+      let s2 = _commit(&mut frame, &mut store, _tree(&[("a", "3")]));
+      (s1, s2)
+    };
+    // NB: every commit was appended to the log, so the reopened store
+    // has every snapshot, w/ the same content.
+    {
+      let store = Store::open(&dir).unwrap();
+      assert_eq!(store.snapshots.len(), 3);
+      assert_eq!(_get_str(&store, &s1, &["a"]).as_deref(), Some("1"));
+      assert_eq!(_get_str(&store, &s1, &["t", "x"]).as_deref(), Some("2"));
+      assert_eq!(_get_str(&store, &s2, &["a"]).as_deref(), Some("3"));
+      assert!(_get_str(&store, &s2, &["t", "x"]).is_none());
+      let frameptr = store.frames.values().next().unwrap();
+      assert_eq!(store.frames.len(), 1);
+      assert_eq!(frameptr.last(), &s2);
+    }
+    // NB: a torn last record (e.g. of a crashed append) is truncated upon
+    // open.
+    let log_path = dir.join("_log.jsonl");
+    let valid_len = log_path.metadata().unwrap().len();
+    {
+      let mut file = OpenOptions::new().append(true).open(&log_path).unwrap();
+      write!(file, "{{\"_type\": \"snapshot\", ").unwrap();
+    }
+    {
+      let store = Store::open(&dir).unwrap();
+      assert_eq!(store.snapshots.len(), 3);
+      assert_eq!(log_path.metadata().unwrap().len(), valid_len);
+    }
+    // NB: a tampered string record fails its hash check upon replay.
+    let log = read_to_string(&log_path).unwrap();
+    let mut tampered = Vec::new();
+    let mut dropped = Vec::new();
+    for line in log.lines() {
+      match serde_json::from_str::<_LogRecord>(line).unwrap() {
+        _LogRecord::String{hash, content} if content == "1" => {
+          let rec = _LogRecord::String{hash, content: "4".into()};
+          tampered.push(serde_json::to_string(&rec).unwrap());
+        }
+        _ => {
+          tampered.push(line.to_owned());
+          dropped.push(line.to_owned());
+        }
+      }
+    }
+    write(&log_path, tampered.join("\n")).unwrap();
+    assert!(matches!(Store::open(&dir), Err(StoreError::Hash(_))));
+    // NB: so does a snapshot record whose data record was dropped.
+    write(&log_path, dropped.join("\n")).unwrap();
+    assert!(matches!(Store::open(&dir), Err(StoreError::Missing(_))));
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_store_dedup_blob_spill() {
+    // NB: an identical subtree is stored once, and shared by the
+    // snapshots of both frames.
+    let mut store = Store::root();
+    let mut data = _tree(&[("a", "1")]);
+    data.tree_insert("t", _tree(&[("x", "2"), ("y", "3")]));
+    let mut frame1 = Frame::root().fresh(&mut store).unwrap();
+    let s1 = _commit(&mut frame1, &mut store, data);
+    let obj_ct = store.objects.len();
+    let mut data2 = _tree(&[("a", "4")]);
+    data2.tree_insert("t", _tree(&[("x", "2"), ("y", "3")]));
+    let mut frame2 = Frame::root().fresh(&mut store).unwrap();
+    let s2 = _commit(&mut frame2, &mut store, data2);
+    // NB: only the new root tree and the new string are stored.
+    assert_eq!(store.objects.len(), obj_ct + 2);
+    let t1 = store.load_object(&store._snapshot_data(&s1)).tree_get("t").unwrap().hash.clone();
+    let t2 = store.load_object(&store._snapshot_data(&s2)).tree_get("t").unwrap().hash.clone();
+    assert_eq!(t1, t2);
+    // NB: a large string is spilled to a blob file, but only if the store
+    // is on disk.
+    let big = "z".repeat(BLOB_SPILL_SIZE);
+    let mut big_data = _tree(&[("a", "1")]);
+    big_data.tree_insert_str("big", &big);
+    let s3 = _commit(&mut frame1, &mut store, big_data.clone());
+    assert!(store.objects.values().all(|obj| !matches!(obj, &_StoredObject::Blob(_))));
+    assert_eq!(_get_str(&store, &s3, &["big"]).as_deref(), Some(big.as_str()));
+    let dir = _test_dir("blob");
+    let (s4, blob_path) = {
+      let mut store = Store::open(&dir).unwrap();
+      let mut frame = Frame::root().fresh(&mut store).unwrap();
+      let s4 = _commit(&mut frame, &mut store, big_data);
+      let mut big_obj = Object::string_from(&big);
+      let blob_path = store._blob_path(&big_obj.content_hash()).unwrap();
+      assert!(blob_path.exists());
+      assert!(store.objects.values().any(|obj| matches!(obj, &_StoredObject::Blob(n) if n == big.len())));
+      let log = read_to_string(dir.join("_log.jsonl")).unwrap();
+      assert!(!log.contains(&big));
+      (s4, blob_path)
+    };
+    {
+      let store = Store::open(&dir).unwrap();
+      assert_eq!(_get_str(&store, &s4, &["big"]).as_deref(), Some(big.as_str()));
+      assert_eq!(_get_str(&store, &s4, &["a"]).as_deref(), Some("1"));
+    }
+    // NB: a corrupt or missing blob fails the replay of its record.
+    write(&blob_path, "z").unwrap();
+    assert!(matches!(Store::open(&dir), Err(StoreError::Hash(_))));
+    remove_file(&blob_path).unwrap();
+    assert!(matches!(Store::open(&dir), Err(StoreError::Missing(_))));
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_store_diff_merge() {
+    fn _data(b: &str, x: &str, c: Option<&str>) -> Object {
+      let mut data = _tree(&[("a", "1"), ("b", b)]);
+      if let Some(c) = c {
+        data.tree_insert_str("c", c);
+      }
+      data.tree_insert("t", _tree(&[("x", x)]));
+      data
+    }
+    fn _path(p: &[&str]) -> TreePath {
+      p.iter().map(|&k| k.to_owned()).collect()
+    }
+    let dir = _test_dir("merge");
+    let mut store = Store::open(&dir).unwrap();
+    let mut frame = Frame::root().fresh(&mut store).unwrap();
+    let base = _commit(&mut frame, &mut store, _data("2", "1", None));
+    let mut frame_a = Frame::checkout(&store, base.clone());
+    let a = _commit(&mut frame_a, &mut store, _data("3", "1", Some("1")));
+    let mut frame_b = Frame::checkout(&store, base.clone());
+    let b = _commit(&mut frame_b, &mut store, _data("2", "2", None));
+    assert!(store.diff(base.clone(), base.clone()).is_empty());
+    let diff = store.diff(base.clone(), a.clone());
+    assert_eq!(diff.added, vec![_path(&["c"])]);
+    assert!(diff.removed.is_empty());
+    assert_eq!(diff.changed, vec![_path(&["b"])]);
+    let diff = store.diff(a.clone(), base.clone());
+    assert_eq!(diff.removed, vec![_path(&["c"])]);
+    let diff = store.diff(base.clone(), b.clone());
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+    assert_eq!(diff.changed, vec![_path(&["t", "x"])]);
+    // NB: the root snapshot diffs as an empty tree.
+    let diff = store.diff(SnapshotHash::root(), base.clone());
+    assert_eq!(diff.added.len(), 3);
+    // NB: the disjoint changes of either side merge cleanly.
+    let (m, conflicts) = store.merge(base.clone(), a.clone(), b.clone()).unwrap();
+    assert!(conflicts.is_empty());
+    assert_eq!(_get_str(&store, &m, &["b"]).as_deref(), Some("3"));
+    assert_eq!(_get_str(&store, &m, &["c"]).as_deref(), Some("1"));
+    assert_eq!(_get_str(&store, &m, &["t", "x"]).as_deref(), Some("2"));
+    let snapshot = store.get_snapshot(m.clone()).unwrap();
+    assert_eq!(snapshot.metadata.prev, vec![a.clone(), b.clone()]);
+    assert_eq!(snapshot.metadata.mark, vec![SnapshotMarker::Merge]);
+    let (m2, _) = store.merge(base.clone(), b.clone(), a.clone()).unwrap();
+    assert!(store.diff(m.clone(), m2).is_empty());
+    // NB: a conflicting path keeps the contents of `a`, and is returned.
+    let mut frame_c = Frame::checkout(&store, base.clone());
+    let c = _commit(&mut frame_c, &mut store, _data("4", "1", Some("1")));
+    let (mc, conflicts) = store.merge(base.clone(), a.clone(), c.clone()).unwrap();
+    assert_eq!(conflicts, vec![_path(&["b"])]);
+    assert_eq!(_get_str(&store, &mc, &["b"]).as_deref(), Some("3"));
+    let snapshot = store.get_snapshot(mc.clone()).unwrap();
+    assert_eq!(snapshot.metadata.mark, vec![SnapshotMarker::Merge, SnapshotMarker::MergeConflict]);
+    // NB: the merged trees are appended to the log, and so are replayed.
+    drop(store);
+    let store = Store::open(&dir).unwrap();
+    assert_eq!(_get_str(&store, &m, &["t", "x"]).as_deref(), Some("2"));
+    assert_eq!(_get_str(&store, &mc, &["c"]).as_deref(), Some("1"));
+    drop(store);
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_store_commit_merge_twice() {
+    // NB: the snapshot hash excludes the timestamp, so committing the
+    // same state twice, or repeating a merge, finds the existing snapshot.
+    let dir = _test_dir("twice");
+    let (a, m) = {
+      let mut store = Store::open(&dir).unwrap();
+      let mut frame = Frame::root().fresh(&mut store).unwrap();
+      let base = _commit(&mut frame, &mut store, _tree(&[("a", "1"), ("b", "1")]));
+      let mut frame_a = Frame::checkout(&store, base.clone());
+      let a = _commit(&mut frame_a, &mut store, _tree(&[("a", "2"), ("b", "1")]));
+      let mut frame_a2 = Frame::checkout(&store, base.clone());
+      assert_eq!(_commit(&mut frame_a2, &mut store, _tree(&[("a", "2"), ("b", "1")])), a);
+      let mut frame_b = Frame::checkout(&store, base.clone());
+      let b = _commit(&mut frame_b, &mut store, _tree(&[("a", "1"), ("b", "2")]));
+      let snapshot_ct = store.snapshots.len();
+      let obj_ct = store.objects.len();
+      let (m, conflicts) = store.merge(base.clone(), a.clone(), b.clone()).unwrap();
+      assert!(conflicts.is_empty());
+      assert_eq!(store.snapshots.len(), snapshot_ct + 1);
+      assert!(store.objects.len() > obj_ct);
+      let obj_ct = store.objects.len();
+      let (m2, conflicts) = store.merge(base.clone(), a.clone(), b.clone()).unwrap();
+      assert!(conflicts.is_empty());
+      assert_eq!(m2, m);
+      assert_eq!(store.snapshots.len(), snapshot_ct + 1);
+      assert_eq!(store.objects.len(), obj_ct);
+      (a, m)
+    };
+    let store = Store::open(&dir).unwrap();
+    assert_eq!(_get_str(&store, &a, &["a"]).as_deref(), Some("2"));
+    assert_eq!(_get_str(&store, &m, &["a"]).as_deref(), Some("2"));
+    assert_eq!(_get_str(&store, &m, &["b"]).as_deref(), Some("2"));
+    drop(store);
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_frame_list_history_checkout() {
+    let mut store = Store::root();
+    let mut frame = Frame::root().fresh(&mut store).unwrap();
+    let s0 = frame.snapshot().clone();
+    let s1 = _commit(&mut frame, &mut store, _tree(&[("a", "1")]));
+    let s2 = _commit(&mut frame, &mut store, _tree(&[("a", "2")]));
+    let frames = store.list_frames();
+    assert_eq!(frames.len(), 1);
+    let (fid, frameptr) = frames[0].clone();
+    assert_eq!(frameptr.init(), &s0);
+    assert_eq!(frameptr.last(), &s2);
+    assert_eq!(store.frame_history(&fid), Some(vec![s2.clone(), s1.clone(), s0.clone()]));
+    // NB: a checkout has the data of its snapshot, and a commit from it
+    // branches off of that snapshot.
+    let mut frame1 = Frame::checkout(&store, s1.clone());
+    assert_eq!(frame1.snapshot(), &s1);
+    assert_eq!(frame1.view().data().tree_get("a").unwrap().as_str(), Some("1"));
+    frame1.commit(&mut store).unwrap();
+    assert_eq!(frame1.snapshot(), &s1);
+    let s3 = _commit(&mut frame1, &mut store, _tree(&[("a", "3")]));
+    assert_eq!(store.frame_history(&fid), Some(vec![s3.clone(), s1.clone(), s0.clone()]));
+    assert!(Frame::checkout(&store, SnapshotHash::root()).snapshot().is_root());
+    // NB: the history of a fresh frame stops at its init snapshot.
+    let frame2 = frame1.fresh(&mut store).unwrap();
+    let s4 = frame2.snapshot().clone();
+    let frames = store.list_frames();
+    assert_eq!(frames.len(), 2);
+    let fid2 = frames.iter().map(|(fid, _)| fid.clone()).find(|f| f != &fid).unwrap();
+    assert_eq!(store.frame_history(&fid2), Some(vec![s4]));
+    // NB: the history of a merge walks through its first prev.
+    let (m, _) = store.merge(s1.clone(), s3.clone(), s2.clone()).unwrap();
+    assert_eq!(store.frame_history(&fid), Some(vec![m, s3, s1, s0]));
+    // NB: the root frame has no frame pointer in the store.
+    assert_eq!(store.frame_history(&FrameId::root()), None);
+  }
+}
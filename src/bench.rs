@@ -0,0 +1,259 @@
+// A small criterion-style benchmark harness, for the benchmarks under
+// `benches/` (enabled by the `bench` feature).
+//
+// Each benchmark is run for a warmup period to estimate its per-iteration
+// time, and then for a fixed number of samples, each of enough iterations
+// to fill the measurement period; the reported numbers are per-iteration
+// times (in ns) over the samples.
+//
+// To compare before/after numbers, save a baseline from the "before" tree,
+// and then compare against it from the "after" tree:
+//
+//     cargo bench --features bench -- --save-baseline before
+//     # ... apply the change ...
+//     cargo bench --features bench -- --baseline before
+//
+// Baselines are stored as JSON in `target/pythia-bench/<name>.json` (see
+// `BenchConfig::dir`). A positional argument filters the benchmarks by
+// substring, e.g. `cargo bench --features bench -- unifier`.
+
+use serde::{Serialize, Deserialize};
+
+use std::collections::{BTreeMap};
+use std::fs;
+use std::hint::{black_box};
+use std::io::{Error as IoError};
+use std::path::{PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct BenchConfig {
+  pub warmup:   Duration,
+  pub measure:  Duration,
+  pub samples:  usize,
+  pub filter:   Option<String>,
+  pub save_baseline: Option<String>,
+  pub baseline: Option<String>,
+  pub dir:      PathBuf,
+}
+
+impl Default for BenchConfig {
+  fn default() -> BenchConfig {
+    BenchConfig{
+      warmup:   Duration::from_millis(500),
+      measure:  Duration::from_secs(3),
+      samples:  50,
+      filter:   None,
+      save_baseline: None,
+      baseline: None,
+      dir:      PathBuf::from("target/pythia-bench"),
+    }
+  }
+}
+
+impl BenchConfig {
+  // NB: unknown flags (e.g. the `--bench` passed by `cargo bench`) are
+  // ignored.
+  pub fn from_args() -> BenchConfig {
+    let mut cfg = BenchConfig::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "--save-baseline" => {
+          cfg.save_baseline = args.next();
+        }
+        "--baseline" => {
+          cfg.baseline = args.next();
+        }
+        "--samples" => {
+          if let Some(n) = args.next().and_then(|s| s.parse().ok()) {
+            cfg.samples = n;
+          }
+        }
+        "--measure-ms" => {
+          if let Some(ms) = args.next().and_then(|s| s.parse().ok()) {
+            cfg.measure = Duration::from_millis(ms);
+          }
+        }
+        _ if arg.starts_with("--") => {}
+        _ => {
+          cfg.filter = Some(arg);
+        }
+      }
+    }
+    cfg
+  }
+
+  pub fn _baseline_path(&self, name: &str) -> PathBuf {
+    self.dir.join(format!("{}.json", name))
+  }
+}
+
+// The per-iteration times (in ns) of one benchmark.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BenchStats {
+  pub samples:  usize,
+  pub iters:    u64,
+  pub mean:     f64,
+  pub median:   f64,
+  pub min:      f64,
+  pub max:      f64,
+  pub stddev:   f64,
+}
+
+impl BenchStats {
+  pub fn from_samples(iters: u64, xs: &mut [f64]) -> BenchStats {
+    assert!(!xs.is_empty());
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = xs.len();
+    let mean = xs.iter().sum::<f64>() / n as f64;
+    let median = if n % 2 == 0 {
+      0.5 * (xs[n / 2 - 1] + xs[n / 2])
+    } else {
+      xs[n / 2]
+    };
+    let var = xs.iter().map(|&x| (x - mean) * (x - mean)).sum::<f64>() / n as f64;
+    BenchStats{
+      samples:  n,
+      iters,
+      mean,
+      median,
+      min:      xs[0],
+      max:      xs[n - 1],
+      stddev:   var.sqrt(),
+    }
+  }
+}
+
+fn _fmt_ns(ns: f64) -> String {
+  if ns < 1.0e3 {
+    format!("{:.1} ns", ns)
+  } else if ns < 1.0e6 {
+    format!("{:.2} us", ns * 1.0e-3)
+  } else if ns < 1.0e9 {
+    format!("{:.2} ms", ns * 1.0e-6)
+  } else {
+    format!("{:.2} s", ns * 1.0e-9)
+  }
+}
+
+// Passed to each benchmark, which runs its routine w/ either `iter` or
+// `iter_batched`; only the routine is timed.
+pub struct Bencher {
+  iters:    u64,
+  elapsed:  Duration,
+}
+
+impl Bencher {
+  pub fn iter<R, F: FnMut() -> R>(&mut self, mut routine: F) {
+    let t0 = Instant::now();
+    for _ in 0 .. self.iters {
+      black_box(routine());
+    }
+    self.elapsed = t0.elapsed();
+  }
+
+  // NB: the input of each iteration is set up (and the output dropped)
+  // outside of the timed region.
+  pub fn iter_batched<S, R, FS: FnMut() -> S, F: FnMut(S) -> R>(&mut self, mut setup: FS, mut routine: F) {
+    let mut elapsed = Duration::ZERO;
+    for _ in 0 .. self.iters {
+      let input = setup();
+      let t0 = Instant::now();
+      let output = black_box(routine(black_box(input)));
+      elapsed += t0.elapsed();
+      drop(output);
+    }
+    self.elapsed = elapsed;
+  }
+
+  pub fn _run<F: FnMut(&mut Bencher)>(iters: u64, f: &mut F) -> Duration {
+    let mut b = Bencher{iters, elapsed: Duration::ZERO};
+    f(&mut b);
+    b.elapsed
+  }
+}
+
+pub struct BenchRunner {
+  cfg:      BenchConfig,
+  baseline: BTreeMap<String, BenchStats>,
+  results:  BTreeMap<String, BenchStats>,
+}
+
+impl BenchRunner {
+  pub fn new(cfg: BenchConfig) -> BenchRunner {
+    let mut baseline = BTreeMap::new();
+    if let Some(name) = cfg.baseline.as_ref() {
+      let path = cfg._baseline_path(name);
+      match fs::read_to_string(&path).ok().and_then(|s| serde_json::from_str(&s).ok()) {
+        None => println!("bench: no baseline at {}", path.display()),
+        Some(b) => baseline = b
+      }
+    }
+    BenchRunner{cfg, baseline, results: BTreeMap::new()}
+  }
+
+  pub fn from_args() -> BenchRunner {
+    BenchRunner::new(BenchConfig::from_args())
+  }
+
+  pub fn bench_function<F: FnMut(&mut Bencher)>(&mut self, name: &str, mut f: F) {
+    if let Some(filter) = self.cfg.filter.as_ref() {
+      if !name.contains(filter.as_str()) {
+        return;
+      }
+    }
+    // NB: the warmup doubles the iterations until it fills the warmup
+    // period, which also gives the per-iteration estimate.
+    let mut warm_iters = 1;
+    let mut warm_elapsed = Duration::ZERO;
+    let mut warm_total = 0;
+    let t0 = Instant::now();
+    while t0.elapsed() < self.cfg.warmup {
+      warm_elapsed += Bencher::_run(warm_iters, &mut f);
+      warm_total += warm_iters;
+      warm_iters *= 2;
+    }
+    let est = (warm_elapsed.as_nanos() as f64 / warm_total.max(1) as f64).max(1.0);
+    let samples = self.cfg.samples.max(1);
+    let budget = self.cfg.measure.as_nanos() as f64 / samples as f64;
+    let iters = ((budget / est) as u64).max(1);
+    let mut xs = Vec::with_capacity(samples);
+    for _ in 0 .. samples {
+      let elapsed = Bencher::_run(iters, &mut f);
+      xs.push(elapsed.as_nanos() as f64 / iters as f64);
+    }
+    let stats = BenchStats::from_samples(iters, &mut xs);
+    print!("{:<40} median {:>12}  mean {:>12}  sd {:>12}",
+        name, _fmt_ns(stats.median), _fmt_ns(stats.mean), _fmt_ns(stats.stddev));
+    if let Some(base) = self.baseline.get(name) {
+      let delta = (stats.median - base.median) / base.median * 100.0;
+      print!("  vs {:>12} ({:+.2}%)", _fmt_ns(base.median), delta);
+    }
+    println!();
+    self.results.insert(name.to_string(), stats);
+  }
+
+  pub fn results(&self) -> &BTreeMap<String, BenchStats> {
+    &self.results
+  }
+
+  // Saves the results as a baseline, if one was requested; the results
+  // are merged into an existing baseline of the same name, so that a
+  // filtered run only replaces the benchmarks that it ran.
+  pub fn finish(self) -> Result<(), IoError> {
+    let name = match self.cfg.save_baseline.as_ref() {
+      None => return Ok(()),
+      Some(name) => name
+    };
+    let path = self.cfg._baseline_path(name);
+    let mut saved: BTreeMap<String, BenchStats> = fs::read_to_string(&path).ok()
+      .and_then(|s| serde_json::from_str(&s).ok())
+      .unwrap_or_default();
+    saved.extend(self.results);
+    fs::create_dir_all(&self.cfg.dir)?;
+    fs::write(&path, serde_json::to_string_pretty(&saved).unwrap())?;
+    println!("bench: saved baseline to {}", path.display());
+    Ok(())
+  }
+}
@@ -12,7 +12,7 @@ use std::num::{ParseIntError};
 use std::ops::{Sub};
 use std::str::{FromStr};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(transparent)]
 pub struct Timedelta {
   inner: Duration,
@@ -34,6 +34,10 @@ impl Timedelta {
   pub fn zero() -> Timedelta {
     Timedelta{inner: Duration::zero()}
   }
+
+  pub fn from_millis(ms: i64) -> Timedelta {
+    Timedelta{inner: Duration::milliseconds(ms)}
+  }
 }
 
 #[derive(Clone, Copy)]
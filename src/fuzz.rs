@@ -0,0 +1,63 @@
+// Entry points for fuzzing the tokenizer and parser on arbitrary bytes
+// (e.g. from a cargo-fuzz target); any panic is a bug.
+//
+// A fuzz input is structured: the first byte selects the parser mode
+// (see `fuzz_mode`), and the rest is the source text, which is skipped
+// if it is not utf-8.
+
+use crate::parse::{FastParser, Mod, ParseSpanError, ParserMode, Token, Tokenizer};
+use crate::test_data::{TestDataConfig};
+
+pub fn fuzz_mode(bytes: &[u8]) -> Option<(ParserMode, &str)> {
+  let (&head, rest) = bytes.split_first()?;
+  let mode = match head & 1 {
+    0 => ParserMode::Pythia,
+    _ => ParserMode::PythonCompat,
+  };
+  let src = std::str::from_utf8(rest).ok()?;
+  Some((mode, src))
+}
+
+pub fn fuzz_tokenize(bytes: &[u8]) {
+  let (mode, src) = match fuzz_mode(bytes) {
+    None => return,
+    Some(t) => t
+  };
+  let mut tokens = Tokenizer::new(src);
+  tokens.set_compat(mode == ParserMode::PythonCompat);
+  let mut prev_end = 0;
+  for t in &mut tokens {
+    assert!(t.span.start <= t.span.end);
+    assert!(t.span.end <= src.len());
+    assert!(prev_end <= t.span.start);
+    prev_end = t.span.end;
+    if let Token::_Eof = t.tok {
+      break;
+    }
+  }
+  if let Some((span, _)) = tokens.err() {
+    assert!(span.end <= src.len());
+  }
+}
+
+pub fn fuzz_parse(bytes: &[u8]) -> Option<Result<Mod, ParseSpanError>> {
+  let (mode, src) = fuzz_mode(bytes)?;
+  let mut parser = FastParser::new(src);
+  parser.set_mode(mode);
+  Some(parser.mod_())
+}
+
+// The seed corpus: each interp test source, in each parser mode.
+pub fn fuzz_corpus() -> Vec<(String, Vec<u8>)> {
+  let cfg = TestDataConfig::interp_tests();
+  let mut corpus = Vec::new();
+  for item in cfg.iter_interp_tests() {
+    for (mode, tag) in [(0, "pythia"), (1, "compat")] {
+      let mut buf = Vec::with_capacity(1 + item.src.len());
+      buf.push(mode);
+      buf.extend_from_slice(item.src.as_bytes());
+      corpus.push((format!("{}.{}", item.key, tag), buf));
+    }
+  }
+  corpus
+}
@@ -6,10 +6,21 @@ use crate::algo::{
   OptionExt,
   SmolStr,
 };
+use crate::aikido::{ContentHash, Frame, Object, SnapshotHash, Store};
 use crate::algo::arc::{Arc};
+use crate::algo::base64::{Base64Format};
+use crate::algo::blake2s::{Blake2s};
 use crate::algo::cell::{RefCell};
+use crate::algo::cow::{CowChunkKey, CowMap, CowOrdMap, CowOrdSet};
 use crate::algo::rc::{Rc};
 use crate::algo::str::{SafeStr, safe_ascii};
+use crate::clock::{Timedelta, Timestamp};
+use crate::journal::{RunJournal, RunRecord, RunStatus};
+#[cfg(feature = "pyo3")]
+use crate::oracle::{
+  ApproxOracleItem, ApproxOracleRequest,
+  OracleBridge, OracleBridgeError, OracleReqId,
+};
 use crate::panick::{Loc, loc};
 use crate::parse::{
   Printer as DebugPrinter,
@@ -21,28 +32,63 @@ use crate::parse::{
   Ident as RawIdent_,
   Lit as RawLit_,
   DefPrefix as RawDefPrefix_,
+  Pragma as RawPragma_,
+  ParseSpanError,
+  ParserMode,
 };
-use crate::tap::{TAPOutput, _debugln, _traceln};
+use crate::parse::cache::{ParseCache};
+use crate::parse::intern::{SharedInterner, Sym};
+use crate::parse::visit::{Rewriter, SpanRebase};
+use crate::smp::{SmpInfo};
+use crate::src::{SourceEncoding, SourceFile, SourceMap, TranscodeConfig, transcode};
+use crate::tap::{LogSubsys, LogWriter, TAPOutput, _debugln, _traceln};
 
 use paste::{paste};
-use serde::{Serialize};
+use serde::{Serialize, Deserialize};
 use serde::ser::{Serializer, SerializeStruct};
 use serde_json_fmt::{JsonFormat};
+use smallvec::{SmallVec};
 
 use std::any::{Any, type_name};
 use std::cell::{Cell};
 use std::cmp::{Ordering, max, min};
+use std::collections::{VecDeque};
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 use std::hash::{Hash, Hasher};
-use std::io::{Write};
-use std::mem::{replace};
+use std::fs;
+use std::io::{Write, Error as IoError, ErrorKind as IoErrorKind};
+use std::iter::{repeat};
+use std::mem::{replace, size_of, take};
 use std::panic::{Location};
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 use std::str::{FromStr};
 
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod choice;
+pub mod clause;
+pub mod debug;
+pub mod dot;
+pub mod explain;
+#[cfg(feature = "flat_import")]
+pub mod flat;
+pub mod hooks;
+pub mod mcts;
+pub mod mem;
+pub mod modules;
 pub mod prelude;
-
-pub type RawSNum = u32;
+pub mod query;
+pub mod redo;
+pub mod registry;
+pub mod render;
+pub mod repl;
+pub mod search;
+pub mod split;
+pub mod stream;
+pub mod tabling;
+pub mod transition;
+
+pub type RawSNum = u64;
 pub type RawLClk = i64;
 pub type RawMClk = u64;
 pub type RawMAddr = Box<[u64]>;
@@ -53,6 +99,9 @@ pub type RawMAddr = Box<[u64]>;
 
 pub const SNUM_TAG_BITS: RawSNum = 8;
 pub const SNUM_TAG_MASK: RawSNum = 0xff;
+// NB: the raw SNum is 64 bits wide, so the key space (56 bits) should
+// not be exhausted by a realistic run; `SNumCtr::_fresh` checks anyway.
+pub const SNUM_KEY_MAX: RawSNum = RawSNum::max_value() >> SNUM_TAG_BITS;
 
 /*pub const SNUM_UNSORT:      RawSNum = 0;
 pub const SNUM_SPAN_SORT:   RawSNum = 1;
@@ -66,7 +115,7 @@ pub const _SNUM_MAX_SORT:   RawSNum = 10;*/
 
 // NB: must sync repr type w/ RawSNum.
 #[derive(Clone, Copy, Hash, Debug)]
-#[repr(u32)]
+#[repr(u64)]
 pub enum SNumSort {
   Unsort = 0,
   Gndstr = 1,
@@ -94,13 +143,15 @@ impl TryFrom<RawSNum> for SNumSort {
     // FIXME: must sync w/ enum variants (above).
     Ok(match raw_sort {
       0 => SNumSort::Unsort,
-      1 => SNumSort::Span,
-      2 => SNumSort::Code,
-      3 => SNumSort::Ident,
+      1 => SNumSort::Gndstr,
+      2 => SNumSort::Span,
+      3 => SNumSort::Code,
+      4 => SNumSort::Ident,
       7 => SNumSort::Cell,
       8 => SNumSort::Litstr,
       9 => SNumSort::Term,
       10 => SNumSort::Val,
+      11 => SNumSort::Frame,
       _ => return Err(raw_sort)
     })
   }
@@ -130,6 +181,14 @@ impl SNum {
   }
 }
 
+impl CowChunkKey for SNum {
+  #[inline]
+  fn cow_chunk(&self) -> u64 {
+    // NB: the sort tag is ignored by `Ord`, so only the key picks the chunk.
+    (self._key() >> 10) as u64
+  }
+}
+
 impl Serialize for SNum {
   fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
     /*let mut state = serializer.serialize_struct("SNum", 2)?;
@@ -152,24 +211,52 @@ impl Debug for SNum {
   }
 }
 
-impl PartialEq for SNum {
-  fn eq(&self, rhs: &SNum) -> bool {
-    let lkey = self.0 >> SNUM_TAG_BITS;
-    let rkey = rhs.0 >> SNUM_TAG_BITS;
+// [Interp-API-Pub]
+//
+// Two SNums w/ the same key but incompatible sort tags (see
+// `SNum::_check_cmp`).
+#[derive(Clone, Copy, Debug)]
+pub struct TagMismatch {
+  pub key:  RawSNum,
+  pub ltag: RawSNum,
+  pub rtag: RawSNum,
+}
+
+impl SNum {
+  // [Interp-API]
+  //
+  // Compares by key, same as `Ord`, but a key w/ incompatible sort tags
+  // (i.e. both sorted, and different) is an error, rather than ordered by
+  // the tags. An unsorted SNum (tag 0) is compatible w/ every sort.
+  #[inline]
+  pub(crate) fn _check_cmp(&self, rhs: &SNum) -> Result<Ordering, TagMismatch> {
+    let lkey = self._key();
+    let rkey = rhs._key();
     if lkey != rkey {
-      return false;
-    }
-    let ltag = self.0 & SNUM_TAG_MASK;
-    let rtag = rhs.0 & SNUM_TAG_MASK;
-    let lrcmp = ltag ^ rtag;
-    if lrcmp == 0 {
-      return true;
+      return Ok(lkey.cmp(&rkey));
     }
-    if lrcmp == ltag || lrcmp == rtag {
-      return true;
+    let ltag = self._tag();
+    let rtag = rhs._tag();
+    if ltag == rtag || ltag == 0 || rtag == 0 {
+      return Ok(Ordering::Equal);
     }
-    panic!("bug: SNum::partial_eq: tag sort mismatch: lkey={} ltag={} rkey={} rtag={}",
-        lkey, ltag, rkey, rtag);
+    Err(TagMismatch{key: lkey, ltag, rtag})
+  }
+
+  // [Interp-API]
+  #[inline]
+  pub(crate) fn _check_eq(&self, rhs: &SNum) -> Result<bool, TagMismatch> {
+    Ok(self._check_cmp(rhs)? == Ordering::Equal)
+  }
+}
+
+// NB: the comparison traits do not panic; a key w/ incompatible sort tags
+// is unequal, and ordered by the tags (the interpreter uses the checked
+// `_check_cmp`/`_check_eq` where a mismatch would be a bug).
+
+impl PartialEq for SNum {
+  fn eq(&self, rhs: &SNum) -> bool {
+    self.cmp(rhs) == Ordering::Equal
   }
 }
 
@@ -183,20 +270,9 @@ impl PartialOrd for SNum {
 
 impl Ord for SNum {
   fn cmp(&self, rhs: &SNum) -> Ordering {
-    let lkey = self.0 >> SNUM_TAG_BITS;
-    let rkey = rhs.0 >> SNUM_TAG_BITS;
-    if lkey == rkey {
-      if self != rhs {
-        panic!("bug");
-      } else {
-        return Ordering::Equal;
-      }
-    } else if lkey < rkey {
-      return Ordering::Less;
-    } else if lkey > rkey {
-      return Ordering::Greater;
-    } else {
-      unreachable!();
+    match self._check_cmp(rhs) {
+      Ok(ord) => ord,
+      Err(e) => e.ltag.cmp(&e.rtag)
     }
   }
 }
@@ -327,7 +403,7 @@ pub fn unimpl<T: Unimpl>() -> T {
 
 pub type SCtr = SNumCtr;
 
-#[derive(Serialize, Debug)]
+#[derive(Clone, Serialize, Debug)]
 pub struct SNumCtr {
   rctr: Cell<RawSNum>,
 }
@@ -346,12 +422,16 @@ impl SNumCtr {
     self.rctr.set(next);
   }
 
-  pub fn _fresh(&self) -> SNum {
-    let next = self.rctr.get() + 1;
+  pub fn _fresh(&self) -> Result<SNum, InterpCheck> {
+    let cur = self.rctr.get();
+    if cur >= SNUM_KEY_MAX {
+      return Err("SNumCtr::_fresh: exhausted SNum key space".into());
+    }
+    let next = cur + 1;
     self.rctr.set(next);
     // NB: untagged/unsorted SNum.
     let x = (next << SNUM_TAG_BITS);
-    SNum(x)
+    Ok(SNum(x))
   }
 
   pub fn _get(&self) -> SNum {
@@ -362,7 +442,7 @@ impl SNumCtr {
   }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct LClkCtr {
   rctr: Cell<RawLClk>,
 }
@@ -399,9 +479,29 @@ pub struct LClkRange {
   pub ub: LClk,
 }
 
-#[derive(Default, Serialize)]
+// A set of invalidated (i.e. undone) clock ranges, kept as disjoint,
+// non-adjacent half-open ranges keyed by their lower bound.
+#[derive(Clone, Default, Serialize)]
 pub struct LClkInvalidSet {
   inner: BTreeMap<LClk, LClk>,
+  #[serde(skip)]
+  insert_ct:  u64,
+  #[serde(skip)]
+  compact_ct: u64,
+  #[serde(skip)]
+  drop_ct:    u64,
+}
+
+// Counts for observability (see `FastInterp::clkinval_stats`).
+#[derive(Clone, Copy, Default, Serialize, Debug)]
+pub struct LClkInvalidStats {
+  // The current number of ranges, and the number of clocks they cover.
+  pub ranges:     usize,
+  pub clocks:     u64,
+  pub insert_ct:  u64,
+  pub compact_ct: u64,
+  // The number of ranges dropped by compaction.
+  pub drop_ct:    u64,
 }
 
 impl LClkInvalidSet {
@@ -409,33 +509,121 @@ impl LClkInvalidSet {
     if lb >= ub {
       return Err(());
     }
-    match (self._find(lb), self._find(ub)) {
-      (Some(lrg), Some(urg)) => {
-        if lrg.lb != urg.lb {
-          let _ = self.inner.remove(&urg.lb);
-          self.inner.insert(lrg.lb, urg.ub);
-        }
-      }
-      (Some(lrg), None) => {
-        /*assert!(lrg.ub < ub);*/
-        self.inner.insert(lrg.lb, ub);
+    self.insert_ct += 1;
+    let (mut lb, mut ub) = (lb, ub);
+    // NB: merge w/ a preceding range that overlaps or abuts...
+    if let Some((&plb, &pub_)) = self.inner.range( ..= lb).next_back() {
+      if pub_ >= lb {
+        lb = plb;
+        ub = max(ub, pub_);
       }
-      (None, Some(urg)) => {
-        /*assert!(lb < urg.ub);*/
-        let _ = self.inner.remove(&urg.lb);
-        self.inner.insert(lb, urg.ub);
-      }
-      (None, None) => {
+    }
+    // ... and absorb any following ranges that start within (or abut)
+    // the merged range.
+    let absorb: Vec<LClk> = self.inner.range(lb ..= ub).map(|(&k, _)| k).collect();
+    for k in absorb.into_iter() {
+      let kub = self.inner.remove(&k).unwrap();
+      ub = max(ub, kub);
+    }
+    self.inner.insert(lb, ub);
+    Ok(())
+  }
+
+  // Invalidates every clock from `lb` up to (but excluding) `ub`, where
+  // `ub` is the next fresh clock; all the ranges from `lb` on are thus
+  // subsumed, and are dropped in bulk.
+  pub fn invalidate_from(&mut self, lb: LClk, ub: LClk) -> Result<(), ()> {
+    if lb >= ub {
+      return Err(());
+    }
+    let _ = self.inner.split_off(&lb);
+    self._insert(lb, ub)
+  }
+
+  // Drops the ranges (or parts of ranges) below `lb`; returns the number
+  // of ranges dropped.
+  //
+  // NB: the caller must first drop anything that could still look up a
+  // clock below `lb` (see `FastInterp::compact_clkinval`).
+  pub fn _compact(&mut self, lb: LClk) -> usize {
+    self.compact_ct += 1;
+    let keep = self.inner.split_off(&lb);
+    let below = replace(&mut self.inner, keep);
+    let mut drop_ct = below.len();
+    if let Some((_, &ub)) = below.iter().next_back() {
+      if ub > lb {
+        // NB: keep the part of the range straddling `lb`.
+        drop_ct -= 1;
         self.inner.insert(lb, ub);
       }
     }
-    Ok(())
+    self.drop_ct += drop_ct as u64;
+    drop_ct
+  }
+
+  pub fn _stats(&self) -> LClkInvalidStats {
+    let mut clocks = 0;
+    for (&lb, &ub) in self.inner.iter() {
+      clocks += (ub._into_raw() - lb._into_raw()) as u64;
+    }
+    LClkInvalidStats{
+      ranges:     self.inner.len(),
+      clocks,
+      insert_ct:  self.insert_ct,
+      compact_ct: self.compact_ct,
+      drop_ct:    self.drop_ct,
+    }
   }
 
   pub fn _contains(&self, v: LClk) -> bool {
     self._find(v).is_some()
   }
 
+  // The ranges (or parts of ranges) within `lb` up to (but excluding) `ub`.
+  pub fn _ranges(&self, lb: LClk, ub: LClk) -> Vec<(LClk, LClk)> {
+    let mut buf = Vec::new();
+    if let Some((_, &pub_)) = self.inner.range( ..= lb).next_back() {
+      if pub_ > lb {
+        buf.push((lb, min(pub_, ub)));
+      }
+    }
+    for (&k, &kub) in self.inner.range(lb .. ub) {
+      if k > lb {
+        buf.push((k, min(kub, ub)));
+      }
+    }
+    buf
+  }
+
+  // Validates every clock from `lb` up to (but excluding) `ub`, except
+  // for the ranges `keep` (see `FastInterp::_redo_replay`).
+  pub fn _revalidate(&mut self, lb: LClk, ub: LClk, keep: &[(LClk, LClk)]) {
+    if lb >= ub {
+      return;
+    }
+    let mut tail = self.inner.split_off(&lb);
+    // NB: trim a preceding range that straddles `lb`...
+    if let Some((&plb, &pub_)) = self.inner.range( ..= lb).next_back() {
+      if pub_ > lb {
+        self.inner.insert(plb, lb);
+        if pub_ > ub {
+          tail.insert(ub, pub_);
+        }
+      }
+    }
+    // ... and the ranges from `lb` on.
+    let above = tail.split_off(&ub);
+    if let Some((_, &kub)) = tail.iter().next_back() {
+      if kub > ub {
+        self.inner.insert(ub, kub);
+      }
+    }
+    self.inner.extend(above);
+    for &(klb, kub) in keep.iter() {
+      let _ = self._insert(klb, kub);
+    }
+  }
+
   pub fn _find(&self, v: LClk) -> Option<LClkRange> {
     match self.inner.range( ..= v).next_back() {
       Some((&lb, &ub)) => {
@@ -461,72 +649,73 @@ impl SNum {
     self.0
   }
 
-  pub fn into_fun(self) -> FunNum {
-    // FIXME: tag sort check.
-    FunNum(self.0)
-  }
-
-  pub fn into_obj_cls(self) -> ObjClsNum {
-    // FIXME: tag sort check.
-    ObjClsNum(self.0)
-  }
-
-  pub fn into_obj_val(self) -> ObjValNum {
-    // FIXME: tag sort check.
-    ObjValNum(self.0)
+  pub fn _sort(&self) -> Result<SNumSort, RawSNum> {
+    SNumSort::try_from(self._tag())
   }
 
-  pub fn into_cell(self) -> CellNum {
-    // FIXME: tag sort check.
-    CellNum(self.0)
-  }
-
-  pub fn into_atom(self) -> AtomNum {
-    // FIXME: tag sort check.
-    AtomNum(self.0)
-  }
-
-  pub fn into_ident(self) -> IdentNum {
-    // FIXME: tag sort check.
-    IdentNum(self.0)
-  }
-
-  pub fn into_lit_str(self) -> LitStrNum {
-    // FIXME: tag sort check.
-    LitStrNum(self.0)
+  // NB: an unsorted SNum (tag 0) is compatible w/ every sort,
+  // same as in `PartialEq`.
+  pub fn _check_sort(&self, sort: SNumSort) -> Result<(), SortMismatch> {
+    let tag = self._tag();
+    if tag == 0 || tag == sort as RawSNum {
+      return Ok(());
+    }
+    Err(SortMismatch{key: self._key(), expected: sort, found: self._sort()})
   }
+}
 
-  pub fn into_term(self) -> TermNum {
-    // FIXME: tag sort check.
-    TermNum(self.0)
-  }
+// Whether the unchecked `SNum::into_*` conversions check the sort tag
+// anyway (and panic on a mismatch); the `try_into_*` conversions always
+// check the sort tag.
+pub const SNUM_SORT_CHECK: bool = cfg!(debug_assertions);
 
-  pub fn into_span(self) -> SpanNum {
-    // FIXME: tag sort check.
-    SpanNum(self.0)
-  }
+// [Interp-API-Pub]
+#[derive(Clone, Copy, Debug)]
+pub struct SortMismatch {
+  pub key: RawSNum,
+  pub expected: SNumSort,
+  pub found: Result<SNumSort, RawSNum>,
+}
 
-  pub fn into_mod_code(self) -> ModCodeNum {
-    // FIXME: tag sort check.
-    ModCodeNum(self.0)
-  }
+impl IntoInterpCheckExt for SortMismatch {}
 
-  pub fn into_stm_code(self) -> StmCodeNum {
-    // FIXME: tag sort check.
-    StmCodeNum(self.0)
-  }
+impl IntoInterpCheckExt for TagMismatch {}
 
-  pub fn into_term_code(self) -> TermCodeNum {
-    // FIXME: tag sort check.
-    TermCodeNum(self.0)
-  }
+macro_rules! impl_snum_into {
+  ($T:tt, $into:ident, $try_into:ident, $sort:expr) => {
+    impl SNum {
+      #[inline]
+      pub fn $into(self) -> $T {
+        if SNUM_SORT_CHECK {
+          if let Err(e) = self._check_sort($sort) {
+            panic!(concat!("bug: SNum::", stringify!($into), ": {:?}"), e);
+          }
+        }
+        $T(self.0)
+      }
 
-  pub fn into_frame(self) -> FrameNum {
-    // FIXME: tag sort check.
-    FrameNum(self.0)
-  }
+      pub fn $try_into(self) -> Result<$T, SortMismatch> {
+        self._check_sort($sort)?;
+        Ok($T(self.0))
+      }
+    }
+  };
 }
 
+impl_snum_into!(FunNum, into_fun, try_into_fun, SNumSort::Val);
+impl_snum_into!(ObjClsNum, into_obj_cls, try_into_obj_cls, SNumSort::Val);
+impl_snum_into!(ObjValNum, into_obj_val, try_into_obj_val, SNumSort::Val);
+impl_snum_into!(CellNum, into_cell, try_into_cell, SNumSort::Cell);
+impl_snum_into!(AtomNum, into_atom, try_into_atom, SNumSort::Val);
+impl_snum_into!(IdentNum, into_ident, try_into_ident, SNumSort::Ident);
+impl_snum_into!(LitStrNum, into_lit_str, try_into_lit_str, SNumSort::Litstr);
+impl_snum_into!(TermNum, into_term, try_into_term, SNumSort::Term);
+impl_snum_into!(SpanNum, into_span, try_into_span, SNumSort::Span);
+impl_snum_into!(ModCodeNum, into_mod_code, try_into_mod_code, SNumSort::Code);
+impl_snum_into!(StmCodeNum, into_stm_code, try_into_stm_code, SNumSort::Code);
+impl_snum_into!(TermCodeNum, into_term_code, try_into_term_code, SNumSort::Code);
+impl_snum_into!(FrameNum, into_frame, try_into_frame, SNumSort::Frame);
+
 // [Interp-API]
 //
 // A tuple-cell, or _cell_, is one element in a linked-list repr of a tuple.
@@ -592,7 +781,12 @@ pub struct BoolTerm_ {
 // () | true | false | Arc<i64> | Arc<str>
 //
 // (The Arc<str> is an impl detail due to SmolStr.)
+//
+// NB: this used to be a tagged pointer, whose clone, hash, and unpack
+// had to panic on an unknown tag; the enum makes those states
+// unrepresentable.
 
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum UnpackedLitTerm_ {
   None,
   True,
@@ -601,211 +795,43 @@ pub enum UnpackedLitTerm_ {
   Str(Arc<SafeStr>),
 }
 
-const LIT_TERM_TAG_BITS: usize = 3;
-const LIT_TERM_TAG_MASK: usize = 7;
-
-const LIT_TERM_TAG_NONE: usize = 0;
-const LIT_TERM_TAG_TRUE: usize = 1;
-const LIT_TERM_TAG_FALSE: usize = 2;
-const LIT_TERM_TAG_INT: usize = 3;
-const LIT_TERM_TAG_STR: usize = 4;
+pub const LIT_TERM_TAG_NONE: usize = 0;
+pub const LIT_TERM_TAG_TRUE: usize = 1;
+pub const LIT_TERM_TAG_FALSE: usize = 2;
+pub const LIT_TERM_TAG_INT: usize = 3;
+pub const LIT_TERM_TAG_STR: usize = 4;
 
+#[derive(Clone, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct LitTerm_ {
-  raw_tptr: usize,
-  //raw_len:  usize,
-}
-
-impl Clone for LitTerm_ {
-  fn clone(&self) -> LitTerm_ {
-    let tag = self.raw_tptr & LIT_TERM_TAG_MASK;
-    let raw_ptr = self.raw_tptr ^ tag;
-    match tag {
-      LIT_TERM_TAG_NONE |
-      LIT_TERM_TAG_TRUE |
-      LIT_TERM_TAG_FALSE => {
-        LitTerm_{raw_tptr: self.raw_tptr}
-      }
-      LIT_TERM_TAG_INT => {
-        let ptr = unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const i64);
-          let ptr2 = ptr.clone();
-          let _ = Arc::into_raw(ptr);
-          ptr2
-        };
-        let raw_ptr = Arc::into_raw(ptr) as usize;
-        assert_eq!(raw_ptr & LIT_TERM_TAG_MASK, 0);
-        let raw_tptr = raw_ptr ^ LIT_TERM_TAG_INT;
-        LitTerm_{raw_tptr}
-      }
-      LIT_TERM_TAG_STR => {
-        let ptr = unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const SafeStr);
-          let ptr2 = ptr.clone();
-          let _ = Arc::into_raw(ptr);
-          ptr2
-        };
-        let raw_ptr = Arc::into_raw(ptr) as usize;
-        assert_eq!(raw_ptr & LIT_TERM_TAG_MASK, 0);
-        let raw_tptr = raw_ptr ^ LIT_TERM_TAG_INT;
-        LitTerm_{raw_tptr}
-      }
-      _ => {
-        // TODO: unfortunately we can't return an interp check here!
-        panic!("bug");
-      }
-    }
-  }
-}
-
-impl PartialEq for LitTerm_ {
-  fn eq(&self, rhs: &LitTerm_) -> bool {
-    let ltag = self.raw_tptr & LIT_TERM_TAG_MASK;
-    let rtag = rhs.raw_tptr & LIT_TERM_TAG_MASK;
-    let l_raw_ptr = self.raw_tptr ^ ltag;
-    let r_raw_ptr = rhs.raw_tptr ^ rtag;
-    match (ltag, rtag) {
-      (LIT_TERM_TAG_NONE, LIT_TERM_TAG_NONE) |
-      (LIT_TERM_TAG_TRUE, LIT_TERM_TAG_TRUE) |
-      (LIT_TERM_TAG_FALSE, LIT_TERM_TAG_FALSE) => {
-        true
-      }
-      (LIT_TERM_TAG_INT, LIT_TERM_TAG_INT) => {
-        unsafe {
-          let l_ptr = Arc::from_raw(l_raw_ptr as *const i64);
-          let r_ptr = Arc::from_raw(r_raw_ptr as *const i64);
-          let res = l_ptr == r_ptr;
-          let _ = Arc::into_raw(l_ptr);
-          let _ = Arc::into_raw(r_ptr);
-          res
-        }
-      }
-      (LIT_TERM_TAG_STR, LIT_TERM_TAG_STR) => {
-        unsafe {
-          let l_ptr = Arc::from_raw(l_raw_ptr as *const SafeStr);
-          let r_ptr = Arc::from_raw(r_raw_ptr as *const SafeStr);
-          let res = l_ptr == r_ptr;
-          let _ = Arc::into_raw(l_ptr);
-          let _ = Arc::into_raw(r_ptr);
-          res
-        }
-      }
-      _ => false
-    }
-  }
-}
-
-impl Eq for LitTerm_ {
-}
-
-impl Hash for LitTerm_ {
-  fn hash<H: Hasher>(&self, state: &mut H) {
-    let tag = self.raw_tptr & LIT_TERM_TAG_MASK;
-    let raw_ptr = self.raw_tptr ^ tag;
-    match tag {
-      LIT_TERM_TAG_NONE => {
-        ().hash(state)
-      }
-      LIT_TERM_TAG_TRUE => {
-        true.hash(state)
-      }
-      LIT_TERM_TAG_FALSE => {
-        false.hash(state)
-      }
-      LIT_TERM_TAG_INT => {
-        unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const i64);
-          ptr.hash(state);
-          let _ = Arc::into_raw(ptr);
-        }
-      }
-      LIT_TERM_TAG_STR => {
-        unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const SafeStr);
-          ptr.hash(state);
-          let _ = Arc::into_raw(ptr);
-        }
-      }
-      _ => {
-        panic!("bug");
-      }
-    }
-  }
+  inner: UnpackedLitTerm_,
 }
 
 impl Serialize for LitTerm_ {
   fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-    let tag = self.raw_tptr & LIT_TERM_TAG_MASK;
-    let raw_ptr = self.raw_tptr ^ tag;
-    match tag {
-      LIT_TERM_TAG_NONE => {
-        serializer.serialize_str(&format!("LitTerm_(None)"))
-      }
-      LIT_TERM_TAG_TRUE => {
-        serializer.serialize_str(&format!("LitTerm_(True)"))
-      }
-      LIT_TERM_TAG_FALSE => {
-        serializer.serialize_str(&format!("LitTerm_(False)"))
-      }
-      LIT_TERM_TAG_INT => {
-        unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const i64);
-          let ret = serializer.serialize_str(&format!("LitTerm_(Int={:?})", ptr));
-          let _ = Arc::into_raw(ptr);
-          ret
-        }
-      }
-      LIT_TERM_TAG_STR => {
-        unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const SafeStr);
-          // NB: ptr is a boxed SafeStr, so okay to directly display;
-          // also, debug print adds an unnecessary quotation level.
-          let ret = serializer.serialize_str(&format!("LitTerm_(Str={})", ptr));
-          let _ = Arc::into_raw(ptr);
-          ret
-        }
-      }
-      _ => {
-        serializer.serialize_str(&format!("LitTerm_(raw={:x}.{})", raw_ptr, tag))
-      }
-    }
+    serializer.serialize_str(&format!("{:?}", self))
   }
 }
 
 impl Debug for LitTerm_ {
   fn fmt(&self, f: &mut Formatter) -> FmtResult {
-    let tag = self.raw_tptr & LIT_TERM_TAG_MASK;
-    let raw_ptr = self.raw_tptr ^ tag;
-    match tag {
-      LIT_TERM_TAG_NONE => {
+    match &self.inner {
+      &UnpackedLitTerm_::None => {
         write!(f, "LitTerm_(None)")
       }
-      LIT_TERM_TAG_TRUE => {
+      &UnpackedLitTerm_::True => {
         write!(f, "LitTerm_(True)")
       }
-      LIT_TERM_TAG_FALSE => {
+      &UnpackedLitTerm_::False => {
         write!(f, "LitTerm_(False)")
       }
-      LIT_TERM_TAG_INT => {
-        unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const i64);
-          write!(f, "LitTerm_(Int={:?})", ptr)?;
-          let _ = Arc::into_raw(ptr);
-        }
-        Ok(())
-      }
-      LIT_TERM_TAG_STR => {
-        unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const SafeStr);
-          // NB: ptr is a boxed SafeStr, so okay to directly display;
-          // also, debug print adds an unnecessary quotation level.
-          write!(f, "LitTerm_(Str={})", ptr)?;
-          let _ = Arc::into_raw(ptr);
-        }
-        Ok(())
+      &UnpackedLitTerm_::Int(ref v) => {
+        write!(f, "LitTerm_(Int={:?})", v)
       }
-      _ => {
-        write!(f, "LitTerm_(raw={:x}.{})", raw_ptr, tag)
+      &UnpackedLitTerm_::Str(ref v) => {
+        // NB: v is a boxed SafeStr, so okay to directly display;
+        // also, debug print adds an unnecessary quotation level.
+        write!(f, "LitTerm_(Str={})", v)
       }
     }
   }
@@ -813,15 +839,15 @@ impl Debug for LitTerm_ {
 
 impl LitTerm_ {
   pub fn new_none() -> LitTerm_ {
-    LitTerm_{raw_tptr: LIT_TERM_TAG_NONE}
+    LitTerm_{inner: UnpackedLitTerm_::None}
   }
 
   pub fn new_true() -> LitTerm_ {
-    LitTerm_{raw_tptr: LIT_TERM_TAG_TRUE}
+    LitTerm_{inner: UnpackedLitTerm_::True}
   }
 
   pub fn new_false() -> LitTerm_ {
-    LitTerm_{raw_tptr: LIT_TERM_TAG_FALSE}
+    LitTerm_{inner: UnpackedLitTerm_::False}
   }
 
   pub fn new_bool(v: bool) -> LitTerm_ {
@@ -832,64 +858,28 @@ impl LitTerm_ {
   }
 
   pub fn new_int(v: i64) -> LitTerm_ {
-    let ptr = Arc::new(v);
-    let raw_ptr = Arc::into_raw(ptr) as usize;
-    assert_eq!(raw_ptr & LIT_TERM_TAG_MASK, 0);
-    let raw_tptr = raw_ptr ^ LIT_TERM_TAG_INT;
-    LitTerm_{raw_tptr}
+    LitTerm_{inner: UnpackedLitTerm_::Int(Arc::new(v))}
   }
 
   pub fn new_str(v: SafeStr) -> LitTerm_ {
     // FIXME: directly convert SafeStr, which is SmolStr-backed, into Arc<str>.
-    let ptr = Arc::new(v);
-    let raw_ptr = Arc::into_raw(ptr) as usize;
-    assert_eq!(raw_ptr & LIT_TERM_TAG_MASK, 0);
-    let raw_tptr = raw_ptr ^ LIT_TERM_TAG_STR;
-    LitTerm_{raw_tptr}
+    LitTerm_{inner: UnpackedLitTerm_::Str(Arc::new(v))}
   }
 
   #[inline]
   pub fn _tag(&self) -> usize {
-    let tag = self.raw_tptr & LIT_TERM_TAG_MASK;
-    tag
+    match &self.inner {
+      &UnpackedLitTerm_::None => LIT_TERM_TAG_NONE,
+      &UnpackedLitTerm_::True => LIT_TERM_TAG_TRUE,
+      &UnpackedLitTerm_::False => LIT_TERM_TAG_FALSE,
+      &UnpackedLitTerm_::Int(_) => LIT_TERM_TAG_INT,
+      &UnpackedLitTerm_::Str(_) => LIT_TERM_TAG_STR,
+    }
   }
 
+  #[inline]
   pub fn _unpack(&self) -> UnpackedLitTerm_ {
-    let tag = self.raw_tptr & LIT_TERM_TAG_MASK;
-    let raw_ptr = self.raw_tptr ^ tag;
-    match tag {
-      LIT_TERM_TAG_NONE => {
-        UnpackedLitTerm_::None
-      }
-      LIT_TERM_TAG_TRUE => {
-        UnpackedLitTerm_::True
-      }
-      LIT_TERM_TAG_FALSE => {
-        UnpackedLitTerm_::False
-      }
-      LIT_TERM_TAG_INT => {
-        let ptr = unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const i64);
-          let ptr2 = ptr.clone();
-          let _ = Arc::into_raw(ptr);
-          ptr2
-        };
-        UnpackedLitTerm_::Int(ptr)
-      }
-      LIT_TERM_TAG_STR => {
-        let ptr = unsafe {
-          let ptr = Arc::from_raw(raw_ptr as *const SafeStr);
-          let ptr2 = ptr.clone();
-          let _ = Arc::into_raw(ptr);
-          ptr2
-        };
-        UnpackedLitTerm_::Str(ptr)
-      }
-      _ => {
-        // TODO: unfortunately we can't return an interp check here!
-        panic!("bug");
-      }
-    }
+    self.inner.clone()
   }
 }
 
@@ -901,12 +891,71 @@ pub struct NEqualTerm_ {
   buf:  [ENum; 2],
 }
 
+// The max number of children of a tuple that are stored inline (i.e.
+// w/out a heap allocation); most tuples are short applies.
+pub const TUPLE_INLINE_LEN: usize = 4;
+
+pub type TupleBuf_ = SmallVec<[ENum; TUPLE_INLINE_LEN]>;
+
 // [Interp-API]
 #[derive(Clone, Serialize, Debug)]
 #[serde(tag = "__type__")]
 #[repr(transparent)]
 pub struct TupleTerm_ {
-  buf:  Box<[ENum]>,
+  buf:  TupleBuf_,
+}
+
+// The instance of the first child of a tuple term (see `tuple_index`).
+pub fn _tuple_first<T: Tabled + ?Sized>(e: &T) -> Option<SNum> {
+  e.as_any().downcast_ref::<TupleTerm_>()
+    .and_then(|t| t.buf.first().map(|c| c.inst))
+}
+
+// [Interp-API]
+//
+// A proc closes over the local scope in which it was defined (nil if it
+// was defined at module scope).
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct ProcTerm_ {
+  code: StmCodeNum,
+  scope: SNum,
+}
+
+// [Interp-API]
+//
+// The defmatch clauses of a name (see `FastInterp::defmatch_candidates`),
+// w/ the scope in which the first clause was run; like a proc, each
+// clause body is interpreted in a fresh scope enclosed by `scope`.
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct MatchTerm_ {
+  id: IdentNum,
+  scope: SNum,
+}
+
+// [Interp-API]
+//
+// A lambda closes over the local scope in which it was evaluated (nil if
+// at module scope); NB: the captured idents are resolved through `scope`
+// when the lambda is applied, so a captured var that has since been
+// further unified (or un-unified, by backtracking) is seen as such.
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct LambdaTerm_ {
+  code: TermCodeNum,
+  scope: SNum,
+}
+
+// [Interp-API]
+//
+// A reified quote block: `buf` holds the code SNums of the quoted stms,
+// in order, and `body_stmp` links them for unquoting.
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct QuoteTerm_ {
+  body_stmp: StmCodeCellNum,
+  buf:  Box<[StmCodeNum]>,
 }
 
 // [Interp-API]
@@ -1039,14 +1088,32 @@ impl Nil for Cell<CellNum> {
 
 impl CellNum {
   pub fn into_stm_code(self) -> StmCodeCellNum {
-    // FIXME: tag sort check.
+    if SNUM_SORT_CHECK {
+      if let Err(e) = self._into_snum()._check_sort(SNumSort::Cell) {
+        panic!("bug: CellNum::into_stm_code: {:?}", e);
+      }
+    }
     StmCodeCellNum(self.0)
   }
 
+  pub fn try_into_stm_code(self) -> Result<StmCodeCellNum, SortMismatch> {
+    self._into_snum()._check_sort(SNumSort::Cell)?;
+    Ok(StmCodeCellNum(self.0))
+  }
+
   pub fn into_term_code(self) -> TermCodeCellNum {
-    // FIXME: tag sort check.
+    if SNUM_SORT_CHECK {
+      if let Err(e) = self._into_snum()._check_sort(SNumSort::Cell) {
+        panic!("bug: CellNum::into_term_code: {:?}", e);
+      }
+    }
     TermCodeCellNum(self.0)
   }
+
+  pub fn try_into_term_code(self) -> Result<TermCodeCellNum, SortMismatch> {
+    self._into_snum()._check_sort(SNumSort::Cell)?;
+    Ok(TermCodeCellNum(self.0))
+  }
 }
 
 // [Interp-API]
@@ -1091,15 +1158,46 @@ pub enum StmCode_ {
   Pass{span: SpanNum},
   Global{span: SpanNum, id: IdentNum},
   Nonlocal{span: SpanNum, static_scope: Option<i16>, id: IdentNum},
+  // NB: each ident is bound to a fresh unbound var (w/out a tabled term)
+  // in the current scope, shadowing any outer binding.
+  Fresh{span: SpanNum, ids: Vec<IdentNum>},
+  // NB: each ident is bound in the current scope to the module of that
+  // name, which must already be loaded (see `load_many`).
+  Import{span: SpanNum, ids: Vec<IdentNum>},
+  // NB: the goals are run in match context, in a scoped sub-run (see
+  // `_scoped_term`); a failure is recorded, and does not abort.
+  Assert{span: SpanNum, term: TermCodeNum},
+  Expect{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
   If{span: SpanNum, cases: Vec<(TermCodeNum, StmCodeCellNum)>, final_case: StmCodeCellNum},
+  // NB: the case patterns are matched against the scrutinee in order
+  // (see `_match_pat`); unless `match_commit` is set, a failing body
+  // backtracks to the later cases.
+  Match{span: SpanNum, scrut: TermCodeNum, cases: Vec<(TermCodeNum, StmCodeCellNum)>},
+  // NB: the case bodies are alternatives, one of which is chosen at a
+  // single choice point (see `_choose_index`); a failing body backtracks
+  // to the later cases.
+  Cases{span: SpanNum, cases: Vec<StmCodeCellNum>},
   With{span: SpanNum, ctx: TermCodeNum, stmp: StmCodeCellNum},
   // TODO
-  Defproc{span: SpanNum, body_stmp: StmCodeCellNum},
-  //Defproc{span: SpanNum, ..., body_stmp: StmCodeCellNum},
-  Defmatch{span: SpanNum, body_stmp: StmCodeCellNum},
-  //Defmatch{span: SpanNum, ..., body_stmp: StmCodeCellNum},
+  // NB: each default is the stm `param = default`, which is run in the
+  // proc scope (see `_bind_call_args`). The rest param (if any) is bound
+  // to a list of the extra positional args.
+  Defproc{span: SpanNum, id: IdentNum, params: Vec<Option<IdentNum>>, rest: Option<IdentNum>, defaults: Vec<(IdentNum, StmCodeNum)>, body_stmp: StmCodeCellNum},
+  Defmatch{span: SpanNum, id: IdentNum, params: Vec<Option<IdentNum>>, body_stmp: StmCodeCellNum},
+  // NB: a rule is indexed (see `rule_index`) when loaded, but running the
+  // stm is a no-op: the head patterns and body goals are not run in the
+  // current scope, but are left to be consumed as is by the (forward
+  // chaining) rule engine (see `lookup_rule`).
+  Defrule{span: SpanNum, id: IdentNum, head: Vec<TermCodeNum>, body_stmp: StmCodeCellNum},
+  // NB: the body stms run in a fresh class scope, whose bindings are the
+  // class attrs (e.g. the methods) of the `prelude::ClassCls` bound to `id`.
+  Class{span: SpanNum, id: IdentNum, body_stmp: StmCodeCellNum},
+  // NB: the body stms of a module (see `load_many`) run in a fresh module
+  // scope, whose bindings are the attrs of the `prelude::ModuleVal` bound
+  // to `id`. There is no surface syntax for this stm.
+  Module{span: SpanNum, id: IdentNum, body_stmp: StmCodeCellNum},
   // FIXME: stm only b/c of parsing hack.
-  Quote{span: SpanNum},
+  Quote{span: SpanNum, body_stmp: StmCodeCellNum},
 }
 
 #[derive(Clone, Serialize, Debug)]
@@ -1117,6 +1215,17 @@ pub enum TermCode_ {
   Bunch{span: SpanNum, tup: TermCodeCellNum},
   Query{span: SpanNum, term: TermCodeNum},
   Equal{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
+  In{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
+  // NB: the connectives are goals: `and` sequences its goals, w/
+  // short-circuit; `or` is a choice point between its goals; and `not`
+  // is negation-as-failure (see `_run_scoped_goal`).
+  And{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
+  Or{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
+  Not{span: SpanNum, term: TermCodeNum},
+  // NB: an await suspends the current continuation on a pending eval of
+  // the value of the term (see `FastInterp::provide_eval_result`), i.e.
+  // as the `eval` builtin does for a non-code value.
+  Await{span: SpanNum, term: TermCodeNum},
   NEqual{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
   QEqual{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
   BindL{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
@@ -1124,18 +1233,85 @@ pub enum TermCode_ {
   Subst{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
   RebindL{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
   RebindR{span: SpanNum, lterm: TermCodeNum, rterm: TermCodeNum},
-  Apply{span: SpanNum, tup: TermCodeCellNum},
+  // NB: `splat` is set iff some arg of the tup is a `Splat`.
+  Apply{span: SpanNum, tup: TermCodeCellNum, splat: bool},
+  Kwarg{span: SpanNum, id: IdentNum, term: TermCodeNum},
+  Splat{span: SpanNum, term: TermCodeNum},
+  Lambda{span: SpanNum, params: Vec<Option<IdentNum>>, body: TermCodeNum},
+  // NB: as for `StmCode_::Quote`, but the reified quote is the result of
+  // the term.
+  Quote{span: SpanNum, body_stmp: StmCodeCellNum},
+  ListComp{span: SpanNum, elt: TermCodeNum, target: Option<IdentNum>, choice: bool, iter: TermCodeNum, cond: Option<TermCodeNum>},
   ApplyBindL{span: SpanNum, lterm: TermCodeNum, tup: TermCodeCellNum},
   ApplyBindR{span: SpanNum, tup: TermCodeCellNum, rterm: TermCodeNum},
   ApplyQuery{span: SpanNum, tup: TermCodeCellNum},
   Effect{span: SpanNum, lterm: TermCodeNum, rtup: TermCodeCellNum},
 }
 
+impl StmCode_ {
+  pub fn _span(&self) -> SpanNum {
+    match self {
+      &StmCode_::Just{span, ..} |
+      &StmCode_::Pass{span} |
+      &StmCode_::Global{span, ..} |
+      &StmCode_::Nonlocal{span, ..} |
+      &StmCode_::Fresh{span, ..} |
+      &StmCode_::Import{span, ..} |
+      &StmCode_::Assert{span, ..} |
+      &StmCode_::Expect{span, ..} |
+      &StmCode_::If{span, ..} |
+      &StmCode_::Match{span, ..} |
+      &StmCode_::Cases{span, ..} |
+      &StmCode_::With{span, ..} |
+      &StmCode_::Defproc{span, ..} |
+      &StmCode_::Defmatch{span, ..} |
+      &StmCode_::Defrule{span, ..} |
+      &StmCode_::Class{span, ..} |
+      &StmCode_::Module{span, ..} |
+      &StmCode_::Quote{span, ..} => span
+    }
+  }
+}
+
 impl TermCode_ {
+  // NB: every term code has a span, so that a raise in any term is
+  // located (see `FastInterp::_locate_exc`).
   pub fn _span(&self) -> Result<SpanNum, ()> {
     Ok(match self {
-      &TermCode_::Apply{span, ..} => span,
-      _ => return Err(())
+      &TermCode_::Ident{span, ..} |
+      &TermCode_::QualIdent{span, ..} |
+      &TermCode_::AtomLit{span, ..} |
+      &TermCode_::IntLit{span, ..} |
+      &TermCode_::BoolLit{span, ..} |
+      &TermCode_::NoneLit{span, ..} |
+      &TermCode_::ListCon{span, ..} |
+      &TermCode_::Neg{span, ..} |
+      &TermCode_::Group{span, ..} |
+      &TermCode_::Bunch{span, ..} |
+      &TermCode_::Query{span, ..} |
+      &TermCode_::Equal{span, ..} |
+      &TermCode_::In{span, ..} |
+      &TermCode_::And{span, ..} |
+      &TermCode_::Or{span, ..} |
+      &TermCode_::Not{span, ..} |
+      &TermCode_::Await{span, ..} |
+      &TermCode_::NEqual{span, ..} |
+      &TermCode_::QEqual{span, ..} |
+      &TermCode_::BindL{span, ..} |
+      &TermCode_::BindR{span, ..} |
+      &TermCode_::Subst{span, ..} |
+      &TermCode_::RebindL{span, ..} |
+      &TermCode_::RebindR{span, ..} |
+      &TermCode_::Apply{span, ..} |
+      &TermCode_::Kwarg{span, ..} |
+      &TermCode_::Splat{span, ..} |
+      &TermCode_::Lambda{span, ..} |
+      &TermCode_::Quote{span, ..} |
+      &TermCode_::ListComp{span, ..} |
+      &TermCode_::ApplyBindL{span, ..} |
+      &TermCode_::ApplyBindR{span, ..} |
+      &TermCode_::ApplyQuery{span, ..} |
+      &TermCode_::Effect{span, ..} => span
     })
   }
 }
@@ -1163,7 +1339,7 @@ impl DebruijnLevel {
 }
 
 // [Interp-API]
-#[derive(Serialize, Debug)]
+#[derive(Clone, Serialize, Debug)]
 #[serde(tag = "__type__")]
 pub struct Frame_ {
   level: DebruijnLevel,
@@ -1179,7 +1355,11 @@ pub type MemKntRef = Option<Box<MemKnt>>;
 // [Interp-API]
 //
 // In-memory continuation.
-#[derive(Clone, Debug)]
+//
+// NB: a continuation chain may be deep (e.g. under deep recursion), so
+// it is cloned and dropped iteratively, rather than recursively via the
+// `prev` links (see `Clone` and `Drop` below).
+#[derive(Debug)]
 pub struct MemKnt {
   clk:  LClk,
   prev: MemKntRef,
@@ -1192,6 +1372,47 @@ impl MemKnt {
   pub fn into_ref(self) -> MemKntRef {
     Some(self.into())
   }
+
+  // The number of continuations in the chain, incl. this one, counted
+  // up to `cap`.
+  pub fn _depth(&self, cap: usize) -> usize {
+    let mut depth = 1;
+    let mut kprev = self.prev.as_ref();
+    while let Some(knt) = kprev {
+      if depth >= cap {
+        break;
+      }
+      depth += 1;
+      kprev = knt.prev.as_ref();
+    }
+    depth
+  }
+}
+
+impl Clone for MemKnt {
+  fn clone(&self) -> MemKnt {
+    let mut spine = Vec::new();
+    let mut kprev = self.prev.as_ref();
+    while let Some(knt) = kprev {
+      spine.push((knt.clk, knt.cur.clone()));
+      kprev = knt.prev.as_ref();
+    }
+    let mut prev = None;
+    for (clk, cur) in spine.into_iter().rev() {
+      prev = MemKnt{clk, prev, cur}.into_ref();
+    }
+    MemKnt{clk: self.clk, prev, cur: self.cur.clone()}
+  }
+}
+
+impl Drop for MemKnt {
+  fn drop(&mut self) {
+    // NB: each unlinked continuation is dropped w/ a nil `prev`.
+    let mut kprev = self.prev.take();
+    while let Some(mut knt) = kprev {
+      kprev = knt.prev.take();
+    }
+  }
 }
 
 pub struct BorrowedMemKnt<'prev> {
@@ -1218,18 +1439,33 @@ pub enum MemKnt_ {
   InterpStmp(StmCodeCellNum, StmCodeCellInterpState_),
   InterpStm(StmCodeNum, StmCodeInterpState_),
   InterpIfStm(StmCodeNum, IfStmCodeInterpState_),
+  InterpMatchStm(StmCodeNum, MatchStmCodeInterpState_),
+  InterpClassStm(StmCodeNum, ClassStmCodeInterpState_),
+  InterpModuleStm(StmCodeNum, ModuleStmCodeInterpState_),
+  InterpProcBody(StmCodeNum, ProcBodyInterpState_),
+  InterpUnquote(StmCodeCellNum, UnquoteInterpState_),
+  InterpLambdaBody(TermCodeNum, LambdaBodyInterpState_),
+  InterpEvalCode(SNum, EvalCodeInterpState_),
   InterpTerm(TermCodeNum, TermCodeInterpState_),
   InterpQualIdentTerm(TermCodeNum, QualIdentTermCodeInterpState_),
   InterpBunchTerm(TermCodeNum, BunchTermCodeInterpState_),
   InterpEqualTerm(TermCodeNum, EqualTermCodeInterpState_),
+  InterpInTerm(TermCodeNum, InTermCodeInterpState_),
+  InterpAndTerm(TermCodeNum, AndTermCodeInterpState_),
+  InterpOrTerm(TermCodeNum, OrTermCodeInterpState_),
   InterpNEqualTerm(TermCodeNum, NEqualTermCodeInterpState_),
   InterpQEqualTerm(TermCodeNum, QEqualTermCodeInterpState_),
   InterpApplyTerm(TermCodeNum, ApplyTermCodeInterpState_),
+  InterpListCompTerm(TermCodeNum, ListCompTermCodeInterpState_),
   InterpApplyBindLTerm(TermCodeNum, ApplyBindLTermCodeInterpState_),
   InterpApplyBindRTerm(TermCodeNum, ApplyBindRTermCodeInterpState_),
   InterpBindLTerm(TermCodeNum, BindLTermCodeInterpState_),
   InterpBindRTerm(TermCodeNum, BindRTermCodeInterpState_),
+  InterpSubstTerm(TermCodeNum, SubstTermCodeInterpState_),
+  InterpRebindLTerm(TermCodeNum, RebindLTermCodeInterpState_),
+  InterpRebindRTerm(TermCodeNum, RebindRTermCodeInterpState_),
   InterpEffectTerm(TermCodeNum, EffectTermCodeInterpState_),
+  InterpAwaitTerm(TermCodeNum, AwaitTermCodeInterpState_),
 }
 
 // [Interp-API]
@@ -1332,6 +1568,71 @@ impl IfStmCodeInterpState_ {
   }
 }
 
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "MatchStmCodeInterpCursor_")]
+pub enum MatchStmCodeInterpCursor_ {
+  Scrut{scrut: TermCodeNum},
+  Case{case_idx: usize},
+  // NB: `pat` is none for a pattern that is a test (e.g. `x in xs`).
+  Choose{case_idx: usize, pat: Option<SNum>},
+  Body{body: StmCodeCellNum},
+  Fin,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct MatchStmCodeInterpState_ {
+  cur:  MatchStmCodeInterpCursor_,
+  cases: Vec<(TermCodeNum, StmCodeCellNum)>,
+  scrut: Option<SNum>,
+  save_tctx: Option<TermContext_>,
+}
+
+impl MatchStmCodeInterpState_ {
+  pub fn fresh(scrut: TermCodeNum, cases: Vec<(TermCodeNum, StmCodeCellNum)>) -> MatchStmCodeInterpState_ {
+    MatchStmCodeInterpState_{
+      cur:  MatchStmCodeInterpCursor_::Scrut{scrut},
+      cases,
+      scrut: None,
+      save_tctx: None,
+    }
+  }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct ClassStmCodeInterpState_ {
+  // NB: the class scope, or nil before the body is entered.
+  scope: SNum,
+  save_scope: SNum,
+}
+
+impl ClassStmCodeInterpState_ {
+  pub fn fresh() -> ClassStmCodeInterpState_ {
+    ClassStmCodeInterpState_{
+      scope: nil(),
+      save_scope: nil(),
+    }
+  }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct ModuleStmCodeInterpState_ {
+  // NB: the module scope, or nil before the body is entered.
+  scope: SNum,
+  save_scope: SNum,
+}
+
+impl ModuleStmCodeInterpState_ {
+  pub fn fresh() -> ModuleStmCodeInterpState_ {
+    ModuleStmCodeInterpState_{
+      scope: nil(),
+      save_scope: nil(),
+    }
+  }
+}
+
 #[derive(Clone, Serialize, Debug)]
 #[serde(tag = "__type__")]
 pub struct TermCodeInterpState_ {
@@ -1417,9 +1718,53 @@ macro_rules! impl_binop_term_code_interp_state {
 }
 
 impl_binop_term_code_interp_state!(Equal);
+impl_binop_term_code_interp_state!(In);
 impl_binop_term_code_interp_state!(NEqual);
 impl_binop_term_code_interp_state!(QEqual);
 
+#[derive(Clone, Copy, Serialize, Debug)]
+#[serde(tag = "AndTermCodeInterpCursor_")]
+pub enum AndTermCodeInterpCursor_ {
+  LTerm{lterm: TermCodeNum, rterm: TermCodeNum},
+  RTerm{rterm: TermCodeNum},
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct AndTermCodeInterpState_ {
+  cur:      AndTermCodeInterpCursor_,
+}
+
+impl AndTermCodeInterpState_ {
+  pub fn fresh(lterm: TermCodeNum, rterm: TermCodeNum) -> AndTermCodeInterpState_ {
+    AndTermCodeInterpState_{
+      cur:  AndTermCodeInterpCursor_::LTerm{lterm, rterm},
+    }
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Debug)]
+#[serde(tag = "OrTermCodeInterpCursor_")]
+pub enum OrTermCodeInterpCursor_ {
+  Choose{lterm: TermCodeNum, rterm: TermCodeNum},
+  LTerm,
+  RTerm,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct OrTermCodeInterpState_ {
+  cur:      OrTermCodeInterpCursor_,
+}
+
+impl OrTermCodeInterpState_ {
+  pub fn fresh(lterm: TermCodeNum, rterm: TermCodeNum) -> OrTermCodeInterpState_ {
+    OrTermCodeInterpState_{
+      cur:  OrTermCodeInterpCursor_::Choose{lterm, rterm},
+    }
+  }
+}
+
 #[derive(Clone, Copy, Serialize, Debug)]
 #[serde(tag = "BindLTermCodeInterpCursor_")]
 pub enum BindLTermCodeInterpCursor_ {
@@ -1472,11 +1817,13 @@ impl BindRTermCodeInterpState_ {
   }
 }
 
+// NB: the substitution (rterm) is interpreted first, in a fresh local
+// scope; the lterm is then interpreted in that same scope.
 #[derive(Clone, Copy, Serialize, Debug)]
 #[serde(tag = "SubstTermCodeInterpCursor_")]
 pub enum SubstTermCodeInterpCursor_ {
-  LTerm{lterm: TermCodeNum, rterm: TermCodeNum},
-  RTerm{rterm: TermCodeNum},
+  RTerm{lterm: TermCodeNum, rterm: TermCodeNum},
+  LTerm{lterm: TermCodeNum},
   Fin,
 }
 
@@ -1485,6 +1832,7 @@ pub enum SubstTermCodeInterpCursor_ {
 pub struct SubstTermCodeInterpState_ {
   lterm:    Option<(TermCodeNum, SNum)>,
   rterm:    Option<(TermCodeNum, SNum)>,
+  save_scope: SNum,
   cur:      SubstTermCodeInterpCursor_,
 }
 
@@ -1493,7 +1841,58 @@ impl SubstTermCodeInterpState_ {
     SubstTermCodeInterpState_{
       lterm: None,
       rterm: None,
-      cur:  SubstTermCodeInterpCursor_::LTerm{lterm, rterm},
+      save_scope: nil(),
+      cur:  SubstTermCodeInterpCursor_::RTerm{lterm, rterm},
+    }
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Debug)]
+#[serde(tag = "RebindLTermCodeInterpCursor_")]
+pub enum RebindLTermCodeInterpCursor_ {
+  RTerm{rterm: TermCodeNum},
+  Fin,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct RebindLTermCodeInterpState_ {
+  lbind:    IdentNum,
+  rterm:    Option<(TermCodeNum, SNum)>,
+  cur:      RebindLTermCodeInterpCursor_,
+}
+
+impl RebindLTermCodeInterpState_ {
+  pub fn fresh(lbind: IdentNum, rterm: TermCodeNum) -> RebindLTermCodeInterpState_ {
+    RebindLTermCodeInterpState_{
+      lbind,
+      rterm: None,
+      cur:  RebindLTermCodeInterpCursor_::RTerm{rterm},
+    }
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Debug)]
+#[serde(tag = "RebindRTermCodeInterpCursor_")]
+pub enum RebindRTermCodeInterpCursor_ {
+  LTerm{lterm: TermCodeNum},
+  Fin,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct RebindRTermCodeInterpState_ {
+  lterm:    Option<(TermCodeNum, SNum)>,
+  rbind:    IdentNum,
+  cur:      RebindRTermCodeInterpCursor_,
+}
+
+impl RebindRTermCodeInterpState_ {
+  pub fn fresh(lterm: TermCodeNum, rbind: IdentNum) -> RebindRTermCodeInterpState_ {
+    RebindRTermCodeInterpState_{
+      lterm: None,
+      rbind,
+      cur:  RebindRTermCodeInterpCursor_::LTerm{lterm},
     }
   }
 }
@@ -1503,6 +1902,8 @@ impl SubstTermCodeInterpState_ {
 pub struct ApplyTermCodeInterpState_ {
   tup:  Vec<(TermCodeNum, SNum)>,
   cur:  TermCodeCellNum,
+  // NB: set while the tup has unexpanded splat args (see `_expand_splats`).
+  splat: bool,
 }
 
 impl ApplyTermCodeInterpState_ {
@@ -1510,6 +1911,128 @@ impl ApplyTermCodeInterpState_ {
     ApplyTermCodeInterpState_{
       tup:  Vec::new(),
       cur:  init_cur,
+      splat: false,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub enum ListCompTermCodeInterpCursor_ {
+  Iter,
+  // NB: `Next` steps through the items in order; `Choose` picks one item
+  // at a choice point (see `TermCode_::ListComp`).
+  Next,
+  Choose,
+  Cond,
+  Elt,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct ListCompTermCodeInterpState_ {
+  cur:  ListCompTermCodeInterpCursor_,
+  items: Vec<SNum>,
+  idx:  usize,
+  buf:  Vec<SNum>,
+  // NB: the enclosing local scope; each item is bound in a fresh child
+  // scope of it.
+  save_scope: SNum,
+  save_tctx: Option<TermContext_>,
+}
+
+impl ListCompTermCodeInterpState_ {
+  pub fn fresh() -> ListCompTermCodeInterpState_ {
+    ListCompTermCodeInterpState_{
+      cur:  ListCompTermCodeInterpCursor_::Iter,
+      items: Vec::new(),
+      idx:  0,
+      buf:  Vec::new(),
+      save_scope: nil(),
+      save_tctx: None,
+    }
+  }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct ProcBodyInterpState_ {
+  // NB: the local scope of the proc definition.
+  scope: SNum,
+  // NB: the caller's local scope, restored upon return.
+  save_scope: SNum,
+  args: Vec<SNum>,
+  ret:  SNum,
+  // NB: set for the producer call of a tabled goal (see `tabling`).
+  table: Option<tabling::GoalKey_>,
+  // NB: the default stms still to run before the body, in reverse order
+  // (see `_bind_call_args`).
+  defaults: Vec<StmCodeNum>,
+  in_body: bool,
+}
+
+impl ProcBodyInterpState_ {
+  pub fn fresh(scope: SNum, args: Vec<SNum>, ret: SNum) -> ProcBodyInterpState_ {
+    ProcBodyInterpState_{
+      scope,
+      save_scope: nil(),
+      args,
+      ret,
+      table: None,
+      defaults: Vec::new(),
+      in_body: false,
+    }
+  }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct UnquoteInterpState_ {
+  // NB: unified w/ the result of the last stm of the unquoted body.
+  ret:  SNum,
+}
+
+impl UnquoteInterpState_ {
+  pub fn fresh(ret: SNum) -> UnquoteInterpState_ {
+    UnquoteInterpState_{
+      ret,
+    }
+  }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct LambdaBodyInterpState_ {
+  // NB: the captured scope of the lambda (see `LambdaTerm_`).
+  scope: SNum,
+  // NB: the caller's local scope, restored upon return.
+  save_scope: SNum,
+  args: Vec<SNum>,
+  ret:  SNum,
+}
+
+impl LambdaBodyInterpState_ {
+  pub fn fresh(scope: SNum, args: Vec<SNum>, ret: SNum) -> LambdaBodyInterpState_ {
+    LambdaBodyInterpState_{
+      scope,
+      save_scope: nil(),
+      args,
+      ret,
+    }
+  }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct EvalCodeInterpState_ {
+  // NB: unified w/ the result of the evaluated stm or term code.
+  ret:  SNum,
+}
+
+impl EvalCodeInterpState_ {
+  pub fn fresh(ret: SNum) -> EvalCodeInterpState_ {
+    EvalCodeInterpState_{
+      ret,
     }
   }
 }
@@ -1578,6 +2101,8 @@ pub enum EffectTermCodeInterpCursor_ {
 #[serde(tag = "__type__")]
 pub struct EffectTermCodeInterpState_ {
   lterm:    Option<(TermCodeNum, SNum)>,
+  // NB: the method ident is the head of the parsed rtup.
+  meth:     Option<IdentNum>,
   rtup:     Vec<(TermCodeNum, SNum)>,
   cur:      EffectTermCodeInterpCursor_,
 }
@@ -1586,12 +2111,36 @@ impl EffectTermCodeInterpState_ {
   pub fn fresh(lterm_cur: TermCodeNum, rtup_cur: TermCodeCellNum) -> EffectTermCodeInterpState_ {
     EffectTermCodeInterpState_{
       lterm: None,
+      meth: None,
       rtup: Vec::new(),
       cur:  EffectTermCodeInterpCursor_::LTerm{lterm: lterm_cur, rtup: rtup_cur},
     }
   }
 }
 
+// NB: the term is interpreted first; its value is then the term of a
+// pending eval (see `FastInterp::provide_eval_result`).
+#[derive(Clone, Copy, Serialize, Debug)]
+#[serde(tag = "AwaitTermCodeInterpCursor_")]
+pub enum AwaitTermCodeInterpCursor_ {
+  Term{term: TermCodeNum},
+  Eval,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(tag = "__type__")]
+pub struct AwaitTermCodeInterpState_ {
+  cur:      AwaitTermCodeInterpCursor_,
+}
+
+impl AwaitTermCodeInterpState_ {
+  pub fn fresh(term: TermCodeNum) -> AwaitTermCodeInterpState_ {
+    AwaitTermCodeInterpState_{
+      cur:  AwaitTermCodeInterpCursor_::Term{term},
+    }
+  }
+}
+
 // [Interp-API]
 #[derive(Clone, Serialize, Debug)]
 #[serde(tag = "__type__")]
@@ -1607,13 +2156,17 @@ pub struct Except_ {
   // FIXME
   //_reg: Option<SNum>,
   _err: Option<Error_>,
+  // NB: the span of the code that raised (and its source range), which
+  // is attached upon the raise (see `FastInterp::_locate_exc`).
+  pub span:   Option<SpanNum>,
+  pub range:  Option<RawSpan_>,
 }
 
 impl<'a> From<&'a str> for Except_ {
   #[track_caller]
   fn from(msg: &'a str) -> Except_ {
     let loc = loc();
-    Except_{_err: Some(Error_{loc, msg: msg.into()})}
+    Except_{_err: Some(Error_{loc, msg: msg.into()}), span: None, range: None}
   }
 }
 
@@ -1621,7 +2174,7 @@ impl From<String> for Except_ {
   #[track_caller]
   fn from(msg: String) -> Except_ {
     let loc = loc();
-    Except_{_err: Some(Error_{loc, msg: msg.into()})}
+    Except_{_err: Some(Error_{loc, msg: msg.into()}), span: None, range: None}
   }
 }
 
@@ -1629,6 +2182,40 @@ impl Except_ {
   pub fn is_some(&self) -> bool {
     self._err.is_some()
   }
+
+  pub fn msg(&self) -> Option<&str> {
+    self._err.as_ref().map(|e| e.msg.as_str())
+  }
+}
+
+// A failed `assert` or `expect` stm (see `FastInterp::test_failures`),
+// w/ its source location and rendered operands: for an `assert`, the
+// source text of the goal; for an `expect`, the values of its two sides.
+#[derive(Clone, Serialize, Debug)]
+pub struct TestFailure {
+  pub kind:   &'static str,
+  pub loc:    Option<String>,
+  pub range:  Option<RawSpan_>,
+  pub operands: Vec<String>,
+}
+
+impl TestFailure {
+  pub fn render(&self) -> String {
+    let mut out = match self.loc.as_ref() {
+      None => format!("{} failed", self.kind),
+      Some(loc) => format!("{}: {} failed", loc, self.kind)
+    };
+    match self.kind {
+      "expect" if self.operands.len() == 2 => {
+        out.push_str(&format!(": {} != {}", self.operands[0], self.operands[1]));
+      }
+      _ => for (i, x) in self.operands.iter().enumerate() {
+        out.push_str(if i == 0 { ": " } else { ", " });
+        out.push_str(x);
+      }
+    }
+    out
+  }
 }
 
 // [Interp-API]
@@ -1686,6 +2273,8 @@ impl Result_ {
 // [Interp-API]
 pub trait Tabled: Any + Debug {
   fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+  fn __clone__(&self) -> Box<dyn Tabled>;
   fn _tap_snapshot(&self) -> Option<String>;
 }
 
@@ -1698,6 +2287,10 @@ macro_rules! impl_tabled {
     impl Tabled for $T {
       fn as_any(&self) -> &dyn Any { self }
 
+      fn as_any_mut(&mut self) -> &mut dyn Any { self }
+
+      fn __clone__(&self) -> Box<dyn Tabled> { Box::new(self.clone()) }
+
       fn _tap_snapshot(&self) -> Option<String> {
         let json_format = JsonFormat::new()
             .ascii(true)
@@ -1738,6 +2331,10 @@ impl_tabled!(LitTerm_);
 
 impl_tabled!(NEqualTerm_);
 impl_tabled!(TupleTerm_);
+impl_tabled!(ProcTerm_);
+impl_tabled!(MatchTerm_);
+impl_tabled!(LambdaTerm_);
+impl_tabled!(QuoteTerm_);
 
 impl_tabled!(LitVal_);
 
@@ -1747,23 +2344,20 @@ impl_tabled!(Frame_);
 
 // [Interp-API]
 //
-// An eclass-enode/eid pair.
+// An eclass-enode/eid pair, as resolved by the unifier at some clock
+// (see `FastUnifier_::_find`).
+//
+// NB: the class of an ENum goes stale once its class is later unified,
+// so ENums are deliberately not comparable (nor hashable); re-resolve
+// them w/ `FastUnifier_::canon` (or `FastInterp::canon`) and compare the
+// classes (see `FastInterp::same_cls`). Likewise, an SNum only becomes
+// an ENum via the unifier (see `FastInterp::find`).
 #[derive(Clone, Copy, Default)]
 pub struct ENum {
   ecls: SNum,
   inst: SNum,
 }
 
-// FIXME: SNum into ENum should go through a unifier.
-impl From<SNum> for ENum {
-  fn from(x: SNum) -> ENum {
-    ENum{
-      ecls: x,
-      inst: x,
-    }
-  }
-}
-
 impl Serialize for ENum {
   fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
     let mut state = serializer.serialize_struct("ENum", 2)?;
@@ -1782,36 +2376,6 @@ impl Debug for ENum {
   }
 }
 
-impl PartialEq for ENum {
-  fn eq(&self, rhs: &ENum) -> bool {
-    // FIXME: ENum comparison should go through a unifier.
-    self.ecls == rhs.ecls
-  }
-}
-
-impl Eq for ENum {
-}
-
-impl PartialOrd for ENum {
-  fn partial_cmp(&self, rhs: &ENum) -> Option<Ordering> {
-    // FIXME: ENum comparison should go through a unifier.
-    self.ecls.partial_cmp(&rhs.ecls)
-  }
-}
-
-impl Ord for ENum {
-  fn cmp(&self, rhs: &ENum) -> Ordering {
-    // FIXME: ENum comparison should go through a unifier.
-    self.ecls.cmp(&rhs.ecls)
-  }
-}
-
-impl Hash for ENum {
-  fn hash<H: Hasher>(&self, state: &mut H) {
-    self.ecls.hash(state)
-  }
-}
-
 impl ENum {
   #[inline]
   pub fn _cls(&self) -> SNum {
@@ -1836,6 +2400,13 @@ impl From<()> for InterpCheck {
 #[derive(Debug)]
 pub enum UnifierCheck {
   _Bot,
+  TagMismatch(TagMismatch),
+}
+
+impl From<TagMismatch> for UnifierCheck {
+  fn from(e: TagMismatch) -> UnifierCheck {
+    UnifierCheck::TagMismatch(e)
+  }
 }
 
 impl From<UnifierCheck> for InterpCheck {
@@ -1850,19 +2421,165 @@ impl From<UnifierCheck> for InterpCheck {
 
 impl IntoInterpCheckExt for UnifierCheck {}
 
+impl From<SortMismatch> for InterpCheck {
+  #[track_caller]
+  fn from(e: SortMismatch) -> InterpCheck {
+    let loc = loc();
+    let msg = format!("{}: {:?}", type_name::<SortMismatch>(), e);
+    InterpCheck{_err: Error_{loc, msg: msg.into()}}
+  }
+}
+
+impl From<TagMismatch> for InterpCheck {
+  #[track_caller]
+  fn from(e: TagMismatch) -> InterpCheck {
+    let loc = loc();
+    let msg = format!("{}: {:?}", type_name::<TagMismatch>(), e);
+    InterpCheck{_err: Error_{loc, msg: msg.into()}}
+  }
+}
+
 // [Interp-API]
 #[derive(Default)]
 pub struct FastUnifier_ {
-  root: BTreeSet<SNum>,
-  next: FxHashMap<SNum, SNum>,
-  prev: FxHashMap<SNum, SNum>,
-  tree: FxHashMap<SNum, (LClk, SNum)>,
+  root: CowOrdSet<SNum>,
+  next: CowMap<SNum, SNum>,
+  prev: CowMap<SNum, SNum>,
+  tree: CowMap<SNum, (LClk, SNum)>,
   cache: RefCell<FxHashMap<SNum, (LClk, SNum)>>,
+  // NB: counts of the steps of `_find` that were resolved via the path
+  // cache (hits) or via the tree (misses).
+  cache_hit_ct: Cell<u64>,
+  cache_miss_ct: Cell<u64>,
+}
+
+// Counts for observability (see `FastInterp::unifier_stats`).
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct UnifierStats {
+  // The number of classes (i.e. roots), and of tree links.
+  pub classes:  usize,
+  pub links:    usize,
+  // The number of keys at each depth of the union tree, where the roots
+  // are at depth 0.
+  pub depth_hist: Vec<u64>,
+  pub cache_len:  usize,
+  pub cache_hit_ct:  u64,
+  pub cache_miss_ct: u64,
+}
+
+impl UnifierStats {
+  pub fn max_depth(&self) -> usize {
+    self.depth_hist.len().saturating_sub(1)
+  }
+
+  pub fn cache_hit_rate(&self) -> Option<f64> {
+    let total = self.cache_hit_ct + self.cache_miss_ct;
+    if total == 0 {
+      return None;
+    }
+    Some(self.cache_hit_ct as f64 / total as f64)
+  }
+}
+
+// Iterates over the members of a class, from its root, along the
+// circular next-list (see `FastUnifier_::iter_class`).
+pub struct UnifierClassIter<'a> {
+  unifier:  &'a FastUnifier_,
+  stop:     SNum,
+  cursor:   Option<SNum>,
+}
+
+impl<'a> Iterator for UnifierClassIter<'a> {
+  type Item = SNum;
+
+  fn next(&mut self) -> Option<SNum> {
+    let cursor = self.cursor?;
+    // NB: a broken next-list (see `_findall_into`) ends the iteration.
+    self.cursor = match self.unifier.next.get(&cursor) {
+      Some(&next) if next != self.stop => Some(next),
+      _ => None
+    };
+    Some(cursor)
+  }
+}
+
+impl Clone for FastUnifier_ {
+  fn clone(&self) -> FastUnifier_ {
+    // NB: the path cache is not shared; the clone starts w/ a cold cache.
+    FastUnifier_{
+      root: self.root.clone(),
+      next: self.next.clone(),
+      prev: self.prev.clone(),
+      tree: self.tree.clone(),
+      cache: RefCell::new(FxHashMap::default()),
+      cache_hit_ct: Cell::new(0),
+      cache_miss_ct: Cell::new(0),
+    }
+  }
 }
 
 impl FastUnifier_ {
+  // [Interp-API-Pub]
+  pub fn iter_roots(&self) -> impl Iterator<Item=SNum> + '_ {
+    self.root.iter().copied()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Iterates over the members of the class of `root`, starting w/ `root`
+  // itself. If `root` is not a root (see `iter_roots`), iterates over
+  // the members of its class starting from `root`, all the same.
+  pub fn iter_class(&self, root: SNum) -> UnifierClassIter<'_> {
+    UnifierClassIter{unifier: self, stop: root, cursor: Some(root)}
+  }
+
+  // [Interp-API-Pub]
+  pub fn class_count(&self) -> usize {
+    self.root.len()
+  }
+
+  // [Interp-API-Pub]
+  pub fn stats(&self) -> UnifierStats {
+    let mut depth: FxHashMap<SNum, usize> = FxHashMap::default();
+    let mut path = Vec::new();
+    let mut depth_hist = vec![self.root.len() as u64];
+    for (&key, _) in self.tree.iter() {
+      // NB: walk up to the first key of known depth (or to a root), then
+      // assign depths back down the path.
+      let mut cursor = key;
+      let mut d = loop {
+        if let Some(&d) = depth.get(&cursor) {
+          break d;
+        }
+        match self.tree.get(&cursor) {
+          Some(&(_, up)) if up != cursor => {
+            path.push(cursor);
+            cursor = up;
+          }
+          _ => break 0
+        }
+      };
+      while let Some(x) = path.pop() {
+        d += 1;
+        depth.insert(x, d);
+        if depth_hist.len() <= d {
+          depth_hist.resize(d + 1, 0);
+        }
+        depth_hist[d] += 1;
+      }
+    }
+    UnifierStats{
+      classes:  self.root.len(),
+      links:    self.tree.len(),
+      depth_hist,
+      cache_len:  self.cache.borrow().len(),
+      cache_hit_ct:  self.cache_hit_ct.get(),
+      cache_miss_ct: self.cache_miss_ct.get(),
+    }
+  }
+
   // [Interp-API]
-  pub fn _next(&self, query: SNum) -> SNum {
+  pub(crate) fn _next(&self, query: SNum) -> SNum {
     match self.next.get(&query) {
       Some(&next) => {
         next
@@ -1874,7 +2591,7 @@ impl FastUnifier_ {
   }
 
   // [Interp-API]
-  pub fn _prev(&self, query: SNum) -> SNum {
+  pub(crate) fn _prev(&self, query: SNum) -> SNum {
     match self.prev.get(&query) {
       Some(&prev) => {
         prev
@@ -1886,20 +2603,59 @@ impl FastUnifier_ {
   }
 
   // [Interp-API]
-  pub fn _link(&mut self, lquery: SNum, rquery: SNum) {
+  pub(crate) fn _link(&mut self, lquery: SNum, rquery: SNum) {
     self.next.insert(lquery, rquery);
     self.prev.insert(rquery, lquery);
   }
 
   // [Interp-API]
-  pub fn _findall(&self, clkinval: &LClkInvalidSet, clk: LClk, query: SNum) -> Result<Vec<ENum>, UnifierCheck> {
+  //
+  // Replays an undone unification (see `_unify`).
+  pub(crate) fn _redo(&mut self, clk: LClk, state: &UnifyUndoState_) {
+    self._link(state.oroot, state.nroot);
+    self._link(state.nprev, state.onext);
+    self.root.remove(&state.oroot);
+    self.root.insert(state.nroot);
+    self.tree.insert(state.oroot, (clk, state.nroot));
+    let mut cache = self.cache.borrow_mut();
+    cache.insert(state.oroot, (clk, state.nroot));
+  }
+
+  // [Interp-API]
+  //
+  // Pushes the other members of the equivalence class of `query`
+  // (i.e. its circular next-list) onto `buf`.
+  pub(crate) fn _gc_eclass(&self, query: SNum, buf: &mut Vec<SNum>) {
+    let mut cursor = self._next(query);
+    while cursor != query {
+      buf.push(cursor);
+      let next = self._next(cursor);
+      if next == cursor {
+        break;
+      }
+      cursor = next;
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _gc_remove(&mut self, query: SNum) -> bool {
+    let mut hit = self.next.remove(&query).is_some();
+    hit |= self.prev.remove(&query).is_some();
+    hit |= self.tree.remove(&query).is_some();
+    hit |= self.root.remove(&query);
+    self.cache.borrow_mut().remove(&query);
+    hit
+  }
+
+  // [Interp-API]
+  pub(crate) fn _findall(&self, clkinval: &LClkInvalidSet, clk: LClk, query: SNum) -> Result<Vec<ENum>, UnifierCheck> {
     let mut buf = Vec::new();
     self._findall_into(clkinval, clk, query, &mut buf)?;
     Ok(buf)
   }
 
   // [Interp-API]
-  pub fn _findall_into(&self, clkinval: &LClkInvalidSet, clk: LClk, query: SNum, buf: &mut Vec<ENum>) -> Result<(), UnifierCheck> {
+  pub(crate) fn _findall_into(&self, clkinval: &LClkInvalidSet, clk: LClk, query: SNum, buf: &mut Vec<ENum>) -> Result<(), UnifierCheck> {
     let root = self._find(clkinval, clk, query)?;
     let stop = root.inst;
     let mut cursor = stop;
@@ -1924,7 +2680,14 @@ impl FastUnifier_ {
   }
 
   // [Interp-API]
-  pub fn _find(&self, clkinval: &LClkInvalidSet, clk: LClk, query: SNum) -> Result<ENum, UnifierCheck> {
+  //
+  // Re-resolves the class of `x` (via its instance) at `clk`.
+  pub(crate) fn canon(&self, clkinval: &LClkInvalidSet, clk: LClk, x: ENum) -> Result<ENum, UnifierCheck> {
+    self._find(clkinval, clk, x.inst)
+  }
+
+  // [Interp-API]
+  pub(crate) fn _find(&self, clkinval: &LClkInvalidSet, clk: LClk, query: SNum) -> Result<ENum, UnifierCheck> {
     if self.root.contains(&query) {
       return Ok(ENum{ecls: query, inst: query});
     }
@@ -1944,6 +2707,7 @@ impl FastUnifier_ {
             if cursor == up {
               return Err(UnifierCheck::_Bot);
             }
+            self.cache_hit_ct.set(self.cache_hit_ct.get() + 1);
             if prev_cursor != cursor {
               // FIXME: check that this max() is sensible; maybe it can be
               // weakened to just up_clk.
@@ -1962,6 +2726,7 @@ impl FastUnifier_ {
           if cursor == up {
             return Err(UnifierCheck::_Bot);
           }
+          self.cache_miss_ct.set(self.cache_miss_ct.get() + 1);
           prev_up_clk = up_clk;
           prev_cursor = cursor;
           cursor = up;
@@ -1974,21 +2739,25 @@ impl FastUnifier_ {
   }
 
   // [Interp-API]
-  pub fn _unify(&mut self, log: &mut FastLog_, clkinval: &LClkInvalidSet, clk: LClk, lquery: SNum, rquery: SNum, ) -> Result<SNum, UnifierCheck> {
-    if lquery == rquery {
-      let root = self._find(clkinval, clk, rquery)?;
-      return Ok(root.ecls);
-    }
-    if lquery > rquery {
-      return self._unify(log, clkinval, clk, rquery, lquery);
+  pub(crate) fn _unify(&mut self, log: &mut FastLog_, clkinval: &LClkInvalidSet, clk: LClk, lquery: SNum, rquery: SNum, ) -> Result<SNum, UnifierCheck> {
+    match lquery._check_cmp(&rquery)? {
+      Ordering::Equal => {
+        let root = self._find(clkinval, clk, rquery)?;
+        return Ok(root.ecls);
+      }
+      Ordering::Greater => {
+        return self._unify(log, clkinval, clk, rquery, lquery);
+      }
+      Ordering::Less => {}
     }
     let l_root = self._find(clkinval, clk, lquery)?;
     let r_root = self._find(clkinval, clk, rquery)?;
-    if l_root.ecls == r_root.ecls {
+    let lrcmp = l_root.ecls._check_cmp(&r_root.ecls)?;
+    if lrcmp == Ordering::Equal {
       return Ok(r_root.ecls);
     }
     // NB: invariant unification (lexicographic ordered roots).
-    let (oroot, nroot) = if l_root.ecls > r_root.ecls {
+    let (oroot, nroot) = if lrcmp == Ordering::Greater {
       (l_root.ecls, r_root.ecls)
     } else {
       (r_root.ecls, l_root.ecls)
@@ -2052,56 +2821,394 @@ pub struct LogEntry_ {
 }
 
 // [Interp-API]
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct FastLog_ {
   buf:  Vec<LogEntry_>,
+  // The running byte count of `buf` (see `mem::MemoryReport`).
+  bytes: usize,
 }
 
 impl FastLog_ {
   // [Interp-API]
-  pub fn _append(&mut self, clk: LClk, val: LogEntryRef_) {
+  pub(crate) fn _append(&mut self, clk: LClk, val: LogEntryRef_) {
     self.buf.push(LogEntry_{clk, val});
+    self.bytes += size_of::<LogEntry_>();
   }
-}
-
-// TODO: the choice point counter type is u16 for historical reasons,
-// but consider bumping it up.
-pub type RawChoiceRank = u16;
 
-#[derive(Debug)]
-pub struct TraceEntry_ {
-  xctr: RawChoiceRank,
-  xlim: RawChoiceRank,
+  // [Interp-API]
+  pub(crate) fn _pop(&mut self) -> Option<LogEntry_> {
+    let e = self.buf.pop()?;
+    self.bytes -= size_of::<LogEntry_>();
+    Some(e)
+  }
 
-  // The fresh linear timestamp at the current step, during which the choice
-  // function is invoked.
+  // [Interp-API]
   //
-  // Actually observe two timestamps:
-  // - "root" timestamp upon the initial _push
-  // - any later timestamp, which is updated upon _push
-  last_clk: Cell<LClk>,
-  root_clk: LClk,
-
-  // `xlb` saved at the very beginning of the same current step.
-  xlb:  SNum,
+  // Drops the entries before `lb` (or else every entry, see
+  // `FastInterp::gc`); returns the number of dropped entries.
+  pub(crate) fn _compact(&mut self, lb: Option<LClk>) -> usize {
+    let len = self.buf.len();
+    match lb {
+      None => self.buf.clear(),
+      Some(lb) => self.buf.retain(|e| e.clk >= lb)
+    }
+    self.bytes = self.buf.len() * size_of::<LogEntry_>();
+    len - self.buf.len()
+  }
 
-  reg:  FastReg_,
-  ctl_: FastCtlReg_,
-  knt_: MemKntRef,
+  // [Interp-API]
+  pub(crate) fn _bytes(&self) -> usize {
+    self.bytes
+  }
 }
 
 // [Interp-API]
 //
-// The choice trace (todo)
-#[derive(Default, Debug)]
-pub struct FastTrace_ {
-  buf:  Vec<TraceEntry_>,
-  clk_pos:  BTreeMap<LClk, u32>,
+// Counts of what was reclaimed by a gc pass (see `FastInterp::gc`).
+#[derive(Clone, Copy, Default, Debug)]
+pub struct GcReport_ {
+  pub table:    usize,
+  pub unifier:  usize,
+  pub log:      usize,
+  pub redo:     usize,
+  pub clkinval: usize,
+}
+
+fn _gc_table_refs(e: &TableEntry_, buf: &mut Vec<SNum>) {
+  let inner = e.inner.as_any();
+  if let Some(t) = inner.downcast_ref::<TupleTerm_>() {
+    for x in t.buf.iter() {
+      buf.push(x.ecls);
+      buf.push(x.inst);
+    }
+  } else if let Some(t) = inner.downcast_ref::<NEqualTerm_>() {
+    for x in t.buf.iter() {
+      buf.push(x.ecls);
+      buf.push(x.inst);
+    }
+  } else if let Some(t) = inner.downcast_ref::<LambdaTerm_>() {
+    buf.push(t.code.into());
+    buf.push(t.scope);
+  } else if let Some(t) = inner.downcast_ref::<QuoteTerm_>() {
+    buf.push(t.body_stmp.into());
+    for &x in t.buf.iter() {
+      buf.push(x.into());
+    }
+  } else if let Some(v) = inner.downcast_ref::<LitVal_>() {
+    match v {
+      &LitVal_::Box{buf: Some(x)} => buf.push(x),
+      &LitVal_::List{buf: ref xs} => buf.extend(xs.iter()),
+      _ => {}
+    }
+  } else if let Some(c) = inner.downcast_ref::<Cell_>() {
+    buf.push(c.dptr);
+    buf.push(c.next.get().into());
+    buf.push(c.prev.get().into());
+  }
 }
 
-impl FastTrace_ {
-  // [Interp-API]
-  pub fn _maybe_get(&self, clk: LClk) -> Option<&TraceEntry_> {
+fn _gc_undo_refs(e: &UndoLogEntry_, buf: &mut Vec<SNum>) {
+  match e {
+    &UndoLogEntry_::Unify(ref state) => {
+      buf.push(state.oroot);
+      buf.push(state.nroot);
+      buf.push(state.onext);
+      buf.push(state.nprev);
+      if let Some((_, y)) = state.otree {
+        buf.push(y);
+      }
+    }
+    &UndoLogEntry_::AllocCell(x) => buf.push(x.into()),
+    &UndoLogEntry_::LinkCells(lcel, olnext, rcel, orprev) => {
+      buf.push(lcel.into());
+      buf.push(olnext.into());
+      buf.push(rcel.into());
+      buf.push(orprev.into());
+    }
+    &UndoLogEntry_::BindGlobalIdent(_, x) |
+    &UndoLogEntry_::BindBuiltinIdent(_, x) |
+    &UndoLogEntry_::BindFunName(_, x) |
+    &UndoLogEntry_::BindObjClsName(_, x) |
+    &UndoLogEntry_::BindLocalIdent(_, _, x) |
+    &UndoLogEntry_::RebindIdent(_, _, x) |
+    &UndoLogEntry_::PutTerm(x) |
+    &UndoLogEntry_::PutVal(x) => buf.push(x),
+    &UndoLogEntry_::BindLitTerm(_, Some(x)) |
+    &UndoLogEntry_::BindLitVal(_, Some(x)) => buf.push(x),
+    &UndoLogEntry_::ConsTuple(_, Some(x)) => buf.push(x.into()),
+    &UndoLogEntry_::TrieInsert(_, _, y, prev_y) => {
+      buf.push(y);
+      buf.push(prev_y);
+    }
+    &UndoLogEntry_::AttrInsert(_, _, y) => buf.push(y),
+    &UndoLogEntry_::IndexTuple(_, first, x) => {
+      buf.push(first);
+      buf.push(x);
+    }
+    _ => {}
+  }
+}
+
+fn _gc_opt_refs(x: &Option<(TermCodeNum, SNum)>, buf: &mut Vec<SNum>) {
+  if let &Some((_, y)) = x {
+    buf.push(y);
+  }
+}
+
+fn _gc_tup_refs(tup: &[(TermCodeNum, SNum)], buf: &mut Vec<SNum>) {
+  buf.extend(tup.iter().map(|&(_, y)| y));
+}
+
+// NB: the runtime entries (terms, vals, and scopes) held by the state of
+// a continuation, which may well be older than the continuation itself.
+fn _gc_knt_refs(knt: &MemKnt_, buf: &mut Vec<SNum>) {
+  match knt {
+    &MemKnt_::InterpMatchStm(_, ref state) => {
+      if let Some(x) = state.scrut {
+        buf.push(x);
+      }
+      if let &MatchStmCodeInterpCursor_::Choose{pat: Some(x), ..} = &state.cur {
+        buf.push(x);
+      }
+    }
+    &MemKnt_::InterpClassStm(_, ref state) => {
+      buf.push(state.scope);
+      buf.push(state.save_scope);
+    }
+    &MemKnt_::InterpModuleStm(_, ref state) => {
+      buf.push(state.scope);
+      buf.push(state.save_scope);
+    }
+    &MemKnt_::InterpProcBody(_, ref state) => {
+      buf.push(state.scope);
+      buf.push(state.save_scope);
+      buf.extend(state.args.iter());
+      buf.push(state.ret);
+    }
+    &MemKnt_::InterpUnquote(_, ref state) => {
+      buf.push(state.ret);
+    }
+    &MemKnt_::InterpLambdaBody(_, ref state) => {
+      buf.push(state.scope);
+      buf.push(state.save_scope);
+      buf.extend(state.args.iter());
+      buf.push(state.ret);
+    }
+    &MemKnt_::InterpEvalCode(x, ref state) => {
+      buf.push(x);
+      buf.push(state.ret);
+    }
+    &MemKnt_::InterpQualIdentTerm(_, ref state) => {
+      _gc_opt_refs(&state.term, buf);
+      if let Some((_, y)) = state.ident {
+        buf.push(y);
+      }
+    }
+    &MemKnt_::InterpBunchTerm(_, ref state) => {
+      _gc_tup_refs(&state.tup, buf);
+    }
+    &MemKnt_::InterpEqualTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+      _gc_opt_refs(&state.rterm, buf);
+    }
+    &MemKnt_::InterpInTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+      _gc_opt_refs(&state.rterm, buf);
+    }
+    &MemKnt_::InterpNEqualTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+      _gc_opt_refs(&state.rterm, buf);
+    }
+    &MemKnt_::InterpQEqualTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+      _gc_opt_refs(&state.rterm, buf);
+    }
+    &MemKnt_::InterpApplyTerm(_, ref state) => {
+      _gc_tup_refs(&state.tup, buf);
+    }
+    &MemKnt_::InterpListCompTerm(_, ref state) => {
+      buf.extend(state.items.iter());
+      buf.extend(state.buf.iter());
+      buf.push(state.save_scope);
+    }
+    &MemKnt_::InterpApplyBindLTerm(_, ref state) => {
+      _gc_opt_refs(&state.bind, buf);
+      _gc_tup_refs(&state.tup, buf);
+    }
+    &MemKnt_::InterpApplyBindRTerm(_, ref state) => {
+      _gc_tup_refs(&state.tup, buf);
+      _gc_opt_refs(&state.bind, buf);
+    }
+    &MemKnt_::InterpBindLTerm(_, ref state) => {
+      _gc_opt_refs(&state.lbind, buf);
+      _gc_opt_refs(&state.rterm, buf);
+    }
+    &MemKnt_::InterpBindRTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+      _gc_opt_refs(&state.rbind, buf);
+    }
+    &MemKnt_::InterpSubstTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+      _gc_opt_refs(&state.rterm, buf);
+      buf.push(state.save_scope);
+    }
+    &MemKnt_::InterpRebindLTerm(_, ref state) => {
+      _gc_opt_refs(&state.rterm, buf);
+    }
+    &MemKnt_::InterpRebindRTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+    }
+    &MemKnt_::InterpEffectTerm(_, ref state) => {
+      _gc_opt_refs(&state.lterm, buf);
+      _gc_tup_refs(&state.rtup, buf);
+    }
+    _ => {}
+  }
+}
+
+fn _gc_knt_chain_refs(knt: &MemKntRef, buf: &mut Vec<SNum>) {
+  let mut knt = knt.as_ref();
+  while let Some(k) = knt {
+    _gc_knt_refs(&k.cur, buf);
+    knt = k.prev.as_ref();
+  }
+}
+
+// TODO: the choice point counter type is u16 for historical reasons,
+// but consider bumping it up.
+pub type RawChoiceRank = u16;
+
+#[derive(Clone, Debug)]
+pub struct TraceEntry_ {
+  xctr: RawChoiceRank,
+  xlim: RawChoiceRank,
+
+  // The fresh linear timestamp at the current step, during which the choice
+  // function is invoked.
+  //
+  // Actually observe two timestamps:
+  // - "root" timestamp upon the initial _push
+  // - any later timestamp, which is updated upon _push
+  last_clk: Cell<LClk>,
+  root_clk: LClk,
+
+  // `xlb` saved at the very beginning of the same current step.
+  xlb:  SNum,
+
+  reg:  FastReg_,
+  ctl_: FastCtlReg_,
+  knt_: MemKntRef,
+
+  // The choice counter increment upon backtracking (see `FastTrace_::split`).
+  xstep: RawChoiceRank,
+
+  // Set for a weighted choice point (see `prelude::WChoiceFun`).
+  worder: Option<WeightedOrder_>,
+}
+
+// The exploration order of a weighted choice point: the rank `r`
+// returns the branch `order[r]`. The order is fixed when the choice
+// point is first pushed, so that backtracking resumes it in place.
+#[derive(Clone, Debug)]
+pub struct WeightedOrder_ {
+  span:   SpanNum,
+  order:  Vec<RawChoiceRank>,
+}
+
+impl TraceEntry_ {
+  // [Interp-API]
+  //
+  // The choice value at the current rank, i.e. the value that the
+  // choice function returns (see `ChoiceHeuristic`).
+  pub(crate) fn _choice_val(&self, heuristic: ChoiceHeuristic) -> RawChoiceRank {
+    if let Some(w) = self.worder.as_ref() {
+      // NB: the weighted order takes precedence over the heuristic.
+      return w.order.get(self.xctr as usize).copied().unwrap_or(self.xctr);
+    }
+    match heuristic {
+      ChoiceHeuristic::Ascending => self.xctr,
+      // NB: an unbounded choice point is always explored in ascending
+      // order.
+      ChoiceHeuristic::Descending if self.xlim == RawChoiceRank::max_value() => self.xctr,
+      ChoiceHeuristic::Descending => self.xlim - 1 - self.xctr
+    }
+  }
+}
+
+// The order in which a bounded choice point explores its values. The
+// choice ranks (and so the trace and the redo log) are the same for
+// either order; only the value returned at each rank differs.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum ChoiceHeuristic {
+  #[default]
+  Ascending,
+  Descending,
+}
+
+// The strictness of the unification of user terms, i.e. unify goals,
+// binds, and match patterns (see `FastInterp::_unify_checked`, and
+// `FastInterp::set_unify_mode`). A rejected unification fails.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum UnifyMode {
+  // Cyclic (i.e. rational tree) terms are allowed.
+  #[default]
+  Rational,
+  // A unification that would make a class occur inside one of its own
+  // tuples (e.g. `x = f(x)`) is rejected.
+  OccursChecked,
+  // As `OccursChecked`; in addition, a unification of two unbound classes
+  // (see `FastInterp::_is_unbound`) is rejected, i.e. one of the sides
+  // must already be bound.
+  GroundOnly,
+}
+
+impl FromStr for UnifyMode {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<UnifyMode, ()> {
+    Ok(match s {
+      "rational" => UnifyMode::Rational,
+      "occurs_check" => UnifyMode::OccursChecked,
+      "ground" => UnifyMode::GroundOnly,
+      _ => return Err(())
+    })
+  }
+}
+
+// The order in which a weighted choice point explores its branches
+// (see `prelude::WChoiceFun`).
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum WeightOrder {
+  // By descending weight, w/ ties in ascending branch order.
+  #[default]
+  Greedy,
+  // By weighted sampling w/out replacement, w/ the interpreter RNG
+  // (see `FastInterp::set_seed`).
+  Sampled,
+}
+
+// [Interp-API]
+//
+// The choice trace (todo)
+#[derive(Clone, Default, Debug)]
+pub struct FastTrace_ {
+  buf:  Vec<TraceEntry_>,
+  clk_pos:  BTreeMap<LClk, u32>,
+  // The running byte count of `buf` and `clk_pos`, incl. the weighted
+  // orders (see `mem::MemoryReport`).
+  bytes: usize,
+
+  // If set, the top-level choice point only explores the ranks
+  // `offset, offset + stride, offset + 2 * stride, ...`
+  // (see `split::split_explore`).
+  split: Option<(RawChoiceRank, RawChoiceRank)>,
+  heuristic: ChoiceHeuristic,
+  weight_order: WeightOrder,
+}
+
+impl FastTrace_ {
+  // [Interp-API]
+  pub(crate) fn _maybe_get(&self, clk: LClk) -> Option<&TraceEntry_> {
     match self.clk_pos.get(&clk) {
       None => {
         None
@@ -2113,10 +3220,14 @@ impl FastTrace_ {
   }
 
   // [Interp-API]
-  pub fn _push(&mut self, clk: LClk, choice_ub: RawChoiceRank, xlb: SNum, reg: FastReg_, ctl_: FastCtlReg_, knt_: MemKntRef) -> Result<(), ()> {
+  pub(crate) fn _push(&mut self, clk: LClk, choice_ub: RawChoiceRank, xlb: SNum, reg: FastReg_, ctl_: FastCtlReg_, knt_: MemKntRef) -> Result<(), ()> {
     let pos: u32 = self.buf.len().try_into().unwrap();
+    let (xctr, xstep) = match self.split {
+      Some((offset, stride)) if self.buf.is_empty() => (offset, stride),
+      _ => (0, 1)
+    };
     self.buf.push(TraceEntry_{
-      xctr: 0,
+      xctr,
       xlim: choice_ub,
       last_clk: Cell::new(clk),
       root_clk: clk,
@@ -2124,6 +3235,8 @@ impl FastTrace_ {
       reg,
       ctl_,
       knt_,
+      xstep,
+      worder: None,
     });
     match self.clk_pos.insert(clk, pos) {
       Some(_) => {
@@ -2132,20 +3245,51 @@ impl FastTrace_ {
       }
       None => {}
     }
+    self.bytes += _trace_entry_bytes(&self.buf[pos as usize]);
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Sets the weighted order of the choice point pushed at `clk`.
+  pub(crate) fn _set_order(&mut self, clk: LClk, span: SpanNum, order: Vec<RawChoiceRank>) -> Result<(), ()> {
+    let pos = match self.clk_pos.get(&clk) {
+      None => return Err(()),
+      Some(&pos) => pos
+    };
+    let te = &mut self.buf[pos as usize];
+    self.bytes -= _trace_entry_bytes(te);
+    te.worder = Some(WeightedOrder_{span, order});
+    self.bytes += _trace_entry_bytes(te);
     Ok(())
   }
 
   // [Interp-API]
-  pub fn _pop(&mut self) -> Result<(), ()> {
+  pub(crate) fn _pop(&mut self) -> Result<(), ()> {
     unimplemented!();
   }
 
   // [Interp-API]
-  pub fn _pop_pos(&mut self, pos: u32) -> Result<(), ()> {
+  //
+  // Marks the choice point pushed at `clk` as exhausted, so that it is
+  // popped upon the next backtrack into it.
+  pub(crate) fn _close(&mut self, clk: LClk) -> Result<(), ()> {
+    let pos = match self.clk_pos.get(&clk) {
+      None => return Err(()),
+      Some(&pos) => pos
+    };
+    let te = &mut self.buf[pos as usize];
+    te.xlim = te.xctr;
+    Ok(())
+  }
+
+  // [Interp-API]
+  pub(crate) fn _pop_pos(&mut self, pos: u32) -> Result<TraceEntry_, ()> {
     if (pos + 1) as usize != self.buf.len() {
       return Err(());
     }
     let te = self.buf.pop().unwrap();
+    self.bytes -= _trace_entry_bytes(&te);
     match self.clk_pos.remove(&te.root_clk) {
       None => {
         return Err(());
@@ -2154,8 +3298,92 @@ impl FastTrace_ {
         return Err(());
       }
     }
+    Ok(te)
+  }
+
+  // [Interp-API]
+  //
+  // Pushes a choice point that was popped earlier, from its first rank
+  // (see `FastInterp::_redo_replay`).
+  pub(crate) fn _repush(&mut self, mut te: TraceEntry_) -> Result<(), ()> {
+    let pos: u32 = self.buf.len().try_into().unwrap();
+    let (xctr, xstep) = match self.split {
+      Some((offset, stride)) if self.buf.is_empty() => (offset, stride),
+      _ => (0, 1)
+    };
+    te.xctr = xctr;
+    te.xstep = xstep;
+    if self.clk_pos.insert(te.root_clk, pos).is_some() {
+      return Err(());
+    }
+    self.bytes += _trace_entry_bytes(&te);
+    self.buf.push(te);
+    Ok(())
+  }
+
+  // [Interp-API]
+  pub(crate) fn _bytes(&self) -> usize {
+    self.bytes
+  }
+}
+
+// The bytes of a trace entry and its clk position, incl. its weighted
+// order (if any).
+fn _trace_entry_bytes(te: &TraceEntry_) -> usize {
+  size_of::<TraceEntry_>() + size_of::<(LClk, u32)>() +
+  te.worder.as_ref().map_or(0, |w| w.order.len() * size_of::<RawChoiceRank>())
+}
+
+// [Interp-API]
+//
+// An eval that is pending on an external (e.g. oracle) result; see
+// `FastInterp::provide_eval_result`.
+#[derive(Clone, Debug)]
+pub struct PendingEval_ {
+  // The term to be evaluated.
+  term: SNum,
+  // The result of the suspended apply, to which the value is bound.
+  ret:  SNum,
+  // The continuation to resume w/ the result.
+  knt_: MemKntRef,
+}
+
+// [Interp-API]
+//
+// The queue of pending evals, keyed by the clock of the step that
+// yielded `Yield_::Eval`.
+#[derive(Clone, Default, Debug)]
+pub struct FastEvalQueue_ {
+  buf:  BTreeMap<LClk, PendingEval_>,
+}
+
+impl FastEvalQueue_ {
+  // [Interp-API]
+  pub(crate) fn _push(&mut self, clk: LClk, term: SNum, ret: SNum, knt_: MemKntRef) -> Result<(), ()> {
+    if self.buf.contains_key(&clk) {
+      return Err(());
+    }
+    self.buf.insert(clk, PendingEval_{term, ret, knt_});
     Ok(())
   }
+
+  // [Interp-API]
+  pub(crate) fn _remove(&mut self, clk: LClk) -> Option<PendingEval_> {
+    self.buf.remove(&clk)
+  }
+
+  // [Interp-API]
+  //
+  // Drop the pending evals at or after `clk` (see `_backtrack`).
+  pub(crate) fn _truncate(&mut self, clk: LClk) -> usize {
+    let rst = self.buf.split_off(&clk);
+    rst.len()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _is_empty(&self) -> bool {
+    self.buf.is_empty()
+  }
 }
 
 // [Interp-API]
@@ -2183,6 +3411,25 @@ impl<V: ?Sized + Debug> Debug for TransparentBox<V> {
   }
 }
 
+macro_rules! impl_transparent_box_clone {
+  ($T:tt) => {
+    impl Clone for TransparentBox<dyn $T> {
+      fn clone(&self) -> TransparentBox<dyn $T> {
+        match self {
+          TransparentBox::Ptr(ref inner) => TransparentBox::Ptr(inner.__clone__()),
+          // NB: a borrowed box is being applied, and is only put back
+          // after the apply returns; so its clone is also borrowed.
+          TransparentBox::Blk => TransparentBox::Blk,
+        }
+      }
+    }
+  };
+}
+
+impl_transparent_box_clone!(Function);
+impl_transparent_box_clone!(ObjCls);
+impl_transparent_box_clone!(ObjVal);
+
 impl<V: ?Sized> TransparentBox<V> {
   pub fn _borrow(&mut self) -> TransparentBox<V> {
     replace(self, TransparentBox::Blk)
@@ -2193,21 +3440,60 @@ impl<V: ?Sized> TransparentBox<V> {
   }
 }
 
+// [Interp-API]
+//
+// A Function borrowed out of the env (see `FastInterp::borrow_fun_guard`).
+// The Function is returned to the env when the guard is dropped, so that
+// an early return (e.g. via `?`) between the borrow and the unborrow does
+// not leave its box blocked.
+pub struct FunBorrow<'a> {
+  interp:   &'a mut FastInterp,
+  x:        FunNum,
+  val:      Option<Box<dyn Function>>,
+}
+
+impl<'a> FunBorrow<'a> {
+  // The interpreter and the borrowed Function, e.g. for `__apply__`.
+  pub fn split(&mut self) -> (&mut FastInterp, &mut dyn Function) {
+    (&mut *self.interp, &mut **self.val.as_mut().unwrap())
+  }
+
+  // Returns the Function to the env, w/ the check that drop ignores.
+  pub fn unborrow(mut self) -> Result<(), InterpCheck> {
+    let val = self.val.take().unwrap();
+    self.interp.unborrow_fun(self.x, val)
+  }
+}
+
+impl<'a> Drop for FunBorrow<'a> {
+  fn drop(&mut self) {
+    if let Some(val) = self.val.take() {
+      if let Err(check) = self.interp.unborrow_fun(self.x, val) {
+        _debugln!(self.interp, "DEBUG: FunBorrow::drop: x={:?} check={:?}", self.x, check);
+      }
+    }
+  }
+}
+
 // [Interp-API]
 pub trait Function: Any + Debug {
   fn as_any(&self) -> &dyn Any;
+  fn __clone__(&self) -> Box<dyn Function>;
   fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck>;
 }
 
 // [Interp-API]
 pub trait ObjCls: Any + Debug {
   fn as_any(&self) -> &dyn Any;
-  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck>;
+  fn __clone__(&self) -> Box<dyn ObjCls>;
+  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<ObjVal_, InterpCheck>;
 }
 
 // [Interp-API]
 pub trait ObjVal: Any + Debug {
   fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+  fn __clone__(&self) -> Box<dyn ObjVal>;
   fn __init__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck>;
   fn __destroy__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck>;
   fn __request__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck>;
@@ -2216,9 +3502,67 @@ pub trait ObjVal: Any + Debug {
 pub type ObjVal_ = Box<dyn ObjVal>;
 
 // [Interp-API]
+//
+// Ident bindings are resolved through a chain of namespaces:
+//
+//   local scopes (innermost first) → module → builtin
+//
+// The module and builtin namespaces are the env's `id_global_bind` and
+// `id_builtin_bind`, respectively. `Namespace` is a local scope; local
+// scopes are lexically linked to their enclosing scope (`sup`), which is
+// the scope in which the proc owning this scope was defined.
+//
+// An ident declared `global` in a local scope is resolved (and bound)
+// in the module namespace. An ident declared `nonlocal` is resolved
+// in an enclosing scope, either the nearest one that binds it, or the
+// one given by the static scope (`nonlocal [k] x`): a non-negative `k`
+// is a debruijn level (0 is the module scope), while a negative `k` is
+// relative to the declaring scope (-1 is the immediately enclosing one).
+#[derive(Clone, Debug)]
 pub struct Namespace {
   // TODO: something that can be addressed via qual ident.
   // in particular, one such thing is `__builtins__`.
+  level:    DebruijnLevel,
+  // NB: nil if the enclosing scope is the module scope.
+  sup:      SNum,
+  bind:     FxHashMap<IdentNum, SNum>,
+  global:   FxHashSet<IdentNum>,
+  nonlocal: FxHashMap<IdentNum, Option<i16>>,
+}
+
+impl Namespace {
+  pub fn fresh(level: DebruijnLevel, sup: SNum) -> Namespace {
+    Namespace{
+      level,
+      sup,
+      bind:     Default::default(),
+      global:   Default::default(),
+      nonlocal: Default::default(),
+    }
+  }
+
+  pub fn get(&self, id: IdentNum) -> Option<SNum> {
+    self.bind.get(&id).map(|&x| x)
+  }
+
+  pub fn is_global(&self, id: IdentNum) -> bool {
+    self.global.contains(&id)
+  }
+
+  pub fn is_nonlocal(&self, id: IdentNum) -> Option<Option<i16>> {
+    self.nonlocal.get(&id).map(|&s| s)
+  }
+}
+
+// [Interp-API]
+//
+// The namespace in which an ident is (to be) bound.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Debug)]
+pub enum NamespaceScope_ {
+  Builtin,
+  Module,
+  // NB: the key of the local scope in `ns_full`.
+  Local(SNum),
 }
 
 // [Interp-API]
@@ -2228,33 +3572,57 @@ pub struct TableEntry_ {
   inner:    Box<dyn Tabled>,
 }
 
+impl Clone for TableEntry_ {
+  fn clone(&self) -> TableEntry_ {
+    TableEntry_{
+      lastclk:  self.lastclk,
+      inner:    self.inner.__clone__(),
+    }
+  }
+}
+
 impl TableEntry_ {
   pub fn as_any(&self) -> &dyn Any {
     self.inner.as_any()
   }
 
+  pub fn as_any_mut(&mut self) -> &mut dyn Any {
+    self.inner.as_any_mut()
+  }
+
   pub fn _tap_snapshot(&self) -> Option<String> {
     self.inner._tap_snapshot()
   }
 }
 
 // [Interp-API]
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct FastEnv_ {
   // NB: below, `SNum` in "key"-like position should be interpreted
   // as the "original instance" of an `ENum`.
 
   // FIXME: may want to fold this in as a sort in the tableau below.
-  fun_name:     FxHashMap<IdentNum, SNum>,
-  fun_full:     FxHashMap<SNum, TransparentBox<dyn Function>>,
+  fun_name:     CowMap<IdentNum, SNum>,
+  // NB: the signatures of the builtin functions (see `registry`).
+  fun_sig:      CowMap<SNum, registry::BuiltinInfo>,
+  fun_full:     CowMap<SNum, TransparentBox<dyn Function>>,
 
   // FIXME: may want to fold this in as a sort in the tableau below.
-  obj_cls_name: FxHashMap<IdentNum, SNum>,
-  obj_cls_full: FxHashMap<SNum, TransparentBox<dyn ObjCls>>,
+  obj_cls_name: CowMap<IdentNum, SNum>,
+  obj_cls_full: CowMap<SNum, TransparentBox<dyn ObjCls>>,
+  obj_val_full: CowMap<SNum, TransparentBox<dyn ObjVal>>,
 
   // TODO: tabled term storage should likely store tuples of _ENum_
   // instead of _SNum_.
-  table_full:   Vec<BTreeMap<SNum, TableEntry_>>,
+  table_full:   Vec<CowOrdMap<SNum, TableEntry_>>,
+  // NB: secondary index of the tabled tuples (see `TupleTerm_`), keyed
+  // by the sort rank and the _instance_ of the tuple's first child; see
+  // `FastInterp::match_tuples` for the lookup by class.
+  tuple_index:  CowMap<(RawSNum, SNum), CowOrdSet<SNum>>,
+  // NB: hash-consing index of the tuple terms, keyed by the classes of
+  // the children upon creation (see `FastInterp::_cons_tuple`). Since the
+  // classes may later be merged, a hit is validated before reuse.
+  tuple_cons:   CowMap<Box<[SNum]>, TermNum>,
   // TODO: seminaive tables.
   //table_prev:   Vec<FxHashMap<SNum, Box<dyn Tabled>>>,
   //table_new:    Vec<FxHashMap<SNum, Box<dyn Tabled>>>,
@@ -2262,43 +3630,107 @@ pub struct FastEnv_ {
   // TODO
   //e_table_full: Vec<FxHashMap<SNum, Box<dyn Tabled>>>,
 
-  frame_super:  FxHashMap<FrameNum, FrameNum>,
+  frame_super:  CowMap<FrameNum, FrameNum>,
   // NB: i.e. "code[-to-frame] index".
-  frame_codex:  FxHashMap<StmCodeNum, FrameNum>,
+  frame_codex:  CowMap<StmCodeNum, FrameNum>,
 
-  raw_span_index: FxHashMap<RawSpan_, SpanNum>,
-  raw_id_index: FxHashMap<RawIdent_, IdentNum>,
+  raw_span_index: CowMap<RawSpan_, SpanNum>,
+  // NB: the idents and lits are keyed by their syms, which are interned
+  // by the parser (see `FastInterp::interner`).
+  syms:         SharedInterner,
+  raw_id_index: CowMap<Sym, IdentNum>,
   // TODO: deprecate id_bind for id_global_bind.
   //id_bind:      FxHashMap<IdentNum, SNum>,
-  id_global_bind: FxHashMap<IdentNum, SNum>,
-  id_builtin_bind: FxHashMap<IdentNum, SNum>,
+  id_global_bind: CowMap<IdentNum, SNum>,
+  id_builtin_bind: CowMap<IdentNum, SNum>,
+  // NB: local scopes; see `Namespace` for resolution order.
+  ns_full:      CowMap<SNum, Namespace>,
+  // NB: the current local scope, or nil at module scope.
+  ns_cur:       SNum,
 
   // TODO: a "qual id" is a pair of a term-like Num and an ident.
-  qual_id_index: FxHashMap<(SNum, RawIdent_), IdentNum>,
+  qual_id_index: CowMap<(SNum, RawIdent_), IdentNum>,
 
   // TODO: literal syntax allows multiple different literal strings
   // to map to one literal term.
-  raw_lit_index: FxHashMap<RawLit_, LitStrNum>,
-  raw_lit_cache: FxHashMap<RawLit_, LitTerm_>,
-  lit_term_bind: FxHashMap<LitTerm_, SNum>,
+  raw_lit_index: CowMap<Sym, LitStrNum>,
+  raw_lit_cache: CowMap<RawLit_, LitTerm_>,
+  lit_term_bind: CowMap<LitTerm_, SNum>,
   // NB: the following is basically a hash-cons-like cache for some
   // sorts of literal values.
-  lit_val_bind:  FxHashMap<LitVal_, SNum>,
+  lit_val_bind:  CowMap<LitVal_, SNum>,
 
   unifier:      FastUnifier_,
 
-  rule_index:   FxHashMap<StmCodeNum, ()>,
+  rule_index:   CowMap<StmCodeNum, ()>,
+  // NB: the defmatch clauses by name (see `clause::ClauseIndex_`).
+  match_index:  CowMap<IdentNum, clause::ClauseIndex_>,
 }
 
 impl FastEnv_ {
   // [Interp-API]
-  pub fn _pre_init(&mut self, ctr: &SNumCtr) {
+  //
+  // Reserves space for about `cap` terms in the per-term indices.
+  pub(crate) fn _reserve(&mut self, cap: usize) {
+    self.raw_span_index.reserve(cap);
+    self.raw_id_index.reserve(cap);
+    self.lit_term_bind.reserve(cap);
+    self.lit_val_bind.reserve(cap);
+    self.tuple_index.reserve(cap);
+    self.tuple_cons.reserve(cap);
+    self.ns_full.reserve(cap);
+  }
+
+  // [Interp-API]
+  pub(crate) fn _intern(&self, s: &str) -> Sym {
+    self.syms.borrow_mut().intern(s)
+  }
+
+  // [Interp-API]
+  //
+  // The loaded ident (if any) of the str `raw_id`; unlike
+  // `FastInterp::_load_raw_ident`, this does not intern `raw_id`.
+  pub(crate) fn _find_raw_ident(&self, raw_id: &str) -> Option<IdentNum> {
+    let sym = self.syms.borrow().get(raw_id)?;
+    self.raw_id_index.get(&sym).copied()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _pre_init(&mut self, ctr: &SNumCtr) {
     // TODO: tableau lookup order.
     while self.table_full.len() <= SNumSort::_max() as _ {
       self.table_full.push(Default::default());
     }
   }
 
+  // [Interp-API]
+  pub(crate) fn _index_tuple(&mut self, sort: SNumSort, first: SNum, x: SNum) {
+    let key = (sort as RawSNum, first);
+    match self.tuple_index.get_mut(&key) {
+      None => {
+        let mut set = CowOrdSet::default();
+        set.insert(x);
+        self.tuple_index.insert(key, set);
+      }
+      Some(set) => {
+        set.insert(x);
+      }
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _unindex_tuple(&mut self, sort: SNumSort, first: SNum, x: SNum) -> bool {
+    let key = (sort as RawSNum, first);
+    let (removed, empty) = match self.tuple_index.get_mut(&key) {
+      None => return false,
+      Some(set) => (set.remove(&x), set.is_empty())
+    };
+    if empty {
+      self.tuple_index.remove(&key);
+    }
+    removed
+  }
+
   /*// [Interp-API]: This is part of the interpreter private API.
   pub fn _get_term<K: Into<SNum>>(&self, key: K) -> Result<Option<&dyn Tabled>, InterpCheck> {
     let x = key.into();
@@ -2384,15 +3816,63 @@ pub enum UndoLogEntryInner_ {
   LoadRawStm(StmCodeNum),
   LoadRawTerm(TermCodeNum),
   LoadRawIdent(IdentNum),
-  LoadRawLit(LitNum),
+  LoadQualIdent(IdentNum, SNum),
   LoadRawLitStr(LitStrNum),
   //BindIdent(IdentNum, SNum),
   BindGlobalIdent(IdentNum, /*SNum,*/ SNum),
-  BindLitStr(LitStrNum, Option<SNum>),
+  BindBuiltinIdent(IdentNum, SNum),
+  // NB: (ident, prev fun or obj cls, or nil); a registered name may
+  // shadow an earlier one (see `_register_builtin_function_box`).
+  BindFunName(IdentNum, SNum),
+  BindObjClsName(IdentNum, SNum),
+  BindLocalIdent(SNum, IdentNum, SNum),
+  DeclGlobalIdent(SNum, IdentNum),
+  DeclNonlocalIdent(SNum, IdentNum),
+  AllocNamespace(SNum),
+  SwitchNamespace(SNum),
+  BindLitTerm(LitTerm_, Option<SNum>),
   BindLitVal(LitVal_, Option<SNum>),
+  // NB: (ident, local scope or nil for module, prev binding).
   RebindIdent(IdentNum, SNum, SNum),
   PutTerm(SNum),
   PutVal(SNum),
+  // NB: (sort, first child instance, tuple).
+  IndexTuple(SNumSort, SNum, SNum),
+  // NB: (trie obj, key, inserted val, prev val or nil).
+  TrieInsert(ObjValNum, SafeStr, SNum, SNum),
+  // NB: (class obj, attr key, inserted attr).
+  AttrInsert(ObjValNum, SafeStr, SNum),
+  // NB: (child classes, prev tuple or none).
+  ConsTuple(Box<[SNum]>, Option<TermNum>),
+  // NB: the load of the next streamed stm (see `FastInterp::feed`).
+  StreamLoad,
+  GoalTableInsert(tabling::GoalKey_),
+}
+
+// [Interp-API]
+//
+// A redo entry pairs an undone log entry with the state that was
+// discarded by the undo (see `FastInterp::undo` and `FastInterp::_redo`).
+#[derive(Debug)]
+pub struct RedoLogEntry_ {
+  clk:  LClk,
+  undo: UndoLogEntryRef,
+  data: RedoData_,
+}
+
+#[derive(Debug)]
+pub enum RedoData_ {
+  Nil,
+  // NB: the binding (or scope) replaced by the undo; nil if none.
+  Bind(SNum),
+  Nonlocal(Option<i16>),
+  Table(TableEntry_),
+  // NB: `clause` is the (ident, arity, key) of an indexed defmatch clause.
+  Stm{code: TableEntry_, frame: Option<(FrameNum, Option<FrameNum>, Option<TableEntry_>)>, rule: bool, clause: Option<(IdentNum, usize, Option<clause::ClauseKey_>)>},
+  Fun(TransparentBox<dyn Function>),
+  ObjCls(TransparentBox<dyn ObjCls>),
+  ObjVal(TransparentBox<dyn ObjVal>),
+  Namespace(Namespace),
 }
 
 #[derive(Default)]
@@ -2400,9 +3880,43 @@ pub struct PVCache {
   leaf:     MaybeLogEntryRef_,
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct SlowPVCache_ {
   tree: BTreeMap<LClk, Vec<TraceEntry_>>,
+  // The learned preferences of the weighted choice points, keyed by
+  // span: the number of solutions (i.e. `Yield_::Quiescent`) that took
+  // each branch (see `FastInterp::choice_prefs`).
+  prefs: FxHashMap<SpanNum, Vec<u32>>,
+}
+
+impl SlowPVCache_ {
+  // [Interp-API]
+  pub(crate) fn _prefs(&self, span: SpanNum, len: usize) -> Option<&[u32]> {
+    match self.prefs.get(&span) {
+      Some(prefs) if prefs.len() == len => Some(prefs),
+      _ => None
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _learn(&mut self, trace: &FastTrace_) {
+    for te in trace.buf.iter() {
+      let w = match te.worder.as_ref() {
+        None => continue,
+        Some(w) => w
+      };
+      let val = te._choice_val(trace.heuristic) as usize;
+      let prefs = self.prefs.entry(w.span).or_insert_with(|| vec![0; w.order.len()]);
+      if prefs.len() != w.order.len() {
+        // NB: the same span w/ a different arity is a different choice
+        // point (e.g. after a reload); start over.
+        *prefs = vec![0; w.order.len()];
+      }
+      if val < prefs.len() {
+        prefs[val] = prefs[val].saturating_add(1);
+      }
+    }
+  }
 }
 
 pub type RawPVNodeId = u32;
@@ -2470,7 +3984,8 @@ pub trait IntoInterpCheckExt: Into<InterpCheck> {
 impl<'a> IntoInterpCheckExt for &'a str {}
 impl IntoInterpCheckExt for String {}
 
-#[derive(Serialize, Debug)]
+#[derive(Clone, Serialize, Debug)]
+#[cfg_attr(feature = "flat_import", derive(Deserialize))]
 pub enum FlatTabled_ {
   _Top,
   // TODO: "sorted" flat cells.
@@ -2490,30 +4005,35 @@ pub enum FlatTabled_ {
 }
 
 #[derive(Serialize, Debug)]
+#[cfg_attr(feature = "flat_import", derive(Deserialize))]
 pub struct FlatSpan {
   pub prim_key: SNum,
   pub flat_val: RawSpan_,
 }
 
 #[derive(Serialize, Debug)]
+#[cfg_attr(feature = "flat_import", derive(Deserialize))]
 pub struct FlatCode {
   pub prim_key: SNum,
   pub flat_val: FlatTabled_,
 }
 
 #[derive(Serialize, Debug)]
+#[cfg_attr(feature = "flat_import", derive(Deserialize))]
 pub struct FlatIdent {
   pub prim_key: SNum,
   pub flat_val: RawIdent_,
 }
 
 #[derive(Serialize, Debug)]
+#[cfg_attr(feature = "flat_import", derive(Deserialize))]
 pub struct FlatTerm {
   pub prim_key: SNum,
   pub flat_val: FlatTabled_,
 }
 
 #[derive(Serialize, Debug, Default)]
+#[cfg_attr(feature = "flat_import", derive(Deserialize))]
 pub struct FlatEnv {
   span: Vec<FlatSpan>,
   code: Vec<FlatCode>,
@@ -2521,27 +4041,81 @@ pub struct FlatEnv {
   term: Vec<FlatTerm>,
 }
 
+// NB: w/ the `flat_import` feature, a `FlatInterp` may also be loaded
+// back from its vector (see `flat::FlatInterp::from_json`).
 #[derive(Serialize, Debug)]
+#[cfg_attr(feature = "flat_import", derive(Deserialize))]
 pub struct FlatInterp {
+  // NB: the number of times this vector has been attested (reviewed)
+  // as correct; a freshly serialized vector has attestation count 0.
+  attest_ct: u32,
   clk:  LClk,
   env:  FlatEnv,
 }
 
+// [Interp-API-Pub]
+#[derive(Debug)]
+pub enum TestvSync {
+  // No test vector was at the destination; we serialized ourselves.
+  Fresh,
+  // The test vector at the destination matched (modulo attestation).
+  Match{attest_ct: u32},
+  // The test vector at the destination did not match, and was replaced;
+  // an attested vector is first kept at `keep`.
+  Invalidated{attest_ct: u32, keep: Option<PathBuf>},
+}
+
 impl FlatInterp {
   pub fn vectorize(&self) -> String {
     serde_json::to_string_pretty(self).unwrap()
   }
 
-  pub fn sync_testv(&self, dst: &PathBuf) {
-    // TODO: logic:
-    // - if there is no test vector at the destination path, then serialize
-    //   ourselves w/ attestation count 0
-    // - if there is a pre-existing test vector at the destination, then
-    //   need to _invalidate_ it; but, might want to keep a copy of the
-    //   pre-existing vector if it had nonzero attestation
+  pub fn sync_testv(&self, dst: &PathBuf) -> Result<TestvSync, IoError> {
+    let prev = match fs::read_to_string(dst) {
+      Err(e) => {
+        if e.kind() != IoErrorKind::NotFound {
+          return Err(e);
+        }
+        if let Some(dir) = dst.parent() {
+          fs::create_dir_all(dir)?;
+        }
+        fs::write(dst, self.vectorize())?;
+        return Ok(TestvSync::Fresh);
+      }
+      Ok(prev) => prev
+    };
+    // NB: an unparseable vector is treated as a (unattested) mismatch.
+    let mut prev: serde_json::Value = serde_json::from_str(&prev).unwrap_or_default();
+    let mut this = serde_json::to_value(self).unwrap();
+    let attest_ct = prev.get("attest_ct").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    if let Some(prev) = prev.as_object_mut() {
+      prev.remove("attest_ct");
+    }
+    if let Some(this) = this.as_object_mut() {
+      this.remove("attest_ct");
+    }
+    if prev == this {
+      return Ok(TestvSync::Match{attest_ct});
+    }
+    let keep = if attest_ct > 0 {
+      let mut keep = dst.clone();
+      keep.set_extension("attested.json");
+      fs::copy(dst, &keep)?;
+      Some(keep)
+    } else {
+      None
+    };
+    fs::write(dst, self.vectorize())?;
+    Ok(TestvSync::Invalidated{attest_ct, keep})
   }
 }
 
+pub fn _flat_term_hash(flat: &str) -> ContentHash {
+  let mut h = Blake2s::new_hash();
+  h.hash_bytes(flat.as_bytes());
+  ContentHash::from(h.finalize())
+}
+
 pub fn json_format_to_string_pretty_indent<T: ?Sized + Serialize>(obj: &T) -> Option<String> {
   textwrap::indent(
       &serde_json::to_string_pretty(obj).unwrap(),
@@ -2556,6 +4130,86 @@ pub fn json_format_to_string_pretty_indent_4<T: ?Sized + Serialize>(obj: &T) ->
   ).get(4 .. ).map(|s| s.to_string())
 }
 
+// The per-run counters of `FastInterp::interp_` (see `RunRecord`).
+#[derive(Default, Debug)]
+pub struct InterpRunStats_ {
+  // The control transitions of `resume_` (see `set_step_limit`).
+  pub step_ct:  u64,
+  pub backtrack_ct: u64,
+  pub yields:   BTreeMap<String, u64>,
+  // The max continuation depth, as sampled (see `set_depth_limit`), and
+  // capped at `KNT_DEPTH_CAP` (or at the depth limit, plus one).
+  pub max_depth: usize,
+}
+
+// The step interval at which the continuation depth is sampled, and the
+// cap of an unlimited sample (see `FastInterp::set_depth_limit`).
+pub const KNT_DEPTH_INTERVAL: u64 = 1024;
+pub const KNT_DEPTH_CAP: usize = 1 << 16;
+
+// The step interval at which the time limit is checked (see
+// `FastInterp::set_time_limit`).
+pub const TIME_CHECK_INTERVAL: u64 = 64;
+
+// The interpreter-owned, seeded SplitMix64 generator, drawn from by the
+// `random_choice` and `random_int` builtins (see `FastInterp::set_seed`).
+//
+// NB: the draws are _not_ undone upon backtracking, so that a retried
+// branch sees fresh draws; instead, a run is reproduced from its seed,
+// or from its recorded draws (see `FastInterp::replay_draws`).
+#[derive(Clone, Default, Debug)]
+pub struct FastRng_ {
+  seed:     u64,
+  state:    u64,
+  // NB: the draws of the current call to `interp_` (see `RunRecord`).
+  draws:    Vec<u64>,
+  replay:   VecDeque<u64>,
+}
+
+impl FastRng_ {
+  pub fn new(seed: u64) -> FastRng_ {
+    FastRng_{
+      seed,
+      state:    seed,
+      draws:    Vec::new(),
+      replay:   VecDeque::new(),
+    }
+  }
+
+  pub fn _next_u64(&mut self) -> u64 {
+    let x = match self.replay.pop_front() {
+      Some(x) => x,
+      None => {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+      }
+    };
+    self.draws.push(x);
+    x
+  }
+
+  // A draw in `0 .. n`, for `n > 0`.
+  pub fn _below(&mut self, n: u64) -> u64 {
+    assert!(n > 0);
+    ((self._next_u64() as u128 * n as u128) >> 64) as u64
+  }
+
+  // A draw in `lo ..= hi`, or `None` if the range is empty.
+  pub fn _range_incl(&mut self, lo: i64, hi: i64) -> Option<i64> {
+    if lo > hi {
+      return None;
+    }
+    let n = (hi as i128 - lo as i128 + 1) as u128;
+    if n > u64::MAX as u128 {
+      return Some(self._next_u64() as i64);
+    }
+    Some(lo.wrapping_add(self._below(n as u64) as i64))
+  }
+}
+
 #[derive(Default)]
 pub struct FastInterp {
   clkctr:   LClkCtr,
@@ -2575,21 +4229,356 @@ pub struct FastInterp {
   clkinval: LClkInvalidSet,
 
   log:      FastLog_,
+  redo:     redo::FastRedoLog_,
   trace:    FastTrace_,
+  // The memory accounting state (see `mem`); the one memory limit is
+  // shared by the gc threshold and the soft limit.
+  mem_lim:  Option<(usize, mem::MemLimitAction)>,
+  mem_hwm:  usize,
+  mem_brk:  bool,
+  mem_ctr:  mem::MemCounters_,
+  mem_gc_floor: usize,
+
+  // The extra builtins, and the metadata of the registered builtins (see
+  // `registry`).
+  prelude_ext: registry::PreludeRegistry,
+  builtins: Vec<registry::BuiltinInfo>,
 
   // TODO: a pv can come from multiple sources:
   // - backtrack only (w/ heuristics, branch/bound, etc.)
   // - "oracles"
   pv_cache: SlowPVCache_,
 
+  // Goal tabling state (see `tabling`).
+
+  tables:   tabling::FastGoalTables_,
+
+  // Provenance state (see `explain`).
+
+  prov:     Option<explain::FastProvLog_>,
+
+  // MCTS state (see `mcts`).
+
+  mcts:     mcts::FastMctsTree_,
+
+  // Event hooks, if any (see `hooks`).
+
+  hooks:    Option<Box<dyn hooks::InterpHooks>>,
+
+  // NB: the scratch buffer of the apply args in `resume_`; see
+  // `_take_scratch`.
+
+  scratch:  Vec<ENum>,
+
+  // Random state (see `set_seed`).
+
+  rng:      FastRng_,
+
+  // Checkpoint state (see `commit_to` and `restore_from`).
+
+  src:      Option<String>,
+  // NB: set when the source was loaded from bytes (see `load_bytes`).
+  src_enc:  Option<SourceEncoding>,
+  // NB: maps the offsets of `src` to the loaded files (see `load_project`).
+  srcmap:   SourceMap,
+  // NB: the sources as they were loaded (see `InterpRecipe_`).
+  load:     LoadRecipe_,
+  brk_clk:  Option<LClk>,
+
+  // Breakpoint state (see `debug`).
+
+  brk:      debug::FastBreakpoints_,
+
+  // Streaming load state (see `feed`).
+
+  stream:   Option<stream::FastStream_>,
+
+  // NB: not undone upon backtracking (see `test_failures`).
+  test_fail: Vec<TestFailure>,
+
+  // Run limits, per call to `interp_` (see `FastInterpBuilder`).
+
+  step_lim: Option<u64>,
+  time_lim: Option<Timedelta>,
+  depth_lim: Option<usize>,
+
+  // Checks enabled per module (see `_apply_pragmas`).
+
+  strict:   bool,
+  unify_mode: UnifyMode,
+  match_commit: bool,
+
+  // Oracle state (see `oracle::OracleBridge`).
+
+  #[cfg(feature = "pyo3")]
+  oracle:   Option<OracleBridge>,
+  // The submitted oracle requests, keyed by the trace position (i.e. the
+  // trace length) at which they were submitted (see `_oracle_cancel_from`).
+  #[cfg(feature = "pyo3")]
+  oracle_pos: BTreeMap<u32, BTreeSet<OracleReqId>>,
+  eval:     FastEvalQueue_,
+
   // Debug I/O state.
 
   snapshot: RefCell<Option<Box<dyn Write>>>,
   tap:      TAPOutput,
   parser_v: i8,
+  // NB: the mode of the parsers that the interpreter runs (see
+  // `set_parser_mode`).
+  parser_mode: ParserMode,
+  // NB: the shared log sink, if any (see `set_log_writer`).
+  logw:     Option<LogWriter>,
+  journal:  Option<RunJournal>,
+
+  // Parse cache state (see `set_parse_cache`).
+
+  parse_cache: Option<ParseCache>,
+}
+
+// [Interp-API]
+//
+// How the source of an interpreter was loaded, so that a restored
+// interpreter loads it the same way (see `FastInterp::restore_from`).
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub enum LoadRecipe_ {
+  // NB: e.g. a streamed source (see `FastInterp::feed`); restored by
+  // `cold_start` of the recorded source.
+  #[default]
+  Nil,
+  // The sources of one module, each w/ its origin path (see `_cold_start`).
+  Module(Vec<(Option<PathBuf>, String)>),
+  // The named modules (see `load_many`).
+  Many(Vec<(String, String)>),
+}
+
+// [Interp-API]
+//
+// The configuration and the load of an interpreter, as saved in its
+// snapshots (see `FastInterp::commit_to`), from which `restore_from`
+// rebuilds the interpreter before replaying it. NB: the writers, the
+// parse cache, the time and memory limits, and the extra builtins of a
+// `FastInterpBuilder` are not saved.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct InterpRecipe_ {
+  seed:     u64,
+  unify_mode: UnifyMode,
+  strict:   bool,
+  match_commit: bool,
+  heuristic: ChoiceHeuristic,
+  weight_order: WeightOrder,
+  step_lim: Option<u64>,
+  depth_lim: Option<usize>,
+  tabled:   Vec<String>,
+  parser_mode: ParserMode,
+  load:     LoadRecipe_,
+}
+
+// A builder for a configured `FastInterp`; by default, the built
+// interpreter is also pre-initialized (see `FastInterp::pre_init`).
+// `FastInterp::default()` remains the unconfigured, un-pre-initialized
+// interpreter.
+pub struct FastInterpBuilder {
+  verbose:  i8,
+  parser_v: i8,
+  parser_mode: ParserMode,
+  step_lim: Option<u64>,
+  time_lim: Option<Timedelta>,
+  depth_lim: Option<usize>,
+  unify_mode: UnifyMode,
+  tap_writer: Option<Box<dyn Write>>,
+  log_writer: Option<LogWriter>,
+  heuristic: ChoiceHeuristic,
+  weight_order: WeightOrder,
+  table_cap: Option<usize>,
+  mem_lim:  Option<(usize, mem::MemLimitAction)>,
+  seed:     Option<u64>,
+  parse_cache: Option<PathBuf>,
+  prelude_ext: Option<registry::PreludeRegistry>,
+  pre_init: bool,
+}
+
+impl Default for FastInterpBuilder {
+  fn default() -> FastInterpBuilder {
+    FastInterpBuilder{
+      verbose:  0,
+      parser_v: 0,
+      parser_mode: ParserMode::default(),
+      step_lim: None,
+      time_lim: None,
+      depth_lim: None,
+      unify_mode: UnifyMode::default(),
+      tap_writer: None,
+      log_writer: None,
+      heuristic: ChoiceHeuristic::default(),
+      weight_order: WeightOrder::default(),
+      table_cap: None,
+      mem_lim:  None,
+      seed:     None,
+      parse_cache: None,
+      prelude_ext: None,
+      pre_init: true,
+    }
+  }
+}
+
+impl FastInterpBuilder {
+  pub fn new() -> FastInterpBuilder {
+    FastInterpBuilder::default()
+  }
+
+  pub fn verbose(mut self, v: i8) -> FastInterpBuilder {
+    self.verbose = v;
+    self
+  }
+
+  pub fn parser_verbose(mut self, v: i8) -> FastInterpBuilder {
+    self.parser_v = v;
+    self
+  }
+
+  // The parser mode (see `FastInterp::set_parser_mode`).
+  pub fn parser_mode(mut self, mode: ParserMode) -> FastInterpBuilder {
+    self.parser_mode = mode;
+    self
+  }
+
+  pub fn step_limit(mut self, lim: u64) -> FastInterpBuilder {
+    self.step_lim = Some(lim);
+    self
+  }
+
+  pub fn time_limit(mut self, lim: Timedelta) -> FastInterpBuilder {
+    self.time_lim = Some(lim);
+    self
+  }
+
+  pub fn depth_limit(mut self, lim: usize) -> FastInterpBuilder {
+    self.depth_lim = Some(lim);
+    self
+  }
+
+  // The unify mode (see `FastInterp::set_unify_mode`); a module pragma
+  // may override it.
+  pub fn unify_mode(mut self, mode: UnifyMode) -> FastInterpBuilder {
+    self.unify_mode = mode;
+    self
+  }
+
+  // The sink of the TAP and debug/trace output (stdout by default).
+  pub fn trace_writer(mut self, writer: Box<dyn Write>) -> FastInterpBuilder {
+    self.tap_writer = Some(writer);
+    self
+  }
+
+  // The shared log sink of the interp and parser debug/trace output (see
+  // `FastInterp::set_log_writer`); overrides `trace_writer`.
+  pub fn log_writer(mut self, writer: LogWriter) -> FastInterpBuilder {
+    self.log_writer = Some(writer);
+    self
+  }
+
+  pub fn choice_heuristic(mut self, heuristic: ChoiceHeuristic) -> FastInterpBuilder {
+    self.heuristic = heuristic;
+    self
+  }
+
+  pub fn weight_order(mut self, order: WeightOrder) -> FastInterpBuilder {
+    self.weight_order = order;
+    self
+  }
+
+  // A hint of the number of terms, to presize the env indices.
+  pub fn table_capacity(mut self, cap: usize) -> FastInterpBuilder {
+    self.table_cap = Some(cap);
+    self
+  }
+
+  // The memory soft limit (see `FastInterp::set_mem_soft_limit`).
+  pub fn mem_soft_limit(mut self, lim: usize, action: mem::MemLimitAction) -> FastInterpBuilder {
+    self.mem_lim = Some((lim, action));
+    self
+  }
+
+  // The seed of the interpreter RNG (0 by default).
+  pub fn seed(mut self, seed: u64) -> FastInterpBuilder {
+    self.seed = Some(seed);
+    self
+  }
+
+  // The dir of the parse cache (see `FastInterp::set_parse_cache`); by
+  // default, there is no parse cache.
+  pub fn parse_cache<P: Into<PathBuf>>(mut self, dir: P) -> FastInterpBuilder {
+    self.parse_cache = Some(dir.into());
+    self
+  }
+
+  // The extra builtins (see `FastInterp::prelude_registry_mut`).
+  pub fn prelude(mut self, registry: registry::PreludeRegistry) -> FastInterpBuilder {
+    self.prelude_ext = Some(registry);
+    self
+  }
+
+  pub fn pre_init(mut self, pre_init: bool) -> FastInterpBuilder {
+    self.pre_init = pre_init;
+    self
+  }
+
+  pub fn build(self) -> Result<FastInterp, InterpCheck> {
+    let mut interp = FastInterp::default();
+    interp.set_verbose(self.verbose);
+    interp.set_parser_verbose(self.parser_v);
+    interp.set_parser_mode(self.parser_mode);
+    interp.set_step_limit(self.step_lim);
+    interp.set_time_limit(self.time_lim);
+    interp.set_depth_limit(self.depth_lim);
+    interp.set_unify_mode(self.unify_mode);
+    if let Some(writer) = self.tap_writer {
+      let _ = interp.set_tap_writer(writer);
+    }
+    if let Some(writer) = self.log_writer {
+      let _ = interp.set_log_writer(writer);
+    }
+    interp.set_choice_heuristic(self.heuristic);
+    interp.set_weight_order(self.weight_order);
+    if let Some(cap) = self.table_cap {
+      interp.env._reserve(cap);
+      interp.log.buf.reserve(cap);
+    }
+    if let Some((lim, action)) = self.mem_lim {
+      interp.set_mem_soft_limit(Some(lim), action);
+    }
+    if let Some(seed) = self.seed {
+      interp.set_seed(seed);
+    }
+    if let Some(dir) = self.parse_cache {
+      interp.set_parse_cache(Some(ParseCache::new(dir)));
+    }
+    if let Some(registry) = self.prelude_ext {
+      interp.set_prelude_registry(registry);
+    }
+    if self.pre_init {
+      interp.pre_init()?;
+    }
+    Ok(interp)
+  }
 }
 
 impl FastInterp {
+  // [Interp-API-Pub]
+  pub fn builder() -> FastInterpBuilder {
+    FastInterpBuilder::new()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The str interner of the idents and lits, which is shared w/ the
+  // parsers of this interpreter (see `Parser::with_interner`), and w/ its
+  // forks. An interned str is never uninterned, so that its sym is stable,
+  // even across a backtrack.
+  pub fn interner(&self) -> &SharedInterner {
+    &self.env.syms
+  }
+
   // [Interp-API-Pub]
   pub fn set_snapshot_writer(&mut self, writer: Box<dyn Write>) -> Option<Box<dyn Write>> {
     replace(&mut *self.snapshot.borrow_mut(), Some(writer))
@@ -2600,6 +4589,16 @@ impl FastInterp {
     replace(&mut *self.snapshot.borrow_mut(), None)
   }
 
+  // [Interp-API-Pub]
+  pub fn set_run_journal(&mut self, journal: RunJournal) -> Option<RunJournal> {
+    replace(&mut self.journal, Some(journal))
+  }
+
+  // [Interp-API-Pub]
+  pub fn unset_run_journal(&mut self) -> Option<RunJournal> {
+    replace(&mut self.journal, None)
+  }
+
   // [Interp-API-Pub]
   pub fn set_tap_writer(&mut self, writer: Box<dyn Write>) -> Box<dyn Write> {
     replace(&mut *self.tap.writer.borrow_mut(), writer)
@@ -2610,6 +4609,98 @@ impl FastInterp {
     replace(&mut *self.tap.writer.borrow_mut(), TAPOutput::stdout_writer())
   }
 
+  // [Interp-API-Pub]
+  #[cfg(feature = "pyo3")]
+  pub fn set_oracle_bridge(&mut self, bridge: OracleBridge) -> Option<OracleBridge> {
+    replace(&mut self.oracle, Some(bridge))
+  }
+
+  // [Interp-API-Pub]
+  #[cfg(feature = "pyo3")]
+  pub fn unset_oracle_bridge(&mut self) -> Option<OracleBridge> {
+    replace(&mut self.oracle, None)
+  }
+
+  // [Interp-API]
+  //
+  // Submits an oracle request w/out blocking. Returns `None` if the
+  // request queue is full, in which case the request may be retried
+  // after yielding.
+  //
+  // The request is registered against the current trace position, so
+  // that it is cancelled if its branch is abandoned (see `_restore_pos`).
+  #[cfg(feature = "pyo3")]
+  pub fn _oracle_submit(&mut self, req: ApproxOracleRequest) -> Result<Option<OracleReqId>, InterpCheck> {
+    let bridge = match self.oracle.as_mut() {
+      None => return Err("oracle: no bridge".into()),
+      Some(bridge) => bridge
+    };
+    match bridge.submit(req) {
+      Ok(id) => {
+        let pos: u32 = self.trace.buf.len().try_into().unwrap();
+        _debugln!(self, "DEBUG: FastInterp::_oracle_submit: id={} pos={}", id, pos);
+        self.oracle_pos.entry(pos).or_default().insert(id);
+        Ok(Some(id))
+      }
+      Err(OracleBridgeError::Full(_)) => Ok(None),
+      Err(OracleBridgeError::Disconnected) => Err("oracle: bridge disconnected".into()),
+      Err(e) => Err(format!("oracle: {:?}", e).into())
+    }
+  }
+
+  // [Interp-API]
+  //
+  // NB: a bridge error that is not tied to a request is returned here.
+  #[cfg(feature = "pyo3")]
+  pub fn _oracle_poll(&mut self) -> Result<usize, InterpCheck> {
+    let bridge = match self.oracle.as_mut() {
+      None => return Ok(0),
+      Some(bridge) => bridge
+    };
+    let n = bridge.poll();
+    match bridge.take_errors().into_iter().next() {
+      None => Ok(n),
+      Some(e) => Err(format!("oracle: {:?}", e).into())
+    }
+  }
+
+  // [Interp-API]
+  #[cfg(feature = "pyo3")]
+  pub fn _oracle_take(&mut self, id: OracleReqId) -> Result<Option<ApproxOracleItem>, InterpCheck> {
+    let item = match self.oracle.as_mut().and_then(|bridge| bridge.take(id)) {
+      None => return Ok(None),
+      Some(item) => item
+    };
+    self.oracle_pos.retain(|_, ids| { ids.remove(&id); !ids.is_empty() });
+    match item {
+      Err(e) => Err(format!("oracle: req id={}: {:?}", id, e).into()),
+      Ok(item) => Ok(Some(item))
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Cancels the oracle requests registered at or after the trace position
+  // `pos`, i.e. those submitted in the branches being abandoned; returns
+  // the number of requests that were still pending.
+  #[cfg(feature = "pyo3")]
+  pub(crate) fn _oracle_cancel_from(&mut self, pos: u32) -> usize {
+    let rst = self.oracle_pos.split_off(&pos);
+    let bridge = match self.oracle.as_mut() {
+      None => return 0,
+      Some(bridge) => bridge
+    };
+    let mut n = 0;
+    for (_, ids) in rst.into_iter() {
+      for id in ids.into_iter() {
+        if bridge.cancel(id) {
+          n += 1;
+        }
+      }
+    }
+    n
+  }
+
   // [Interp-API-Pub]
   pub fn set_verbose(&mut self, v: i8) {
     self.tap.verbose = v;
@@ -2627,116 +4718,408 @@ impl FastInterp {
 
   // [Interp-API-Pub]
   pub fn set_parser_debug(&mut self) {
-    self.parser_v = 3;
+    self.set_parser_verbose(3);
   }
 
-  // [Interp-API]
-  pub fn _fresh(&self) -> SNum {
-    self.ctr._fresh()
+  // [Interp-API-Pub]
+  pub fn set_parser_verbose(&mut self, v: i8) {
+    self.parser_v = v;
   }
 
-  // [Interp-API]
-  pub fn _peek(&self) -> SNum {
-    self.ctr._get()
+  // [Interp-API-Pub]
+  //
+  // Sets the mode of the parsers of the loaded sources and of the REPL
+  // lines (`ParserMode::Pythia` by default); e.g. keyword args and
+  // default params are only parsed in `ParserMode::PythonCompat`.
+  pub fn set_parser_mode(&mut self, mode: ParserMode) {
+    self.parser_mode = mode;
   }
 
-  // [Interp-API]
-  pub fn _load_raw_mod(&mut self, raw_mod: &RawMod_) -> Result<ModCodeNum, InterpCheck> {
-    let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_mod_code();
-    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawMod(x).into()));
-    let span = self._load_raw_span(&raw_mod.span)?;
-    let mut stmp: CellNum = nil();
-    let mut cur_stmp: CellNum = nil();
-    for raw_stm in raw_mod.body.iter() {
-      let stm = self._load_raw_stm(DebruijnLevel::root(), nil(), raw_stm)?;
-      let next_stmp = self._alloc_cell(stm.into());
-      self._link_cells(cur_stmp, next_stmp)?;
-      if stmp.is_nil() {
-        stmp = next_stmp;
-      }
-      cur_stmp = next_stmp;
+  // [Interp-API-Pub]
+  pub fn parser_mode(&self) -> ParserMode {
+    self.parser_mode
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets the log level of a subsystem (see `LogSubsys`).
+  pub fn set_log_level(&mut self, subsys: LogSubsys, v: i8) {
+    match subsys {
+      LogSubsys::Parse => self.set_parser_verbose(v),
+      LogSubsys::Interp => self.set_verbose(v),
     }
-    let code = ModCode_{span, stmp: stmp.into_stm_code()};
-    _traceln!(self, "DEBUG: FastInterp::_load_raw_mod: x={:?} code={:?}", x, code);
-    let e = TableEntry_{
-      lastclk: clk,
-      inner: Box::new(code),
-    };
-    self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
-    Ok(x)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Redirects the debug/trace output of the interpreter, and of the
+  // parsers that it runs, to the shared log sink `writer`; forks write to
+  // the same sink. Returns the previous TAP writer.
+  pub fn set_log_writer(&mut self, writer: LogWriter) -> Box<dyn Write> {
+    self.logw = Some(writer.clone());
+    self.tap.set_log_writer(writer)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets (or, w/ `None`, disables) the parse cache, which is checked for
+  // each source before it is tokenized (see `parse::cache::ParseCache`).
+  pub fn set_parse_cache(&mut self, cache: Option<ParseCache>) -> Option<ParseCache> {
+    replace(&mut self.parse_cache, cache)
   }
 
   // [Interp-API]
-  pub fn _load_raw_stm(&mut self, sup_level: DebruijnLevel, sup_frame: FrameNum, raw_stm: &RawStm_) -> Result<StmCodeNum, InterpCheck> {
-    let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_stm_code();
-    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawStm(x).into()));
-    match raw_stm {
-      &RawStm_::Just(ref raw_span, ref raw_term) => {
-        let span = self._load_raw_span(raw_span)?;
-        let term = self._load_raw_term(raw_term)?;
-        let code = StmCode_::Just{span, term};
-        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
-        let e = TableEntry_{
-          lastclk: clk,
-          inner: Box::new(code),
-        };
-        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
-        return Ok(x);
-      }
-      &RawStm_::Pass(ref raw_span) => {
-        let span = self._load_raw_span(raw_span)?;
-        let code = StmCode_::Pass{span};
-        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
-        let e = TableEntry_{
-          lastclk: clk,
-          inner: Box::new(code),
-        };
-        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
-        return Ok(x);
+  //
+  // Parses `text` at the span offset `base` (see `Parser::set_span_base`),
+  // via the parse cache if any.
+  pub(crate) fn _parse_text(&self, text: &str, base: usize) -> Result<RawMod_, ParseSpanError> {
+    // NB: the cache is keyed by the source alone, so only `Pythia` mode
+    // parses are cached.
+    let cache = match self.parse_cache.as_ref() {
+      Some(cache) if self.parser_mode == ParserMode::Pythia => cache,
+      _ => {
+        let mut parser = self._new_parser(text);
+        parser.set_span_base(base);
+        return parser.mod_();
       }
-      &RawStm_::Global(ref raw_span, ref raw_id) => {
-        let span = self._load_raw_span(raw_span)?;
-        let id = self._load_raw_ident(raw_id)?;
-        let code = StmCode_::Global{span, id};
-        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
-        let e = TableEntry_{
-          lastclk: clk,
-          inner: Box::new(code),
-        };
-        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
-        return Ok(x);
+    };
+    // NB: the cache holds the module parsed at offset 0.
+    let y = match cache.get(text) {
+      Some(y) => {
+        _debugln!(self, "DEBUG: FastInterp::_parse_text: cache hit: len={}", text.len());
+        y
       }
-      &RawStm_::Nonlocal(ref raw_span, static_scope, ref raw_id) => {
-        let span = self._load_raw_span(raw_span)?;
-        let id = self._load_raw_ident(raw_id)?;
-        let code = StmCode_::Nonlocal{span, static_scope, id};
-        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
-        let e = TableEntry_{
-          lastclk: clk,
-          inner: Box::new(code),
-        };
-        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
-        return Ok(x);
+      None => {
+        _debugln!(self, "DEBUG: FastInterp::_parse_text: cache miss: len={}", text.len());
+        let mut parser = self._new_parser(text);
+        let y = parser.mod_().map_err(|mut e| {
+          e.span = e.span.start + base .. e.span.end + base;
+          e
+        })?;
+        if let Err(e) = cache.put(text, &y) {
+          _debugln!(self, "DEBUG: FastInterp::_parse_text: cache put error: {:?}", e);
+        }
+        y
       }
-      &RawStm_::If(ref raw_span, ref raw_cases, ref raw_final_case) => {
-        let span = self._load_raw_span(raw_span)?;
-        let mut cases = Vec::new();
-        for &(ref raw_cond, ref raw_body) in raw_cases.iter() {
+    };
+    if base == 0 {
+      return Ok(y);
+    }
+    Ok(SpanRebase{base}.rewrite_mod(y))
+  }
+
+  // [Interp-API]
+  //
+  // A parser of `src`, w/ the parser log level and log sink.
+  pub(crate) fn _new_parser<'s>(&self, src: &'s str) -> FastParser<&'s str> {
+    let mut parser = FastParser::with_interner(src, self.env.syms.clone());
+    if self.parser_v > 0 {
+      parser.set_verbose(self.parser_v);
+    }
+    parser.set_mode(self.parser_mode);
+    if let Some(writer) = self.logw.as_ref() {
+      parser.set_log_writer(writer.clone());
+    }
+    parser
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Reseeds the interpreter RNG, and drops any pending replay draws.
+  // W/ the same seed (and the same source), a run draws the same values
+  // in the same order.
+  pub fn set_seed(&mut self, seed: u64) {
+    self.rng = FastRng_::new(seed);
+  }
+
+  // [Interp-API-Pub]
+  pub fn seed(&self) -> u64 {
+    self.rng.seed
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Queues the recorded draws of a run (see `RunRecord::draws`), which
+  // are then returned in order before the RNG resumes its own draws.
+  pub fn replay_draws(&mut self, draws: Vec<u64>) {
+    self.rng.replay = draws.into();
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets whether the sort of every key put in the term and val tables is
+  // checked, even where `SNUM_SORT_CHECK` is off.
+  pub fn set_strict(&mut self, strict: bool) {
+    self.strict = strict;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets whether a unification that would make a class occur inside one
+  // of its own tuples is rejected (i.e. fails); i.e. sets the unify mode
+  // to `OccursChecked` (or, if false, to `Rational`).
+  pub fn set_occurs_check(&mut self, occurs_ck: bool) {
+    self.unify_mode = if occurs_ck {
+      UnifyMode::OccursChecked
+    } else {
+      UnifyMode::Rational
+    };
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets the strictness of unification (see `UnifyMode`); a rejected
+  // unification fails, and so is backtracked from.
+  pub fn set_unify_mode(&mut self, mode: UnifyMode) {
+    self.unify_mode = mode;
+  }
+
+  // [Interp-API-Pub]
+  pub fn unify_mode(&self) -> UnifyMode {
+    self.unify_mode
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets whether a match stm commits to the first case whose pattern
+  // matches; by default, a failing body backtracks to the later cases.
+  pub fn set_match_commit(&mut self, match_commit: bool) {
+    self.match_commit = match_commit;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Limits the number of steps (i.e. control transitions of `resume_`)
+  // per call to `interp_`; upon reaching the limit, `interp_` yields
+  // `Yield_::Interrupt`, and may be called again to continue.
+  pub fn set_step_limit(&mut self, lim: Option<u64>) {
+    self.step_lim = lim;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Limits the wall-clock time per call to `interp_` (see
+  // `set_step_limit`). NB: the time is checked every `TIME_CHECK_INTERVAL`
+  // steps.
+  pub fn set_time_limit(&mut self, lim: Option<Timedelta>) {
+    self.time_lim = lim;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Limits the depth of the continuation chain (e.g. of nested proc
+  // calls); upon exceeding the limit, a (catchable) exception is raised.
+  // NB: the depth is sampled every `KNT_DEPTH_INTERVAL` steps (i.e. control
+  // transitions, see `_check_run_limits`); since a transition pushes at
+  // most one continuation, the limit may be overshot by as many.
+  pub fn set_depth_limit(&mut self, lim: Option<usize>) {
+    self.depth_lim = lim;
+  }
+
+  // [Interp-API-Pub]
+  pub fn set_choice_heuristic(&mut self, heuristic: ChoiceHeuristic) {
+    self.trace.heuristic = heuristic;
+  }
+
+  // [Interp-API-Pub]
+  pub fn set_weight_order(&mut self, order: WeightOrder) {
+    self.trace.weight_order = order;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The learned preferences of the weighted choice points (`wchoice`),
+  // keyed by span. A weighted choice point scales its weights by one
+  // plus the preference of each branch, so later runs (and forks) of the
+  // same source try the previously successful branches first.
+  pub fn choice_prefs(&self) -> &FxHashMap<SpanNum, Vec<u32>> {
+    &self.pv_cache.prefs
+  }
+
+  // [Interp-API-Pub]
+  pub fn set_choice_prefs(&mut self, prefs: FxHashMap<SpanNum, Vec<u32>>) -> FxHashMap<SpanNum, Vec<u32>> {
+    replace(&mut self.pv_cache.prefs, prefs)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets the gc threshold to a fraction of the physical memory size,
+  // i.e. the memory soft limit w/ `MemLimitAction::Gc` (see
+  // `set_mem_soft_limit`). If the physical memory size is unknown, the
+  // limit is unset (though `gc` may still be run manually).
+  pub fn set_gc_threshold(&mut self, frac: f64) {
+    let lim = SmpInfo::tl_clone().physical_memory_size()
+      .map(|sz| (sz as f64 * frac) as usize);
+    self.set_mem_soft_limit(lim, mem::MemLimitAction::Gc);
+  }
+
+  // [Interp-API]
+  pub(crate) fn _fresh(&self) -> Result<SNum, InterpCheck> {
+    self.ctr._fresh()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _peek(&self) -> SNum {
+    self.ctr._get()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _get_clock(&self) -> LClk {
+    self.clkctr._get_clock()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _load_raw_mod(&mut self, raw_mod: &RawMod_) -> Result<ModCodeNum, InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let x = self._fresh()?.into_mod_code();
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawMod(x).into()));
+    let span = self._load_raw_span(&raw_mod.span)?;
+    let mut stmp: CellNum = nil();
+    let mut cur_stmp: CellNum = nil();
+    for raw_stm in raw_mod.body.iter() {
+      let stm = self._load_raw_stm(DebruijnLevel::root(), nil(), raw_stm)?;
+      let next_stmp = self._alloc_cell(stm.into())?;
+      self._link_cells(cur_stmp, next_stmp)?;
+      if stmp.is_nil() {
+        stmp = next_stmp;
+      }
+      cur_stmp = next_stmp;
+    }
+    let code = ModCode_{span, stmp: stmp.try_into_stm_code().map_err(|e| e.into_check())?};
+    _traceln!(self, "DEBUG: FastInterp::_load_raw_mod: x={:?} code={:?}", x, code);
+    let e = TableEntry_{
+      lastclk: clk,
+      inner: Box::new(code),
+    };
+    self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+    Ok(x)
+  }
+
+  // [Interp-API]
+  pub(crate) fn _load_raw_stm(&mut self, sup_level: DebruijnLevel, sup_frame: FrameNum, raw_stm: &RawStm_) -> Result<StmCodeNum, InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let x = self._fresh()?.try_into_stm_code().map_err(|e| e.into_check())?;
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawStm(x).into()));
+    match raw_stm {
+      &RawStm_::Just(ref raw_span, ref raw_term) => {
+        let span = self._load_raw_span(raw_span)?;
+        let term = self._load_raw_term(raw_term)?;
+        let code = StmCode_::Just{span, term};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Pass(ref raw_span) => {
+        let span = self._load_raw_span(raw_span)?;
+        let code = StmCode_::Pass{span};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Global(ref raw_span, ref raw_id) => {
+        let span = self._load_raw_span(raw_span)?;
+        let id = self._load_raw_ident(raw_id)?;
+        let code = StmCode_::Global{span, id};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Nonlocal(ref raw_span, static_scope, ref raw_id) => {
+        let span = self._load_raw_span(raw_span)?;
+        let id = self._load_raw_ident(raw_id)?;
+        let code = StmCode_::Nonlocal{span, static_scope, id};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Assert(ref raw_span, ref raw_term) => {
+        let span = self._load_raw_span(raw_span)?;
+        let term = self._load_raw_term(raw_term)?;
+        let code = StmCode_::Assert{span, term};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Expect(ref raw_span, ref raw_lterm, ref raw_rterm) => {
+        let span = self._load_raw_span(raw_span)?;
+        let lterm = self._load_raw_term(raw_lterm)?;
+        let rterm = self._load_raw_term(raw_rterm)?;
+        let code = StmCode_::Expect{span, lterm, rterm};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Fresh(ref raw_span, ref raw_ids) => {
+        let span = self._load_raw_span(raw_span)?;
+        let mut ids = Vec::with_capacity(raw_ids.len());
+        for raw_id in raw_ids.iter() {
+          ids.push(self._load_raw_ident(raw_id)?);
+        }
+        let code = StmCode_::Fresh{span, ids};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Import(ref raw_span, ref raw_ids) => {
+        let span = self._load_raw_span(raw_span)?;
+        let mut ids = Vec::with_capacity(raw_ids.len());
+        for raw_id in raw_ids.iter() {
+          ids.push(self._load_raw_ident(raw_id)?);
+        }
+        let code = StmCode_::Import{span, ids};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::If(ref raw_span, ref raw_cases, ref raw_final_case) => {
+        let span = self._load_raw_span(raw_span)?;
+        let mut cases = Vec::new();
+        for &(ref raw_cond, ref raw_body) in raw_cases.iter() {
           let cond = self._load_raw_term(raw_cond)?;
           let mut body: CellNum = nil();
           let mut cur_body: CellNum = nil();
           for raw_body_stm in raw_body.iter() {
             let stm = self._load_raw_stm(sup_level, sup_frame, raw_body_stm)?;
-            let next_body = self._alloc_cell(stm.into());
+            let next_body = self._alloc_cell(stm.into())?;
             self._link_cells(cur_body, next_body)?;
             cur_body = next_body;
             if body.is_nil() {
               body = next_body;
             }
           }
-          let body_stmp = body.into_stm_code();
+          let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
           cases.push((cond, body_stmp));
         }
         let mut final_case = None;
@@ -2745,14 +5128,14 @@ impl FastInterp {
           let mut cur_body: CellNum = nil();
           for raw_body_stm in raw_body.iter() {
             let stm = self._load_raw_stm(sup_level, sup_frame, raw_body_stm)?;
-            let next_body = self._alloc_cell(stm.into());
+            let next_body = self._alloc_cell(stm.into())?;
             self._link_cells(cur_body, next_body)?;
             cur_body = next_body;
             if body.is_nil() {
               body = next_body;
             }
           }
-          let body_stmp = body.into_stm_code();
+          let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
           final_case = Some(body_stmp);
         }
         let final_case = final_case.try_into_nil()?;
@@ -2765,16 +5148,89 @@ impl FastInterp {
         self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
         return Ok(x);
       }
-      &RawStm_::Defproc(ref raw_span, prefix, .., ref raw_body) => {
+      &RawStm_::Match(ref raw_span, ref raw_scrut, ref raw_cases) => {
+        let span = self._load_raw_span(raw_span)?;
+        let scrut = self._load_raw_term(raw_scrut)?;
+        let mut cases = Vec::new();
+        for &(ref raw_pat, ref raw_body) in raw_cases.iter() {
+          let pat = self._load_raw_term(raw_pat)?;
+          let mut body: CellNum = nil();
+          let mut cur_body: CellNum = nil();
+          for raw_body_stm in raw_body.iter() {
+            let stm = self._load_raw_stm(sup_level, sup_frame, raw_body_stm)?;
+            let next_body = self._alloc_cell(stm.into())?;
+            self._link_cells(cur_body, next_body)?;
+            cur_body = next_body;
+            if body.is_nil() {
+              body = next_body;
+            }
+          }
+          let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+          cases.push((pat, body_stmp));
+        }
+        let code = StmCode_::Match{span, scrut, cases};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Cases(ref raw_span, ref raw_cases) => {
+        let span = self._load_raw_span(raw_span)?;
+        let mut cases = Vec::with_capacity(raw_cases.len());
+        for raw_body in raw_cases.iter() {
+          let mut body: CellNum = nil();
+          let mut cur_body: CellNum = nil();
+          for raw_body_stm in raw_body.iter() {
+            let stm = self._load_raw_stm(sup_level, sup_frame, raw_body_stm)?;
+            let next_body = self._alloc_cell(stm.into())?;
+            self._link_cells(cur_body, next_body)?;
+            cur_body = next_body;
+            if body.is_nil() {
+              body = next_body;
+            }
+          }
+          cases.push(body.try_into_stm_code().map_err(|e| e.into_check())?);
+        }
+        let code = StmCode_::Cases{span, cases};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawStm_::Defproc(ref raw_span, prefix, ref raw_id, ref raw_params, ref raw_rest, ref raw_defaults, ref raw_body) => {
         //_debugln!(self, "DEBUG: FastInterp::_load_raw_stm: raw span={:?} Defproc: prefix={:?}", raw_span, prefix);
+        let id = self._load_raw_ident(raw_id)?;
+        let mut params = Vec::with_capacity(raw_params.len());
+        for raw_param in raw_params.iter() {
+          let param = match raw_param.as_ref() {
+            None => None,
+            Some(raw_param) => Some(self._load_raw_ident(raw_param)?)
+          };
+          params.push(param);
+        }
+        let rest = match raw_rest.as_ref() {
+          None => None,
+          Some(raw_rest) => Some(self._load_raw_ident(raw_rest)?)
+        };
         let level = sup_level.push();
-        let frame = self._fresh().into_frame();
+        let frame = self._fresh()?.into_frame();
         if !sup_frame.is_nil() {
           // TODO: maybe also use cells for frame supers,
           // but w/ 1-way link.
           self.env.frame_super.insert(frame, sup_frame);
         }
-        let frame_ = Frame_{level, ids: Default::default()};
+        let mut frame_ = Frame_{level, ids: Default::default()};
+        for &param in params.iter().chain(Some(&rest)) {
+          if let Some(param) = param {
+            frame_.ids.insert(param);
+          }
+        }
         /*self.env.frame_full.insert(frame, frame_.into());*/
         let e = TableEntry_{
           lastclk: clk,
@@ -2782,20 +5238,30 @@ impl FastInterp {
         };
         self.env.table_full[SNumSort::Frame as usize].insert(frame.into(), e);
         let span = self._load_raw_span(raw_span)?;
+        let mut defaults = Vec::with_capacity(raw_defaults.len());
+        for &(ref raw_param, ref raw_default) in raw_defaults.iter() {
+          let param = self._load_raw_ident(raw_param)?;
+          let raw_span = raw_default.span();
+          let raw_lterm = RawTerm_::Ident(raw_span.clone(), raw_param.clone());
+          let raw_term = RawTerm_::Equal(raw_span.clone(), raw_lterm.into(), raw_default.clone());
+          let raw_stm = RawStm_::Just(raw_span, raw_term.into());
+          let stm = self._load_raw_stm(level, frame, &raw_stm)?;
+          defaults.push((param, stm));
+        }
         let mut body: CellNum = nil();
         let mut cur_body: CellNum = nil();
         for raw_body_stm in raw_body.iter() {
           // TODO: stms in this def body should update the frame.
           let stm = self._load_raw_stm(level, frame, raw_body_stm)?;
-          let next_body = self._alloc_cell(stm.into());
+          let next_body = self._alloc_cell(stm.into())?;
           self._link_cells(cur_body, next_body)?;
           cur_body = next_body;
           if body.is_nil() {
             body = next_body;
           }
         }
-        let body_stmp = body.into_stm_code();
-        let code = StmCode_::Defproc{span, body_stmp};
+        let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+        let code = StmCode_::Defproc{span, id, params, rest, defaults, body_stmp};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} frame={:?} code={:?}", x, frame, code);
         let e = TableEntry_{
           lastclk: clk,
@@ -2811,28 +5277,40 @@ impl FastInterp {
         }
         return Ok(x);
       }
-      &RawStm_::Defmatch(ref raw_span, prefix, .., ref raw_body) => {
+      &RawStm_::Defmatch(ref raw_span, prefix, ref raw_id, ref raw_params, ref raw_body) => {
         //_debugln!(self, "DEBUG: FastInterp::_load_raw_stm: raw span={:?} Defproc: prefix={:?}", raw_span, prefix);
+        let id = self._load_raw_ident(raw_id)?;
+        let mut params = Vec::with_capacity(raw_params.len());
+        for raw_param in raw_params.iter() {
+          let param = match raw_param.as_ref() {
+            None => None,
+            Some(raw_param) => Some(self._load_raw_ident(raw_param)?)
+          };
+          params.push(param);
+        }
+        let key = clause::_raw_clause_key(raw_params, raw_body);
         let span = self._load_raw_span(raw_span)?;
         let mut body: CellNum = nil();
         let mut cur_body: CellNum = nil();
         for raw_body_stm in raw_body.iter() {
           let stm = self._load_raw_stm(sup_level, sup_frame, raw_body_stm)?;
-          let next_body = self._alloc_cell(stm.into());
+          let next_body = self._alloc_cell(stm.into())?;
           self._link_cells(cur_body, next_body)?;
           cur_body = next_body;
           if body.is_nil() {
             body = next_body;
           }
         }
-        let body_stmp = body.into_stm_code();
-        let code = StmCode_::Defmatch{span, body_stmp};
+        let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+        let arity = params.len();
+        let code = StmCode_::Defmatch{span, id, params, body_stmp};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
         let e = TableEntry_{
           lastclk: clk,
           inner: Box::new(code),
         };
         self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        self._index_defmatch(id, arity, key, x.into());
         match prefix {
           None => {}
           Some(RawDefPrefix_::Rule) => {
@@ -2841,11 +5319,91 @@ impl FastInterp {
         }
         return Ok(x);
       }
-      // FIXME: this quote _statement_ was a parsing hack.
-      &RawStm_::Quote(ref raw_span, ..) => {
+      &RawStm_::Defrule(ref raw_span, ref raw_id, ref raw_head, ref raw_body) => {
+        let id = self._load_raw_ident(raw_id)?;
         let span = self._load_raw_span(raw_span)?;
-        let code = StmCode_::Quote{span};
-        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let mut head = Vec::with_capacity(raw_head.len());
+        for raw_arg in raw_head.iter() {
+          let arg = self._load_raw_term(raw_arg)?;
+          head.push(arg);
+        }
+        let mut body: CellNum = nil();
+        let mut cur_body: CellNum = nil();
+        for raw_body_stm in raw_body.iter() {
+          let stm = self._load_raw_stm(sup_level, sup_frame, raw_body_stm)?;
+          let next_body = self._alloc_cell(stm.into())?;
+          self._link_cells(cur_body, next_body)?;
+          cur_body = next_body;
+          if body.is_nil() {
+            body = next_body;
+          }
+        }
+        let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+        let code = StmCode_::Defrule{span, id, head, body_stmp};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        self.env.rule_index.insert(x.into(), ());
+        return Ok(x);
+      }
+      &RawStm_::Class(ref raw_span, ref raw_id, ref raw_body) => {
+        let id = self._load_raw_ident(raw_id)?;
+        // NB: the class scope is a local scope (see `InterpClassStm`),
+        // so the class body has its own frame, as for a defproc.
+        let level = sup_level.push();
+        let frame = self._fresh()?.into_frame();
+        if !sup_frame.is_nil() {
+          self.env.frame_super.insert(frame, sup_frame);
+        }
+        let frame_ = Frame_{level, ids: Default::default()};
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(frame_),
+        };
+        self.env.table_full[SNumSort::Frame as usize].insert(frame.into(), e);
+        let span = self._load_raw_span(raw_span)?;
+        let mut body: CellNum = nil();
+        let mut cur_body: CellNum = nil();
+        for raw_body_stm in raw_body.iter() {
+          let stm = self._load_raw_stm(level, frame, raw_body_stm)?;
+          let next_body = self._alloc_cell(stm.into())?;
+          self._link_cells(cur_body, next_body)?;
+          cur_body = next_body;
+          if body.is_nil() {
+            body = next_body;
+          }
+        }
+        let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+        let code = StmCode_::Class{span, id, body_stmp};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} frame={:?} code={:?}", x, frame, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        self.env.frame_codex.insert(x.into(), frame);
+        return Ok(x);
+      }
+      // FIXME: this quote _statement_ was a parsing hack.
+      &RawStm_::Quote(ref raw_span, _, ref raw_body) => {
+        let span = self._load_raw_span(raw_span)?;
+        let mut body: CellNum = nil();
+        let mut cur_body: CellNum = nil();
+        for raw_body_stm in raw_body.iter() {
+          let stm = self._load_raw_stm(sup_level, sup_frame, raw_body_stm)?;
+          let next_body = self._alloc_cell(stm.into())?;
+          self._link_cells(cur_body, next_body)?;
+          cur_body = next_body;
+          if body.is_nil() {
+            body = next_body;
+          }
+        }
+        let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+        let code = StmCode_::Quote{span, body_stmp};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_stm: x={:?} code={:?}", x, code);
         let e = TableEntry_{
           lastclk: clk,
           inner: Box::new(code),
@@ -2860,9 +5418,9 @@ impl FastInterp {
   }
 
   // [Interp-API]
-  pub fn _load_raw_term(&mut self, raw_term: &RawTerm_) -> Result<TermCodeNum, InterpCheck> {
+  pub(crate) fn _load_raw_term(&mut self, raw_term: &RawTerm_) -> Result<TermCodeNum, InterpCheck> {
     let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_term_code();
+    let x = self._fresh()?.try_into_term_code().map_err(|e| e.into_check())?;
     self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawTerm(x).into()));
     match raw_term {
       &RawTerm_::Ident(ref raw_span, ref raw_id) => {
@@ -2946,14 +5504,14 @@ impl FastInterp {
         let mut cur_tup: CellNum = nil();
         for raw_tup_term in raw_tup.iter() {
           let term = self._load_raw_term(raw_tup_term)?;
-          let next_tup = self._alloc_cell(term.into());
+          let next_tup = self._alloc_cell(term.into())?;
           self._link_cells(cur_tup, next_tup)?;
           cur_tup = next_tup;
           if tup.is_nil() {
             tup = next_tup;
           }
         }
-        let tup = tup.into_term_code();
+        let tup = tup.try_into_term_code().map_err(|e| e.into_check())?;
         let code = TermCode_::ListCon{span, tup};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
         let e = TableEntry_{
@@ -2963,20 +5521,32 @@ impl FastInterp {
         self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
         return Ok(x);
       }
+      &RawTerm_::Group(ref raw_span, ref raw_term) => {
+        let span = self._load_raw_span(raw_span)?;
+        let term = self._load_raw_term(raw_term)?;
+        let code = TermCode_::Group{span, term};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
       &RawTerm_::Bunch(ref raw_span, ref raw_tup) => {
         let span = self._load_raw_span(raw_span)?;
         let mut tup: CellNum = nil();
         let mut cur_tup: CellNum = nil();
         for raw_tup_term in raw_tup.iter() {
           let term = self._load_raw_term(raw_tup_term)?;
-          let next_tup = self._alloc_cell(term.into());
+          let next_tup = self._alloc_cell(term.into())?;
           self._link_cells(cur_tup, next_tup)?;
           cur_tup = next_tup;
           if tup.is_nil() {
             tup = next_tup;
           }
         }
-        let tup = tup.into_term_code();
+        let tup = tup.try_into_term_code().map_err(|e| e.into_check())?;
         let code = TermCode_::Bunch{span, tup};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
         let e = TableEntry_{
@@ -2999,6 +5569,69 @@ impl FastInterp {
         self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
         return Ok(x);
       }
+      &RawTerm_::In(ref raw_span, ref raw_lterm, ref raw_rterm) => {
+        let span = self._load_raw_span(raw_span)?;
+        let lterm = self._load_raw_term(raw_lterm)?;
+        let rterm = self._load_raw_term(raw_rterm)?;
+        let code = TermCode_::In{span, lterm, rterm};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::And(ref raw_span, ref raw_lterm, ref raw_rterm) => {
+        let span = self._load_raw_span(raw_span)?;
+        let lterm = self._load_raw_term(raw_lterm)?;
+        let rterm = self._load_raw_term(raw_rterm)?;
+        let code = TermCode_::And{span, lterm, rterm};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::Or(ref raw_span, ref raw_lterm, ref raw_rterm) => {
+        let span = self._load_raw_span(raw_span)?;
+        let lterm = self._load_raw_term(raw_lterm)?;
+        let rterm = self._load_raw_term(raw_rterm)?;
+        let code = TermCode_::Or{span, lterm, rterm};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::Not(ref raw_span, ref raw_term) => {
+        let span = self._load_raw_span(raw_span)?;
+        let term = self._load_raw_term(raw_term)?;
+        let code = TermCode_::Not{span, term};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::Await(ref raw_span, ref raw_term) => {
+        let span = self._load_raw_span(raw_span)?;
+        let term = self._load_raw_term(raw_term)?;
+        let code = TermCode_::Await{span, term};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
       &RawTerm_::NEqual(ref raw_span, ref raw_lterm, ref raw_rterm) => {
         let span = self._load_raw_span(raw_span)?;
         let lterm = self._load_raw_term(raw_lterm)?;
@@ -3038,21 +5671,156 @@ impl FastInterp {
         self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
         return Ok(x);
       }
+      &RawTerm_::Subst(ref raw_span, ref raw_lterm, ref raw_rterm) => {
+        let span = self._load_raw_span(raw_span)?;
+        let lterm = self._load_raw_term(raw_lterm)?;
+        let rterm = self._load_raw_term(raw_rterm)?;
+        let code = TermCode_::Subst{span, lterm, rterm};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::RebindL(ref raw_span, ref raw_lterm, ref raw_rterm) => {
+        let span = self._load_raw_span(raw_span)?;
+        let lterm = self._load_raw_term(raw_lterm)?;
+        let rterm = self._load_raw_term(raw_rterm)?;
+        let code = TermCode_::RebindL{span, lterm, rterm};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::RebindR(ref raw_span, ref raw_lterm, ref raw_rterm) => {
+        let span = self._load_raw_span(raw_span)?;
+        let lterm = self._load_raw_term(raw_lterm)?;
+        let rterm = self._load_raw_term(raw_rterm)?;
+        let code = TermCode_::RebindR{span, lterm, rterm};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
       &RawTerm_::Apply(ref raw_span, ref raw_tup) => {
         let span = self._load_raw_span(raw_span)?;
         let mut tup: CellNum = nil();
         let mut cur_tup: CellNum = nil();
+        let mut splat = false;
         for raw_tup_term in raw_tup.iter() {
+          if let &RawTerm_::Splat(..) = &**raw_tup_term {
+            splat = true;
+          }
           let term = self._load_raw_term(raw_tup_term)?;
-          let next_tup = self._alloc_cell(term.into());
+          let next_tup = self._alloc_cell(term.into())?;
           self._link_cells(cur_tup, next_tup)?;
           cur_tup = next_tup;
           if tup.is_nil() {
             tup = next_tup;
           }
         }
-        let tup = tup.into_term_code();
-        let code = TermCode_::Apply{span, tup};
+        let tup = tup.try_into_term_code().map_err(|e| e.into_check())?;
+        let code = TermCode_::Apply{span, tup, splat};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::Kwarg(ref raw_span, ref raw_id, ref raw_term) => {
+        let span = self._load_raw_span(raw_span)?;
+        let id = self._load_raw_ident(raw_id)?;
+        let term = self._load_raw_term(raw_term)?;
+        let code = TermCode_::Kwarg{span, id, term};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::Splat(ref raw_span, ref raw_term) => {
+        let span = self._load_raw_span(raw_span)?;
+        let term = self._load_raw_term(raw_term)?;
+        let code = TermCode_::Splat{span, term};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::Lambda(ref raw_span, ref raw_params, ref raw_body) => {
+        let span = self._load_raw_span(raw_span)?;
+        let mut params = Vec::with_capacity(raw_params.len());
+        for raw_param in raw_params.iter() {
+          let param = match raw_param.as_ref() {
+            None => None,
+            Some(raw_param) => Some(self._load_raw_ident(raw_param)?)
+          };
+          params.push(param);
+        }
+        let body = self._load_raw_term(raw_body)?;
+        let code = TermCode_::Lambda{span, params, body};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::Quote(ref raw_span, ref raw_body) => {
+        let span = self._load_raw_span(raw_span)?;
+        let mut body: CellNum = nil();
+        let mut cur_body: CellNum = nil();
+        for raw_body_stm in raw_body.iter() {
+          // NB: the term loader is not passed the static scope, so the
+          // quoted stms are loaded as if at the top level (as for a
+          // repl line, see `eval_line`).
+          let stm = self._load_raw_stm(DebruijnLevel::root(), nil(), raw_body_stm)?;
+          let next_body = self._alloc_cell(stm.into())?;
+          self._link_cells(cur_body, next_body)?;
+          cur_body = next_body;
+          if body.is_nil() {
+            body = next_body;
+          }
+        }
+        let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+        let code = TermCode_::Quote{span, body_stmp};
+        _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
+        let e = TableEntry_{
+          lastclk: clk,
+          inner: Box::new(code),
+        };
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+        return Ok(x);
+      }
+      &RawTerm_::ListComp(ref raw_span, ref raw_elt, ref raw_target, choice, ref raw_iter, ref raw_cond) => {
+        let span = self._load_raw_span(raw_span)?;
+        let elt = self._load_raw_term(raw_elt)?;
+        let target = match raw_target.as_ref() {
+          None => None,
+          Some(raw_target) => Some(self._load_raw_ident(raw_target)?)
+        };
+        let iter = self._load_raw_term(raw_iter)?;
+        let cond = match raw_cond.as_ref() {
+          None => None,
+          Some(raw_cond) => Some(self._load_raw_term(raw_cond)?)
+        };
+        let code = TermCode_::ListComp{span, elt, target, choice, iter, cond};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
         let e = TableEntry_{
           lastclk: clk,
@@ -3068,14 +5836,14 @@ impl FastInterp {
         let mut cur_tup: CellNum = nil();
         for raw_tup_term in raw_tup.iter() {
           let term = self._load_raw_term(raw_tup_term)?;
-          let next_tup = self._alloc_cell(term.into());
+          let next_tup = self._alloc_cell(term.into())?;
           self._link_cells(cur_tup, next_tup)?;
           cur_tup = next_tup;
           if tup.is_nil() {
             tup = next_tup;
           }
         }
-        let tup = tup.into_term_code();
+        let tup = tup.try_into_term_code().map_err(|e| e.into_check())?;
         let code = TermCode_::ApplyBindL{span, lterm, tup};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
         let e = TableEntry_{
@@ -3091,14 +5859,14 @@ impl FastInterp {
         let mut cur_tup: CellNum = nil();
         for raw_tup_term in raw_tup.iter() {
           let term = self._load_raw_term(raw_tup_term)?;
-          let next_tup = self._alloc_cell(term.into());
+          let next_tup = self._alloc_cell(term.into())?;
           self._link_cells(cur_tup, next_tup)?;
           cur_tup = next_tup;
           if tup.is_nil() {
             tup = next_tup;
           }
         }
-        let tup = tup.into_term_code();
+        let tup = tup.try_into_term_code().map_err(|e| e.into_check())?;
         let rterm = self._load_raw_term(raw_rterm)?;
         let code = TermCode_::ApplyBindR{span, tup, rterm};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
@@ -3116,14 +5884,14 @@ impl FastInterp {
         let mut rtup_cur: CellNum = nil();
         for raw_tup_term in raw_rtup.iter() {
           let term = self._load_raw_term(raw_tup_term)?;
-          let next_tup = self._alloc_cell(term.into());
+          let next_tup = self._alloc_cell(term.into())?;
           self._link_cells(rtup_cur, next_tup)?;
           rtup_cur = next_tup;
           if rtup.is_nil() {
             rtup = next_tup;
           }
         }
-        let rtup = rtup.into_term_code();
+        let rtup = rtup.try_into_term_code().map_err(|e| e.into_check())?;
         let code = TermCode_::Effect{span, lterm, rtup};
         _traceln!(self, "DEBUG: FastInterp::_load_raw_term: x={:?} code={:?}", x, code);
         let e = TableEntry_{
@@ -3140,15 +5908,16 @@ impl FastInterp {
   }
 
   // [Interp-API]
-  pub fn _load_raw_ident(&mut self, raw_id: &RawIdent_) -> Result<IdentNum, InterpCheck> {
-    match self.env.raw_id_index.get(raw_id) {
+  pub(crate) fn _load_raw_ident(&mut self, raw_id: &RawIdent_) -> Result<IdentNum, InterpCheck> {
+    let sym = self.env._intern(raw_id.as_raw_str());
+    match self.env.raw_id_index.get(&sym) {
       None => {}
       Some(&x) => {
         return Ok(x);
       }
     }
     let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_ident();
+    let x = self._fresh()?.into_ident();
     self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawIdent(x).into()));
     _traceln!(self, "DEBUG: FastInterp::_load_raw_ident: x={:?} raw ident={:?}", x, raw_id);
     let e = TableEntry_{
@@ -3156,20 +5925,21 @@ impl FastInterp {
       inner: Box::new(raw_id.clone()),
     };
     self.env.table_full[SNumSort::Ident as usize].insert(x.into(), e);
-    self.env.raw_id_index.insert(raw_id.clone(), x.into());
+    self.env.raw_id_index.insert(sym, x.into());
     Ok(x)
   }
 
   // [Interp-API]
-  pub fn _load_raw_lit_str(&mut self, raw_lit_str: &RawLit_) -> Result<LitStrNum, InterpCheck> {
-    match self.env.raw_lit_index.get(raw_lit_str) {
+  pub(crate) fn _load_raw_lit_str(&mut self, raw_lit_str: &RawLit_) -> Result<LitStrNum, InterpCheck> {
+    let sym = self.env._intern(raw_lit_str.as_raw_str());
+    match self.env.raw_lit_index.get(&sym) {
       None => {}
       Some(&x) => {
         return Ok(x);
       }
     }
     let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_lit_str();
+    let x = self._fresh()?.into_lit_str();
     self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawLitStr(x).into()));
     _traceln!(self, "DEBUG: FastInterp::_load_raw_lit_str: x={:?} raw lit str={:?}", x, raw_lit_str);
     let e = TableEntry_{
@@ -3177,12 +5947,12 @@ impl FastInterp {
       inner: Box::new(raw_lit_str.clone()),
     };
     self.env.table_full[SNumSort::Litstr as usize].insert(x.into(), e);
-    self.env.raw_lit_index.insert(raw_lit_str.clone(), x.into());
+    self.env.raw_lit_index.insert(sym, x.into());
     Ok(x)
   }
 
   // [Interp-API]
-  pub fn _load_raw_span(&mut self, raw_span: &RawSpan_) -> Result<SpanNum, InterpCheck> {
+  pub(crate) fn _load_raw_span(&mut self, raw_span: &RawSpan_) -> Result<SpanNum, InterpCheck> {
     match self.env.raw_span_index.get(raw_span) {
       None => {}
       Some(&x) => {
@@ -3190,7 +5960,7 @@ impl FastInterp {
       }
     }
     let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_span();
+    let x = self._fresh()?.into_span();
     self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawSpan(x).into()));
     _traceln!(self, "DEBUG: FastInterp::_load_raw_span: x={:?} raw span={:?}", x, raw_span);
     let e = TableEntry_{
@@ -3204,530 +5974,2257 @@ impl FastInterp {
 
   // [Interp-API]
   pub fn _load_function<V: Function>(&mut self, fun: V) -> Result<FunNum, InterpCheck> {
+    self._load_function_box(Box::new(fun))
+  }
+
+  // [Interp-API]
+  pub(crate) fn _load_function_box(&mut self, fun: Box<dyn Function>) -> Result<FunNum, InterpCheck> {
     let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_fun();
+    let x = self._fresh()?.into_fun();
     self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadFunction(x).into()));
     _traceln!(self, "DEBUG: FastInterp::_load_function: x={:?} fun={:?}", x, fun);
-    self.env.fun_full.insert(x.into(), (Box::new(fun) as Box<dyn Function>).into());
+    self.env.fun_full.insert(x.into(), fun.into());
     Ok(x)
   }
 
   // [Interp-API]
   pub fn _register_builtin_function<RawId: Into<RawIdent_>, V: Function>(&mut self, raw_id: RawId, cls: V) -> Result<FunNum, InterpCheck> {
+    self._register_builtin_function_box(raw_id, Box::new(cls))
+  }
+
+  // [Interp-API]
+  pub(crate) fn _register_builtin_function_box<RawId: Into<RawIdent_>>(&mut self, raw_id: RawId, cls: Box<dyn Function>) -> Result<FunNum, InterpCheck> {
     let clk = self.clkctr._get_clock();
-    // FIXME: should the id belong in the "builtin" namespace?
     let id = self._load_raw_ident(&raw_id.into())?;
-    let fun = self._load_function(cls)?;
-    self.env.fun_name.insert(id.into(), fun.into());
+    let fun = self._load_function_box(cls)?;
+    let prev_x = self.env.fun_name.insert(id, fun.into()).try_into_nil()
+        .map_err(|_| format!("nil-bound fun name: id = {:?}", id).into_check())?;
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindFunName(id, prev_x).into()));
+    self._bind_ident(clk, NamespaceScope_::Builtin, id, fun.into())?;
     self.unify(clk, id, fun)?;
     Ok(fun)
   }
 
   // [Interp-API]
-  pub fn _load_obj_cls<V: ObjCls>(&mut self, cls: V) -> Result<ObjClsNum, InterpCheck> {
+  pub(crate) fn _load_obj_cls<V: ObjCls>(&mut self, cls: V) -> Result<ObjClsNum, InterpCheck> {
+    self._load_obj_cls_box(Box::new(cls))
+  }
+
+  // [Interp-API]
+  pub(crate) fn _load_obj_cls_box(&mut self, cls: Box<dyn ObjCls>) -> Result<ObjClsNum, InterpCheck> {
     let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_obj_cls();
+    let x = self._fresh()?.into_obj_cls();
     self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadObjCls(x).into()));
     _traceln!(self, "DEBUG: FastInterp::_load_object: x={:?} obj cls={:?}", x, cls);
-    self.env.obj_cls_full.insert(x.into(), (Box::new(cls) as Box<dyn ObjCls>).into());
+    self.env.obj_cls_full.insert(x.into(), cls.into());
     Ok(x)
   }
 
   // [Interp-API]
-  pub fn _register_builtin_obj_cls<RawId: Into<RawIdent_>, V: ObjCls>(&mut self, raw_id: RawId, cls: V) -> Result<ObjClsNum, InterpCheck> {
-    let clk = self.clkctr._get_clock();
-    let id = self._load_raw_ident(&raw_id.into())?;
-    // FIXME: should the id belong in the "builtin" namespace?
-    let obj_cls = self._load_obj_cls(cls)?;
-    self.env.obj_cls_name.insert(id.into(), obj_cls.into());
-    self.unify(clk, id, obj_cls)?;
-    Ok(obj_cls)
+  pub(crate) fn _load_obj_val(&mut self, clk: LClk, val: ObjVal_) -> Result<ObjValNum, InterpCheck> {
+    let x = self._fresh()?.into_obj_val();
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadObjVal(x).into()));
+    _traceln!(self, "DEBUG: FastInterp::_load_obj_val: x={:?} obj val={:?}", x, val);
+    self.env.obj_val_full.insert(x.into(), val.into());
+    Ok(x)
   }
 
   // [Interp-API]
-  pub fn maybe_borrow_fun(&mut self, x: FunNum) -> Result<Option<Box<dyn Function>>, InterpCheck> {
-    match self.env.fun_full.get_mut(&x.into()) {
+  //
+  // Rebind `key` in a trie obj val to `y`, or unbind it if `y` is nil
+  // (see `UndoLogEntry_::TrieInsert`).
+  pub(crate) fn _trie_restore(&mut self, obj: ObjValNum, key: &SafeStr, y: SNum) -> Result<(), InterpCheck> {
+    let mut val = self.borrow_obj_val(obj)?;
+    let result = match val.as_any_mut().downcast_mut::<self::prelude::TokenTrieVal>() {
       None => {
-        Ok(None)
+        _debugln!(self, "DEBUG: FastInterp::_trie_restore: obj={:?} not a trie", obj);
+        Err(bot())
       }
-      Some(obj) => {
-        match obj._borrow() {
-          TransparentBox::Blk => {
-            Err(format!("Function is already borrowed: x = {x:?}").into())
-          }
-          TransparentBox::Ptr(val) => {
-            Ok(val.into())
-          }
-        }
+      Some(trie) => {
+        trie._restore(key, y);
+        Ok(())
       }
-    }
+    };
+    self.unborrow_obj_val(obj, val)?;
+    result
   }
 
   // [Interp-API]
-  pub fn borrow_fun(&mut self, x: FunNum) -> Result<Box<dyn Function>, InterpCheck> {
-    match self.env.fun_full.get_mut(&x.into()) {
+  //
+  // Bind the attr `key` of a class obj val to `y`, or unbind it if `y` is
+  // nil (see `UndoLogEntry_::AttrInsert`).
+  pub(crate) fn _attr_restore(&mut self, obj: ObjValNum, key: &SafeStr, y: SNum) -> Result<(), InterpCheck> {
+    let mut val = self.borrow_obj_val(obj)?;
+    let result = match val.as_any_mut().downcast_mut::<self::prelude::ClassVal>() {
       None => {
-        Err(format!("failed to lookup Function: x = {x:?}").into())
+        _debugln!(self, "DEBUG: FastInterp::_attr_restore: obj={:?} not a class obj", obj);
+        Err(bot())
       }
-      Some(obj) => {
-        match obj._borrow() {
-          TransparentBox::Blk => {
-            Err(format!("Function is already borrowed: x = {x:?}").into())
-          }
-          TransparentBox::Ptr(val) => {
-            Ok(val)
-          }
-        }
+      Some(cls_val) => {
+        cls_val._restore(key, y);
+        Ok(())
       }
-    }
+    };
+    self.unborrow_obj_val(obj, val)?;
+    result
   }
 
   // [Interp-API]
-  pub fn unborrow_fun(&mut self, x: FunNum, val: Box<dyn Function>) -> Result<(), InterpCheck> {
-    match self.env.fun_full.get_mut(&x.into()) {
+  //
+  // Find (or put and bind) the canonical SNum of a lit val.
+  pub(crate) fn _load_lit_val(&mut self, clk: LClk, val: LitVal_) -> Result<SNum, InterpCheck> {
+    match self.env.lit_val_bind.get(&val) {
+      Some(&y) => Ok(y),
       None => {
-        Err(format!("failed to lookup Function: x = {x:?}").into())
-      }
-      Some(obj) => {
-        match obj._swap(val) {
-          TransparentBox::Ptr(_) => {
-            Err(format!("Function was not already borrowed: x = {x:?}").into())
-          }
-          TransparentBox::Blk => {
-            Ok(())
-          }
-        }
+        let y = self._fresh()?;
+        self.put_val(clk, y, val.clone())?;
+        let prev_y = self.env.lit_val_bind.insert(val.clone(), y.into());
+        self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitVal(val, prev_y).into()));
+        Ok(y.into())
       }
     }
   }
 
   // [Interp-API]
-  pub fn _alloc_cell(&mut self, dptr: SNum) -> CellNum {
+  //
+  // Loads the int lit `v` as a term, interned (as for an int lit in the
+  // source) through `lit_term_bind` and unified w/ its interned val.
+  pub(crate) fn _load_int_lit(&mut self, clk: LClk, v: i64) -> Result<SNum, InterpCheck> {
+    let lit_term_ = LitTerm_::new_int(v);
+    if let Some(&x) = self.env.lit_term_bind.get(&lit_term_) {
+      return Ok(x);
+    }
+    let x = self._fresh()?;
+    self.put_term(clk, x, lit_term_.clone())?;
+    let prev_x = self.env.lit_term_bind.insert(lit_term_.clone(), x.into());
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitTerm(lit_term_, prev_x).into()));
+    let y = self._load_lit_val(clk, LitVal_::Int(v))?;
+    self.unify(clk, x, y)?;
+    Ok(x)
+  }
+
+  // [Interp-API]
+  pub fn _register_builtin_obj_cls<RawId: Into<RawIdent_>, V: ObjCls>(&mut self, raw_id: RawId, cls: V) -> Result<ObjClsNum, InterpCheck> {
+    self._register_builtin_obj_cls_box(raw_id, Box::new(cls))
+  }
+
+  // [Interp-API]
+  pub(crate) fn _register_builtin_obj_cls_box<RawId: Into<RawIdent_>>(&mut self, raw_id: RawId, cls: Box<dyn ObjCls>) -> Result<ObjClsNum, InterpCheck> {
     let clk = self.clkctr._get_clock();
-    let x = self._fresh().into_cell();
-    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::AllocCell(x).into()));
-    let cel = Cell_{
-      dptr,
-      next: nil(),
-      prev: nil(),
-    };
-    let e = TableEntry_{
-      lastclk: clk,
-      inner: Box::new(cel),
-    };
-    self.env.table_full[SNumSort::Cell as usize].insert(x.into(), e);
-    x
+    let id = self._load_raw_ident(&raw_id.into())?;
+    let obj_cls = self._load_obj_cls_box(cls)?;
+    let prev_x = self.env.obj_cls_name.insert(id, obj_cls.into()).try_into_nil()
+        .map_err(|_| format!("nil-bound obj cls name: id = {:?}", id).into_check())?;
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindObjClsName(id, prev_x).into()));
+    self._bind_ident(clk, NamespaceScope_::Builtin, id, obj_cls.into())?;
+    self.unify(clk, id, obj_cls)?;
+    Ok(obj_cls)
   }
 
   // [Interp-API]
-  pub fn _link_cells(&mut self, lcel: CellNum, rcel: CellNum) -> Result<(), InterpCheck> {
-    // NB: shortcut case for the common pattern of building a tuple-cell
-    // from left-to-right.
-    if lcel.is_nil() {
-      return Ok(());
-    }
-    match self.env.table_full[SNumSort::Cell as usize].get(&lcel.into())
-      .and_then(|y| y.as_any().downcast_ref::<Cell_>())
-    {
+  #[track_caller]
+  pub(crate) fn lookup_namespace(&self, scope: SNum) -> Result<&Namespace, InterpCheck> {
+    match self.env.ns_full.get(&scope) {
       None => {
-        return Err("bug".into());
+        Err(format!("failed to lookup Namespace: scope = {scope:?}").into())
       }
-      Some(lcel_) => {
-        match self.env.table_full[SNumSort::Cell as usize].get(&rcel.into())
-          .and_then(|y| y.as_any().downcast_ref::<Cell_>())
-        {
-          None => {
-            return Err("bug".into());
-          }
-          Some(rcel_) => {
-            let clk = self.clkctr._get_clock();
-            let olnext = lcel_.next.get();
-            let orprev = rcel_.prev.get();
-            self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LinkCells(lcel, olnext, rcel, orprev).into()));
-            lcel_.next.set(rcel);
-            rcel_.prev.set(lcel);
-            return Ok(());
-          }
+      Some(ns) => Ok(ns)
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Resolve the namespace that an ident is bound in, or would be bound in
+  // upon its first occurrence. Returns the scope along with the existing
+  // binding, if any.
+  pub(crate) fn resolve_ident(&self, id: IdentNum) -> Result<(NamespaceScope_, Option<SNum>), InterpCheck> {
+    let mut scope = self.env.ns_cur;
+    while !scope.is_nil() {
+      let ns = self.lookup_namespace(scope)?;
+      if ns.is_global(id) {
+        break;
+      }
+      match ns.is_nonlocal(id) {
+        None => {}
+        Some(None) => {
+          // NB: a nonlocal decl w/out a static scope forwards to the
+          // nearest enclosing scope that binds the ident.
+          scope = ns.sup;
+          continue;
+        }
+        Some(Some(static_scope)) => {
+          let scope = self._static_scope(scope, id, static_scope)?;
+          return Ok(self._resolve_ident_in(scope, id));
         }
       }
+      if let Some(x) = ns.get(id) {
+        return Ok((NamespaceScope_::Local(scope), Some(x)));
+      }
+      scope = ns.sup;
+    }
+    if let Some(&x) = self.env.id_global_bind.get(&id) {
+      return Ok((NamespaceScope_::Module, Some(x)));
     }
+    if let Some(&x) = self.env.id_builtin_bind.get(&id) {
+      return Ok((NamespaceScope_::Builtin, Some(x)));
+    }
+    Ok((self._bind_target(id)?, None))
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn lookup_mod_code(&self, x: ModCodeNum) -> Result<ModCode_, InterpCheck> {
-    match self.env.table_full[SNumSort::Code as usize].get(&x.into()) {
-      None => {
-        Err(format!("failed to lookup ModCode_: x = {x:?}").into())
+  fn _resolve_ident_in(&self, scope: NamespaceScope_, id: IdentNum) -> (NamespaceScope_, Option<SNum>) {
+    let x = match scope {
+      NamespaceScope_::Builtin => self.env.id_builtin_bind.get(&id).map(|&x| x),
+      NamespaceScope_::Module => self.env.id_global_bind.get(&id).map(|&x| x),
+      NamespaceScope_::Local(scope) => self.env.ns_full.get(&scope).and_then(|ns| ns.get(id)),
+    };
+    (scope, x)
+  }
+
+  // NB: the local scope `scope` has debruijn level `ns.level`; the static
+  // scope is either a (non-negative) debruijn level, or a (negative)
+  // relative index.
+  fn _static_scope(&self, scope: SNum, id: IdentNum, static_scope: i16) -> Result<NamespaceScope_, InterpCheck> {
+    let ns = self.lookup_namespace(scope)?;
+    let level = if static_scope >= 0 {
+      static_scope
+    } else {
+      ns.level.inner + static_scope
+    };
+    if level < 0 || level >= ns.level.inner {
+      return Err(format!("invalid nonlocal static scope: id = {:?} scope = {}", id, static_scope).into());
+    }
+    let mut cursor = ns.sup;
+    while !cursor.is_nil() {
+      let ns = self.lookup_namespace(cursor)?;
+      if ns.level.inner == level {
+        return Ok(NamespaceScope_::Local(cursor));
       }
-      Some(t) => {
-        match t.as_any().downcast_ref::<ModCode_>() {
-          None => {
-            Err(format!("lookup is not a ModCode_: x = {x:?}").into())
-          }
-          Some(code) => {
-            Ok(code.clone())
+      cursor = ns.sup;
+    }
+    Ok(NamespaceScope_::Module)
+  }
+
+  // [Interp-API]
+  //
+  // The namespace in which a new binding of an ident is placed: the current
+  // scope, unless the ident was declared `global` or `nonlocal`.
+  pub(crate) fn _bind_target(&self, id: IdentNum) -> Result<NamespaceScope_, InterpCheck> {
+    let scope = self.env.ns_cur;
+    if scope.is_nil() {
+      return Ok(NamespaceScope_::Module);
+    }
+    let ns = self.lookup_namespace(scope)?;
+    if ns.is_global(id) {
+      return Ok(NamespaceScope_::Module);
+    }
+    match ns.is_nonlocal(id) {
+      None => {}
+      Some(None) => {
+        let mut cursor = ns.sup;
+        while !cursor.is_nil() {
+          let ns = self.lookup_namespace(cursor)?;
+          if ns.get(id).is_some() || ns.is_nonlocal(id).is_none() {
+            return Ok(NamespaceScope_::Local(cursor));
           }
+          cursor = ns.sup;
         }
+        return Ok(NamespaceScope_::Module);
+      }
+      Some(Some(static_scope)) => {
+        return self._static_scope(scope, id, static_scope);
       }
     }
+    Ok(NamespaceScope_::Local(scope))
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn lookup_stm_code(&self, x: StmCodeNum) -> Result<StmCode_, InterpCheck> {
-    match self.env.table_full[SNumSort::Code as usize].get(&x.into()) {
-      None => {
-        Err(format!("failed to lookup StmCode_: x = {x:?}").into())
+  // [Interp-API]
+  //
+  // Allocate a fresh local scope, lexically enclosed by `sup`.
+  pub(crate) fn _alloc_namespace(&mut self, clk: LClk, sup: SNum) -> Result<SNum, InterpCheck> {
+    let level = if sup.is_nil() {
+      DebruijnLevel::root().push()
+    } else {
+      self.lookup_namespace(sup)?.level.push()
+    };
+    let x = self._fresh()?;
+    self.env.ns_full.insert(x, Namespace::fresh(level, sup));
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::AllocNamespace(x).into()));
+    Ok(x)
+  }
+
+  // [Interp-API]
+  //
+  // Switch the current local scope; returns the previous one.
+  pub(crate) fn _switch_namespace(&mut self, clk: LClk, scope: SNum) -> SNum {
+    let prev = replace(&mut self.env.ns_cur, scope);
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::SwitchNamespace(prev).into()));
+    prev
+  }
+
+  // [Interp-API]
+  pub(crate) fn _bind_ident(&mut self, clk: LClk, scope: NamespaceScope_, id: IdentNum, x: SNum) -> Result<(), InterpCheck> {
+    match scope {
+      NamespaceScope_::Builtin => {
+        let prev_x = self.env.id_builtin_bind.insert(id, x).try_into_nil()
+            .map_err(|_| format!("nil-bound builtin ident: id = {:?}", id).into_check())?;
+        self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindBuiltinIdent(id, prev_x).into()));
+      }
+      NamespaceScope_::Module => {
+        let prev_x = self.env.id_global_bind.insert(id, x).try_into_nil()
+            .map_err(|_| format!("nil-bound global ident: id = {:?}", id).into_check())?;
+        self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindGlobalIdent(id, prev_x).into()));
+      }
+      NamespaceScope_::Local(scope) => {
+        let ns = match self.env.ns_full.get_mut(&scope) {
+          None => return Err(format!("failed to lookup Namespace: scope = {:?} id = {:?}", scope, id).into()),
+          Some(ns) => ns
+        };
+        let prev_x = ns.bind.insert(id, x).try_into_nil()
+            .map_err(|_| format!("nil-bound local ident: id = {:?}", id).into_check())?;
+        self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLocalIdent(scope, id, prev_x).into()));
       }
-      Some(t) => {
-        match t.as_any().downcast_ref::<StmCode_>() {
-          None => {
-            Err(format!("lookup is not a StmCode_: x = {x:?}").into())
-          }
-          Some(code) => {
-            Ok(code.clone())
-          }
-        }
+    }
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Bind a fresh ident term in the given namespace.
+  pub(crate) fn _bind_fresh_ident(&mut self, clk: LClk, scope: NamespaceScope_, id: IdentNum) -> Result<SNum, InterpCheck> {
+    let raw_id = self.lookup_raw_ident(id)?.clone();
+    let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+    let term_ = IdentTerm_{id, raw_id};
+    self.put_term(clk, x, term_)?;
+    self._bind_ident(clk, scope, id, x.into())?;
+    Ok(x.into())
+  }
+
+  // [Interp-API]
+  //
+  // Bind a fresh unbound var (i.e. an SNum w/out a tabled term, unlike
+  // `_bind_fresh_ident`) to the ident in the current scope, shadowing any
+  // binding of the ident in an enclosing scope, and replacing any binding
+  // in the current scope.
+  pub(crate) fn _bind_fresh_var(&mut self, clk: LClk, id: IdentNum) -> Result<SNum, InterpCheck> {
+    let x: SNum = self._fresh()?.try_into_term().map_err(|e| e.into_check())?.into();
+    let scope = self.env.ns_cur;
+    let prev_x = if scope.is_nil() {
+      self.env.id_global_bind.insert(id, x)
+    } else {
+      let ns = match self.env.ns_full.get_mut(&scope) {
+        None => return Err(format!("failed to lookup Namespace: scope = {:?} id = {:?}", scope, id).into()),
+        Some(ns) => ns
+      };
+      ns.bind.insert(id, x)
+    };
+    let prev_x = prev_x.try_into_nil()
+        .map_err(|_| format!("nil-bound ident: id = {:?}", id).into_check())?;
+    _traceln!(self, "DEBUG: FastInterp::_bind_fresh_var: id={:?} scope={:?} x={:?} prev x={:?}", id, scope, x, prev_x);
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::RebindIdent(id, scope, prev_x).into()));
+    Ok(x)
+  }
+
+  // [Interp-API]
+  //
+  // Replace the binding of an ident (rather than unifying w/ it). An ident
+  // that resolves to a builtin is instead rebound where it would be bound.
+  pub(crate) fn _rebind_ident(&mut self, clk: LClk, id: IdentNum, x: SNum) -> Result<(), InterpCheck> {
+    let scope = match self.resolve_ident(id)? {
+      (NamespaceScope_::Builtin, _) => self._bind_target(id)?,
+      (scope, _) => scope
+    };
+    let (scope, prev_x) = match scope {
+      NamespaceScope_::Builtin => {
+        return Err(bot());
+      }
+      NamespaceScope_::Module => {
+        (nil(), self.env.id_global_bind.insert(id, x))
+      }
+      NamespaceScope_::Local(scope) => {
+        let ns = match self.env.ns_full.get_mut(&scope) {
+          None => return Err(format!("failed to lookup Namespace: scope = {:?} id = {:?}", scope, id).into()),
+          Some(ns) => ns
+        };
+        (scope, ns.bind.insert(id, x))
+      }
+    };
+    let prev_x = prev_x.try_into_nil()
+        .map_err(|_| format!("nil-bound ident: id = {:?}", id).into_check())?;
+    _traceln!(self, "DEBUG: FastInterp::_rebind_ident: id={:?} scope={:?} x={:?} prev x={:?}", id, scope, x, prev_x);
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::RebindIdent(id, scope, prev_x).into()));
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Find the function (if any) in the unifier class of `query`.
+  pub(crate) fn _find_fun(&self, clk: LClk, query: SNum) -> Result<Option<FunNum>, InterpCheck> {
+    if self.env.fun_full.contains_key(&query) {
+      return Ok(Some(query.try_into_fun().map_err(|e| e.into_check())?));
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if self.env.fun_full.contains_key(&key.inst) {
+        return Ok(Some(key.inst.try_into_fun().map_err(|e| e.into_check())?));
       }
     }
+    Ok(None)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn lookup_stm_code_cell(&self, x: StmCodeCellNum) -> Result<Cell_, InterpCheck> {
-    match self.env.table_full[SNumSort::Cell as usize].get(&x.into()) {
-      None => {
-        Err(format!("failed to lookup cell: x = {x:?}").into())
+  // [Interp-API]
+  //
+  // Find the proc (if any) in the unifier class of `query`.
+  pub(crate) fn _find_proc(&self, clk: LClk, query: SNum) -> Result<Option<ProcTerm_>, InterpCheck> {
+    if let Some(t) = self.get_term(query)? {
+      if let Some(t) = t.as_any().downcast_ref::<ProcTerm_>() {
+        return Ok(Some(t.clone()));
       }
-      Some(t) => {
-        match t.as_any().downcast_ref::<Cell_>() {
-          None => {
-            Err(format!("lookup is not a cell: x = {x:?}").into())
-          }
-          Some(cel_) => {
-            // FIXME: the deref of this cell must be a stm code.
-            /*if cel_.dptr ... {
-              return Err(format!("lookup is not a stm code cell: x = {x:?}").into());
-            }*/
-            Ok(cel_.clone())
-          }
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if let Some(t) = self.get_term(key.inst)? {
+        if let Some(t) = t.as_any().downcast_ref::<ProcTerm_>() {
+          return Ok(Some(t.clone()));
         }
       }
     }
+    Ok(None)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn lookup_term_code(&self, x: TermCodeNum) -> Result<TermCode_, InterpCheck> {
-    match self.env.table_full[SNumSort::Code as usize].get(&x.into()) {
-      None => {
-        Err(format!("failed to lookup TermCode_: x = {x:?}").into())
+  // [Interp-API]
+  //
+  // Find the defmatch (if any) in the unifier class of `query`.
+  pub(crate) fn _find_match(&self, clk: LClk, query: SNum) -> Result<Option<MatchTerm_>, InterpCheck> {
+    if let Some(t) = self.get_term(query)? {
+      if let Some(t) = t.as_any().downcast_ref::<MatchTerm_>() {
+        return Ok(Some(t.clone()));
       }
-      Some(t) => {
-        match t.as_any().downcast_ref::<TermCode_>() {
-          None => {
-            Err(format!("lookup is not a TermCode_: x = {x:?}").into())
-          }
-          Some(code) => {
-            Ok(code.clone())
-          }
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if let Some(t) = self.get_term(key.inst)? {
+        if let Some(t) = t.as_any().downcast_ref::<MatchTerm_>() {
+          return Ok(Some(t.clone()));
         }
       }
     }
+    Ok(None)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn lookup_term_code_cell(&self, x: TermCodeCellNum) -> Result<Cell_, InterpCheck> {
-    match self.env.table_full[SNumSort::Cell as usize].get(&x.into()) {
-      None => {
-        Err(format!("failed to lookup cell: x = {x:?}").into())
+  // [Interp-API]
+  //
+  // Find the lambda (if any) in the unifier class of `query`.
+  pub(crate) fn _find_lambda(&self, clk: LClk, query: SNum) -> Result<Option<LambdaTerm_>, InterpCheck> {
+    if let Some(t) = self.get_term(query)? {
+      if let Some(t) = t.as_any().downcast_ref::<LambdaTerm_>() {
+        return Ok(Some(t.clone()));
       }
-      Some(t) => {
-        match t.as_any().downcast_ref::<Cell_>() {
-          None => {
-            Err(format!("lookup is not a cell: x = {x:?}").into())
-          }
-          Some(cel_) => {
-            // FIXME: the deref of this cell must be a term code.
-            /*if cel_.dptr ... {
-              return Err(format!("lookup is not a term code cell: x = {x:?}").into());
-            }*/
-            Ok(cel_.clone())
-          }
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if let Some(t) = self.get_term(key.inst)? {
+        if let Some(t) = t.as_any().downcast_ref::<LambdaTerm_>() {
+          return Ok(Some(t.clone()));
         }
       }
     }
+    Ok(None)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn lookup_raw_ident(&self, id: IdentNum) -> Result<&RawIdent_, InterpCheck> {
-    match self.env.table_full[SNumSort::Ident as usize].get(&id.into()) {
-      None => {
-        Err(format!("failed to lookup raw ident: id = {id:?}").into())
+  // [Interp-API]
+  //
+  // Find the reified quote (if any) in the unifier class of `query`.
+  pub(crate) fn _find_quote(&self, clk: LClk, query: SNum) -> Result<Option<QuoteTerm_>, InterpCheck> {
+    if let Some(t) = self.get_term(query)? {
+      if let Some(t) = t.as_any().downcast_ref::<QuoteTerm_>() {
+        return Ok(Some(t.clone()));
       }
-      Some(t) => {
-        match t.as_any().downcast_ref::<RawIdent_>() {
-          None => {
-            Err(format!("lookup is not a raw ident: id = {id:?}").into())
-          }
-          Some(raw_id) => {
-            Ok(raw_id)
-          }
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if let Some(t) = self.get_term(key.inst)? {
+        if let Some(t) = t.as_any().downcast_ref::<QuoteTerm_>() {
+          return Ok(Some(t.clone()));
         }
       }
     }
+    Ok(None)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn lookup_raw_lit_str(&self, lit_str: LitStrNum) -> Result<&RawLit_, InterpCheck> {
-    match self.env.table_full[SNumSort::Litstr as usize].get(&lit_str.into()) {
-      None => {
-        Err(format!("failed to lookup raw literal: lit str = {lit_str:?}").into())
-      }
-      Some(t) => {
-        match t.as_any().downcast_ref::<RawLit_>() {
-          None => {
-            Err(format!("lookup is not a raw literal: lit str = {lit_str:?}").into())
+  // [Interp-API]
+  //
+  // Find the tabled tuples that match `pattern` elementwise: a `None`
+  // element matches any child, and a `Some(q)` element matches a child in
+  // the unifier class of `q`. The tuples are returned in key order.
+  pub(crate) fn match_tuples(&self, clk: LClk, pattern: &[Option<SNum>]) -> Result<Vec<SNum>, InterpCheck> {
+    let mut cand = Vec::new();
+    match pattern.first() {
+      None => return Ok(Vec::new()),
+      Some(&Some(q)) => {
+        // NB: the index is keyed by instance, so look up each instance
+        // of the first element's class.
+        let keys = self.env.unifier._findall(&self.clkinval, clk, q).map_err(|e| e.into_check())?;
+        let mut xs = BTreeSet::new();
+        for &key in keys.iter() {
+          if let Some(set) = self.env.tuple_index.get(&(SNumSort::Term as RawSNum, key.inst)) {
+            xs.extend(set.iter().copied());
           }
-          Some(lit_) => {
-            Ok(lit_)
+        }
+        cand.extend(xs);
+      }
+      Some(&None) => {
+        for (&x, e) in self.env.table_full[SNumSort::Term as usize].iter() {
+          if _tuple_first(&*e.inner).is_some() {
+            cand.push(x);
           }
         }
       }
     }
-  }
+    let mut pat_cls = Vec::with_capacity(pattern.len());
+    for &q in pattern.iter() {
+      match q {
+        None => pat_cls.push(None),
+        Some(q) => {
+          let q = self.env.unifier._find(&self.clkinval, clk, q).map_err(|e| e.into_check())?;
+          pat_cls.push(Some(q.ecls));
+        }
+      }
+    }
+    let mut mats = Vec::new();
+    'cand: for &x in cand.iter() {
+      let e = match self.env.table_full[SNumSort::Term as usize].get(&x) {
+        None => continue,
+        Some(e) => e
+      };
+      let tup = match e.as_any().downcast_ref::<TupleTerm_>() {
+        None => continue,
+        Some(tup) => tup
+      };
+      if tup.buf.len() != pat_cls.len() {
+        continue;
+      }
+      for (c, q) in tup.buf.iter().zip(pat_cls.iter()) {
+        if let &Some(q) = q {
+          let c = self.env.unifier._find(&self.clkinval, clk, c.inst).map_err(|e| e.into_check())?;
+          if c.ecls != q {
+            continue 'cand;
+          }
+        }
+      }
+      mats.push(x);
+    }
+    Ok(mats)
+  }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn find<K: Into<SNum>>(&self, clk: LClk, query: K) -> Result<ENum, InterpCheck> {
-    self.env.unifier._find(&self.clkinval, clk, query.into()).map_err(|e| e.into())
+  // [Interp-API]
+  //
+  // Whether the unifier class of `query` has no tabled term or val, and
+  // no builtin function or object.
+  //
+  // NB: an ident term only names its class, and does not bind it.
+  pub(crate) fn _is_unbound(&self, clk: LClk, query: SNum) -> Result<bool, InterpCheck> {
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if let Some(t) = self.get_term(key.inst)? {
+        if t.as_any().downcast_ref::<IdentTerm_>().is_none() {
+          return Ok(false);
+        }
+      }
+      if self.env.table_full[SNumSort::Val as usize].contains_key(&key.inst) ||
+         self.env.fun_full.contains_key(&key.inst) ||
+         self.env.obj_cls_full.contains_key(&key.inst) ||
+         self.env.obj_val_full.contains_key(&key.inst)
+      {
+        return Ok(false);
+      }
+    }
+    Ok(true)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn unify<LK: Into<SNum>, RK: Into<SNum>>(&mut self, clk: LClk, lquery: LK, rquery: RK) -> Result<SNum, InterpCheck> {
-    self.env.unifier._unify(&mut self.log, &self.clkinval, clk, lquery.into(), rquery.into()).map_err(|e| e.into())
+  // [Interp-API]
+  //
+  // Reify a quoted stm list as a fresh quote term.
+  pub(crate) fn _reify_quote(&mut self, clk: LClk, body_stmp: StmCodeCellNum) -> Result<SNum, InterpCheck> {
+    let mut buf = Vec::new();
+    let mut stmp = body_stmp;
+    while !stmp.is_nil() {
+      let stmp_ = self.lookup_stm_code_cell(stmp)?;
+      buf.push(stmp_.dptr.try_into_stm_code().map_err(|e| e.into_check())?);
+      stmp = stmp_.next.get().try_into_stm_code().map_err(|e| e.into_check())?;
+    }
+    let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+    self.put_term(clk, x, QuoteTerm_{body_stmp, buf: buf.into()})?;
+    Ok(x.into())
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  pub fn get_vals(&self, clk: LClk, query: ENum) -> Result<Vec<(ENum, Val_)>, InterpCheck> {
-    let keys = self.env.unifier._findall(&self.clkinval, clk, query.inst).map_err(|e| e.into_check())?;
-    _debugln!(self, "DEBUG: FastInterp::get_vals: query={:?} keys={:?}", query, keys);
-    let mut vals = Vec::new();
+  // [Interp-API]
+  //
+  // Unquote a reified quote: push a continuation to interpret the quoted
+  // stms in the current env, in place of the continuation `knt` of a
+  // function apply (see `InterpApplyTerm`); the result of the last stm
+  // is unified w/ `ret`.
+  pub(crate) fn _push_unquote(&mut self, clk: LClk, quote: &QuoteTerm_, ret: SNum, knt: &BorrowedMemKnt) -> Result<(), InterpCheck> {
+    if self.knt_.is_some() {
+      return Err(bot());
+    }
+    self.knt_ = MemKnt{
+      clk,
+      prev: knt.prev.clone(),
+      cur:  MemKnt_::InterpUnquote(quote.body_stmp, UnquoteInterpState_::fresh(ret)),
+    }.into_ref();
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Call a proc w/ the positional `args`: push a continuation to interpret
+  // the proc body, in place of the continuation `knt` of an object request
+  // (see `InterpEffectTerm`); the result of the call is `ret`. The proc
+  // defaults are not applied.
+  pub(crate) fn _push_proc_call(&mut self, clk: LClk, proc_: &ProcTerm_, args: Vec<SNum>, ret: SNum, knt: &BorrowedMemKnt) -> Result<(), InterpCheck> {
+    if self.knt_.is_some() {
+      return Err(bot());
+    }
+    self.knt_ = MemKnt{
+      clk,
+      prev: knt.prev.clone(),
+      cur:  MemKnt_::InterpProcBody(proc_.code, ProcBodyInterpState_::fresh(proc_.scope, args, ret)),
+    }.into_ref();
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Find the stm or term code (if any) in the unifier class of `query`.
+  pub(crate) fn _find_code(&self, clk: LClk, query: SNum) -> Result<Option<SNum>, InterpCheck> {
+    let is_code = |x: &SNum| {
+      match self.env.table_full[SNumSort::Code as usize].get(x) {
+        None => false,
+        Some(e) => {
+          let inner = e.inner.as_any();
+          inner.downcast_ref::<StmCode_>().is_some() ||
+          inner.downcast_ref::<TermCode_>().is_some()
+        }
+      }
+    };
+    if is_code(&query) {
+      return Ok(Some(query));
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
     for &key in keys.iter() {
-      if key.ecls != query.ecls {
-        _debugln!(self, "DEBUG: FastInterp::get_vals: cls mismatch: query={:?} key={:?}", query, key);
-        return Err(bot());
+      if is_code(&key.inst) {
+        return Ok(Some(key.inst));
       }
-      // NB: invariant: table lookups go through the ENum _instance_.
-      match self.env.table_full[SNumSort::Val as usize].get(&key.inst) {
-        None => {
-          _debugln!(self, "DEBUG: FastInterp::get_vals: not a val: query={:?} key={:?}", query, key);
+    }
+    Ok(None)
+  }
+
+  // [Interp-API]
+  //
+  // Like `_push_unquote`, but for a single stm or term code.
+  pub(crate) fn _push_eval_code(&mut self, clk: LClk, code: SNum, ret: SNum, knt: &BorrowedMemKnt) -> Result<(), InterpCheck> {
+    if self.knt_.is_some() {
+      return Err(bot());
+    }
+    self.knt_ = MemKnt{
+      clk,
+      prev: knt.prev.clone(),
+      cur:  MemKnt_::InterpEvalCode(code, EvalCodeInterpState_::fresh(ret)),
+    }.into_ref();
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Find the object class (if any) in the unifier class of `query`.
+  pub(crate) fn _find_obj_cls(&self, clk: LClk, query: SNum) -> Result<Option<ObjClsNum>, InterpCheck> {
+    if self.env.obj_cls_full.contains_key(&query) {
+      return Ok(Some(query.try_into_obj_cls().map_err(|e| e.into_check())?));
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if self.env.obj_cls_full.contains_key(&key.inst) {
+        return Ok(Some(key.inst.try_into_obj_cls().map_err(|e| e.into_check())?));
+      }
+    }
+    Ok(None)
+  }
+
+  // [Interp-API]
+  //
+  // Find the object value (if any) in the unifier class of `query`.
+  pub(crate) fn _find_obj_val(&self, clk: LClk, query: SNum) -> Result<Option<ObjValNum>, InterpCheck> {
+    if self.env.obj_val_full.contains_key(&query) {
+      return Ok(Some(query.try_into_obj_val().map_err(|e| e.into_check())?));
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if self.env.obj_val_full.contains_key(&key.inst) {
+        return Ok(Some(key.inst.try_into_obj_val().map_err(|e| e.into_check())?));
+      }
+    }
+    Ok(None)
+  }
+
+  // [Interp-API]
+  //
+  // A qual ident (`lhs.id`) is keyed on the unifier class of the lhs and
+  // on the raw ident; each distinct key is loaded as its own ident.
+  pub(crate) fn _load_qual_ident(&mut self, clk: LClk, lhs: SNum, id: IdentNum) -> Result<IdentNum, InterpCheck> {
+    let lhs = self.find(clk, lhs)?.ecls;
+    let raw_id = self.lookup_raw_ident(id)?.clone();
+    match self.env.qual_id_index.get(&(lhs, raw_id.clone())) {
+      None => {}
+      Some(&x) => {
+        return Ok(x);
+      }
+    }
+    let x = self._fresh()?.into_ident();
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadQualIdent(x, lhs).into()));
+    _traceln!(self, "DEBUG: FastInterp::_load_qual_ident: x={:?} lhs={:?} raw ident={:?}", x, lhs, raw_id);
+    let e = TableEntry_{
+      lastclk: clk,
+      inner: Box::new(raw_id.clone()),
+    };
+    self.env.table_full[SNumSort::Ident as usize].insert(x.into(), e);
+    self.env.qual_id_index.insert((lhs, raw_id), x);
+    Ok(x)
+  }
+
+  // [Interp-API]
+  pub fn maybe_borrow_fun(&mut self, x: FunNum) -> Result<Option<Box<dyn Function>>, InterpCheck> {
+    match self.env.fun_full.get_mut(&x.into()) {
+      None => {
+        Ok(None)
+      }
+      Some(obj) => {
+        match obj._borrow() {
+          TransparentBox::Blk => {
+            Err(format!("Function is already borrowed: x = {x:?}").into())
+          }
+          TransparentBox::Ptr(val) => {
+            Ok(val.into())
+          }
         }
-        Some(entry) => {
-          if let Some(val) = entry.as_any().downcast_ref::<LitVal_>() {
-            vals.push((key, Val_::Lit(val.clone())));
-          } else {
-            _debugln!(self, "DEBUG: FastInterp::get_vals: currently only supporting LitVal_: query={:?} key={:?}", query, key);
+      }
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn borrow_fun(&mut self, x: FunNum) -> Result<Box<dyn Function>, InterpCheck> {
+    match self.env.fun_full.get_mut(&x.into()) {
+      None => {
+        Err(format!("failed to lookup Function: x = {x:?}").into())
+      }
+      Some(obj) => {
+        match obj._borrow() {
+          TransparentBox::Blk => {
+            Err(format!("Function is already borrowed: x = {x:?}").into())
+          }
+          TransparentBox::Ptr(val) => {
+            Ok(val)
           }
         }
       }
     }
-    Ok(vals)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  pub fn get_term<K: Into<SNum>>(&self, key: K) -> Result<Option<&dyn Tabled>, InterpCheck> {
-    let x = key.into();
-    match self.env.table_full[SNumSort::Term as usize].get(&x) {
-      Some(e) => {
-        Ok(Some(&*e.inner))
+  // [Interp-API]
+  //
+  // Borrows the Function `x` (see `borrow_fun`) behind a guard that
+  // unborrows it upon drop (see `FunBorrow`).
+  pub(crate) fn borrow_fun_guard(&mut self, x: FunNum) -> Result<FunBorrow<'_>, InterpCheck> {
+    let val = self.borrow_fun(x)?;
+    Ok(FunBorrow{interp: self, x, val: Some(val)})
+  }
+
+  // [Interp-API]
+  pub(crate) fn borrow_obj_cls(&mut self, x: ObjClsNum) -> Result<Box<dyn ObjCls>, InterpCheck> {
+    match self.env.obj_cls_full.get_mut(&x.into()) {
+      None => {
+        Err(format!("failed to lookup ObjCls: x = {x:?}").into())
+      }
+      Some(obj) => {
+        match obj._borrow() {
+          TransparentBox::Blk => {
+            Err(format!("ObjCls is already borrowed: x = {x:?}").into())
+          }
+          TransparentBox::Ptr(val) => {
+            Ok(val)
+          }
+        }
       }
-      None => Ok(None)
     }
   }
 
-  // FIXME: `V: Tabled` is too loose.
-  // [Interp-API]: This is part of the interpreter private API.
-  pub fn put_term<K: Into<SNum>, V: Tabled>(&mut self, clk: LClk, key: K, term: V) -> Result<(), InterpCheck> {
-    let x = key.into();
-    _traceln!(self, "DEBUG: FastInterp::put_term: clk={:?} x={:?} term={:?}", clk, x, term);
+  // [Interp-API]
+  pub(crate) fn unborrow_obj_cls(&mut self, x: ObjClsNum, val: Box<dyn ObjCls>) -> Result<(), InterpCheck> {
+    match self.env.obj_cls_full.get_mut(&x.into()) {
+      None => {
+        Err(format!("failed to lookup ObjCls: x = {x:?}").into())
+      }
+      Some(obj) => {
+        match obj._swap(val) {
+          TransparentBox::Ptr(_) => {
+            Err(format!("ObjCls was not already borrowed: x = {x:?}").into())
+          }
+          TransparentBox::Blk => {
+            Ok(())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn borrow_obj_val(&mut self, x: ObjValNum) -> Result<Box<dyn ObjVal>, InterpCheck> {
+    match self.env.obj_val_full.get_mut(&x.into()) {
+      None => {
+        Err(format!("failed to lookup ObjVal: x = {x:?}").into())
+      }
+      Some(obj) => {
+        match obj._borrow() {
+          TransparentBox::Blk => {
+            Err(format!("ObjVal is already borrowed: x = {x:?}").into())
+          }
+          TransparentBox::Ptr(val) => {
+            Ok(val)
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn unborrow_obj_val(&mut self, x: ObjValNum, val: Box<dyn ObjVal>) -> Result<(), InterpCheck> {
+    match self.env.obj_val_full.get_mut(&x.into()) {
+      None => {
+        Err(format!("failed to lookup ObjVal: x = {x:?}").into())
+      }
+      Some(obj) => {
+        match obj._swap(val) {
+          TransparentBox::Ptr(_) => {
+            Err(format!("ObjVal was not already borrowed: x = {x:?}").into())
+          }
+          TransparentBox::Blk => {
+            Ok(())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn unborrow_fun(&mut self, x: FunNum, val: Box<dyn Function>) -> Result<(), InterpCheck> {
+    match self.env.fun_full.get_mut(&x.into()) {
+      None => {
+        Err(format!("failed to lookup Function: x = {x:?}").into())
+      }
+      Some(obj) => {
+        match obj._swap(val) {
+          TransparentBox::Ptr(_) => {
+            Err(format!("Function was not already borrowed: x = {x:?}").into())
+          }
+          TransparentBox::Blk => {
+            Ok(())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _alloc_cell(&mut self, dptr: SNum) -> Result<CellNum, InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let x = self._fresh()?.into_cell();
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::AllocCell(x).into()));
+    let cel = Cell_{
+      dptr,
+      next: nil(),
+      prev: nil(),
+    };
     let e = TableEntry_{
       lastclk: clk,
-      inner: Box::new(term),
+      inner: Box::new(cel),
     };
-    self.env.table_full[SNumSort::Term as usize].insert(x, e);
-    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::PutTerm(x).into()));
-    Ok(())
+    self.env.table_full[SNumSort::Cell as usize].insert(x.into(), e);
+    Ok(x)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  pub fn get_val<K: Into<SNum>>(&self, key: K) -> Result<Option<&dyn Tabled>, InterpCheck> {
-    let x = key.into();
-    match self.env.table_full[SNumSort::Val as usize].get(&x) {
-      Some(e) => {
-        Ok(Some(&*e.inner))
+  // [Interp-API]
+  pub(crate) fn _link_cells(&mut self, lcel: CellNum, rcel: CellNum) -> Result<(), InterpCheck> {
+    // NB: shortcut case for the common pattern of building a tuple-cell
+    // from left-to-right.
+    if lcel.is_nil() {
+      return Ok(());
+    }
+    let olnext = match self.env.table_full[SNumSort::Cell as usize].get(&lcel.into())
+      .and_then(|y| y.as_any().downcast_ref::<Cell_>())
+    {
+      None => {
+        return Err("bug".into());
+      }
+      Some(lcel_) => lcel_.next.get()
+    };
+    let orprev = match self.env.table_full[SNumSort::Cell as usize].get(&rcel.into())
+      .and_then(|y| y.as_any().downcast_ref::<Cell_>())
+    {
+      None => {
+        return Err("bug".into());
+      }
+      Some(rcel_) => rcel_.prev.get()
+    };
+    let clk = self.clkctr._get_clock();
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LinkCells(lcel, olnext, rcel, orprev).into()));
+    // NB: the cell links are set through `get_mut` (rather than through
+    // the shared `Cell`s), so that a table chunk shared w/ a fork is
+    // copied before the write (see `fork`).
+    match self.env.table_full[SNumSort::Cell as usize].get_mut(&lcel.into())
+      .and_then(|y| y.as_any_mut().downcast_mut::<Cell_>())
+    {
+      None => return Err("bug".into()),
+      Some(lcel_) => lcel_.next.set(rcel)
+    }
+    match self.env.table_full[SNumSort::Cell as usize].get_mut(&rcel.into())
+      .and_then(|y| y.as_any_mut().downcast_mut::<Cell_>())
+    {
+      None => return Err("bug".into()),
+      Some(rcel_) => rcel_.prev.set(lcel)
+    }
+    Ok(())
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn lookup_mod_code(&self, x: ModCodeNum) -> Result<ModCode_, InterpCheck> {
+    match self.env.table_full[SNumSort::Code as usize].get(&x.into()) {
+      None => {
+        Err(format!("failed to lookup ModCode_: x = {x:?}").into())
+      }
+      Some(t) => {
+        match t.as_any().downcast_ref::<ModCode_>() {
+          None => {
+            Err(format!("lookup is not a ModCode_: x = {x:?}").into())
+          }
+          Some(code) => {
+            Ok(code.clone())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn lookup_stm_code(&self, x: StmCodeNum) -> Result<StmCode_, InterpCheck> {
+    match self.env.table_full[SNumSort::Code as usize].get(&x.into()) {
+      None => {
+        Err(format!("failed to lookup StmCode_: x = {x:?}").into())
+      }
+      Some(t) => {
+        match t.as_any().downcast_ref::<StmCode_>() {
+          None => {
+            Err(format!("lookup is not a StmCode_: x = {x:?}").into())
+          }
+          Some(code) => {
+            Ok(code.clone())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  //
+  // The name, head patterns, and body goals of a `defrule` stm (see
+  // `rule_index`).
+  #[track_caller]
+  pub fn lookup_rule(&self, x: StmCodeNum) -> Result<(IdentNum, Vec<TermCodeNum>, StmCodeCellNum), InterpCheck> {
+    match self.lookup_stm_code(x)? {
+      StmCode_::Defrule{id, head, body_stmp, ..} => Ok((id, head, body_stmp)),
+      _ => Err(format!("lookup is not a rule: x = {x:?}").into())
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  //
+  // The loaded `defrule` stms (see `rule_index`), in SNum order.
+  pub fn rules(&self) -> Vec<StmCodeNum> {
+    let mut xs: Vec<StmCodeNum> = self.env.rule_index.keys().cloned().collect();
+    xs.sort_by_key(|&x| SNum::from(x));
+    xs
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn lookup_stm_code_cell(&self, x: StmCodeCellNum) -> Result<Cell_, InterpCheck> {
+    match self.env.table_full[SNumSort::Cell as usize].get(&x.into()) {
+      None => {
+        Err(format!("failed to lookup cell: x = {x:?}").into())
+      }
+      Some(t) => {
+        match t.as_any().downcast_ref::<Cell_>() {
+          None => {
+            Err(format!("lookup is not a cell: x = {x:?}").into())
+          }
+          Some(cel_) => {
+            // FIXME: the deref of this cell must be a stm code.
+            /*if cel_.dptr ... {
+              return Err(format!("lookup is not a stm code cell: x = {x:?}").into());
+            }*/
+            Ok(cel_.clone())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn lookup_term_code(&self, x: TermCodeNum) -> Result<TermCode_, InterpCheck> {
+    match self.env.table_full[SNumSort::Code as usize].get(&x.into()) {
+      None => {
+        Err(format!("failed to lookup TermCode_: x = {x:?}").into())
+      }
+      Some(t) => {
+        match t.as_any().downcast_ref::<TermCode_>() {
+          None => {
+            Err(format!("lookup is not a TermCode_: x = {x:?}").into())
+          }
+          Some(code) => {
+            Ok(code.clone())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn lookup_term_code_cell(&self, x: TermCodeCellNum) -> Result<Cell_, InterpCheck> {
+    match self.env.table_full[SNumSort::Cell as usize].get(&x.into()) {
+      None => {
+        Err(format!("failed to lookup cell: x = {x:?}").into())
+      }
+      Some(t) => {
+        match t.as_any().downcast_ref::<Cell_>() {
+          None => {
+            Err(format!("lookup is not a cell: x = {x:?}").into())
+          }
+          Some(cel_) => {
+            // FIXME: the deref of this cell must be a term code.
+            /*if cel_.dptr ... {
+              return Err(format!("lookup is not a term code cell: x = {x:?}").into());
+            }*/
+            Ok(cel_.clone())
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn lookup_raw_ident(&self, id: IdentNum) -> Result<&RawIdent_, InterpCheck> {
+    match self.env.table_full[SNumSort::Ident as usize].get(&id.into()) {
+      None => {
+        Err(format!("failed to lookup raw ident: id = {id:?}").into())
+      }
+      Some(t) => {
+        match t.as_any().downcast_ref::<RawIdent_>() {
+          None => {
+            Err(format!("lookup is not a raw ident: id = {id:?}").into())
+          }
+          Some(raw_id) => {
+            Ok(raw_id)
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn lookup_raw_lit_str(&self, lit_str: LitStrNum) -> Result<&RawLit_, InterpCheck> {
+    match self.env.table_full[SNumSort::Litstr as usize].get(&lit_str.into()) {
+      None => {
+        Err(format!("failed to lookup raw literal: lit str = {lit_str:?}").into())
+      }
+      Some(t) => {
+        match t.as_any().downcast_ref::<RawLit_>() {
+          None => {
+            Err(format!("lookup is not a raw literal: lit str = {lit_str:?}").into())
+          }
+          Some(lit_) => {
+            Ok(lit_)
+          }
+        }
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn find<K: Into<SNum>>(&self, clk: LClk, query: K) -> Result<ENum, InterpCheck> {
+    self.env.unifier._find(&self.clkinval, clk, query.into()).map_err(|e| e.into())
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn canon(&self, clk: LClk, x: ENum) -> Result<ENum, InterpCheck> {
+    self.env.unifier.canon(&self.clkinval, clk, x).map_err(|e| e.into())
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  //
+  // Whether `lhs` and `rhs` are (now) in the same class.
+  pub fn same_cls(&self, clk: LClk, lhs: ENum, rhs: ENum) -> Result<bool, InterpCheck> {
+    let lhs = self.canon(clk, lhs)?;
+    let rhs = self.canon(clk, rhs)?;
+    Ok(lhs.ecls._check_eq(&rhs.ecls)?)
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn unify<LK: Into<SNum>, RK: Into<SNum>>(&mut self, clk: LClk, lquery: LK, rquery: RK) -> Result<SNum, InterpCheck> {
+    let (lhs, rhs) = (lquery.into(), rquery.into());
+    if let Some(prov) = self.prov.as_mut() {
+      prov._push(clk, explain::ProvKind_::Unify{lhs, rhs});
+    }
+    self._hook_unify(clk, lhs, rhs);
+    self._watch_unify(clk, lhs, rhs)?;
+    self.env.unifier._unify(&mut self.log, &self.clkinval, clk, lhs, rhs).map_err(|e| e.into())
+  }
+
+  // [Interp-API]
+  //
+  // Unifies two user terms, unless the unify mode rejects it (see
+  // `UnifyMode`), in which case this returns false and the caller fails.
+  // Every unification of user terms goes through here: unify goals, the
+  // binds `:=` and `=:` (incl. an apply head, e.g. `x := f(x)`), the
+  // captures of a match pattern, and the choice of an `in` item.
+  //
+  // NB: `unify` is unchecked; it is only for binding a fresh class (e.g.
+  // a result, or a lit), or the name of a def stm.
+  pub(crate) fn _unify_checked(&mut self, clk: LClk, lhs: SNum, rhs: SNum) -> Result<bool, InterpCheck> {
+    match self.unify_mode {
+      UnifyMode::Rational => {}
+      UnifyMode::OccursChecked |
+      UnifyMode::GroundOnly => {
+        let (lroot, rroot) = (self.find(clk, lhs)?.ecls, self.find(clk, rhs)?.ecls);
+        if lroot == rroot {
+          // NB: already unified; nothing to check.
+        } else if self.unify_mode == UnifyMode::GroundOnly &&
+            self._is_unbound(clk, lroot)? && self._is_unbound(clk, rroot)?
+        {
+          _debugln!(self, "DEBUG: FastInterp::_unify_checked: ground only: unbound lhs={:?} rhs={:?}", lhs, rhs);
+          return Ok(false);
+        } else if self._occurs(clk, lroot, rroot)? || self._occurs(clk, rroot, lroot)? {
+          _debugln!(self, "DEBUG: FastInterp::_unify_checked: occurs check: lhs={:?} rhs={:?}", lhs, rhs);
+          return Ok(false);
+        }
+      }
+    }
+    self.unify(clk, lhs, rhs)?;
+    Ok(true)
+  }
+
+  // [Interp-API]
+  //
+  // Whether the class `ecls` occurs inside a tuple of the class `query`
+  // (see `set_occurs_check`).
+  pub(crate) fn _occurs(&self, clk: LClk, ecls: SNum, query: SNum) -> Result<bool, InterpCheck> {
+    let mut seen = FxHashSet::default();
+    let mut work = vec![query];
+    while let Some(x) = work.pop() {
+      let keys = self.env.unifier._findall(&self.clkinval, clk, x).map_err(|e| e.into_check())?;
+      match keys.first() {
+        None => continue,
+        Some(key) if !seen.insert(key.ecls) => continue,
+        _ => {}
+      }
+      for key in keys.iter() {
+        let t = match self.get_term(key.inst)?.and_then(|t| t.as_any().downcast_ref::<TupleTerm_>()) {
+          None => continue,
+          Some(t) => t
+        };
+        for c in t.buf.iter() {
+          let c = self.find(clk, c.inst)?.ecls;
+          if c == ecls {
+            return Ok(true);
+          }
+          work.push(c);
+        }
+      }
+    }
+    Ok(false)
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  pub(crate) fn get_vals(&self, clk: LClk, query: ENum) -> Result<Vec<(ENum, Val_)>, InterpCheck> {
+    // NB: `query` may have been resolved before a later unification.
+    let query = self.canon(clk, query)?;
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query.inst).map_err(|e| e.into_check())?;
+    _debugln!(self, "DEBUG: FastInterp::get_vals: query={:?} keys={:?}", query, keys);
+    let mut vals = Vec::new();
+    for &key in keys.iter() {
+      if !key.ecls._check_eq(&query.ecls)? {
+        _debugln!(self, "DEBUG: FastInterp::get_vals: cls mismatch: query={:?} key={:?}", query, key);
+        return Err(bot());
+      }
+      // NB: invariant: table lookups go through the ENum _instance_.
+      match self.env.table_full[SNumSort::Val as usize].get(&key.inst) {
+        None => {
+          _debugln!(self, "DEBUG: FastInterp::get_vals: not a val: query={:?} key={:?}", query, key);
+        }
+        Some(entry) => {
+          if let Some(val) = entry.as_any().downcast_ref::<LitVal_>() {
+            vals.push((key, Val_::Lit(val.clone())));
+          } else {
+            _debugln!(self, "DEBUG: FastInterp::get_vals: currently only supporting LitVal_: query={:?} key={:?}", query, key);
+          }
+        }
+      }
+    }
+    Ok(vals)
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  pub(crate) fn get_term<K: Into<SNum>>(&self, key: K) -> Result<Option<&dyn Tabled>, InterpCheck> {
+    let x = key.into();
+    match self.env.table_full[SNumSort::Term as usize].get(&x) {
+      Some(e) => {
+        Ok(Some(&*e.inner))
+      }
+      None => Ok(None)
+    }
+  }
+
+  // FIXME: `V: Tabled` is too loose.
+  // [Interp-API]: This is part of the interpreter private API.
+  pub(crate) fn put_term<K: Into<SNum>, V: Tabled>(&mut self, clk: LClk, key: K, term: V) -> Result<(), InterpCheck> {
+    let x = key.into();
+    if self.strict {
+      x._check_sort(SNumSort::Term)?;
+    }
+    _traceln!(self, "DEBUG: FastInterp::put_term: clk={:?} x={:?} term={:?}", clk, x, term);
+    let first = _tuple_first(&term);
+    if let (Some(_), Some(prov)) = (first, self.prov.as_mut()) {
+      let premises = term.as_any().downcast_ref::<TupleTerm_>().unwrap().buf.to_vec();
+      prov._push(clk, explain::ProvKind_::Tuple{x, premises});
+    }
+    let e = TableEntry_{
+      lastclk: clk,
+      inner: Box::new(term),
+    };
+    self.mem_ctr.term += mem::_table_entry_bytes(&e);
+    self.env.table_full[SNumSort::Term as usize].insert(x, e);
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::PutTerm(x).into()));
+    if let Some(first) = first {
+      self.env._index_tuple(SNumSort::Term, first, x);
+      self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::IndexTuple(SNumSort::Term, first, x).into()));
+    }
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Takes the (cleared) scratch buffer; a nested apply (e.g. from within
+  // a function) that finds it taken allocates its own.
+  pub(crate) fn _take_scratch(&mut self) -> Vec<ENum> {
+    let mut buf = take(&mut self.scratch);
+    buf.clear();
+    buf
+  }
+
+  // [Interp-API]
+  pub(crate) fn _put_scratch(&mut self, buf: Vec<ENum>) {
+    if buf.capacity() > self.scratch.capacity() {
+      self.scratch = buf;
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Hash-conses a tuple term: a tuple whose children are in the same
+  // classes as those of an existing tuple is the existing tuple.
+  pub(crate) fn _cons_tuple(&mut self, clk: LClk, buf: TupleBuf_) -> Result<TermNum, InterpCheck> {
+    let mut key = Vec::with_capacity(buf.len());
+    for c in buf.iter() {
+      key.push(self.find(clk, c.inst)?.ecls);
+    }
+    let key: Box<[SNum]> = key.into();
+    let prev = self.env.tuple_cons.get(&key).copied();
+    if let Some(x) = prev {
+      let mut hit = false;
+      if let Some(t) = self.get_term(x)?.and_then(|t| t.as_any().downcast_ref::<TupleTerm_>()) {
+        if t.buf.len() == key.len() {
+          let cs: SmallVec<[SNum; TUPLE_INLINE_LEN]> = t.buf.iter().map(|c| c.inst).collect();
+          hit = true;
+          for (&c, &k) in cs.iter().zip(key.iter()) {
+            if !self.find(clk, c)?.ecls._check_eq(&k)? {
+              hit = false;
+              break;
+            }
+          }
+        }
+      }
+      if hit {
+        _traceln!(self, "DEBUG: FastInterp::_cons_tuple: clk={:?} hit x={:?}", clk, x);
+        return Ok(x);
+      }
+    }
+    let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+    self.put_term(clk, x, TupleTerm_{buf})?;
+    self.env.tuple_cons.insert(key.clone(), x);
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::ConsTuple(key, prev).into()));
+    Ok(x)
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  pub fn get_val<K: Into<SNum>>(&self, key: K) -> Result<Option<&dyn Tabled>, InterpCheck> {
+    let x = key.into();
+    match self.env.table_full[SNumSort::Val as usize].get(&x) {
+      Some(e) => {
+        Ok(Some(&*e.inner))
+      }
+      None => Ok(None)
+    }
+  }
+
+  // FIXME: `V: Tabled` is too loose.
+  // [Interp-API]: This is part of the interpreter private API.
+  pub(crate) fn put_val<K: Into<SNum>, V: Tabled>(&mut self, clk: LClk, key: K, val: V) -> Result<(), InterpCheck> {
+    let x = key.into();
+    if self.strict {
+      x._check_sort(SNumSort::Val)?;
+    }
+    self._watch_put_val(clk, x)?;
+    let e = TableEntry_{
+      lastclk: clk,
+      inner: Box::new(val),
+    };
+    self.mem_ctr.val += mem::_table_entry_bytes(&e);
+    self.env.table_full[SNumSort::Val as usize].insert(x, e);
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::PutVal(x).into()));
+    Ok(())
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn reset_res(&mut self) -> Result<(), InterpCheck> {
+    self.res_.reset();
+    Ok(())
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  pub(crate) fn put_mat_res(&mut self, v: bool) -> Result<(), InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let x = ResReg_::Mat(v);
+    match self.res_.put(clk, x) {
+      ResReg_::Emp => {
+        Ok(())
+      }
+      y => {
+        Err(format!("already filled result register: x = {:?} y = {:?}", x, y).into())
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  pub(crate) fn get_mat_res(&mut self) -> Result<bool, InterpCheck> {
+    match self.res_.get() {
+      ResReg_::Emp => {
+        Err(format!("expected result register").into())
+      }
+      ResReg_::Mat(v) => {
+        Ok(v)
+      }
+      y => {
+        Err(format!("unexpected result register: y = {:?}", y).into())
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn put_res<K: Into<SNum>>(&mut self, key: K) -> Result<(), InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let x = key.into();
+    let x = ResReg_::Key(x);
+    match self.res_.put(clk, x) {
+      ResReg_::Emp => {
+        Ok(())
+      }
+      y => {
+        Err(format!("already filled result register: x = {:?} y = {:?}", x, y).into())
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  #[track_caller]
+  pub(crate) fn get_res(&mut self) -> Result<SNum, InterpCheck> {
+    match self.res_.get() {
+      ResReg_::Emp => {
+        Err(format!("expected result register").into())
+      }
+      ResReg_::Key(x) => {
+        Ok(x)
+      }
+      y => {
+        Err(format!("unexpected result register: y = {:?}", y).into())
+      }
+    }
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  //
+  // Currently only used in the `Yield::Fail` case of `interp_`
+  // for cleaning up after failure.
+  //
+  // The returned redo entry holds whatever state was discarded by the
+  // undo, so that the entry may later be replayed by `_redo`.
+  pub(crate) fn undo(&mut self, clk: LClk, entry: UndoLogEntryRef) -> Result<RedoLogEntry_, InterpCheck> {
+    let data = match &*entry {
+      &UndoLogEntry_::Unify(ref state) => {
+        self.env.unifier._link(state.oroot, state.onext);
+        self.env.unifier._link(state.nprev, state.nroot);
+        self.env.unifier.root.remove(&state.nroot);
+        self.env.unifier.root.insert(state.oroot);
+        if let Some(otree) = state.otree {
+          self.env.unifier.tree.insert(state.oroot, otree);
+        } else {
+          self.env.unifier.tree.remove(&state.oroot);
+        }
+        RedoData_::Nil
+      }
+      /*&UndoLogEntry_::BindIdent(id, prev_x) => {
+        /*let raw_id = self.lookup_raw_ident(id)?.clone();*/
+        if prev_x.is_nil() {
+          self.env.id_bind.remove(&id);
+        } else {
+          self.env.id_bind.insert(id, prev_x.into());
+        }
+      }*/
+      &UndoLogEntry_::AllocCell(x) => {
+        match self.env.table_full[SNumSort::Cell as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: AllocCell x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => RedoData_::Table(e)
+        }
+      }
+      &UndoLogEntry_::LinkCells(lcel, olnext, rcel, orprev) => {
+        match self.env.table_full[SNumSort::Cell as usize].get_mut(&lcel.into())
+          .and_then(|y| y.as_any_mut().downcast_mut::<Cell_>())
+        {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LinkCells lcel={:?} nonexist", lcel);
+            return Err(bot());
+          }
+          Some(lcel_) => {
+            lcel_.next.set(olnext);
+          }
+        }
+        match self.env.table_full[SNumSort::Cell as usize].get_mut(&rcel.into())
+          .and_then(|y| y.as_any_mut().downcast_mut::<Cell_>())
+        {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LinkCells rcel={:?} nonexist", rcel);
+            return Err(bot());
+          }
+          Some(rcel_) => {
+            rcel_.prev.set(orprev);
+          }
+        }
+        RedoData_::Nil
+      }
+      &UndoLogEntry_::LoadFunction(x) => {
+        match self.env.fun_full.remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadFunction x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(f) => RedoData_::Fun(f)
+        }
+      }
+      &UndoLogEntry_::LoadObjCls(x) => {
+        match self.env.obj_cls_full.remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadObjCls x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(cls) => RedoData_::ObjCls(cls)
+        }
+      }
+      &UndoLogEntry_::LoadRawSpan(x) => {
+        let e = match self.env.table_full[SNumSort::Span as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadRawSpan x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => e
+        };
+        match e.inner.as_any().downcast_ref::<RawSpan_>() {
+          None => return Err(bot()),
+          Some(raw_span) => {
+            self.env.raw_span_index.remove(raw_span);
+          }
+        }
+        RedoData_::Table(e)
+      }
+      &UndoLogEntry_::LoadRawMod(x) => {
+        match self.env.table_full[SNumSort::Code as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadRawMod x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => RedoData_::Table(e)
+        }
+      }
+      &UndoLogEntry_::LoadRawStm(x) => {
+        let code = match self.env.table_full[SNumSort::Code as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadRawStm x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => e
+        };
+        // NB: a def stm also allocates its frame (see `_load_raw_stm`).
+        let frame = match self.env.frame_codex.remove(&x) {
+          None => None,
+          Some(frame) => {
+            let sup = self.env.frame_super.remove(&frame);
+            let e = self.env.table_full[SNumSort::Frame as usize].remove(&SNum::from(frame));
+            Some((frame, sup, e))
+          }
+        };
+        let rule = self.env.rule_index.remove(&x).is_some();
+        // NB: likewise, a defmatch stm indexes its clause.
+        let clause = match code.inner.as_any().downcast_ref::<StmCode_>() {
+          Some(&StmCode_::Defmatch{id, ref params, ..}) => {
+            let arity = params.len();
+            self._unindex_defmatch(id, arity, x).map(|key| (id, arity, key))
+          }
+          _ => None
+        };
+        RedoData_::Stm{code, frame, rule, clause}
+      }
+      &UndoLogEntry_::LoadRawTerm(x) => {
+        match self.env.table_full[SNumSort::Code as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadRawTerm x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => RedoData_::Table(e)
+        }
+      }
+      &UndoLogEntry_::LoadRawIdent(x) => {
+        let e = match self.env.table_full[SNumSort::Ident as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadRawIdent x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => e
+        };
+        match e.inner.as_any().downcast_ref::<RawIdent_>() {
+          None => return Err(bot()),
+          Some(raw_id) => {
+            let sym = self.env._intern(raw_id.as_raw_str());
+            self.env.raw_id_index.remove(&sym);
+          }
+        }
+        RedoData_::Table(e)
+      }
+      &UndoLogEntry_::LoadRawLitStr(x) => {
+        let e = match self.env.table_full[SNumSort::Litstr as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadRawLitStr x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => e
+        };
+        match e.inner.as_any().downcast_ref::<RawLit_>() {
+          None => return Err(bot()),
+          Some(raw_lit) => {
+            let sym = self.env._intern(raw_lit.as_raw_str());
+            self.env.raw_lit_index.remove(&sym);
+          }
+        }
+        RedoData_::Table(e)
+      }
+      &UndoLogEntry_::BindGlobalIdent(id, /*prev_x,*/ prev_global_x) => {
+        /*if prev_x.is_nil() {
+          self.env.id_bind.remove(&id);
+        } else {
+          self.env.id_bind.insert(id, prev_x.into());
+        }*/
+        let x = if prev_global_x.is_nil() {
+          self.env.id_global_bind.remove(&id)
+        } else {
+          self.env.id_global_bind.insert(id, prev_global_x.into())
+        };
+        RedoData_::Bind(x.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::BindBuiltinIdent(id, prev_x) => {
+        let x = if prev_x.is_nil() {
+          self.env.id_builtin_bind.remove(&id)
+        } else {
+          self.env.id_builtin_bind.insert(id, prev_x)
+        };
+        RedoData_::Bind(x.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::BindFunName(id, prev_x) => {
+        let x = if prev_x.is_nil() {
+          self.env.fun_name.remove(&id)
+        } else {
+          self.env.fun_name.insert(id, prev_x)
+        };
+        RedoData_::Bind(x.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::BindObjClsName(id, prev_x) => {
+        let x = if prev_x.is_nil() {
+          self.env.obj_cls_name.remove(&id)
+        } else {
+          self.env.obj_cls_name.insert(id, prev_x)
+        };
+        RedoData_::Bind(x.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::BindLocalIdent(scope, id, prev_x) => {
+        let ns = match self.env.ns_full.get_mut(&scope) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: BindLocalIdent scope={:?} id={:?} nonexist", scope, id);
+            return Err(bot());
+          }
+          Some(ns) => ns
+        };
+        let x = if prev_x.is_nil() {
+          ns.bind.remove(&id)
+        } else {
+          ns.bind.insert(id, prev_x)
+        };
+        RedoData_::Bind(x.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::DeclGlobalIdent(scope, id) => {
+        match self.env.ns_full.get_mut(&scope) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: DeclGlobalIdent scope={:?} id={:?} nonexist", scope, id);
+            return Err(bot());
+          }
+          Some(ns) => {
+            ns.global.remove(&id);
+          }
+        }
+        RedoData_::Nil
+      }
+      &UndoLogEntry_::DeclNonlocalIdent(scope, id) => {
+        match self.env.ns_full.get_mut(&scope) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: DeclNonlocalIdent scope={:?} id={:?} nonexist", scope, id);
+            return Err(bot());
+          }
+          Some(ns) => {
+            match ns.nonlocal.remove(&id) {
+              None => return Err(bot()),
+              Some(k) => RedoData_::Nonlocal(k)
+            }
+          }
+        }
+      }
+      &UndoLogEntry_::AllocNamespace(scope) => {
+        match self.env.ns_full.remove(&scope) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: AllocNamespace scope={:?} nonexist", scope);
+            return Err(bot());
+          }
+          Some(ns) => RedoData_::Namespace(ns)
+        }
+      }
+      &UndoLogEntry_::SwitchNamespace(prev_scope) => {
+        let scope = self.env.ns_cur;
+        self.env.ns_cur = prev_scope;
+        RedoData_::Bind(scope)
+      }
+      &UndoLogEntry_::LoadObjVal(x) => {
+        match self.env.obj_val_full.remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadObjVal x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(obj) => RedoData_::ObjVal(obj)
+        }
+      }
+      &UndoLogEntry_::LoadQualIdent(x, lhs) => {
+        let e = match self.env.table_full[SNumSort::Ident as usize].remove(&SNum::from(x)) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: LoadQualIdent x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => e
+        };
+        match e.inner.as_any().downcast_ref::<RawIdent_>() {
+          None => return Err(bot()),
+          Some(raw_id) => {
+            self.env.qual_id_index.remove(&(lhs, raw_id.clone()));
+          }
+        }
+        RedoData_::Table(e)
+      }
+      &UndoLogEntry_::RebindIdent(id, scope, prev_x) => {
+        let x = if scope.is_nil() {
+          if prev_x.is_nil() {
+            self.env.id_global_bind.remove(&id)
+          } else {
+            self.env.id_global_bind.insert(id, prev_x)
+          }
+        } else {
+          let bind = match self.env.ns_full.get_mut(&scope) {
+            None => {
+              _debugln!(self, "DEBUG: FastInterp::_undo: RebindIdent scope={:?} id={:?} nonexist", scope, id);
+              return Err(bot());
+            }
+            Some(ns) => &mut ns.bind
+          };
+          if prev_x.is_nil() {
+            bind.remove(&id)
+          } else {
+            bind.insert(id, prev_x)
+          }
+        };
+        RedoData_::Bind(x.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::BindLitTerm(ref term, prev_x) => {
+        let x = if prev_x.is_none() {
+          self.env.lit_term_bind.remove(&term)
+        } else {
+          // FIXME: unnecessary term.clone().
+          self.env.lit_term_bind.insert(term.clone(), prev_x.unwrap())
+        };
+        RedoData_::Bind(x.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::BindLitVal(ref val, prev_y) => {
+        let y = if prev_y.is_none() {
+          self.env.lit_val_bind.remove(&val)
+        } else {
+          // FIXME: unnecessary val.clone().
+          self.env.lit_val_bind.insert(val.clone(), prev_y.unwrap())
+        };
+        RedoData_::Bind(y.unwrap_or(nil()))
+      }
+      &UndoLogEntry_::PutTerm(x) => {
+        match self.env.table_full[SNumSort::Term as usize].remove(&x) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: PutTerm x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => {
+            self.mem_ctr.term -= mem::_table_entry_bytes(&e);
+            RedoData_::Table(e)
+          }
+        }
+      }
+      &UndoLogEntry_::PutVal(x) => {
+        match self.env.table_full[SNumSort::Val as usize].remove(&x) {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: PutVal x={:?} nonexist", x);
+            return Err(bot());
+          }
+          Some(e) => {
+            self.mem_ctr.val -= mem::_table_entry_bytes(&e);
+            RedoData_::Table(e)
+          }
+        }
+      }
+      &UndoLogEntry_::TrieInsert(obj, ref key, _, prev_y) => {
+        self._trie_restore(obj, key, prev_y)?;
+        RedoData_::Nil
+      }
+      &UndoLogEntry_::AttrInsert(obj, ref key, _) => {
+        self._attr_restore(obj, key, nil())?;
+        RedoData_::Nil
+      }
+      &UndoLogEntry_::IndexTuple(sort, first, x) => {
+        if !self.env._unindex_tuple(sort, first, x) {
+          _debugln!(self, "DEBUG: FastInterp::_undo: IndexTuple x={:?} nonexist", x);
+          return Err(bot());
+        }
+        RedoData_::Nil
+      }
+      &UndoLogEntry_::ConsTuple(ref key, prev) => {
+        let x = match prev {
+          None => self.env.tuple_cons.remove(key),
+          Some(prev) => self.env.tuple_cons.insert(key.clone(), prev)
+        };
+        match x {
+          None => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: ConsTuple nonexist");
+            return Err(bot());
+          }
+          Some(x) => RedoData_::Bind(x.into())
+        }
+      }
+      &UndoLogEntry_::GoalTableInsert(ref key) => {
+        if !self.tables._undo_insert(key) {
+          _debugln!(self, "DEBUG: FastInterp::_undo: GoalTableInsert nonexist");
+          return Err(bot());
+        }
+        // NB: a redo segment is not retained while any goal table is live
+        // (see `_restore_pos`).
+        RedoData_::Nil
+      }
+      &UndoLogEntry_::StreamLoad => {
+        match self.stream.as_mut() {
+          Some(stream) if stream.load_ct > 0 => {
+            stream.load_ct -= 1;
+          }
+          _ => {
+            _debugln!(self, "DEBUG: FastInterp::_undo: StreamLoad nonexist");
+            return Err(bot());
+          }
+        }
+        RedoData_::Nil
+      }
+    };
+    Ok(RedoLogEntry_{clk, undo: entry, data})
+  }
+
+  // [Interp-API]: This is part of the interpreter private API.
+  //
+  // Replays a redo entry (see `undo`) forward, and re-appends its
+  // original undo entry to the log.
+  pub(crate) fn _redo(&mut self, entry: RedoLogEntry_) -> Result<(), InterpCheck> {
+    let RedoLogEntry_{clk, undo, data} = entry;
+    match (&*undo, data) {
+      (&UndoLogEntry_::Unify(ref state), RedoData_::Nil) => {
+        self.env.unifier._redo(clk, state);
+      }
+      (&UndoLogEntry_::AllocCell(x), RedoData_::Table(e)) => {
+        self.env.table_full[SNumSort::Cell as usize].insert(x.into(), e);
+      }
+      (&UndoLogEntry_::LinkCells(lcel, _, rcel, _), RedoData_::Nil) => {
+        match self.env.table_full[SNumSort::Cell as usize].get_mut(&lcel.into())
+          .and_then(|y| y.as_any_mut().downcast_mut::<Cell_>())
+        {
+          None => return Err(bot()),
+          Some(lcel_) => {
+            lcel_.next.set(rcel);
+          }
+        }
+        match self.env.table_full[SNumSort::Cell as usize].get_mut(&rcel.into())
+          .and_then(|y| y.as_any_mut().downcast_mut::<Cell_>())
+        {
+          None => return Err(bot()),
+          Some(rcel_) => {
+            rcel_.prev.set(lcel);
+          }
+        }
+      }
+      (&UndoLogEntry_::LoadFunction(x), RedoData_::Fun(f)) => {
+        self.env.fun_full.insert(x.into(), f);
+      }
+      (&UndoLogEntry_::LoadObjCls(x), RedoData_::ObjCls(cls)) => {
+        self.env.obj_cls_full.insert(x.into(), cls);
+      }
+      (&UndoLogEntry_::LoadObjVal(x), RedoData_::ObjVal(obj)) => {
+        self.env.obj_val_full.insert(x.into(), obj);
+      }
+      (&UndoLogEntry_::LoadRawSpan(x), RedoData_::Table(e)) => {
+        match e.inner.as_any().downcast_ref::<RawSpan_>() {
+          None => return Err(bot()),
+          Some(raw_span) => {
+            self.env.raw_span_index.insert(raw_span.clone(), x);
+          }
+        }
+        self.env.table_full[SNumSort::Span as usize].insert(x.into(), e);
+      }
+      (&UndoLogEntry_::LoadRawMod(x), RedoData_::Table(e)) => {
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+      }
+      (&UndoLogEntry_::LoadRawStm(x), RedoData_::Stm{code, frame, rule, clause}) => {
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), code);
+        if let Some((frame, sup, e)) = frame {
+          self.env.frame_codex.insert(x, frame);
+          if let Some(sup) = sup {
+            self.env.frame_super.insert(frame, sup);
+          }
+          if let Some(e) = e {
+            self.env.table_full[SNumSort::Frame as usize].insert(frame.into(), e);
+          }
+        }
+        if rule {
+          self.env.rule_index.insert(x, ());
+        }
+        if let Some((id, arity, key)) = clause {
+          self._index_defmatch(id, arity, key, x);
+        }
+      }
+      (&UndoLogEntry_::LoadRawTerm(x), RedoData_::Table(e)) => {
+        self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+      }
+      (&UndoLogEntry_::LoadRawIdent(x), RedoData_::Table(e)) => {
+        match e.inner.as_any().downcast_ref::<RawIdent_>() {
+          None => return Err(bot()),
+          Some(raw_id) => {
+            let sym = self.env._intern(raw_id.as_raw_str());
+            self.env.raw_id_index.insert(sym, x);
+          }
+        }
+        self.env.table_full[SNumSort::Ident as usize].insert(x.into(), e);
+      }
+      (&UndoLogEntry_::LoadQualIdent(x, lhs), RedoData_::Table(e)) => {
+        match e.inner.as_any().downcast_ref::<RawIdent_>() {
+          None => return Err(bot()),
+          Some(raw_id) => {
+            self.env.qual_id_index.insert((lhs, raw_id.clone()), x);
+          }
+        }
+        self.env.table_full[SNumSort::Ident as usize].insert(x.into(), e);
+      }
+      (&UndoLogEntry_::LoadRawLitStr(x), RedoData_::Table(e)) => {
+        match e.inner.as_any().downcast_ref::<RawLit_>() {
+          None => return Err(bot()),
+          Some(raw_lit) => {
+            let sym = self.env._intern(raw_lit.as_raw_str());
+            self.env.raw_lit_index.insert(sym, x);
+          }
+        }
+        self.env.table_full[SNumSort::Litstr as usize].insert(x.into(), e);
+      }
+      (&UndoLogEntry_::BindGlobalIdent(id, _), RedoData_::Bind(x)) => {
+        self.env.id_global_bind.insert(id, x);
+      }
+      (&UndoLogEntry_::BindBuiltinIdent(id, _), RedoData_::Bind(x)) => {
+        self.env.id_builtin_bind.insert(id, x);
+      }
+      (&UndoLogEntry_::BindFunName(id, _), RedoData_::Bind(x)) => {
+        self.env.fun_name.insert(id, x);
+      }
+      (&UndoLogEntry_::BindObjClsName(id, _), RedoData_::Bind(x)) => {
+        self.env.obj_cls_name.insert(id, x);
+      }
+      (&UndoLogEntry_::BindLocalIdent(scope, id, _), RedoData_::Bind(x)) => {
+        match self.env.ns_full.get_mut(&scope) {
+          None => return Err(bot()),
+          Some(ns) => {
+            ns.bind.insert(id, x);
+          }
+        }
+      }
+      (&UndoLogEntry_::DeclGlobalIdent(scope, id), RedoData_::Nil) => {
+        match self.env.ns_full.get_mut(&scope) {
+          None => return Err(bot()),
+          Some(ns) => {
+            ns.global.insert(id);
+          }
+        }
+      }
+      (&UndoLogEntry_::DeclNonlocalIdent(scope, id), RedoData_::Nonlocal(k)) => {
+        match self.env.ns_full.get_mut(&scope) {
+          None => return Err(bot()),
+          Some(ns) => {
+            ns.nonlocal.insert(id, k);
+          }
+        }
+      }
+      (&UndoLogEntry_::AllocNamespace(scope), RedoData_::Namespace(ns)) => {
+        self.env.ns_full.insert(scope, ns);
+      }
+      (&UndoLogEntry_::SwitchNamespace(_), RedoData_::Bind(scope)) => {
+        self.env.ns_cur = scope;
+      }
+      (&UndoLogEntry_::RebindIdent(id, scope, _), RedoData_::Bind(x)) => {
+        if scope.is_nil() {
+          self.env.id_global_bind.insert(id, x);
+        } else {
+          match self.env.ns_full.get_mut(&scope) {
+            None => return Err(bot()),
+            Some(ns) => {
+              ns.bind.insert(id, x);
+            }
+          }
+        }
+      }
+      (&UndoLogEntry_::BindLitTerm(ref term, _), RedoData_::Bind(x)) => {
+        self.env.lit_term_bind.insert(term.clone(), x);
+      }
+      (&UndoLogEntry_::BindLitVal(ref val, _), RedoData_::Bind(y)) => {
+        self.env.lit_val_bind.insert(val.clone(), y);
+      }
+      (&UndoLogEntry_::PutTerm(x), RedoData_::Table(e)) => {
+        self.mem_ctr.term += mem::_table_entry_bytes(&e);
+        self.env.table_full[SNumSort::Term as usize].insert(x, e);
+      }
+      (&UndoLogEntry_::PutVal(x), RedoData_::Table(e)) => {
+        self.mem_ctr.val += mem::_table_entry_bytes(&e);
+        self.env.table_full[SNumSort::Val as usize].insert(x, e);
+      }
+      (&UndoLogEntry_::TrieInsert(obj, ref key, y, _), RedoData_::Nil) => {
+        self._trie_restore(obj, key, y)?;
+      }
+      (&UndoLogEntry_::AttrInsert(obj, ref key, y), RedoData_::Nil) => {
+        self._attr_restore(obj, key, y)?;
+      }
+      (&UndoLogEntry_::IndexTuple(sort, first, x), RedoData_::Nil) => {
+        self.env._index_tuple(sort, first, x);
+      }
+      (&UndoLogEntry_::ConsTuple(ref key, _), RedoData_::Bind(x)) => {
+        self.env.tuple_cons.insert(key.clone(), x.try_into_term().map_err(|e| e.into_check())?);
+      }
+      (&UndoLogEntry_::StreamLoad, RedoData_::Nil) => {
+        match self.stream.as_mut() {
+          Some(stream) => {
+            stream.load_ct += 1;
+          }
+          None => return Err(bot())
+        }
+      }
+      (e, _) => {
+        _debugln!(self, "DEBUG: FastInterp::_redo: mismatched redo data: e={:?}", e);
+        return Err(bot());
       }
-      None => Ok(None)
     }
+    self.log._append(clk, LogEntryRef_::Undo(undo));
+    Ok(())
   }
 
-  // FIXME: `V: Tabled` is too loose.
-  // [Interp-API]: This is part of the interpreter private API.
-  pub fn put_val<K: Into<SNum>, V: Tabled>(&mut self, clk: LClk, key: K, val: V) -> Result<(), InterpCheck> {
-    let x = key.into();
-    let e = TableEntry_{
-      lastclk: clk,
-      inner: Box::new(val),
+  // [Interp-API]
+  //
+  // Garbage collection (see `gc`) is conservative: only entries of the
+  // runtime sorts (terms, vals, and cells) are collected, and only if
+  // they are older than every live continuation and choice point.
+  fn _gc_live_clk(&self) -> Option<LClk> {
+    let mut live_clk: Option<LClk> = None;
+    let mut bound = |clk: LClk| {
+      live_clk = Some(live_clk.map_or(clk, |c| min(c, clk)));
     };
-    self.env.table_full[SNumSort::Val as usize].insert(x, e);
-    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::PutVal(x).into()));
-    Ok(())
+    let mut knt = self.knt_.as_ref();
+    while let Some(k) = knt {
+      bound(k.clk);
+      knt = k.prev.as_ref();
+    }
+    for e in self.trace.buf.iter() {
+      bound(e.root_clk);
+      let mut knt = e.knt_.as_ref();
+      while let Some(k) = knt {
+        bound(k.clk);
+        knt = k.prev.as_ref();
+      }
+    }
+    for e in self.eval.buf.values() {
+      let mut knt = e.knt_.as_ref();
+      while let Some(k) = knt {
+        bound(k.clk);
+        knt = k.prev.as_ref();
+      }
+    }
+    live_clk
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn reset_res(&mut self) -> Result<(), InterpCheck> {
-    self.res_.reset();
-    Ok(())
+  // [Interp-API]
+  //
+  // The memory estimate (in bytes) of the gc threshold, i.e. the total
+  // of `memory_report`.
+  pub fn _gc_mem_estimate(&self) -> usize {
+    self.memory_report().total
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  pub fn put_mat_res(&mut self, v: bool) -> Result<(), InterpCheck> {
-    let clk = self.clkctr._get_clock();
-    let x = ResReg_::Mat(v);
-    match self.res_.put(clk, x) {
-      ResReg_::Emp => {
-        Ok(())
-      }
-      y => {
-        Err(format!("already filled result register: x = {:?} y = {:?}", x, y).into())
+  // [Interp-API]
+  //
+  // Runs a gc pass:
+  // - the log is compacted below the lowest live choice point (those
+  //   entries can never be undone);
+  // - the redo log is dropped;
+  // - unreachable term and val entries are dropped, together with their
+  //   unifier equivalence classes.
+  //
+  // NB: cells are never dropped, since the bodies of the loaded code (in
+  // the code table, which is not collected) are cell lists; so, every
+  // cell is a root.
+  pub(crate) fn gc(&mut self) -> Result<GcReport_, InterpCheck> {
+    let mut report = GcReport_::default();
+
+    // Log compaction.
+    let log_lb = self.trace.buf.iter().map(|e| e.root_clk).min();
+    report.log = self.log._compact(log_lb);
+    report.redo = self.redo._clear();
+    report.clkinval = self.compact_clkinval();
+
+    // Mark.
+    let live_clk = self._gc_live_clk();
+    const GC_SORTS: [SNumSort; 2] = [SNumSort::Term, SNumSort::Val];
+    let mut cand: FxHashSet<SNum> = FxHashSet::default();
+    let mut work: Vec<SNum> = Vec::new();
+    for &sort in GC_SORTS.iter() {
+      for (&x, e) in self.env.table_full[sort as usize].iter() {
+        if live_clk.map_or(true, |c| e.lastclk < c) {
+          cand.insert(x);
+        }
       }
     }
-  }
-
-  // [Interp-API]: This is part of the interpreter private API.
-  pub fn get_mat_res(&mut self) -> Result<bool, InterpCheck> {
-    match self.res_.get() {
-      ResReg_::Emp => {
-        Err(format!("expected result register").into())
+    // NB: an SNum w/ a live entry in any of the sorts is a root.
+    for &sort in GC_SORTS.iter() {
+      for (&x, e) in self.env.table_full[sort as usize].iter() {
+        if !live_clk.map_or(true, |c| e.lastclk < c) {
+          cand.remove(&x);
+          work.push(x);
+        }
       }
-      ResReg_::Mat(v) => {
-        Ok(v)
+    }
+    for &x in self.env.unifier.next.keys() {
+      if !cand.contains(&x) {
+        work.push(x);
       }
-      y => {
-        Err(format!("unexpected result register: y = {:?}", y).into())
+    }
+    work.extend(self.env.id_global_bind.values());
+    work.extend(self.env.id_builtin_bind.values());
+    work.extend(self.env.lit_term_bind.values());
+    work.extend(self.env.lit_val_bind.values());
+    for ns in self.env.ns_full.values() {
+      work.extend(ns.bind.values());
+    }
+    work.extend(self.env.obj_val_full.keys());
+    for e in self.env.table_full[SNumSort::Cell as usize].values() {
+      _gc_table_refs(e, &mut work);
+    }
+    if let ResReg_::Key(x) = self.res_.reg {
+      work.push(x);
+    }
+    _gc_knt_chain_refs(&self.knt_, &mut work);
+    for e in self.trace.buf.iter() {
+      if let ResReg_::Key(x) = e.ctl_.res_.reg {
+        work.push(x);
       }
+      _gc_knt_chain_refs(&e.knt_, &mut work);
     }
-  }
-
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn put_res<K: Into<SNum>>(&mut self, key: K) -> Result<(), InterpCheck> {
-    let clk = self.clkctr._get_clock();
-    let x = key.into();
-    let x = ResReg_::Key(x);
-    match self.res_.put(clk, x) {
-      ResReg_::Emp => {
-        Ok(())
+    for e in self.eval.buf.values() {
+      work.push(e.term);
+      work.push(e.ret);
+      _gc_knt_chain_refs(&e.knt_, &mut work);
+    }
+    for e in self.log.buf.iter() {
+      match &e.val {
+        &LogEntryRef_::Undo(ref e) => _gc_undo_refs(e, &mut work),
       }
-      y => {
-        Err(format!("already filled result register: x = {:?} y = {:?}", x, y).into())
+    }
+    let mut mark: FxHashSet<SNum> = FxHashSet::default();
+    while let Some(x) = work.pop() {
+      if x.is_nil() || !mark.insert(x) {
+        continue;
+      }
+      for &sort in GC_SORTS.iter() {
+        if let Some(e) = self.env.table_full[sort as usize].get(&x) {
+          _gc_table_refs(e, &mut work);
+        }
       }
+      self.env.unifier._gc_eclass(x, &mut work);
     }
-  }
 
-  // [Interp-API]: This is part of the interpreter private API.
-  #[track_caller]
-  pub fn get_res(&mut self) -> Result<SNum, InterpCheck> {
-    match self.res_.get() {
-      ResReg_::Emp => {
-        Err(format!("expected result register").into())
+    // Sweep.
+    for &x in cand.iter() {
+      if mark.contains(&x) {
+        continue;
       }
-      ResReg_::Key(x) => {
-        Ok(x)
+      for &sort in GC_SORTS.iter() {
+        if let Some(e) = self.env.table_full[sort as usize].remove(&x) {
+          if let Some(first) = _tuple_first(&*e.inner) {
+            self.env._unindex_tuple(sort, first, x);
+          }
+          self.mem_ctr._sub(sort, mem::_table_entry_bytes(&e));
+          report.table += 1;
+        }
       }
-      y => {
-        Err(format!("unexpected result register: y = {:?}", y).into())
+      if self.env.unifier._gc_remove(x) {
+        report.unifier += 1;
       }
     }
+    _debugln!(self, "DEBUG: FastInterp::gc: live clk={:?} report={:?}", live_clk, report);
+    Ok(report)
   }
 
-  // [Interp-API]: This is part of the interpreter private API.
+  // [Interp-API-Pub]
   //
-  // Currently only used in the `Yield::Fail` case of `interp_`
-  // for cleaning up after failure.
-  pub fn undo(&mut self, clk: LClk, entry: UndoLogEntryRef) -> Result<(), InterpCheck> {
-    match &*entry {
-      &UndoLogEntry_::Unify(ref state) => {
-        self.env.unifier._link(state.oroot, state.onext);
-        self.env.unifier._link(state.nprev, state.nroot);
-        self.env.unifier.root.remove(&state.nroot);
-        self.env.unifier.root.insert(state.oroot);
-        if let Some(otree) = state.otree {
-          self.env.unifier.tree.insert(state.oroot, otree);
-        } else {
-          self.env.unifier.tree.remove(&state.oroot);
-        }
-      }
-      /*&UndoLogEntry_::BindIdent(id, prev_x) => {
-        /*let raw_id = self.lookup_raw_ident(id)?.clone();*/
-        if prev_x.is_nil() {
-          self.env.id_bind.remove(&id);
-        } else {
-          self.env.id_bind.insert(id, prev_x.into());
-        }
-      }*/
-      &UndoLogEntry_::BindGlobalIdent(id, /*prev_x,*/ prev_global_x) => {
-        /*if prev_x.is_nil() {
-          self.env.id_bind.remove(&id);
-        } else {
-          self.env.id_bind.insert(id, prev_x.into());
-        }*/
-        if prev_global_x.is_nil() {
-          self.env.id_global_bind.remove(&id);
-        } else {
-          self.env.id_global_bind.insert(id, prev_global_x.into());
-        }
-      }
-      &UndoLogEntry_::BindLitVal(ref val, prev_y) => {
-        if prev_y.is_none() {
-          self.env.lit_val_bind.remove(&val);
-        } else {
-          // FIXME: unnecessary val.clone().
-          self.env.lit_val_bind.insert(val.clone(), prev_y.unwrap());
-        }
-      }
-      &UndoLogEntry_::PutTerm(x) => {
-        if self.env.table_full[SNumSort::Term as usize].remove(&x).is_none() {
-          _debugln!(self, "DEBUG: FastInterp::_undo: PutTerm x={:?} nonexist", x);
-          return Err(bot());
-        }
-      }
-      &UndoLogEntry_::PutVal(x) => {
-        if self.env.table_full[SNumSort::Val as usize].remove(&x).is_none() {
-          _debugln!(self, "DEBUG: FastInterp::_undo: PutVal x={:?} nonexist", x);
-          return Err(bot());
+  // Compacts the clock-invalid set below the lowest live choice point
+  // (or else below the next clock); returns the number of ranges dropped.
+  // The unifier path cache entries through the dropped clocks are
+  // evicted first, since their clocks would otherwise become valid.
+  pub fn compact_clkinval(&mut self) -> usize {
+    let live_lb = self.trace.buf.iter().map(|e| e.root_clk).min()
+      .unwrap_or_else(|| self.clkctr._next_clock());
+    let clkinval = &self.clkinval;
+    self.env.unifier.cache.borrow_mut().retain(|_, &mut (up_clk, _)| {
+      !(up_clk < live_lb && clkinval._contains(up_clk))
+    });
+    self.redo._compact(live_lb);
+    self.clkinval._compact(live_lb)
+  }
+
+  // [Interp-API-Pub]
+  pub fn clkinval_stats(&self) -> LClkInvalidStats {
+    self.clkinval._stats()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The unifier, e.g. to iterate over its classes (see
+  // `FastUnifier_::iter_roots` and `iter_class`).
+  pub fn unifier(&self) -> &FastUnifier_ {
+    &self.env.unifier
+  }
+
+  // [Interp-API-Pub]
+  pub fn unifier_stats(&self) -> UnifierStats {
+    self.env.unifier.stats()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // A flattened view (see `flatten_`) of the env as it was at the clock
+  // `clk`. The view is taken from a fork (see `fork`), on which the log
+  // entries after `clk` are undone; this interpreter is not disturbed.
+  //
+  // Only the clocks of the current history can be viewed: the entries
+  // of a clock that was since backtracked over are no longer in the log
+  // (see `_restore_pos`).
+  pub fn view_at(&self, clk: LClk) -> Result<FlatInterp, InterpCheck> {
+    let now = self.clkctr._get_clock();
+    if clk > now {
+      return Err(format!("view_at: future clk: clk = {:?} now = {:?}", clk, now).into());
+    }
+    if self.clkinval._contains(clk) {
+      return Err(format!("view_at: backtracked clk: clk = {:?}", clk).into());
+    }
+    let mut interp = self.fork();
+    while interp.log.buf.last().map(|e| e.clk > clk).unwrap_or(false) {
+      let e = interp.log._pop().unwrap();
+      match e.val {
+        LogEntryRef_::Undo(u) => {
+          let _ = interp.undo(e.clk, u)?;
         }
       }
-      e => return Err(format!("_undo: unimpl: clk={:?} e={:?}", clk, e).into())
     }
-    Ok(())
+    if clk < now {
+      // NB: as upon backtracking, the undone clocks are invalidated.
+      interp.clkinval.invalidate_from(LClk(clk.0 + 1), interp.clkctr._next_clock())
+        .map_err(|_| bot::<InterpCheck>())?;
+    }
+    _debugln!(self, "DEBUG: FastInterp::view_at: clk={:?} log.len={} (now={:?} log.len={})",
+        clk, interp.log.buf.len(), now, self.log.buf.len());
+    let mut flat = interp.flatten_();
+    flat.clk = clk;
+    Ok(flat)
   }
 
   // FIXME: separate flat interp is annoying to maintain...
-  // [Deprecated/Interp-API-Pub]
-  /*pub fn flatten_(&self) -> FlatInterp {
+  // [Interp-API-Pub]
+  pub fn flatten_(&self) -> FlatInterp {
     let clk = self.clkctr._get_clock();
     let mut interp = FlatInterp{
+      attest_ct: 0,
       clk,
       env:  FlatEnv::default(),
     };
-    // FIXME: sort flattened env sections (table_full is FxHashMap).
     for (&prim_key, entry) in self.env.table_full[SNumSort::Code as usize].iter() {
       let flat_val = if let Some(ref code) = entry.as_any().downcast_ref::<ModCode_>() {
         FlatTabled_::ModCode
@@ -3774,7 +8271,7 @@ impl FastInterp {
           }
         }
       } else if let Some(ref t) = entry.as_any().downcast_ref::<TupleTerm_>() {
-        FlatTabled_::TupleTerm{buf: t.buf.clone()}
+        FlatTabled_::TupleTerm{buf: t.buf.to_vec().into()}
       } else if let Some(_) = entry.as_any().downcast_ref::<ModCode_>() {
         FlatTabled_::ModCode
       } else if let Some(_) = entry.as_any().downcast_ref::<StmCode_>() {
@@ -3788,10 +8285,10 @@ impl FastInterp {
     }
     // FIXME: flatten is missing lots of state.
     interp
-  }*/
+  }
 
   // [Interp-API]
-  pub fn write_snapshot(&self) -> () {
+  pub(crate) fn write_snapshot(&self) -> () {
     if let Some(snapshot) = self.snapshot.borrow_mut().as_mut() {
       writeln!(snapshot, "{{").unwrap();
 
@@ -3925,6 +8422,455 @@ impl FastInterp {
     }
   }
 
+  // [Interp-API]
+  //
+  // Flattens the interpreter state into an aikido object tree. Unordered
+  // (hashed) sections are keyed per entry, so that the resulting content
+  // hash is deterministic, and so that unchanged entries are shared
+  // between snapshots in the store.
+  pub(crate) fn _aikido_object(&self) -> Result<Object, InterpCheck> {
+    fn _key<K: Serialize>(k: &K) -> String {
+      match serde_json::to_value(k).unwrap() {
+        serde_json::Value::String(s) => s,
+        v => v.to_string()
+      }
+    }
+    #[derive(Serialize)]
+    struct _TraceSummary {
+      root_clk: LClk,
+      last_clk: LClk,
+      xctr: RawChoiceRank,
+      xlim: RawChoiceRank,
+      xlb:  SNum,
+    }
+    let json_format = JsonFormat::new()
+        .ascii(true)
+        .comma(", ").unwrap()
+        .colon(": ").unwrap()
+    ;
+    let clk = self.clkctr._get_clock();
+    let mut obj = Object::tree();
+    obj.tree_insert_str("src", self.src.as_ref().map(|s| s.as_str()).unwrap_or(""));
+    obj.tree_insert_str("clk", &format!("{}", clk._into_raw()));
+    obj.tree_insert_str("ctr", &json_format.to_string(&self.ctr).unwrap());
+    obj.tree_insert_str("reg", &json_format.to_string(&self.reg).unwrap());
+    obj.tree_insert_str("exc_", &json_format.to_string(&self.exc_).unwrap());
+    obj.tree_insert_str("res_", &json_format.to_string(&self.res_).unwrap());
+    obj.tree_insert_str("port", &json_format.to_string(&self.port).unwrap());
+    obj.tree_insert_str("recipe", &json_format.to_string(&self._recipe()).unwrap());
+    let mut env = Object::tree();
+    let mut fun_name = Object::tree();
+    for (key, item) in self.env.fun_name.iter() {
+      fun_name.tree_insert_str(&_key(key), &json_format.to_string(item).unwrap());
+    }
+    env.tree_insert("fun_name", fun_name);
+    let mut table_full = Object::tree();
+    for (sort_rank, sort_tab) in self.env.table_full.iter().enumerate() {
+      if sort_tab.is_empty() {
+        continue;
+      }
+      let sort = SNumSort::try_from(sort_rank as RawSNum).unwrap();
+      let mut tab = Object::tree();
+      for (key, item) in sort_tab.iter() {
+        tab.tree_insert_str(&_key(key), &item._tap_snapshot().unwrap_or_else(|| "null".into()));
+      }
+      table_full.tree_insert(&_key(&sort), tab);
+    }
+    env.tree_insert("table_full", table_full);
+    let mut frame_super = Object::tree();
+    for (key, item) in self.env.frame_super.iter() {
+      frame_super.tree_insert_str(&_key(key), &json_format.to_string(item).unwrap());
+    }
+    env.tree_insert("frame_super", frame_super);
+    let mut frame_codex = Object::tree();
+    for (key, item) in self.env.frame_codex.iter() {
+      frame_codex.tree_insert_str(&_key(key), &json_format.to_string(item).unwrap());
+    }
+    env.tree_insert("frame_codex", frame_codex);
+    let mut unifier = Object::tree();
+    for &x in self.env.unifier.root.iter() {
+      let xs = self.env.unifier._findall(&self.clkinval, clk, x)?;
+      unifier.tree_insert_str(&_key(&x), &json_format.to_string(&xs).unwrap());
+    }
+    env.tree_insert("unifier", unifier);
+    obj.tree_insert("env", env);
+    let mut trace = Vec::with_capacity(self.trace.buf.len());
+    for e in self.trace.buf.iter() {
+      trace.push(_TraceSummary{
+        root_clk: e.root_clk,
+        last_clk: e.last_clk.get(),
+        xctr: e.xctr,
+        xlim: e.xlim,
+        xlb:  e.xlb,
+      });
+    }
+    obj.tree_insert_str("trace", &json_format.to_string(&trace).unwrap());
+    obj.tree_insert_str("clkinval", &json_format.to_string(&self.clkinval).unwrap());
+    Ok(obj)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Commits the current interpreter state as an aikido snapshot on the
+  // given frame. Identical states (e.g. across runs) hash identically,
+  // in which case no new snapshot is committed.
+  pub fn commit_to(&self, frame: &mut Frame, store: &mut Store) -> Result<SnapshotHash, InterpCheck> {
+    let obj = self._aikido_object()?;
+    let _ = replace(frame.modify().mut_data(), obj);
+    frame.commit(store).map_err(|e| format!("commit_to: {}", e))?;
+    Ok(frame.snapshot().clone())
+  }
+
+  // [Interp-API]
+  pub(crate) fn _recipe(&self) -> InterpRecipe_ {
+    InterpRecipe_{
+      seed:     self.seed(),
+      unify_mode: self.unify_mode,
+      strict:   self.strict,
+      match_commit: self.match_commit,
+      heuristic: self.trace.heuristic,
+      weight_order: self.trace.weight_order,
+      step_lim: self.step_lim,
+      depth_lim: self.depth_lim,
+      tabled:   self.tables._procs().map(|name| name.to_string()).collect(),
+      parser_mode: self.parser_mode,
+      load:     self.load.clone(),
+    }
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Restores an interpreter from an aikido snapshot that was committed
+  // by `commit_to`. The interpreter is rebuilt per the saved config,
+  // loads the saved sources as they were loaded (after `pre_init`), and
+  // is then deterministically replayed up to the saved clock; the
+  // replayed state is then checked against the snapshot content hash.
+  pub fn restore_from(store: &Store, hash: SnapshotHash) -> Result<FastInterp, InterpCheck> {
+    let frame = Frame::checkout(store, hash);
+    let mut data = frame.view().data().clone();
+    let src = match data.tree_get("src").and_then(|o| o.as_str()) {
+      None => return Err("restore: missing src".into()),
+      Some(s) => s.to_owned()
+    };
+    let clk = match data.tree_get("clk").and_then(|o| o.as_str()) {
+      None => return Err("restore: missing clk".into()),
+      Some(s) => LClk(s.parse().map_err(|_| format!("restore: invalid clk: {:?}", s))?)
+    };
+    let recipe: InterpRecipe_ = match data.tree_get("recipe").and_then(|o| o.as_str()) {
+      None => return Err("restore: missing recipe".into()),
+      Some(s) => serde_json::from_str(s).map_err(|e| format!("restore: invalid recipe: {:?}", e))?
+    };
+    let mut interp = FastInterp::default();
+    interp.set_seed(recipe.seed);
+    interp.set_unify_mode(recipe.unify_mode);
+    interp.set_strict(recipe.strict);
+    interp.set_match_commit(recipe.match_commit);
+    interp.set_choice_heuristic(recipe.heuristic);
+    interp.set_weight_order(recipe.weight_order);
+    interp.set_depth_limit(recipe.depth_lim);
+    interp.set_parser_mode(recipe.parser_mode);
+    for name in recipe.tabled.iter() {
+      interp.set_tabled(name, true);
+    }
+    interp.pre_init()?;
+    match &recipe.load {
+      &LoadRecipe_::Nil => {
+        interp.cold_start(&src)?;
+      }
+      &LoadRecipe_::Module(ref srcs) => {
+        let srcs: Vec<_> = srcs.iter().map(|&(ref path, ref text)| (path.as_deref(), text.as_str())).collect();
+        interp._cold_start(&srcs)?;
+      }
+      &LoadRecipe_::Many(ref mods) => {
+        let mods: Vec<_> = mods.iter().map(|&(ref name, ref text)| (name.as_str(), text.as_str())).collect();
+        interp.load_many(&mods)?;
+      }
+    }
+    // NB: the step limit (which a pragma may also set) is only restored
+    // after the replay, which it would otherwise interrupt.
+    interp.set_step_limit(None);
+    interp.brk_clk = Some(clk);
+    let _ = interp.interp_()?;
+    interp.brk_clk = None;
+    interp.set_step_limit(recipe.step_lim);
+    if interp._aikido_object()?.content_hash() != data.content_hash() {
+      return Err("restore: replayed state does not match snapshot".into());
+    }
+    Ok(interp)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Forks the interpreter. The env tables and the unifier maps are
+  // copy-on-write (see `algo::cow`), so the fork initially shares them
+  // w/ this interpreter, and either side only copies the chunks that it
+  // later writes to.
+  //
+  // The fork starts w/ an empty redo log and a cold unifier cache, does
+  // not inherit the snapshot writer, the oracle bridge, the event hooks,
+  // or the run journal, and writes TAP output to stdout (or else to the
+  // log sink). The fork does keep the trace positions of the submitted
+  // oracle requests, so that a fork that is adopted (see `_adopt`) may
+  // still cancel them.
+  pub fn fork(&self) -> FastInterp {
+    let tap = TAPOutput::stdout();
+    if let Some(writer) = self.logw.as_ref() {
+      let _ = tap.set_log_writer(writer.clone());
+    }
+    FastInterp{
+      clkctr:   self.clkctr.clone(),
+      ctr:      self.ctr.clone(),
+      reg:      self.reg,
+      exc_:     self.exc_.clone(),
+      res_:     self.res_.clone(),
+      port:     self.port,
+      knt_:     self.knt_.clone(),
+      env:      self.env.clone(),
+      clkinval: self.clkinval.clone(),
+      log:      self.log.clone(),
+      redo:     self.redo._fork(),
+      trace:    self.trace.clone(),
+      mem_lim:  self.mem_lim,
+      mem_hwm:  self.mem_hwm,
+      mem_brk:  false,
+      mem_ctr:  self.mem_ctr.clone(),
+      mem_gc_floor: 0,
+      prelude_ext: self.prelude_ext.clone(),
+      builtins: self.builtins.clone(),
+      pv_cache: self.pv_cache.clone(),
+      tables:   self.tables.clone(),
+      prov:     self.prov.clone(),
+      mcts:     self.mcts.clone(),
+      hooks:    None,
+      scratch:  Vec::new(),
+      rng:      self.rng.clone(),
+      src:      self.src.clone(),
+      src_enc:  self.src_enc.clone(),
+      srcmap:   self.srcmap.clone(),
+      load:     self.load.clone(),
+      brk_clk:  self.brk_clk,
+      brk:      self.brk.clone(),
+      stream:   self.stream.clone(),
+      test_fail: self.test_fail.clone(),
+      step_lim: self.step_lim,
+      time_lim: self.time_lim,
+      depth_lim: self.depth_lim,
+      strict:   self.strict,
+      unify_mode: self.unify_mode,
+      match_commit: self.match_commit,
+      #[cfg(feature = "pyo3")]
+      oracle:   None,
+      #[cfg(feature = "pyo3")]
+      oracle_pos: self.oracle_pos.clone(),
+      eval:     self.eval.clone(),
+      snapshot: RefCell::new(None),
+      tap:      TAPOutput{verbose: self.tap.verbose, ..tap},
+      parser_v: self.parser_v,
+      parser_mode: self.parser_mode,
+      logw:     self.logw.clone(),
+      journal:  None,
+      parse_cache: self.parse_cache.clone(),
+    }
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The pending evals, as pairs of (1) the clock of the step that
+  // yielded `Yield_::Eval` and (2) the term to be evaluated.
+  pub fn pending_evals(&self) -> Vec<(LClk, SNum)> {
+    self.eval.buf.iter().map(|(&clk, e)| (clk, e.term)).collect()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The eval-yield protocol:
+  //
+  // 1. a function (e.g. `eval`), or an `await` term, records a pending
+  //    eval (see `_push_eval`) and yields `Yield_::Eval`; the suspended
+  //    apply is not continued, and `interp_` returns `Yield_::Eval` to
+  //    the caller.
+  // 2. the caller evaluates the pending term (e.g. w/ an oracle), and
+  //    provides the result value w/ `provide_eval_result`, which binds
+  //    the value into the result register and resumes the continuation
+  //    of the suspended apply.
+  // 3. the caller resumes interpretation w/ `interp_`.
+  pub fn provide_eval_result(&mut self, clk: LClk, value: LitVal_) -> Result<(), InterpCheck> {
+    if self.knt_.is_some() {
+      // NB: evals must be provided while suspended, not mid-step; the
+      // pending eval is kept, so that it may be provided later.
+      return Err("provide_eval_result: not suspended".into());
+    }
+    let e = match self.eval._remove(clk) {
+      None => return Err(format!("provide_eval_result: no pending eval: clk={:?}", clk).into()),
+      Some(e) => e
+    };
+    _debugln!(self, "DEBUG: FastInterp::provide_eval_result: clk={:?} term={:?} ret={:?} value={:?}", clk, e.term, e.ret, value);
+    let rclk = self.clkctr._fresh_clock();
+    let y = self._fresh()?;
+    self.put_val(rclk, y, value)?;
+    self.unify(rclk, y, e.ret)?;
+    self.put_res(e.ret)?;
+    self.knt_ = e.knt_;
+    self.port = Port_::Return;
+    Ok(())
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The pending evals, keyed by the content hash of their canonically
+  // flattened term (see `_flatten_term`), which is the same for identical
+  // terms in different branches (e.g. for deduplicating oracle queries).
+  pub fn pending_eval_keys(&self) -> Result<Vec<(LClk, ContentHash, String)>, InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let mut keys = Vec::with_capacity(self.eval.buf.len());
+    for (&eclk, e) in self.eval.buf.iter() {
+      let flat = self._flatten_term(clk, e.term)?;
+      keys.push((eclk, _flat_term_hash(&flat), flat));
+    }
+    Ok(keys)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // A canonical content hash of the interpreter state at the current
+  // clock: the global bindings (in the order of their idents), then the
+  // entries of the term and value tables (in sorted SNum order), each
+  // hashed via its canonical flattening through the unifier (see
+  // `_flatten_term`). Thus, states that bind the same idents to the same
+  // values hash the same, regardless of how the values were unified.
+  //
+  // NB: the cost is linear in the size of the tables, times the size of
+  // the flattened values; this is meant for test harnesses and caches,
+  // not for the hot path.
+  pub fn state_hash(&self) -> Result<ContentHash, InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let mut h = Blake2s::new_hash();
+    let mut binds = Vec::with_capacity(self.env.id_global_bind.len());
+    for (&id, &x) in self.env.id_global_bind.iter() {
+      let raw_id = serde_json::to_string(self.lookup_raw_ident(id)?).unwrap();
+      binds.push((raw_id, x));
+    }
+    binds.sort_by(|l, r| l.0.cmp(&r.0));
+    h.hash_bytes(b"bind\n");
+    for (raw_id, x) in binds.into_iter() {
+      let flat = self._flatten_term(clk, x)?;
+      h.hash_bytes(raw_id.as_bytes());
+      h.hash_bytes(b"=");
+      h.hash_bytes(flat.as_bytes());
+      h.hash_bytes(b"\n");
+    }
+    for &sort in [SNumSort::Term, SNumSort::Val].iter() {
+      h.hash_bytes(format!("{:?}\n", sort).as_bytes());
+      for (&x, _) in self.env.table_full[sort as usize].iter() {
+        let flat = self._flatten_term(clk, x)?;
+        h.hash_bytes(flat.as_bytes());
+        h.hash_bytes(b"\n");
+      }
+    }
+    Ok(ContentHash::from(h.finalize()))
+  }
+
+  // [Interp-API]
+  //
+  // A canonical flattening of the term `x` into a JSON-like string, which
+  // depends only on the structure of the term (and not on the numbering
+  // of its SNums). Unbound (or otherwise opaque) subterms flatten to `_`.
+  pub(crate) fn _flatten_term(&self, clk: LClk, x: SNum) -> Result<String, InterpCheck> {
+    let mut buf = String::new();
+    self._flatten_term_(clk, x, 0, &mut buf)?;
+    Ok(buf)
+  }
+
+  fn _flatten_term_(&self, clk: LClk, x: SNum, depth: usize, buf: &mut String) -> Result<(), InterpCheck> {
+    // NB: cyclic terms are cut off at a fixed depth.
+    if depth >= 64 {
+      buf.push_str("\"_\"");
+      return Ok(());
+    }
+    let e = self.find(clk, x)?;
+    let vals = self.get_vals(clk, e)?;
+    if let Some(&(_, Val_::Lit(ref v))) = vals.first() {
+      match v {
+        &LitVal_::Box{buf: ref y} => {
+          buf.push_str("{\"Box\":");
+          match y {
+            &None => buf.push_str("null"),
+            &Some(y) => self._flatten_term_(clk, y, depth + 1, buf)?
+          }
+          buf.push_str("}");
+        }
+        &LitVal_::List{buf: ref ys} => {
+          buf.push_str("{\"List\":[");
+          for (i, &y) in ys.iter().enumerate() {
+            if i > 0 {
+              buf.push_str(",");
+            }
+            self._flatten_term_(clk, y, depth + 1, buf)?;
+          }
+          buf.push_str("]}");
+        }
+        _ => {
+          buf.push_str(&serde_json::to_string(v).unwrap());
+        }
+      }
+      return Ok(());
+    }
+    let t = match self.get_term(e._inst())? {
+      None => {
+        buf.push_str("\"_\"");
+        return Ok(());
+      }
+      Some(t) => t.as_any()
+    };
+    if let Some(t) = t.downcast_ref::<TupleTerm_>() {
+      buf.push_str("{\"Tuple\":[");
+      for (i, y) in t.buf.iter().enumerate() {
+        if i > 0 {
+          buf.push_str(",");
+        }
+        self._flatten_term_(clk, y._inst(), depth + 1, buf)?;
+      }
+      buf.push_str("]}");
+    } else if let Some(t) = t.downcast_ref::<IdentTerm_>() {
+      buf.push_str("{\"Ident\":");
+      buf.push_str(&serde_json::to_string(&t.raw_id).unwrap());
+      buf.push_str("}");
+    } else if let Some(t) = t.downcast_ref::<LitTerm_>() {
+      let v = match t._unpack() {
+        UnpackedLitTerm_::None => LitVal_::None,
+        UnpackedLitTerm_::True => LitVal_::Bool(true),
+        UnpackedLitTerm_::False => LitVal_::Bool(false),
+        UnpackedLitTerm_::Int(v) => LitVal_::Int(*v),
+        UnpackedLitTerm_::Str(v) => {
+          buf.push_str("{\"Str\":");
+          buf.push_str(&serde_json::to_string(&*v).unwrap());
+          buf.push_str("}");
+          return Ok(());
+        }
+      };
+      buf.push_str(&serde_json::to_string(&v).unwrap());
+    } else {
+      buf.push_str("\"_\"");
+    }
+    Ok(())
+  }
+
+  // [Interp-API]
+  pub fn _term_content_hash(&self, clk: LClk, x: SNum) -> Result<ContentHash, InterpCheck> {
+    let flat = self._flatten_term(clk, x)?;
+    Ok(_flat_term_hash(&flat))
+  }
+
+  // [Interp-API]
+  //
+  // Records a pending eval of `term` from within `Function::__apply__`;
+  // the apply should then yield `Yield_::Eval`.
+  pub(crate) fn _push_eval(&mut self, clk: LClk, term: SNum, ret: SNum, knt: &BorrowedMemKnt) -> Result<(), InterpCheck> {
+    _debugln!(self, "DEBUG: FastInterp::_push_eval: clk={:?} term={:?} ret={:?}", clk, term, ret);
+    self.eval._push(clk, term, ret, knt.prev.clone())
+      .map_err(|_| InterpCheck::from("_push_eval: duplicate pending eval"))
+  }
+
   // [Interp-API-Pub]
   //
   // This pre-initializes the interpreter with the builtin prelude.
@@ -3934,12 +8880,8 @@ impl FastInterp {
       writeln!(snapshot, "{{\"_event\": \"pre_init\"}}").unwrap();
     }
     self.env._pre_init(&self.ctr);
-    self._register_builtin_function("choice",   self::prelude::ChoiceFun::default())?;
-    self._register_builtin_function("failure",  self::prelude::FailureFun::default())?;
-    self._register_builtin_function("eval",     self::prelude::EvalFun::default())?;
-    self._register_builtin_function("input",    self::prelude::InputFun::default())?;
-    self._register_builtin_function("print",    self::prelude::PrintFun::default())?;
-    self._register_builtin_obj_cls("TokenTrie", self::prelude::TokenTrieCls::default())?;
+    // NB: the core prelude is listed in `registry::PreludeRegistry::core`.
+    self._register_prelude()?;
     // TODO
     //self._register_builtin_obj_cls("List", self::prelude::ListCls::default())?;
     //self._register_builtin_obj_cls("Set",  self::prelude::SetCls::default())?;
@@ -3953,24 +8895,61 @@ impl FastInterp {
   //
   // This loads the given source code into the interpreter.
   pub fn cold_start(&mut self, src: &str) -> Result<(), InterpCheck> {
+    self._cold_start(&[(None, src)])
+  }
+
+  // [Interp-API]
+  //
+  // This loads the sources `srcs`, each w/ its origin path (if any), into
+  // the interpreter as one module; the sources are combined per a fresh
+  // `src::SourceMap`, which also attributes parse errors to their
+  // `path:line:col`.
+  pub(crate) fn _cold_start(&mut self, srcs: &[(Option<&Path>, &str)]) -> Result<(), InterpCheck> {
     _debugln!(self, "DEBUG: FastInterp::cold_start: ...");
     if let Some(snapshot) = self.snapshot.borrow_mut().as_mut() {
       writeln!(snapshot, "{{\"_event\": \"cold-start\"}}").unwrap();
     }
     if _debugln!(self, "DEBUG: FastInterp::cold_start: parse...") {
     }
-    let mut parser = FastParser::new(src);
-    if self.parser_v > 0 {
-      parser.set_verbose(self.parser_v);
+    let mut srcmap = SourceMap::default();
+    let mut src = String::new();
+    let mut body = Vec::new();
+    let mut pragmas = Vec::new();
+    for &(path, text) in srcs.iter() {
+      let (_, base) = srcmap.add_file(path.map(|path| path.to_owned()), text);
+      while src.len() < base {
+        src.push('\n');
+      }
+      src.push_str(text);
+      let y = self._parse_text(text, base).map_err(|e| match srcmap.span_loc(&e.span) {
+        None => format!("parse error: {:?}", e),
+        Some(loc) => format!("parse error: {}: {:?}", loc, e)
+      })?;
+      body.extend(y.body);
+      pragmas.extend(y.pragmas);
     }
-    let y = parser.mod_().map_err(|e| format!("parse error: {:?}", e))?;
+    let y = RawMod_{span: 0 .. src.len(), body, pragmas};
+    self._apply_pragmas(&y.pragmas)?;
     if _debugln!(self, "DEBUG: FastInterp::cold_start: pretty print...") {
-      let printer = DebugPrinter::new(src);
+      let printer = DebugPrinter::new(src.as_str());
       printer.pretty_print(&y);
     }
     _debugln!(self, "DEBUG: FastInterp::cold_start: load...");
     let x = self._load_raw_mod(&y)?;
-    drop(parser);
+    self._enter_mod(x, src, srcmap);
+    self.load = LoadRecipe_::Module(srcs.iter().map(|&(path, text)| (path.map(|path| path.to_owned()), text.to_owned())).collect());
+    _debugln!(self, "DEBUG: FastInterp::cold_start: done");
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Records the combined source of the loaded module `x`, and sets up the
+  // interpreter to enter `x` upon the next `interp_`.
+  pub(crate) fn _enter_mod(&mut self, x: ModCodeNum, src: String, srcmap: SourceMap) {
+    self.src = Some(src);
+    self.src_enc = None;
+    self.srcmap = srcmap;
     let clk = self.clkctr._get_clock();
     self.knt_ = MemKnt{
       clk,
@@ -3979,10 +8958,250 @@ impl FastInterp {
     }.into_ref();
     self.port = Port_::Enter;
     self.write_snapshot();
-    _debugln!(self, "DEBUG: FastInterp::cold_start: done");
+  }
+
+  // [Interp-API]
+  //
+  // Applies the recognized module pragmas (see `parse::Pragma`):
+  // - `strict`: check the sort of every key put in the term and val
+  //   tables (see `set_strict`);
+  // - `occurs_check`: reject cyclic unifications (see `set_occurs_check`);
+  // - `unify = rational|occurs_check|ground`: the unify mode (see
+  //   `set_unify_mode`);
+  // - `match_commit`: see `set_match_commit`;
+  // - `step_limit = N`: see `set_step_limit`;
+  // - `trace = N`: the interp log level (see `set_verbose`).
+  // Other pragmas are ignored.
+  pub(crate) fn _apply_pragmas(&mut self, pragmas: &[RawPragma_]) -> Result<(), InterpCheck> {
+    for pragma in pragmas.iter() {
+      let key = pragma.key.as_raw_str();
+      let val = pragma.val.as_ref().map(|val| val.as_raw_str());
+      _debugln!(self, "DEBUG: FastInterp::_apply_pragmas: key={:?} val={:?}", key, val);
+      match (key, val) {
+        ("strict", None) => self.set_strict(true),
+        ("occurs_check", None) => self.set_occurs_check(true),
+        ("match_commit", None) => self.set_match_commit(true),
+        ("unify", Some(val)) => {
+          let mode: UnifyMode = val.parse()
+            .map_err(|_| format!("pragma: unify: expected a unify mode, found {:?}", val))?;
+          self.set_unify_mode(mode);
+        }
+        ("step_limit", Some(val)) => {
+          let lim: u64 = val.parse()
+            .map_err(|_| format!("pragma: step_limit: expected an int, found {:?}", val))?;
+          self.set_step_limit(Some(lim));
+        }
+        ("trace", Some(val)) => {
+          let v: i8 = val.parse()
+            .map_err(|_| format!("pragma: trace: expected a log level, found {:?}", val))?;
+          self.set_verbose(v);
+        }
+        ("strict", Some(_)) |
+        ("occurs_check", Some(_)) |
+        ("match_commit", Some(_)) |
+        ("unify", None) |
+        ("step_limit", None) |
+        ("trace", None) => {
+          return Err(format!("pragma: {}: malformed", key).into());
+        }
+        _ => {
+          _debugln!(self, "DEBUG: FastInterp::_apply_pragmas:   ignored");
+        }
+      }
+    }
+    Ok(())
+  }
+
+  // [Interp-API-Pub]
+  //
+  // This loads the given source bytes into the interpreter (see
+  // `cold_start`), after transcoding them to UTF-8 (see `load_bytes_with`).
+  pub fn load_bytes(&mut self, buf: &[u8]) -> Result<(), InterpCheck> {
+    self.load_bytes_with(buf, &TranscodeConfig::default())
+  }
+
+  // [Interp-API-Pub]
+  //
+  // This transcodes the given source bytes to UTF-8 per `cfg` (see
+  // `src::transcode`), and then loads them into the interpreter. The
+  // encoding is recorded (see `src_encoding`), and is also appended to
+  // the message of any check raised while loading.
+  pub fn load_bytes_with(&mut self, buf: &[u8], cfg: &TranscodeConfig) -> Result<(), InterpCheck> {
+    let (src, enc) = transcode(buf, cfg).map_err(|e| format!("transcode error: {:?}", e))?;
+    _debugln!(self, "DEBUG: FastInterp::load_bytes: encoding={:?}", enc);
+    let ret = self.cold_start(&src);
+    let ret = ret.map_err(|mut check| {
+      check._err.msg = format!("{} (source encoding: {})", check._err.msg, enc.name).into();
+      check
+    });
+    self.src_enc = Some(enc);
+    ret
+  }
+
+  // [Interp-API-Pub]
+  pub fn src_encoding(&self) -> Option<&SourceEncoding> {
+    self.src_enc.as_ref()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // This opens the source file at `path` (see `src::SourceFile::open`),
+  // and loads it into the interpreter. The origin path is recorded (see
+  // `srcmap`), and the check raised by a parse error is attributed to
+  // its `path:line:col`.
+  pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), InterpCheck> {
+    let file = SourceFile::open(path.as_ref())
+      .map_err(|e| format!("source file error: {}: {:?}", path.as_ref().display(), e))?;
+    self.load_source_file(&file)
+  }
+
+  // [Interp-API-Pub]
+  pub fn load_source_file(&mut self, file: &SourceFile) -> Result<(), InterpCheck> {
+    _debugln!(self, "DEBUG: FastInterp::load_source_file: path={:?} gzip={} encoding={:?}",
+        file.path, file.gzip, file.enc);
+    self._cold_start(&[(Some(file.path.as_path()), file.as_str())])?;
+    self.src_enc = Some(file.enc.clone());
+    Ok(())
+  }
+
+  // [Interp-API-Pub]
+  //
+  // This opens the source files at `paths` (see `load_file`), and loads
+  // them into the interpreter as one module, i.e. as if the files were
+  // concatenated in the given order. Each span of the module is
+  // attributed to its file by `srcmap`.
+  pub fn load_project<P: AsRef<Path>>(&mut self, paths: &[P]) -> Result<(), InterpCheck> {
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths.iter() {
+      let file = SourceFile::open(path.as_ref())
+        .map_err(|e| format!("source file error: {}: {:?}", path.as_ref().display(), e))?;
+      _debugln!(self, "DEBUG: FastInterp::load_project: path={:?} gzip={} encoding={:?}",
+          file.path, file.gzip, file.enc);
+      files.push(file);
+    }
+    let srcs: Vec<_> = files.iter().map(|file| (Some(file.path.as_path()), file.as_str())).collect();
+    self._cold_start(&srcs)
+  }
+
+  // [Interp-API-Pub]
+  pub fn srcmap(&self) -> &SourceMap {
+    &self.srcmap
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The origin path of the source, if it was loaded from one file.
+  pub fn src_path(&self) -> Option<&Path> {
+    match self.srcmap.files() {
+      &[ref file] => file.path.as_deref(),
+      _ => None
+    }
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Formats the start of the loaded span `x` as `path:line:col` (see
+  // `src::SourceMap::span_loc`).
+  pub fn span_loc(&self, x: SpanNum) -> Option<String> {
+    let e = self.env.table_full[SNumSort::Span as usize].get(&SNum::from(x))?;
+    let raw_span = e.inner.as_any().downcast_ref::<RawSpan_>()?;
+    self.srcmap.span_loc(raw_span)
+  }
+
+  // [Interp-API]
+  //
+  // Attaches to a pending raise (if it does not already have one) the
+  // span of the innermost code of the current continuation that has a
+  // span (see `_knt_span`), i.e. the code that raised, and its source
+  // range.
+  pub(crate) fn _locate_exc(&mut self) -> Result<(), InterpCheck> {
+    if !self.exc_.is_some() || self.exc_.span.is_some() {
+      return Ok(());
+    }
+    let mut span = None;
+    let mut kcur = self.knt_.as_ref();
+    while let Some(knt) = kcur {
+      span = self._knt_span(&knt.cur)?;
+      if span.is_some() {
+        break;
+      }
+      kcur = knt.prev.as_ref();
+    }
+    if let Some(x) = span {
+      let range = self.env.table_full[SNumSort::Span as usize].get(&SNum::from(x))
+        .and_then(|e| e.inner.as_any().downcast_ref::<RawSpan_>())
+        .cloned();
+      _debugln!(self, "DEBUG: FastInterp::_locate_exc: span={:?} range={:?}", x, range);
+      self.exc_.span = Some(x);
+      self.exc_.range = range;
+    }
     Ok(())
   }
 
+  // [Interp-API-Pub]
+  //
+  // Renders the pending raise (if any) as `path:line:col: error: msg`,
+  // followed by the source line of the code that raised, w/ the span of
+  // the code underlined.
+  pub fn render_error(&self) -> Option<String> {
+    let msg = self.exc_.msg()?;
+    let mut out = match self.exc_.span.and_then(|x| self.span_loc(x)) {
+      None => format!("error: {}", msg),
+      Some(loc) => format!("{}: error: {}", loc, msg)
+    };
+    let (range, src) = match (self.exc_.range.as_ref(), self.src.as_ref()) {
+      (Some(range), Some(src)) if self.exc_.span.is_some() &&
+                                  range.end <= src.len() &&
+                                  src.is_char_boundary(range.start) &&
+                                  src.is_char_boundary(range.end) => (range.clone(), src),
+      _ => return Some(out)
+    };
+    let line_start = src[ .. range.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = src[range.start .. ].find('\n').map(|i| range.start + i).unwrap_or(src.len());
+    let col = src[line_start .. range.start].chars().count();
+    let width = max(1, src[range.start .. min(range.end, line_end)].chars().count());
+    out.push_str("\n  | ");
+    out.push_str(&src[line_start .. line_end]);
+    out.push_str("\n  | ");
+    out.extend(repeat(' ').take(col));
+    out.extend(repeat('^').take(width));
+    Some(out)
+  }
+
+  // [Interp-API]
+  //
+  // The source text of the span `x`, if the source is loaded.
+  pub(crate) fn _span_text(&self, x: SpanNum) -> Option<String> {
+    let e = self.env.table_full[SNumSort::Span as usize].get(&SNum::from(x))?;
+    let range = e.inner.as_any().downcast_ref::<RawSpan_>()?;
+    self.src.as_ref()?.get(range.clone()).map(|s| s.to_string())
+  }
+
+  // [Interp-API]
+  pub(crate) fn _record_test_failure(&mut self, span: SpanNum, kind: &'static str, operands: Vec<String>) {
+    let range = self.env.table_full[SNumSort::Span as usize].get(&SNum::from(span))
+      .and_then(|e| e.inner.as_any().downcast_ref::<RawSpan_>())
+      .cloned();
+    let fail = TestFailure{kind, loc: self.span_loc(span), range, operands};
+    _debugln!(self, "DEBUG: FastInterp::_record_test_failure: {}", fail.render());
+    self.test_fail.push(fail);
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The failed `assert` and `expect` stms, in the order they ran.
+  //
+  // NB: a failure is recorded even in a branch that is later backtracked
+  // over, so that a source-level test suite reports every failure.
+  pub fn test_failures(&self) -> &[TestFailure] {
+    &self.test_fail
+  }
+
+  // [Interp-API-Pub]
+  pub fn take_test_failures(&mut self) -> Vec<TestFailure> {
+    take(&mut self.test_fail)
+  }
+
   // [Interp-API-Pub]
   //
   // This re-initializes the interpreter with the new source code.
@@ -4002,7 +9221,61 @@ impl FastInterp {
   }
 
   // [Interp-API-Pub]
+  //
+  // When a run journal is attached (see `set_run_journal`), each call
+  // appends a `journal::RunRecord` of the run to it.
   pub fn interp_(&mut self) -> Result<Yield_, InterpCheck> {
+    self.rng.draws.clear();
+    if self.journal.is_none() {
+      let mut stats = InterpRunStats_::default();
+      return self._interp_(&mut stats);
+    }
+    let t_start = Timestamp::fresh();
+    let mut stats = InterpRunStats_::default();
+    let ret = self._interp_(&mut stats);
+    let t_end = Timestamp::fresh();
+    let mod_hash = match self.src.as_ref() {
+      None => String::new(),
+      Some(src) => {
+        let mut h = Blake2s::new_hash();
+        h.hash_bytes(src.as_bytes());
+        Base64Format::default().to_string(&h.finalize())
+      }
+    };
+    let status = match &ret {
+      &Err(ref check) => RunStatus::Check(format!("{:?}", check)),
+      &Ok(ref yield_) => RunStatus::Yield(format!("{:?}", yield_))
+    };
+    let rec = RunRecord{
+      t_start,
+      t_end,
+      mod_hash,
+      yields:   stats.yields,
+      status,
+      step_ct:  stats.step_ct,
+      backtrack_ct: stats.backtrack_ct,
+      seed:     self.rng.seed,
+      draws:    take(&mut self.rng.draws),
+      max_depth: stats.max_depth,
+      unifier:  Some(self.unifier_stats()),
+    };
+    // NB: a journal write error does not fail the run.
+    if let Err(e) = self.journal.as_mut().unwrap().append(&rec) {
+      _debugln!(self, "DEBUG: FastInterp::interp_: journal: append error={:?}", e);
+    }
+    ret
+  }
+
+  pub fn _interp_(&mut self, stats: &mut InterpRunStats_) -> Result<Yield_, InterpCheck> {
+    self._interp_bt_(stats, true)
+  }
+
+  // [Interp-API]
+  //
+  // W/out `backtrack`, a failure is yielded as `Yield_::Fail`, rather
+  // than backtracked from (see `search`).
+  pub(crate) fn _interp_bt_(&mut self, stats: &mut InterpRunStats_, backtrack: bool) -> Result<Yield_, InterpCheck> {
+    let t0 = self.time_lim.map(|_| Timestamp::fresh());
     //let mut ictr = 0;
     'resume: loop {
       //ictr += 1;
@@ -4011,7 +9284,12 @@ impl FastInterp {
         _debugln!(self, "DEBUG: FastInterp::interp_:   breakpoint (timeout)");
         return Ok(Yield_::Break);
       }*/
-      let yield_ = self.resume_()?;
+      // NB: a restored choice point may instead be replayed (see `redo`).
+      let yield_ = match self._redo_replay()? {
+        Some(yield_) => yield_,
+        None => self._resume_run_(Some((stats, t0)))?
+      };
+      *stats.yields.entry(format!("{:?}", yield_)).or_default() += 1;
 
       // NB: at this point, ^ has yielded for some reason.
       match yield_ {
@@ -4024,6 +9302,8 @@ impl FastInterp {
           match yield_ {
             Yield_::Quiescent => {
               _debugln!(self, "DEBUG: FastInterp::interp_: clk={:?} quiescent", clk);
+              self.pv_cache._learn(&self.trace);
+              self.redo._mark_leaf(clk, true);
             }
             Yield_::Halt => {
               _debugln!(self, "DEBUG: FastInterp::interp_: clk={:?} halt", clk);
@@ -4067,69 +9347,22 @@ impl FastInterp {
             _debugln!(self, "DEBUG: FastInterp::interp_: log:  p={} e={:?}",
                 p, &self.log.buf[p]);
           }
+          self._hook_yield(&yield_);
           return Ok(yield_);
         }
         Yield_::Fail => {
           let clk = self.clkctr._get_clock();
           _debugln!(self, "DEBUG: FastInterp::interp_: yield: clk={:?} failure", clk);
+          self._hook_fail(clk);
+          self.redo._mark_leaf(clk, false);
           _debugln!(self, "DEBUG: FastInterp::interp_: yield:   trace.buf.len={}", self.trace.buf.len());
-          for p in (0 .. self.trace.buf.len()).rev() {
-            self.trace.buf[p].xctr += 1;
-            // NB: in this case, still undo, but also continue to backtrack.
-            let stop = self.trace.buf[p].xctr < self.trace.buf[p].xlim;
-            let rst_clk = self.trace.buf[p].root_clk;
-            let rst_xlb = self.trace.buf[p].xlb;
-            _debugln!(self, "DEBUG: FastInterp::interp_: yield:   trace.buf[{}]: choice ctr={} ub={} rst clk={:?} xlb={:?}", p, self.trace.buf[p].xctr, self.trace.buf[p].xlim, rst_clk, rst_xlb);
-            for logp in (0 .. self.log.buf.len()).rev() {
-              if self.log.buf[logp].clk < rst_clk {
-                _debugln!(self, "DEBUG: FastInterp::interp_: yield:   trace.buf[{}]: undo[{}]: clk={:?} stop", p, logp, self.log.buf[logp].clk);
-                break;
-              }
-              match &self.log.buf[logp].val {
-                &LogEntryRef_::Undo(ref e) => {
-                  _debugln!(self, "DEBUG: FastInterp::interp_: yield:   trace.buf[{}]: undo[{}]: clk={:?} entry={:?}", p, logp, self.log.buf[logp].clk, e);
-                  self.undo(self.log.buf[logp].clk, e.clone())?;
-                }
-                //_ => return Err(bot())
-              }
-              let _ = self.log.buf.pop().unwrap();
-            }
-            self.ctr._reset(rst_xlb);
-            // NB: restoring a choice point _should not_ reset linear time!
-            // instead, allocate a fresh timestamp next step.
-            // (this is the whole point of a _linear_ timestamp.)
-            /*self.clkctr._reset_clock(rst_clk);*/
-            self.reg.rst_clk = rst_clk;
-            self.exc_ = self.trace.buf[p].ctl_.exc_.clone();
-            self.res_ = self.trace.buf[p].ctl_.res_.clone();
-            self.port = self.trace.buf[p].ctl_.port.clone();
-            // FIXME: would prefer to take instead of clone; do we really
-            // need to store full trace entries (in the PV cache)?
-            let knt = self.trace.buf[p].knt_.clone();
-            _debugln!(self, "DEBUG: FastInterp::interp_: yield:   knt={:?}", knt);
-            if knt.is_some() {
-              let knt = knt.unwrap();
-              _traceln!(self, "DEBUG: FastInterp::interp_: yield:     kcur ={:?} {:?}", knt.clk, &knt.cur);
-              let mut kprev = knt.prev.as_ref();
-              loop {
-                if kprev.is_none() {
-                  _traceln!(self, "DEBUG: FastInterp::interp_: yield:     kprev=nil");
-                  break;
-                }
-                let knt = kprev.unwrap();
-                _traceln!(self, "DEBUG: FastInterp::interp_: yield:     kprev={:?} {:?}", knt.clk, &knt.cur);
-                kprev = knt.prev.as_ref();
-              }
-            }
-            self.knt_ = self.trace.buf[p].knt_.clone();
-            // FIXME: only update the pv cache if this actually a new best pv.
-            /*self.pv_cache.tree.insert(clk, self.trace.buf.clone());*/
-            if stop {
-              _debugln!(self, "DEBUG: FastInterp::interp_: yield:   stop: p={}", p);
-              continue 'resume;
-            }
-            _debugln!(self, "DEBUG: FastInterp::interp_: yield:   pop: p={}", p);
-            self.trace._pop_pos(p as _)?;
+          if !backtrack {
+            self._hook_yield(&Yield_::Fail);
+            return Ok(Yield_::Fail);
+          }
+          if self._backtrack()? {
+            stats.backtrack_ct += 1;
+            continue 'resume;
           }
           _debugln!(self, "DEBUG: FastInterp::interp_: yield:   halt");
           _debugln!(self, "DEBUG: FastInterp::interp_: env:  id   tab={:?}",
@@ -4151,40 +9384,600 @@ impl FastInterp {
           for &x in self.env.unifier.root.iter() {
             _debugln!(self, "DEBUG: FastInterp::interp_:       unifier[{:?}]={:?}", x, self.env.unifier._findall(&self.clkinval, clk, x)?);
           }
-          _debugln!(self, "DEBUG: FastInterp::interp_:       rule idx={:?}",
-              &self.env.rule_index);
-          _debugln!(self, "DEBUG: FastInterp::interp_: res:  log={:?} reg={:?}",
-              &self.res_._log[min(1, self.res_._log.len()) .. ],
-              self.res_.reg);
-          for p in 0 .. self.log.buf.len() {
-            _debugln!(self, "DEBUG: FastInterp::interp_: log:  p={} e={:?}",
-                p, &self.log.buf[p]);
+          _debugln!(self, "DEBUG: FastInterp::interp_:       rule idx={:?}",
+              &self.env.rule_index);
+          _debugln!(self, "DEBUG: FastInterp::interp_: res:  log={:?} reg={:?}",
+              &self.res_._log[min(1, self.res_._log.len()) .. ],
+              self.res_.reg);
+          for p in 0 .. self.log.buf.len() {
+            _debugln!(self, "DEBUG: FastInterp::interp_: log:  p={} e={:?}",
+                p, &self.log.buf[p]);
+          }
+          self._hook_yield(&Yield_::Halt);
+          return Ok(Yield_::Halt);
+        }
+        Yield_::Eval => {
+          let clk = self.clkctr._get_clock();
+          _debugln!(self, "DEBUG: FastInterp::interp_: yield: clk={:?} eval", clk);
+          _debugln!(self, "DEBUG: FastInterp::interp_: yield:   trace.buf.len={}", self.trace.buf.len());
+          _debugln!(self, "DEBUG: FastInterp::interp_: yield:   eval.buf.len={}", self.eval.buf.len());
+          self._hook_yield(&Yield_::Eval);
+          return Ok(Yield_::Eval);
+        }
+      }
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Backtracks to the innermost choice point that still has untried
+  // alternatives, undoing the log back to it (see `redo`).
+  // Returns false if every choice point has been exhausted.
+  pub(crate) fn _backtrack(&mut self) -> Result<bool, InterpCheck> {
+    self.redo._reset_popped();
+    for p in (0 .. self.trace.buf.len()).rev() {
+      let rank = self.trace.buf[p].xctr;
+      self.trace.buf[p].xctr = rank.saturating_add(self.trace.buf[p].xstep);
+      // NB: in this case, still undo, but also continue to backtrack.
+      let stop = self.trace.buf[p].xctr < self.trace.buf[p].xlim;
+      self._restore_pos(p, rank)?;
+      // FIXME: only update the pv cache if this actually a new best pv.
+      /*self.pv_cache.tree.insert(clk, self.trace.buf.clone());*/
+      if stop {
+        _debugln!(self, "DEBUG: FastInterp::_backtrack:   stop: p={}", p);
+        return Ok(true);
+      }
+      _debugln!(self, "DEBUG: FastInterp::_backtrack:   pop: p={}", p);
+      let te = self.trace._pop_pos(p as _)?;
+      self.redo._set_popped(te);
+    }
+    Ok(false)
+  }
+
+  // [Interp-API]
+  //
+  // Backtracks to the choice point at trace position `target`, even if
+  // an inner choice point still has untried alternatives; the inner
+  // choice points are undone and popped (see `search`). Returns false
+  // (and pops the target) if the target has been exhausted.
+  pub(crate) fn _backtrack_to(&mut self, target: usize) -> Result<bool, InterpCheck> {
+    if target >= self.trace.buf.len() {
+      return Err(bot());
+    }
+    self.redo._reset_popped();
+    for p in (target + 1 .. self.trace.buf.len()).rev() {
+      let rank = self.trace.buf[p].xctr;
+      self._restore_pos(p, rank)?;
+      _debugln!(self, "DEBUG: FastInterp::_backtrack_to:   pop: p={}", p);
+      let te = self.trace._pop_pos(p as _)?;
+      self.redo._set_popped(te);
+    }
+    let p = target;
+    let rank = self.trace.buf[p].xctr;
+    self.trace.buf[p].xctr = rank.saturating_add(self.trace.buf[p].xstep);
+    let stop = self.trace.buf[p].xctr < self.trace.buf[p].xlim;
+    self._restore_pos(p, rank)?;
+    if !stop {
+      _debugln!(self, "DEBUG: FastInterp::_backtrack_to:   exhausted: p={}", p);
+      self.trace._pop_pos(p as _)?;
+    }
+    Ok(stop)
+  }
+
+  // [Interp-API]
+  //
+  // Undoes the log back to the choice point at trace position `p`, and
+  // restores the registers and the continuation saved at the choice
+  // point; `rank` is the rank being left (see `FastRedoLog_`).
+  pub(crate) fn _restore_pos(&mut self, p: usize, rank: RawChoiceRank) -> Result<(), InterpCheck> {
+    let rst_clk = self.trace.buf[p].root_clk;
+    let rst_xlb = self.trace.buf[p].xlb;
+    _debugln!(self, "DEBUG: FastInterp::_backtrack:   trace.buf[{}]: choice ctr={} ub={} rst clk={:?} xlb={:?}", p, self.trace.buf[p].xctr, self.trace.buf[p].xlim, rst_clk, rst_xlb);
+    let mut redo = self._redo_begin(p);
+    for logp in (0 .. self.log.buf.len()).rev() {
+      if self.log.buf[logp].clk < rst_clk {
+        _debugln!(self, "DEBUG: FastInterp::_backtrack:   trace.buf[{}]: undo[{}]: clk={:?} stop", p, logp, self.log.buf[logp].clk);
+        break;
+      }
+      match &self.log.buf[logp].val {
+        &LogEntryRef_::Undo(ref e) => {
+          _debugln!(self, "DEBUG: FastInterp::_backtrack:   trace.buf[{}]: undo[{}]: clk={:?} entry={:?}", p, logp, self.log.buf[logp].clk, e);
+          let e = self.undo(self.log.buf[logp].clk, e.clone())?;
+          if let Some(seg) = redo.as_mut() {
+            seg._push(e);
+          }
+        }
+        //_ => return Err(bot())
+      }
+      let _ = self.log._pop().unwrap();
+    }
+    // NB: the undone clocks are invalidated in bulk, so that no stale
+    // unifier path through them is taken (see `FastUnifier_::_find`).
+    self.clkinval.invalidate_from(rst_clk, self.clkctr._next_clock())?;
+    self.ctr._reset(rst_xlb);
+    // NB: the continuations of pending evals since the choice point
+    // are abandoned along w/ the undone log.
+    let drop_evals = self.eval._truncate(rst_clk);
+    if drop_evals > 0 {
+      _debugln!(self, "DEBUG: FastInterp::_backtrack:   trace.buf[{}]: drop pending evals={}", p, drop_evals);
+      redo = None;
+    }
+    // NB: likewise, the oracle requests submitted since the choice point
+    // are cancelled, so as not to spend worker concurrency on them.
+    #[cfg(feature = "pyo3")]
+    {
+      let cancel_ct = self._oracle_cancel_from((p + 1) as u32);
+      if cancel_ct > 0 {
+        _debugln!(self, "DEBUG: FastInterp::_backtrack:   trace.buf[{}]: cancel oracle reqs={}", p, cancel_ct);
+        redo = None;
+      }
+    }
+    if let Some(prov) = self.prov.as_mut() {
+      let _ = prov._truncate(rst_clk);
+    }
+    // NB: retain the undone segment for replay (see `redo`).
+    if let Some(seg) = redo {
+      self._redo_retain(rst_clk, rank, seg);
+    }
+    // NB: restoring a choice point _should not_ reset linear time!
+    // instead, allocate a fresh timestamp next step.
+    // (this is the whole point of a _linear_ timestamp.)
+    /*self.clkctr._reset_clock(rst_clk);*/
+    self._restore_ctl(p);
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Restores the registers and the continuation saved at the choice
+  // point at trace position `p`, so that it is resumed next.
+  pub(crate) fn _restore_ctl(&mut self, p: usize) {
+    self.reg.rst_clk = self.trace.buf[p].root_clk;
+    self.exc_ = self.trace.buf[p].ctl_.exc_.clone();
+    self.res_ = self.trace.buf[p].ctl_.res_.clone();
+    self.port = self.trace.buf[p].ctl_.port.clone();
+    // FIXME: would prefer to take instead of clone; do we really
+    // need to store full trace entries (in the PV cache)?
+    let knt = self.trace.buf[p].knt_.clone();
+    _debugln!(self, "DEBUG: FastInterp::_backtrack:   knt={:?}", knt);
+    if knt.is_some() {
+      let knt = knt.unwrap();
+      _traceln!(self, "DEBUG: FastInterp::_backtrack:     kcur ={:?} {:?}", knt.clk, &knt.cur);
+      let mut kprev = knt.prev.as_ref();
+      loop {
+        if kprev.is_none() {
+          _traceln!(self, "DEBUG: FastInterp::_backtrack:     kprev=nil");
+          break;
+        }
+        let knt = kprev.unwrap();
+        _traceln!(self, "DEBUG: FastInterp::_backtrack:     kprev={:?} {:?}", knt.clk, &knt.cur);
+        kprev = knt.prev.as_ref();
+      }
+    }
+    self.knt_ = self.trace.buf[p].knt_.clone();
+  }
+
+  // [Interp-API]
+  //
+  // Expands each splat arg (see `TermCode_::Splat`) of an apply tup, in
+  // place, into the items of its list value.
+  pub(crate) fn _expand_splats(&self, clk: LClk, tup: &mut Vec<(TermCodeNum, SNum)>) -> Result<Result<(), String>, InterpCheck> {
+    let mut buf = Vec::with_capacity(tup.len());
+    for &(term, arg) in tup.iter() {
+      match self.lookup_term_code(term)? {
+        TermCode_::Splat{..} => {
+          let query = self.find(clk, arg)?;
+          match self::prelude::_find_list_items(self, clk, query)? {
+            None => {
+              return Ok(Err("splat of a non-list value".into()));
+            }
+            Some(items) => {
+              for item in items.into_iter() {
+                buf.push((term, item));
+              }
+            }
+          }
+        }
+        _ => {
+          buf.push((term, arg));
+        }
+      }
+    }
+    *tup = buf;
+    Ok(Ok(()))
+  }
+
+  // [Interp-API]
+  //
+  // Picks an index in `0 .. n` at a choice point (i.e. that of `choice(n)`,
+  // see `prelude::ChoiceFun`), for the knt `knt`; upon backtracking, `knt`
+  // is re-entered, and then this picks the next index. A yield (e.g. the
+  // failure once the indices are exhausted) is the inner `Err`.
+  pub(crate) fn _choose_index(&mut self, clk: LClk, span: SpanNum, n: usize, knt: BorrowedMemKnt) -> Result<Result<usize, Yield_>, InterpCheck> {
+    let ub = self._fresh()?;
+    self.put_val(clk, ub, LitVal_::Int(n as i64))?;
+    let ret = self._fresh()?;
+    // NB: the head of the tup is unused.
+    let tup = [self.find(clk, ret)?, self.find(clk, ub)?];
+    let mut fun = self::prelude::ChoiceFun::default();
+    if let Some(yield_) = fun.__apply__(self, span, ret, &tup, ret, knt)? {
+      return Ok(Err(yield_));
+    }
+    let query = self.find(clk, ret)?;
+    match self::prelude::_find_int_val(self, clk, query)? {
+      Some(idx) if idx >= 0 && (idx as usize) < n => Ok(Ok(idx as usize)),
+      _ => Ok(Err(Yield_::Fail))
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Takes the result register as the truth value of a goal (see
+  // `TermCode_::And`): a match result is its own truth value; a key
+  // result is true unless it has the bool lit val `False`; and no result
+  // (e.g. of a unification in unify context) is true.
+  pub(crate) fn _take_goal_res(&mut self, clk: LClk) -> Result<bool, InterpCheck> {
+    match self.res_.get() {
+      ResReg_::Emp => Ok(true),
+      ResReg_::Mat(v) => Ok(v),
+      ResReg_::Key(x) => {
+        let root = self.find(clk, x)?;
+        for &(_, ref val) in self.get_vals(clk, root)?.iter() {
+          if let &Val_::Lit(LitVal_::Bool(false)) = val {
+            return Ok(false);
+          }
+        }
+        Ok(true)
+      }
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Whether the goal `term` succeeds, i.e. whether some alternative of
+  // it (in match context) is true (see `_scoped_goal`).
+  pub(crate) fn _run_scoped_goal(&mut self, term: TermCodeNum) -> Result<Result<bool, Yield_>, InterpCheck> {
+    let mut found = false;
+    let ret = self._scoped_goal(term, |_, _| {
+      found = true;
+      Ok(false)
+    })?;
+    Ok(ret.map(|_| found))
+  }
+
+  // [Interp-API]
+  //
+  // Runs the goal `term` in match context in a scoped sub-run (see
+  // `_scoped_term`), and calls `on_sol` w/ the result register upon each
+  // true solution (see `_take_goal_res`), until `on_sol` returns false
+  // or the alternatives of the goal are exhausted.
+  pub(crate) fn _scoped_goal<F>(&mut self, term: TermCodeNum, mut on_sol: F) -> Result<Result<(), Yield_>, InterpCheck>
+  where F: FnMut(&mut FastInterp, ResReg_) -> Result<bool, InterpCheck> {
+    self._scoped_term(term, |interp, res| {
+      let clk = interp.clkctr._get_clock();
+      if interp._take_goal_res(clk)? {
+        on_sol(interp, res)
+      } else {
+        Ok(true)
+      }
+    })
+  }
+
+  // [Interp-API]
+  //
+  // The canonical flattening (see `_flatten_term`) and the rendering of
+  // the first value of `term` (see `_scoped_term`), or `None` if `term`
+  // has no value. A match result is rendered as a bool.
+  pub(crate) fn _scoped_value(&mut self, term: TermCodeNum) -> Result<Result<Option<(String, String)>, Yield_>, InterpCheck> {
+    let mut side = None;
+    let ret = self._scoped_term(term, |interp, res| {
+      let clk = interp.clkctr._get_clock();
+      side = Some(match res {
+        ResReg_::Key(x) => {
+          let root = interp.find(clk, x)?;
+          (interp._flatten_term(clk, x)?, interp.render_value(clk, root))
+        }
+        ResReg_::Mat(v) => {
+          let s = if v { "True" } else { "False" };
+          (format!("{{\"Bool\":{}}}", v), s.to_string())
+        }
+        ResReg_::Emp => ("\"_\"".to_string(), "_".to_string())
+      });
+      Ok(false)
+    })?;
+    Ok(ret.map(|_| side))
+  }
+
+  // [Interp-API]
+  //
+  // Runs the term `term` in match context in a scoped sub-run (cf.
+  // `eval_line`), and calls `on_res` w/ the result register upon each
+  // solution (true or not), until `on_res` returns false or the
+  // alternatives of the term are exhausted.
+  //
+  // The sub-run has its own trace, which is a barrier: its failure does
+  // not backtrack into the enclosing choice points. The continuation,
+  // the ctl registers, and the trace are saved beforehand and restored
+  // afterward, and the effects of the goal are always rolled back; so,
+  // this may be called from within a step (e.g. from `__apply__`).
+  // Breakpoints, pending evals, and gc are suspended for the sub-run.
+  //
+  // A raise in the goal is propagated (w/ `exc_` set), as `Err`.
+  pub(crate) fn _scoped_term<F>(&mut self, term: TermCodeNum, mut on_res: F) -> Result<Result<(), Yield_>, InterpCheck>
+  where F: FnMut(&mut FastInterp, ResReg_) -> Result<bool, InterpCheck> {
+    let log_len = self.log.buf.len();
+    let rst_clk = self.clkctr._next_clock();
+    let rst_xlb = self._peek();
+    let heuristic = self.trace.heuristic;
+    let trace = replace(&mut self.trace, FastTrace_::default());
+    self.trace.heuristic = heuristic;
+    let knt_ = self.knt_.take();
+    let port = self.port;
+    let res_ = replace(&mut self.res_, Result_::default());
+    let reg = self.reg;
+    self.reg.tctx = TermContext_::Match;
+    let eval = take(&mut self.eval);
+    let brk_clk = self.brk_clk.take();
+    let brk = take(&mut self.brk);
+    let mem_lim = self.mem_lim.take();
+    self.knt_ = MemKnt{
+      clk:  self.clkctr._get_clock(),
+      cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
+      prev: nil(),
+    }.into_ref();
+    self.port = Port_::Enter;
+    let mut stats = InterpRunStats_::default();
+    let ret = loop {
+      match self._interp_bt_(&mut stats, true) {
+        Err(check) => break Err(check),
+        Ok(Yield_::Quiescent) => {
+          let res = self.res_.peek();
+          match on_res(self, res) {
+            Err(check) => break Err(check),
+            Ok(false) => break Ok(Ok(())),
+            Ok(true) => {}
+          }
+          match self._backtrack() {
+            Err(check) => break Err(check),
+            Ok(false) => break Ok(Ok(())),
+            Ok(true) => {}
+          }
+        }
+        Ok(Yield_::Halt) => break Ok(Ok(())),
+        Ok(Yield_::Raise) => break Ok(Err(Yield_::Raise)),
+        Ok(yield_) => {
+          break Err(format!("scoped goal: unsupported yield: {:?}", yield_).into());
+        }
+      }
+    };
+    _debugln!(self, "DEBUG: FastInterp::_scoped_term: ret={:?}", ret);
+    self._rollback_log(log_len)?;
+    self.clkinval.invalidate_from(rst_clk, self.clkctr._next_clock())?;
+    self.ctr._reset(rst_xlb);
+    if let Some(prov) = self.prov.as_mut() {
+      let _ = prov._truncate(rst_clk);
+    }
+    self.trace = trace;
+    self.knt_ = knt_;
+    self.port = port;
+    self.res_ = res_;
+    self.reg = reg;
+    self.eval = eval;
+    self.brk_clk = brk_clk;
+    self.brk = brk;
+    self.mem_lim = mem_lim;
+    ret
+  }
+
+  // [Interp-API]
+  //
+  // Whether the scrutinee `x` matches the case pattern `y` (see
+  // `StmCode_::Match`): an unbound side matches anything; otherwise, the
+  // two match if they are in the same class, if they have equal lit vals,
+  // or if they are lists whose items match pairwise. If `bind` is set,
+  // the matching sides are also unified, so that the unbound idents of
+  // the pattern capture the scrutinee.
+  pub(crate) fn _match_pat(&mut self, clk: LClk, x: SNum, y: SNum, bind: bool) -> Result<bool, InterpCheck> {
+    let xroot = self.find(clk, x)?;
+    let yroot = self.find(clk, y)?;
+    let mut mat = xroot.ecls == yroot.ecls ||
+                  self._is_unbound(clk, x)? ||
+                  self._is_unbound(clk, y)?;
+    if !mat {
+      match (self::prelude::_find_list_items(self, clk, xroot)?,
+             self::prelude::_find_list_items(self, clk, yroot)?) {
+        (Some(xitems), Some(yitems)) => {
+          if xitems.len() != yitems.len() {
+            return Ok(false);
+          }
+          for (&xitem, &yitem) in xitems.iter().zip(yitems.iter()) {
+            if !self._match_pat(clk, xitem, yitem, bind)? {
+              return Ok(false);
+            }
+          }
+          return Ok(true);
+        }
+        _ => {}
+      }
+      // NB: lit terms are not (yet) hash-consed (see `TermCode_::In`).
+      let xvals = self.get_vals(clk, xroot)?;
+      for &(_, ref yval) in self.get_vals(clk, yroot)?.iter() {
+        for &(_, ref xval) in xvals.iter() {
+          match (xval, yval) {
+            (&Val_::Lit(ref xv), &Val_::Lit(ref yv)) if xv == yv => {
+              mat = true;
+            }
+            _ => {}
+          }
+        }
+      }
+    }
+    if mat && bind {
+      return self._unify_checked(clk, x, y);
+    }
+    Ok(mat)
+  }
+
+  // [Interp-API]
+  //
+  // Binds the args of a call to the params of the proc `code`: the
+  // positional args bind the leading params in order, and each keyword
+  // arg (see `TermCode_::Kwarg`) binds the param of the same name. A
+  // param w/out an arg is bound to a fresh term, which its default stm
+  // then unifies w/ the default value; the default stms run in the proc
+  // scope, in param order, before the body. The extra positional args
+  // (if any) are packed into a fresh list value, which is the arg of the
+  // rest param (if any).
+  //
+  // Returns the args in param order (followed by the rest arg), and the
+  // default stms to run (in reverse order, see `ProcBodyInterpState_`);
+  // a bad call (e.g. an unknown keyword, or a missing arg w/out a default)
+  // is the inner `Err`.
+  pub(crate) fn _bind_call_args(&mut self, clk: LClk, code: StmCodeNum, tup: &[(TermCodeNum, SNum)]) -> Result<Result<(Vec<SNum>, Vec<StmCodeNum>), String>, InterpCheck> {
+    let (params, rest, defaults) = match self.lookup_stm_code(code)? {
+      StmCode_::Defproc{params, rest, defaults, ..} => (params, rest, defaults),
+      _ => return Err(bot())
+    };
+    let mut args: Vec<Option<SNum>> = vec![None; params.len()];
+    let mut rest_buf = Vec::new();
+    let mut pos_ct = 0;
+    let mut kw = false;
+    for &(term, arg) in tup.iter() {
+      match self.lookup_term_code(term)? {
+        TermCode_::Kwarg{id, ..} => {
+          kw = true;
+          let idx = match params.iter().position(|&param| param == Some(id)) {
+            None => {
+              let raw_id = self.lookup_raw_ident(id)?;
+              return Ok(Err(format!("proc got an unexpected keyword argument {:?}", raw_id.as_raw_str())));
+            }
+            Some(idx) => idx
+          };
+          if args[idx].is_some() {
+            let raw_id = self.lookup_raw_ident(id)?;
+            return Ok(Err(format!("proc got multiple values for argument {:?}", raw_id.as_raw_str())));
+          }
+          args[idx] = Some(arg);
+        }
+        _ => {
+          if kw {
+            return Ok(Err("proc got a positional argument after a keyword argument".into()));
           }
-          return Ok(Yield_::Halt);
+          if pos_ct >= params.len() {
+            if rest.is_none() {
+              return Ok(Err(format!("proc expected {} arguments, got {}", params.len(), tup.len())));
+            }
+            rest_buf.push(arg);
+            continue;
+          }
+          args[pos_ct] = Some(arg);
+          pos_ct += 1;
         }
-        Yield_::Eval => {
-          let clk = self.clkctr._get_clock();
-          _debugln!(self, "DEBUG: FastInterp::interp_: yield: clk={:?} eval", clk);
-          _debugln!(self, "DEBUG: FastInterp::interp_: yield:   trace.buf.len={}", self.trace.buf.len());
-          return Err(unimpl());
+      }
+    }
+    let mut buf = Vec::with_capacity(params.len());
+    let mut pending = Vec::new();
+    for (&param, arg) in params.iter().zip(args.into_iter()) {
+      match (arg, param.and_then(|param| defaults.iter().find(|&&(id, _)| id == param))) {
+        (Some(arg), _) => {
+          buf.push(arg);
+        }
+        (None, Some(&(_, default_stm))) => {
+          buf.push(self._fresh()?.try_into_term().map_err(|e| e.into_check())?.into());
+          pending.push(default_stm);
         }
+        (None, None) => {
+          return Ok(Err(format!("proc expected {} arguments, got {}", params.len(), tup.len())));
+        }
+      }
+    }
+    if rest.is_some() {
+      let y = self._fresh()?;
+      self.put_val(clk, y, LitVal_::List{buf: rest_buf})?;
+      buf.push(y);
+    }
+    pending.reverse();
+    Ok(Ok((buf, pending)))
+  }
+
+  // [Interp-API]
+  //
+  // Checks the run limits (see `set_step_limit`) before a control
+  // transition of `resume_`, and counts the transition as a step.
+  pub(crate) fn _check_run_limits(&mut self, stats: &mut InterpRunStats_, t0: Option<Timestamp>) -> Result<Option<Yield_>, InterpCheck> {
+    if let Some(lim) = self.step_lim {
+      if stats.step_ct >= lim {
+        _debugln!(self, "DEBUG: FastInterp::resume_: clk={:?} interrupt (step limit)", self.clkctr._get_clock());
+        return Ok(Some(Yield_::Interrupt));
+      }
+    }
+    if let (Some(lim), Some(t0)) = (self.time_lim, t0) {
+      if stats.step_ct % TIME_CHECK_INTERVAL == 0 && Timestamp::fresh() - t0 >= lim {
+        _debugln!(self, "DEBUG: FastInterp::resume_: clk={:?} interrupt (time limit)", self.clkctr._get_clock());
+        return Ok(Some(Yield_::Interrupt));
+      }
+    }
+    if stats.step_ct % KNT_DEPTH_INTERVAL == 0 {
+      if let Some(knt) = self.knt_.as_ref() {
+        let cap = self.depth_lim.map(|lim| lim + 1).unwrap_or(KNT_DEPTH_CAP);
+        let depth = knt._depth(cap);
+        stats.max_depth = max(stats.max_depth, depth);
+        match self.depth_lim {
+          Some(lim) if depth > lim && !self.exc_.is_some() => {
+            _debugln!(self, "DEBUG: FastInterp::resume_: clk={:?} depth limit: depth={}", self.clkctr._get_clock(), depth);
+            self.exc_ = format!("continuation depth limit exceeded: limit = {}", lim).into();
+          }
+          _ => {}
+        }
+      }
+      if let Some(yield_) = self._mem_sample()? {
+        return Ok(Some(yield_));
       }
     }
+    stats.step_ct += 1;
+    Ok(None)
   }
 
   // [Interp-API]
-  pub fn resume_(&mut self) -> Result<Yield_, InterpCheck> {
+  pub(crate) fn resume_(&mut self) -> Result<Yield_, InterpCheck> {
+    self._resume_run_(None)
+  }
+
+  // [Interp-API]
+  //
+  // W/ the run counters (and the start time) of `interp_`, the run limits
+  // are checked before each control transition, rather than once per
+  // yield, so that a run which never yields (e.g. a deterministic runaway
+  // recursion) is still interrupted.
+  pub(crate) fn _resume_run_(&mut self, mut run: Option<(&mut InterpRunStats_, Option<Timestamp>)>) -> Result<Yield_, InterpCheck> {
     _traceln!(self, "DEBUG: FastInterp::resume_: ...");
     loop {
+      if self.knt_.is_some() {
+        if let Some((stats, t0)) = run.as_mut() {
+          if let Some(yield_) = self._check_run_limits(stats, *t0)? {
+            return Ok(yield_);
+          }
+        }
+      }
       if let Some(snapshot) = self.snapshot.borrow_mut().as_mut() {
         writeln!(snapshot, "{{\"_event\": \"resume-step\"}}").unwrap();
       }
       if self.exc_.is_some() {
         /*self.port = Port_::Except;*/
+        self._locate_exc()?;
         return Ok(Yield_::Raise);
       }
+      if let Some(brk_clk) = self.brk_clk {
+        if self.knt_.is_some() && self.clkctr._get_clock() >= brk_clk {
+          return Ok(Yield_::Break);
+        }
+      }
       let knt = self.knt_.take();
       if knt.is_none() {
+        if !self.eval._is_empty() {
+          // NB: still suspended on a pending eval.
+          return Ok(Yield_::Eval);
+        }
         self.port = Port_::Quiescent;
         return Ok(Yield_::Quiescent);
       }
@@ -4222,37 +10015,69 @@ impl FastInterp {
       for &x in self.env.unifier.root.iter() {
         _traceln!(self, "DEBUG: FastInterp::resume_:       unifier[{:?}]={:?}", x, self.env.unifier._findall(&self.clkinval, clk, x)?);
       }
+      let kind = knt.cur.kind();
+      match transition::_knt_transition(self.port, kind) {
+        transition::KntTransition_::Handled => {}
+        tr => {
+          return Err(format!("bug: FastInterp::resume_: {:?} transition: port={:?} knt={}",
+              tr, self.port, kind.name()).into());
+        }
+      }
       match (self.port, &mut knt.cur) {
         (Port_::Enter, &mut MemKnt_::InterpMod(cur_mod_code, ref mut state)) => {
           _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpMod: state.stmp={:?} (prev)", state.stmp);
           state.stmp = self.lookup_mod_code(cur_mod_code)?.stmp;
           _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpMod: state.stmp={:?} (next)", state.stmp);
-          if state.stmp.is_nil() {
-            self.knt_ = nil();
-            self.port = Port_::Quiescent;
-            return Ok(Yield_::Quiescent);
+          if state.stmp.is_nil() && self.stream.as_ref().map(|s| s.mod_code) == Some(cur_mod_code) {
+            // NB: a streamed module is interpreted stm by stm (see below).
+            self.knt_ = knt.into();
+            self.port = Port_::Return;
+          } else {
+            if state.stmp.is_nil() {
+              self.knt_ = nil();
+              self.port = Port_::Quiescent;
+              return Ok(Yield_::Quiescent);
+            }
+            let next_stmp = state.stmp;
+            self.knt_ = MemKnt{
+              clk,
+              prev: knt.into(),
+              cur:  MemKnt_::InterpStmp(next_stmp, StmCodeCellInterpState_::fresh(next_stmp)),
+            }.into_ref();
+            /*self.port = Port_::Enter;*/
           }
-          let next_stmp = state.stmp;
-          self.knt_ = MemKnt{
-            clk,
-            prev: knt.into(),
-            cur:  MemKnt_::InterpStmp(next_stmp, StmCodeCellInterpState_::fresh(next_stmp)),
-          }.into_ref();
-          /*self.port = Port_::Enter;*/
         }
-        (Port_::Return, &mut MemKnt_::InterpMod(_cur_mod_code, ref mut state)) => {
-          self.knt_ = nil();
-          self.port = Port_::Quiescent;
-          return Ok(Yield_::Quiescent);
+        (Port_::Return, &mut MemKnt_::InterpMod(cur_mod_code, ref mut state)) => {
+          match self._stream_next(clk, cur_mod_code)? {
+            stream::StreamNext_::Stm(stm) => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpMod: stream: stm={:?}", stm);
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpStm(stm, StmCodeInterpState_::fresh()),
+              }.into_ref();
+              self.port = Port_::Enter;
+            }
+            stream::StreamNext_::Pending => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpMod: stream: pending");
+              self.knt_ = knt.into();
+              return Ok(Yield_::Interrupt);
+            }
+            stream::StreamNext_::Done => {
+              self.knt_ = nil();
+              self.port = Port_::Quiescent;
+              return Ok(Yield_::Quiescent);
+            }
+          }
         }
         (Port_::Enter, &mut MemKnt_::InterpStmp(_cur_stm_code_ptr, ref mut state)) => {
           if state.stmp.is_nil() {
-            self.knt_ = knt.prev;
+            self.knt_ = knt.prev.take();
             self.port = Port_::Return;
           } else {
             let stmp_ = self.lookup_stm_code_cell(state.stmp)?;
-            let stm = stmp_.dptr.into_stm_code();
-            state.stmp = stmp_.next.get().into_stm_code();
+            let stm = stmp_.dptr.try_into_stm_code().map_err(|e| e.into_check())?;
+            state.stmp = stmp_.next.get().try_into_stm_code().map_err(|e| e.into_check())?;
             self.knt_ = MemKnt{
               clk,
               prev: knt.into(),
@@ -4266,19 +10091,32 @@ impl FastInterp {
           self.port = Port_::Enter;
         }
         (Port_::Enter, &mut MemKnt_::InterpStm(cur_stm_code, ref mut state)) => {
+          if let Some(prov) = self.prov.as_mut() {
+            prov._set_stm(cur_stm_code);
+          }
           let cur_stm_code_ = self.lookup_stm_code(cur_stm_code)?;
+          if self._break_stm(cur_stm_code, cur_stm_code_._span()) {
+            self.knt_ = knt.into();
+            return Ok(Yield_::Break);
+          }
           match cur_stm_code_ {
             StmCode_::Just{span, term} => {
               self.reset_res()?;
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
               }.into_ref();
               /*self.port = Port_::Enter;*/
             }
             // FIXME: this quote _statement_ was a parsing hack.
-            StmCode_::Quote{..} => {
+            StmCode_::Quote{body_stmp, ..} => {
+              // NB: the quoted stms are not run; rather, the quote is
+              // reified as a term, which is the result of this stm.
+              self.reset_res()?;
+              let x = self._reify_quote(clk, body_stmp)?;
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Quote: x={:?}", x);
+              self.put_res(x)?;
               self.knt_ = knt.into();
               self.port = Port_::Return;
             }
@@ -4288,46 +10126,526 @@ impl FastInterp {
               self.port = Port_::Return;
             }
             StmCode_::Global{span, id} => {
-              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Global: ...");
-              // FIXME
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Global: id={:?}", id);
+              let scope = self.env.ns_cur;
+              if scope.is_nil() {
+                // NB: at module scope, `global` eagerly binds the ident
+                // in the module namespace, which shadows any builtin.
+                if !self.env.id_global_bind.contains_key(&id) {
+                  self._bind_fresh_ident(clk, NamespaceScope_::Module, id)?;
+                }
+              } else {
+                let ns = self.env.ns_full.get_mut(&scope).ok_or_else(|| bot::<InterpCheck>())?;
+                if ns.bind.contains_key(&id) || ns.nonlocal.contains_key(&id) {
+                  let raw_id = self.lookup_raw_ident(id)?.clone();
+                  self.exc_ = format!("name {:?} is used prior to global declaration", raw_id.as_raw_str()).into();
+                } else if ns.global.insert(id) {
+                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::DeclGlobalIdent(scope, id).into()));
+                }
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            StmCode_::Assert{span, term} => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Assert: term={:?}", term);
+              let mat = match self._run_scoped_goal(term)? {
+                Err(yield_) => {
+                  self.knt_ = knt.into();
+                  return Ok(yield_);
+                }
+                Ok(v) => v
+              };
+              if !mat {
+                let text = self.lookup_term_code(term)?._span().ok()
+                  .and_then(|term_span| self._span_text(term_span))
+                  .unwrap_or_default();
+                self._record_test_failure(span, "assert", vec![text]);
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            StmCode_::Expect{span, lterm, rterm} => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Expect: lterm={:?} rterm={:?}", lterm, rterm);
+              let mut sides = Vec::with_capacity(2);
+              for &term in [lterm, rterm].iter() {
+                match self._scoped_value(term)? {
+                  Err(yield_) => {
+                    self.knt_ = knt.into();
+                    return Ok(yield_);
+                  }
+                  Ok(side) => sides.push(side)
+                }
+              }
+              let mat = match (&sides[0], &sides[1]) {
+                (&Some((ref lflat, _)), &Some((ref rflat, _))) => lflat == rflat,
+                _ => false
+              };
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Expect: mat={:?} sides={:?}", mat, sides);
+              if !mat {
+                let operands = sides.into_iter().map(|side| match side {
+                  None => "<fail>".to_string(),
+                  Some((_, s)) => s
+                }).collect();
+                self._record_test_failure(span, "expect", operands);
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            StmCode_::Fresh{span, ids} => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Fresh: ids={:?}", ids);
+              let scope = self.env.ns_cur;
+              let mut decl = None;
+              if !scope.is_nil() {
+                let ns = self.lookup_namespace(scope)?;
+                decl = ids.iter().copied().find(|&id| ns.is_global(id) || ns.is_nonlocal(id).is_some());
+              }
+              if let Some(id) = decl {
+                // NB: a global or nonlocal ident does not belong to the
+                // current scope, so it cannot be shadowed here.
+                let raw_id = self.lookup_raw_ident(id)?.clone();
+                self.exc_ = format!("name {:?} is declared global or nonlocal prior to fresh declaration", raw_id.as_raw_str()).into();
+              } else {
+                for &id in ids.iter() {
+                  self._bind_fresh_var(clk, id)?;
+                }
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            StmCode_::Import{span, ids} => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Import: ids={:?}", ids);
+              for &id in ids.iter() {
+                let m = match self._find_module(clk, id)? {
+                  None => {
+                    let raw_id = self.lookup_raw_ident(id)?.clone();
+                    self.exc_ = format!("no module named {:?}", raw_id.as_raw_str()).into();
+                    break;
+                  }
+                  Some(m) => m
+                };
+                let target = self._bind_target(id)?;
+                match self._resolve_ident_in(target, id) {
+                  (_, None) => {
+                    self._bind_ident(clk, target, id, m.into())?;
+                  }
+                  (_, Some(y)) => {
+                    self.unify(clk, y, m)?;
+                  }
+                }
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            StmCode_::Nonlocal{span, static_scope, id} => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Nonlocal: id={:?} static scope={:?}", id, static_scope);
+              let scope = self.env.ns_cur;
+              if scope.is_nil() {
+                // NB: at module scope, there is no enclosing local scope,
+                // so `nonlocal` is a no-op (the ident resolves in the
+                // module namespace regardless).
+              } else {
+                let ns = self.env.ns_full.get_mut(&scope).ok_or_else(|| bot::<InterpCheck>())?;
+                if ns.bind.contains_key(&id) || ns.global.contains(&id) {
+                  let raw_id = self.lookup_raw_ident(id)?.clone();
+                  self.exc_ = format!("name {:?} is used prior to nonlocal declaration", raw_id.as_raw_str()).into();
+                } else if ns.nonlocal.insert(id, static_scope).is_none() {
+                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::DeclNonlocalIdent(scope, id).into()));
+                }
+              }
               self.knt_ = knt.into();
               self.port = Port_::Return;
             }
-            StmCode_::Nonlocal{span, static_scope, id} => {
-              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Nonlocal: ...");
-              // FIXME
+            StmCode_::If{span, cases, final_case} => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: If: ");
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpIfStm(
+                    cur_stm_code,
+                    IfStmCodeInterpState_::fresh(cases, final_case)
+                ),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            StmCode_::Match{scrut, cases, ..} => {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Match: ");
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpMatchStm(
+                    cur_stm_code,
+                    MatchStmCodeInterpState_::fresh(scrut, cases)
+                ),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            StmCode_::Cases{span, cases} => {
+              // NB: the choice point resumes at this stm, so that the
+              // chosen body replaces this stm in the continuation.
+              let idx = if cases.len() <= 1 {
+                0
+              } else {
+                let knt_ = BorrowedMemKnt{
+                  clk:  knt.clk,
+                  prev: &knt.prev,
+                  cur:  MemKnt_::InterpStm(cur_stm_code, state.clone()),
+                };
+                match self._choose_index(clk, span, cases.len(), knt_)? {
+                  Err(yield_) => {
+                    return Ok(yield_);
+                  }
+                  Ok(idx) => idx
+                }
+              };
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Cases: idx={}", idx);
+              let body = cases[idx];
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpStmp(body, StmCodeCellInterpState_::fresh(body)),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            StmCode_::Defproc{id, ..} => {
+              let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+              let term_ = ProcTerm_{code: cur_stm_code, scope: self.env.ns_cur};
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Defproc: x={:?} term={:?}", x, term_);
+              self.put_term(clk, x, term_)?;
+              let target = self._bind_target(id)?;
+              match self._resolve_ident_in(target, id) {
+                (_, None) => {
+                  self._bind_ident(clk, target, id, x.into())?;
+                }
+                (_, Some(y)) => {
+                  // NB: an ident that was already bound (e.g. by a prior
+                  // occurrence) is unified w/ the proc.
+                  self.unify(clk, y, x)?;
+                }
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            StmCode_::Class{..} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpClassStm(cur_stm_code, ClassStmCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            StmCode_::Module{..} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpModuleStm(cur_stm_code, ModuleStmCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            StmCode_::Defmatch{id, ..} => {
+              // NB: the clause was already indexed when loaded; the name
+              // is bound to the defmatch once, by its first clause, and a
+              // call tries the candidate clauses (see `InterpApplyTerm`).
+              let target = self._bind_target(id)?;
+              match self._resolve_ident_in(target, id) {
+                (_, None) => {
+                  let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+                  let term_ = MatchTerm_{id, scope: self.env.ns_cur};
+                  _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: Defmatch: x={:?} term={:?}", x, term_);
+                  self.put_term(clk, x, term_)?;
+                  self._bind_ident(clk, target, id, x.into())?;
+                }
+                (_, Some(y)) => {
+                  match self._find_match(clk, y)? {
+                    Some(m) if m.id == id => {}
+                    _ => {
+                      let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+                      let term_ = MatchTerm_{id, scope: self.env.ns_cur};
+                      self.put_term(clk, x, term_)?;
+                      self.unify(clk, y, x)?;
+                    }
+                  }
+                }
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            StmCode_::Defrule{..} => {
+              // NB: the rule was already indexed when loaded.
+              self.knt_ = knt.into();
+              self.port = Port_::Return;
+            }
+            cur_stm_code_ => {
+              return Err(format!("{:?}", cur_stm_code_).into());
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpClassStm(cur_stm_code, ref mut state)) => {
+          let body_stmp = match self.lookup_stm_code(cur_stm_code)? {
+            StmCode_::Class{body_stmp, ..} => body_stmp,
+            _ => return Err(bot())
+          };
+          state.scope = self._alloc_namespace(clk, self.env.ns_cur)?;
+          state.save_scope = self._switch_namespace(clk, state.scope);
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpClassStm: scope={:?} save scope={:?}", state.scope, state.save_scope);
+          self.knt_ = MemKnt{
+            clk,
+            prev: knt.into(),
+            cur:  MemKnt_::InterpStmp(body_stmp, StmCodeCellInterpState_::fresh(body_stmp)),
+          }.into_ref();
+          /*self.port = Port_::Enter;*/
+        }
+        (Port_::Return, &mut MemKnt_::InterpClassStm(cur_stm_code, ref mut state)) => {
+          let id = match self.lookup_stm_code(cur_stm_code)? {
+            StmCode_::Class{id, ..} => id,
+            _ => return Err(bot())
+          };
+          self._switch_namespace(clk, state.save_scope);
+          self.reset_res()?;
+          let name = self.lookup_raw_ident(id)?.clone();
+          let cls = self._load_obj_cls(self::prelude::ClassCls::new(name, state.scope))?;
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpClassStm: scope={:?} cls={:?}", state.scope, cls);
+          let target = self._bind_target(id)?;
+          match self._resolve_ident_in(target, id) {
+            (_, None) => {
+              self._bind_ident(clk, target, id, cls.into())?;
+            }
+            (_, Some(y)) => {
+              self.unify(clk, y, cls)?;
+            }
+          }
+          self.knt_ = knt.prev.take();
+          /*self.port = Port_::Return;*/
+        }
+        (Port_::Enter, &mut MemKnt_::InterpModuleStm(cur_stm_code, ref mut state)) => {
+          let body_stmp = match self.lookup_stm_code(cur_stm_code)? {
+            StmCode_::Module{body_stmp, ..} => body_stmp,
+            _ => return Err(bot())
+          };
+          // NB: the module scope is enclosed by the global namespace, not
+          // by the scope of the loading module.
+          state.scope = self._alloc_namespace(clk, nil())?;
+          state.save_scope = self._switch_namespace(clk, state.scope);
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpModuleStm: scope={:?} save scope={:?}", state.scope, state.save_scope);
+          if body_stmp.is_nil() {
+            self.knt_ = knt.into();
+            self.port = Port_::Return;
+          } else {
+            self.knt_ = MemKnt{
+              clk,
+              prev: knt.into(),
+              cur:  MemKnt_::InterpStmp(body_stmp, StmCodeCellInterpState_::fresh(body_stmp)),
+            }.into_ref();
+            /*self.port = Port_::Enter;*/
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpModuleStm(cur_stm_code, ref mut state)) => {
+          let id = match self.lookup_stm_code(cur_stm_code)? {
+            StmCode_::Module{id, ..} => id,
+            _ => return Err(bot())
+          };
+          self._switch_namespace(clk, state.save_scope);
+          self.reset_res()?;
+          let name = self.lookup_raw_ident(id)?.clone();
+          let m = self._load_obj_val(clk, Box::new(self::prelude::ModuleVal::new(name, state.scope)))?;
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpModuleStm: scope={:?} module={:?}", state.scope, m);
+          // NB: the module is bound by name in the global namespace, from
+          // which it is imported (see `_find_module`).
+          match self.env.id_global_bind.get(&id) {
+            None => {
+              self._bind_ident(clk, NamespaceScope_::Module, id, m.into())?;
+            }
+            Some(&y) => {
+              self.unify(clk, y, m)?;
+            }
+          }
+          self.knt_ = knt.prev.take();
+          /*self.port = Port_::Return;*/
+        }
+        (Port_::Return, &mut MemKnt_::InterpStm(_cur_stm_code, ref mut state)) => {
+          self.knt_ = knt.prev.take();
+          /*self.port = Port_::Return;*/
+        }
+        (Port_::Enter, &mut MemKnt_::InterpProcBody(cur_stm_code, ref mut state)) => {
+          // NB: a defmatch clause is interpreted as a proc body (see
+          // `InterpApplyTerm`).
+          let (params, rest, body_stmp) = match self.lookup_stm_code(cur_stm_code)? {
+            StmCode_::Defproc{params, rest, body_stmp, ..} => (params, rest, body_stmp),
+            StmCode_::Defmatch{params, body_stmp, ..} => (params, None, body_stmp),
+            _ => return Err(bot())
+          };
+          if let Some(key) = state.table.as_ref() {
+            let knt_ = BorrowedMemKnt{
+              clk:  knt.clk,
+              prev: &knt.prev,
+              cur:  MemKnt_::InterpProcBody(cur_stm_code, state.clone()),
+            };
+            if let Some(yield_) = self._goal_produce(clk, key, knt_)? {
+              return Ok(yield_);
+            }
+          }
+          // NB: the rest arg (if any) follows the param args.
+          if params.len() + rest.is_some() as usize != state.args.len() {
+            self.exc_ = format!("proc expected {} arguments, got {}", params.len(), state.args.len()).into();
+            self.knt_ = knt.into();
+          } else {
+            let scope = self._alloc_namespace(clk, state.scope)?;
+            for (&param, &arg) in params.iter().chain(Some(&rest)).zip(state.args.iter()) {
+              if let Some(param) = param {
+                self._bind_ident(clk, NamespaceScope_::Local(scope), param, arg)?;
+              }
+            }
+            state.save_scope = self._switch_namespace(clk, scope);
+            _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpProcBody: scope={:?} save scope={:?}", scope, state.save_scope);
+            let cur = match state.defaults.pop() {
+              Some(default_stm) => {
+                MemKnt_::InterpStm(default_stm, StmCodeInterpState_::fresh())
+              }
+              None => {
+                state.in_body = true;
+                MemKnt_::InterpStmp(body_stmp, StmCodeCellInterpState_::fresh(body_stmp))
+              }
+            };
+            self.knt_ = MemKnt{
+              clk,
+              prev: knt.into(),
+              cur,
+            }.into_ref();
+            /*self.port = Port_::Enter;*/
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpProcBody(cur_stm_code, ref mut state)) if !state.in_body => {
+          // NB: a default stm returned; run the next one, or the body.
+          let body_stmp = match self.lookup_stm_code(cur_stm_code)? {
+            StmCode_::Defproc{body_stmp, ..} |
+            StmCode_::Defmatch{body_stmp, ..} => body_stmp,
+            _ => return Err(bot())
+          };
+          self.reset_res()?;
+          let cur = match state.defaults.pop() {
+            Some(default_stm) => {
+              MemKnt_::InterpStm(default_stm, StmCodeInterpState_::fresh())
+            }
+            None => {
+              state.in_body = true;
+              MemKnt_::InterpStmp(body_stmp, StmCodeCellInterpState_::fresh(body_stmp))
+            }
+          };
+          self.knt_ = MemKnt{
+            clk,
+            prev: knt.into(),
+            cur,
+          }.into_ref();
+          self.port = Port_::Enter;
+        }
+        (Port_::Return, &mut MemKnt_::InterpProcBody(_cur_stm_code, ref mut state)) => {
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpProcBody: restore scope={:?}", state.save_scope);
+          if let Some(key) = state.table.take() {
+            if !self._goal_answer(clk, &key, &state.args, state.ret)? {
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpProcBody: tabled: duplicate answer");
+              return Ok(Yield_::Fail);
+            }
+          }
+          self._switch_namespace(clk, state.save_scope);
+          self.reset_res()?;
+          self.put_res(state.ret)?;
+          self.knt_ = knt.prev.take();
+          /*self.port = Port_::Return;*/
+        }
+        (Port_::Enter, &mut MemKnt_::InterpUnquote(body_stmp, ref mut state)) => {
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpUnquote: body stmp={:?} ret={:?}", body_stmp, state.ret);
+          self.reset_res()?;
+          self.knt_ = MemKnt{
+            clk,
+            prev: knt.into(),
+            cur:  MemKnt_::InterpStmp(body_stmp, StmCodeCellInterpState_::fresh(body_stmp)),
+          }.into_ref();
+          /*self.port = Port_::Enter;*/
+        }
+        (Port_::Return, &mut MemKnt_::InterpUnquote(_body_stmp, ref mut state)) => {
+          // NB: an unquoted body w/out a result leaves `ret` unbound.
+          if let ResReg_::Key(y) = self.res_.get() {
+            self.unify(clk, y, state.ret)?;
+          }
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpUnquote: ret={:?}", state.ret);
+          self.reset_res()?;
+          self.put_res(state.ret)?;
+          self.knt_ = knt.prev.take();
+          /*self.port = Port_::Return;*/
+        }
+        (Port_::Enter, &mut MemKnt_::InterpLambdaBody(cur_term_code, ref mut state)) => {
+          let (params, body) = match self.lookup_term_code(cur_term_code)? {
+            TermCode_::Lambda{params, body, ..} => (params, body),
+            _ => return Err(bot())
+          };
+          if params.len() != state.args.len() {
+            self.exc_ = format!("lambda expected {} arguments, got {}", params.len(), state.args.len()).into();
+            self.knt_ = knt.into();
+          } else {
+            let scope = self._alloc_namespace(clk, state.scope)?;
+            for (&param, &arg) in params.iter().zip(state.args.iter()) {
+              if let Some(param) = param {
+                self._bind_ident(clk, NamespaceScope_::Local(scope), param, arg)?;
+              }
+            }
+            state.save_scope = self._switch_namespace(clk, scope);
+            _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpLambdaBody: scope={:?} save scope={:?}", scope, state.save_scope);
+            self.knt_ = MemKnt{
+              clk,
+              prev: knt.into(),
+              cur:  MemKnt_::InterpTerm(body, TermCodeInterpState_::fresh()),
+            }.into_ref();
+            /*self.port = Port_::Enter;*/
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpLambdaBody(_cur_term_code, ref mut state)) => {
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpLambdaBody: restore scope={:?}", state.save_scope);
+          let y = self.get_res()?;
+          self._switch_namespace(clk, state.save_scope);
+          self.unify(clk, y, state.ret)?;
+          self.reset_res()?;
+          self.put_res(state.ret)?;
+          self.knt_ = knt.prev.take();
+          /*self.port = Port_::Return;*/
+        }
+        (Port_::Enter, &mut MemKnt_::InterpEvalCode(code, ref mut state)) => {
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpEvalCode: code={:?} ret={:?}", code, state.ret);
+          let cur = match self.env.table_full[SNumSort::Code as usize].get(&code) {
+            None => None,
+            Some(e) => {
+              let inner = e.inner.as_any();
+              if inner.downcast_ref::<StmCode_>().is_some() {
+                Some(MemKnt_::InterpStm(code.try_into_stm_code().map_err(|e| e.into_check())?, StmCodeInterpState_::fresh()))
+              } else if inner.downcast_ref::<TermCode_>().is_some() {
+                Some(MemKnt_::InterpTerm(code.try_into_term_code().map_err(|e| e.into_check())?, TermCodeInterpState_::fresh()))
+              } else {
+                None
+              }
+            }
+          };
+          match cur {
+            None => {
+              self.exc_ = "eval: not a stm or term code".into();
               self.knt_ = knt.into();
-              self.port = Port_::Return;
             }
-            StmCode_::If{span, cases, final_case} => {
-              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpStm: If: ");
+            Some(cur) => {
+              self.reset_res()?;
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
-                cur:  MemKnt_::InterpIfStm(
-                    cur_stm_code,
-                    IfStmCodeInterpState_::fresh(cases, final_case)
-                ),
+                prev: knt.into(),
+                cur,
               }.into_ref();
               /*self.port = Port_::Enter;*/
             }
-            StmCode_::Defproc{..} => {
-              // FIXME
-              self.knt_ = knt.into();
-              self.port = Port_::Return;
-            }
-            StmCode_::Defmatch{..} => {
-              // FIXME
-              self.knt_ = knt.into();
-              self.port = Port_::Return;
-            }
-            cur_stm_code_ => {
-              return Err(format!("{:?}", cur_stm_code_).into());
-            }
           }
         }
-        (Port_::Return, &mut MemKnt_::InterpStm(_cur_stm_code, ref mut state)) => {
-          self.knt_ = knt.prev.into();
+        (Port_::Return, &mut MemKnt_::InterpEvalCode(_code, ref mut state)) => {
+          if let ResReg_::Key(y) = self.res_.get() {
+            self.unify(clk, y, state.ret)?;
+          }
+          _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpEvalCode: ret={:?}", state.ret);
+          self.reset_res()?;
+          self.put_res(state.ret)?;
+          self.knt_ = knt.prev.take();
           /*self.port = Port_::Return;*/
         }
         (Port_::Enter, &mut MemKnt_::InterpIfStm(_cur_stm_code, ref mut state)) => {
@@ -4366,7 +10684,7 @@ impl FastInterp {
               /*self.port = Port_::Enter;*/
             }
             IfStmCodeInterpCursor_::Fin => {
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -4423,37 +10741,152 @@ impl FastInterp {
             }
           }
         }
+        (Port_::Enter, &mut MemKnt_::InterpMatchStm(cur_stm_code, ref mut state)) => {
+          match state.cur {
+            MatchStmCodeInterpCursor_::Scrut{scrut} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(scrut, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            MatchStmCodeInterpCursor_::Case{case_idx} => {
+              if case_idx >= state.cases.len() {
+                // NB: no case matched, or every matching case failed.
+                _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpMatchStm: Case: fallthrough");
+                return Ok(Yield_::Fail);
+              }
+              let pat = state.cases[case_idx].0;
+              let save_tctx = self.reg.tctx;
+              self.reg.tctx = TermContext_::Match;
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpMatchStm: Case: idx={} save tctx={:?}", case_idx, save_tctx);
+              if state.save_tctx.replace(save_tctx).is_some() {
+                return Err(bot());
+              }
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(pat, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            MatchStmCodeInterpCursor_::Choose{case_idx, pat} => {
+              // NB: unless `match_commit` is set (or this is the last
+              // case), the body is tried at a choice point, whose other
+              // branch tries the later cases. The pattern captures the
+              // scrutinee after the choice, so that the capture is undone
+              // upon backtracking.
+              let alt = if self.match_commit || case_idx + 1 >= state.cases.len() {
+                0
+              } else {
+                let span = self.lookup_stm_code(cur_stm_code)?._span();
+                let knt_ = BorrowedMemKnt{
+                  clk:  knt.clk,
+                  prev: &knt.prev,
+                  cur:  MemKnt_::InterpMatchStm(cur_stm_code, state.clone()),
+                };
+                match self._choose_index(clk, span, 2, knt_)? {
+                  Err(yield_) => {
+                    return Ok(yield_);
+                  }
+                  Ok(alt) => alt
+                }
+              };
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpMatchStm: Choose: idx={} alt={}", case_idx, alt);
+              if alt == 0 {
+                if let Some(pat) = pat {
+                  let scrut = state.scrut.unwrap();
+                  if !self._match_pat(clk, scrut, pat, true)? {
+                    _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  InterpMatchStm: unify mode: fail");
+                    return Ok(Yield_::Fail);
+                  }
+                }
+                state.cur = MatchStmCodeInterpCursor_::Body{body: state.cases[case_idx].1};
+              } else {
+                state.cur = MatchStmCodeInterpCursor_::Case{case_idx: case_idx + 1};
+              }
+              self.knt_ = knt.into();
+              /*self.port = Port_::Enter;*/
+            }
+            MatchStmCodeInterpCursor_::Body{body} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpStmp(body, StmCodeCellInterpState_::fresh(body)),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            MatchStmCodeInterpCursor_::Fin => {
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpMatchStm(_cur_stm_code, ref mut state)) => {
+          match state.cur {
+            MatchStmCodeInterpCursor_::Scrut{..} => {
+              state.scrut = Some(self.get_res()?);
+              state.cur = MatchStmCodeInterpCursor_::Case{case_idx: 0};
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            MatchStmCodeInterpCursor_::Case{case_idx} => {
+              let save_tctx = match state.save_tctx.take() {
+                None => return Err(bot()),
+                Some(tctx) => tctx
+              };
+              self.reg.tctx = save_tctx;
+              let (mat, pat) = match self.res_.get() {
+                ResReg_::Emp => {
+                  return Err(format!("expected result register").into());
+                }
+                ResReg_::Key(y) => {
+                  let scrut = state.scrut.unwrap();
+                  (self._match_pat(clk, scrut, y, false)?, Some(y))
+                }
+                ResReg_::Mat(mat) => (mat, None)
+              };
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Return InterpMatchStm: Case: idx={} mat={:?}", case_idx, mat);
+              if mat {
+                state.cur = MatchStmCodeInterpCursor_::Choose{case_idx, pat};
+              } else {
+                state.cur = MatchStmCodeInterpCursor_::Case{case_idx: case_idx + 1};
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            MatchStmCodeInterpCursor_::Body{..} => {
+              state.cur = MatchStmCodeInterpCursor_::Fin;
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            MatchStmCodeInterpCursor_::Choose{..} |
+            MatchStmCodeInterpCursor_::Fin => {
+              return Err(bot());
+            }
+          }
+        }
         (Port_::Return, &mut MemKnt_::InterpStm(cur_stm, ref mut state)) => {
-          self.knt_ = knt.prev;
+          self.knt_ = knt.prev.take();
           /*self.port = Port_::Return;*/
         }
         (Port_::Enter, &mut MemKnt_::InterpTerm(cur_term_code, ref mut state)) => {
           let cur_term_code_ = self.lookup_term_code(cur_term_code)?;
           match cur_term_code_ {
             TermCode_::Ident{span, id} => {
-              let raw_id = self.lookup_raw_ident(id)?.clone();
-              match self.env.id_global_bind.get(&id) {
-                Some(&x) => {
+              match self.resolve_ident(id)? {
+                (scope, Some(x)) => {
+                  _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  Ident: id={:?} scope={:?} x={:?}", id, scope, x);
                   self.put_res(x)?;
-                  self.knt_ = knt.prev;
+                  self.knt_ = knt.prev.take();
                   self.port = Port_::Return;
                 }
-                None => {
-                  let x = self._fresh().into_term();
-                  let term_ = IdentTerm_{id, raw_id: raw_id.clone()};
-                  self.put_term(clk, x, term_)?;
-                  let prev_x = self.env.id_global_bind.insert(id, x.into()).try_into_nil()
-                      .map_err(|_| format!("nil-bound ident: id = {:?} raw id = {:?}", id, raw_id).into_check())?;
-                  // FIXME: global scope.
-                  /*if false {
-                    let prev_global_x = self.env.id_global_bind.insert(id, x.into()).try_into_nil()
-                        .map_err(|_| format!("nil-bound global ident: id = {:?} raw id = {:?}", id, raw_id).into_check())?;
-                    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindGlobalIdent(id, prev_x, prev_global_x).into()));
-                  } else {
-                  }*/
-                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindGlobalIdent(id, prev_x).into()));
+                (scope, None) => {
+                  let x = self._bind_fresh_ident(clk, scope, id)?;
+                  _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  Ident: id={:?} scope={:?} fresh x={:?}", id, scope, x);
                   self.put_res(x)?;
-                  self.knt_ = knt.prev;
+                  self.knt_ = knt.prev.take();
                   self.port = Port_::Return;
                 }
               }
@@ -4461,7 +10894,7 @@ impl FastInterp {
             TermCode_::QualIdent{span, term, id} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpQualIdentTerm(cur_term_code, QualIdentTermCodeInterpState_::fresh(term, id)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
@@ -4474,20 +10907,20 @@ impl FastInterp {
               let lit_term_ = LitTerm_::new_str(inner_val.clone());
               let x = match self.env.lit_term_bind.get(&lit_term_) {
                 None => {
-                  let x = self._fresh();
+                  let x = self._fresh()?;
                   self.put_term(clk, x, lit_term_.clone())?;
-                  let y = self._fresh();
+                  let y = self._fresh()?;
                   let val = LitVal_::Atom(inner_val);
                   self.put_val(clk, y, val)?;
                   self.unify(clk, x, y)?;
-                  /*let prev_x = self.env.lit_term_bind.insert(lit_term_, x.into());
-                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitStr(lit_str, prev_x).into()));*/
+                  /*let prev_x = self.env.lit_term_bind.insert(lit_term_.clone(), x.into());
+                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitTerm(lit_term_, prev_x).into()));*/
                   x
                 }
                 Some(&x) => x
               };
               self.put_res(x)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
             TermCode_::IntLit{span, lit_str} => {
@@ -4496,10 +10929,10 @@ impl FastInterp {
               let lit_term_ = LitTerm_::new_int(inner_val);
               let x = match self.env.lit_term_bind.get(&lit_term_) {
                 None => {
-                  let x = self._fresh();
+                  let x = self._fresh()?;
                   self.put_term(clk, x, lit_term_.clone())?;
-                  /*let prev_x = self.env.lit_term_bind.insert(lit_term_, x.into());
-                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitStr(lit_str, prev_x).into()));*/
+                  /*let prev_x = self.env.lit_term_bind.insert(lit_term_.clone(), x.into());
+                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitTerm(lit_term_, prev_x).into()));*/
                   x
                 }
                 Some(&x) => x
@@ -4507,7 +10940,7 @@ impl FastInterp {
               let val = LitVal_::Int(inner_val);
               let y = match self.env.lit_val_bind.get(&val) {
                 None => {
-                  let y = self._fresh();
+                  let y = self._fresh()?;
                   self.put_val(clk, y, val.clone())?;
                   let prev_y = self.env.lit_val_bind.insert(val.clone(), y.into());
                   self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitVal(val, prev_y).into()));
@@ -4517,7 +10950,7 @@ impl FastInterp {
               };
               self.unify(clk, x, y)?;
               self.put_res(x)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
             TermCode_::BoolLit{span, lit_str} => {
@@ -4532,20 +10965,20 @@ impl FastInterp {
               let lit_term_ = LitTerm_::new_bool(inner_val);
               let x = match self.env.lit_term_bind.get(&lit_term_) {
                 None => {
-                  let x = self._fresh();
+                  let x = self._fresh()?;
                   self.put_term(clk, x, lit_term_.clone())?;
-                  let y = self._fresh();
+                  let y = self._fresh()?;
                   let val = LitVal_::Bool(inner_val);
                   self.put_val(clk, y, val)?;
                   self.unify(clk, x, y)?;
-                  let prev_x = self.env.lit_term_bind.insert(lit_term_, x.into());
-                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitStr(lit_str, prev_x).into()));
+                  let prev_x = self.env.lit_term_bind.insert(lit_term_.clone(), x.into());
+                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitTerm(lit_term_, prev_x).into()));
                   x
                 }
                 Some(&x) => x
               };
               self.put_res(x)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
             TermCode_::NoneLit{span, lit_str} => {
@@ -4559,45 +10992,82 @@ impl FastInterp {
               let lit_term_ = LitTerm_::new_none();
               let x = match self.env.lit_term_bind.get(&lit_term_) {
                 None => {
-                  let x = self._fresh();
+                  let x = self._fresh()?;
                   self.put_term(clk, x, lit_term_.clone())?;
-                  let y = self._fresh();
+                  let y = self._fresh()?;
                   let val = LitVal_::None;
                   self.put_val(clk, y, val)?;
                   self.unify(clk, x, y)?;
-                  let prev_x = self.env.lit_term_bind.insert(lit_term_, x.into());
-                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitStr(lit_str, prev_x).into()));
+                  let prev_x = self.env.lit_term_bind.insert(lit_term_.clone(), x.into());
+                  self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::BindLitTerm(lit_term_, prev_x).into()));
                   x
                 }
                 Some(&x) => x
               };
               self.put_res(x)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
             TermCode_::ListCon{span, tup} => {
-              let x = self._fresh();
+              let x = self._fresh()?;
               // FIXME: build actual list obj val from this constructor.
               let obj = LitVal_::List{buf: Vec::new()};
               _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  ListCon: fresh obj val = {:?}", x);
               /*self.env.table_full[SNumSort::Val as usize].insert(x.into(), Box::new(obj));*/
               self.put_val(clk, x, obj)?;
               self.put_res(x)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
             TermCode_::Group{span, term} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            // NB: a keyword arg evaluates to its value; the keyword is
+            // matched to a param by the proc apply (see `_bind_call_args`).
+            // Likewise, a splat arg evaluates to its (list) value, which
+            // is expanded by the apply (see `_expand_splats`).
+            TermCode_::Kwarg{span, term, ..} |
+            TermCode_::Splat{span, term} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
               }.into_ref();
               /*self.port = Port_::Enter;*/
             }
+            TermCode_::ListComp{..} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpListCompTerm(cur_term_code, ListCompTermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            TermCode_::Lambda{..} => {
+              let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+              let term_ = LambdaTerm_{code: cur_term_code, scope: self.env.ns_cur};
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  Lambda: x={:?} term={:?}", x, term_);
+              self.put_term(clk, x, term_)?;
+              self.put_res(x)?;
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
+            TermCode_::Quote{body_stmp, ..} => {
+              let x = self._reify_quote(clk, body_stmp)?;
+              _traceln!(self, "DEBUG: FastInterp::resume_:   Enter  Quote: x={:?}", x);
+              self.put_res(x)?;
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
             TermCode_::Bunch{span, tup} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpBunchTerm(cur_term_code, BunchTermCodeInterpState_::fresh(tup)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
@@ -4605,15 +11075,72 @@ impl FastInterp {
             TermCode_::Equal{span, lterm, rterm} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpEqualTerm(cur_term_code, EqualTermCodeInterpState_::fresh(lterm, rterm)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
             }
+            TermCode_::In{span, lterm, rterm} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpInTerm(cur_term_code, InTermCodeInterpState_::fresh(lterm, rterm)),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            TermCode_::And{span, lterm, rterm} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpAndTerm(cur_term_code, AndTermCodeInterpState_::fresh(lterm, rterm)),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            TermCode_::Or{span, lterm, rterm} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpOrTerm(cur_term_code, OrTermCodeInterpState_::fresh(lterm, rterm)),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            TermCode_::Not{span, term} => {
+              // NB: the inner goal is run to completion here, in its own
+              // scope (see `_run_scoped_goal`), and its effects are then
+              // rolled back; so, a negation never binds.
+              let mat = match self._run_scoped_goal(term)? {
+                Err(yield_) => {
+                  self.knt_ = knt.into();
+                  return Ok(yield_);
+                }
+                Ok(v) => !v
+              };
+              _traceln!(self, "DEBUG: InterpTerm: Enter:  Not: mat = {:?}", mat);
+              match (mat, self.reg.tctx) {
+                (false, TermContext_::Unify) => {
+                  return Ok(Yield_::Fail);
+                }
+                (mat, tctx) => {
+                  if let TermContext_::Match = tctx {
+                    self.put_mat_res(mat)?;
+                  }
+                  self.knt_ = knt.prev.take();
+                  self.port = Port_::Return;
+                }
+              }
+            }
+            TermCode_::Await{span, term} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpAwaitTerm(cur_term_code, AwaitTermCodeInterpState_::fresh(term)),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
             TermCode_::NEqual{span, lterm, rterm} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpNEqualTerm(cur_term_code, NEqualTermCodeInterpState_::fresh(lterm, rterm)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
@@ -4621,23 +11148,25 @@ impl FastInterp {
             TermCode_::QEqual{span, lterm, rterm} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpQEqualTerm(cur_term_code, QEqualTermCodeInterpState_::fresh(lterm, rterm)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
             }
-            TermCode_::Apply{span, tup} => {
+            TermCode_::Apply{span, tup, splat} => {
+              let mut state = ApplyTermCodeInterpState_::fresh(tup);
+              state.splat = splat;
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
-                cur:  MemKnt_::InterpApplyTerm(cur_term_code, ApplyTermCodeInterpState_::fresh(tup)),
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpApplyTerm(cur_term_code, state),
               }.into_ref();
               /*self.port = Port_::Enter;*/
             }
             TermCode_::ApplyBindL{span, lterm, tup} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpApplyBindLTerm(cur_term_code, ApplyBindLTermCodeInterpState_::fresh(lterm, tup)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
@@ -4645,7 +11174,7 @@ impl FastInterp {
             TermCode_::ApplyBindR{span, tup, rterm} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpApplyBindRTerm(cur_term_code, ApplyBindRTermCodeInterpState_::fresh(tup, rterm)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
@@ -4653,23 +11182,55 @@ impl FastInterp {
             TermCode_::BindL{span, lterm, rterm} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpBindLTerm(cur_term_code, BindLTermCodeInterpState_::fresh(lterm, rterm)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
             }
-            /*TermCode_::RebindL{span, lterm, rterm} => {
+            TermCode_::Subst{span, lterm, rterm} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
-                cur:  MemKnt_::InterpRebindLTerm(cur_term_code, RebindLTermCodeInterpState_::fresh(lterm, rterm)),
+                prev: knt.prev.take(),
+                cur:  MemKnt_::InterpSubstTerm(cur_term_code, SubstTermCodeInterpState_::fresh(lterm, rterm)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
-            }*/
+            }
+            TermCode_::RebindL{span, lterm, rterm} => {
+              match self.lookup_term_code(lterm)? {
+                TermCode_::Ident{id, ..} => {
+                  self.knt_ = MemKnt{
+                    clk,
+                    prev: knt.prev.take(),
+                    cur:  MemKnt_::InterpRebindLTerm(cur_term_code, RebindLTermCodeInterpState_::fresh(id, rterm)),
+                  }.into_ref();
+                  /*self.port = Port_::Enter;*/
+                }
+                _ => {
+                  self.exc_ = "cannot rebind a non-ident term".into();
+                  self.knt_ = knt.into();
+                }
+              }
+            }
+            TermCode_::RebindR{span, lterm, rterm} => {
+              match self.lookup_term_code(rterm)? {
+                TermCode_::Ident{id, ..} => {
+                  self.knt_ = MemKnt{
+                    clk,
+                    prev: knt.prev.take(),
+                    cur:  MemKnt_::InterpRebindRTerm(cur_term_code, RebindRTermCodeInterpState_::fresh(lterm, id)),
+                  }.into_ref();
+                  /*self.port = Port_::Enter;*/
+                }
+                _ => {
+                  self.exc_ = "cannot rebind a non-ident term".into();
+                  self.knt_ = knt.into();
+                }
+              }
+            }
             TermCode_::Effect{span, lterm, rtup} => {
               self.knt_ = MemKnt{
                 clk,
-                prev: knt.prev,
+                prev: knt.prev.take(),
                 cur:  MemKnt_::InterpEffectTerm(cur_term_code, EffectTermCodeInterpState_::fresh(lterm, rtup)),
               }.into_ref();
               /*self.port = Port_::Enter;*/
@@ -4680,10 +11241,10 @@ impl FastInterp {
           }
         }
         (Port_::Return, &mut MemKnt_::InterpTerm(_cur_term, ref mut _state)) => {
-          self.knt_ = knt.prev;
+          self.knt_ = knt.prev.take();
           /*self.port = Port_::Return;*/
         }
-        (Port_::Enter, &mut MemKnt_::InterpQualIdentTerm(_cur_term, ref mut state)) => {
+        (Port_::Enter, &mut MemKnt_::InterpQualIdentTerm(cur_term_code, ref mut state)) => {
           match state.cur {
             QualIdentTermCodeInterpCursor_::Term{term, ..} => {
               self.knt_ = MemKnt{
@@ -4694,10 +11255,62 @@ impl FastInterp {
               /*self.port = Port_::Enter;*/
             }
             QualIdentTermCodeInterpCursor_::Ident{ident} => {
-              return Err(unimpl());
+              let lhs = match state.term {
+                None => return Err(bot()),
+                Some((_, t)) => t
+              };
+              let qual_id = self._load_qual_ident(clk, lhs, ident)?;
+              if let Some(obj) = self._find_obj_val(clk, lhs)? {
+                // NB: object attributes are requested from the object value.
+                _traceln!(self, "DEBUG: InterpQualIdentTerm: Enter:  obj request: obj={:?} qual id={:?}", obj, qual_id);
+                let span = self.lookup_term_code(cur_term_code)?._span()?;
+                let raw_id = self.lookup_raw_ident(qual_id)?.clone();
+                let attr = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+                self.put_term(clk, attr, IdentTerm_{id: qual_id, raw_id})?;
+                let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+                let tup = [self.find(clk, lhs)?, self.find(clk, attr)?];
+                let mut val = self.borrow_obj_val(obj)?;
+                let knt_ = BorrowedMemKnt{
+                  clk:  knt.clk,
+                  prev: &knt.prev,
+                  cur:  MemKnt_::InterpQualIdentTerm(cur_term_code, state.clone()),
+                };
+                let result = val.__request__(self, span, obj.into(), &tup, x.into(), knt_);
+                self.unborrow_obj_val(obj, val)?;
+                result?;
+                if self.exc_.is_some() {
+                  // NB: the request raised.
+                  self.knt_ = knt.into();
+                } else if self.knt_.is_some() {
+                  // NB: the request pushed a child continuation in place of
+                  // this one (e.g. a method call), which provides the result.
+                  _traceln!(self, "DEBUG: InterpQualIdentTerm: Enter:  obj request pushed knt");
+                } else {
+                  self.put_res(x)?;
+                  self.knt_ = knt.into();
+                  self.port = Port_::Return;
+                }
+              } else {
+                // NB: otherwise, the qual ident is resolved (and bound
+                // upon first occurrence) in the module namespace.
+                let x = match self.env.id_global_bind.get(&qual_id) {
+                  None => self._bind_fresh_ident(clk, NamespaceScope_::Module, qual_id)?,
+                  Some(&x) => x
+                };
+                _traceln!(self, "DEBUG: InterpQualIdentTerm: Enter:  qual id={:?} x={:?}", qual_id, x);
+                self.put_res(x)?;
+                self.knt_ = knt.into();
+                self.port = Port_::Return;
+              }
             }
             QualIdentTermCodeInterpCursor_::Fin => {
-              return Err(unimpl());
+              let x = match state.ident {
+                None => return Err(bot()),
+                Some((_, x)) => x
+              };
+              self.put_res(x)?;
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
             }
           }
         }
@@ -4722,24 +11335,30 @@ impl FastInterp {
         }
         (Port_::Enter, &mut MemKnt_::InterpBunchTerm(_cur_term, ref mut state)) => {
           if state.cur.is_nil() {
-            // FIXME: fill result.
-            //self.put_res(_)?;
-            self.knt_ = knt.prev;
+            // NB: a bunch (e.g. the tuple `(x, y)`, or `(x,)`) evaluates
+            // to a data tuple, which is hash-consed as for a data apply.
+            let mut tup_buf = TupleBuf_::with_capacity(state.tup.len());
+            for &(_, t) in state.tup.iter() {
+              tup_buf.push(self.find(clk, t)?);
+            }
+            let x = self._cons_tuple(clk, tup_buf)?;
+            self.put_res(x)?;
+            self.knt_ = knt.prev.take();
             self.port = Port_::Return;
           } else {
             let cur_cel = self.lookup_term_code_cell(state.cur)?;
             self.knt_ = MemKnt{
               clk,
               prev: knt.into(),
-              cur:  MemKnt_::InterpTerm(cur_cel.dptr.into_term_code(), TermCodeInterpState_::fresh()),
+              cur:  MemKnt_::InterpTerm(cur_cel.dptr.try_into_term_code().map_err(|e| e.into_check())?, TermCodeInterpState_::fresh()),
             }.into_ref();
             /*self.port = Port_::Enter;*/
           }
         }
         (Port_::Return, &mut MemKnt_::InterpBunchTerm(_cur_term, ref mut state)) => {
           let cur_cel = self.lookup_term_code_cell(state.cur)?;
-          state.tup.push((cur_cel.dptr.into_term_code(), self.get_res()?));
-          state.cur = cur_cel.next.get().into_term_code();
+          state.tup.push((cur_cel.dptr.try_into_term_code().map_err(|e| e.into_check())?, self.get_res()?));
+          state.cur = cur_cel.next.get().try_into_term_code().map_err(|e| e.into_check())?;
           self.knt_ = knt.into();
           self.port = Port_::Enter;
         }
@@ -4770,7 +11389,10 @@ impl FastInterp {
               _traceln!(self, "DEBUG: InterpEqualTerm: Enter:    lterm = {:?} rterm = {:?}", lterm, rterm);
               match self.reg.tctx {
                 TermContext_::Unify => {
-                  self.unify(clk, lterm, rterm)?;
+                  if !self._unify_checked(clk, lterm, rterm)? {
+                    _traceln!(self, "DEBUG: InterpEqualTerm: Enter:    unify mode: fail");
+                    return Ok(Yield_::Fail);
+                  }
                 }
                 TermContext_::Match => {
                   let lroot = self.find(clk, lterm)?;
@@ -4781,7 +11403,7 @@ impl FastInterp {
                   self.res_.reg = ResReg_::Mat(mat);
                 }
               }
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -4800,9 +11422,221 @@ impl FastInterp {
               self.knt_ = knt.into();
               self.port = Port_::Enter;
             }
-            EqualTermCodeInterpCursor_::Fin => {
+            EqualTermCodeInterpCursor_::Fin => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpInTerm(cur_term_code, ref mut state)) => {
+          match state.cur {
+            InTermCodeInterpCursor_::LTerm{lterm, ..} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(lterm, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            InTermCodeInterpCursor_::RTerm{rterm} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(rterm, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            InTermCodeInterpCursor_::Fin => {
+              // NB: w/ an unbound lterm, a membership is a generator, w/
+              // a choice point over the items; otherwise, it is a test.
+              // Upon backtracking, this knt is re-entered, and the lterm
+              // is again unbound.
+              _traceln!(self, "DEBUG: InterpInTerm: Enter:  Fin: tctx = {:?}", self.reg.tctx);
+              let lterm = state.lterm.unwrap().1;
+              let rterm = state.rterm.unwrap().1;
+              let rquery = self.find(clk, rterm)?;
+              let items = self::prelude::_find_member_items(self, clk, rquery)?;
+              let mat = match items {
+                None => None,
+                Some(items) => if self._is_unbound(clk, lterm)? {
+                  let span = self.lookup_term_code(cur_term_code)?._span()?;
+                  let knt_ = BorrowedMemKnt{
+                    clk:  knt.clk,
+                    prev: &knt.prev,
+                    cur:  MemKnt_::InterpInTerm(cur_term_code, state.clone()),
+                  };
+                  match self._choose_index(clk, span, items.len(), knt_)? {
+                    Err(yield_) => {
+                      return Ok(yield_);
+                    }
+                    Ok(idx) => {
+                      _traceln!(self, "DEBUG: InterpInTerm: Enter:    choose: idx = {} items.len = {}", idx, items.len());
+                      if !self._unify_checked(clk, lterm, items[idx])? {
+                        _traceln!(self, "DEBUG: InterpInTerm: Enter:    unify mode: fail");
+                        return Ok(Yield_::Fail);
+                      }
+                      Some(true)
+                    }
+                  }
+                } else {
+                  // NB: lit terms are not (yet) hash-consed, so an item
+                  // w/ an equal lit val is also a match.
+                  let lroot = self.find(clk, lterm)?;
+                  let lvals = self.get_vals(clk, lroot)?;
+                  let mut mat = false;
+                  for &y in items.iter() {
+                    let yroot = self.find(clk, y)?;
+                    if yroot.ecls == lroot.ecls {
+                      mat = true;
+                      break;
+                    }
+                    for &(_, ref yval) in self.get_vals(clk, yroot)?.iter() {
+                      for &(_, ref lval) in lvals.iter() {
+                        match (lval, yval) {
+                          (&Val_::Lit(ref lv), &Val_::Lit(ref yv)) if lv == yv => {
+                            mat = true;
+                          }
+                          _ => {}
+                        }
+                      }
+                    }
+                    if mat {
+                      break;
+                    }
+                  }
+                  Some(mat)
+                }
+              };
+              _traceln!(self, "DEBUG: InterpInTerm: Enter:    mat = {:?}", mat);
+              match (mat, self.reg.tctx) {
+                (None, _) => {
+                  self.exc_ = "membership in a non-list value".into();
+                  self.knt_ = knt.into();
+                }
+                (Some(false), TermContext_::Unify) => {
+                  return Ok(Yield_::Fail);
+                }
+                (Some(mat), tctx) => {
+                  if let TermContext_::Match = tctx {
+                    self.res_.reg = ResReg_::Mat(mat);
+                  }
+                  self.knt_ = knt.prev.take();
+                  self.port = Port_::Return;
+                }
+              }
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpInTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            InTermCodeInterpCursor_::LTerm{lterm, rterm} => {
+              state.lterm = Some((lterm, self.get_res()?));
+              state.cur = InTermCodeInterpCursor_::RTerm{rterm};
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            InTermCodeInterpCursor_::RTerm{rterm} => {
+              state.rterm = Some((rterm, self.get_res()?));
+              state.cur = InTermCodeInterpCursor_::Fin;
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            InTermCodeInterpCursor_::Fin => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpAndTerm(_cur_term, ref mut state)) => {
+          let term = match state.cur {
+            AndTermCodeInterpCursor_::LTerm{lterm, ..} => lterm,
+            AndTermCodeInterpCursor_::RTerm{rterm} => rterm,
+          };
+          self.knt_ = MemKnt{
+            clk,
+            prev: knt.into(),
+            cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
+          }.into_ref();
+          /*self.port = Port_::Enter;*/
+        }
+        (Port_::Return, &mut MemKnt_::InterpAndTerm(_cur_term, ref mut state)) => {
+          let v = self._take_goal_res(clk)?;
+          _traceln!(self, "DEBUG: InterpAndTerm: Return: cur = {:?} v = {:?}", state.cur, v);
+          match (state.cur, v) {
+            (AndTermCodeInterpCursor_::LTerm{rterm, ..}, true) => {
+              state.cur = AndTermCodeInterpCursor_::RTerm{rterm};
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            // NB: a false lterm short-circuits; in match context, this
+            // does not backtrack into the choice points of the lterm.
+            (_, false) if matches!(self.reg.tctx, TermContext_::Unify) => {
+              return Ok(Yield_::Fail);
+            }
+            (_, v) => {
+              if let TermContext_::Match = self.reg.tctx {
+                self.put_mat_res(v)?;
+              }
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpOrTerm(cur_term_code, ref mut state)) => {
+          match state.cur {
+            OrTermCodeInterpCursor_::Choose{lterm, rterm} => {
+              // NB: the choice point is made before either goal, so that
+              // a false (or failed) lterm backtracks to the rterm.
+              let span = self.lookup_term_code(cur_term_code)?._span()?;
+              let knt_ = BorrowedMemKnt{
+                clk:  knt.clk,
+                prev: &knt.prev,
+                cur:  MemKnt_::InterpOrTerm(cur_term_code, state.clone()),
+              };
+              let idx = match self._choose_index(clk, span, 2, knt_)? {
+                Err(yield_) => {
+                  return Ok(yield_);
+                }
+                Ok(idx) => idx
+              };
+              _traceln!(self, "DEBUG: InterpOrTerm: Enter:  choose: idx = {}", idx);
+              let term = if idx == 0 {
+                state.cur = OrTermCodeInterpCursor_::LTerm;
+                lterm
+              } else {
+                state.cur = OrTermCodeInterpCursor_::RTerm;
+                rterm
+              };
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            _ => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpOrTerm(_cur_term, ref mut state)) => {
+          let v = self._take_goal_res(clk)?;
+          _traceln!(self, "DEBUG: InterpOrTerm: Return: cur = {:?} v = {:?}", state.cur, v);
+          match (state.cur, v) {
+            (OrTermCodeInterpCursor_::Choose{..}, _) => {
               return Err(bot());
             }
+            (OrTermCodeInterpCursor_::LTerm, false) => {
+              return Ok(Yield_::Fail);
+            }
+            (_, false) if matches!(self.reg.tctx, TermContext_::Unify) => {
+              return Ok(Yield_::Fail);
+            }
+            (_, v) => {
+              if let TermContext_::Match = self.reg.tctx {
+                self.put_mat_res(v)?;
+              }
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
           }
         }
         (Port_::Enter, &mut MemKnt_::InterpNEqualTerm(_cur_term, ref mut state)) => {
@@ -4831,11 +11665,11 @@ impl FastInterp {
               let rterm = state.rterm.unwrap().1;
               let lroot = self.find(clk, lterm)?;
               let rroot = self.find(clk, rterm)?;
-              let x = self._fresh().into_term();
+              let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
               let term_ = NEqualTerm_{buf: [lroot, rroot]};
               self.put_term(clk, x, term_)?;
               self.put_res(x)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -4883,14 +11717,14 @@ impl FastInterp {
               _traceln!(self, "DEBUG: InterpQEqualTerm: Enter:  Fin");
               let lterm_ = state.lterm.unwrap().1;
               let rterm_ = state.rterm.unwrap().1;
-              let x = self._fresh().into_term();
+              let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
               // FIXME: this is the old value semantics.
               let obj = LitVal_::Bool(lterm_ == rterm_);
               /*//self.env.table_full[SNumSort::Term as usize].insert(x.into(), Box::new(_));
               self.env.table_full[SNumSort::Val as usize].insert(x.into(), Box::new(obj));*/
               self.put_val(clk, x, obj)?;
               self.put_res(x)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -4915,62 +11749,264 @@ impl FastInterp {
           }
         }
         (Port_::Enter, &mut MemKnt_::InterpApplyTerm(cur_term_code, ref mut state)) => {
-          if state.cur.is_nil() {
+          if state.cur.is_nil() && state.splat {
+            // NB: expand the splat args, and then re-enter to apply.
+            _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  splat");
+            state.splat = false;
+            if let Err(msg) = self._expand_splats(clk, &mut state.tup)? {
+              self.exc_ = msg.into();
+            }
+            self.knt_ = knt.into();
+          } else if state.cur.is_nil() {
             _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fin");
-            let x = self._fresh().into_term();
-            let mut tup_buf: Vec<ENum> = Vec::with_capacity(state.tup.len());
+            let mut tup_buf = TupleBuf_::with_capacity(state.tup.len());
             for &(_, t) in state.tup.iter() {
-              // FIXME: SNum into ENum should go through unifier.
-              tup_buf.push(t.into());
+              tup_buf.push(self.find(clk, t)?);
             }
-            let term_ = TupleTerm_{buf: tup_buf.into()};
-            self.put_term(clk, x, term_)?;
+            let head = state.tup[0].1;
+            _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  head={:?}", head);
+            // NB: the head resolves to a function via the namespace chain
+            // (e.g. a builtin), unless it has been shadowed.
+            let fun_head = self._find_fun(clk, head)?;
+            let cls_head = match fun_head {
+              None => self._find_obj_cls(clk, head)?,
+              Some(_) => None
+            };
+            let proc_head = match (fun_head, cls_head) {
+              (None, None) => self._find_proc(clk, head)?,
+              _ => None
+            };
+            let lambda_head = match (fun_head, cls_head, proc_head.as_ref()) {
+              (None, None, None) => self._find_lambda(clk, head)?,
+              _ => None
+            };
+            let match_head = match (fun_head, cls_head, proc_head.as_ref(), lambda_head.as_ref()) {
+              (None, None, None, None) => self._find_match(clk, head)?,
+              _ => None
+            };
+            // NB: only a data tuple (i.e. not the result of an apply,
+            // which may differ between applies) is hash-consed.
+            let x = if fun_head.is_none() && cls_head.is_none() && proc_head.is_none() && lambda_head.is_none() && match_head.is_none() {
+              self._cons_tuple(clk, tup_buf)?
+            } else {
+              let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+              self.put_term(clk, x, TupleTerm_{buf: tup_buf})?;
+              x
+            };
             // FIXME: undo entry for function-based apply.
             //self.log.push(LogEntryRef_::Undo(UndoLogEntry_::ApplyTerm(x).into()));
-            let fun_head = state.tup[0].1.into_fun();
-            _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun head={:?}", fun_head);
-            if let Some(fun_head_term) = self.get_term(fun_head)? {
-              _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun head is term = {:?}", fun_head_term);
-              if let Some(id_term) = fun_head_term.as_any().downcast_ref::<IdentTerm_>() {
-                //if let Some(&id) = self.env.raw_id_index.get(&id_term.raw_id) {
-                  let id = id_term.id;
-                  if let Some(&fun_head) = self.env.fun_name.get(&id) {
-                    _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun apply...");
-                    let span = self.lookup_term_code(cur_term_code)?._span()?;
-                    let fun_head = fun_head.into_fun();
-                    let mut fun = self.borrow_fun(fun_head)?;
-                    let mut tup = Vec::with_capacity(state.tup.len());
-                    for &(_, t) in state.tup.iter() {
-                      tup.push(self.find(clk, t)?);
+            if let Some(fun_head) = fun_head {
+              _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun apply: fun head={:?}", fun_head);
+              let span = self.lookup_term_code(cur_term_code)?._span()?;
+              let mut tup = self._take_scratch();
+              for &(_, t) in state.tup.iter() {
+                tup.push(self.find(clk, t)?);
+              }
+              if let Some(msg) = self._check_fun_sig(clk, fun_head, &tup)? {
+                // NB: as w/ a raising init (below), the apply knt is kept,
+                // so that the raise is located at the apply term.
+                _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun sig mismatch: msg={:?}", msg);
+                self._put_scratch(tup);
+                self.exc_ = msg.into();
+                self.knt_ = knt.into();
+              } else {
+                let fun_knt = BorrowedMemKnt{
+                  clk:  knt.clk,
+                  prev: &knt.prev,
+                  cur:  MemKnt_::InterpApplyTerm(cur_term_code, state.clone()),
+                };
+                let mut fun = self.borrow_fun_guard(fun_head)?;
+                let result = {
+                  let (this, fun_) = fun.split();
+                  fun_.__apply__(this, span, fun_head.into(), &tup, x.into(), fun_knt)
+                };
+                fun.unborrow()?;
+                let result = result?;
+                self._put_scratch(tup);
+                match result {
+                  None => {}
+                  Some(yield_) => {
+                    _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun yield...");
+                    return Ok(yield_);
+                  }
+                }
+                if self.knt_.is_some() {
+                  // NB: the function pushed a child continuation in place
+                  // of this one (e.g. `_push_unquote`), which provides the
+                  // result.
+                  _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun pushed knt");
+                } else if let ResReg_::Mat(mat) = self.res_.peek() {
+                  // NB: the function put a match result in place of the
+                  // result key (e.g. `prelude::CmpFun` in match context).
+                  _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun mat={:?}", mat);
+                  self.knt_ = knt.prev.take();
+                  self.port = Port_::Return;
+                } else {
+                  self.put_res(x)?;
+                  self.knt_ = knt.prev.take();
+                  self.port = Port_::Return;
+                }
+              }
+            } else if let Some(cls_head) = cls_head {
+              // NB: applying an object class creates (and initializes)
+              // a fresh object value, which is the result.
+              _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  obj cls apply: cls head={:?}", cls_head);
+              let span = self.lookup_term_code(cur_term_code)?._span()?;
+              let mut tup = self._take_scratch();
+              for &(_, t) in state.tup.iter() {
+                tup.push(self.find(clk, t)?);
+              }
+              let mut cls = self.borrow_obj_cls(cls_head)?;
+              let knt_ = BorrowedMemKnt{
+                clk:  knt.clk,
+                prev: &knt.prev,
+                cur:  MemKnt_::InterpApplyTerm(cur_term_code, state.clone()),
+              };
+              let result = cls.__create__(self, span, cls_head.into(), &tup, x.into(), knt_);
+              self.unborrow_obj_cls(cls_head, cls)?;
+              let obj = self._load_obj_val(clk, result?)?;
+              let mut val = self.borrow_obj_val(obj)?;
+              let knt_ = BorrowedMemKnt{
+                clk:  knt.clk,
+                prev: &knt.prev,
+                cur:  MemKnt_::InterpApplyTerm(cur_term_code, state.clone()),
+              };
+              let result = val.__init__(self, span, obj.into(), &tup, x.into(), knt_);
+              self.unborrow_obj_val(obj, val)?;
+              self._put_scratch(tup);
+              result?;
+              _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  obj cls apply: obj={:?}", obj);
+              if self.exc_.is_some() {
+                // NB: the init raised.
+                self.knt_ = knt.into();
+              } else if self.knt_.is_some() {
+                // NB: the init pushed a child continuation in place of this
+                // one (e.g. a call to a class `__init__`), which provides
+                // the result (i.e. the object).
+                _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  obj cls apply: init pushed knt");
+              } else {
+                self.put_res(obj)?;
+                self.knt_ = knt.prev.take();
+                self.port = Port_::Return;
+              }
+            } else if let Some(proc_head) = proc_head {
+              _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  proc apply: proc head={:?}", proc_head);
+              match self._bind_call_args(clk, proc_head.code, &state.tup[1 .. ])? {
+                Err(msg) => {
+                  self.exc_ = msg.into();
+                  self.knt_ = knt.into();
+                }
+                Ok((args, defaults)) => {
+                  match self._goal_call(clk, proc_head.code, &args)? {
+                    tabling::GoalCall_::Consume(answers) => {
+                      _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  proc apply: tabled: answers={}", answers.len());
+                      let knt_ = BorrowedMemKnt{
+                        clk:  knt.clk,
+                        prev: &knt.prev,
+                        cur:  MemKnt_::InterpApplyTerm(cur_term_code, state.clone()),
+                      };
+                      if let Some(yield_) = self._goal_consume(clk, &args, answers, x.into(), knt_)? {
+                        return Ok(yield_);
+                      }
+                      self.put_res(x)?;
+                      self.knt_ = knt.prev.take();
+                      self.port = Port_::Return;
+                    }
+                    call => {
+                      let mut proc_state = ProcBodyInterpState_::fresh(proc_head.scope, args, x.into());
+                      proc_state.defaults = defaults;
+                      if let tabling::GoalCall_::Produce(key) = call {
+                        proc_state.table = Some(key);
+                      }
+                      self.knt_ = MemKnt{
+                        clk,
+                        prev: knt.prev.take(),
+                        cur:  MemKnt_::InterpProcBody(proc_head.code, proc_state),
+                      }.into_ref();
+                      /*self.port = Port_::Enter;*/
                     }
-                    let knt = BorrowedMemKnt{
+                  }
+                }
+              }
+            } else if let Some(lambda_head) = lambda_head {
+              _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  lambda apply: lambda head={:?}", lambda_head);
+              // NB: a lambda only takes positional args.
+              let mut args = Vec::with_capacity(state.tup.len() - 1);
+              let mut kw = false;
+              for &(term, arg) in state.tup[1 .. ].iter() {
+                if let TermCode_::Kwarg{..} = self.lookup_term_code(term)? {
+                  kw = true;
+                }
+                args.push(arg);
+              }
+              if kw {
+                self.exc_ = "lambda got a keyword argument".into();
+                self.knt_ = knt.into();
+              } else {
+                self.knt_ = MemKnt{
+                  clk,
+                  prev: knt.prev.take(),
+                  cur:  MemKnt_::InterpLambdaBody(lambda_head.code, LambdaBodyInterpState_::fresh(lambda_head.scope, args, x.into())),
+                }.into_ref();
+                /*self.port = Port_::Enter;*/
+              }
+            } else if let Some(match_head) = match_head {
+              _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  match apply: match head={:?}", match_head);
+              // NB: as w/ a lambda, a defmatch only takes positional args.
+              let mut args = Vec::with_capacity(state.tup.len() - 1);
+              let mut kw = false;
+              for &(term, arg) in state.tup[1 .. ].iter() {
+                if let TermCode_::Kwarg{..} = self.lookup_term_code(term)? {
+                  kw = true;
+                }
+                args.push(arg);
+              }
+              if kw {
+                self.exc_ = "defmatch got a keyword argument".into();
+                self.knt_ = knt.into();
+              } else {
+                let clauses = self.defmatch_candidates(clk, match_head.id, &args)?;
+                _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  match apply: candidates={:?}", clauses);
+                // NB: the candidates are alternatives, as w/ the cases of
+                // `StmCode_::Cases`; upon backtracking, this knt is
+                // re-entered, and picks the next candidate.
+                let idx = match clauses.len() {
+                  0 => return Ok(Yield_::Fail),
+                  1 => 0,
+                  n => {
+                    let span = self.lookup_term_code(cur_term_code)?._span()?;
+                    let knt_ = BorrowedMemKnt{
                       clk:  knt.clk,
                       prev: &knt.prev,
                       cur:  MemKnt_::InterpApplyTerm(cur_term_code, state.clone()),
                     };
-                    let result = fun.__apply__(self, span, fun_head.into(), &tup, x.into(), knt)?;
-                    self.unborrow_fun(fun_head, fun)?;
-                    match result {
-                      None => {}
-                      Some(yield_) => {
-                        _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  fun yield...");
+                    match self._choose_index(clk, span, n, knt_)? {
+                      Err(yield_) => {
                         return Ok(yield_);
                       }
+                      Ok(idx) => idx
                     }
                   }
-                //}
+                };
+                self.knt_ = MemKnt{
+                  clk,
+                  prev: knt.prev.take(),
+                  cur:  MemKnt_::InterpProcBody(clauses[idx], ProcBodyInterpState_::fresh(match_head.scope, args, x.into())),
+                }.into_ref();
+                /*self.port = Port_::Enter;*/
               }
+            } else {
+              self.put_res(x)?;
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
             }
-            self.put_res(x)?;
-            self.knt_ = knt.prev;
-            self.port = Port_::Return;
           } else {
             let cur_cel = self.lookup_term_code_cell(state.cur)?;
             _traceln!(self, "DEBUG: InterpApplyTerm: Enter:  dptr={:?}", cur_cel.dptr);
             self.knt_ = MemKnt{
               clk,
               prev: knt.into(),
-              cur:  MemKnt_::InterpTerm(cur_cel.dptr.into_term_code(), TermCodeInterpState_::fresh()),
+              cur:  MemKnt_::InterpTerm(cur_cel.dptr.try_into_term_code().map_err(|e| e.into_check())?, TermCodeInterpState_::fresh()),
             }.into_ref();
             /*self.port = Port_::Enter;*/
           }
@@ -4978,11 +12014,157 @@ impl FastInterp {
         (Port_::Return, &mut MemKnt_::InterpApplyTerm(_cur_term, ref mut state)) => {
           let cur_cel_ = self.lookup_term_code_cell(state.cur)?;
           _traceln!(self, "DEBUG: InterpApplyTerm: Return: dptr={:?} next={:?}", cur_cel_.dptr, cur_cel_.next.get());
-          state.tup.push((cur_cel_.dptr.into_term_code(), self.get_res()?));
-          state.cur = cur_cel_.next.get().into_term_code();
+          state.tup.push((cur_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?, self.get_res()?));
+          state.cur = cur_cel_.next.get().try_into_term_code().map_err(|e| e.into_check())?;
           self.knt_ = knt.into();
           self.port = Port_::Enter;
         }
+        (Port_::Enter, &mut MemKnt_::InterpListCompTerm(cur_term_code, ref mut state)) => {
+          let (elt, target, iter, cond) = match self.lookup_term_code(cur_term_code)? {
+            TermCode_::ListComp{elt, target, iter, cond, ..} => (elt, target, iter, cond),
+            _ => return Err(bot())
+          };
+          match state.cur {
+            ListCompTermCodeInterpCursor_::Iter => {
+              state.save_scope = self.env.ns_cur;
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(iter, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            ListCompTermCodeInterpCursor_::Next |
+            ListCompTermCodeInterpCursor_::Choose => {
+              if let ListCompTermCodeInterpCursor_::Choose = state.cur {
+                // NB: upon backtracking, this knt is re-entered, and
+                // picks the next item (see `_choose_index`).
+                _traceln!(self, "DEBUG: InterpListCompTerm: Enter:  choose: items.len={}", state.items.len());
+                let span = self.lookup_term_code(cur_term_code)?._span()?;
+                let knt_ = BorrowedMemKnt{
+                  clk:  knt.clk,
+                  prev: &knt.prev,
+                  cur:  MemKnt_::InterpListCompTerm(cur_term_code, state.clone()),
+                };
+                match self._choose_index(clk, span, state.items.len(), knt_)? {
+                  Err(yield_) => {
+                    return Ok(yield_);
+                  }
+                  Ok(idx) => {
+                    state.idx = idx;
+                  }
+                }
+              }
+              if state.idx >= state.items.len() {
+                // NB: only the `Next` cursor gets here.
+                let y = self._fresh()?;
+                self.put_val(clk, y, LitVal_::List{buf: state.buf.clone()})?;
+                _traceln!(self, "DEBUG: InterpListCompTerm: Enter:  fin: y={:?} buf.len={}", y, state.buf.len());
+                self.reset_res()?;
+                self.put_res(y)?;
+                self.knt_ = knt.prev.take();
+                self.port = Port_::Return;
+              } else {
+                let scope = self._alloc_namespace(clk, state.save_scope)?;
+                if let Some(target) = target {
+                  self._bind_ident(clk, NamespaceScope_::Local(scope), target, state.items[state.idx])?;
+                }
+                self._switch_namespace(clk, scope);
+                state.cur = match cond {
+                  None => ListCompTermCodeInterpCursor_::Elt,
+                  Some(_) => ListCompTermCodeInterpCursor_::Cond
+                };
+                self.knt_ = knt.into();
+                /*self.port = Port_::Enter;*/
+              }
+            }
+            ListCompTermCodeInterpCursor_::Cond => {
+              let save_tctx = self.reg.tctx;
+              self.reg.tctx = TermContext_::Match;
+              if state.save_tctx.replace(save_tctx).is_some() {
+                return Err(bot());
+              }
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(cond.unwrap(), TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            ListCompTermCodeInterpCursor_::Elt => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(elt, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpListCompTerm(cur_term_code, ref mut state)) => {
+          let choice = match self.lookup_term_code(cur_term_code)? {
+            TermCode_::ListComp{choice, ..} => choice,
+            _ => return Err(bot())
+          };
+          match state.cur {
+            ListCompTermCodeInterpCursor_::Iter => {
+              let y = self.get_res()?;
+              let query = self.find(clk, y)?;
+              match self::prelude::_find_list_items(self, clk, query)? {
+                None => {
+                  self.exc_ = "comprehension over a non-list value".into();
+                }
+                Some(items) => {
+                  state.items = items;
+                  state.cur = if choice {
+                    ListCompTermCodeInterpCursor_::Choose
+                  } else {
+                    ListCompTermCodeInterpCursor_::Next
+                  };
+                }
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            ListCompTermCodeInterpCursor_::Cond => {
+              match state.save_tctx.take() {
+                None => return Err(bot()),
+                Some(save_tctx) => {
+                  self.reg.tctx = save_tctx;
+                }
+              }
+              if self.get_mat_res()? {
+                state.cur = ListCompTermCodeInterpCursor_::Elt;
+              } else if choice {
+                _traceln!(self, "DEBUG: InterpListCompTerm: Return: cond: fail");
+                return Ok(Yield_::Fail);
+              } else {
+                self._switch_namespace(clk, state.save_scope);
+                state.idx += 1;
+                state.cur = ListCompTermCodeInterpCursor_::Next;
+              }
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            ListCompTermCodeInterpCursor_::Elt => {
+              let y = self.get_res()?;
+              self._switch_namespace(clk, state.save_scope);
+              if choice {
+                self.reset_res()?;
+                self.put_res(y)?;
+                self.knt_ = knt.prev.take();
+                /*self.port = Port_::Return;*/
+              } else {
+                state.buf.push(y);
+                state.idx += 1;
+                state.cur = ListCompTermCodeInterpCursor_::Next;
+                self.knt_ = knt.into();
+                self.port = Port_::Enter;
+              }
+            }
+            _ => return Err(bot())
+          }
+        }
         (Port_::Enter, &mut MemKnt_::InterpApplyBindLTerm(_cur_term, ref mut state)) => {
           match state.cur {
             ApplyBindLTermCodeInterpCursor_::Bind{bind, tup} => {
@@ -5004,26 +12186,27 @@ impl FastInterp {
                 self.knt_ = MemKnt{
                   clk,
                   prev: knt.into(),
-                  cur:  MemKnt_::InterpTerm(tup_cel_.dptr.into_term_code(), TermCodeInterpState_::fresh()),
+                  cur:  MemKnt_::InterpTerm(tup_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?, TermCodeInterpState_::fresh()),
                 }.into_ref();
                 /*self.port = Port_::Enter;*/
               }
             }
             ApplyBindLTermCodeInterpCursor_::Fin => {
-              let x = self._fresh().into_term();
-              let mut tup_buf: Vec<ENum> = Vec::with_capacity(state.tup.len());
+              let mut tup_buf = TupleBuf_::with_capacity(state.tup.len());
               for &(_, t) in state.tup.iter() {
                 tup_buf.push(self.find(clk, t)?);
               }
-              let term_ = TupleTerm_{buf: tup_buf.into()};
-              self.put_term(clk, x, term_)?;
+              let x = self._cons_tuple(clk, tup_buf)?;
               let y = match state.bind {
                 None => return Err(bot()),
                 Some((_, v)) => v
               };
-              self.unify(clk, x, y)?;
+              if !self._unify_checked(clk, x.into(), y)? {
+                _traceln!(self, "DEBUG: InterpApplyBindLTerm: Enter:    unify mode: fail");
+                return Ok(Yield_::Fail);
+              }
               self.put_res(y)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -5041,8 +12224,8 @@ impl FastInterp {
                 state.cur = ApplyBindLTermCodeInterpCursor_::Fin;
               } else {
                 let tup_cel_ = self.lookup_term_code_cell(tup)?;
-                state.tup.push((tup_cel_.dptr.into_term_code(), self.get_res()?));
-                state.cur = ApplyBindLTermCodeInterpCursor_::Tup{tup: tup_cel_.next.get().into_term_code()};
+                state.tup.push((tup_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?, self.get_res()?));
+                state.cur = ApplyBindLTermCodeInterpCursor_::Tup{tup: tup_cel_.next.get().try_into_term_code().map_err(|e| e.into_check())?};
               }
               self.knt_ = knt.into();
               self.port = Port_::Enter;
@@ -5065,7 +12248,7 @@ impl FastInterp {
                 self.knt_ = MemKnt{
                   clk,
                   prev: knt.into(),
-                  cur:  MemKnt_::InterpTerm(tup_cel_.dptr.into_term_code(), TermCodeInterpState_::fresh()),
+                  cur:  MemKnt_::InterpTerm(tup_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?, TermCodeInterpState_::fresh()),
                 }.into_ref();
                 /*self.port = Port_::Enter;*/
               }
@@ -5079,20 +12262,21 @@ impl FastInterp {
               /*self.port = Port_::Enter;*/
             }
             ApplyBindRTermCodeInterpCursor_::Fin => {
-              let x = self._fresh().into_term();
-              let mut tup_buf: Vec<ENum> = Vec::with_capacity(state.tup.len());
+              let mut tup_buf = TupleBuf_::with_capacity(state.tup.len());
               for &(_, t) in state.tup.iter() {
                 tup_buf.push(self.find(clk, t)?);
               }
-              let term_ = TupleTerm_{buf: tup_buf.into()};
-              self.put_term(clk, x, term_)?;
+              let x = self._cons_tuple(clk, tup_buf)?;
               let y = match state.bind {
                 None => return Err(bot()),
                 Some((_, v)) => v
               };
-              self.unify(clk, x, y)?;
+              if !self._unify_checked(clk, x.into(), y)? {
+                _traceln!(self, "DEBUG: InterpApplyBindRTerm: Enter:    unify mode: fail");
+                return Ok(Yield_::Fail);
+              }
               self.put_res(y)?;
-              self.knt_ = knt.prev;
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -5104,8 +12288,8 @@ impl FastInterp {
                 state.cur = ApplyBindRTermCodeInterpCursor_::Bind{bind};
               } else {
                 let tup_cel_ = self.lookup_term_code_cell(tup)?;
-                state.tup.push((tup_cel_.dptr.into_term_code(), self.get_res()?));
-                state.cur = ApplyBindRTermCodeInterpCursor_::Tup{tup: tup_cel_.next.get().into_term_code(), bind};
+                state.tup.push((tup_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?, self.get_res()?));
+                state.cur = ApplyBindRTermCodeInterpCursor_::Tup{tup: tup_cel_.next.get().try_into_term_code().map_err(|e| e.into_check())?, bind};
               }
               self.knt_ = knt.into();
               self.port = Port_::Enter;
@@ -5148,8 +12332,11 @@ impl FastInterp {
                 None => return Err(bot()),
                 Some((_, v)) => v
               };
-              self.unify(clk, x, y)?;
-              self.knt_ = knt.prev;
+              if !self._unify_checked(clk, x, y)? {
+                _traceln!(self, "DEBUG: InterpBindLTerm: Enter:    unify mode: fail");
+                return Ok(Yield_::Fail);
+              }
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -5200,8 +12387,11 @@ impl FastInterp {
                 None => return Err(bot()),
                 Some((_, v)) => v
               };
-              self.unify(clk, x, y)?;
-              self.knt_ = knt.prev;
+              if !self._unify_checked(clk, x, y)? {
+                _traceln!(self, "DEBUG: InterpBindRTerm: Enter:    unify mode: fail");
+                return Ok(Yield_::Fail);
+              }
+              self.knt_ = knt.prev.take();
               self.port = Port_::Return;
             }
           }
@@ -5225,7 +12415,171 @@ impl FastInterp {
             }
           }
         }
-        (Port_::Enter, &mut MemKnt_::InterpEffectTerm(_cur_term, ref mut state)) => {
+        (Port_::Enter, &mut MemKnt_::InterpSubstTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            SubstTermCodeInterpCursor_::RTerm{rterm, ..} => {
+              let scope = self._alloc_namespace(clk, self.env.ns_cur)?;
+              state.save_scope = self._switch_namespace(clk, scope);
+              _traceln!(self, "DEBUG: InterpSubstTerm: Enter:  scope={:?} save scope={:?}", scope, state.save_scope);
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(rterm, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            SubstTermCodeInterpCursor_::LTerm{lterm} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(lterm, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            SubstTermCodeInterpCursor_::Fin => {
+              let x = match state.lterm {
+                None => return Err(bot()),
+                Some((_, v)) => v
+              };
+              self._switch_namespace(clk, state.save_scope);
+              self.put_res(x)?;
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpSubstTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            SubstTermCodeInterpCursor_::RTerm{lterm, rterm} => {
+              // NB: the rterm is run for its bindings (e.g. `w := 4`), and
+              // so it need not put a result.
+              if let ResReg_::Key(x) = self.res_.get() {
+                state.rterm = Some((rterm, x));
+              }
+              state.cur = SubstTermCodeInterpCursor_::LTerm{lterm};
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            SubstTermCodeInterpCursor_::LTerm{lterm} => {
+              state.lterm = Some((lterm, self.get_res()?));
+              state.cur = SubstTermCodeInterpCursor_::Fin;
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            SubstTermCodeInterpCursor_::Fin => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpAwaitTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            AwaitTermCodeInterpCursor_::Term{term} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            AwaitTermCodeInterpCursor_::Eval => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpAwaitTerm(cur_term_code, ref mut state)) => {
+          match state.cur {
+            AwaitTermCodeInterpCursor_::Term{..} => {
+              let x = self.get_res()?;
+              state.cur = AwaitTermCodeInterpCursor_::Eval;
+              // NB: the result is provided later, by the caller of
+              // `interp_`, which then resumes the continuation of this
+              // term (see `FastInterp::provide_eval_result`).
+              let ret = self._fresh()?;
+              let knt_ = BorrowedMemKnt{
+                clk:  knt.clk,
+                prev: &knt.prev,
+                cur:  MemKnt_::InterpAwaitTerm(cur_term_code, state.clone()),
+              };
+              _traceln!(self, "DEBUG: InterpAwaitTerm: Return: x = {:?} ret = {:?}", x, ret);
+              self._push_eval(clk, x, ret, &knt_)?;
+              return Ok(Yield_::Eval);
+            }
+            AwaitTermCodeInterpCursor_::Eval => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpRebindLTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            RebindLTermCodeInterpCursor_::RTerm{rterm} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(rterm, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            RebindLTermCodeInterpCursor_::Fin => {
+              let x = match state.rterm {
+                None => return Err(bot()),
+                Some((_, v)) => v
+              };
+              self._rebind_ident(clk, state.lbind, x)?;
+              self.put_res(x)?;
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpRebindLTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            RebindLTermCodeInterpCursor_::RTerm{rterm} => {
+              state.rterm = Some((rterm, self.get_res()?));
+              state.cur = RebindLTermCodeInterpCursor_::Fin;
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            RebindLTermCodeInterpCursor_::Fin => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpRebindRTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            RebindRTermCodeInterpCursor_::LTerm{lterm} => {
+              self.knt_ = MemKnt{
+                clk,
+                prev: knt.into(),
+                cur:  MemKnt_::InterpTerm(lterm, TermCodeInterpState_::fresh()),
+              }.into_ref();
+              /*self.port = Port_::Enter;*/
+            }
+            RebindRTermCodeInterpCursor_::Fin => {
+              let x = match state.lterm {
+                None => return Err(bot()),
+                Some((_, v)) => v
+              };
+              self._rebind_ident(clk, state.rbind, x)?;
+              self.put_res(x)?;
+              self.knt_ = knt.prev.take();
+              self.port = Port_::Return;
+            }
+          }
+        }
+        (Port_::Return, &mut MemKnt_::InterpRebindRTerm(_cur_term, ref mut state)) => {
+          match state.cur {
+            RebindRTermCodeInterpCursor_::LTerm{lterm} => {
+              state.lterm = Some((lterm, self.get_res()?));
+              state.cur = RebindRTermCodeInterpCursor_::Fin;
+              self.knt_ = knt.into();
+              self.port = Port_::Enter;
+            }
+            RebindRTermCodeInterpCursor_::Fin => {
+              return Err(bot());
+            }
+          }
+        }
+        (Port_::Enter, &mut MemKnt_::InterpEffectTerm(cur_term_code, ref mut state)) => {
           match state.cur {
             EffectTermCodeInterpCursor_::LTerm{lterm, ..} => {
               self.knt_ = MemKnt{
@@ -5245,21 +12599,80 @@ impl FastInterp {
                 self.knt_ = MemKnt{
                   clk,
                   prev: knt.into(),
-                  cur:  MemKnt_::InterpTerm(tup_cel_.dptr.into_term_code(), TermCodeInterpState_::fresh()),
+                  cur:  MemKnt_::InterpTerm(tup_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?, TermCodeInterpState_::fresh()),
                 }.into_ref();
                 /*self.port = Port_::Enter;*/
               }
             }
             EffectTermCodeInterpCursor_::Fin => {
-              self.knt_ = knt.prev;
-              self.port = Port_::Return;
+              let lhs = match state.lterm {
+                None => return Err(bot()),
+                Some((_, x)) => x
+              };
+              match (state.meth, self._find_obj_val(clk, lhs)?) {
+                (None, _) => {
+                  self.exc_ = "effect term is missing a method ident".into();
+                  self.knt_ = knt.into();
+                }
+                (_, None) => {
+                  self.exc_ = "effect term on a non-object".into();
+                  self.knt_ = knt.into();
+                }
+                (Some(meth), Some(obj)) => {
+                  // NB: the effect is requested from the object value, as
+                  // `(lhs, meth, args...)`.
+                  _traceln!(self, "DEBUG: InterpEffectTerm: Enter:  obj request: obj={:?} meth={:?}", obj, meth);
+                  let span = self.lookup_term_code(cur_term_code)?._span()?;
+                  let raw_id = self.lookup_raw_ident(meth)?.clone();
+                  let attr = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+                  self.put_term(clk, attr, IdentTerm_{id: meth, raw_id})?;
+                  let x = self._fresh()?.try_into_term().map_err(|e| e.into_check())?;
+                  let mut tup = self._take_scratch();
+                  tup.push(self.find(clk, lhs)?);
+                  tup.push(self.find(clk, attr)?);
+                  for &(_, y) in state.rtup.iter() {
+                    tup.push(self.find(clk, y)?);
+                  }
+                  let mut val = self.borrow_obj_val(obj)?;
+                  let knt_ = BorrowedMemKnt{
+                    clk:  knt.clk,
+                    prev: &knt.prev,
+                    cur:  MemKnt_::InterpEffectTerm(cur_term_code, state.clone()),
+                  };
+                  let result = val.__request__(self, span, obj.into(), &tup, x.into(), knt_);
+                  self.unborrow_obj_val(obj, val)?;
+                  self._put_scratch(tup);
+                  result?;
+                  if self.exc_.is_some() {
+                    // NB: the request raised.
+                    self.knt_ = knt.into();
+                  } else if self.knt_.is_some() {
+                    // NB: the request pushed a child continuation in place
+                    // of this one (e.g. a method call, see `_push_proc_call`),
+                    // which provides the result.
+                    _traceln!(self, "DEBUG: InterpEffectTerm: Enter:  obj request pushed knt");
+                  } else {
+                    self.put_res(x)?;
+                    self.knt_ = knt.prev.take();
+                    self.port = Port_::Return;
+                  }
+                }
+              }
             }
           }
         }
         (Port_::Return, &mut MemKnt_::InterpEffectTerm(_cur_term, ref mut state)) => {
           match state.cur {
-            EffectTermCodeInterpCursor_::LTerm{lterm, rtup} => {
+            EffectTermCodeInterpCursor_::LTerm{lterm, mut rtup} => {
               state.lterm = Some((lterm, self.get_res()?));
+              // NB: the method ident is not itself interpreted.
+              if !rtup.is_nil() {
+                let head_cel_ = self.lookup_term_code_cell(rtup)?;
+                if let TermCode_::Ident{id, ..} = self.lookup_term_code(head_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?)? {
+                  state.meth = Some(id);
+                  rtup = head_cel_.next.get().try_into_term_code().map_err(|e| e.into_check())?;
+                }
+              }
               state.cur = EffectTermCodeInterpCursor_::RTup{rtup};
               self.knt_ = knt.into();
               self.port = Port_::Enter;
@@ -5270,8 +12683,8 @@ impl FastInterp {
                 return Err(bot());
               } else {
                 let cur_cel_ = self.lookup_term_code_cell(rtup)?;
-                state.rtup.push((cur_cel_.dptr.into_term_code(), self.get_res()?));
-                state.cur = EffectTermCodeInterpCursor_::RTup{rtup: cur_cel_.next.get().into_term_code()};
+                state.rtup.push((cur_cel_.dptr.try_into_term_code().map_err(|e| e.into_check())?, self.get_res()?));
+                state.cur = EffectTermCodeInterpCursor_::RTup{rtup: cur_cel_.next.get().try_into_term_code().map_err(|e| e.into_check())?};
               }
               self.knt_ = knt.into();
               self.port = Port_::Enter;
@@ -5281,8 +12694,9 @@ impl FastInterp {
             }
           }
         }
-        _ => {
-          return Err(("unimpl").into());
+        transition::knt_unreachable!(&mut MemKnt_::Uninit) => {
+          // NB: already rejected by the dispatch above.
+          return Err(("bug: FastInterp::resume_: unreachable transition").into());
         }
       }
       let x_post = self._peek();
@@ -5292,6 +12706,9 @@ impl FastInterp {
         _traceln!(self, "DEBUG: FastInterp::resume_:       fresh=({:?} .. {:?}]", xlb, x_post);
       }
       self.write_snapshot();
+      if take(&mut self.brk.watch_brk) || self.brk.step {
+        return Ok(Yield_::Break);
+      }
     }
   }
 }
@@ -0,0 +1,121 @@
+use crate::bench::{Bencher};
+use crate::interp::*;
+
+// Hot-path benchmark routines (see `benches/interp.rs`); these live in
+// the interp module so that they can drive the unifier and the tuple
+// constructor directly.
+
+// NB: a unifier w/ a single class of `depth + 1` members, linked so that
+// `_find` of the last member walks all `depth` links (i.e. the roots are
+// ordered, and each unify makes the new member the root).
+fn _chain_unifier(depth: usize) -> (FastUnifier_, LClkInvalidSet, LClk, Vec<SNum>) {
+  let ctr = SNumCtr::default();
+  let clkctr = LClkCtr::default();
+  let clk = clkctr._fresh_clock();
+  let clkinval = LClkInvalidSet::default();
+  let mut log = FastLog_::default();
+  let mut unifier = FastUnifier_::default();
+  let xs: Vec<SNum> = (0 ..= depth).map(|_| ctr._fresh().unwrap()).collect();
+  for i in (0 .. depth).rev() {
+    unifier._unify(&mut log, &clkinval, clk, xs[i], xs[i + 1]).unwrap();
+  }
+  unifier.cache.borrow_mut().clear();
+  (unifier, clkinval, clk, xs)
+}
+
+// `_find` of the end of a chain, w/ the path cache cleared before each
+// iteration.
+pub fn bench_unifier_find_cold(b: &mut Bencher, depth: usize) {
+  let (unifier, clkinval, clk, xs) = _chain_unifier(depth);
+  let leaf = xs[depth];
+  b.iter(|| {
+    unifier.cache.borrow_mut().clear();
+    unifier._find(&clkinval, clk, leaf).unwrap()
+  });
+}
+
+// `_find` of the end of a chain, w/ a warm path cache.
+pub fn bench_unifier_find_warm(b: &mut Bencher, depth: usize) {
+  let (unifier, clkinval, clk, xs) = _chain_unifier(depth);
+  let leaf = xs[depth];
+  unifier._find(&clkinval, clk, leaf).unwrap();
+  b.iter(|| {
+    unifier._find(&clkinval, clk, leaf).unwrap()
+  });
+}
+
+// The `depth` unifications that build a chain.
+pub fn bench_unifier_unify_chain(b: &mut Bencher, depth: usize) {
+  b.iter_batched(|| {
+    let ctr = SNumCtr::default();
+    let xs: Vec<SNum> = (0 ..= depth).map(|_| ctr._fresh().unwrap()).collect();
+    (FastUnifier_::default(), FastLog_::default(), xs)
+  }, |(mut unifier, mut log, xs)| {
+    let clkinval = LClkInvalidSet::default();
+    let clk = LClkCtr::default()._fresh_clock();
+    for i in (0 .. depth).rev() {
+      unifier._unify(&mut log, &clkinval, clk, xs[i], xs[i + 1]).unwrap();
+    }
+    (unifier, log)
+  });
+}
+
+fn _pre_init_interp() -> FastInterp {
+  let mut interp = FastInterp::default();
+  interp.pre_init().unwrap();
+  interp
+}
+
+// `_cons_tuple` of `len` fresh members, i.e. a hash-cons miss and a
+// fresh tuple term.
+pub fn bench_tuple_alloc(b: &mut Bencher, len: usize) {
+  let mut interp = _pre_init_interp();
+  let clk = interp.clkctr._get_clock();
+  b.iter(|| {
+    let mut buf = TupleBuf_::new();
+    for _ in 0 .. len {
+      let x = interp._fresh().unwrap();
+      buf.push(ENum{ecls: x, inst: x});
+    }
+    interp._cons_tuple(clk, buf).unwrap()
+  });
+}
+
+// `_cons_tuple` of the same `len` members, i.e. a hash-cons hit.
+pub fn bench_tuple_cons_hit(b: &mut Bencher, len: usize) {
+  let mut interp = _pre_init_interp();
+  let clk = interp.clkctr._get_clock();
+  let mut buf = TupleBuf_::new();
+  for _ in 0 .. len {
+    let x = interp._fresh().unwrap();
+    buf.push(ENum{ecls: x, inst: x});
+  }
+  interp._cons_tuple(clk, buf.clone()).unwrap();
+  b.iter(|| {
+    interp._cons_tuple(clk, buf.clone()).unwrap()
+  });
+}
+
+// A run of `resume_` (via `interp_`) over the module `src`, from a fresh
+// cold start; the cold start itself is not timed.
+pub fn bench_resume(b: &mut Bencher, src: &str) {
+  b.iter_batched(|| {
+    let mut interp = _pre_init_interp();
+    interp.cold_start(src).unwrap();
+    interp
+  }, |mut interp| {
+    let yield_ = interp.interp_();
+    (interp, yield_)
+  });
+}
+
+// A synthetic module that backtracks through every branch of `depth`
+// nested choice points of `width` branches each, and then fails.
+pub fn backtrack_churn_src(width: usize, depth: usize) -> String {
+  let mut src = String::new();
+  for _ in 0 .. depth {
+    src.push_str(&format!("choice({})\n", width));
+  }
+  src.push_str("failure()\n");
+  src
+}
@@ -0,0 +1,95 @@
+use crate::interp::*;
+use crate::tap::{_debugln};
+
+// A read-only view of a live choice point (see
+// `FastInterp::choice_points`), for external search drivers.
+#[derive(Clone, Serialize, Debug)]
+pub struct ChoicePointInfo {
+  // The clock at which the choice point was first pushed; this is the
+  // key of the choice point (see `select_branch`).
+  pub clk:    LClk,
+  // The rank currently being explored, and the branch that it returns
+  // (see `ChoiceHeuristic` and `WeightOrder`).
+  pub rank:   RawChoiceRank,
+  pub branch: RawChoiceRank,
+  // `None` for an unbounded choice point.
+  pub limit:  Option<RawChoiceRank>,
+  pub span:   Option<SpanNum>,
+  pub loc:    Option<String>,
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // The live choice points, outermost first.
+  pub fn choice_points(&self) -> Vec<ChoicePointInfo> {
+    let mut buf = Vec::with_capacity(self.trace.buf.len());
+    for te in self.trace.buf.iter() {
+      let limit = if te.xlim == RawChoiceRank::max_value() {
+        None
+      } else {
+        Some(te.xlim)
+      };
+      let span = self._choice_span(te);
+      let loc = span.and_then(|span| self.span_loc(span));
+      buf.push(ChoicePointInfo{
+        clk:    te.root_clk,
+        rank:   te.xctr,
+        branch: te._choice_val(self.trace.heuristic),
+        limit,
+        span,
+        loc,
+      });
+    }
+    buf
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The number of live choice points.
+  pub fn current_depth(&self) -> usize {
+    self.trace.buf.len()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Forces the choice point at `clk` to take the rank `rank` next: the
+  // choice points inside of it are undone and popped, and the state is
+  // restored to the choice point, so that the next `interp_` resumes
+  // from there w/ `rank`. Any rank below the limit may be selected,
+  // including the ranks that were already explored.
+  pub fn select_branch(&mut self, clk: LClk, rank: RawChoiceRank) -> Result<(), InterpCheck> {
+    let p = match self.trace.clk_pos.get(&clk) {
+      None => return Err(format!("select_branch: no choice point at clk={:?}", clk).into()),
+      Some(&p) => p as usize
+    };
+    if rank >= self.trace.buf[p].xlim {
+      return Err(format!("select_branch: rank={} is not below the limit={}", rank, self.trace.buf[p].xlim).into());
+    }
+    _debugln!(self, "DEBUG: FastInterp::select_branch: p={} clk={:?} rank={}", p, clk, rank);
+    self.redo._reset_popped();
+    for q in (p + 1 .. self.trace.buf.len()).rev() {
+      let q_rank = self.trace.buf[q].xctr;
+      self._restore_pos(q, q_rank)?;
+      let te = self.trace._pop_pos(q as _)?;
+      self.redo._set_popped(te);
+    }
+    let prev_rank = self.trace.buf[p].xctr;
+    self.trace.buf[p].xctr = rank;
+    self._restore_pos(p, prev_rank)?;
+    Ok(())
+  }
+
+  // NB: the span of the apply that pushed the choice point, if any.
+  pub fn _choice_span(&self, te: &TraceEntry_) -> Option<SpanNum> {
+    if let Some(w) = te.worder.as_ref() {
+      return Some(w.span);
+    }
+    match te.knt_.as_ref().map(|knt| &knt.cur) {
+      Some(&MemKnt_::InterpApplyTerm(term_code, _)) => {
+        self.lookup_term_code(term_code).ok()?._span().ok()
+      }
+      _ => None
+    }
+  }
+}
@@ -0,0 +1,253 @@
+use crate::interp::*;
+
+// [Interp-API]
+//
+// The first-argument key of a defmatch clause (or of a call): a literal
+// value, or a constructor (an apply of an ident head) w/ its arity.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ClauseKey_ {
+  None,
+  Bool(bool),
+  Int(i64),
+  Atom(SafeStr),
+  List(usize),
+  Ctor(RawIdent_, usize),
+}
+
+// [Interp-API]
+//
+// The clauses of one arity, as a decision tree on the first-argument key.
+// Each clause list is in definition order; a clause w/out a key (i.e. a
+// non-indexable head) is in every list.
+#[derive(Clone, Default, Debug)]
+pub struct ClauseArity_ {
+  by_key:   FxHashMap<ClauseKey_, Vec<StmCodeNum>>,
+  // NB: the non-indexable clauses, which are the candidates for a key
+  // that no clause head has.
+  any:      Vec<StmCodeNum>,
+  all:      Vec<StmCodeNum>,
+}
+
+// [Interp-API]
+//
+// The clause index of the defmatch clauses of one name (see
+// `FastInterp::defmatch_candidates`).
+#[derive(Clone, Default, Debug)]
+pub struct ClauseIndex_ {
+  by_arity: BTreeMap<usize, ClauseArity_>,
+}
+
+impl ClauseIndex_ {
+  pub fn _insert(&mut self, arity: usize, key: Option<ClauseKey_>, x: StmCodeNum) {
+    let node = self.by_arity.entry(arity).or_default();
+    node.all.push(x);
+    match key {
+      None => {
+        node.any.push(x);
+        for xs in node.by_key.values_mut() {
+          xs.push(x);
+        }
+      }
+      Some(key) => {
+        let any = &node.any;
+        node.by_key.entry(key).or_insert_with(|| any.clone()).push(x);
+      }
+    }
+  }
+
+  // Removes the clause `x`, and returns the key it was inserted w/ (if it
+  // was inserted); NB: this undoes `_insert`, so `x` must be the latest
+  // clause of the arity.
+  pub fn _remove(&mut self, arity: usize, x: StmCodeNum) -> Option<Option<ClauseKey_>> {
+    let node = self.by_arity.get_mut(&arity)?;
+    if !node.all.contains(&x) {
+      return None;
+    }
+    node.all.retain(|&y| y != x);
+    let key = if node.any.contains(&x) {
+      node.any.retain(|&y| y != x);
+      for xs in node.by_key.values_mut() {
+        xs.retain(|&y| y != x);
+      }
+      None
+    } else {
+      let key = node.by_key.iter().find(|&(_, xs)| xs.contains(&x)).map(|(key, _)| key.clone());
+      if let Some(key) = key.as_ref() {
+        // NB: a key w/ no clause of its own is equivalent to no key.
+        let xs = node.by_key.get_mut(key).unwrap();
+        xs.retain(|&y| y != x);
+        if xs.len() == node.any.len() {
+          node.by_key.remove(key);
+        }
+      }
+      key
+    };
+    if node.all.is_empty() {
+      self.by_arity.remove(&arity);
+    }
+    Some(key)
+  }
+
+  pub fn _is_empty(&self) -> bool {
+    self.by_arity.is_empty()
+  }
+
+  // The candidate clauses, in definition order; `key` is `None` if the
+  // first argument is not indexable (e.g. unbound), in which case every
+  // clause of the arity is a candidate.
+  pub fn _lookup(&self, arity: usize, key: Option<&ClauseKey_>) -> &[StmCodeNum] {
+    let node = match self.by_arity.get(&arity) {
+      None => return &[],
+      Some(node) => node
+    };
+    match key {
+      None => &node.all,
+      Some(key) => match node.by_key.get(key) {
+        None => &node.any,
+        Some(xs) => xs
+      }
+    }
+  }
+}
+
+// The key of a raw clause pattern, if it is indexable.
+fn _raw_pattern_key(pat: &RawTerm_) -> Option<ClauseKey_> {
+  match pat {
+    &RawTerm_::NoneLit(..) => Some(ClauseKey_::None),
+    &RawTerm_::BoolLit(_, ref lit) => match lit.as_raw_str() {
+      "True" => Some(ClauseKey_::Bool(true)),
+      "False" => Some(ClauseKey_::Bool(false)),
+      _ => None
+    },
+    &RawTerm_::IntLit(_, ref lit) => {
+      i64::from_str(lit.as_raw_str()).ok().map(ClauseKey_::Int)
+    }
+    &RawTerm_::Neg(_, ref inner) => match &**inner {
+      &RawTerm_::IntLit(_, ref lit) => {
+        i64::from_str(&format!("-{}", lit.as_raw_str())).ok().map(ClauseKey_::Int)
+      }
+      _ => None
+    },
+    &RawTerm_::AtomLit(_, ref lit) => Some(ClauseKey_::Atom(lit.clone())),
+    &RawTerm_::ListLit(_, ref items) => Some(ClauseKey_::List(items.len())),
+    &RawTerm_::Group(_, ref pat) => _raw_pattern_key(pat),
+    &RawTerm_::Apply(_, ref tup) => match tup.first().map(|head| &**head) {
+      Some(&RawTerm_::Ident(_, ref head)) => {
+        Some(ClauseKey_::Ctor(head.clone(), tup.len() - 1))
+      }
+      _ => None
+    },
+    _ => None
+  }
+}
+
+// [Interp-API]
+//
+// The first-argument key of a raw defmatch clause: the clause head is
+// indexable if the first stm of its body (skipping comments) matches
+// the first param against an indexable pattern, i.e. `x ?= PAT` (or
+// `PAT ?= x`).
+pub(crate) fn _raw_clause_key(params: &[Option<RawIdent_>], body: &[Box<RawStm_>]) -> Option<ClauseKey_> {
+  let param = params.first()?.as_ref()?;
+  let head = body.iter().find(|stm| !matches!(&***stm, &RawStm_::Comment(..)))?;
+  let (lhs, rhs) = match &**head {
+    &RawStm_::Just(_, ref term) => match &**term {
+      &RawTerm_::QEqual(_, ref lhs, ref rhs) => (lhs, rhs),
+      _ => return None
+    },
+    _ => return None
+  };
+  match (&**lhs, &**rhs) {
+    (&RawTerm_::Ident(_, ref id), pat) if id == param => _raw_pattern_key(pat),
+    (pat, &RawTerm_::Ident(_, ref id)) if id == param => _raw_pattern_key(pat),
+    _ => None
+  }
+}
+
+impl FastInterp {
+  // [Interp-API]
+  //
+  // NB: the insert is undone w/ the `LoadRawStm` entry of `x` (see
+  // `_unindex_defmatch`).
+  pub(crate) fn _index_defmatch(&mut self, id: IdentNum, arity: usize, key: Option<ClauseKey_>, x: StmCodeNum) {
+    _traceln!(self, "DEBUG: FastInterp::_index_defmatch: id={:?} arity={} key={:?} x={:?}", id, arity, key, x);
+    match self.env.match_index.get_mut(&id) {
+      None => {
+        let mut index = ClauseIndex_::default();
+        index._insert(arity, key, x);
+        self.env.match_index.insert(id, index);
+      }
+      Some(index) => {
+        index._insert(arity, key, x);
+      }
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Undoes `_index_defmatch` of the clause `x`; returns the key it was
+  // indexed w/, if it was indexed.
+  pub(crate) fn _unindex_defmatch(&mut self, id: IdentNum, arity: usize, x: StmCodeNum) -> Option<Option<ClauseKey_>> {
+    let index = self.env.match_index.get_mut(&id)?;
+    let key = index._remove(arity, x);
+    if index._is_empty() {
+      self.env.match_index.remove(&id);
+    }
+    _traceln!(self, "DEBUG: FastInterp::_unindex_defmatch: id={:?} arity={} key={:?} x={:?}", id, arity, key, x);
+    key
+  }
+
+  // [Interp-API]
+  //
+  // The key of the value of `query`, if it is indexable (see
+  // `ClauseKey_`).
+  pub(crate) fn _clause_key(&self, clk: LClk, query: SNum) -> Result<Option<ClauseKey_>, InterpCheck> {
+    let query = self.find(clk, query)?;
+    for (_, val) in self.get_vals(clk, query)?.into_iter() {
+      let key = match val {
+        Val_::Lit(LitVal_::None) => ClauseKey_::None,
+        Val_::Lit(LitVal_::Bool(v)) => ClauseKey_::Bool(v),
+        Val_::Lit(LitVal_::Int(v)) => ClauseKey_::Int(v),
+        Val_::Lit(LitVal_::Atom(v)) => ClauseKey_::Atom(v),
+        Val_::Lit(LitVal_::List{buf}) => ClauseKey_::List(buf.len()),
+        _ => continue
+      };
+      return Ok(Some(key));
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query._inst()).map_err(|e| e.into_check())?;
+    for key in keys.iter() {
+      let t = match self.get_term(key._inst())?.and_then(|t| t.as_any().downcast_ref::<TupleTerm_>()) {
+        None => continue,
+        Some(t) => t
+      };
+      let head = match t.buf.first() {
+        None => continue,
+        Some(head) => head._inst()
+      };
+      let hkeys = self.env.unifier._findall(&self.clkinval, clk, head).map_err(|e| e.into_check())?;
+      for hkey in hkeys.iter() {
+        if let Some(h) = self.get_term(hkey._inst())?.and_then(|h| h.as_any().downcast_ref::<IdentTerm_>()) {
+          return Ok(Some(ClauseKey_::Ctor(h.raw_id.clone(), t.buf.len() - 1)));
+        }
+      }
+    }
+    Ok(None)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The candidate defmatch clauses of `id` for a call w/ the args `args`,
+  // in definition order (see `ClauseIndex_`).
+  pub fn defmatch_candidates(&self, clk: LClk, id: IdentNum, args: &[SNum]) -> Result<Vec<StmCodeNum>, InterpCheck> {
+    let index = match self.env.match_index.get(&id) {
+      None => return Ok(Vec::new()),
+      Some(index) => index
+    };
+    let key = match args.first() {
+      None => None,
+      Some(&arg) => self._clause_key(clk, arg)?
+    };
+    _traceln!(self, "DEBUG: FastInterp::defmatch_candidates: id={:?} arity={} key={:?}", id, args.len(), key);
+    Ok(index._lookup(args.len(), key.as_ref()).to_vec())
+  }
+}
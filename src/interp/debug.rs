@@ -0,0 +1,293 @@
+use crate::algo::{FxHashSet};
+use crate::interp::*;
+use crate::parse::{Ident as RawIdent_};
+use crate::tap::{_debugln};
+
+use std::mem::{take};
+
+// The breakpoint state (see `FastInterp::add_breakpoint_span` and
+// `FastInterp::add_breakpoint_stm`).
+//
+// A breakpoint is checked when a stm is entered. Upon a hit, `resume_`
+// yields `Yield_::Break` before the stm is run; the stm is recorded in
+// `hit`, so that the next resume enters it rather than breaking again.
+//
+// A watchpoint is checked upon a unification or a put val. Upon a hit,
+// the hit is recorded in `watch_hits`, and `resume_` yields
+// `Yield_::Break` at the end of the current step.
+#[derive(Clone, Default, Debug)]
+pub struct FastBreakpoints_ {
+  span:     FxHashSet<SpanNum>,
+  stm:      FxHashSet<StmCodeNum>,
+  hit:      Option<StmCodeNum>,
+  watch:    FxHashSet<SNum>,
+  watch_id: Vec<RawIdent_>,
+  watch_hits: Vec<WatchHit>,
+  pub watch_brk: bool,
+  // NB: set for the duration of `step`.
+  pub step: bool,
+}
+
+impl FastBreakpoints_ {
+  pub fn _is_empty(&self) -> bool {
+    self.span.is_empty() && self.stm.is_empty()
+  }
+
+  pub fn _is_watching(&self) -> bool {
+    !(self.watch.is_empty() && self.watch_id.is_empty())
+  }
+}
+
+// The change to a watched class (see `WatchHit`).
+#[derive(Clone, Debug)]
+pub enum WatchChange_ {
+  Unify{lhs: SNum, rhs: SNum},
+  PutVal{key: SNum},
+}
+
+// A watchpoint hit (see `FastInterp::watch`).
+#[derive(Clone, Debug)]
+pub struct WatchHit {
+  pub clk:  LClk,
+  // The watched SNum; for a watched ident, its binding at `clk`.
+  pub watch: SNum,
+  pub change: WatchChange_,
+}
+
+// The control state after a single step (see `FastInterp::step`).
+#[derive(Debug)]
+pub struct StepInfo {
+  // The clock after the step.
+  pub clk:  LClk,
+  pub port: Port_,
+  // The continuation stack, from the innermost continuation outwards,
+  // as pairs of (1) the clock at which it was pushed and (2) the name
+  // of its variant.
+  pub knt:  Vec<(LClk, &'static str)>,
+  // The span of the code of the innermost continuation, if any.
+  pub span: Option<SpanNum>,
+  // The yield of the step, if the step did not simply transition.
+  pub yield_: Option<Yield_>,
+}
+
+impl MemKnt_ {
+  pub fn _name(&self) -> &'static str {
+    self.kind().name()
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Adds a breakpoint on every stm w/ the given span.
+  pub fn add_breakpoint_span(&mut self, span: SpanNum) {
+    self.brk.span.insert(span);
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Adds a breakpoint on the given stm.
+  pub fn add_breakpoint_stm(&mut self, stm: SNum) -> Result<(), InterpCheck> {
+    let stm = stm.try_into_stm_code().map_err(|e| e.into_check())?;
+    self.brk.stm.insert(stm);
+    Ok(())
+  }
+
+  // [Interp-API-Pub]
+  pub fn clear_breakpoints(&mut self) {
+    self.brk.span.clear();
+    self.brk.stm.clear();
+    self.brk.hit = None;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Adds a watchpoint on the class of `x`: every unification that merges
+  // the class, and every val put in it, is a hit.
+  pub fn watch(&mut self, x: SNum) {
+    self.brk.watch.insert(x);
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Adds a watchpoint on the ident `name`. The ident is resolved upon
+  // each check, in the scope current at the time, so that it may be
+  // watched before it is bound.
+  pub fn watch_ident(&mut self, name: &str) {
+    let raw_id: RawIdent_ = name.into();
+    if !self.brk.watch_id.contains(&raw_id) {
+      self.brk.watch_id.push(raw_id);
+    }
+  }
+
+  // [Interp-API-Pub]
+  pub fn clear_watches(&mut self) {
+    self.brk.watch.clear();
+    self.brk.watch_id.clear();
+    self.brk.watch_brk = false;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Takes the watchpoint hits since the last call.
+  pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+    take(&mut self.brk.watch_hits)
+  }
+
+  // The watched SNums, paired w/ their classes at `clk`.
+  fn _watch_roots(&self, clk: LClk) -> Result<Vec<(SNum, SNum)>, InterpCheck> {
+    let mut roots = Vec::new();
+    for &x in self.brk.watch.iter() {
+      roots.push((x, self.find(clk, x)?.ecls));
+    }
+    for raw_id in self.brk.watch_id.iter() {
+      let id = match self.env._find_raw_ident(raw_id.as_raw_str()) {
+        None => continue,
+        Some(id) => id
+      };
+      if let (_, Some(x)) = self.resolve_ident(id)? {
+        roots.push((x, self.find(clk, x)?.ecls));
+      }
+    }
+    Ok(roots)
+  }
+
+  // [Interp-API]
+  //
+  // Checks the watchpoints before the unification of `lhs` and `rhs`; a
+  // unification within a single class is not a change.
+  pub(crate) fn _watch_unify(&mut self, clk: LClk, lhs: SNum, rhs: SNum) -> Result<(), InterpCheck> {
+    if !self.brk._is_watching() {
+      return Ok(());
+    }
+    let (lroot, rroot) = (self.find(clk, lhs)?.ecls, self.find(clk, rhs)?.ecls);
+    if lroot == rroot {
+      return Ok(());
+    }
+    for (watch, root) in self._watch_roots(clk)?.into_iter() {
+      if root == lroot || root == rroot {
+        _debugln!(self, "DEBUG: FastInterp::_watch_unify: clk={:?} watch={:?} lhs={:?} rhs={:?}", clk, watch, lhs, rhs);
+        self.brk.watch_hits.push(WatchHit{clk, watch, change: WatchChange_::Unify{lhs, rhs}});
+        self.brk.watch_brk = true;
+      }
+    }
+    Ok(())
+  }
+
+  // [Interp-API]
+  pub(crate) fn _watch_put_val(&mut self, clk: LClk, key: SNum) -> Result<(), InterpCheck> {
+    if !self.brk._is_watching() {
+      return Ok(());
+    }
+    let kroot = self.find(clk, key)?.ecls;
+    for (watch, root) in self._watch_roots(clk)?.into_iter() {
+      if root == kroot {
+        _debugln!(self, "DEBUG: FastInterp::_watch_put_val: clk={:?} watch={:?} key={:?}", clk, watch, key);
+        self.brk.watch_hits.push(WatchHit{clk, watch, change: WatchChange_::PutVal{key}});
+        self.brk.watch_brk = true;
+      }
+    }
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Whether entering the stm hits a breakpoint. Breakpoints are not hit
+  // while stepping.
+  pub(crate) fn _break_stm(&mut self, stm: StmCodeNum, span: SpanNum) -> bool {
+    if self.brk.step || self.brk._is_empty() {
+      return false;
+    }
+    if self.brk.hit.take() == Some(stm) {
+      return false;
+    }
+    if self.brk.stm.contains(&stm) || self.brk.span.contains(&span) {
+      _debugln!(self, "DEBUG: FastInterp::_break_stm: stm={:?} span={:?}", stm, span);
+      self.brk.hit = Some(stm);
+      return true;
+    }
+    false
+  }
+
+  // [Interp-API]
+  pub(crate) fn _knt_span(&self, cur: &MemKnt_) -> Result<Option<SpanNum>, InterpCheck> {
+    Ok(match cur {
+      &MemKnt_::InterpStm(x, _) |
+      &MemKnt_::InterpIfStm(x, _) |
+      &MemKnt_::InterpMatchStm(x, _) |
+      &MemKnt_::InterpClassStm(x, _) |
+      &MemKnt_::InterpModuleStm(x, _) |
+      &MemKnt_::InterpProcBody(x, _) => {
+        Some(self.lookup_stm_code(x)?._span())
+      }
+      &MemKnt_::InterpLambdaBody(x, _) |
+      &MemKnt_::InterpTerm(x, _) |
+      &MemKnt_::InterpQualIdentTerm(x, _) |
+      &MemKnt_::InterpBunchTerm(x, _) |
+      &MemKnt_::InterpEqualTerm(x, _) |
+      &MemKnt_::InterpInTerm(x, _) |
+      &MemKnt_::InterpAndTerm(x, _) |
+      &MemKnt_::InterpOrTerm(x, _) |
+      &MemKnt_::InterpNEqualTerm(x, _) |
+      &MemKnt_::InterpQEqualTerm(x, _) |
+      &MemKnt_::InterpApplyTerm(x, _) |
+      &MemKnt_::InterpListCompTerm(x, _) |
+      &MemKnt_::InterpApplyBindLTerm(x, _) |
+      &MemKnt_::InterpApplyBindRTerm(x, _) |
+      &MemKnt_::InterpBindLTerm(x, _) |
+      &MemKnt_::InterpBindRTerm(x, _) |
+      &MemKnt_::InterpSubstTerm(x, _) |
+      &MemKnt_::InterpRebindLTerm(x, _) |
+      &MemKnt_::InterpRebindRTerm(x, _) |
+      &MemKnt_::InterpEffectTerm(x, _) |
+      &MemKnt_::InterpAwaitTerm(x, _) => {
+        self.lookup_term_code(x)?._span().ok()
+      }
+      _ => None
+    })
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Runs exactly one control transition of `resume_`. Unlike `interp_`,
+  // the run limits are not checked, nor is the gc run.
+  //
+  // Upon a failure, the step also backtracks, as in `interp_`; if there
+  // is no choice point left to backtrack to, the step yields
+  // `Yield_::Halt`.
+  pub fn step(&mut self) -> Result<StepInfo, InterpCheck> {
+    self.brk.step = true;
+    let ret = self.resume_();
+    self.brk.step = false;
+    let yield_ = match ret? {
+      Yield_::Break => None,
+      Yield_::Fail => {
+        let clk = self.clkctr._get_clock();
+        self._hook_fail(clk);
+        if self._backtrack()? {
+          Some(Yield_::Fail)
+        } else {
+          Some(Yield_::Halt)
+        }
+      }
+      yield_ => Some(yield_)
+    };
+    let mut knt = Vec::new();
+    let mut span = None;
+    let mut kcur = self.knt_.as_ref();
+    while let Some(k) = kcur {
+      if knt.is_empty() {
+        span = self._knt_span(&k.cur)?;
+      }
+      knt.push((k.clk, k.cur._name()));
+      kcur = k.prev.as_ref();
+    }
+    Ok(StepInfo{
+      clk:  self.clkctr._get_clock(),
+      port: self.port,
+      knt,
+      span,
+      yield_,
+    })
+  }
+}
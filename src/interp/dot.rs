@@ -0,0 +1,188 @@
+use crate::interp::*;
+
+use std::fmt::{Write as FmtWrite};
+
+// The parts of the interpreter state rendered by `FastInterp::export_dot`.
+#[derive(Clone, Copy, Debug)]
+pub struct DotConfig {
+  // Only render the continuations, choice points, and unifier classes
+  // whose clock falls in the range; `None` renders all of them.
+  pub clk_range: Option<LClkRange>,
+  pub knt:      bool,
+  pub trace:    bool,
+  pub eclasses: bool,
+}
+
+impl Default for DotConfig {
+  fn default() -> DotConfig {
+    DotConfig{
+      clk_range: None,
+      knt:      true,
+      trace:    true,
+      eclasses: true,
+    }
+  }
+}
+
+impl DotConfig {
+  pub fn _in_range(&self, clk: LClk) -> bool {
+    match self.clk_range {
+      None => true,
+      Some(rg) => rg.lb <= clk && clk < rg.ub
+    }
+  }
+}
+
+// NB: escapes a DOT quoted string.
+fn _dot_escape(s: &str, buf: &mut String) {
+  for c in s.chars() {
+    match c {
+      '"' => buf.push_str("\\\""),
+      '\\' => buf.push_str("\\\\"),
+      '\n' => buf.push_str("\\n"),
+      _ => buf.push(c)
+    }
+  }
+}
+
+fn _dot_snum(x: SNum) -> String {
+  format!("{}.{}", x._key(), x._tag())
+}
+
+// NB: the variant name of a continuation, w/out its micro-state.
+fn _knt_name(cur: &MemKnt_) -> String {
+  let s = format!("{:?}", cur);
+  match s.find('(') {
+    None => s,
+    Some(i) => s[ .. i].to_string()
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Writes the current continuation chain, the choice trace, and the
+  // unifier classes (roots w/ their members) as a DOT graph.
+  pub fn export_dot(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+    self.export_dot_with(writer, DotConfig::default())
+  }
+
+  // [Interp-API-Pub]
+  pub fn export_dot_with(&self, writer: &mut dyn Write, cfg: DotConfig) -> std::io::Result<()> {
+    let buf = self._dot(cfg);
+    writer.write_all(buf.as_bytes())?;
+    writer.flush()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _dot(&self, cfg: DotConfig) -> String {
+    let clk = self.clkctr._get_clock();
+    let mut buf = String::new();
+    writeln!(&mut buf, "digraph interp {{").unwrap();
+    writeln!(&mut buf, "  label=\"clk={}\";", clk._into_raw()).unwrap();
+    writeln!(&mut buf, "  node [shape=box, fontname=\"monospace\"];").unwrap();
+    if cfg.knt {
+      self._dot_knt(&cfg, &mut buf);
+    }
+    if cfg.trace {
+      self._dot_trace(&cfg, &mut buf);
+    }
+    if cfg.eclasses {
+      self._dot_eclasses(&cfg, clk, &mut buf);
+    }
+    writeln!(&mut buf, "}}").unwrap();
+    buf
+  }
+
+  // NB: the continuation chain, innermost first; each continuation has
+  // an edge to the continuation that it returns to.
+  fn _dot_knt(&self, cfg: &DotConfig, buf: &mut String) {
+    writeln!(buf, "  subgraph cluster_knt {{").unwrap();
+    writeln!(buf, "    label=\"knt\";").unwrap();
+    let mut cursor = self.knt_.as_ref();
+    let mut prev_node: Option<usize> = None;
+    let mut idx = 0;
+    while let Some(knt) = cursor {
+      if cfg._in_range(knt.clk) {
+        let mut label = String::new();
+        _dot_escape(&format!("{} clk={}", _knt_name(&knt.cur), knt.clk._into_raw()), &mut label);
+        writeln!(buf, "    knt{} [label=\"{}\"];", idx, label).unwrap();
+        if let Some(p) = prev_node {
+          writeln!(buf, "    knt{} -> knt{};", p, idx).unwrap();
+        }
+        prev_node = Some(idx);
+      }
+      idx += 1;
+      cursor = knt.prev.as_ref();
+    }
+    writeln!(buf, "  }}").unwrap();
+  }
+
+  // NB: the choice points, outermost first (see `choice_points`).
+  fn _dot_trace(&self, cfg: &DotConfig, buf: &mut String) {
+    writeln!(buf, "  subgraph cluster_trace {{").unwrap();
+    writeln!(buf, "    label=\"trace\";").unwrap();
+    let mut prev_node: Option<usize> = None;
+    for (idx, cp) in self.choice_points().into_iter().enumerate() {
+      if !cfg._in_range(cp.clk) {
+        continue;
+      }
+      let mut s = format!("clk={} rank={} branch={}", cp.clk._into_raw(), cp.rank, cp.branch);
+      match cp.limit {
+        None => s.push_str(" limit=*"),
+        Some(lim) => write!(&mut s, " limit={}", lim).unwrap()
+      }
+      if let Some(loc) = cp.loc.as_ref() {
+        write!(&mut s, "\n{}", loc).unwrap();
+      }
+      let mut label = String::new();
+      _dot_escape(&s, &mut label);
+      writeln!(buf, "    cp{} [shape=diamond, label=\"{}\"];", idx, label).unwrap();
+      if let Some(p) = prev_node {
+        writeln!(buf, "    cp{} -> cp{};", p, idx).unwrap();
+      }
+      prev_node = Some(idx);
+    }
+    writeln!(buf, "  }}").unwrap();
+  }
+
+  // NB: a class is in the clock range if any of its members was joined
+  // to it in the range.
+  fn _dot_eclasses(&self, cfg: &DotConfig, clk: LClk, buf: &mut String) {
+    let unifier = &self.env.unifier;
+    let stats = unifier.stats();
+    writeln!(buf, "  subgraph cluster_eclasses {{").unwrap();
+    let mut s = format!("eclasses (n={}, max depth={}", stats.classes, stats.max_depth());
+    if let Some(rate) = stats.cache_hit_rate() {
+      write!(&mut s, ", cache hit={:.3}", rate).unwrap();
+    }
+    s.push(')');
+    writeln!(buf, "    label=\"{}\";", s).unwrap();
+    for root in unifier.iter_roots() {
+      if cfg.clk_range.is_some() {
+        let hit = unifier.iter_class(root).any(|x| {
+          match unifier.tree.get(&x) {
+            None => false,
+            Some(&(link_clk, _)) => cfg._in_range(link_clk)
+          }
+        });
+        if !hit {
+          continue;
+        }
+      }
+      let mut s = _dot_snum(root);
+      write!(&mut s, " = {}", self.render_value(clk, ENum{ecls: root, inst: root})).unwrap();
+      s.push('\n');
+      for (i, x) in unifier.iter_class(root).enumerate() {
+        if i > 0 {
+          s.push_str(", ");
+        }
+        s.push_str(&_dot_snum(x));
+      }
+      let mut label = String::new();
+      _dot_escape(&s, &mut label);
+      writeln!(buf, "    ecls_{} [label=\"{}\"];", root._key(), label).unwrap();
+    }
+    writeln!(buf, "  }}").unwrap();
+  }
+}
@@ -0,0 +1,153 @@
+use crate::algo::{FxHashSet};
+use crate::interp::*;
+
+// [Interp-API]
+#[derive(Clone, Debug)]
+pub enum ProvKind_ {
+  Unify{lhs: SNum, rhs: SNum},
+  // NB: the premises are the children of the tuple, in order.
+  Tuple{x: SNum, premises: Vec<ENum>},
+}
+
+// [Interp-API]
+//
+// The provenance of a derived unification or tuple: the stm being
+// interpreted when it was derived.
+#[derive(Clone, Debug)]
+pub struct ProvEntry_ {
+  clk:  LClk,
+  stm:  Option<StmCodeNum>,
+  kind: ProvKind_,
+}
+
+// [Interp-API]
+//
+// The provenance log, in clock order (see `FastInterp::set_explain`).
+// Backtracking truncates it at the restored choice point, along w/ the
+// undo log.
+#[derive(Clone, Default, Debug)]
+pub struct FastProvLog_ {
+  buf:  Vec<ProvEntry_>,
+  // NB: the most recently entered stm; after a nested stm (e.g. in a
+  // proc body) returns, this is still the nested stm.
+  cur_stm: Option<StmCodeNum>,
+}
+
+impl FastProvLog_ {
+  // [Interp-API]
+  pub(crate) fn _set_stm(&mut self, stm: StmCodeNum) {
+    self.cur_stm = Some(stm);
+  }
+
+  // [Interp-API]
+  pub(crate) fn _push(&mut self, clk: LClk, kind: ProvKind_) {
+    self.buf.push(ProvEntry_{clk, stm: self.cur_stm, kind});
+  }
+
+  // [Interp-API]
+  //
+  // Drop the records at or after `clk` (see `_restore_pos`).
+  pub(crate) fn _truncate(&mut self, clk: LClk) -> usize {
+    let p = self.buf.partition_point(|e| e.clk < clk);
+    let n = self.buf.len() - p;
+    self.buf.truncate(p);
+    n
+  }
+}
+
+// A derivation tree (see `FastInterp::explain`), serialized as JSON
+// via `to_json`. A node is derived `via`:
+// - "class": a unifier class; the premises are the derivations of its
+//   members, in clock order.
+// - "unify": a unification of two members (in `term`, as `l = r`).
+// - "tuple": a tuple; the premises are the classes of its children.
+// - "seen": a class already explained on the path (e.g. in a cycle).
+#[derive(Clone, Serialize, Debug)]
+pub struct ProofTree {
+  pub term: String,
+  pub via:  &'static str,
+  // The stm (and its source location) that derived the node.
+  pub stm:  Option<SNum>,
+  pub loc:  Option<String>,
+  pub premises: Vec<ProofTree>,
+}
+
+impl ProofTree {
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).unwrap()
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Sets whether the provenance of each derived unification and tuple is
+  // recorded, for `explain`; the recording is off by default. Turning it
+  // off drops the records.
+  pub fn set_explain(&mut self, explain: bool) {
+    if !explain {
+      self.prov = None;
+    } else if self.prov.is_none() {
+      self.prov = Some(FastProvLog_::default());
+    }
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Explains the class of `query` as a tree of the derivations (on the
+  // current trace) that built it.
+  pub fn explain(&self, query: ENum) -> Result<ProofTree, InterpCheck> {
+    let prov = match self.prov.as_ref() {
+      None => return Err("explain: provenance is not recorded (see set_explain)".into()),
+      Some(prov) => prov
+    };
+    let clk = self.clkctr._get_clock();
+    let mut ecls = Vec::with_capacity(prov.buf.len());
+    for e in prov.buf.iter() {
+      let x = match &e.kind {
+        &ProvKind_::Unify{lhs, ..} => lhs,
+        &ProvKind_::Tuple{x, ..} => x
+      };
+      ecls.push(self.find(clk, x)?.ecls);
+    }
+    let mut path = FxHashSet::default();
+    self._explain_class(clk, prov, &ecls, query.inst, &mut path)
+  }
+
+  fn _explain_class(&self, clk: LClk, prov: &FastProvLog_, ecls: &[SNum], query: SNum, path: &mut FxHashSet<SNum>) -> Result<ProofTree, InterpCheck> {
+    let query = self.find(clk, query)?;
+    let term = self.render_value(clk, query);
+    if !path.insert(query.ecls) {
+      return Ok(ProofTree{term, via: "seen", stm: None, loc: None, premises: Vec::new()});
+    }
+    let mut premises = Vec::new();
+    for (e, &e_ecls) in prov.buf.iter().zip(ecls.iter()) {
+      if e_ecls != query.ecls {
+        continue;
+      }
+      let stm = e.stm.map(|stm| stm.into());
+      let loc = match e.stm {
+        None => None,
+        Some(stm) => self.span_loc(self.lookup_stm_code(stm)?._span())
+      };
+      match &e.kind {
+        &ProvKind_::Unify{lhs, rhs} => {
+          let term = format!("{} = {}",
+              self.render_value(clk, self.find(clk, lhs)?),
+              self.render_value(clk, self.find(clk, rhs)?));
+          premises.push(ProofTree{term, via: "unify", stm, loc, premises: Vec::new()});
+        }
+        &ProvKind_::Tuple{x, premises: ref xs} => {
+          let term = self.render_value(clk, self.find(clk, x)?);
+          let mut tup = Vec::with_capacity(xs.len());
+          for &x in xs.iter() {
+            tup.push(self._explain_class(clk, prov, ecls, x.inst, path)?);
+          }
+          premises.push(ProofTree{term, via: "tuple", stm, loc, premises: tup});
+        }
+      }
+    }
+    path.remove(&query.ecls);
+    Ok(ProofTree{term, via: "class", stm: None, loc: None, premises})
+  }
+}
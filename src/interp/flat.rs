@@ -0,0 +1,207 @@
+use crate::algo::{BTreeMap};
+use crate::interp::*;
+
+use serde::{Deserialize};
+use serde::de::{Deserializer, Error as DeError};
+
+use std::fs;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::path::{Path};
+
+// NB: the inverse of `impl Serialize for SNum`, i.e. parses either
+// "SNum(nil)" or "SNum(<key>.<tag>)".
+fn _parse_snum(s: &str) -> Option<SNum> {
+  let s = s.strip_prefix("SNum(")?.strip_suffix(')')?;
+  if s == "nil" {
+    return Some(nil());
+  }
+  let (key, tag) = s.split_once('.')?;
+  let key: RawSNum = key.parse().ok()?;
+  let tag: RawSNum = tag.parse().ok()?;
+  if key > SNUM_KEY_MAX || tag > SNUM_TAG_MASK {
+    return None;
+  }
+  Some(SNum((key << SNUM_TAG_BITS) | tag))
+}
+
+fn _parse_lclk(s: &str) -> Option<LClk> {
+  let s = s.strip_prefix("LClk(")?.strip_suffix(')')?;
+  if s == "nil" {
+    return Some(nil());
+  }
+  s.parse().ok().map(LClk)
+}
+
+impl<'de> Deserialize<'de> for SNum {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SNum, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    _parse_snum(&s).ok_or_else(|| D::Error::custom(format!("invalid SNum: {:?}", s)))
+  }
+}
+
+impl<'de> Deserialize<'de> for LClk {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<LClk, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    _parse_lclk(&s).ok_or_else(|| D::Error::custom(format!("invalid LClk: {:?}", s)))
+  }
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+struct _ENumRepr {
+  ENum_cls:  SNum,
+  ENum_inst: SNum,
+}
+
+impl<'de> Deserialize<'de> for ENum {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ENum, D::Error> {
+    let repr = _ENumRepr::deserialize(deserializer)?;
+    Ok(ENum{ecls: repr.ENum_cls, inst: repr.ENum_inst})
+  }
+}
+
+// NB: the entries are compared structurally; in particular, the ENums
+// of a tuple term are compared by their (class, instance) pairs, not by
+// unification (cf. `FastInterp::same_cls`).
+impl PartialEq for FlatTabled_ {
+  fn eq(&self, rhs: &FlatTabled_) -> bool {
+    match (self, rhs) {
+      (&FlatTabled_::_Top, &FlatTabled_::_Top) |
+      (&FlatTabled_::Cell, &FlatTabled_::Cell) |
+      (&FlatTabled_::ModCode, &FlatTabled_::ModCode) |
+      (&FlatTabled_::StmCode, &FlatTabled_::StmCode) |
+      (&FlatTabled_::TermCode, &FlatTabled_::TermCode) |
+      (&FlatTabled_::NoneLitTerm, &FlatTabled_::NoneLitTerm) |
+      (&FlatTabled_::TrueLitTerm, &FlatTabled_::TrueLitTerm) |
+      (&FlatTabled_::FalseLitTerm, &FlatTabled_::FalseLitTerm) |
+      (&FlatTabled_::LitTerm, &FlatTabled_::LitTerm) => true,
+      (&FlatTabled_::IdentTerm{raw_id: ref l}, &FlatTabled_::IdentTerm{raw_id: ref r}) => l == r,
+      (&FlatTabled_::IntLitTerm(l), &FlatTabled_::IntLitTerm(r)) => l == r,
+      (&FlatTabled_::StrLitTerm(ref l), &FlatTabled_::StrLitTerm(ref r)) => l == r,
+      (&FlatTabled_::TupleTerm{buf: ref l}, &FlatTabled_::TupleTerm{buf: ref r}) => {
+        l.len() == r.len() &&
+        l.iter().zip(r.iter()).all(|(l, r)| l.ecls == r.ecls && l.inst == r.inst)
+      }
+      _ => false
+    }
+  }
+}
+
+// The tables of a `FlatInterp`, keyed by their primary keys (see
+// `FlatInterp::tables`).
+#[derive(Debug)]
+pub struct FlatTables {
+  pub clk:    LClk,
+  pub span:   BTreeMap<SNum, RawSpan_>,
+  pub code:   BTreeMap<SNum, FlatTabled_>,
+  pub ident:  BTreeMap<SNum, RawIdent_>,
+  pub term:   BTreeMap<SNum, FlatTabled_>,
+}
+
+// One structural difference between two `FlatInterp`s (see
+// `FlatInterp::diff`). An entry is `Missing` if it is only in the lhs,
+// and `Extra` if it is only in the rhs.
+#[derive(Clone, Debug)]
+pub enum FlatDiff {
+  Clk{lhs: LClk, rhs: LClk},
+  Missing{table: &'static str, key: SNum},
+  Extra{table: &'static str, key: SNum},
+  Changed{table: &'static str, key: SNum},
+}
+
+fn _diff_table<V: PartialEq>(table: &'static str, lhs: &BTreeMap<SNum, V>, rhs: &BTreeMap<SNum, V>, buf: &mut Vec<FlatDiff>) {
+  for (&key, lv) in lhs.iter() {
+    match rhs.get(&key) {
+      None => buf.push(FlatDiff::Missing{table, key}),
+      Some(rv) => if lv != rv {
+        buf.push(FlatDiff::Changed{table, key});
+      }
+    }
+  }
+  for &key in rhs.keys() {
+    if !lhs.contains_key(&key) {
+      buf.push(FlatDiff::Extra{table, key});
+    }
+  }
+}
+
+impl FlatInterp {
+  // [Interp-API-Pub]
+  //
+  // The inverse of `vectorize`.
+  pub fn from_json(s: &str) -> Result<FlatInterp, serde_json::Error> {
+    serde_json::from_str(s)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Loads the test vector at `src` (see `sync_testv`); `None` if there
+  // is no test vector at `src`.
+  pub fn load_testv<P: AsRef<Path>>(src: P) -> Result<Option<FlatInterp>, IoError> {
+    let s = match fs::read_to_string(src) {
+      Err(e) => {
+        if e.kind() == IoErrorKind::NotFound {
+          return Ok(None);
+        }
+        return Err(e);
+      }
+      Ok(s) => s
+    };
+    FlatInterp::from_json(&s)
+      .map(Some)
+      .map_err(|e| IoError::new(IoErrorKind::InvalidData, e))
+  }
+
+  pub fn attest_ct(&self) -> u32 {
+    self.attest_ct
+  }
+
+  pub fn clk(&self) -> LClk {
+    self.clk
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Reconstructs the keyed tables from the flattened entries. Should a
+  // primary key recur in a table, its last entry wins.
+  pub fn tables(&self) -> FlatTables {
+    let mut tables = FlatTables{
+      clk:    self.clk,
+      span:   BTreeMap::new(),
+      code:   BTreeMap::new(),
+      ident:  BTreeMap::new(),
+      term:   BTreeMap::new(),
+    };
+    for e in self.env.span.iter() {
+      tables.span.insert(e.prim_key, e.flat_val.clone());
+    }
+    for e in self.env.code.iter() {
+      tables.code.insert(e.prim_key, e.flat_val.clone());
+    }
+    for e in self.env.ident.iter() {
+      tables.ident.insert(e.prim_key, e.flat_val.clone());
+    }
+    for e in self.env.term.iter() {
+      tables.term.insert(e.prim_key, e.flat_val.clone());
+    }
+    tables
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The structural differences between this and `rhs`, modulo the
+  // attestation count and the order of the table entries.
+  pub fn diff(&self, rhs: &FlatInterp) -> Vec<FlatDiff> {
+    let lhs = self.tables();
+    let rhs = rhs.tables();
+    let mut buf = Vec::new();
+    if lhs.clk != rhs.clk {
+      buf.push(FlatDiff::Clk{lhs: lhs.clk, rhs: rhs.clk});
+    }
+    _diff_table("span", &lhs.span, &rhs.span, &mut buf);
+    _diff_table("code", &lhs.code, &rhs.code, &mut buf);
+    _diff_table("ident", &lhs.ident, &rhs.ident, &mut buf);
+    _diff_table("term", &lhs.term, &rhs.term, &mut buf);
+    buf
+  }
+}
@@ -0,0 +1,75 @@
+use crate::interp::*;
+
+// Event callbacks for embedders (e.g. visualizers, learning loops);
+// install w/ `FastInterp::set_hooks`. Every callback defaults to a
+// no-op.
+//
+// NB: the callbacks only observe the run; they are invoked while the
+// interpreter is mid-step, and so they are not passed the interpreter.
+pub trait InterpHooks {
+  // A unification of `lhs` and `rhs`, before it is applied.
+  fn on_unify(&mut self, _lhs: SNum, _rhs: SNum, _clk: LClk) {
+  }
+
+  // A failure, before backtracking (if any).
+  fn on_fail(&mut self, _clk: LClk) {
+  }
+
+  // A choice point (keyed by the clock at which it was first pushed, see
+  // `ChoicePointInfo`) taking the branch of `rank`; `limit` is `None` for
+  // an unbounded choice point.
+  fn on_choice(&mut self, _clk: LClk, _rank: RawChoiceRank, _limit: Option<RawChoiceRank>) {
+  }
+
+  // A yield of `interp_` back to the caller.
+  fn on_yield(&mut self, _yield_: &Yield_) {
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Installs the event hooks, returning the previously installed hooks.
+  // Hooks are not inherited by a fork.
+  pub fn set_hooks(&mut self, hooks: Box<dyn InterpHooks>) -> Option<Box<dyn InterpHooks>> {
+    self.hooks.replace(hooks)
+  }
+
+  // [Interp-API-Pub]
+  pub fn unset_hooks(&mut self) -> Option<Box<dyn InterpHooks>> {
+    self.hooks.take()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _hook_unify(&mut self, clk: LClk, lhs: SNum, rhs: SNum) {
+    if let Some(hooks) = self.hooks.as_mut() {
+      hooks.on_unify(lhs, rhs, clk);
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _hook_fail(&mut self, clk: LClk) {
+    if let Some(hooks) = self.hooks.as_mut() {
+      hooks.on_fail(clk);
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _hook_choice(&mut self, clk: LClk, rank: RawChoiceRank, xlim: RawChoiceRank) {
+    if let Some(hooks) = self.hooks.as_mut() {
+      let limit = if xlim == RawChoiceRank::max_value() {
+        None
+      } else {
+        Some(xlim)
+      };
+      hooks.on_choice(clk, rank, limit);
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _hook_yield(&mut self, yield_: &Yield_) {
+    if let Some(hooks) = self.hooks.as_mut() {
+      hooks.on_yield(yield_);
+    }
+  }
+}
@@ -0,0 +1,222 @@
+use crate::algo::{FxHashMap};
+use crate::interp::*;
+use crate::tap::{_debugln};
+
+use std::mem::{take};
+
+// The stats of a branch (i.e. a rank) of an MCTS node.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MctsEdge_ {
+  visits: u64,
+  value:  f64,
+}
+
+// An MCTS node: a choice point, keyed by the path of ranks taken at the
+// choice points before it (see `FastMctsTree_`).
+#[derive(Clone, Default, Debug)]
+pub struct MctsNode_ {
+  visits: u64,
+  edges:  BTreeMap<RawChoiceRank, MctsEdge_>,
+}
+
+// The MCTS tree of `FastInterp::interp_mcts_`, which persists between
+// calls from the same root state (i.e. the same clock and trace depth);
+// from any other state, the tree starts over.
+#[derive(Clone, Default, Debug)]
+pub struct FastMctsTree_ {
+  root:   Option<(LClk, usize)>,
+  nodes:  FxHashMap<Vec<RawChoiceRank>, MctsNode_>,
+  // The UCT exploration constant (sqrt(2) by default).
+  explore: Option<f64>,
+  best:   Option<(f64, Vec<RawChoiceRank>)>,
+  iter_ct: u64,
+}
+
+#[derive(Clone, Serialize, Debug)]
+pub struct MctsStats {
+  pub iter_ct:  u64,
+  pub node_ct:  usize,
+  // The best reward so far, and the path of ranks (from the root) of
+  // its rollout.
+  pub best_reward: Option<f64>,
+  pub best_path:   Vec<RawChoiceRank>,
+}
+
+impl FastMctsTree_ {
+  pub fn _uct_rank(&self, path: &[RawChoiceRank], te: &TraceEntry_, prefs: Option<&[u32]>) -> RawChoiceRank {
+    let node = self.nodes.get(path);
+    // NB: an unbounded choice point is widened by one untried rank at a
+    // time.
+    let width = if te.xlim == RawChoiceRank::max_value() {
+      let max_tried = node.and_then(|n| n.edges.keys().next_back().copied());
+      max_tried.map(|r| r.saturating_add(2)).unwrap_or(1).min(te.xlim)
+    } else {
+      te.xlim
+    };
+    // The untried ranks go first, most preferred first (see
+    // `SlowPVCache_::_prefs`).
+    let mut untried: Option<(u32, RawChoiceRank)> = None;
+    for r in 0 .. width {
+      if node.map(|n| n.edges.contains_key(&r)).unwrap_or(false) {
+        continue;
+      }
+      let pref = match (prefs, te.worder.as_ref()) {
+        (Some(prefs), Some(w)) => {
+          w.order.get(r as usize).and_then(|&b| prefs.get(b as usize)).copied().unwrap_or(0)
+        }
+        _ => 0
+      };
+      match untried {
+        Some((p, _)) if p >= pref => {}
+        _ => untried = Some((pref, r))
+      }
+    }
+    if let Some((_, r)) = untried {
+      return r;
+    }
+    let node = match node {
+      None => return 0,
+      Some(n) => n
+    };
+    let c = self.explore.unwrap_or(std::f64::consts::SQRT_2);
+    let ln_n = (node.visits.max(1) as f64).ln();
+    let mut best: Option<(f64, RawChoiceRank)> = None;
+    for (&r, e) in node.edges.iter() {
+      if r >= width {
+        continue;
+      }
+      let n = e.visits.max(1) as f64;
+      let uct = e.value / n + c * (ln_n / n).sqrt();
+      match best {
+        Some((b, _)) if b >= uct => {}
+        _ => best = Some((uct, r))
+      }
+    }
+    best.map(|(_, r)| r).unwrap_or(0)
+  }
+
+  pub fn _backup(&mut self, path: &[RawChoiceRank], reward: f64) {
+    for d in 0 .. path.len() {
+      let node = self.nodes.entry(path[ .. d].to_vec()).or_default();
+      node.visits += 1;
+      let e = node.edges.entry(path[d]).or_default();
+      e.visits += 1;
+      e.value += reward;
+    }
+    match &self.best {
+      Some((b, _)) if *b >= reward => {}
+      _ => self.best = Some((reward, path.to_vec()))
+    }
+  }
+
+  // NB: the most visited rank at each node along the path.
+  pub fn _most_visited(&self, path: &[RawChoiceRank]) -> Option<RawChoiceRank> {
+    let node = self.nodes.get(path)?;
+    let mut best: Option<(u64, RawChoiceRank)> = None;
+    for (&r, e) in node.edges.iter() {
+      match best {
+        Some((v, _)) if v >= e.visits => {}
+        _ => best = Some((e.visits, r))
+      }
+    }
+    best.map(|(_, r)| r)
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  pub fn set_mcts_explore(&mut self, c: f64) {
+    self.mcts.explore = Some(c);
+  }
+
+  // [Interp-API-Pub]
+  pub fn reset_mcts(&mut self) {
+    self.mcts = FastMctsTree_::default();
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Runs `iter_ct` MCTS rollouts from the current state, w/out changing
+  // it: each choice point on a rollout is a node of the tree, whose
+  // branch is picked by UCT (the untried branches first), and the
+  // rollout runs until it yields (w/out backtracking). The reward of the
+  // rollout, from `reward_fn` (e.g. w/ an oracle estimate), is backed up
+  // along its path. See `mcts_play_best` to follow the tree.
+  pub fn interp_mcts_<F: FnMut(&FastInterp, &Yield_) -> f64>(&mut self, iter_ct: u64, mut reward_fn: F) -> Result<MctsStats, InterpCheck> {
+    let root = (self.clkctr._get_clock(), self.trace.buf.len());
+    if self.mcts.root != Some(root) {
+      let explore = self.mcts.explore;
+      self.mcts = FastMctsTree_{root: Some(root), explore, ..FastMctsTree_::default()};
+    }
+    // NB: the tree is taken out, so that the rollout forks do not copy it.
+    let mut tree = take(&mut self.mcts);
+    let mut result = Ok(());
+    for _ in 0 .. iter_ct {
+      let mut sim = self.fork();
+      let mut path = Vec::new();
+      match sim._mcts_rollout(root.1, &mut path, |sim, path, te| {
+        let prefs = te.worder.as_ref().and_then(|w| sim.pv_cache._prefs(w.span, w.order.len()));
+        tree._uct_rank(path, te, prefs)
+      }) {
+        Err(e) => {
+          result = Err(e);
+          break;
+        }
+        Ok(yield_) => {
+          let reward = reward_fn(&sim, &yield_);
+          _debugln!(self, "DEBUG: FastInterp::interp_mcts_: iter={} path={:?} yield={:?} reward={}", tree.iter_ct, path, yield_, reward);
+          tree._backup(&path, reward);
+          tree.iter_ct += 1;
+        }
+      }
+    }
+    let stats = MctsStats{
+      iter_ct:  tree.iter_ct,
+      node_ct:  tree.nodes.len(),
+      best_reward: tree.best.as_ref().map(|&(r, _)| r),
+      best_path:   tree.best.as_ref().map(|(_, p)| p.clone()).unwrap_or_default(),
+    };
+    self.mcts = tree;
+    result.map(|_| stats)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Follows the most visited branches of the MCTS tree from the current
+  // state (and the first branch past the tree), and yields.
+  pub fn mcts_play_best(&mut self) -> Result<Yield_, InterpCheck> {
+    let tree = take(&mut self.mcts);
+    let base = self.trace.buf.len();
+    let mut path = Vec::new();
+    let result = self._mcts_rollout(base, &mut path, |_, path, te| {
+      tree._most_visited(path).unwrap_or(te.xctr)
+    });
+    self.mcts = tree;
+    result
+  }
+
+  // [Interp-API]
+  //
+  // Runs w/out backtracking, and at each choice point (past the trace
+  // position `base`) takes the rank from `choose`, given the path of the
+  // ranks taken so far; a choice point whose chosen rank differs from the
+  // rank that the run took is re-run via `select_branch`.
+  pub(crate) fn _mcts_rollout<C: FnMut(&FastInterp, &[RawChoiceRank], &TraceEntry_) -> RawChoiceRank>(&mut self, base: usize, path: &mut Vec<RawChoiceRank>, mut choose: C) -> Result<Yield_, InterpCheck> {
+    let mut stats = InterpRunStats_::default();
+    let mut yield_ = self._interp_bt_(&mut stats, false)?;
+    loop {
+      let p = base + path.len();
+      if p >= self.trace.buf.len() {
+        return Ok(yield_);
+      }
+      let te = &self.trace.buf[p];
+      let rank = choose(self, path, te);
+      if rank != te.xctr {
+        let clk = te.root_clk;
+        self.select_branch(clk, rank)?;
+        yield_ = self._interp_bt_(&mut stats, false)?;
+      }
+      path.push(rank);
+    }
+  }
+}
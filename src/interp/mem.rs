@@ -0,0 +1,213 @@
+use crate::interp::*;
+use crate::tap::{_debugln};
+
+use std::cmp::{max};
+use std::mem::{size_of, size_of_val};
+
+// What to do when the memory estimate exceeds the soft limit (see
+// `FastInterp::set_mem_soft_limit`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MemLimitAction {
+  // Run a gc pass (see `FastInterp::gc`).
+  Gc,
+  // Yield `Yield_::Break`, once per excursion above the soft limit.
+  Break,
+}
+
+// An approximate accounting (in bytes) of the memory held by each of the
+// major interpreter structures (see `FastInterp::memory_report`).
+//
+// NB: every entry is counted at the fixed size of its type, except that
+// the Term and Val table entries also count their boxed term (or val) at
+// the size of its concrete type, and the trace entries also count their
+// weighted orders; the other heap payloads of the entries (strings, etc.)
+// and the overhead of the maps are not counted.
+#[derive(Clone, Default, Debug)]
+pub struct MemoryReport {
+  // The bytes of each nonempty table, in sort order.
+  pub tables:   Vec<(SNumSort, usize)>,
+  pub unifier:  usize,
+  pub log:      usize,
+  pub redo:     usize,
+  pub trace:    usize,
+  pub pv_cache: usize,
+  pub total:    usize,
+  // The highest total that has been sampled (see `_mem_sample`), or else
+  // the current total, whichever is higher.
+  pub high_water: usize,
+  pub soft_limit: Option<usize>,
+}
+
+// [Interp-API]
+//
+// The running byte counts of the Term and Val tables, updated upon their
+// inserts and removals (see `put_term`, `put_val`, `undo`, `_redo`, and
+// `gc`). Along w/ the running counts of the log and the trace (see
+// `FastLog_::_bytes` and `FastTrace_::_bytes`), these are the structures
+// that grow w/ the run, and so a memory sample (see `_mem_sample`) does
+// not walk them.
+#[derive(Clone, Default, Debug)]
+pub struct MemCounters_ {
+  pub term: usize,
+  pub val:  usize,
+}
+
+impl MemCounters_ {
+  pub fn _sub(&mut self, sort: SNumSort, n: usize) {
+    match sort {
+      SNumSort::Term => self.term -= n,
+      SNumSort::Val => self.val -= n,
+      _ => {}
+    }
+  }
+}
+
+// The bytes of a table entry, counting its boxed term (or val) at the
+// size of its concrete type.
+pub fn _table_entry_bytes(e: &TableEntry_) -> usize {
+  size_of::<(SNum, TableEntry_)>() + size_of_val(&*e.inner)
+}
+
+// NB: a gc pass (w/ `MemLimitAction::Gc`) that leaves the memory estimate
+// above the soft limit is only rerun once the estimate has grown by
+// 1 / `MEM_GC_BACKOFF_DIV` of what it was after the pass.
+pub const MEM_GC_BACKOFF_DIV: usize = 4;
+
+impl MemoryReport {
+  pub fn table_total(&self) -> usize {
+    self.tables.iter().map(|&(_, n)| n).sum()
+  }
+}
+
+impl FastInterp {
+  // [Interp-API]
+  //
+  // Recount the running byte counts of the Term and Val tables (see
+  // `MemCounters_`) by walking the tables.
+  pub fn _recount_mem_ctr(&self) -> MemCounters_ {
+    let mut ctr = MemCounters_::default();
+    for (sort, ctr_n) in [(SNumSort::Term, &mut ctr.term), (SNumSort::Val, &mut ctr.val)] {
+      if let Some(sort_tab) = self.env.table_full.get(sort as usize) {
+        for e in sort_tab.values() {
+          *ctr_n += _table_entry_bytes(e);
+        }
+      }
+    }
+    ctr
+  }
+
+  // [Interp-API-Pub]
+  pub fn memory_report(&self) -> MemoryReport {
+    let mut report = MemoryReport::default();
+    for (sort_rank, sort_tab) in self.env.table_full.iter().enumerate() {
+      if sort_tab.is_empty() {
+        continue;
+      }
+      let sort = SNumSort::try_from(sort_rank as RawSNum).unwrap();
+      let n = match sort {
+        SNumSort::Term => self.mem_ctr.term,
+        SNumSort::Val => self.mem_ctr.val,
+        _ => sort_tab.len() * size_of::<(SNum, TableEntry_)>()
+      };
+      report.tables.push((sort, n));
+    }
+    let unifier = &self.env.unifier;
+    report.unifier += unifier.root.len() * size_of::<SNum>();
+    report.unifier += unifier.next.len() * size_of::<(SNum, SNum)>();
+    report.unifier += unifier.prev.len() * size_of::<(SNum, SNum)>();
+    report.unifier += unifier.tree.len() * size_of::<(SNum, (LClk, SNum))>();
+    report.unifier += unifier.cache.borrow().len() * size_of::<(SNum, (LClk, SNum))>();
+    report.log = self.log._bytes();
+    report.redo = self.redo._entry_ct() * size_of::<RedoLogEntry_>();
+    report.trace = self.trace._bytes();
+    for pv in self.pv_cache.tree.values() {
+      report.pv_cache += size_of::<LClk>() + pv.len() * size_of::<TraceEntry_>();
+    }
+    for prefs in self.pv_cache.prefs.values() {
+      report.pv_cache += size_of::<SpanNum>() + prefs.len() * size_of::<u32>();
+    }
+    report.total = report.table_total()
+                 + report.unifier
+                 + report.log
+                 + report.redo
+                 + report.trace
+                 + report.pv_cache;
+    report.high_water = max(self.mem_hwm, report.total);
+    report.soft_limit = self.mem_lim.map(|(lim, _)| lim);
+    report
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The highest memory estimate that has been sampled so far (see
+  // `memory_report`).
+  pub fn mem_high_water(&self) -> usize {
+    self.mem_hwm
+  }
+
+  // [Interp-API-Pub]
+  pub fn reset_mem_high_water(&mut self) {
+    self.mem_hwm = 0;
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Sets (or unsets) a soft limit (in bytes) on the memory estimate (see
+  // `memory_report`). The estimate is sampled periodically during `interp_`
+  // (every `KNT_DEPTH_INTERVAL` control transitions of `resume_`, see
+  // `_check_run_limits`); when it exceeds the limit, either a gc pass is
+  // run, or else `interp_` yields `Yield_::Break`, after which it may be
+  // resumed. In the latter case, the break is only yielded again after the
+  // estimate has dropped back under the limit.
+  //
+  // NB: this is the same limit as the gc threshold (see
+  // `set_gc_threshold`), which it replaces.
+  pub fn set_mem_soft_limit(&mut self, lim: Option<usize>, action: MemLimitAction) {
+    self.mem_lim = lim.map(|lim| (lim, action));
+    self.mem_brk = false;
+    self.mem_gc_floor = 0;
+  }
+
+  // [Interp-API]
+  //
+  // Samples the memory estimate, updating the high-water mark, and then
+  // applies the soft limit (if any).
+  pub(crate) fn _mem_sample(&mut self) -> Result<Option<Yield_>, InterpCheck> {
+    let report = self.memory_report();
+    self.mem_hwm = report.high_water;
+    let (lim, action) = match self.mem_lim {
+      None => return Ok(None),
+      Some(lim) => lim
+    };
+    if report.total <= lim {
+      self.mem_brk = false;
+      self.mem_gc_floor = 0;
+      return Ok(None);
+    }
+    _debugln!(self, "DEBUG: FastInterp::_mem_sample: clk={:?} soft limit: total={} lim={} action={:?}",
+        self.clkctr._get_clock(), report.total, lim, action);
+    match action {
+      MemLimitAction::Gc => {
+        // NB: back off from a gc pass that would reclaim (next to)
+        // nothing (see `MEM_GC_BACKOFF_DIV`).
+        if report.total <= self.mem_gc_floor {
+          return Ok(None);
+        }
+        let gc_report = self.gc()?;
+        let total = self.memory_report().total;
+        _debugln!(self, "DEBUG: FastInterp::_mem_sample: gc: report={:?} total={}", gc_report, total);
+        if total > lim {
+          self.mem_gc_floor = total + total / MEM_GC_BACKOFF_DIV;
+        }
+        Ok(None)
+      }
+      MemLimitAction::Break => {
+        if self.mem_brk {
+          return Ok(None);
+        }
+        self.mem_brk = true;
+        Ok(Some(Yield_::Break))
+      }
+    }
+  }
+}
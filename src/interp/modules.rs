@@ -0,0 +1,264 @@
+use crate::algo::{BTreeMap, BTreeSet};
+use crate::interp::*;
+use crate::interp::prelude::{ModuleVal};
+use crate::tap::{_debugln, _traceln};
+
+use std::io::{Write};
+use std::path::{PathBuf};
+
+// Orders the modules so that each module follows the modules that it
+// imports (`deps`, by index); ties are broken by index, so that the order
+// is otherwise the given order. Upon an import cycle, returns the modules
+// on the cycle, beginning and ending w/ the same module.
+pub fn _order_modules(deps: &[Vec<usize>]) -> Result<Vec<usize>, Vec<usize>> {
+  let n = deps.len();
+  let mut rdeps = vec![Vec::new(); n];
+  let mut indeg = vec![0; n];
+  for (i, mod_deps) in deps.iter().enumerate() {
+    indeg[i] = mod_deps.len();
+    for &j in mod_deps.iter() {
+      rdeps[j].push(i);
+    }
+  }
+  let mut ready: BTreeSet<usize> = (0 .. n).filter(|&i| indeg[i] == 0).collect();
+  let mut order = Vec::with_capacity(n);
+  while let Some(i) = ready.pop_first() {
+    order.push(i);
+    for &r in rdeps[i].iter() {
+      indeg[r] -= 1;
+      if indeg[r] == 0 {
+        ready.insert(r);
+      }
+    }
+  }
+  if order.len() == n {
+    return Ok(order);
+  }
+  // NB: every unordered module imports another unordered module, so the
+  // imports from any unordered module eventually lead around a cycle.
+  let mut path = Vec::new();
+  let mut i = (0 .. n).find(|&i| indeg[i] > 0).unwrap();
+  while !path.contains(&i) {
+    path.push(i);
+    i = deps[i].iter().copied().find(|&j| indeg[j] > 0).unwrap();
+  }
+  let start = path.iter().position(|&j| j == i).unwrap();
+  let mut cycle = path.split_off(start);
+  cycle.push(i);
+  Err(cycle)
+}
+
+// The recognized pragmas of a module (see `FastInterp::_apply_pragmas`),
+// by key; `occurs_check` is keyed as `unify = occurs_check`.
+fn _module_pragmas(pragmas: &[RawPragma_]) -> BTreeMap<&str, Option<&str>> {
+  let mut prags = BTreeMap::new();
+  for pragma in pragmas.iter() {
+    let key = pragma.key.as_raw_str();
+    let val = pragma.val.as_ref().map(|val| val.as_raw_str());
+    match (key, val) {
+      ("occurs_check", None) => {
+        prags.insert("unify", Some("occurs_check"));
+      }
+      ("strict", _) |
+      ("occurs_check", _) |
+      ("unify", _) |
+      ("match_commit", _) |
+      ("step_limit", _) |
+      ("trace", _) => {
+        prags.insert(key, val);
+      }
+      _ => {}
+    }
+  }
+  prags
+}
+
+// The pragmas that change how the code of a module runs, which every
+// module of `load_many` must agree on (incl. by omission).
+const _RUN_PRAGMAS: &'static [&'static str] = &["strict", "unify", "match_commit"];
+
+// Checks that the pragmas of the modules `mods` (by name) do not conflict:
+// since the pragmas configure the whole interpreter, rather than only the
+// module in which they occur, the modules must agree on each of
+// `_RUN_PRAGMAS`, and must not set any other pragma to different values.
+pub fn _check_module_pragmas(mods: &[(&str, &[RawPragma_])]) -> Result<(), String> {
+  let prags: Vec<_> = mods.iter().map(|&(_, pragmas)| _module_pragmas(pragmas)).collect();
+  for i in 1 .. mods.len() {
+    for j in 0 .. i {
+      let keys: BTreeSet<&str> = prags[i].keys().chain(prags[j].keys()).copied().collect();
+      for &key in keys.iter() {
+        let conflict = match (prags[i].get(key), prags[j].get(key)) {
+          (Some(lval), Some(rval)) => lval != rval,
+          (None, None) => false,
+          _ => _RUN_PRAGMAS.contains(&key)
+        };
+        if conflict {
+          return Err(format!("load_many: modules {:?} and {:?} have conflicting pragma {:?}",
+              mods[j].0, mods[i].0, key));
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // This loads the modules `mods`, each given by its name and source, into
+  // the interpreter. The modules are parsed, and then ordered so that each
+  // module follows the modules named by its top-level `import` stms; an
+  // import cycle is an error, which lists the modules on the cycle. In that
+  // order, each module body then runs in its own module scope, after which
+  // the module is bound by its name (see `prelude::ModuleVal`).
+  //
+  // As w/ `load_project`, each span is attributed by `srcmap` to the
+  // module in which it occurs, w/ the module name as the origin path.
+  //
+  // NB: the pragmas of every module configure the whole interpreter, so
+  // conflicting pragmas are an error (see `_check_module_pragmas`).
+  pub fn load_many(&mut self, mods: &[(&str, &str)]) -> Result<(), InterpCheck> {
+    _debugln!(self, "DEBUG: FastInterp::load_many: mods.len={}", mods.len());
+    if let Some(snapshot) = self.snapshot.borrow_mut().as_mut() {
+      writeln!(snapshot, "{{\"_event\": \"cold-start\"}}").unwrap();
+    }
+    let mut index = BTreeMap::new();
+    for (i, &(name, _)) in mods.iter().enumerate() {
+      if index.insert(name, i).is_some() {
+        return Err(format!("load_many: duplicate module {:?}", name).into());
+      }
+    }
+    let mut srcmap = SourceMap::default();
+    let mut src = String::new();
+    let mut raw_mods = Vec::with_capacity(mods.len());
+    let mut deps = Vec::with_capacity(mods.len());
+    for &(name, text) in mods.iter() {
+      let (_, base) = srcmap.add_file(Some(PathBuf::from(name)), text);
+      while src.len() < base {
+        src.push('\n');
+      }
+      src.push_str(text);
+      let y = self._parse_text(text, base).map_err(|e| match srcmap.span_loc(&e.span) {
+        None => format!("parse error: {:?}", e),
+        Some(loc) => format!("parse error: {}: {:?}", loc, e)
+      })?;
+      let mut mod_deps = Vec::new();
+      for raw_stm in y.body.iter() {
+        let raw_ids = match &**raw_stm {
+          &RawStm_::Import(_, ref raw_ids) => raw_ids,
+          _ => continue
+        };
+        for raw_id in raw_ids.iter() {
+          let j = match index.get(raw_id.as_raw_str()) {
+            None => {
+              return Err(format!("load_many: module {:?} imports unknown module {:?}", name, raw_id.as_raw_str()).into());
+            }
+            Some(&j) => j
+          };
+          if !mod_deps.contains(&j) {
+            mod_deps.push(j);
+          }
+        }
+      }
+      _debugln!(self, "DEBUG: FastInterp::load_many: name={:?} deps={:?}", name, mod_deps);
+      deps.push(mod_deps);
+      raw_mods.push((base .. base + text.len(), y));
+    }
+    let order = _order_modules(&deps).map_err(|cycle| {
+      let names: Vec<_> = cycle.iter().map(|&i| mods[i].0).collect();
+      format!("load_many: import cycle: {}", names.join(" -> "))
+    })?;
+    _debugln!(self, "DEBUG: FastInterp::load_many: order={:?}", order);
+    let mod_pragmas: Vec<_> = order.iter().map(|&i| (mods[i].0, &raw_mods[i].1.pragmas[ .. ])).collect();
+    _check_module_pragmas(&mod_pragmas)?;
+    for &i in order.iter() {
+      self._apply_pragmas(&raw_mods[i].1.pragmas)?;
+    }
+    let clk = self.clkctr._get_clock();
+    let x = self._fresh()?.into_mod_code();
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawMod(x).into()));
+    let span = self._load_raw_span(&(0 .. src.len()))?;
+    let mut stmp: CellNum = nil();
+    let mut cur_stmp: CellNum = nil();
+    for &i in order.iter() {
+      let (ref raw_span, ref raw_mod) = raw_mods[i];
+      let stm = self._load_raw_module(raw_span, mods[i].0, &raw_mod.body)?;
+      let next_stmp = self._alloc_cell(stm.into())?;
+      self._link_cells(cur_stmp, next_stmp)?;
+      if stmp.is_nil() {
+        stmp = next_stmp;
+      }
+      cur_stmp = next_stmp;
+    }
+    let code = ModCode_{span, stmp: stmp.try_into_stm_code().map_err(|e| e.into_check())?};
+    _traceln!(self, "DEBUG: FastInterp::load_many: x={:?} code={:?}", x, code);
+    let e = TableEntry_{
+      lastclk: clk,
+      inner: Box::new(code),
+    };
+    self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+    self._enter_mod(x, src, srcmap);
+    self.load = LoadRecipe_::Many(mods.iter().map(|&(name, text)| (name.to_owned(), text.to_owned())).collect());
+    _debugln!(self, "DEBUG: FastInterp::load_many: done");
+    Ok(())
+  }
+
+  // [Interp-API]
+  //
+  // Loads the body of the module `name` as one stm (see `StmCode_::Module`).
+  // As w/ a class body, the module body has its own frame.
+  pub(crate) fn _load_raw_module(&mut self, raw_span: &RawSpan_, name: &str, raw_body: &[Box<RawStm_>]) -> Result<StmCodeNum, InterpCheck> {
+    let clk = self.clkctr._get_clock();
+    let x = self._fresh()?.try_into_stm_code().map_err(|e| e.into_check())?;
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::LoadRawStm(x).into()));
+    let id = self._load_raw_ident(&name.into())?;
+    let level = DebruijnLevel::root().push();
+    let frame = self._fresh()?.into_frame();
+    let frame_ = Frame_{level, ids: Default::default()};
+    let e = TableEntry_{
+      lastclk: clk,
+      inner: Box::new(frame_),
+    };
+    self.env.table_full[SNumSort::Frame as usize].insert(frame.into(), e);
+    let span = self._load_raw_span(raw_span)?;
+    let mut body: CellNum = nil();
+    let mut cur_body: CellNum = nil();
+    for raw_body_stm in raw_body.iter() {
+      let stm = self._load_raw_stm(level, frame, raw_body_stm)?;
+      let next_body = self._alloc_cell(stm.into())?;
+      self._link_cells(cur_body, next_body)?;
+      cur_body = next_body;
+      if body.is_nil() {
+        body = next_body;
+      }
+    }
+    let body_stmp = body.try_into_stm_code().map_err(|e| e.into_check())?;
+    let code = StmCode_::Module{span, id, body_stmp};
+    _traceln!(self, "DEBUG: FastInterp::_load_raw_module: x={:?} frame={:?} code={:?}", x, frame, code);
+    let e = TableEntry_{
+      lastclk: clk,
+      inner: Box::new(code),
+    };
+    self.env.table_full[SNumSort::Code as usize].insert(x.into(), e);
+    self.env.frame_codex.insert(x.into(), frame);
+    Ok(x)
+  }
+
+  // [Interp-API]
+  //
+  // The loaded module (if any) named by the ident `id`.
+  pub(crate) fn _find_module(&self, clk: LClk, id: IdentNum) -> Result<Option<ObjValNum>, InterpCheck> {
+    let y = match self.env.id_global_bind.get(&id) {
+      None => return Ok(None),
+      Some(&y) => y
+    };
+    let obj = match self._find_obj_val(clk, y)? {
+      None => return Ok(None),
+      Some(obj) => obj
+    };
+    match self.env.obj_val_full.get(&obj.into()) {
+      Some(&TransparentBox::Ptr(ref val)) if val.as_any().is::<ModuleVal>() => Ok(Some(obj)),
+      _ => Ok(None)
+    }
+  }
+}
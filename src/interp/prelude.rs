@@ -1,11 +1,14 @@
+use crate::algo::str::{StrParserConfig};
 use crate::algo::token::*;
 use crate::interp::*;
+use crate::parse::{Token, Tokenizer};
 //use crate::interp::services::*;
 use crate::tap::{_debugln, _traceln};
 
 use std::any::{Any};
+use std::cmp::{Ordering};
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct ChoiceFun {
   // NB: this is just a "function"!
 }
@@ -15,6 +18,10 @@ impl Function for ChoiceFun {
     self
   }
 
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
   fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
     let clk = interp.clkctr._get_clock();
     let nextclk = interp.clkctr._next_clock();
@@ -27,11 +34,6 @@ impl Function for ChoiceFun {
     interp.reg.rst_clk = nil();
     _traceln!(interp, "DEBUG: ChoiceFun::__apply__: clk={:?} nextclk={:?} xlb={:?} tup.len={}", clk, nextclk, xlb, tup.len());
 
-    if tup.len() > 2 {
-      _traceln!(interp, "DEBUG: ChoiceFun::__apply__:   tup={:?}", tup);
-      return Err(bot());
-    }
-
     let mut choice_ub: Option<RawChoiceRank> = None;
     if tup.len() > 1 {
       _traceln!(interp, "DEBUG: ChoiceFun::__apply__:   tup={:?}", tup);
@@ -116,17 +118,20 @@ impl Function for ChoiceFun {
 
     // NB: the choice point counter should be exposed as a val.
     let choice_ctr = te.xctr;
+    let choice_val = te._choice_val(interp.trace.heuristic);
+    let (te_clk, te_lim) = (te.root_clk, te.xlim);
     if choice_ub.is_none() || choice_ctr < choice_ub.unwrap() {
       _traceln!(interp, "DEBUG: ChoiceFun::__apply__: choice ctr={} ub={:?}", choice_ctr, choice_ub);
+      interp._hook_choice(te_clk, choice_ctr, te_lim);
       // FIXME(20250119): strictly, should lookup a lit val index.
-      let val_ = LitVal_::Int(choice_ctr.into());
+      let val_ = LitVal_::Int(choice_val.into());
       let y = match interp.env.lit_val_bind.get(&val_) {
         Some(&y) => {
           _traceln!(interp, "DEBUG: ChoiceFun::__apply__:   found lit term: y={:?} val={:?}", y, val_);
           y
         }
         None => {
-          let y = interp._fresh();
+          let y = interp._fresh()?;
           _traceln!(interp, "DEBUG: ChoiceFun::__apply__:   fresh lit val: y={:?} val={:?}", y, val_);
           interp.put_val(clk, y, val_.clone())?;
           let prev_y = interp.env.lit_val_bind.insert(val_.clone(), y.into());
@@ -143,7 +148,123 @@ impl Function for ChoiceFun {
   }
 }
 
-#[derive(Debug, Default)]
+// The branch order of a weighted choice point (see `WeightOrder`).
+fn _weighted_order(interp: &mut FastInterp, weights: &[f64]) -> Vec<RawChoiceRank> {
+  let mut keyed: Vec<(f64, RawChoiceRank)> = Vec::with_capacity(weights.len());
+  for (i, &w) in weights.iter().enumerate() {
+    let key = match interp.trace.weight_order {
+      WeightOrder::Greedy => w,
+      WeightOrder::Sampled => {
+        // NB: Efraimidis-Spirakis keys, `ln(u) / w` for `u` in (0, 1];
+        // sorting by descending key samples w/out replacement.
+        let u = ((interp.rng._next_u64() >> 11) + 1) as f64 / (1_u64 << 53) as f64;
+        if w > 0.0 { u.ln() / w } else { f64::NEG_INFINITY }
+      }
+    };
+    keyed.push((key, i as RawChoiceRank));
+  }
+  keyed.sort_by(|&(lk, li), &(rk, ri)| rk.partial_cmp(&lk).unwrap().then(li.cmp(&ri)));
+  keyed.into_iter().map(|(_, i)| i).collect()
+}
+
+// A weighted choice point: `wchoice(w_0, ..., w_(n-1))` returns a branch
+// in `0 .. n`, like `choice(n)`, but explores the branches in the order
+// of their (nonnegative int) weights, scaled by the learned preferences
+// of the PV cache (see `FastInterp::choice_prefs`).
+#[derive(Clone, Debug, Default)]
+pub struct WChoiceFun {
+  // NB: this is just a "function"!
+}
+
+impl Function for WChoiceFun {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
+  fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    let xlb = interp.reg.xlb;
+    let rst_clk = interp.reg.rst_clk;
+    // NB: need to nil out reg.rst_clk.
+    interp.reg.rst_clk = nil();
+    _traceln!(interp, "DEBUG: WChoiceFun::__apply__: clk={:?} xlb={:?} tup.len={}", clk, xlb, tup.len());
+
+    if tup.len() < 2 {
+      _traceln!(interp, "DEBUG: WChoiceFun::__apply__: no branches fail");
+      return Ok(Some(Yield_::Fail));
+    }
+    if tup.len() > RawChoiceRank::max_value() as usize {
+      _traceln!(interp, "DEBUG: WChoiceFun::__apply__:   tup={:?}", tup);
+      return Err(bot());
+    }
+    let choice_ub = (tup.len() - 1) as RawChoiceRank;
+
+    let te_clk = match interp.trace._maybe_get(rst_clk) {
+      Some(te) if !rst_clk.is_nil() => {
+        _traceln!(interp, "DEBUG: WChoiceFun::__apply__: trace: get: rst clk={:?} clk={:?}", rst_clk, clk);
+        te.last_clk.set(clk);
+        rst_clk
+      }
+      Some(_) => {
+        _traceln!(interp, "DEBUG: WChoiceFun::__apply__: trace: get: rst clk={:?} clk={:?}", rst_clk, clk);
+        return Err(bot());
+      }
+      None => {
+        let mut weights = Vec::with_capacity(tup.len() - 1);
+        for &arg in tup[1 .. ].iter() {
+          match _find_int_val(interp, clk, arg)? {
+            Some(w) if w >= 0 => weights.push(w as f64),
+            _ => {
+              interp.exc_ = "wchoice: expected nonnegative int weights".into();
+              return Ok(None);
+            }
+          }
+        }
+        if let Some(prefs) = interp.pv_cache._prefs(this_span, weights.len()) {
+          for (w, &p) in weights.iter_mut().zip(prefs.iter()) {
+            *w *= 1.0 + p as f64;
+          }
+        }
+        let order = _weighted_order(interp, &weights);
+        _traceln!(interp, "DEBUG: WChoiceFun::__apply__:   weights={:?} order={:?}", weights, order);
+        let ctl_reg = FastCtlReg_{
+          exc_: interp.exc_.clone(),
+          res_: interp.res_.clone(),
+          port: interp.port.clone(),
+        };
+        let knt_ = MemKnt{
+          clk:  knt.clk,
+          prev: knt.prev.clone(),
+          cur:  knt.cur,
+        }.into_ref();
+        _traceln!(interp, "DEBUG: WChoiceFun::__apply__: trace: push: clk={:?}", clk);
+        interp.trace._push(clk, choice_ub, xlb, interp.reg, ctl_reg, knt_)?;
+        interp.trace._set_order(clk, this_span, order)?;
+        clk
+      }
+    };
+    let te = interp.trace._maybe_get(te_clk).unwrap();
+    let choice_ctr = te.xctr;
+    let choice_val = te._choice_val(interp.trace.heuristic);
+    let te_root_clk = te.root_clk;
+    if choice_ctr < choice_ub {
+      _traceln!(interp, "DEBUG: WChoiceFun::__apply__: choice ctr={} val={} ub={}", choice_ctr, choice_val, choice_ub);
+      interp._hook_choice(te_root_clk, choice_ctr, choice_ub);
+      let y = interp._load_lit_val(clk, LitVal_::Int(choice_val.into()))?;
+      interp.unify(clk, y, ret)?;
+      Ok(None)
+    } else {
+      _traceln!(interp, "DEBUG: WChoiceFun::__apply__: choice ctr={} ub={} fail", choice_ctr, choice_ub);
+      Ok(Some(Yield_::Fail))
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct FailureFun {
   // NB: this is just a "function"!
 }
@@ -153,12 +274,16 @@ impl Function for FailureFun {
     self
   }
 
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
   fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
     Ok(Some(Yield_::Fail))
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct EvalFun {
 }
 
@@ -167,28 +292,41 @@ impl Function for EvalFun {
     self
   }
 
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
   fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
     let clk = interp.clkctr._get_clock();
     let nextclk = interp.clkctr._next_clock();
     let xlb = interp.reg.xlb;
     _traceln!(interp, "DEBUG: EvalFun::__apply__: clk={:?} nextclk={:?} xlb={:?} tup.len={}", clk, nextclk, xlb, tup.len());
 
-    if tup.len() != 1 {
-      _traceln!(interp, "DEBUG: EvalFun::__apply__:   tup={:?}", tup);
-      return Err(bot());
+    // NB: `tup[0]` is the function head; the arity is checked at the call
+    // site (see `registry::BuiltinInfo`).
+    _traceln!(interp, "DEBUG: EvalFun::__apply__:   tup={:?}", tup);
+    if let Some(quote) = interp._find_quote(clk, tup[1]._inst())? {
+      // NB: a reified quote is unquoted in place.
+      _traceln!(interp, "DEBUG: EvalFun::__apply__:   unquote={:?}", quote);
+      interp._push_unquote(clk, &quote, ret, &knt)?;
+      return Ok(None);
     }
-
-    {
-      _traceln!(interp, "DEBUG: EvalFun::__apply__:   tup={:?}", tup);
-      // TODO
-      return Err(unimpl());
+    if let Some(code) = interp._find_code(clk, tup[1]._inst())? {
+      // NB: a stm or term code is evaluated in place, in the current
+      // env; if it fails, then backtracking undoes it like any other
+      // code (the trace entries of its choice points hold this knt).
+      _traceln!(interp, "DEBUG: EvalFun::__apply__:   code={:?}", code);
+      interp._push_eval_code(clk, code, ret, &knt)?;
+      return Ok(None);
     }
-
+    // NB: the result is provided later, by the caller of `interp_`
+    // (see `FastInterp::provide_eval_result`).
+    interp._push_eval(clk, tup[1]._inst(), ret, &knt)?;
     Ok(Some(Yield_::Eval))
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct InputFun {
 }
 
@@ -197,22 +335,16 @@ impl Function for InputFun {
     self
   }
 
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
   fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
     let clk = interp.clkctr._get_clock();
     let nextclk = interp.clkctr._next_clock();
     let xlb = interp.reg.xlb;
     _traceln!(interp, "DEBUG: InputFun::__apply__: clk={:?} nextclk={:?} xlb={:?} tup.len={}", clk, nextclk, xlb, tup.len());
 
-    if tup.len() > 1 {
-      _traceln!(interp, "DEBUG: InputFun::__apply__:   trailing tup={:?}", tup);
-      return Err(bot());
-    }
-
-    if tup.len() < 1 {
-      _traceln!(interp, "DEBUG: InputFun::__apply__:   missing tup={:?}", tup);
-      return Err(bot());
-    }
-
     {
       _traceln!(interp, "DEBUG: InputFun::__apply__:   tup={:?}", tup);
       // TODO
@@ -222,7 +354,7 @@ impl Function for InputFun {
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct PrintFun {
 }
 
@@ -231,17 +363,16 @@ impl Function for PrintFun {
     self
   }
 
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
   fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
     let clk = interp.clkctr._get_clock();
     let nextclk = interp.clkctr._next_clock();
     let xlb = interp.reg.xlb;
     _traceln!(interp, "DEBUG: PrintFun::__apply__: clk={:?} nextclk={:?} xlb={:?} tup.len={}", clk, nextclk, xlb, tup.len());
 
-    if tup.len() > 2 {
-      _traceln!(interp, "DEBUG: PrintFun::__apply__:   trailing tup={:?}", tup);
-      return Err(bot());
-    }
-
     if tup.len() == 2 {
       _traceln!(interp, "DEBUG: PrintFun::__apply__:   tup={:?}", tup);
       let vals = interp.get_vals(clk, tup[1])?;
@@ -274,7 +405,487 @@ impl Function for PrintFun {
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
+pub struct TokenizeFun {
+}
+
+impl Function for TokenizeFun {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
+  fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: TokenizeFun::__apply__: clk={:?} tup.len={}", clk, tup.len());
+
+    let vals = interp.get_vals(clk, tup[1])?;
+    let mut src = None;
+    for &(_, ref val) in vals.iter() {
+      match val {
+        &Val_::Lit(LitVal_::Atom(ref v)) => {
+          // NB: an atom val is the raw (quoted) lit of the str.
+          src = TokenTrieVal::_unquote_key(v);
+          // TODO: when to catch contradictory vals?
+          break;
+        }
+        _ => {}
+      }
+    }
+    let src = match src {
+      None => {
+        interp.exc_ = "tokenize: expected a str".into();
+        return Ok(None);
+      }
+      Some(src) => src
+    };
+
+    // NB: each token is a list `[kind, start, end]`, where the kind is
+    // the atom of the token variant name (e.g. "Ident", "LParen"), and
+    // the span is in bytes of the unquoted str.
+    let mut buf = Vec::new();
+    let tokenizer = Tokenizer::new(src.as_raw_str());
+    for stok in tokenizer {
+      if let Token::_Eof = stok.tok {
+        break;
+      }
+      let kind = format!("{:?}", stok.tok);
+      let kind = match kind.find('(') {
+        None => kind,
+        Some(p) => kind[ .. p].to_string()
+      };
+      let kind_y = interp._load_lit_val(clk, LitVal_::Atom(TokenTrieVal::_quote_key(&kind).into()))?;
+      let start_y = interp._load_lit_val(clk, LitVal_::Int(stok.span.start as i64))?;
+      let end_y = interp._load_lit_val(clk, LitVal_::Int(stok.span.end as i64))?;
+      let y = interp._fresh()?;
+      interp.put_val(clk, y, LitVal_::List{buf: vec![kind_y, start_y, end_y]})?;
+      buf.push(y.into());
+    }
+    _traceln!(interp, "DEBUG: TokenizeFun::__apply__:   toks.len={}", buf.len());
+    let y = interp._fresh()?;
+    interp.put_val(clk, y, LitVal_::List{buf})?;
+    interp.unify(clk, y, ret)?;
+    Ok(None)
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct QueryFun {
+}
+
+impl Function for QueryFun {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
+  fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: QueryFun::__apply__: clk={:?} tup.len={}", clk, tup.len());
+
+    // NB: the pattern is the args, w/ unbound args as wildcards; e.g.
+    // `query(edge, a, _)` finds the tabled tuples `edge(a, _)`.
+    let mut pattern = Vec::with_capacity(tup.len() - 1);
+    for &arg in tup[1 .. ].iter() {
+      if interp._is_unbound(clk, arg.inst)? {
+        pattern.push(None);
+      } else {
+        pattern.push(Some(arg.inst));
+      }
+    }
+    let buf = interp.match_tuples(clk, &pattern)?;
+    _traceln!(interp, "DEBUG: QueryFun::__apply__:   mats.len={}", buf.len());
+    let y = interp._fresh()?;
+    interp.put_val(clk, y, LitVal_::List{buf})?;
+    interp.unify(clk, y, ret)?;
+    Ok(None)
+  }
+}
+
+// Finds the items of the list value (if any) of `query`, either a list
+// literal or a `ListVal` object.
+pub fn _find_list_items(interp: &FastInterp, clk: LClk, query: ENum) -> Result<Option<Vec<SNum>>, InterpCheck> {
+  let vals = interp.get_vals(clk, query)?;
+  for &(_, ref val) in vals.iter() {
+    match val {
+      &Val_::Lit(LitVal_::List{ref buf}) => {
+        return Ok(Some(buf.clone()));
+      }
+      _ => {}
+    }
+  }
+  if let Some(obj) = interp._find_obj_val(clk, query.inst)? {
+    match interp.env.obj_val_full.get(&obj.into()) {
+      Some(&TransparentBox::Ptr(ref obj)) => {
+        if let Some(obj) = obj.as_any().downcast_ref::<ListVal>() {
+          return Ok(Some(obj._items().iter().map(|x| x.inst).collect()));
+        }
+      }
+      _ => {}
+    }
+  }
+  Ok(None)
+}
+
+// Finds the member items (if any) of `query` (see `TermCode_::In`): the
+// items of a list value, or the keys (as atom vals) of a `TokenTrieVal`
+// object.
+pub fn _find_member_items(interp: &mut FastInterp, clk: LClk, query: ENum) -> Result<Option<Vec<SNum>>, InterpCheck> {
+  if let Some(items) = _find_list_items(interp, clk, query)? {
+    return Ok(Some(items));
+  }
+  let keys = match interp._find_obj_val(clk, query.inst)? {
+    None => return Ok(None),
+    Some(obj) => match interp.env.obj_val_full.get(&obj.into()) {
+      Some(&TransparentBox::Ptr(ref obj)) => match obj.as_any().downcast_ref::<TokenTrieVal>() {
+        None => return Ok(None),
+        Some(obj) => obj._items()
+      },
+      _ => return Ok(None)
+    }
+  };
+  let mut buf = Vec::with_capacity(keys.len());
+  for (key, _) in keys.into_iter() {
+    buf.push(interp._load_lit_val(clk, LitVal_::Atom(key.into()))?);
+  }
+  Ok(Some(buf))
+}
+
+pub fn _find_int_val(interp: &FastInterp, clk: LClk, query: ENum) -> Result<Option<i64>, InterpCheck> {
+  let vals = interp.get_vals(clk, query)?;
+  for &(_, ref val) in vals.iter() {
+    match val {
+      &Val_::Lit(LitVal_::Int(v)) => {
+        // TODO: when to catch contradictory vals?
+        return Ok(Some(v));
+      }
+      _ => {}
+    }
+  }
+  Ok(None)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RandomChoiceFun {
+  // NB: this is just a "function"!
+}
+
+impl Function for RandomChoiceFun {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
+  fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: RandomChoiceFun::__apply__: clk={:?} tup.len={}", clk, tup.len());
+
+    let items = match _find_list_items(interp, clk, tup[1])? {
+      None => {
+        interp.exc_ = "random_choice: expected a list".into();
+        return Ok(None);
+      }
+      Some(items) => items
+    };
+    // NB: there is nothing to choose from an empty list.
+    if items.is_empty() {
+      _traceln!(interp, "DEBUG: RandomChoiceFun::__apply__: empty list fail");
+      return Ok(Some(Yield_::Fail));
+    }
+    let idx = interp.rng._below(items.len() as u64) as usize;
+    _traceln!(interp, "DEBUG: RandomChoiceFun::__apply__:   idx={} len={}", idx, items.len());
+    interp.unify(clk, items[idx], ret)?;
+    Ok(None)
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RandomIntFun {
+  // NB: this is just a "function"!
+}
+
+impl Function for RandomIntFun {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
+  fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: RandomIntFun::__apply__: clk={:?} tup.len={}", clk, tup.len());
+
+    let (lo, hi) = match (_find_int_val(interp, clk, tup[1])?, _find_int_val(interp, clk, tup[2])?) {
+      (Some(lo), Some(hi)) => (lo, hi),
+      _ => {
+        interp.exc_ = "random_int: expected two ints".into();
+        return Ok(None);
+      }
+    };
+    // NB: as in Python `random.randint`, both bounds are inclusive.
+    let v = match interp.rng._range_incl(lo, hi) {
+      None => {
+        _traceln!(interp, "DEBUG: RandomIntFun::__apply__: empty range fail: lo={} hi={}", lo, hi);
+        return Ok(Some(Yield_::Fail));
+      }
+      Some(v) => v
+    };
+    _traceln!(interp, "DEBUG: RandomIntFun::__apply__:   v={}", v);
+    let y = interp._load_lit_val(clk, LitVal_::Int(v))?;
+    interp.unify(clk, y, ret)?;
+    Ok(None)
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ArithOp_ {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Mod,
+  Abs,
+  Min,
+  Max,
+}
+
+impl ArithOp_ {
+  pub fn all() -> &'static [ArithOp_] {
+    &[
+      ArithOp_::Add,
+      ArithOp_::Sub,
+      ArithOp_::Mul,
+      ArithOp_::Div,
+      ArithOp_::Mod,
+      ArithOp_::Abs,
+      ArithOp_::Min,
+      ArithOp_::Max,
+    ]
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      ArithOp_::Add => "add",
+      ArithOp_::Sub => "sub",
+      ArithOp_::Mul => "mul",
+      ArithOp_::Div => "div",
+      ArithOp_::Mod => "mod",
+      ArithOp_::Abs => "abs",
+      ArithOp_::Min => "min",
+      ArithOp_::Max => "max",
+    }
+  }
+
+  // NB: `None` for a variadic op (i.e. `min` and `max`, of at least one
+  // arg).
+  pub fn arity(self) -> Option<usize> {
+    match self {
+      ArithOp_::Abs => Some(1),
+      ArithOp_::Min |
+      ArithOp_::Max => None,
+      _ => Some(2)
+    }
+  }
+}
+
+// NB: as in Python, `div` rounds toward negative infinity, and the
+// result of `mod` has the sign of the divisor.
+fn _floor_div(a: i64, b: i64) -> Option<i64> {
+  let q = a.checked_div(b)?;
+  if a % b != 0 && ((a < 0) != (b < 0)) {
+    q.checked_sub(1)
+  } else {
+    Some(q)
+  }
+}
+
+fn _floor_mod(a: i64, b: i64) -> Option<i64> {
+  if b == -1 {
+    return Some(0);
+  }
+  let r = a.checked_rem(b)?;
+  if r != 0 && ((r < 0) != (b < 0)) {
+    r.checked_add(b)
+  } else {
+    Some(r)
+  }
+}
+
+// The checked int arithmetic builtins (see `ArithOp_`). An overflow,
+// like a division by zero, raises an exception rather than panicking.
+#[derive(Clone, Debug)]
+pub struct ArithFun {
+  pub op:   ArithOp_,
+}
+
+impl Function for ArithFun {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
+  fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    let name = self.op.name();
+    _traceln!(interp, "DEBUG: ArithFun::__apply__: clk={:?} op={:?} tup.len={}", clk, self.op, tup.len());
+
+    let nargs = tup.len() - 1;
+    let mut args = Vec::with_capacity(nargs);
+    for &x in tup[1 .. ].iter() {
+      match _find_int_val(interp, clk, x)? {
+        None => {
+          interp.exc_ = format!("{}: expected ints", name).into();
+          return Ok(None);
+        }
+        Some(v) => args.push(v)
+      }
+    }
+    if (self.op == ArithOp_::Div || self.op == ArithOp_::Mod) && args[1] == 0 {
+      interp.exc_ = format!("{}: division by zero", name).into();
+      return Ok(None);
+    }
+    let v = match self.op {
+      ArithOp_::Add => args[0].checked_add(args[1]),
+      ArithOp_::Sub => args[0].checked_sub(args[1]),
+      ArithOp_::Mul => args[0].checked_mul(args[1]),
+      ArithOp_::Div => _floor_div(args[0], args[1]),
+      ArithOp_::Mod => _floor_mod(args[0], args[1]),
+      ArithOp_::Abs => args[0].checked_abs(),
+      ArithOp_::Min => args.iter().copied().min(),
+      ArithOp_::Max => args.iter().copied().max(),
+    };
+    let v = match v {
+      None => {
+        interp.exc_ = format!("{}: integer overflow", name).into();
+        return Ok(None);
+      }
+      Some(v) => v
+    };
+    _traceln!(interp, "DEBUG: ArithFun::__apply__:   args={:?} v={}", args, v);
+    let y = interp._load_int_lit(clk, v)?;
+    interp.unify(clk, y, ret)?;
+    Ok(None)
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CmpOp_ {
+  Lt,
+  Le,
+  Gt,
+  Ge,
+}
+
+impl CmpOp_ {
+  pub fn all() -> &'static [CmpOp_] {
+    &[CmpOp_::Lt, CmpOp_::Le, CmpOp_::Gt, CmpOp_::Ge]
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      CmpOp_::Lt => "lt",
+      CmpOp_::Le => "le",
+      CmpOp_::Gt => "gt",
+      CmpOp_::Ge => "ge",
+    }
+  }
+
+  // NB: the ident of the lowered comparison operator (see
+  // `parse::ParserMode`).
+  pub fn py_name(self) -> &'static str {
+    match self {
+      CmpOp_::Lt => "__py_lt__",
+      CmpOp_::Le => "__py_le__",
+      CmpOp_::Gt => "__py_gt__",
+      CmpOp_::Ge => "__py_ge__",
+    }
+  }
+
+  pub fn test(self, ord: Ordering) -> bool {
+    match self {
+      CmpOp_::Lt => ord == Ordering::Less,
+      CmpOp_::Le => ord != Ordering::Greater,
+      CmpOp_::Gt => ord == Ordering::Greater,
+      CmpOp_::Ge => ord != Ordering::Less,
+    }
+  }
+}
+
+// The ordered lit val (i.e. an int or an atom) of `query`, if any.
+fn _find_ord_val(interp: &FastInterp, clk: LClk, query: ENum) -> Result<Option<LitVal_>, InterpCheck> {
+  let vals = interp.get_vals(clk, query)?;
+  for (_, val) in vals.into_iter() {
+    match val {
+      Val_::Lit(v @ LitVal_::Int(_)) |
+      Val_::Lit(v @ LitVal_::Atom(_)) => {
+        return Ok(Some(v));
+      }
+      _ => {}
+    }
+  }
+  Ok(None)
+}
+
+// The comparison builtins (see `CmpOp_`), over two ints or two atoms. In
+// match context (e.g. an `if` condition), the comparison is a match
+// result; otherwise, it is a bool.
+#[derive(Clone, Debug)]
+pub struct CmpFun {
+  pub op:   CmpOp_,
+}
+
+impl Function for CmpFun {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn Function> {
+    Box::new(self.clone())
+  }
+
+  fn __apply__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<Option<Yield_>, InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    let name = self.op.name();
+    _traceln!(interp, "DEBUG: CmpFun::__apply__: clk={:?} op={:?} tup.len={}", clk, self.op, tup.len());
+
+    let ord = match (_find_ord_val(interp, clk, tup[1])?, _find_ord_val(interp, clk, tup[2])?) {
+      (Some(LitVal_::Int(a)), Some(LitVal_::Int(b))) => a.cmp(&b),
+      (Some(LitVal_::Atom(a)), Some(LitVal_::Atom(b))) => a.as_raw_str().cmp(b.as_raw_str()),
+      _ => {
+        interp.exc_ = format!("{}: expected two ints or two strs", name).into();
+        return Ok(None);
+      }
+    };
+    let v = self.op.test(ord);
+    _traceln!(interp, "DEBUG: CmpFun::__apply__:   ord={:?} v={}", ord, v);
+    match interp.reg.tctx {
+      TermContext_::Match => {
+        interp.put_mat_res(v)?;
+      }
+      TermContext_::Unify => {
+        let y = interp._load_lit_val(clk, LitVal_::Bool(v))?;
+        interp.unify(clk, y, ret)?;
+      }
+    }
+    Ok(None)
+  }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct ListCls {
   // TODO
 }
@@ -284,23 +895,41 @@ impl ObjCls for ListCls {
     self
   }
 
-  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+  fn __clone__(&self) -> Box<dyn ObjCls> {
+    Box::new(self.clone())
+  }
+
+  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<ObjVal_, InterpCheck> {
     // TODO
     unimplemented!();
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct ListVal {
   // TODO
   buf:  Vec<ENum>,
 }
 
+impl ListVal {
+  pub fn _items(&self) -> &[ENum] {
+    &self.buf
+  }
+}
+
 impl ObjVal for ListVal {
   fn as_any(&self) -> &dyn Any {
     self
   }
 
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn ObjVal> {
+    Box::new(self.clone())
+  }
+
   fn __init__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
     // TODO
     unimplemented!();
@@ -317,7 +946,7 @@ impl ObjVal for ListVal {
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct DictCls {
   // TODO
 }
@@ -327,13 +956,17 @@ impl ObjCls for DictCls {
     self
   }
 
-  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+  fn __clone__(&self) -> Box<dyn ObjCls> {
+    Box::new(self.clone())
+  }
+
+  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<ObjVal_, InterpCheck> {
     // TODO
     unimplemented!();
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct SetCls {
   // TODO
 }
@@ -343,13 +976,17 @@ impl ObjCls for SetCls {
     self
   }
 
-  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+  fn __clone__(&self) -> Box<dyn ObjCls> {
+    Box::new(self.clone())
+  }
+
+  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<ObjVal_, InterpCheck> {
     // TODO
     unimplemented!();
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct OrdMapCls {
   // TODO
 }
@@ -359,13 +996,17 @@ impl ObjCls for OrdMapCls {
     self
   }
 
-  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+  fn __clone__(&self) -> Box<dyn ObjCls> {
+    Box::new(self.clone())
+  }
+
+  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<ObjVal_, InterpCheck> {
     // TODO
     unimplemented!();
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct TokenTrieCls {
   // TODO
 }
@@ -375,13 +1016,468 @@ impl ObjCls for TokenTrieCls {
     self
   }
 
-  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
-    // TODO
-    unimplemented!();
+  fn __clone__(&self) -> Box<dyn ObjCls> {
+    Box::new(self.clone())
+  }
+
+  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<ObjVal_, InterpCheck> {
+    Ok(Box::new(TokenTrieVal::default()))
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct TokenTrieVal {
-  // TODO
+  trie: TokenCharTrieMap<SNum>,
+}
+
+impl TokenTrieVal {
+  // NB: returns the prev val, or nil.
+  pub fn _insert(&mut self, key: &SafeStr, y: SNum) -> SNum {
+    self.trie.insert(key.as_raw_str(), y).unwrap_or(nil())
+  }
+
+  // NB: see `FastInterp::_trie_restore`.
+  pub fn _restore(&mut self, key: &SafeStr, y: SNum) {
+    if y.is_nil() {
+      self.trie.remove(key.as_raw_str());
+    } else {
+      self.trie.insert(key.as_raw_str(), y);
+    }
+  }
+
+  // NB: the entries in key order, w/ the keys requoted as atom lits.
+  pub fn _items(&self) -> Vec<(String, SNum)> {
+    self._iter_prefix("")
+  }
+
+  pub fn _iter_prefix(&self, pat: &str) -> Vec<(String, SNum)> {
+    self.trie.iter_prefix(pat).into_iter()
+      .map(|(key, v)| (TokenTrieVal::_quote_key(&key), v))
+      .collect()
+  }
+
+  // NB: the trie is keyed on the unquoted str of an atom lit; an emitted
+  // key is the escaped double-quoted lit of the str, i.e. the same
+  // normalization as for a single-quoted str (see `Tokenizer::next`).
+  fn _quote_key(key: &str) -> String {
+    serde_json::to_string(key).unwrap()
+  }
+
+  pub fn _unquote_key(raw: &SafeStr) -> Option<SafeStr> {
+    let raw = raw.as_raw_str();
+    let delim = if raw.starts_with("\"\"\"") {
+      "\"\"\""
+    } else if raw.starts_with("\"") {
+      "\""
+    } else {
+      return None;
+    };
+    let strconfig = StrParserConfig{
+      delim,
+      lines: delim.len() > 1,
+      tabs: delim.len() > 1,
+    };
+    let mut strparser = strconfig.parser_from_str_at(raw, 0);
+    match strparser.parse_str() {
+      Ok(s) if strparser.offset() == raw.len() => Some(s.into()),
+      _ => None
+    }
+  }
+
+  fn _get_key(interp: &FastInterp, clk: LClk, query: ENum) -> Result<Option<SafeStr>, InterpCheck> {
+    let vals = interp.get_vals(clk, query)?;
+    for &(_, ref val) in vals.iter() {
+      match val {
+        &Val_::Lit(LitVal_::Atom(ref v)) => {
+          // TODO: when to catch contradictory vals?
+          return Ok(TokenTrieVal::_unquote_key(v));
+        }
+        _ => {}
+      }
+    }
+    Ok(None)
+  }
+}
+
+impl ObjVal for TokenTrieVal {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn ObjVal> {
+    Box::new(self.clone())
+  }
+
+  fn __init__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    _traceln!(interp, "DEBUG: TokenTrieVal::__init__: this term={:?} tup.len={}", this_term, tup.len());
+    if tup.len() > 1 {
+      _traceln!(interp, "DEBUG: TokenTrieVal::__init__:   trailing tup={:?}", tup);
+      return Err(bot());
+    }
+    Ok(())
+  }
+
+  fn __destroy__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    Ok(())
+  }
+
+  fn __request__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: TokenTrieVal::__request__: this term={:?} tup={:?}", this_term, tup);
+
+    // NB: `tup[0]` is the object, and `tup[1]` is the attr ident term.
+    if tup.len() < 2 {
+      return Err(bot());
+    }
+    let meth = match interp.get_term(tup[1].inst)?.and_then(|t| t.as_any().downcast_ref::<IdentTerm_>()) {
+      None => {
+        _traceln!(interp, "DEBUG: TokenTrieVal::__request__:   not an attr ident: tup[1]={:?}", tup[1]);
+        return Err(bot());
+      }
+      Some(t) => t.raw_id.clone()
+    };
+    let args = &tup[2 .. ];
+    match (meth.as_raw_str(), args.len()) {
+      ("insert", 2) => {
+        let key = match TokenTrieVal::_get_key(interp, clk, args[0])? {
+          None => {
+            interp.exc_ = "TokenTrie.insert: key is not a str".into();
+            return Ok(());
+          }
+          Some(key) => key
+        };
+        if key.as_raw_str().is_empty() {
+          interp.exc_ = "TokenTrie.insert: key is empty".into();
+          return Ok(());
+        }
+        let y = args[1]._inst();
+        let prev_y = self._insert(&key, y);
+        _traceln!(interp, "DEBUG: TokenTrieVal::__request__:   insert: key={:?} y={:?} prev y={:?}", key, y, prev_y);
+        interp.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::TrieInsert(this_term.into_obj_val(), key, y, prev_y).into()));
+        let none_y = interp._load_lit_val(clk, LitVal_::None)?;
+        interp.unify(clk, none_y, ret)?;
+      }
+      ("lookup", 1) => {
+        let y = match TokenTrieVal::_get_key(interp, clk, args[0])? {
+          None => None,
+          Some(key) => self.trie.get(key.as_raw_str()).copied()
+        };
+        let y = match y {
+          None => interp._load_lit_val(clk, LitVal_::None)?,
+          Some(y) => y
+        };
+        interp.unify(clk, y, ret)?;
+      }
+      ("longest_prefix", 1) => {
+        let mat = match TokenTrieVal::_get_key(interp, clk, args[0])? {
+          None => None,
+          Some(pat) => self.trie.longest_prefix(pat.as_raw_str())
+        };
+        let y = match mat {
+          None => interp._load_lit_val(clk, LitVal_::None)?,
+          Some((len, v)) => {
+            let len_y = interp._load_lit_val(clk, LitVal_::Int(len as i64))?;
+            let y = interp._fresh()?;
+            interp.put_val(clk, y, LitVal_::List{buf: vec![len_y, v]})?;
+            y.into()
+          }
+        };
+        interp.unify(clk, y, ret)?;
+      }
+      ("iter_prefix", 1) => {
+        let mats = match TokenTrieVal::_get_key(interp, clk, args[0])? {
+          None => Vec::new(),
+          Some(pat) => self._iter_prefix(pat.as_raw_str())
+        };
+        let mut buf = Vec::with_capacity(mats.len());
+        for (key, v) in mats.into_iter() {
+          let key_y = interp._load_lit_val(clk, LitVal_::Atom(key.into()))?;
+          let y = interp._fresh()?;
+          interp.put_val(clk, y, LitVal_::List{buf: vec![key_y, v]})?;
+          buf.push(y.into());
+        }
+        let y = interp._fresh()?;
+        interp.put_val(clk, y, LitVal_::List{buf})?;
+        interp.unify(clk, y, ret)?;
+      }
+      _ => {
+        _traceln!(interp, "DEBUG: TokenTrieVal::__request__:   unknown meth={:?} args.len={}", meth, args.len());
+        interp.exc_ = format!("TokenTrie: no method {:?} w/ {} args", meth.as_raw_str(), args.len()).into();
+      }
+    }
+    Ok(())
+  }
+}
+
+// A user-defined class (see `StmCode_::Class`): the methods (and other
+// class attrs) are the bindings of the class scope.
+#[derive(Clone, Debug)]
+pub struct ClassCls {
+  name: SafeStr,
+  scope: SNum,
+}
+
+impl ClassCls {
+  pub fn new(name: SafeStr, scope: SNum) -> ClassCls {
+    ClassCls{name, scope}
+  }
+
+  pub fn _name(&self) -> &SafeStr {
+    &self.name
+  }
+}
+
+impl ObjCls for ClassCls {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn ObjCls> {
+    Box::new(self.clone())
+  }
+
+  fn __create__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<ObjVal_, InterpCheck> {
+    _traceln!(interp, "DEBUG: ClassCls::__create__: name={:?} scope={:?}", self.name, self.scope);
+    Ok(Box::new(ClassVal{
+      name: self.name.clone(),
+      scope: self.scope,
+      attrs: Default::default(),
+    }))
+  }
+}
+
+// An instance of a `ClassCls`. The instance attrs are created upon first
+// request, as fresh terms (so that e.g. `self.x = 1` binds the attr `x`).
+#[derive(Clone, Debug)]
+pub struct ClassVal {
+  name: SafeStr,
+  scope: SNum,
+  attrs: FxHashMap<SafeStr, SNum>,
+}
+
+impl ClassVal {
+  pub fn _name(&self) -> &SafeStr {
+    &self.name
+  }
+
+  // NB: the instance attrs in key order.
+  pub fn _items(&self) -> Vec<(SafeStr, SNum)> {
+    let mut items: Vec<_> = self.attrs.iter().map(|(k, &y)| (k.clone(), y)).collect();
+    items.sort_by(|lhs, rhs| lhs.0.as_raw_str().cmp(rhs.0.as_raw_str()));
+    items
+  }
+
+  // NB: see `FastInterp::_attr_restore`.
+  pub fn _restore(&mut self, key: &SafeStr, y: SNum) {
+    if y.is_nil() {
+      self.attrs.remove(key);
+    } else {
+      self.attrs.insert(key.clone(), y);
+    }
+  }
+
+  // Finds the binding (if any) of the class attr `key` in the class scope.
+  fn _find_cls_attr(&self, interp: &FastInterp, key: &SafeStr) -> Result<Option<SNum>, InterpCheck> {
+    let id = match interp.env._find_raw_ident(key.as_raw_str()) {
+      None => return Ok(None),
+      Some(id) => id
+    };
+    Ok(interp.lookup_namespace(self.scope)?.get(id))
+  }
+}
+
+impl ObjVal for ClassVal {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn ObjVal> {
+    Box::new(self.clone())
+  }
+
+  fn __init__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: ClassVal::__init__: this term={:?} tup.len={}", this_term, tup.len());
+    // NB: `tup[0]` is the class.
+    let init = match self._find_cls_attr(interp, &"__init__".into())? {
+      None => None,
+      Some(x) => interp._find_proc(clk, x)?
+    };
+    match init {
+      None => {
+        if tup.len() > 1 {
+          interp.exc_ = format!("{}() takes no arguments", self.name.as_raw_str()).into();
+        }
+      }
+      Some(init) => {
+        // NB: the result of the init call is the object itself.
+        let mut args = Vec::with_capacity(tup.len());
+        args.push(this_term);
+        args.extend(tup[1 .. ].iter().map(|x| x.inst));
+        interp._push_proc_call(clk, &init, args, this_term, &knt)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn __destroy__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    Ok(())
+  }
+
+  fn __request__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: ClassVal::__request__: this term={:?} tup={:?}", this_term, tup);
+
+    // NB: `tup[0]` is the object, and `tup[1]` is the attr ident term.
+    if tup.len() < 2 {
+      return Err(bot());
+    }
+    let key = match interp.get_term(tup[1].inst)?.and_then(|t| t.as_any().downcast_ref::<IdentTerm_>()) {
+      None => {
+        _traceln!(interp, "DEBUG: ClassVal::__request__:   not an attr ident: tup[1]={:?}", tup[1]);
+        return Err(bot());
+      }
+      Some(t) => t.raw_id.clone()
+    };
+    let args = &tup[2 .. ];
+    if args.is_empty() {
+      if let Some(&y) = self.attrs.get(&key) {
+        _traceln!(interp, "DEBUG: ClassVal::__request__:   attr: key={:?} y={:?}", key, y);
+        interp.unify(clk, y, ret)?;
+        return Ok(());
+      }
+    }
+    let cls_y = self._find_cls_attr(interp, &key)?;
+    let meth = match cls_y {
+      None => None,
+      Some(x) => interp._find_proc(clk, x)?
+    };
+    match (meth, cls_y) {
+      (Some(meth), _) => {
+        // NB: a method takes the object as its first arg; the method
+        // call is in place of the request, and its result is `ret`.
+        _traceln!(interp, "DEBUG: ClassVal::__request__:   meth: key={:?} args.len={}", key, args.len());
+        let mut meth_args = Vec::with_capacity(args.len() + 1);
+        meth_args.push(this_term);
+        meth_args.extend(args.iter().map(|x| x.inst));
+        interp._push_proc_call(clk, &meth, meth_args, ret, &knt)?;
+      }
+      (None, Some(y)) if args.is_empty() => {
+        interp.unify(clk, y, ret)?;
+      }
+      (None, _) if args.is_empty() => {
+        let y = interp._fresh()?;
+        _traceln!(interp, "DEBUG: ClassVal::__request__:   fresh attr: key={:?} y={:?}", key, y);
+        self.attrs.insert(key.clone(), y);
+        interp.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::AttrInsert(this_term.into_obj_val(), key, y).into()));
+        interp.unify(clk, y, ret)?;
+      }
+      (None, _) => {
+        interp.exc_ = format!("{}: no method {:?}", self.name.as_raw_str(), key.as_raw_str()).into();
+      }
+    }
+    Ok(())
+  }
+}
+
+// A module loaded by `FastInterp::load_many`. The module attrs are the
+// bindings of the module scope, in which the module body ran.
+#[derive(Clone, Debug)]
+pub struct ModuleVal {
+  name: SafeStr,
+  scope: SNum,
+}
+
+impl ModuleVal {
+  pub fn new(name: SafeStr, scope: SNum) -> ModuleVal {
+    ModuleVal{name, scope}
+  }
+
+  pub fn _name(&self) -> &SafeStr {
+    &self.name
+  }
+
+  pub fn _scope(&self) -> SNum {
+    self.scope
+  }
+
+  // Finds the binding (if any) of the module attr `key`.
+  fn _find_attr(&self, interp: &FastInterp, key: &SafeStr) -> Result<Option<SNum>, InterpCheck> {
+    let id = match interp.env._find_raw_ident(key.as_raw_str()) {
+      None => return Ok(None),
+      Some(id) => id
+    };
+    Ok(interp.lookup_namespace(self.scope)?.get(id))
+  }
+}
+
+impl ObjVal for ModuleVal {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  fn __clone__(&self) -> Box<dyn ObjVal> {
+    Box::new(self.clone())
+  }
+
+  fn __init__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    Ok(())
+  }
+
+  fn __destroy__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    Ok(())
+  }
+
+  fn __request__(&mut self, interp: &mut FastInterp, this_span: SpanNum, this_term: SNum, tup: &[ENum], ret: SNum, knt: BorrowedMemKnt, ) -> Result<(), InterpCheck> {
+    let clk = interp.clkctr._get_clock();
+    _traceln!(interp, "DEBUG: ModuleVal::__request__: name={:?} tup={:?}", self.name, tup);
+
+    // NB: `tup[0]` is the module, and `tup[1]` is the attr ident term.
+    if tup.len() < 2 {
+      return Err(bot());
+    }
+    let key = match interp.get_term(tup[1].inst)?.and_then(|t| t.as_any().downcast_ref::<IdentTerm_>()) {
+      None => {
+        _traceln!(interp, "DEBUG: ModuleVal::__request__:   not an attr ident: tup[1]={:?}", tup[1]);
+        return Err(bot());
+      }
+      Some(t) => t.raw_id.clone()
+    };
+    let args = &tup[2 .. ];
+    let y = self._find_attr(interp, &key)?;
+    let proc_ = match y {
+      Some(y) if !args.is_empty() => interp._find_proc(clk, y)?,
+      _ => None
+    };
+    match (proc_, y) {
+      (Some(proc_), _) => {
+        // NB: unlike a method, a module proc does not take the module as
+        // its first arg.
+        let args = args.iter().map(|x| x.inst).collect();
+        interp._push_proc_call(clk, &proc_, args, ret, &knt)?;
+      }
+      (None, Some(y)) if args.is_empty() => {
+        interp.unify(clk, y, ret)?;
+      }
+      (None, Some(_)) => {
+        interp.exc_ = format!("{}.{} is not a proc", self.name.as_raw_str(), key.as_raw_str()).into();
+      }
+      (None, None) => {
+        interp.exc_ = format!("module {:?} has no attribute {:?}", self.name.as_raw_str(), key.as_raw_str()).into();
+      }
+    }
+    Ok(())
+  }
 }
@@ -0,0 +1,297 @@
+use crate::interp::*;
+use crate::parse::{Stm as RawStm_, Term as RawTerm_};
+use crate::parse::visit::{Visitor, walk_term};
+use crate::tap::{_debugln};
+
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use std::mem::{replace, take};
+
+// Collects the idents of a query term, in order of first occurrence,
+// excluding the params of lambdas and the targets of list comprehensions.
+#[derive(Default)]
+struct QueryIdents_ {
+  ids:  Vec<RawIdent_>,
+  bound: Vec<RawIdent_>,
+}
+
+impl Visitor for QueryIdents_ {
+  fn visit_term(&mut self, term: &RawTerm_) {
+    match term {
+      &RawTerm_::Ident(_, ref id) => {
+        if !self.ids.contains(id) {
+          self.ids.push(id.clone());
+        }
+      }
+      &RawTerm_::Lambda(_, ref params, _) => {
+        for id in params.iter().flatten() {
+          self.bound.push(id.clone());
+        }
+        walk_term(self, term);
+      }
+      &RawTerm_::ListComp(_, _, Some(ref id), ..) => {
+        self.bound.push(id.clone());
+        walk_term(self, term);
+      }
+      _ => walk_term(self, term)
+    }
+  }
+}
+
+// The bindings of the free idents of a query, rendered by the value
+// printer (see `FastInterp::render_value`), in order of first occurrence
+// in the query term. An ident that remains unbound is rendered as `_`.
+pub type QueryBindings = Vec<(SafeStr, String)>;
+
+// An iterator over the solutions of a query (see `FastInterp::query`).
+//
+// Each call to `next` after the first backtracks into the choice points
+// of the query, to the next solution. The session state saved by
+// `query` is restored upon drop, rolling back the effects of the query.
+pub struct QueryIter<'a> {
+  interp:   &'a mut FastInterp,
+  free:     Vec<(SafeStr, IdentNum)>,
+  log_len:  usize,
+  trace:    FastTrace_,
+  knt_:     MemKntRef,
+  port:     Port_,
+  res_:     Result_,
+  tctx:     TermContext_,
+  started:  bool,
+  done:     bool,
+}
+
+impl<'a> QueryIter<'a> {
+  fn _bindings(&mut self) -> Result<QueryBindings, InterpCheck> {
+    let clk = self.interp.clkctr._get_clock();
+    let mut bindings = Vec::with_capacity(self.free.len());
+    for &(ref name, id) in self.free.iter() {
+      let s = match self.interp.resolve_ident(id)? {
+        (_, None) => "_".into(),
+        (_, Some(x)) => {
+          let x = self.interp.find(clk, x)?;
+          self.interp.render_value(clk, x)
+        }
+      };
+      bindings.push((name.clone(), s));
+    }
+    Ok(bindings)
+  }
+
+  // The bindings of `vars` as a JSON object (see `FastInterp::solutions`).
+  fn _json_bindings(&mut self, vars: &[&str]) -> Result<JsonValue, InterpCheck> {
+    let clk = self.interp.clkctr._get_clock();
+    let mut m = JsonMap::new();
+    for &name in vars.iter() {
+      let id = match self.interp.env._find_raw_ident(name) {
+        None => {
+          m.insert(name.into(), JsonValue::Null);
+          continue;
+        }
+        Some(id) => id
+      };
+      let v = match self.interp.resolve_ident(id)? {
+        (_, None) => JsonValue::Null,
+        (_, Some(x)) => {
+          let x = self.interp.find(clk, x)?;
+          self.interp.render_value_json(clk, x)
+        }
+      };
+      m.insert(name.into(), v);
+    }
+    Ok(JsonValue::Object(m))
+  }
+
+  // Runs (or backtracks) to the next solution; false if there is none.
+  fn _next(&mut self) -> Result<bool, InterpCheck> {
+    let mut stats = InterpRunStats_::default();
+    if self.started {
+      if !self.interp._backtrack()? {
+        return Ok(false);
+      }
+    }
+    self.started = true;
+    loop {
+      match self.interp._interp_bt_(&mut stats, false)? {
+        Yield_::Quiescent => {
+          match self.interp.res_.peek() {
+            ResReg_::Mat(false) => {}
+            _ => return Ok(true)
+          }
+        }
+        Yield_::Fail => {}
+        Yield_::Halt => {
+          return Ok(false);
+        }
+        Yield_::Raise => {
+          let exc_ = replace(&mut self.interp.exc_, Except_::default());
+          let msg = match exc_._err {
+            None => String::new(),
+            Some(e) => e.msg.to_string()
+          };
+          return Err(format!("query: raised: {}", msg).into());
+        }
+        yield_ => {
+          return Err(format!("query: unexpected yield: {:?}", yield_).into());
+        }
+      }
+      if !self.interp._backtrack()? {
+        return Ok(false);
+      }
+    }
+  }
+}
+
+impl<'a> Iterator for QueryIter<'a> {
+  type Item = Result<QueryBindings, InterpCheck>;
+
+  fn next(&mut self) -> Option<Result<QueryBindings, InterpCheck>> {
+    if self.done {
+      return None;
+    }
+    let ret = match self._next() {
+      Err(check) => Err(check),
+      Ok(false) => Ok(None),
+      Ok(true) => self._bindings().map(Some)
+    };
+    _debugln!(self.interp, "DEBUG: QueryIter::next: ret={:?}", ret);
+    match ret {
+      Ok(None) => {
+        self.done = true;
+        None
+      }
+      Ok(Some(bindings)) => Some(Ok(bindings)),
+      Err(check) => {
+        self.done = true;
+        Some(Err(check))
+      }
+    }
+  }
+}
+
+impl<'a> Drop for QueryIter<'a> {
+  fn drop(&mut self) {
+    let interp = &mut *self.interp;
+    if let Err(check) = interp._rollback_log(self.log_len) {
+      _debugln!(interp, "DEBUG: QueryIter::drop: rollback: check={:?}", check);
+    }
+    interp.exc_ = Except_::default();
+    interp.trace = take(&mut self.trace);
+    interp.knt_ = self.knt_.take();
+    interp.port = self.port;
+    interp.res_ = take(&mut self.res_);
+    interp.reg.tctx = self.tctx;
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Parses the single term `src_term` as a query, and interprets it in
+  // match context against the current session (cf. `eval_line`). The
+  // idents of the query that are unbound beforehand are its free idents;
+  // the returned iterator yields their bindings upon each solution.
+  //
+  // As w/ `eval_line`, the query cannot backtrack into the choice
+  // points of the session, and it does not disturb a module that is
+  // still loaded; unlike `eval_line`, the effects of the query are
+  // always rolled back, once the iterator is dropped.
+  pub fn query(&mut self, src_term: &str) -> Result<QueryIter<'_>, InterpCheck> {
+    let mut parser = self._new_parser(src_term);
+    let raw_mod = parser.mod_().map_err(|e| format!("parse error: {:?}", e))?;
+    drop(parser);
+    let raw_term = match &raw_mod.body[ .. ] {
+      &[ref raw_stm] => match &**raw_stm {
+        &RawStm_::Just(_, ref term) => term,
+        _ => return Err(format!("query: expected one term").into())
+      },
+      _ => return Err(format!("query: expected one term").into())
+    };
+    let mut visitor = QueryIdents_::default();
+    visitor.visit_term(raw_term);
+    let log_len = self.log.buf.len();
+    let heuristic = self.trace.heuristic;
+    let trace = replace(&mut self.trace, FastTrace_::default());
+    self.trace.heuristic = heuristic;
+    let knt_ = self.knt_.take();
+    let port = self.port;
+    let res_ = replace(&mut self.res_, Result_::default());
+    let tctx = replace(&mut self.reg.tctx, TermContext_::Match);
+    let mut iter = QueryIter{
+      interp: self,
+      free: Vec::new(),
+      log_len,
+      trace,
+      knt_,
+      port,
+      res_,
+      tctx,
+      started: false,
+      done: false,
+    };
+    let interp = &mut *iter.interp;
+    let term = interp._load_raw_term(raw_term)?;
+    for raw_id in visitor.ids.into_iter() {
+      if visitor.bound.contains(&raw_id) {
+        continue;
+      }
+      let id = interp._load_raw_ident(&raw_id)?;
+      if let (_, None) = interp.resolve_ident(id)? {
+        iter.free.push((raw_id, id));
+      }
+    }
+    let clk = interp.clkctr._get_clock();
+    interp.knt_ = MemKnt{
+      clk,
+      cur:  MemKnt_::InterpTerm(term, TermCodeInterpState_::fresh()),
+      prev: nil(),
+    }.into_ref();
+    interp.port = Port_::Enter;
+    Ok(iter)
+  }
+  // [Interp-API-Pub]
+  //
+  // Runs the goal term `goal` as a query (see `query`), and extracts the
+  // bindings of `vars` upon each solution, up to `limit` solutions, as a
+  // JSON object per solution (see `ValueRenderer::render_json`). A var
+  // that is unbound, or that does not occur in the session or the goal,
+  // is `null`. As w/ `query`, the effects of the goal are rolled back.
+  pub fn solutions(&mut self, goal: &str, vars: &[&str], limit: Option<usize>) -> Result<Vec<JsonValue>, InterpCheck> {
+    let mut iter = self.query(goal)?;
+    let mut sols = Vec::new();
+    while limit.map(|lim| sols.len() < lim).unwrap_or(true) {
+      if !iter._next()? {
+        break;
+      }
+      sols.push(iter._json_bindings(vars)?);
+    }
+    Ok(sols)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Evaluates the goal `term` (e.g. a term code passed to a builtin, see
+  // `_find_code`) in a scoped sub-run (see `_scoped_goal`), and pushes
+  // the result value of each solution onto `results`, as JSON (see
+  // `ValueRenderer::render_json`); a solution w/out a value result (e.g.
+  // a true match) is `null`. The effects of the goal are rolled back.
+  //
+  // This may be called from `Function::__apply__`, but NB: the applied
+  // Function is borrowed meanwhile, so the goal cannot apply it again.
+  // Upon `Err(Yield_::Raise)`, `exc_` is still set, so that a builtin
+  // may just return to propagate the raise.
+  pub fn call_goal(&mut self, term: TermCodeNum, results: &mut Vec<JsonValue>) -> Result<Result<(), Yield_>, InterpCheck> {
+    self._scoped_goal(term, |interp, res| {
+      let v = match res {
+        ResReg_::Key(x) => {
+          let clk = interp.clkctr._get_clock();
+          let x = interp.find(clk, x)?;
+          interp.render_value_json(clk, x)
+        }
+        _ => JsonValue::Null
+      };
+      results.push(v);
+      Ok(true)
+    })
+  }
+}
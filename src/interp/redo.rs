@@ -0,0 +1,297 @@
+use crate::algo::{BTreeMap};
+use crate::interp::*;
+use crate::tap::{_debugln};
+
+// What follows the end of a redo segment: either the choice point that
+// was pushed next (and was since popped), or else the yield at which the
+// run was backtracked from.
+#[derive(Debug)]
+pub enum RedoNext_ {
+  Push(TraceEntry_),
+  Fail,
+  Quiescent(RedoLeaf_),
+}
+
+// The registers and the continuation upon a quiescent yield.
+#[derive(Debug)]
+pub struct RedoLeaf_ {
+  reg:  FastReg_,
+  ctl_: FastCtlReg_,
+  knt_: MemKntRef,
+}
+
+// [Interp-API]
+//
+// The undone log entries of one rank of a choice point, in log order
+// and w/ their original clocks.
+#[derive(Debug)]
+pub struct RedoSegment_ {
+  // The SNum counter at the start and at the end of the segment.
+  xlb:  SNum,
+  xub:  SNum,
+  // The clocks spanned by the rank, from the step that took it up to
+  // the end of the segment; `inval` are the ranges within that were
+  // already invalid (e.g. a rolled back scoped goal) when it was undone.
+  clk_lb: LClk,
+  clk_ub: LClk,
+  inval:  Vec<(LClk, LClk)>,
+  buf:  Vec<RedoLogEntry_>,
+  next: RedoNext_,
+}
+
+// [Interp-API]
+//
+// The redo log retains the log segments that were undone upon
+// backtracking, keyed by the root timestamp and the rank of the
+// backtracked choice point. A plain depth-first search never revisits
+// a segment; the redo log is intended for re-search drivers (iterative
+// deepening, PV-guided re-search) which revisit an identical prefix,
+// e.g. via `FastInterp::select_branch`, and then replay the segment
+// instead of recomputing it.
+//
+// A segment ends either where the next choice point was pushed, in which
+// case replay re-pushes that choice point and continues w/ the segment of
+// its first rank, or else at the yield that was backtracked from. Replay
+// only redoes the log: the output and the hook callbacks of the replayed
+// steps are not repeated.
+//
+// NB: a segment is not retained if it cannot be replayed from the log
+// alone, i.e. if the rank had pending evals or oracle requests, or if
+// provenance recording or any goal table is live.
+#[derive(Default, Debug)]
+pub struct FastRedoLog_ {
+  enabled:  bool,
+  seg:  BTreeMap<(LClk, RawChoiceRank), RedoSegment_>,
+  // The choice point that was just popped while backtracking, if any.
+  popped:   Option<TraceEntry_>,
+  // The clock of the last yield backtracked from, and whether it was
+  // quiescent (rather than a failure).
+  leaf:     Option<(LClk, bool)>,
+  retain_ct:  u64,
+  replay_ct:  u64,
+}
+
+// A summary of the redo log (see `FastInterp::redo_stats`).
+#[derive(Clone, Copy, Default, Serialize, Debug)]
+pub struct RedoStats {
+  pub segments: usize,
+  pub entries:  usize,
+  // The number of segments retained, and replayed.
+  pub retain_ct:  u64,
+  pub replay_ct:  u64,
+}
+
+impl RedoSegment_ {
+  // [Interp-API]
+  pub(crate) fn _push(&mut self, entry: RedoLogEntry_) {
+    self.buf.push(entry);
+  }
+}
+
+impl FastRedoLog_ {
+  // [Interp-API]
+  pub(crate) fn _entry_ct(&self) -> usize {
+    self.seg.values().map(|seg| seg.buf.len()).sum()
+  }
+
+  // [Interp-API]
+  //
+  // A fork starts w/ an empty redo log, since the clocks of either side
+  // (and so the keys of the segments) may coincide after the fork.
+  pub(crate) fn _fork(&self) -> FastRedoLog_ {
+    FastRedoLog_{
+      enabled:  self.enabled,
+      ..FastRedoLog_::default()
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Drops every segment; returns the number of entries dropped.
+  pub(crate) fn _clear(&mut self) -> usize {
+    let entry_ct = self._entry_ct();
+    self.seg.clear();
+    self.popped = None;
+    self.leaf = None;
+    entry_ct
+  }
+
+  // [Interp-API]
+  //
+  // Drops the segments that span clocks below `lb` (see
+  // `FastInterp::compact_clkinval`).
+  pub(crate) fn _compact(&mut self, lb: LClk) {
+    self.seg.retain(|_, seg| seg.clk_lb >= lb);
+  }
+
+  // [Interp-API]
+  pub(crate) fn _mark_leaf(&mut self, clk: LClk, quiescent: bool) {
+    if self.enabled {
+      self.leaf = Some((clk, quiescent));
+    }
+  }
+
+  // [Interp-API]
+  pub(crate) fn _reset_popped(&mut self) {
+    self.popped = None;
+  }
+
+  // [Interp-API]
+  pub(crate) fn _set_popped(&mut self, te: TraceEntry_) {
+    if self.enabled {
+      self.popped = Some(te);
+    }
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Sets whether the segments undone upon backtracking are retained for
+  // replay (see `FastRedoLog_`); unsetting drops the retained segments.
+  pub fn set_redo(&mut self, enabled: bool) {
+    self.redo.enabled = enabled;
+    if !enabled {
+      let _ = self.redo._clear();
+    }
+  }
+
+  // [Interp-API-Pub]
+  pub fn redo_stats(&self) -> RedoStats {
+    RedoStats{
+      segments: self.redo.seg.len(),
+      entries:  self.redo._entry_ct(),
+      retain_ct:  self.redo.retain_ct,
+      replay_ct:  self.redo.replay_ct,
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Upon restoring the choice point at trace position `p`, begins the
+  // segment about to be undone, or else returns `None` if the segment is
+  // not to be retained. NB: this must precede the undo, and the
+  // invalidation of the undone clocks.
+  pub(crate) fn _redo_begin(&mut self, p: usize) -> Option<RedoSegment_> {
+    let popped = self.redo.popped.take();
+    let leaf = self.redo.leaf.take();
+    if !self.redo.enabled || self.prov.is_some() || !self.tables._is_empty() {
+      return None;
+    }
+    let next = match (popped, leaf) {
+      (Some(te), _) => {
+        if te.root_clk <= self.trace.buf[p].root_clk {
+          return None;
+        }
+        RedoNext_::Push(te)
+      }
+      // NB: the leaf is only valid if nothing has run since its yield.
+      (None, Some((clk, quiescent))) if clk == self.clkctr._get_clock() => {
+        if !quiescent {
+          RedoNext_::Fail
+        } else {
+          RedoNext_::Quiescent(RedoLeaf_{
+            reg:  self.reg,
+            ctl_: FastCtlReg_{
+              exc_: self.exc_.clone(),
+              res_: self.res_.clone(),
+              port: self.port.clone(),
+            },
+            knt_: self.knt_.clone(),
+          })
+        }
+      }
+      _ => return None
+    };
+    let clk_lb = self.trace.buf[p].last_clk.get();
+    let clk_ub = match &next {
+      &RedoNext_::Push(ref te) => te.root_clk,
+      _ => self.clkctr._next_clock()
+    };
+    Some(RedoSegment_{
+      xlb:  self.trace.buf[p].xlb,
+      xub:  self._peek(),
+      clk_lb,
+      clk_ub,
+      inval:  self.clkinval._ranges(clk_lb, clk_ub),
+      buf:  Vec::new(),
+      next,
+    })
+  }
+
+  // [Interp-API]
+  //
+  // Retains the segment undone from the choice point rooted at `root_clk`
+  // and left from rank `rank`.
+  pub(crate) fn _redo_retain(&mut self, root_clk: LClk, rank: RawChoiceRank, mut seg: RedoSegment_) {
+    // NB: the segment was undone in reverse log order.
+    seg.buf.reverse();
+    _debugln!(self, "DEBUG: FastInterp::_redo_retain: root clk={:?} rank={} clk={:?}..{:?} len={}",
+        root_clk, rank, seg.clk_lb, seg.clk_ub, seg.buf.len());
+    self.redo.retain_ct += 1;
+    self.redo.seg.insert((root_clk, rank), seg);
+  }
+
+  // [Interp-API]
+  //
+  // Upon resuming a restored choice point, replays the retained segment
+  // of its current rank, if any, and then that of each choice point that
+  // followed it. Returns the yield at the end of the replay, or else
+  // `None` if the run is to resume from the (last) restored choice point.
+  //
+  // NB: a segment is stale unless nothing has run since the choice point
+  // was restored, i.e. the SNum counter is still at the lower bound of
+  // the segment and the log is still below its clocks.
+  pub(crate) fn _redo_replay(&mut self) -> Result<Option<Yield_>, InterpCheck> {
+    if !self.redo.enabled {
+      return Ok(None);
+    }
+    loop {
+      let p = match self.trace.buf.len() {
+        0 => return Ok(None),
+        n => n - 1
+      };
+      let root_clk = self.trace.buf[p].root_clk;
+      if self.reg.rst_clk.is_nil() || self.reg.rst_clk != root_clk {
+        return Ok(None);
+      }
+      let rank = self.trace.buf[p].xctr;
+      let seg = match self.redo.seg.remove(&(root_clk, rank)) {
+        None => return Ok(None),
+        Some(seg) => seg
+      };
+      if self._peek() != seg.xlb ||
+         self.log.buf.last().map_or(false, |e| e.clk >= seg.clk_lb)
+      {
+        _debugln!(self, "DEBUG: FastInterp::_redo_replay: root clk={:?} rank={} stale", root_clk, rank);
+        return Ok(None);
+      }
+      _debugln!(self, "DEBUG: FastInterp::_redo_replay: root clk={:?} rank={} len={}", root_clk, rank, seg.buf.len());
+      for entry in seg.buf.into_iter() {
+        self._redo(entry)?;
+      }
+      self.clkinval._revalidate(seg.clk_lb, seg.clk_ub, &seg.inval);
+      self.trace.buf[p].last_clk.set(seg.clk_lb);
+      self.ctr._reset(seg.xub);
+      self.redo.replay_ct += 1;
+      match seg.next {
+        RedoNext_::Push(te) => {
+          self.trace._repush(te)?;
+          self._restore_ctl(p + 1);
+        }
+        RedoNext_::Fail => {
+          self.reg.rst_clk = nil();
+          return Ok(Some(Yield_::Fail));
+        }
+        RedoNext_::Quiescent(leaf) => {
+          self.reg = leaf.reg;
+          self.exc_ = leaf.ctl_.exc_;
+          self.res_ = leaf.ctl_.res_;
+          self.port = leaf.ctl_.port;
+          self.knt_ = leaf.knt_;
+          return Ok(Some(Yield_::Quiescent));
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,354 @@
+use crate::interp::*;
+use crate::interp::prelude::{
+  ArithFun, ArithOp_, ChoiceFun, CmpFun, CmpOp_, EvalFun, FailureFun,
+  InputFun, PrintFun, QueryFun, RandomChoiceFun, RandomIntFun,
+  TokenTrieCls, TokenizeFun, WChoiceFun,
+  _find_int_val, _find_list_items,
+};
+use crate::tap::{_debugln};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Debug)]
+pub enum BuiltinKind {
+  Function,
+  ObjCls,
+}
+
+// The number of args (excl. the head) that a builtin accepts: at least
+// `min`, and at most `max`, if any.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Debug)]
+pub struct BuiltinArity {
+  pub min:  usize,
+  pub max:  Option<usize>,
+}
+
+impl BuiltinArity {
+  pub fn exact(n: usize) -> BuiltinArity {
+    BuiltinArity{min: n, max: Some(n)}
+  }
+
+  pub fn range(min: usize, max: usize) -> BuiltinArity {
+    BuiltinArity{min, max: Some(max)}
+  }
+
+  pub fn at_least(min: usize) -> BuiltinArity {
+    BuiltinArity{min, max: None}
+  }
+
+  pub fn accepts(&self, nargs: usize) -> bool {
+    nargs >= self.min && self.max.map(|max| nargs <= max).unwrap_or(true)
+  }
+
+  // E.g. "1 arg", "0..1 args", or "1..N args".
+  pub fn describe(&self) -> String {
+    match self.max {
+      Some(1) if self.min == 1 => "1 arg".to_string(),
+      Some(max) if max == self.min => format!("{} args", max),
+      Some(max) => format!("{}..{} args", self.min, max),
+      None => format!("{}..N args", self.min)
+    }
+  }
+}
+
+// The expected sort of an arg of a builtin function, which is checked at
+// the call site (see `FastInterp::_check_fun_sig`).
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Debug)]
+pub enum ArgSort {
+  Any,
+  Int,
+  Str,
+  List,
+}
+
+impl Default for ArgSort {
+  fn default() -> ArgSort {
+    ArgSort::Any
+  }
+}
+
+impl ArgSort {
+  pub fn describe(self) -> &'static str {
+    match self {
+      ArgSort::Any => "any value",
+      ArgSort::Int => "an int",
+      ArgSort::Str => "a str",
+      ArgSort::List => "a list",
+    }
+  }
+}
+
+// The metadata of a registered builtin (see `FastInterp::registered_builtins`).
+// The signature of a builtin function (i.e. its arity and arg sorts) is
+// checked at the call site, before `Function::__apply__`; so, a builtin
+// function need not check its own args against its signature.
+#[derive(Clone, Serialize, Debug)]
+pub struct BuiltinInfo {
+  pub name:   SmolStr,
+  pub kind:   BuiltinKind,
+  pub arity:  BuiltinArity,
+  // The sort of each arg, by position; the args past the listed ones are
+  // of the `rest` sort.
+  pub sorts:  Vec<ArgSort>,
+  pub rest:   ArgSort,
+  pub doc:    SmolStr,
+  // Whether the builtin was registered by the embedder, rather than being
+  // part of the core prelude.
+  pub ext:    bool,
+}
+
+impl BuiltinInfo {
+  pub fn args(&mut self, sorts: &[ArgSort]) -> &mut BuiltinInfo {
+    self.sorts = sorts.to_vec();
+    self
+  }
+
+  pub fn rest(&mut self, sort: ArgSort) -> &mut BuiltinInfo {
+    self.rest = sort;
+    self
+  }
+
+  pub fn arg_sort(&self, idx: usize) -> ArgSort {
+    self.sorts.get(idx).copied().unwrap_or(self.rest)
+  }
+}
+
+#[derive(Debug)]
+enum PreludeItem_ {
+  Function(Box<dyn Function>),
+  ObjCls(Box<dyn ObjCls>),
+}
+
+impl Clone for PreludeItem_ {
+  fn clone(&self) -> PreludeItem_ {
+    match self {
+      &PreludeItem_::Function(ref fun) => PreludeItem_::Function(fun.__clone__()),
+      &PreludeItem_::ObjCls(ref cls) => PreludeItem_::ObjCls(cls.__clone__()),
+    }
+  }
+}
+
+// A registry of the builtins that `FastInterp::pre_init` binds in the
+// builtin scope, in registration order.
+//
+// The core prelude is `PreludeRegistry::core`. An embedder may register
+// extra builtins in the registry of an interpreter (see
+// `FastInterp::prelude_registry_mut`, or `FastInterpBuilder::prelude`)
+// before `pre_init`; these are then registered after the core prelude. A
+// name may only be registered once, and may not shadow a core builtin.
+#[derive(Clone, Debug)]
+pub struct PreludeRegistry {
+  entries:  Vec<(BuiltinInfo, PreludeItem_)>,
+  ext:      bool,
+}
+
+// NB: the default registry is of extra builtins (see `new`).
+impl Default for PreludeRegistry {
+  fn default() -> PreludeRegistry {
+    PreludeRegistry::new()
+  }
+}
+
+impl PreludeRegistry {
+  pub fn new() -> PreludeRegistry {
+    PreludeRegistry{entries: Vec::new(), ext: true}
+  }
+
+  pub fn core() -> PreludeRegistry {
+    let mut reg = PreludeRegistry{entries: Vec::new(), ext: false};
+    reg._core().unwrap();
+    reg
+  }
+
+  fn _core(&mut self) -> Result<(), InterpCheck> {
+    self.register_function("choice", BuiltinArity::range(0, 1),
+        "choice(n): a choice point over the branches 0 .. n",
+        ChoiceFun::default())?;
+    // NB: `wchoice()` (i.e. of no branches) fails.
+    self.register_function("wchoice", BuiltinArity::at_least(0),
+        "wchoice(w_0, ..., w_(n-1)): a choice point, explored in the order of the weights",
+        WChoiceFun::default())?;
+    self.register_function("failure", BuiltinArity::exact(0),
+        "failure(): fails the current branch",
+        FailureFun::default())?;
+    self.register_function("eval", BuiltinArity::exact(1),
+        "eval(x): suspends on an eval request, resolved by the embedder",
+        EvalFun::default())?;
+    self.register_function("input", BuiltinArity::exact(0),
+        "input(): reads a line of input",
+        InputFun::default())?;
+    self.register_function("print", BuiltinArity::range(0, 1),
+        "print(x): prints x",
+        PrintFun::default())?;
+    self.register_function("tokenize", BuiltinArity::exact(1),
+        "tokenize(src): the tokens of src, as [kind, start, end] lists",
+        TokenizeFun::default())?
+      .args(&[ArgSort::Str]);
+    self.register_function("query", BuiltinArity::at_least(1),
+        "query(goal, ...): the solutions of a goal pattern",
+        QueryFun::default())?;
+    self.register_function("random_choice", BuiltinArity::exact(1),
+        "random_choice(xs): a seeded random item of xs",
+        RandomChoiceFun::default())?
+      .args(&[ArgSort::List]);
+    self.register_function("random_int", BuiltinArity::exact(2),
+        "random_int(lo, hi): a seeded random int in lo .. hi",
+        RandomIntFun::default())?
+      .args(&[ArgSort::Int, ArgSort::Int]);
+    for &op in ArithOp_::all().iter() {
+      let arity = match op.arity() {
+        None => BuiltinArity::at_least(1),
+        Some(n) => BuiltinArity::exact(n)
+      };
+      self.register_function(op.name(), arity, "checked int arithmetic", ArithFun{op})?
+        .rest(ArgSort::Int);
+    }
+    for &op in CmpOp_::all().iter() {
+      self.register_function(op.name(), BuiltinArity::exact(2), "int or atom comparison", CmpFun{op})?;
+      self.register_function(op.py_name(), BuiltinArity::exact(2), "int or atom comparison", CmpFun{op})?;
+    }
+    self.register_obj_cls("TokenTrie", BuiltinArity::exact(0),
+        "TokenTrie(): a trie of token sequences",
+        TokenTrieCls::default())?;
+    Ok(())
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn contains(&self, name: &str) -> bool {
+    self.entries.iter().any(|&(ref info, _)| info.name == name)
+  }
+
+  pub fn infos(&self) -> Vec<BuiltinInfo> {
+    self.entries.iter().map(|&(ref info, _)| info.clone()).collect()
+  }
+
+  pub fn register_function<V: Function>(&mut self, name: &str, arity: BuiltinArity, doc: &str, fun: V) -> Result<&mut BuiltinInfo, InterpCheck> {
+    self._register(name, BuiltinKind::Function, arity, doc, PreludeItem_::Function(Box::new(fun)))
+  }
+
+  pub fn register_obj_cls<V: ObjCls>(&mut self, name: &str, arity: BuiltinArity, doc: &str, cls: V) -> Result<&mut BuiltinInfo, InterpCheck> {
+    self._register(name, BuiltinKind::ObjCls, arity, doc, PreludeItem_::ObjCls(Box::new(cls)))
+  }
+
+  fn _register(&mut self, name: &str, kind: BuiltinKind, arity: BuiltinArity, doc: &str, item: PreludeItem_) -> Result<&mut BuiltinInfo, InterpCheck> {
+    if self.contains(name) {
+      return Err(format!("prelude registry: duplicate builtin {:?}", name).into());
+    }
+    let info = BuiltinInfo{
+      name: name.into(),
+      kind,
+      arity,
+      sorts: Vec::new(),
+      rest: ArgSort::Any,
+      doc:  doc.into(),
+      ext:  self.ext,
+    };
+    self.entries.push((info, item));
+    Ok(&mut self.entries.last_mut().unwrap().0)
+  }
+
+  // Appends the entries of `other` (e.g. the extensions of an embedder).
+  pub fn _extend(&mut self, other: &PreludeRegistry) -> Result<(), InterpCheck> {
+    for &(ref info, ref item) in other.entries.iter() {
+      let info_ = self._register(&info.name, info.kind, info.arity, &info.doc, item.clone())?;
+      info_.sorts = info.sorts.clone();
+      info_.rest = info.rest;
+      info_.ext = info.ext;
+    }
+    Ok(())
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // The registry of the extra builtins, which are registered by `pre_init`
+  // after the core prelude. Registering after `pre_init` has no effect on
+  // this interpreter.
+  pub fn prelude_registry_mut(&mut self) -> &mut PreludeRegistry {
+    &mut self.prelude_ext
+  }
+
+  // [Interp-API-Pub]
+  pub fn set_prelude_registry(&mut self, registry: PreludeRegistry) -> PreludeRegistry {
+    replace(&mut self.prelude_ext, registry)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // The metadata of the builtins that were registered by `pre_init`, in
+  // registration order; empty before `pre_init`.
+  pub fn registered_builtins(&self) -> &[BuiltinInfo] {
+    &self.builtins
+  }
+
+  // [Interp-API]
+  //
+  // Registers the core prelude, and then the extra builtins (see
+  // `prelude_registry_mut`).
+  pub(crate) fn _register_prelude(&mut self) -> Result<(), InterpCheck> {
+    let mut registry = PreludeRegistry::core();
+    registry._extend(&self.prelude_ext)?;
+    for &(ref info, ref item) in registry.entries.iter() {
+      _debugln!(self, "DEBUG: FastInterp::_register_prelude: name={:?} kind={:?} ext={:?}",
+          info.name, info.kind, info.ext);
+      match item {
+        &PreludeItem_::Function(ref fun) => {
+          let fun = self._register_builtin_function_box(info.name.as_str(), fun.__clone__())?;
+          self.env.fun_sig.insert(fun.into(), info.clone());
+        }
+        &PreludeItem_::ObjCls(ref cls) => {
+          self._register_builtin_obj_cls_box(info.name.as_str(), cls.__clone__())?;
+        }
+      }
+    }
+    self.builtins = registry.infos();
+    Ok(())
+  }
+}
+
+impl FastInterp {
+  // [Interp-API]
+  //
+  // Checks the args `tup[1 .. ]` of an apply of the builtin function `fun`
+  // against its signature (if any); upon a mismatch, returns the msg of the
+  // exception to raise.
+  pub(crate) fn _check_fun_sig(&self, clk: LClk, fun: FunNum, tup: &[ENum]) -> Result<Option<String>, InterpCheck> {
+    let info = match self.env.fun_sig.get(&fun.into()) {
+      None => return Ok(None),
+      Some(info) => info
+    };
+    // NB: `tup[0]` is the function head.
+    let nargs = tup.len().saturating_sub(1);
+    if !info.arity.accepts(nargs) {
+      return Ok(Some(format!("{} expects {}, got {}", info.name, info.arity.describe(), nargs)));
+    }
+    for (idx, &arg) in tup.iter().skip(1).enumerate() {
+      let sort = info.arg_sort(idx);
+      if !self._check_arg_sort(clk, sort, arg)? {
+        _debugln!(self, "DEBUG: FastInterp::_check_fun_sig: name={:?} idx={} sort={:?} arg={:?}",
+            info.name, idx, sort, arg);
+        return Ok(Some(format!("{}: arg {} expects {}", info.name, idx + 1, sort.describe())));
+      }
+    }
+    Ok(None)
+  }
+
+  // [Interp-API]
+  pub(crate) fn _check_arg_sort(&self, clk: LClk, sort: ArgSort, arg: ENum) -> Result<bool, InterpCheck> {
+    match sort {
+      ArgSort::Any => Ok(true),
+      ArgSort::Int => Ok(_find_int_val(self, clk, arg)?.is_some()),
+      ArgSort::Str => {
+        let vals = self.get_vals(clk, arg)?;
+        Ok(vals.iter().any(|&(_, ref val)| matches!(val, &Val_::Lit(LitVal_::Atom(_)))))
+      }
+      ArgSort::List => Ok(_find_list_items(self, clk, arg)?.is_some()),
+    }
+  }
+}
@@ -0,0 +1,348 @@
+use crate::interp::*;
+use crate::interp::prelude::{ClassVal, ListVal, TokenTrieVal};
+
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+// The default max nesting depth of `FastInterp::render_value`; deeper
+// values are elided as `...`.
+pub const RENDER_MAX_DEPTH: usize = 32;
+
+// NB: a str is held as its raw (double-quoted) lit, which is already its
+// surface syntax; as JSON, it is the unquoted str.
+fn _json_str(raw: &SafeStr) -> JsonValue {
+  match TokenTrieVal::_unquote_key(raw) {
+    None => JsonValue::String(raw.as_raw_str().into()),
+    Some(s) => JsonValue::String(s.as_raw_str().into())
+  }
+}
+
+// Renders interpreter values as (roughly) surface syntax. A value that
+// recurs on its own path (i.e. a cyclic value) is elided as `...`, as is
+// any value nested deeper than `max_depth`.
+pub struct ValueRenderer<'a> {
+  interp:   &'a FastInterp,
+  clk:      LClk,
+  max_depth: usize,
+  // NB: the classes on the current path, for cycle detection.
+  path:     Vec<SNum>,
+}
+
+impl<'a> ValueRenderer<'a> {
+  pub fn new(interp: &'a FastInterp, clk: LClk) -> ValueRenderer<'a> {
+    ValueRenderer{
+      interp,
+      clk,
+      max_depth: RENDER_MAX_DEPTH,
+      path:     Vec::new(),
+    }
+  }
+
+  pub fn set_max_depth(&mut self, max_depth: usize) {
+    self.max_depth = max_depth;
+  }
+
+  pub fn render(&mut self, query: ENum) -> String {
+    let mut out = String::new();
+    self._render(query.inst, &mut out);
+    out
+  }
+
+  // Renders the value as JSON: `None`, bools, ints, and atoms as such,
+  // lists and tuples as arrays, token tries as objects, and class
+  // instances as objects w/ the class name keyed by `__class__`. An
+  // unbound or elided value is `null`; any other value (e.g. a proc) is
+  // its rendered string.
+  pub fn render_json(&mut self, query: ENum) -> JsonValue {
+    self._render_json(query.inst)
+  }
+
+  fn _render_json_seq(&mut self, xs: &[SNum]) -> JsonValue {
+    JsonValue::Array(xs.iter().map(|&x| self._render_json(x)).collect())
+  }
+
+  fn _render_json(&mut self, query: SNum) -> JsonValue {
+    let interp = self.interp;
+    let keys = match interp.env.unifier._findall(&interp.clkinval, self.clk, query) {
+      Err(_) => return JsonValue::Null,
+      Ok(keys) => keys
+    };
+    let ecls = match keys.first() {
+      None => query,
+      Some(key) => key.ecls
+    };
+    if self.path.contains(&ecls) || self.path.len() >= self.max_depth {
+      return JsonValue::Null;
+    }
+    self.path.push(ecls);
+    let v = self._render_json_keys(&keys);
+    self.path.pop();
+    v
+  }
+
+  fn _render_json_keys(&mut self, keys: &[ENum]) -> JsonValue {
+    let interp = self.interp;
+    for &key in keys.iter() {
+      let e = match interp.env.table_full[SNumSort::Val as usize].get(&key.inst) {
+        None => continue,
+        Some(e) => e
+      };
+      let val = match e.as_any().downcast_ref::<LitVal_>() {
+        None => continue,
+        Some(val) => val
+      };
+      return match val {
+        &LitVal_::None => JsonValue::Null,
+        &LitVal_::Bool(v) => JsonValue::Bool(v),
+        &LitVal_::Int(v) => JsonValue::from(v),
+        &LitVal_::Atom(ref v) => _json_str(v),
+        &LitVal_::Box{buf} => {
+          match buf {
+            None => JsonValue::Null,
+            Some(x) => self._render_json(x)
+          }
+        }
+        &LitVal_::List{ref buf} => self._render_json_seq(buf),
+      };
+    }
+    for &key in keys.iter() {
+      let obj = match interp.env.obj_val_full.get(&key.inst) {
+        Some(&TransparentBox::Ptr(ref obj)) => obj,
+        _ => continue
+      };
+      if let Some(obj) = obj.as_any().downcast_ref::<ListVal>() {
+        let xs: Vec<_> = obj._items().iter().map(|x| x.inst).collect();
+        return self._render_json_seq(&xs);
+      } else if let Some(obj) = obj.as_any().downcast_ref::<TokenTrieVal>() {
+        let mut m = JsonMap::new();
+        for (k, x) in obj._items().into_iter() {
+          m.insert(k, self._render_json(x));
+        }
+        return JsonValue::Object(m);
+      } else if let Some(obj) = obj.as_any().downcast_ref::<ClassVal>() {
+        let mut m = JsonMap::new();
+        m.insert("__class__".into(), JsonValue::String(obj._name().as_raw_str().into()));
+        for (k, x) in obj._items().into_iter() {
+          m.insert(k.as_raw_str().into(), self._render_json(x));
+        }
+        return JsonValue::Object(m);
+      }
+    }
+    for &key in keys.iter() {
+      let e = match interp.env.table_full[SNumSort::Term as usize].get(&key.inst) {
+        None => continue,
+        Some(e) => e
+      };
+      if let Some(t) = e.as_any().downcast_ref::<TupleTerm_>() {
+        let xs: Vec<_> = t.buf.iter().map(|x| x.inst).collect();
+        return self._render_json_seq(&xs);
+      } else if let Some(t) = e.as_any().downcast_ref::<LitTerm_>() {
+        return match t._unpack() {
+          UnpackedLitTerm_::None => JsonValue::Null,
+          UnpackedLitTerm_::True => JsonValue::Bool(true),
+          UnpackedLitTerm_::False => JsonValue::Bool(false),
+          UnpackedLitTerm_::Int(v) => JsonValue::from(*v),
+          UnpackedLitTerm_::Str(v) => _json_str(&v),
+        };
+      } else if let Some(t) = e.as_any().downcast_ref::<AtomTerm_>() {
+        return _json_str(&t.raw);
+      }
+    }
+    let mut out = String::new();
+    if self._render_keys(keys, &mut out) {
+      JsonValue::String(out)
+    } else {
+      JsonValue::Null
+    }
+  }
+
+  fn _render_seq(&mut self, xs: &[SNum], out: &mut String) {
+    for (i, &x) in xs.iter().enumerate() {
+      if i > 0 {
+        out.push_str(", ");
+      }
+      self._render(x, out);
+    }
+  }
+
+  fn _render(&mut self, query: SNum, out: &mut String) {
+    let interp = self.interp;
+    let keys = match interp.env.unifier._findall(&interp.clkinval, self.clk, query) {
+      Err(_) => {
+        out.push_str("<?>");
+        return;
+      }
+      Ok(keys) => keys
+    };
+    let ecls = match keys.first() {
+      None => query,
+      Some(key) => key.ecls
+    };
+    if self.path.contains(&ecls) || self.path.len() >= self.max_depth {
+      out.push_str("...");
+      return;
+    }
+    self.path.push(ecls);
+    if !self._render_keys(&keys, out) {
+      out.push('_');
+    }
+    self.path.pop();
+  }
+
+  // NB: renders the first renderable key of the class, preferring vals
+  // to terms; returns false if there is none (i.e. the class is unbound).
+  fn _render_keys(&mut self, keys: &[ENum], out: &mut String) -> bool {
+    let interp = self.interp;
+    for &key in keys.iter() {
+      let e = match interp.env.table_full[SNumSort::Val as usize].get(&key.inst) {
+        None => continue,
+        Some(e) => e
+      };
+      let val = match e.as_any().downcast_ref::<LitVal_>() {
+        None => continue,
+        Some(val) => val
+      };
+      match val {
+        &LitVal_::None => out.push_str("None"),
+        &LitVal_::Bool(true) => out.push_str("True"),
+        &LitVal_::Bool(false) => out.push_str("False"),
+        &LitVal_::Int(v) => out.push_str(&v.to_string()),
+        &LitVal_::Atom(ref v) => out.push_str(v.as_raw_str()),
+        &LitVal_::Box{buf} => {
+          out.push_str("box(");
+          if let Some(x) = buf {
+            self._render(x, out);
+          }
+          out.push(')');
+        }
+        &LitVal_::List{ref buf} => {
+          out.push('[');
+          self._render_seq(buf, out);
+          out.push(']');
+        }
+      }
+      return true;
+    }
+    for &key in keys.iter() {
+      if let Some(obj) = interp.env.obj_val_full.get(&key.inst) {
+        let obj = match obj {
+          &TransparentBox::Ptr(ref obj) => obj,
+          // NB: the obj is currently borrowed (i.e. being applied).
+          &TransparentBox::Blk => {
+            out.push_str("<object>");
+            return true;
+          }
+        };
+        if let Some(obj) = obj.as_any().downcast_ref::<ListVal>() {
+          let xs: Vec<_> = obj._items().iter().map(|x| x.inst).collect();
+          out.push('[');
+          self._render_seq(&xs, out);
+          out.push(']');
+        } else if let Some(obj) = obj.as_any().downcast_ref::<TokenTrieVal>() {
+          out.push('{');
+          for (i, (k, x)) in obj._items().into_iter().enumerate() {
+            if i > 0 {
+              out.push_str(", ");
+            }
+            out.push_str(&format!("{:?}: ", k));
+            self._render(x, out);
+          }
+          out.push('}');
+        } else if let Some(obj) = obj.as_any().downcast_ref::<ClassVal>() {
+          out.push_str(obj._name().as_raw_str());
+          out.push('(');
+          for (i, (k, x)) in obj._items().into_iter().enumerate() {
+            if i > 0 {
+              out.push_str(", ");
+            }
+            out.push_str(&format!("{}=", k.as_raw_str()));
+            self._render(x, out);
+          }
+          out.push(')');
+        } else {
+          out.push_str("<object>");
+        }
+        return true;
+      }
+    }
+    // NB: an ident is only rendered if no other term of the class is
+    // (e.g. the tuple `(1, 2)` of `x = (1, 2)` rather than `x`).
+    let mut ident = None;
+    for &key in keys.iter() {
+      let e = match interp.env.table_full[SNumSort::Term as usize].get(&key.inst) {
+        None => continue,
+        Some(e) => e
+      };
+      if let Some(t) = e.as_any().downcast_ref::<TupleTerm_>() {
+        let xs: Vec<_> = t.buf.iter().map(|x| x.inst).collect();
+        out.push('(');
+        self._render_seq(&xs, out);
+        if xs.len() == 1 {
+          out.push(',');
+        }
+        out.push(')');
+      } else if let Some(t) = e.as_any().downcast_ref::<LitTerm_>() {
+        match t._unpack() {
+          UnpackedLitTerm_::None => out.push_str("None"),
+          UnpackedLitTerm_::True => out.push_str("True"),
+          UnpackedLitTerm_::False => out.push_str("False"),
+          UnpackedLitTerm_::Int(v) => out.push_str(&v.to_string()),
+          UnpackedLitTerm_::Str(v) => out.push_str(v.as_raw_str()),
+        }
+      } else if let Some(t) = e.as_any().downcast_ref::<AtomTerm_>() {
+        out.push_str(t.raw.as_raw_str());
+      } else if let Some(t) = e.as_any().downcast_ref::<IdentTerm_>() {
+        if ident.is_none() {
+          ident = Some(t.raw_id.as_raw_str());
+        }
+        continue;
+      } else if let Some(t) = e.as_any().downcast_ref::<NEqualTerm_>() {
+        self._render(t.buf[0].inst, out);
+        out.push_str(" != ");
+        self._render(t.buf[1].inst, out);
+      } else if e.as_any().downcast_ref::<ProcTerm_>().is_some() {
+        out.push_str("<proc>");
+      } else if e.as_any().downcast_ref::<LambdaTerm_>().is_some() {
+        out.push_str("<lambda>");
+      } else if e.as_any().downcast_ref::<QuoteTerm_>().is_some() {
+        out.push_str("<quote>");
+      } else {
+        continue;
+      }
+      return true;
+    }
+    if let Some(ident) = ident {
+      out.push_str(ident);
+      return true;
+    }
+    for &key in keys.iter() {
+      if interp.env.fun_full.contains_key(&key.inst) {
+        out.push_str("<function>");
+        return true;
+      }
+      if interp.env.obj_cls_full.contains_key(&key.inst) {
+        out.push_str("<class>");
+        return true;
+      }
+    }
+    false
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Renders the value of `query` at the clock `clk` (see
+  // `ValueRenderer`). The class of `query` is followed through the
+  // unifier; an unbound class is rendered as `_`.
+  pub fn render_value(&self, clk: LClk, query: ENum) -> String {
+    ValueRenderer::new(self, clk).render(query)
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Renders the value of `query` at the clock `clk` as JSON (see
+  // `ValueRenderer::render_json`).
+  pub fn render_value_json(&self, clk: LClk, query: ENum) -> JsonValue {
+    ValueRenderer::new(self, clk).render_json(query)
+  }
+}
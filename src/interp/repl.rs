@@ -0,0 +1,107 @@
+use crate::interp::*;
+use crate::tap::{_debugln};
+
+use std::mem::{replace};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ReplOutcome {
+  // A stm w/out a result (e.g. a def).
+  Done,
+  // The rendered value of a term stm (see `FastInterp::render_value`).
+  Value(String),
+  // The outcome of a match or unify stm (e.g. `x = 1`).
+  Match(bool),
+  // The line failed, i.e. exhausted its choices; its effects are
+  // rolled back.
+  Fail,
+  // The line raised an exception; its effects are rolled back.
+  Raise(String),
+  // The line yielded otherwise (e.g. on a step limit).
+  Yield(Yield_),
+}
+
+impl FastInterp {
+  // [Interp-API]
+  //
+  // Undoes (and discards) the log back to the length `len`.
+  pub(crate) fn _rollback_log(&mut self, len: usize) -> Result<(), InterpCheck> {
+    while self.log.buf.len() > len {
+      let e = self.log._pop().unwrap();
+      match e.val {
+        LogEntryRef_::Undo(u) => {
+          let _ = self.undo(e.clk, u)?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Parses the single stm `line`, and interprets it in the current
+  // session, i.e. against the env of the previously loaded and
+  // interpreted stms, but w/out resuming (or otherwise disturbing) any
+  // module that is still loaded.
+  //
+  // The line cannot backtrack into the choice points of the session;
+  // conversely, the choices made by a successful line are committed. A
+  // line that fails or raises is rolled back.
+  pub fn eval_line(&mut self, line: &str) -> Result<ReplOutcome, InterpCheck> {
+    let mut parser = self._new_parser(line);
+    let raw_mod = parser.mod_().map_err(|e| format!("parse error: {:?}", e))?;
+    drop(parser);
+    if raw_mod.body.len() != 1 {
+      return Err(format!("eval_line: expected one stm, got {}", raw_mod.body.len()).into());
+    }
+    let log_len = self.log.buf.len();
+    let heuristic = self.trace.heuristic;
+    let trace = replace(&mut self.trace, FastTrace_::default());
+    self.trace.heuristic = heuristic;
+    let knt_ = self.knt_.take();
+    let port = self.port;
+    let res_ = replace(&mut self.res_, Result_::default());
+    let stm = self._load_raw_stm(DebruijnLevel::root(), nil(), &raw_mod.body[0])?;
+    let clk = self.clkctr._get_clock();
+    self.knt_ = MemKnt{
+      clk,
+      cur:  MemKnt_::InterpStm(stm, StmCodeInterpState_::fresh()),
+      prev: nil(),
+    }.into_ref();
+    self.port = Port_::Enter;
+    let mut stats = InterpRunStats_::default();
+    let ret = self._interp_(&mut stats);
+    let outcome = match ret {
+      Err(check) => Err(check),
+      Ok(Yield_::Quiescent) => {
+        let clk = self.clkctr._get_clock();
+        match self.res_.peek() {
+          ResReg_::Emp => Ok(ReplOutcome::Done),
+          ResReg_::Mat(v) => Ok(ReplOutcome::Match(v)),
+          ResReg_::Key(x) => {
+            self.find(clk, x).map(|x| ReplOutcome::Value(self.render_value(clk, x)))
+          }
+        }
+      }
+      Ok(Yield_::Halt) => {
+        self._rollback_log(log_len)?;
+        Ok(ReplOutcome::Fail)
+      }
+      Ok(Yield_::Raise) => {
+        let exc_ = replace(&mut self.exc_, Except_::default());
+        self._rollback_log(log_len)?;
+        let msg = match exc_._err {
+          None => String::new(),
+          Some(e) => e.msg.to_string()
+        };
+        Ok(ReplOutcome::Raise(msg))
+      }
+      Ok(yield_) => Ok(ReplOutcome::Yield(yield_))
+    };
+    _debugln!(self, "DEBUG: FastInterp::eval_line: outcome={:?}", outcome);
+    self.trace = trace;
+    self.knt_ = knt_;
+    self.port = port;
+    self.res_ = res_;
+    outcome
+  }
+}
@@ -0,0 +1,124 @@
+#[cfg(feature = "pyo3")]
+use crate::algo::{BTreeSet};
+use crate::interp::*;
+#[cfg(feature = "pyo3")]
+use crate::oracle::{OracleReqId};
+use crate::tap::{_debugln};
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap};
+use std::mem::{swap};
+#[cfg(feature = "pyo3")]
+use std::mem::{take};
+
+// A frontier state of `FastInterp::interp_best_first`: a fork of the
+// interpreter, restored to the next untried rank of the choice point at
+// trace position `base`.
+struct FrontierNode_ {
+  cost: f64,
+  seq:  u64,
+  base: usize,
+  interp: FastInterp,
+}
+
+impl PartialEq for FrontierNode_ {
+  fn eq(&self, other: &FrontierNode_) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for FrontierNode_ {}
+
+impl PartialOrd for FrontierNode_ {
+  fn partial_cmp(&self, other: &FrontierNode_) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for FrontierNode_ {
+  fn cmp(&self, other: &FrontierNode_) -> Ordering {
+    // NB: ties are broken in the order of expansion.
+    self.cost.total_cmp(&other.cost).then(self.seq.cmp(&other.seq))
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Explores the choice points best-first, rather than depth-first (as
+  // in `interp_`): upon each failure, the untried alternatives of the
+  // choice points on the trace are forked into a frontier, each scored
+  // by `cost_fn` (e.g. by oracle estimates), and the cheapest frontier
+  // state is resumed next. Since the forks are copy-on-write (see
+  // `fork`), a frontier state is restored out of trace order, w/out
+  // replaying the states in between.
+  //
+  // The state that yields (e.g. `Yield_::Quiescent`) becomes this
+  // interpreter's state, keeping this interpreter's debug I/O, event
+  // hooks, and run journal; the rest of the frontier is dropped.
+  // `Yield_::Halt` means the frontier has been exhausted.
+  pub fn interp_best_first<F: FnMut(&FastInterp) -> f64>(&mut self, mut cost_fn: F) -> Result<Yield_, InterpCheck> {
+    let mut frontier = BinaryHeap::new();
+    let mut seq = 0;
+    let mut base = 0;
+    let mut stats = InterpRunStats_::default();
+    loop {
+      match self._interp_bt_(&mut stats, false)? {
+        Yield_::Fail => {}
+        yield_ => return Ok(yield_)
+      }
+      // NB: a state only expands the choice points at or after its own
+      // base; the choice points before the base are expanded by the
+      // state that it was forked from.
+      for p in base .. self.trace.buf.len() {
+        let mut interp = self.fork();
+        if !interp._backtrack_to(p)? {
+          continue;
+        }
+        let cost = cost_fn(&interp);
+        _debugln!(self, "DEBUG: FastInterp::interp_best_first: push: p={} seq={} cost={}", p, seq, cost);
+        frontier.push(Reverse(FrontierNode_{cost, seq, base: p, interp}));
+        seq += 1;
+      }
+      let node = match frontier.pop() {
+        None => {
+          _debugln!(self, "DEBUG: FastInterp::interp_best_first: halt: backtracks={}", stats.backtrack_ct);
+          return Ok(Yield_::Halt);
+        }
+        Some(Reverse(node)) => node
+      };
+      _debugln!(self, "DEBUG: FastInterp::interp_best_first: pop: seq={} cost={} frontier.len={}", node.seq, node.cost, frontier.len());
+      stats.backtrack_ct += 1;
+      base = node.base;
+      self._adopt(node.interp);
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Replaces this interpreter's state w/ that of the fork `other`, but
+  // keeps this interpreter's debug I/O, event hooks, oracle bridge, and
+  // run journal.
+  pub(crate) fn _adopt(&mut self, mut other: FastInterp) {
+    swap(&mut self.snapshot, &mut other.snapshot);
+    swap(&mut self.tap, &mut other.tap);
+    swap(&mut self.hooks, &mut other.hooks);
+    swap(&mut self.journal, &mut other.journal);
+    #[cfg(feature = "pyo3")]
+    {
+      // NB: the oracle requests of this state that `other` does not share
+      // were submitted in the branches that are abandoned by the adopt.
+      let keep: BTreeSet<OracleReqId> = other.oracle_pos.values().flatten().copied().collect();
+      let pos = take(&mut self.oracle_pos);
+      if let Some(bridge) = self.oracle.as_mut() {
+        for id in pos.into_values().flatten() {
+          if !keep.contains(&id) && bridge.cancel(id) {
+            _debugln!(self, "DEBUG: FastInterp::_adopt: cancel oracle req: id={}", id);
+          }
+        }
+      }
+      swap(&mut self.oracle, &mut other.oracle);
+    }
+    *self = other;
+  }
+}
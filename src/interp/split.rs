@@ -0,0 +1,117 @@
+use crate::aikido::{Object};
+use crate::interp::*;
+use crate::smp::{SmpInfo};
+use crate::smp::pool::{WorkerPool};
+use crate::tap::{_debugln};
+
+use std::sync::{Arc};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitAggregate {
+  // Stop at the first solution (in sequential exploration order).
+  First,
+  // Enumerate every solution.
+  All,
+}
+
+pub struct SplitSolution {
+  pub worker:   RawChoiceRank,
+  // The choice ranks along the trace, from the top-level choice point.
+  pub pv:       Vec<RawChoiceRank>,
+  pub clk:      LClk,
+  pub state:    Object,
+}
+
+pub fn default_split_workers() -> RawChoiceRank {
+  SmpInfo::tl_clone().physical_core_count().unwrap_or(1).max(1)
+}
+
+// [Interp-API-Pub]
+//
+// Explores the program `src` by splitting its top-level choice point
+// among `workers` workers, where worker `w` tries the ranks
+// `w, w + workers, w + 2 * workers, ...`.
+//
+// Every worker runs in an isolated `FastInterp`, which it boots from the
+// source (via `pre_init` and `cold_start`). The interpreter state holds
+// `Rc`s and cells, and so is not `Send`; neither is a `FastInterp::fork`,
+// so a worker cannot be handed a fork of an interpreter booted by the
+// caller. Hence the split only covers a source run w/ the default config,
+// and every worker pays for its own boot. The workers run on a fresh
+// `smp::pool::WorkerPool`, each pinned to a core if the cpuset allows
+// (see `split_explore_in`).
+//
+// A worker yield other than a solution (`Quiescent`) or the end of its
+// branches (`Halt`) is returned as an error.
+//
+// Solutions are returned in sequential exploration order (i.e. sorted
+// by their pv); with `SplitAggregate::First`, only the first solution in
+// that order is returned.
+pub fn split_explore(src: &str, workers: RawChoiceRank, agg: SplitAggregate) -> Result<Vec<SplitSolution>, InterpCheck> {
+  let workers = workers.max(1);
+  let pool = WorkerPool::new(workers as usize)
+    .map_err(|e| format!("split: worker pool: {:?}", e))?;
+  split_explore_in(&pool, src, workers, agg)
+}
+
+// [Interp-API-Pub]
+//
+// As `split_explore`, but on the given pool; split worker `w` runs on
+// pool worker `w % pool.worker_count()`.
+pub fn split_explore_in(pool: &WorkerPool, src: &str, workers: RawChoiceRank, agg: SplitAggregate) -> Result<Vec<SplitSolution>, InterpCheck> {
+  let workers = workers.max(1);
+  let src: Arc<str> = src.into();
+  let mut handles = Vec::with_capacity(workers as usize);
+  for w in 0 .. workers {
+    let src = src.clone();
+    let handle = pool.submit_to(w as usize % pool.worker_count(), move || {
+      _explore_worker(&src, w, workers, agg)
+    }).map_err(|e| format!("split: worker pool: {:?}", e))?;
+    handles.push(handle);
+  }
+  let mut sols = Vec::new();
+  for (w, handle) in handles.into_iter().enumerate() {
+    match handle.join() {
+      Err(e) => return Err(format!("split: worker {}: {:?}", w, e).into()),
+      Ok(r) => sols.extend(r?)
+    }
+  }
+  sols.sort_by(|lsol, rsol| lsol.pv.cmp(&rsol.pv));
+  if agg == SplitAggregate::First {
+    sols.truncate(1);
+  }
+  Ok(sols)
+}
+
+fn _explore_worker(src: &str, w: RawChoiceRank, workers: RawChoiceRank, agg: SplitAggregate) -> Result<Vec<SplitSolution>, InterpCheck> {
+  let mut interp = FastInterp::default();
+  interp.trace.split = Some((w, workers));
+  interp.pre_init()?;
+  interp.cold_start(src)?;
+  let mut sols = Vec::new();
+  loop {
+    match interp.interp_()? {
+      Yield_::Quiescent => {
+        // NB: a solution w/out any choice points is the same in every
+        // worker, so only the first worker reports it.
+        if !interp.trace.buf.is_empty() || w == 0 {
+          _debugln!(interp, "DEBUG: split::_explore_worker: w={} solution: clk={:?}", w, interp.clkctr._get_clock());
+          sols.push(SplitSolution{
+            worker: w,
+            pv:     interp.trace.buf.iter().map(|te| te.xctr).collect(),
+            clk:    interp.clkctr._get_clock(),
+            state:  interp._aikido_object()?,
+          });
+        }
+        if agg == SplitAggregate::First || !interp._backtrack()? {
+          break;
+        }
+      }
+      Yield_::Halt => break,
+      y => {
+        return Err(format!("split: worker {}: unexpected yield: {:?}", w, y).into());
+      }
+    }
+  }
+  Ok(sols)
+}
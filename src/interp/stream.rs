@@ -0,0 +1,193 @@
+use crate::algo::rc::{Rc};
+use crate::interp::*;
+use crate::parse::{FastParser, Mod as RawMod_};
+use crate::tap::{_debugln};
+
+// The state of a streaming load (see `FastInterp::feed`).
+//
+// The fed text is accumulated in `src`. The complete top-level stms
+// (i.e. those that are followed by the start of another top-level stm,
+// or all of them after `finish`) are parsed into `raw_mod`; note that
+// the whole complete prefix is re-parsed upon each feed, so that the
+// spans remain offsets into `src`.
+//
+// A stm is loaded only once interpretation reaches it, rather than upon
+// being fed: loads after a choice point are undone upon backtracking,
+// and `load_ct` is undone (and so the stm reloaded) along w/ them.
+#[derive(Clone, Debug)]
+pub struct FastStream_ {
+  pub mod_code: ModCodeNum,
+  src:      String,
+  raw_mod:  Rc<RawMod_>,
+  pub load_ct: usize,
+  eof:      bool,
+}
+
+pub enum StreamNext_ {
+  Stm(StmCodeNum),
+  Pending,
+  Done,
+}
+
+fn _is_stream_continuation(line: &str) -> bool {
+  line.starts_with("elif") ||
+  line.starts_with("else") ||
+  line.starts_with("except") ||
+  line.starts_with("finally") ||
+  line.starts_with(")") ||
+  line.starts_with("]")
+}
+
+// The end of the prefix of `src` that consists of complete top-level
+// stms, i.e. the start of the last line that begins a top-level stm.
+pub fn _stream_complete_end(src: &str) -> usize {
+  let mut end = 0;
+  let mut quote = false;
+  let mut pos = 0;
+  for line in src.split_inclusive('\n') {
+    let start = pos;
+    pos += line.len();
+    if !line.ends_with('\n') {
+      break;
+    }
+    if line.starts_with("```") || line.trim_end().ends_with("```quote") {
+      // NB: a quote stm (or a stm w/ a quote term, e.g. `q = ```quote`)
+      // spans from its opening to its closing fence.
+      if !quote {
+        end = start;
+      }
+      quote = !quote;
+      continue;
+    }
+    if quote {
+      continue;
+    }
+    match line.chars().next() {
+      None => {}
+      Some(c) if c.is_whitespace() || c == '#' => {}
+      Some(_) if _is_stream_continuation(line) => {}
+      Some(_) => {
+        end = start;
+      }
+    }
+  }
+  end
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Accumulates the text `text` of a streamed program, and queues any
+  // newly complete top-level stms for interpretation. Returns the number
+  // of newly queued stms.
+  //
+  // Interpretation may overlap w/ feeding: once `interp_` runs out of
+  // queued stms before `finish`, it yields `Yield_::Interrupt`, and may
+  // be called again after the next feed.
+  pub fn feed(&mut self, text: &str) -> Result<usize, InterpCheck> {
+    self._stream_start()?;
+    self.stream.as_mut().unwrap().src.push_str(text);
+    self._stream_parse()
+  }
+
+  // [Interp-API-Pub]
+  //
+  // Flags the end of a streamed program (see `feed`), and queues any
+  // remaining stms. Returns the number of newly queued stms.
+  pub fn finish(&mut self) -> Result<usize, InterpCheck> {
+    self._stream_start()?;
+    self.stream.as_mut().unwrap().eof = true;
+    self._stream_parse()
+  }
+
+  // [Interp-API]
+  pub(crate) fn _stream_start(&mut self) -> Result<(), InterpCheck> {
+    match self.stream.as_ref() {
+      Some(stream) if stream.eof => {
+        return Err("feed: stream already finished".into());
+      }
+      Some(_) => return Ok(()),
+      None => {}
+    }
+    if self.knt_.is_some() {
+      return Err("feed: a module is already loaded".into());
+    }
+    let raw_mod = RawMod_{span: 0 .. 0, body: Vec::new(), pragmas: Vec::new()};
+    let x = self._load_raw_mod(&raw_mod)?;
+    let clk = self.clkctr._get_clock();
+    self.knt_ = MemKnt{
+      clk,
+      cur:  MemKnt_::InterpMod(x, ModCodeInterpState_::fresh()),
+      prev: nil(),
+    }.into_ref();
+    self.port = Port_::Enter;
+    self.stream = Some(FastStream_{
+      mod_code: x,
+      src:      String::new(),
+      raw_mod:  Rc::new(raw_mod),
+      load_ct:  0,
+      eof:      false,
+    });
+    Ok(())
+  }
+
+  // [Interp-API]
+  pub(crate) fn _stream_parse(&mut self) -> Result<usize, InterpCheck> {
+    let stream = self.stream.as_mut().unwrap();
+    let end = if stream.eof {
+      stream.src.len()
+    } else {
+      _stream_complete_end(&stream.src)
+    };
+    if end <= stream.raw_mod.span.end {
+      return Ok(0);
+    }
+    // NB: not `_new_parser`, since `stream` is borrowed from `self`.
+    let mut parser = FastParser::with_interner(&stream.src[ .. end], self.env.syms.clone());
+    if self.parser_v > 0 {
+      parser.set_verbose(self.parser_v);
+    }
+    parser.set_mode(self.parser_mode);
+    if let Some(writer) = self.logw.as_ref() {
+      parser.set_log_writer(writer.clone());
+    }
+    let raw_mod = parser.mod_().map_err(|e| format!("parse error: {:?}", e))?;
+    drop(parser);
+    let prev_ct = stream.raw_mod.body.len();
+    if raw_mod.body.len() < prev_ct {
+      return Err("feed: re-parse dropped stms".into());
+    }
+    let ct = raw_mod.body.len() - prev_ct;
+    // NB: the span end is the end of the parsed text, incl. any trailing
+    // blank lines, which is exactly the (complete) parsed prefix.
+    // NB: the pragmas of a streamed module are recorded, but not applied
+    // (see `_apply_pragmas`).
+    let raw_mod = RawMod_{span: 0 .. end, body: raw_mod.body, pragmas: raw_mod.pragmas};
+    stream.raw_mod = Rc::new(raw_mod);
+    self.src = Some(stream.src[ .. end].to_owned());
+    _debugln!(self, "DEBUG: FastInterp::_stream_parse: end={} queued={}", end, ct);
+    Ok(ct)
+  }
+
+  // [Interp-API]
+  //
+  // Loads the next queued stm of the streamed module `x`, if any.
+  pub(crate) fn _stream_next(&mut self, clk: LClk, x: ModCodeNum) -> Result<StreamNext_, InterpCheck> {
+    let (raw_mod, load_ct, eof) = match self.stream.as_ref() {
+      Some(stream) if stream.mod_code == x => {
+        (stream.raw_mod.clone(), stream.load_ct, stream.eof)
+      }
+      _ => return Ok(StreamNext_::Done)
+    };
+    if load_ct >= raw_mod.body.len() {
+      if eof {
+        return Ok(StreamNext_::Done);
+      }
+      return Ok(StreamNext_::Pending);
+    }
+    let stm = self._load_raw_stm(DebruijnLevel::root(), nil(), &raw_mod.body[load_ct])?;
+    self.stream.as_mut().unwrap().load_ct = load_ct + 1;
+    self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::StreamLoad.into()));
+    Ok(StreamNext_::Stm(stm))
+  }
+}
@@ -0,0 +1,491 @@
+use crate::algo::{BTreeSet, FxHashMap, SmolStr};
+use crate::algo::str::{SafeStr};
+use crate::interp::*;
+use crate::tap::{_debugln, _traceln};
+
+// [Interp-API]
+//
+// A tabled value. An answer outlives the terms of the derivation that
+// produced it (which are undone upon backtracking), so it is kept as a
+// value, and is loaded as fresh terms upon each consumption.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Debug)]
+pub enum TableVal_ {
+  // An unbound term, numbered in order of first occurrence within the
+  // goal (or the answer); loaded as a fresh (unbound) term per number.
+  Var(u32),
+  None,
+  Bool(bool),
+  Int(i64),
+  Atom(SafeStr),
+  List(Vec<TableVal_>),
+}
+
+// [Interp-API]
+//
+// The canonical call of a tabled goal: the proc code and the args.
+//
+// NB: the goals are keyed by variant, i.e. the unbound args are numbered
+// in order of first occurrence (see `TableVal_::Var`), so e.g. `f(x, x)`
+// and `f(x, y)` w/ both `x` and `y` unbound are different goals, while
+// `f(x, y)` and `f(y, x)` are the same goal.
+pub type GoalKey_ = (StmCodeNum, Vec<TableVal_>);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GoalStatus_ {
+  // The producer call has not yet returned w/out pending choice points,
+  // or has not yet reached the fixpoint of its answers.
+  Evaluating,
+  Complete,
+}
+
+// [Interp-API]
+#[derive(Clone, Debug)]
+pub struct GoalTable_ {
+  status:   GoalStatus_,
+  // NB: each answer is the args and then the result of a return of the
+  // producer call, in order of return, w/out variant duplicates.
+  answers:  Vec<Vec<TableVal_>>,
+  // NB: the root clock of the completion choice point of the producer
+  // call (see `FastInterp::_goal_produce`), and the trace len just after
+  // it was pushed.
+  choice_clk: LClk,
+  trace_len: usize,
+  // NB: set upon a variant call during the current iteration of the
+  // producer call; `iter_len` is the number of answers upon the start of
+  // the current iteration.
+  cycle:    bool,
+  iter_len: usize,
+  // NB: set upon an answer that cannot be tabled (e.g. an object); the
+  // goal is then called through.
+  opaque:   bool,
+}
+
+// The outcome of a call to a tabled proc (see `FastInterp::_goal_call`).
+pub enum GoalCall_ {
+  // Call the proc as usual.
+  Through,
+  // Call the proc as the producer of the goal table `key`.
+  Produce(GoalKey_),
+  // Consume the (complete, or so far) answers of the goal table.
+  Consume(Vec<Vec<TableVal_>>),
+}
+
+// [Interp-API]
+//
+// The goal tables of the tabled procs (see `FastInterp::set_tabled`).
+//
+// A tabled goal is evaluated once, by its first (producer) call; every
+// later call of the same goal consumes the answers of its table instead
+// of recomputing them. A variant call during the evaluation of its own
+// goal (i.e. a cycle, as in a left-recursive rule) consumes the answers
+// found so far, and fails if there are none, rather than looping.
+//
+// The producer call returns each answer once, w/out variant duplicates.
+// As a cycle may consume the answers only partially, the producer call
+// is iterated to a fixpoint: upon the entry of the producer call, a
+// completion choice point is pushed, and each backtrack into it re-runs
+// the producer call for as long as the previous run both had a cycle
+// and found new answers. The goal tables created during a run are
+// dropped upon backtracking into the completion choice point, and so
+// every goal of the same SCC is recomputed along w/ its leader.
+//
+// The tables respect backtracking: a goal table is created w/ an undo
+// log entry, and so is dropped upon backtracking past its producer call.
+// The answers of a table are _not_ undone by backtracking into the
+// producer, which instead accumulates them; the table is complete once
+// the producer returns w/out pending choice points of its own and w/out
+// a cycle, or else once the iteration reaches its fixpoint.
+#[derive(Clone, Default, Debug)]
+pub struct FastGoalTables_ {
+  procs:    BTreeSet<SmolStr>,
+  goals:    FxHashMap<GoalKey_, GoalTable_>,
+  hit_ct:   u64,
+  cycle_ct: u64,
+}
+
+// A summary of the goal tables (see `FastInterp::tabling_stats`).
+#[derive(Clone, Default, Debug)]
+pub struct TablingStats {
+  pub goals:    usize,
+  pub complete: usize,
+  pub answers:  usize,
+  // The number of consumer calls.
+  pub hits:     u64,
+  // The number of variant calls during the evaluation of their own goal.
+  pub cycles:   u64,
+}
+
+impl FastGoalTables_ {
+  pub fn _undo_insert(&mut self, key: &GoalKey_) -> bool {
+    self.goals.remove(key).is_some()
+  }
+
+  pub fn _is_empty(&self) -> bool {
+    self.goals.is_empty()
+  }
+
+  // The names of the tabled procs (see `FastInterp::set_tabled`).
+  pub fn _procs(&self) -> impl Iterator<Item=&SmolStr> {
+    self.procs.iter()
+  }
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Sets whether the procs named `name` are tabled (see `FastGoalTables_`).
+  pub fn set_tabled(&mut self, name: &str, tabled: bool) {
+    if tabled {
+      self.tables.procs.insert(name.into());
+    } else {
+      self.tables.procs.remove(name);
+    }
+  }
+
+  // [Interp-API-Pub]
+  pub fn tabling_stats(&self) -> TablingStats {
+    let mut stats = TablingStats::default();
+    for (_, table) in self.tables.goals.iter() {
+      stats.goals += 1;
+      if table.status == GoalStatus_::Complete {
+        stats.complete += 1;
+      }
+      stats.answers += table.answers.len();
+    }
+    stats.hits = self.tables.hit_ct;
+    stats.cycles = self.tables.cycle_ct;
+    stats
+  }
+
+  // [Interp-API]
+  //
+  // Convert the value of `query` to a tabled value; `None` if it has no
+  // tabled value (e.g. an object or a proc). `vars` are the class roots of
+  // the unbound terms numbered so far (see `TableVal_::Var`).
+  pub(crate) fn _table_val(&self, clk: LClk, query: SNum, vars: &mut Vec<SNum>) -> Result<Option<TableVal_>, InterpCheck> {
+    let query = self.find(clk, query)?;
+    for (_, val) in self.get_vals(clk, query)?.into_iter() {
+      let v = match val {
+        Val_::Lit(LitVal_::None) => TableVal_::None,
+        Val_::Lit(LitVal_::Bool(v)) => TableVal_::Bool(v),
+        Val_::Lit(LitVal_::Int(v)) => TableVal_::Int(v),
+        Val_::Lit(LitVal_::Atom(v)) => TableVal_::Atom(v),
+        Val_::Lit(LitVal_::List{buf}) => {
+          let mut vs = Vec::with_capacity(buf.len());
+          for x in buf.into_iter() {
+            match self._table_val(clk, x, vars)? {
+              None => return Ok(None),
+              Some(v) => vs.push(v)
+            }
+          }
+          TableVal_::List(vs)
+        }
+        _ => continue
+      };
+      return Ok(Some(v));
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query.inst).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      let t = match self.get_term(key.inst)? {
+        None => continue,
+        Some(t) => t
+      };
+      if let Some(t) = t.as_any().downcast_ref::<LitTerm_>() {
+        let v = match t._unpack() {
+          UnpackedLitTerm_::None => TableVal_::None,
+          UnpackedLitTerm_::True => TableVal_::Bool(true),
+          UnpackedLitTerm_::False => TableVal_::Bool(false),
+          UnpackedLitTerm_::Int(v) => TableVal_::Int(*v),
+          UnpackedLitTerm_::Str(v) => TableVal_::Atom((*v).clone()),
+        };
+        return Ok(Some(v));
+      } else if let Some(t) = t.as_any().downcast_ref::<AtomTerm_>() {
+        return Ok(Some(TableVal_::Atom(t.raw.clone())));
+      }
+    }
+    if self._is_unbound(clk, query.inst)? {
+      let k = match vars.iter().position(|&v| v == query.ecls) {
+        None => {
+          vars.push(query.ecls);
+          vars.len() - 1
+        }
+        Some(k) => k
+      };
+      return Ok(Some(TableVal_::Var(k as u32)));
+    }
+    Ok(None)
+  }
+
+  // [Interp-API]
+  //
+  // Load a tabled value as fresh terms; `vars` are the fresh terms loaded
+  // so far per var number, so that a repeated var is loaded as the same
+  // term.
+  pub(crate) fn _load_table_val(&mut self, clk: LClk, val: &TableVal_, vars: &mut FxHashMap<u32, SNum>) -> Result<SNum, InterpCheck> {
+    match val {
+      &TableVal_::Var(k) => {
+        match vars.get(&k) {
+          Some(&x) => Ok(x),
+          None => {
+            let x = self._fresh()?;
+            vars.insert(k, x);
+            Ok(x)
+          }
+        }
+      }
+      &TableVal_::None => self._load_lit_val(clk, LitVal_::None),
+      &TableVal_::Bool(v) => self._load_lit_val(clk, LitVal_::Bool(v)),
+      &TableVal_::Int(v) => self._load_lit_val(clk, LitVal_::Int(v)),
+      &TableVal_::Atom(ref v) => self._load_lit_val(clk, LitVal_::Atom(v.clone())),
+      &TableVal_::List(ref vs) => {
+        let mut buf = Vec::with_capacity(vs.len());
+        for v in vs.iter() {
+          buf.push(self._load_table_val(clk, v, vars)?);
+        }
+        let y = self._fresh()?;
+        self.put_val(clk, y, LitVal_::List{buf})?;
+        Ok(y)
+      }
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Look up (or create) the goal table of a call to the proc `code`.
+  pub(crate) fn _goal_call(&mut self, clk: LClk, code: StmCodeNum, args: &[SNum]) -> Result<GoalCall_, InterpCheck> {
+    if self.tables.procs.is_empty() {
+      return Ok(GoalCall_::Through);
+    }
+    let id = match self.lookup_stm_code(code)? {
+      StmCode_::Defproc{id, ..} => id,
+      _ => return Ok(GoalCall_::Through)
+    };
+    if !self.tables.procs.contains(self.lookup_raw_ident(id)?.as_raw_str()) {
+      return Ok(GoalCall_::Through);
+    }
+    let mut key_args = Vec::with_capacity(args.len());
+    let mut vars = Vec::new();
+    for &arg in args.iter() {
+      match self._table_val(clk, arg, &mut vars)? {
+        None => {
+          _traceln!(self, "DEBUG: FastInterp::_goal_call: code={:?} untabled arg={:?}", code, arg);
+          return Ok(GoalCall_::Through);
+        }
+        Some(v) => key_args.push(v)
+      }
+    }
+    let key = (code, key_args);
+    match self.tables.goals.get(&key) {
+      Some(table) if table.opaque => {
+        Ok(GoalCall_::Through)
+      }
+      Some(_) => {
+        let table = self.tables.goals.get_mut(&key).unwrap();
+        self.tables.hit_ct += 1;
+        if table.status == GoalStatus_::Evaluating {
+          table.cycle = true;
+          self.tables.cycle_ct += 1;
+          _debugln!(self, "DEBUG: FastInterp::_goal_call: code={:?} cycle: answers={}", code, table.answers.len());
+        }
+        Ok(GoalCall_::Consume(table.answers.clone()))
+      }
+      None => {
+        _traceln!(self, "DEBUG: FastInterp::_goal_call: code={:?} produce: key={:?}", code, &key.1);
+        let table = GoalTable_{
+          status:   GoalStatus_::Evaluating,
+          answers:  Vec::new(),
+          choice_clk: nil(),
+          trace_len: self.trace.buf.len(),
+          cycle:    false,
+          iter_len: 0,
+          opaque:   false,
+        };
+        self.tables.goals.insert(key.clone(), table);
+        self.log._append(clk, LogEntryRef_::Undo(UndoLogEntry_::GoalTableInsert(key.clone()).into()));
+        Ok(GoalCall_::Produce(key))
+      }
+    }
+  }
+
+  // [Interp-API]
+  //
+  // Convert the result of a call to a tabled value. Unlike an arg, the
+  // result is the class of the call term itself, which is not a value of
+  // the result; a result w/out a value is an unbound var.
+  pub(crate) fn _table_ret(&self, clk: LClk, ret: SNum, vars: &mut Vec<SNum>) -> Result<Option<TableVal_>, InterpCheck> {
+    if let Some(v) = self._table_val(clk, ret, vars)? {
+      return Ok(Some(v));
+    }
+    let query = self.find(clk, ret)?;
+    if !self.get_vals(clk, query)?.is_empty() {
+      return Ok(None);
+    }
+    let keys = self.env.unifier._findall(&self.clkinval, clk, query.inst).map_err(|e| e.into_check())?;
+    for &key in keys.iter() {
+      if key.inst == ret {
+        continue;
+      }
+      if let Some(t) = self.get_term(key.inst)? {
+        if t.as_any().downcast_ref::<IdentTerm_>().is_none() {
+          return Ok(None);
+        }
+      }
+      if self.env.fun_full.contains_key(&key.inst) ||
+         self.env.obj_cls_full.contains_key(&key.inst) ||
+         self.env.obj_val_full.contains_key(&key.inst)
+      {
+        return Ok(None);
+      }
+    }
+    let k = match vars.iter().position(|&v| v == query.ecls) {
+      None => {
+        vars.push(query.ecls);
+        vars.len() - 1
+      }
+      Some(k) => k
+    };
+    Ok(Some(TableVal_::Var(k as u32)))
+  }
+
+  // [Interp-API]
+  //
+  // Enter the producer call of `key`. The first entry pushes the
+  // completion choice point of the goal table; a resume of it iterates the
+  // producer call if the previous iteration had a cycle and found new
+  // answers, and otherwise completes the table and fails.
+  pub(crate) fn _goal_produce(&mut self, clk: LClk, key: &GoalKey_, knt: BorrowedMemKnt) -> Result<Option<Yield_>, InterpCheck> {
+    let rst_clk = self.reg.rst_clk;
+    let table = match self.tables.goals.get_mut(key) {
+      None => return Err(bot()),
+      Some(table) => table
+    };
+    if !rst_clk.is_nil() && rst_clk == table.choice_clk {
+      // NB: need to nil out reg.rst_clk.
+      self.reg.rst_clk = nil();
+      if let Some(te) = self.trace._maybe_get(rst_clk) {
+        te.last_clk.set(clk);
+      }
+      if table.status == GoalStatus_::Evaluating && table.cycle &&
+         table.answers.len() > table.iter_len
+      {
+        table.cycle = false;
+        table.iter_len = table.answers.len();
+        _debugln!(self, "DEBUG: FastInterp::_goal_produce: clk={:?} iterate: answers={}", clk, table.iter_len);
+        return Ok(None);
+      }
+      table.status = GoalStatus_::Complete;
+      _debugln!(self, "DEBUG: FastInterp::_goal_produce: clk={:?} complete: answers={}", clk, table.answers.len());
+      self.trace._close(rst_clk).map_err(|_| bot::<InterpCheck>())?;
+      return Ok(Some(Yield_::Fail));
+    }
+    let xlb = self.reg.xlb;
+    let ctl_reg = FastCtlReg_{
+      exc_: self.exc_.clone(),
+      res_: self.res_.clone(),
+      port: self.port.clone(),
+    };
+    let knt_ = MemKnt{
+      clk:  knt.clk,
+      prev: knt.prev.clone(),
+      cur:  knt.cur,
+    }.into_ref();
+    _traceln!(self, "DEBUG: FastInterp::_goal_produce: trace: push: clk={:?}", clk);
+    self.trace._push(clk, RawChoiceRank::max_value(), xlb, self.reg, ctl_reg, knt_)?;
+    let trace_len = self.trace.buf.len();
+    let table = self.tables.goals.get_mut(key).unwrap();
+    table.choice_clk = clk;
+    table.trace_len = trace_len;
+    Ok(None)
+  }
+
+  // [Interp-API]
+  //
+  // Record an answer upon a return of the producer call of `key`; returns
+  // false if the answer is a variant duplicate of an earlier answer, i.e.
+  // if the return should fail.
+  pub(crate) fn _goal_answer(&mut self, clk: LClk, key: &GoalKey_, args: &[SNum], ret: SNum) -> Result<bool, InterpCheck> {
+    let mut answer = Vec::with_capacity(args.len() + 1);
+    let mut vars = Vec::new();
+    for &x in args.iter() {
+      match self._table_val(clk, x, &mut vars)? {
+        None => break,
+        Some(v) => answer.push(v)
+      }
+    }
+    if answer.len() == args.len() {
+      if let Some(v) = self._table_ret(clk, ret, &mut vars)? {
+        answer.push(v);
+      }
+    }
+    let trace_len = self.trace.buf.len();
+    let table = match self.tables.goals.get_mut(key) {
+      None => return Err(bot()),
+      Some(table) => table
+    };
+    if answer.len() != args.len() + 1 {
+      table.opaque = true;
+      return Ok(true);
+    }
+    if table.answers.contains(&answer) {
+      return Ok(false);
+    }
+    table.answers.push(answer);
+    if trace_len <= table.trace_len && !table.cycle {
+      table.status = GoalStatus_::Complete;
+    }
+    Ok(true)
+  }
+
+  // [Interp-API]
+  //
+  // Consume the answers of a goal table, w/ a choice point if there are
+  // several; returns a yield if the consumer fails.
+  pub(crate) fn _goal_consume(&mut self, clk: LClk, args: &[SNum], answers: Vec<Vec<TableVal_>>, ret: SNum, knt: BorrowedMemKnt) -> Result<Option<Yield_>, InterpCheck> {
+    let rst_clk = self.reg.rst_clk;
+    let resume = !rst_clk.is_nil() && self.trace._maybe_get(rst_clk).is_some();
+    let (rank, choice) = if answers.len() <= 1 && !resume {
+      (0, None)
+    } else {
+      let xlb = self.reg.xlb;
+      // NB: need to nil out reg.rst_clk.
+      self.reg.rst_clk = nil();
+      if resume {
+        let te = self.trace._maybe_get(rst_clk).unwrap();
+        te.last_clk.set(clk);
+        (te.xctr as usize, Some((te.root_clk, te.xctr, te.xlim)))
+      } else {
+        let xlim: RawChoiceRank = answers.len().try_into().map_err(|_| bot::<InterpCheck>())?;
+        let ctl_reg = FastCtlReg_{
+          exc_: self.exc_.clone(),
+          res_: self.res_.clone(),
+          port: self.port.clone(),
+        };
+        let knt_ = MemKnt{
+          clk:  knt.clk,
+          prev: knt.prev.clone(),
+          cur:  knt.cur,
+        }.into_ref();
+        _traceln!(self, "DEBUG: FastInterp::_goal_consume: trace: push: clk={:?} answers={}", clk, answers.len());
+        self.trace._push(clk, xlim, xlb, self.reg, ctl_reg, knt_)?;
+        let te = self.trace._maybe_get(clk).unwrap();
+        (te.xctr as usize, Some((te.root_clk, te.xctr, te.xlim)))
+      }
+    };
+    let answer = match answers.get(rank) {
+      None => {
+        _traceln!(self, "DEBUG: FastInterp::_goal_consume: rank={} answers={} fail", rank, answers.len());
+        return Ok(Some(Yield_::Fail));
+      }
+      Some(answer) => answer
+    };
+    if let Some((te_clk, xctr, xlim)) = choice {
+      self._hook_choice(te_clk, xctr, xlim);
+    }
+    let mut vars = FxHashMap::default();
+    for (&x, v) in args.iter().chain(Some(&ret)).zip(answer.iter()) {
+      let y = self._load_table_val(clk, v, &mut vars)?;
+      self.unify(clk, y, x)?;
+    }
+    Ok(None)
+  }
+}
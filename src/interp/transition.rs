@@ -0,0 +1,171 @@
+use crate::interp::*;
+use crate::tap::{_debugln};
+
+// Generates `MemKntKind_`, i.e. the fieldless kinds of the `MemKnt_`
+// variants, together w/ `MemKnt_::kind`. The match in the latter is
+// exhaustive, so that a new `MemKnt_` variant must also be listed here.
+macro_rules! memknt_kinds {
+  ($($kind:ident),* $(,)?) => {
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    #[repr(u8)]
+    pub enum MemKntKind_ {
+      $($kind,)*
+    }
+
+    impl MemKntKind_ {
+      pub const ALL: &'static [MemKntKind_] = &[$(MemKntKind_::$kind,)*];
+
+      pub fn name(self) -> &'static str {
+        match self {
+          $(MemKntKind_::$kind => stringify!($kind),)*
+        }
+      }
+    }
+
+    impl MemKnt_ {
+      pub fn kind(&self) -> MemKntKind_ {
+        match self {
+          $(&MemKnt_::$kind{..} => MemKntKind_::$kind,)*
+        }
+      }
+    }
+  };
+}
+
+memknt_kinds! {
+  Uninit,
+  InterpMod,
+  InterpStmp,
+  InterpStm,
+  InterpIfStm,
+  InterpMatchStm,
+  InterpClassStm,
+  InterpModuleStm,
+  InterpProcBody,
+  InterpUnquote,
+  InterpLambdaBody,
+  InterpEvalCode,
+  InterpTerm,
+  InterpQualIdentTerm,
+  InterpBunchTerm,
+  InterpEqualTerm,
+  InterpInTerm,
+  InterpAndTerm,
+  InterpOrTerm,
+  InterpNEqualTerm,
+  InterpQEqualTerm,
+  InterpApplyTerm,
+  InterpListCompTerm,
+  InterpApplyBindLTerm,
+  InterpApplyBindRTerm,
+  InterpBindLTerm,
+  InterpBindRTerm,
+  InterpSubstTerm,
+  InterpRebindLTerm,
+  InterpRebindRTerm,
+  InterpEffectTerm,
+  InterpAwaitTerm,
+}
+
+pub const KNT_PORTS: [Port_; 3] = [Port_::Quiescent, Port_::Enter, Port_::Return];
+
+// The classification of a (port, continuation kind) pair of `resume_`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KntTransition_ {
+  // `resume_` has an arm for the pair.
+  Handled,
+  // The pair never reaches `resume_` by construction: the quiescent port
+  // only goes w/ a nil continuation, and an uninit continuation is never
+  // pushed.
+  Unreachable,
+  // The pair may reach `resume_`, but `resume_` has no arm for it.
+  Unhandled,
+}
+
+// The pattern of the (port, continuation) pairs that never reach
+// `resume_` (see `KntTransition_::Unreachable`), given the pattern of
+// the uninit continuation; shared by `_knt_transition` and `resume_`.
+macro_rules! knt_unreachable {
+  ($uninit:pat) => {
+    (Port_::Quiescent, _) | (_, $uninit)
+  };
+}
+pub(crate) use knt_unreachable;
+
+// Generates `_knt_transition`, the transition table of `resume_`: each
+// continuation kind is listed w/ the ports that `resume_` handles for it.
+//
+// NB: `resume_` dispatches through this table before its match, which is
+// exhaustive w/o a wildcard arm; so a pair that lacks an arm fails to
+// compile, and a pair w/ an arm that is not listed here fails at runtime
+// (and in `verify_transitions`).
+macro_rules! knt_transitions {
+  ($($kind:ident => [$($port:ident),*];)*) => {
+    // NB: the wildcard arm is unreachable while the table is complete.
+    #[allow(unreachable_patterns)]
+    pub fn _knt_transition(port: Port_, kind: MemKntKind_) -> KntTransition_ {
+      match (port, kind) {
+        $($((Port_::$port, MemKntKind_::$kind) => KntTransition_::Handled,)*)*
+        knt_unreachable!(MemKntKind_::Uninit) => KntTransition_::Unreachable,
+        _ => KntTransition_::Unhandled
+      }
+    }
+  };
+}
+
+knt_transitions! {
+  InterpMod => [Enter, Return];
+  InterpStmp => [Enter, Return];
+  InterpStm => [Enter, Return];
+  InterpIfStm => [Enter, Return];
+  InterpMatchStm => [Enter, Return];
+  InterpClassStm => [Enter, Return];
+  InterpModuleStm => [Enter, Return];
+  InterpProcBody => [Enter, Return];
+  InterpUnquote => [Enter, Return];
+  InterpLambdaBody => [Enter, Return];
+  InterpEvalCode => [Enter, Return];
+  InterpTerm => [Enter, Return];
+  InterpQualIdentTerm => [Enter, Return];
+  InterpBunchTerm => [Enter, Return];
+  InterpEqualTerm => [Enter, Return];
+  InterpInTerm => [Enter, Return];
+  InterpAndTerm => [Enter, Return];
+  InterpOrTerm => [Enter, Return];
+  InterpNEqualTerm => [Enter, Return];
+  InterpQEqualTerm => [Enter, Return];
+  InterpApplyTerm => [Enter, Return];
+  InterpListCompTerm => [Enter, Return];
+  InterpApplyBindLTerm => [Enter, Return];
+  InterpApplyBindRTerm => [Enter, Return];
+  InterpBindLTerm => [Enter, Return];
+  InterpBindRTerm => [Enter, Return];
+  InterpSubstTerm => [Enter, Return];
+  InterpRebindLTerm => [Enter, Return];
+  InterpRebindRTerm => [Enter, Return];
+  InterpEffectTerm => [Enter, Return];
+  InterpAwaitTerm => [Enter, Return];
+}
+
+impl FastInterp {
+  // [Interp-API-Pub]
+  //
+  // Lists the (port, continuation kind) pairs that may reach `resume_`,
+  // but that `resume_` does not handle (see `_knt_transition`); i.e. the
+  // pairs that would fail at runtime. Should be empty.
+  pub fn verify_transitions(&self) -> Vec<(Port_, MemKntKind_)> {
+    let mut holes = Vec::new();
+    for &port in KNT_PORTS.iter() {
+      for &kind in MemKntKind_::ALL.iter() {
+        match _knt_transition(port, kind) {
+          KntTransition_::Unhandled => {
+            _debugln!(self, "DEBUG: FastInterp::verify_transitions: unhandled: port={:?} knt={}", port, kind.name());
+            holes.push((port, kind));
+          }
+          _ => {}
+        }
+      }
+    }
+    holes
+  }
+}
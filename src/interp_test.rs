@@ -74,9 +74,54 @@ impl InterpTestsProver {
       Ok(yield_) => yield_
     };
     let t2 = Timestamp::fresh();
-    /*let flatinterp = interp.flatten_();
-    self.conf.set_vector_file(&item.key, &flatinterp.vectorize());*/
+    let flatinterp = interp.flatten_();
+    let vdst = self.conf.vector_path(&item.key);
+    let testv = match flatinterp.sync_testv(&vdst) {
+      Err(e) => {
+        writeln!(writer, "{} {} - {:?}", "not ok".red().bold(), rank, &item.key)?;
+        writeln!(writer, "# testv error = {:?}", e)?;
+        return Ok(());
+      }
+      Ok(testv) => testv
+    };
+    // NB: the prior vector (if any) is compared structurally, so that the
+    // changed table entries of an invalidated vector may be reported.
+    #[cfg(feature = "flat_import")]
+    if let &TestvSync::Invalidated{..} = &testv {
+      if let Some(prev) = item.vdst.as_ref().and_then(|s| FlatInterp::from_json(s).ok()) {
+        let diffs = prev.diff(&flatinterp);
+        writeln!(writer, "# testv diff ct = {}", diffs.len())?;
+        for d in diffs.iter().take(8) {
+          writeln!(writer, "# testv diff = {:?}", d)?;
+        }
+      }
+    }
+    match &testv {
+      // NB: invalidating an attested vector is a regression.
+      &TestvSync::Invalidated{attest_ct, ref keep} if attest_ct > 0 => {
+        writeln!(writer, "{} {} - {:?}", "not ok".red().bold(), rank, &item.key)?;
+        writeln!(writer, "# testv = invalidated: attest ct = {} keep = {:?}", attest_ct, keep)?;
+        return Ok(());
+      }
+      _ => {}
+    }
+    // NB: the failed in-language tests (see `FastInterp::test_failures`)
+    // are all reported, after the run.
+    let failures = interp.take_test_failures();
+    if !failures.is_empty() {
+      writeln!(writer, "{} {} - {:?}", "not ok".red().bold(), rank, &item.key)?;
+      writeln!(writer, "# test failure ct = {}", failures.len())?;
+      for fail in failures.iter() {
+        writeln!(writer, "# {}", fail.render())?;
+      }
+      return Ok(());
+    }
     writeln!(writer, "{} {} - {:?}", "ok".green(), rank, &item.key)?;
+    writeln!(writer, "# testv = {:?}", testv)?;
+    match interp.state_hash() {
+      Err(check) => writeln!(writer, "# state hash check = {:?}", check)?,
+      Ok(hash) => writeln!(writer, "# state hash = {}", hash.to_string())?
+    }
     if yield_ == Yield_::Quiescent {
     } else {
       println!("# yield = {:?}", yield_);
@@ -115,3 +160,1626 @@ impl TAPProver for InterpTestsProver {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algo::rc::{Rc};
+  use crate::interp::mem::{MemLimitAction};
+  use crate::interp::repl::{ReplOutcome};
+  use crate::interp::transition::{KNT_PORTS, KntTransition_, MemKntKind_, _knt_transition};
+
+  use std::cell::{Cell};
+
+  // The shared fixture: a pre-inited interpreter, w/ the config `conf`
+  // applied before any source is loaded.
+  fn _interp<F: FnOnce(&mut FastInterp)>(conf: F) -> FastInterp {
+    let mut interp = FastInterp::default();
+    interp.pre_init().unwrap();
+    conf(&mut interp);
+    interp
+  }
+
+  fn _start<F: FnOnce(&mut FastInterp)>(src: &str, conf: F) -> FastInterp {
+    let mut interp = _interp(conf);
+    interp.cold_start(src).unwrap();
+    interp
+  }
+
+  // NB: the first yield of the run must be quiescent.
+  fn _run<F: FnOnce(&mut FastInterp)>(src: &str, conf: F) -> FastInterp {
+    let mut interp = _start(src, conf);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    interp
+  }
+
+  #[test]
+  fn test_gc_then_call() {
+    // NB: w/ no live continuation or choice point, the body cells of `f`
+    // are only reachable from the code table.
+    let mut interp = _run("defproc f(x, y):\n    z = x\n    y = z\nf(1, w)\n", |_| {});
+    interp.gc().unwrap();
+    match interp.eval_line("f(2, v)").unwrap() {
+      ReplOutcome::Raise(msg) => panic!("raise: {}", msg),
+      _ => {}
+    }
+    interp.eval_line("assert v = 2").unwrap();
+    assert!(interp.take_test_failures().is_empty());
+  }
+
+  // NB: a deterministic runaway recursion never yields (nor backtracks).
+  const RUNAWAY_SRC: &'static str = "defproc f(x):\n    f(x)\nf(1)\n";
+
+  #[test]
+  fn test_step_limit_runaway() {
+    let mut interp = _start(RUNAWAY_SRC, |interp| interp.set_step_limit(Some(10_000)));
+    assert_eq!(interp.interp_().unwrap(), Yield_::Interrupt);
+    // NB: the run may be continued, up to the limit again.
+    assert_eq!(interp.interp_().unwrap(), Yield_::Interrupt);
+  }
+
+  #[test]
+  fn test_depth_limit_runaway() {
+    let mut interp = _start(RUNAWAY_SRC, |interp| interp.set_depth_limit(Some(2_000)));
+    assert_eq!(interp.interp_().unwrap(), Yield_::Raise);
+  }
+
+  #[test]
+  fn test_mem_soft_limit_runaway() {
+    let mut interp = _start(RUNAWAY_SRC, |interp| {
+      interp.set_step_limit(Some(10_000));
+      interp.set_mem_soft_limit(Some(1), MemLimitAction::Break);
+    });
+    assert_eq!(interp.interp_().unwrap(), Yield_::Break);
+    // NB: the break is not yielded again while still above the limit.
+    assert_eq!(interp.interp_().unwrap(), Yield_::Interrupt);
+  }
+
+  #[test]
+  fn test_mem_counters_recount() {
+    // NB: the running counts of the Term and Val tables must agree w/ a
+    // recount, after the undos of a backtrack, and after the sweep of a gc.
+    fn _check(interp: &FastInterp) {
+      let ctr = interp._recount_mem_ctr();
+      for (sort, n) in interp.memory_report().tables {
+        match sort {
+          SNumSort::Term => assert_eq!(n, ctr.term),
+          SNumSort::Val => assert_eq!(n, ctr.val),
+          _ => {}
+        }
+      }
+    }
+    let mut interp = _start("x = choice(2)\ny = x\n", |_| {});
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    _check(&interp);
+    assert!(interp._backtrack().unwrap());
+    _check(&interp);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    interp.gc().unwrap();
+    _check(&interp);
+  }
+
+  #[test]
+  fn test_mem_soft_limit_gc_backoff() {
+    // NB: a gc pass that leaves the estimate above the limit is not rerun
+    // at every sample, and so the run still reaches the step limit.
+    let mut interp = _start(RUNAWAY_SRC, |interp| {
+      interp.set_step_limit(Some(10_000));
+      interp.set_mem_soft_limit(Some(1), MemLimitAction::Gc);
+    });
+    assert_eq!(interp.interp_().unwrap(), Yield_::Interrupt);
+  }
+
+  #[test]
+  fn test_tabling_variant_goals() {
+    // NB: `g(c, c)` is not a variant of `g(a, b)`, but `g(d, e)` is.
+    let interp = _run("defproc g(x, y):\n    pass\ng(a, b)\ng(c, c)\ng(d, e)\n", |interp| interp.set_tabled("g", true));
+    let stats = interp.tabling_stats();
+    assert_eq!(stats.goals, 2);
+    assert_eq!(stats.hits, 1);
+  }
+
+  #[test]
+  fn test_tabling_variant_answers() {
+    // NB: the answer of `g(a, b)` aliases its args, and so must the
+    // consumed answer of `g(c, d)`.
+    let mut interp = _run("defproc g(x, y):\n    x = y\ng(a, b)\ng(c, d)\nc = 1\n", |interp| interp.set_tabled("g", true));
+    assert_eq!(interp.tabling_stats().hits, 1);
+    interp.eval_line("assert d = 1").unwrap();
+    assert!(interp.take_test_failures().is_empty());
+    interp.eval_line("assert d = 2").unwrap();
+    assert_eq!(interp.take_test_failures().len(), 1);
+  }
+
+  #[test]
+  fn test_tabling_left_recursion() {
+    // NB: the recursive call `path(x, z)` is a variant of its caller, and
+    // so consumes the answers found so far; the producer is iterated until
+    // no new answers are found, i.e. all the nodes of the cycle.
+    let src = "defproc edge(x, y):\n    k = choice(3)\n    if k = 0:\n        x = 1\n        y = 2\n    elif k = 1:\n        x = 2\n        y = 3\n    else:\n        x = 3\n        y = 1\ndefproc path(x, y):\n    k = choice(2)\n    if k = 0:\n        path(x, z)\n        edge(z, y)\n    else:\n        edge(x, y)\n";
+    let mut interp = _run(&format!("{}path(1, y)\n", src), |interp| interp.set_tabled("path", true));
+    let mut ys = Vec::new();
+    loop {
+      match interp.eval_line("y").unwrap() {
+        ReplOutcome::Value(y) => ys.push(y),
+        outcome => panic!("unexpected outcome: {:?}", outcome)
+      }
+      if !interp._backtrack().unwrap() {
+        break;
+      }
+      match interp.interp_().unwrap() {
+        Yield_::Quiescent => {}
+        Yield_::Halt => break,
+        yield_ => panic!("unexpected yield: {:?}", yield_)
+      }
+    }
+    ys.sort();
+    assert_eq!(ys, vec!["1", "2", "3"]);
+    assert!(interp.tabling_stats().cycles >= 1);
+  }
+
+  #[test]
+  fn test_undo_shadowed_builtin() {
+    // NB: re-registering a builtin name after a choice point shadows the
+    // earlier binding, which backtracking must restore (not just remove).
+    fn _fun_names(interp: &FastInterp) -> String {
+      let obj = interp._aikido_object().unwrap();
+      let mut fun_name = obj.tree_get("env").unwrap().tree_get("fun_name").unwrap().clone();
+      format!("{:?}", fun_name.content_hash())
+    }
+    let mut interp = _run("k = choice(2)\n", |_| {});
+    let fun_names = _fun_names(&interp);
+    interp._register_builtin_function("print", self::prelude::FailureFun::default()).unwrap();
+    assert!(_fun_names(&interp) != fun_names);
+    assert!(matches!(interp.eval_line("print(1)").unwrap(), ReplOutcome::Fail));
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_fun_names(&interp), fun_names);
+    assert!(!matches!(interp.eval_line("print(1)").unwrap(), ReplOutcome::Fail));
+  }
+
+  #[test]
+  fn test_redo_replay_reselected_branch() {
+    // NB: re-selecting an explored rank of the outer choice point replays
+    // its retained segments (incl. those of the inner choice point), and
+    // yields the same solutions as the original run.
+    fn _xy(interp: &mut FastInterp) -> (String, String) {
+      match (interp.eval_line("x").unwrap(), interp.eval_line("y").unwrap()) {
+        (ReplOutcome::Value(x), ReplOutcome::Value(y)) => (x, y),
+        outcome => panic!("unexpected outcome: {:?}", outcome)
+      }
+    }
+    let mut interp = _start("x = choice(3)\ny = choice(2)\nif y = 0:\n    failure()\n", |interp| interp.set_redo(true));
+    let mut sols = Vec::new();
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    let outer_clk = interp.choice_points()[0].clk;
+    sols.push(_xy(&mut interp));
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    sols.push(_xy(&mut interp));
+    assert_eq!(interp.redo_stats().replay_ct, 0);
+    interp.select_branch(outer_clk, 0).unwrap();
+    loop {
+      match interp.interp_().unwrap() {
+        Yield_::Quiescent => sols.push(_xy(&mut interp)),
+        Yield_::Halt => break,
+        yield_ => panic!("unexpected yield: {:?}", yield_)
+      }
+      if !interp._backtrack().unwrap() {
+        break;
+      }
+    }
+    let one = || "1".to_string();
+    let expected: Vec<_> = ["0", "1", "0", "1", "2"].iter().map(|x| (x.to_string(), one())).collect();
+    assert_eq!(sols, expected);
+    // NB: the ranks 0 and 1 of the outer choice point, and both ranks of
+    // the inner choice point under each.
+    assert_eq!(interp.redo_stats().replay_ct, 6);
+  }
+
+  #[test]
+  fn test_defmatch_candidates() {
+    // NB: the clause `x ?= 0` is not a candidate of `f(1, a)`, and the
+    // catch-all clause is a candidate of every call.
+    fn _sols(line: &str) -> (Vec<String>, usize) {
+      let src = "defmatch f(x, y):\n    x ?= 0\n    y = 10\ndefmatch f(x, y):\n    x ?= 1\n    y = 20\ndefmatch f(x, y):\n    y = 30\n";
+      let mut interp = _run(&format!("{}{}\n", src, line), |_| {});
+      let choice_ct = interp.choice_points().len();
+      let mut sols = Vec::new();
+      loop {
+        match interp.eval_line("a").unwrap() {
+          ReplOutcome::Value(a) => sols.push(a),
+          outcome => panic!("unexpected outcome: {:?}", outcome)
+        }
+        if !interp._backtrack().unwrap() {
+          break;
+        }
+        match interp.interp_().unwrap() {
+          Yield_::Quiescent => {}
+          Yield_::Halt => break,
+          yield_ => panic!("unexpected yield: {:?}", yield_)
+        }
+      }
+      (sols, choice_ct)
+    }
+    assert_eq!(_sols("f(1, a)"), (vec!["20".to_string(), "30".to_string()], 1));
+    assert_eq!(_sols("f(0, a)"), (vec!["10".to_string(), "30".to_string()], 1));
+    assert_eq!(_sols("f(2, a)"), (vec!["30".to_string()], 0));
+  }
+
+  #[test]
+  fn test_best_first_hooks() {
+    // NB: each failure is observed by the hooks, incl. those of the
+    // frontier states that were adopted after the first failure.
+    struct FailCounter(Rc<Cell<usize>>);
+    impl crate::interp::hooks::InterpHooks for FailCounter {
+      fn on_fail(&mut self, _clk: LClk) {
+        self.0.set(self.0.get() + 1);
+      }
+    }
+    let fail_ct = Rc::new(Cell::new(0));
+    let src = "x = choice(4)\nif x = 0:\n    failure()\nif x = 1:\n    failure()\nif x = 2:\n    failure()\n";
+    let mut interp = _start(src, |interp| {
+      let _ = interp.set_hooks(Box::new(FailCounter(fail_ct.clone())));
+    });
+    assert_eq!(interp.interp_best_first(|_| 0.0).unwrap(), Yield_::Quiescent);
+    assert_eq!(fail_ct.get(), 3);
+    assert!(interp.unset_hooks().is_some());
+  }
+
+  #[cfg(feature = "pyo3")]
+  #[test]
+  fn test_adopt_cancels_pruned_oracle_req() {
+    use crate::oracle::{ApproxOracleRequest, OracleBridge};
+    // NB: the request is submitted in rank 0 of the choice point; the
+    // adopted fork has since backtracked to rank 1, which prunes it.
+    let mut interp = _run("x = choice(3)\n", |_| {});
+    let (bridge, ends) = OracleBridge::detached(4);
+    interp.set_oracle_bridge(bridge);
+    let req = ApproxOracleRequest{query: vec!["q".into()], ..ApproxOracleRequest::default()};
+    let id = interp._oracle_submit(req).unwrap().unwrap();
+    assert_eq!(ends.req_rx.try_recv().unwrap().id, id);
+    // NB: a fork on the same branch shares the request.
+    let fork = interp.fork();
+    interp._adopt(fork);
+    assert!(ends.cancel_rx.try_recv().is_err());
+    let mut fork = interp.fork();
+    assert!(fork._backtrack_to(0).unwrap());
+    interp._adopt(fork);
+    assert_eq!(ends.cancel_rx.try_recv().unwrap(), id);
+    assert!(ends.cancel_rx.try_recv().is_err());
+  }
+
+  #[cfg(feature = "pyo3")]
+  #[test]
+  fn test_oracle_bridge_errors() {
+    use crate::oracle::{ApproxOracleRequest, OracleBridge, OracleBridgeError, OracleBridgeResponse};
+    // NB: a worker error comes back over the response queue, either as
+    // the response of its request, or else as an error of the bridge.
+    let mut interp = _run("x = 1\n", |_| {});
+    let (bridge, ends) = OracleBridge::detached(4);
+    interp.set_oracle_bridge(bridge);
+    let req = ApproxOracleRequest{query: vec!["q".into()], ..ApproxOracleRequest::default()};
+    let id = interp._oracle_submit(req).unwrap().unwrap();
+    ends.res_tx.send(OracleBridgeResponse::Item(id, Err(OracleBridgeError::Worker("put: bug".into())))).unwrap();
+    assert_eq!(interp._oracle_poll().unwrap(), 1);
+    assert!(interp._oracle_take(id).is_err());
+    assert!(interp._oracle_take(id).unwrap().is_none());
+    ends.res_tx.send(OracleBridgeResponse::Error(OracleBridgeError::Uncorrelated(vec!["r".into()]))).unwrap();
+    assert!(interp._oracle_poll().is_err());
+    assert_eq!(interp._oracle_poll().unwrap(), 0);
+    drop(ends);
+    let req = ApproxOracleRequest{query: vec!["q".into()], ..ApproxOracleRequest::default()};
+    assert!(interp._oracle_submit(req).is_err());
+  }
+
+  #[cfg(feature = "pyo3")]
+  fn _oracle_item(query: &str, value: &str) -> crate::oracle::ApproxOracleItem {
+    crate::oracle::ApproxOracleItem{
+      key: None,
+      query: vec![query.into()],
+      tag: None,
+      ctr: 0,
+      model: crate::oracle::ApproxOracleModel::DeepSeek_V3_Chat_20250324,
+      sample: None,
+      think: None,
+      value: Some(value.into()),
+      extra: None,
+    }
+  }
+
+  #[cfg(feature = "pyo3")]
+  #[test]
+  fn test_oracle_cache_dedup_batch() {
+    use crate::aikido::{ContentHash};
+    use crate::oracle::{ApproxOracleRequest, OracleBridge, OracleBridgeError, OracleBridgeResponse, OracleCache, OracleCacheStatus};
+    let (k1, k2) = (ContentHash::from([1; 32]), ContentHash::from([2; 32]));
+    let req = |q: &str| ApproxOracleRequest{query: vec![q.into()], ..ApproxOracleRequest::default()};
+    let mut cache = OracleCache::new();
+    // NB: an identical request (by key) is only batched once.
+    assert_eq!(cache.request(k1.clone(), req("q1")), OracleCacheStatus::Batched);
+    assert_eq!(cache.request(k1.clone(), req("q1")), OracleCacheStatus::Batched);
+    assert_eq!(cache.request(k2.clone(), req("q2")), OracleCacheStatus::Batched);
+    assert_eq!(cache.batch_len(), 2);
+    // NB: the flush stops at a full bridge queue, and keeps the rest of
+    // the batch.
+    let (mut bridge, ends) = OracleBridge::detached(1);
+    assert_eq!(cache.flush(&mut bridge).unwrap(), 1);
+    assert_eq!(cache.batch_len(), 1);
+    assert_eq!(cache.inflight_len(), 1);
+    assert_eq!(cache.status(&k1), Some(OracleCacheStatus::InFlight));
+    assert_eq!(cache.status(&k2), Some(OracleCacheStatus::Batched));
+    assert_eq!(cache.request(k1.clone(), req("q1")), OracleCacheStatus::InFlight);
+    let r = ends.req_rx.try_recv().unwrap();
+    assert_eq!(r.req.query, vec!["q1".into()]);
+    assert_eq!(cache.collect(&mut bridge).unwrap(), 0);
+    ends.res_tx.send(OracleBridgeResponse::Item(r.id, Ok(_oracle_item("q1", "a1")))).unwrap();
+    assert_eq!(cache.collect(&mut bridge).unwrap(), 1);
+    assert_eq!(cache.status(&k1), Some(OracleCacheStatus::Done));
+    assert_eq!(cache.get(&k1).unwrap().value.as_ref().unwrap().as_raw_str(), "a1");
+    assert_eq!(cache.request(k1.clone(), req("q1")), OracleCacheStatus::Done);
+    // NB: a failed request is dropped, so that its key may be requested
+    // again, and its error is kept until claimed.
+    assert_eq!(cache.flush(&mut bridge).unwrap(), 1);
+    assert_eq!(cache.batch_len(), 0);
+    let r = ends.req_rx.try_recv().unwrap();
+    ends.res_tx.send(OracleBridgeResponse::Item(r.id, Err(OracleBridgeError::Worker("poll: bug".into())))).unwrap();
+    assert_eq!(cache.collect(&mut bridge).unwrap(), 0);
+    let failed = cache.take_failed();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0, k2);
+    assert!(matches!(failed[0].1, OracleBridgeError::Worker(_)));
+    assert!(cache.take_failed().is_empty());
+    assert_eq!(cache.status(&k2), None);
+    assert_eq!(cache.request(k2.clone(), req("q2")), OracleCacheStatus::Batched);
+    assert_eq!(cache.len(), 1);
+    // NB: the request that fails to submit is dropped as well.
+    drop(ends);
+    assert!(matches!(cache.flush(&mut bridge), Err(OracleBridgeError::Disconnected)));
+    assert_eq!(cache.batch_len(), 0);
+    assert_eq!(cache.status(&k2), None);
+  }
+
+  #[cfg(feature = "pyo3")]
+  #[test]
+  fn test_oracle_cache_persist() {
+    use crate::aikido::{ContentHash};
+    use crate::oracle::{OracleCache};
+    use std::fs::{OpenOptions, remove_file};
+    use std::io::{ErrorKind, Write};
+    let path = std::env::temp_dir().join(format!("pythia-test-oracle-cache-{}.jsonl", std::process::id()));
+    let _ = remove_file(&path);
+    let (k1, k2, k3) = (ContentHash::from([1; 32]), ContentHash::from([2; 32]), ContentHash::from([3; 32]));
+    {
+      let mut cache = OracleCache::open(&path).unwrap();
+      assert_eq!(cache.len(), 0);
+      cache.insert(k1.clone(), _oracle_item("q1", "a1")).unwrap();
+      cache.insert(k2.clone(), _oracle_item("q2", "a2")).unwrap();
+    }
+    let valid_len = path.metadata().unwrap().len();
+    {
+      let cache = OracleCache::open(&path).unwrap();
+      assert_eq!(cache.len(), 2);
+      assert_eq!(cache.get(&k2).unwrap().value.as_ref().unwrap().as_raw_str(), "a2");
+    }
+    // NB: a torn last record is truncated upon open, and later records
+    // are appended after the valid ones.
+    {
+      let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+      write!(file, "{{\"hash\":").unwrap();
+    }
+    {
+      let mut cache = OracleCache::open(&path).unwrap();
+      assert_eq!(cache.len(), 2);
+      assert_eq!(path.metadata().unwrap().len(), valid_len);
+      cache.insert(k3.clone(), _oracle_item("q3", "a3")).unwrap();
+    }
+    {
+      let cache = OracleCache::open(&path).unwrap();
+      assert_eq!(cache.len(), 3);
+      assert!(cache.get(&k3).is_some());
+    }
+    // NB: an invalid record followed by a valid one is not torn, but an
+    // error.
+    {
+      let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+      writeln!(file, "garbage").unwrap();
+      write!(file, "{{\"hash\":").unwrap();
+    }
+    match OracleCache::open(&path) {
+      Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidData),
+      Ok(_) => panic!("expected an invalid record error")
+    }
+    let _ = remove_file(&path);
+  }
+
+  #[test]
+  fn test_run_journal_rotation() {
+    use crate::journal::{RunJournal, RunStatus, read_runs};
+    use std::fs::{OpenOptions, remove_dir_all};
+    use std::io::{Write};
+    let dir = std::env::temp_dir().join(format!("pythia-test-run-journal-{}", std::process::id()));
+    let _ = remove_dir_all(&dir);
+    // NB: w/ a max size of 1 byte, every append after the first rotates.
+    let mut journal = RunJournal::open(&dir).unwrap();
+    journal.set_max_bytes(1);
+    let mut interp = _start("x = choice(4)\nif x = 1:\n    failure()\n", |interp| {
+      let _ = interp.set_run_journal(journal);
+    });
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert!(interp.unset_run_journal().is_some());
+    assert!(dir.join("runs.1.jsonl").exists());
+    assert!(dir.join("runs.2.jsonl").exists());
+    assert!(!dir.join("runs.3.jsonl").exists());
+    // NB: a torn last line (e.g. of a crashed append) is skipped.
+    {
+      let mut file = OpenOptions::new().append(true).open(dir.join("runs.jsonl")).unwrap();
+      write!(file, "{{\"t_start\":").unwrap();
+    }
+    let recs = read_runs(&dir).unwrap();
+    assert_eq!(recs.len(), 3);
+    for (rec, y) in recs.iter().zip(["Quiescent"; 3]) {
+      assert!(matches!(&rec.status, &RunStatus::Yield(ref s) if s == y), "status: {:?}", rec.status);
+      assert_eq!(rec.mod_hash, recs[0].mod_hash);
+    }
+    assert!(recs[0].step_ct > 0);
+    assert!(recs[1].backtrack_ct > 0);
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_restore_from_recipe() {
+    // NB: the restored interpreter is rebuilt w/ the config of the
+    // committed one, and loads the same modules in the same way.
+    use crate::aikido::{Frame, Store};
+    let mut interp = _interp(|interp| {
+      interp.set_seed(7);
+      interp.set_unify_mode(UnifyMode::OccursChecked);
+      interp.set_tabled("g", true);
+    });
+    interp.load_many(&[("a", "z = 1\n"), ("main", "import a\nx = f(y)\n")]).unwrap();
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    let mut store = Store::root();
+    let mut frame = Frame::root().fresh(&mut store).unwrap();
+    let hash = interp.commit_to(&mut frame, &mut store).unwrap();
+    let mut restored = FastInterp::restore_from(&store, hash).unwrap();
+    assert_eq!(restored.seed(), 7);
+    assert_eq!(restored.unify_mode(), UnifyMode::OccursChecked);
+    assert_eq!(restored.srcmap().files().len(), 2);
+    assert!(matches!(restored.eval_line("x = f(x)").unwrap(), ReplOutcome::Fail));
+    assert!(matches!(restored.eval_line("y = 1").unwrap(), ReplOutcome::Done));
+  }
+
+  #[test]
+  fn test_load_many_pragma_conflict() {
+    fn _load(mods: &[(&str, &str)]) -> Result<FastInterp, InterpCheck> {
+      let mut interp = _interp(|_| {});
+      interp.load_many(mods)?;
+      Ok(interp)
+    }
+    // NB: `occurs_check` is the same as `unify = occurs_check`.
+    let interp = _load(&[("a", "-- pragma: occurs_check\nz = 1\n"), ("main", "-- pragma: unify = occurs_check\nimport a\n")]).unwrap();
+    assert_eq!(interp.unify_mode(), UnifyMode::OccursChecked);
+    assert!(_load(&[("a", "-- pragma: unify = ground\nz = 1\n"), ("main", "-- pragma: unify = rational\nimport a\n")]).is_err());
+    // NB: a run pragma applies to every module, and so must be in each.
+    assert!(_load(&[("a", "-- pragma: strict\nz = 1\n"), ("main", "import a\n")]).is_err());
+    assert!(_load(&[("a", "-- pragma: step_limit = 100\nz = 1\n"), ("main", "import a\n")]).is_ok());
+    assert!(_load(&[("a", "-- pragma: step_limit = 100\nz = 1\n"), ("main", "-- pragma: step_limit = 200\nimport a\n")]).is_err());
+  }
+
+  fn _unify_outcome(mode: UnifyMode, src: &str, line: &str) -> ReplOutcome {
+    _run(src, |interp| interp.set_unify_mode(mode)).eval_line(line).unwrap()
+  }
+
+  #[test]
+  fn test_unify_mode_cyclic() {
+    let src = "x = f(y)\n";
+    assert!(matches!(_unify_outcome(UnifyMode::Rational, src, "x = f(x)"), ReplOutcome::Done));
+    assert!(matches!(_unify_outcome(UnifyMode::OccursChecked, src, "x = f(x)"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::OccursChecked, src, "y = 1"), ReplOutcome::Done));
+  }
+
+  #[test]
+  fn test_unify_mode_unbound() {
+    let src = "z = 1\n";
+    assert!(matches!(_unify_outcome(UnifyMode::Rational, src, "x = y"), ReplOutcome::Done));
+    assert!(matches!(_unify_outcome(UnifyMode::OccursChecked, src, "x = y"), ReplOutcome::Done));
+    assert!(matches!(_unify_outcome(UnifyMode::GroundOnly, src, "x = y"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::GroundOnly, src, "x = z"), ReplOutcome::Done));
+  }
+
+  #[test]
+  fn test_unify_mode_head() {
+    // NB: the binds unify w/ an apply head (see `_unify_checked`).
+    let src = "z = 1\n";
+    assert!(!matches!(_unify_outcome(UnifyMode::Rational, src, "x := f(x)"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::OccursChecked, src, "x := f(x)"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::OccursChecked, src, "f(x) =: x"), ReplOutcome::Fail));
+    assert!(!matches!(_unify_outcome(UnifyMode::OccursChecked, src, "x := f(y)"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::GroundOnly, src, "x := y"), ReplOutcome::Fail));
+    assert!(!matches!(_unify_outcome(UnifyMode::GroundOnly, src, "x := z"), ReplOutcome::Fail));
+  }
+
+  #[test]
+  fn test_unify_mode_tuple() {
+    let src = "z = 1\n";
+    assert!(!matches!(_unify_outcome(UnifyMode::Rational, src, "x := (x,)"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::OccursChecked, src, "x := (x,)"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::OccursChecked, src, "x = (x, z)"), ReplOutcome::Fail));
+    assert!(!matches!(_unify_outcome(UnifyMode::OccursChecked, src, "x := (z,)"), ReplOutcome::Fail));
+    // NB: unbound-to-unbound, either bare or under a tuple.
+    assert!(matches!(_unify_outcome(UnifyMode::GroundOnly, src, "x := y"), ReplOutcome::Fail));
+    assert!(matches!(_unify_outcome(UnifyMode::GroundOnly, src, "x = y"), ReplOutcome::Fail));
+    assert!(!matches!(_unify_outcome(UnifyMode::GroundOnly, src, "x := (z,)"), ReplOutcome::Fail));
+  }
+
+  fn _eval(interp: &mut FastInterp, line: &str) -> String {
+    match interp.eval_line(line).unwrap() {
+      ReplOutcome::Value(v) => v,
+      ReplOutcome::Raise(msg) => format!("raise: {}", msg),
+      outcome => format!("{:?}", outcome)
+    }
+  }
+
+  #[test]
+  fn test_arith_checked() {
+    let mut interp = _run("z = 1\n", |_| {});
+    for (line, v) in [
+      ("add(2, 3)", "5"), ("sub(2, 3)", "-1"), ("mul(-2, 3)", "-6"),
+      ("abs(-3)", "3"), ("min(3, 1, 2)", "1"), ("max(3, 1, 2)", "3"),
+      // NB: as in Python, `div` floors, and `mod` has the sign of the
+      // divisor.
+      ("div(-7, 2)", "-4"), ("mod(-7, 2)", "1"), ("mod(7, -2)", "-1"),
+      ("div(7, -1)", "-7"), ("mod(-7, -1)", "0"),
+    ] {
+      assert_eq!(_eval(&mut interp, line), v, "line: {}", line);
+    }
+    // NB: an overflow raises (rather than panicking), and the interpreter
+    // is still usable afterwards.
+    assert_eq!(_eval(&mut interp, "add(9223372036854775807, 1)"), "raise: add: integer overflow");
+    assert_eq!(_eval(&mut interp, "mul(4611686018427387904, 2)"), "raise: mul: integer overflow");
+    assert_eq!(_eval(&mut interp, "abs(sub(-9223372036854775807, 1))"), "raise: abs: integer overflow");
+    assert_eq!(_eval(&mut interp, "div(sub(-9223372036854775807, 1), -1)"), "raise: div: integer overflow");
+    assert_eq!(_eval(&mut interp, "div(1, 0)"), "raise: div: division by zero");
+    assert_eq!(_eval(&mut interp, "mod(1, 0)"), "raise: mod: division by zero");
+    assert!(_eval(&mut interp, "add(\"a\", 1)").starts_with("raise: add: "));
+    assert_eq!(_eval(&mut interp, "add(z, 1)"), "2");
+  }
+
+  #[test]
+  fn test_cmp_builtins() {
+    let mut interp = _run("z = 1\n", |_| {});
+    for (line, v) in [
+      ("lt(1, 2)", "True"), ("lt(2, 2)", "False"), ("le(2, 2)", "True"),
+      ("gt(2, 1)", "True"), ("ge(1, 2)", "False"), ("le(z, 1)", "True"),
+      ("lt(\"a\", \"b\")", "True"), ("ge(\"a\", \"b\")", "False"),
+      // NB: the lowered comparison operators (see `CmpOp_::py_name`).
+      ("__py_lt__(1, 2)", "True"), ("__py_ge__(1, 2)", "False"),
+    ] {
+      assert_eq!(_eval(&mut interp, line), v, "line: {}", line);
+    }
+    assert_eq!(_eval(&mut interp, "gt(1, \"b\")"), "raise: gt: expected two ints or two strs");
+    // NB: in an `if` condition, a comparison is a match result, which
+    // composes w/ `else` and w/ `not`.
+    interp.eval_line("if ge(1, 2):\n    a = 1\nelse:\n    a = 2\n").unwrap();
+    assert_eq!(_eval(&mut interp, "a"), "2");
+    interp.eval_line("if not lt(2, 1):\n    b = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "b"), "1");
+    // NB: `c` is left unbound, and so evaluates to its ident.
+    interp.eval_line("if lt(z, 0):\n    c = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "c"), "c");
+  }
+
+  #[test]
+  fn test_kwargs_defaults() {
+    use crate::parse::{ParserMode};
+    // NB: keyword args and default params are only parsed in compat mode.
+    let src = "def f(r, y=2):\n    r = y\nf(a)\nf(b, y=3)\nf(y=4, r=c)\n";
+    let mut interp = _run(src, |interp| interp.set_parser_mode(ParserMode::PythonCompat));
+    assert_eq!(interp.parser_mode(), ParserMode::PythonCompat);
+    assert_eq!(_eval(&mut interp, "a"), "2");
+    assert_eq!(_eval(&mut interp, "b"), "3");
+    assert_eq!(_eval(&mut interp, "c"), "4");
+    assert_eq!(_eval(&mut interp, "f(d, z=3)"), "raise: proc got an unexpected keyword argument \"z\"");
+    assert_eq!(_eval(&mut interp, "f(e, r=e)"), "raise: proc got multiple values for argument \"r\"");
+    assert_eq!(_eval(&mut interp, "f(y=1)"), "raise: proc expected 2 arguments, got 1");
+  }
+
+  #[test]
+  fn test_token_trie() {
+    let src = "t = TokenTrie()\nt.insert(\"ab\", 1)\nt.insert(\"abcd\", 2)\nk = choice(2)\nif k = 0:\n    t.insert(\"abc\", 3)\n";
+    let mut interp = _run(src, |_| {});
+    for (line, v) in [
+      ("t.lookup(\"ab\")", "1"), ("t.lookup(\"abc\")", "3"), ("t.lookup(\"a\")", "None"),
+      ("t.longest_prefix(\"abcx\")", "[3, 3]"), ("t.longest_prefix(\"x\")", "None"),
+      // NB: the keys are emitted as (quoted) atom lits.
+      ("t.iter_prefix(\"abc\")", r#"[["abc", 3], ["abcd", 2]]"#),
+      ("t.iter_prefix(\"\")", r#"[["ab", 1], ["abc", 3], ["abcd", 2]]"#),
+    ] {
+      assert_eq!(_eval(&mut interp, line), v, "line: {}", line);
+    }
+    assert_eq!(_eval(&mut interp, "t.insert(\"\", 1)"), "raise: TokenTrie.insert: key is empty");
+    assert_eq!(_eval(&mut interp, "t.insert(1, 1)"), "raise: TokenTrie.insert: key is not a str");
+    assert_eq!(_eval(&mut interp, "t.foo(1)"), "raise: TokenTrie: no method \"foo\" w/ 1 args");
+    interp.eval_line("if \"abc\" in t:\n    m = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "m"), "1");
+    // NB: the insert of the first rank is undone upon backtracking.
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "k"), "1");
+    assert_eq!(_eval(&mut interp, "t.lookup(\"abc\")"), "None");
+    assert_eq!(_eval(&mut interp, "t.longest_prefix(\"abcx\")"), "[2, 1]");
+    assert_eq!(_eval(&mut interp, "t.iter_prefix(\"a\")"), r#"[["ab", 1], ["abcd", 2]]"#);
+  }
+
+  #[test]
+  fn test_defrule_lookup() {
+    // NB: the name of the head ident of each body goal of the rule `x`.
+    fn _body_heads(interp: &FastInterp, x: StmCodeNum) -> Vec<String> {
+      let (_, _, mut stmp) = interp.lookup_rule(x).unwrap();
+      let mut heads = Vec::new();
+      while !stmp.is_nil() {
+        let stmp_ = interp.lookup_stm_code_cell(stmp).unwrap();
+        let term = match interp.lookup_stm_code(stmp_.dptr.try_into_stm_code().unwrap()).unwrap() {
+          StmCode_::Just{term, ..} => term,
+          code => panic!("unexpected stm: {:?}", code)
+        };
+        let tup = match interp.lookup_term_code(term).unwrap() {
+          TermCode_::Apply{tup, ..} => tup,
+          code => panic!("unexpected term: {:?}", code)
+        };
+        let head = interp.lookup_term_code_cell(tup).unwrap().dptr.try_into_term_code().unwrap();
+        heads.push(_ident_name(interp, head));
+        stmp = stmp_.next.get().try_into_stm_code().unwrap();
+      }
+      heads
+    }
+    fn _ident_name(interp: &FastInterp, x: TermCodeNum) -> String {
+      match interp.lookup_term_code(x).unwrap() {
+        TermCode_::Ident{id, ..} => interp.lookup_raw_ident(id).unwrap().as_raw_str().to_string(),
+        code => panic!("unexpected term: {:?}", code)
+      }
+    }
+    let src = "defrule ancestor(x, z):\n    parent(x, y)\n    ancestor(y, z)\nk = choice(2)\n";
+    let mut interp = _run(src, |_| {});
+    let rules = interp.rules();
+    assert_eq!(rules.len(), 1);
+    let (id, head, _) = interp.lookup_rule(rules[0]).unwrap();
+    assert_eq!(interp.lookup_raw_ident(id).unwrap().as_raw_str(), "ancestor");
+    let params: Vec<_> = head.iter().map(|&x| _ident_name(&interp, x)).collect();
+    assert_eq!(params, vec!["x", "z"]);
+    assert_eq!(_body_heads(&interp, rules[0]), vec!["parent", "ancestor"]);
+    // NB: running a rule stm does not run its body in the current scope.
+    assert_eq!(_eval(&mut interp, "y"), "y");
+    // NB: the load of a rule after the choice point is undone upon
+    // backtracking, incl. its rule index entry.
+    interp.eval_line("defrule parent(x, y):\n    edge(x, y)\n").unwrap();
+    let rules2 = interp.rules();
+    assert_eq!(rules2.len(), 2);
+    let x2 = rules2.iter().cloned().find(|&x| x != rules[0]).unwrap();
+    assert_eq!(_body_heads(&interp, x2), vec!["edge"]);
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(interp.rules(), rules);
+    assert!(interp.lookup_rule(x2).is_err());
+  }
+
+  #[test]
+  fn test_splat_rest() {
+    // NB: a list lit is (as yet) always empty, so the lists here are packed
+    // by a rest param.
+    let src = "defproc f(x, *rest):\n    x = rest\nf(l, 1, 2)\nf(a, 1, 2, 3)\nf(b)\nf(c, *l)\nf(d, 0, *l, 9)\n";
+    let mut interp = _run(src, |_| {});
+    for (line, v) in [
+      ("a", "[1, 2, 3]"), ("b", "[]"), ("c", "[1, 2]"), ("d", "[0, 1, 2, 9]"),
+    ] {
+      assert_eq!(_eval(&mut interp, line), v, "line: {}", line);
+    }
+    assert_eq!(_eval(&mut interp, "f()"), "raise: proc expected 1 arguments, got 0");
+    assert_eq!(_eval(&mut interp, "f(e, *1)"), "raise: splat of a non-list value");
+  }
+
+  #[test]
+  fn test_lambda_capture() {
+    let src = "defproc mk(y, g):\n    g = lambda x: y\nmk(10, h)\nz = 5\nk = lambda x: z\nm = lambda x, v: w\nn = lambda x: x\n";
+    let mut interp = _run(src, |_| {});
+    // NB: `h` closes over the local scope of the call to `mk`, which is not
+    // visible at module scope.
+    assert_eq!(_eval(&mut interp, "h(1)"), "10");
+    assert_eq!(_eval(&mut interp, "y"), "y");
+    assert_eq!(_eval(&mut interp, "k(1)"), "5");
+    assert_eq!(_eval(&mut interp, "n(7)"), "7");
+    // NB: the captured `w` is resolved when `m` is applied, after `w` has
+    // been bound.
+    interp.eval_line("w = 3").unwrap();
+    assert_eq!(_eval(&mut interp, "m(1, 0)"), "3");
+    assert_eq!(_eval(&mut interp, "h(1, 2)"), "raise: lambda expected 1 arguments, got 2");
+  }
+
+  #[test]
+  fn test_list_comp_choice() {
+    let src = "defproc pack(x, *r):\n    x = r\npack(l, 1, 2, 3)\ne = [x for x choice in l]\n";
+    let mut interp = _run(src, |_| {});
+    assert_eq!(_eval(&mut interp, "e"), "1");
+    interp.eval_line("c = [x for x in l]").unwrap();
+    assert_eq!(_eval(&mut interp, "c"), "[1, 2, 3]");
+    interp.eval_line("y = 7").unwrap();
+    interp.eval_line("d = [y for x in l]").unwrap();
+    assert_eq!(_eval(&mut interp, "d"), "[7, 7, 7]");
+    interp.eval_line("f = [x for x in l if x = 2]").unwrap();
+    assert_eq!(_eval(&mut interp, "f"), "[2]");
+    assert_eq!(_eval(&mut interp, "g = [x for x in 1]"), "raise: comprehension over a non-list value");
+    // NB: the choice form picks the next item upon backtracking, and then
+    // fails once the items are exhausted.
+    for v in ["2", "3"] {
+      assert!(interp._backtrack().unwrap());
+      assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+      assert_eq!(_eval(&mut interp, "e"), v);
+    }
+    assert!(!interp._backtrack().unwrap());
+  }
+
+  #[test]
+  fn test_in_generate() {
+    let src = "defproc pack(x, *r):\n    x = r\npack(l, 1, 2, 3)\ny in l\n";
+    let mut interp = _run(src, |_| {});
+    assert_eq!(_eval(&mut interp, "y"), "1");
+    // NB: w/ a bound left side, `in` is a membership test.
+    interp.eval_line("if 2 in l:\n    m = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "m"), "1");
+    interp.eval_line("if 5 in l:\n    n = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "n"), "n");
+    assert_eq!(_eval(&mut interp, "z in 1"), "raise: membership in a non-list value");
+    // NB: w/ an unbound left side, `in` generates the items in order, at a
+    // choice point.
+    for v in ["2", "3"] {
+      assert!(interp._backtrack().unwrap());
+      assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+      assert_eq!(_eval(&mut interp, "y"), v);
+    }
+    assert!(!interp._backtrack().unwrap());
+  }
+
+  #[test]
+  fn test_match_fall_through() {
+    let src = "defproc pack(x, *r):\n    x = r\npack(l, 1, 2)\n";
+    let fall_src = "match 2:\n    case 1:\n        a = 1\n    case y:\n        failure()\n    case 2:\n        a = 2\n";
+    let mut interp = _run(src, |_| {});
+    // NB: the failing body of the matching case `y` falls through to the
+    // later case `2`, and the capture of `y` is undone.
+    assert!(matches!(interp.eval_line(fall_src).unwrap(), ReplOutcome::Done));
+    assert_eq!(_eval(&mut interp, "a"), "2");
+    assert_eq!(_eval(&mut interp, "y"), "y");
+    interp.eval_line("match l:\n    case z:\n        b = z\n").unwrap();
+    assert_eq!(_eval(&mut interp, "b"), "[1, 2]");
+    interp.eval_line("match 3:\n    case 4:\n        c = 4\n    case 3:\n        c = 3\n").unwrap();
+    assert_eq!(_eval(&mut interp, "c"), "3");
+    assert!(matches!(interp.eval_line("match 3:\n    case 1:\n        d = 1\n").unwrap(), ReplOutcome::Fail));
+    // NB: w/ `match_commit` set, the match commits to the case `y`.
+    let mut interp = _run(src, |interp| interp.set_match_commit(true));
+    assert!(matches!(interp.eval_line(fall_src).unwrap(), ReplOutcome::Fail));
+    assert_eq!(_eval(&mut interp, "a"), "a");
+  }
+
+  #[test]
+  fn test_class_attrs() {
+    // NB: the attr term `self.x` is the last stm of its method, and of its
+    // class, body.
+    let src = "class Point:\n  defproc __init__(self, x):\n    self.x = x\n  defproc get_x(self, r):\n    r = self.x\np = Point(1)\nq = Point(2)\np.get_x(a)\nq.get_x(b)\n";
+    let mut interp = _run(src, |_| {});
+    for (line, v) in [
+      ("a", "1"), ("b", "2"), ("p.x", "1"), ("q.x", "2"),
+    ] {
+      assert_eq!(_eval(&mut interp, line), v, "line: {}", line);
+    }
+    // NB: the instance attrs are per instance.
+    interp.eval_line("p.y = 3").unwrap();
+    assert_eq!(_eval(&mut interp, "p.y"), "3");
+    assert_eq!(_eval(&mut interp, "q.y"), "_");
+    assert_eq!(_eval(&mut interp, "p.foo(c)"), "raise: Point: no method \"foo\"");
+  }
+
+  #[test]
+  fn test_connectives_backtrack() {
+    let src = "x = 1 or x = 2\n";
+    let mut interp = _run(src, |_| {});
+    assert_eq!(_eval(&mut interp, "x"), "1");
+    interp.eval_line("if x = 1 and x = 2:\n    a = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "a"), "a");
+    interp.eval_line("if x = 5 or x = 1:\n    b = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "b"), "1");
+    interp.eval_line("if not x = 3:\n    c = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "c"), "1");
+    interp.eval_line("if not x = 1:\n    d = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "d"), "d");
+    // NB: a negation never binds.
+    interp.eval_line("if not e = 1:\n    f = 1\n").unwrap();
+    assert_eq!(_eval(&mut interp, "e"), "e");
+    // NB: the `or` backtracks to its rterm, and then fails.
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "2");
+    assert!(!interp._backtrack().unwrap());
+  }
+
+  #[test]
+  fn test_await_resume() {
+    let mut interp = _start("x = await \"q\"\ny = 1\nz = await x\n", |_| {});
+    assert_eq!(interp.interp_().unwrap(), Yield_::Eval);
+    let evals = interp.pending_evals();
+    assert_eq!(evals.len(), 1);
+    assert!(interp.provide_eval_result(evals[0].0, LitVal_::Int(5)).is_ok());
+    // NB: the run resumes after the await, up to the next await (of the
+    // provided value).
+    assert_eq!(interp.interp_().unwrap(), Yield_::Eval);
+    let evals2 = interp.pending_evals();
+    assert_eq!(evals2.len(), 1);
+    assert!(evals2[0].0 > evals[0].0);
+    assert!(interp.provide_eval_result(evals[0].0, LitVal_::Int(6)).is_err());
+    assert!(interp.provide_eval_result(evals2[0].0, LitVal_::Int(6)).is_ok());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert!(interp.pending_evals().is_empty());
+    for (line, v) in [("x", "5"), ("y", "1"), ("z", "6")] {
+      assert_eq!(_eval(&mut interp, line), v, "line: {}", line);
+    }
+  }
+
+  #[test]
+  fn test_step_breakpoints() {
+    let src = "k = choice(2)\nx = k\ny = 1\n";
+    let mut interp = _start(src, |_| {});
+    // NB: the span of each stm, upon entering it.
+    let mut spans = Vec::new();
+    let mut clk = None;
+    loop {
+      let info = interp.step().unwrap();
+      assert!(clk.map(|clk| clk < info.clk).unwrap_or(true));
+      clk = Some(info.clk);
+      if info.knt.first().map(|&(_, name)| name) == Some("InterpStm") {
+        spans.push(info.span.unwrap());
+      }
+      match info.yield_ {
+        None => {
+          assert_eq!(info.knt.last().map(|&(_, name)| name), Some("InterpMod"));
+        }
+        Some(yield_) => {
+          assert_eq!(yield_, Yield_::Quiescent);
+          assert!(info.knt.is_empty());
+          break;
+        }
+      }
+    }
+    assert_eq!(spans.len(), 3);
+    // NB: the stm is entered upon resuming from its breakpoint, w/out
+    // breaking again.
+    let mut interp2 = _start(src, |interp| interp.add_breakpoint_span(spans[2]));
+    assert_eq!(interp2.interp_().unwrap(), Yield_::Break);
+    assert_eq!(interp2.interp_().unwrap(), Yield_::Quiescent);
+    let mut interp2 = _start(src, |interp| {
+      interp.add_breakpoint_span(spans[2]);
+      interp.clear_breakpoints();
+    });
+    assert_eq!(interp2.interp_().unwrap(), Yield_::Quiescent);
+    // NB: the stm `x = k` is re-entered upon backtracking.
+    interp.add_breakpoint_span(spans[1]);
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Break);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "1");
+  }
+
+  #[test]
+  fn test_watch_ident() {
+    use crate::interp::debug::{WatchChange_};
+    let src = "x = y\ny = 1\nz = 2\n";
+    let mut interp = _start(src, |interp| interp.watch_ident("x"));
+    assert_eq!(interp.interp_().unwrap(), Yield_::Break);
+    let hits = interp.take_watch_hits();
+    assert_eq!(hits.len(), 1);
+    assert!(matches!(hits[0].change, WatchChange_::Unify{..}));
+    // NB: `y` is in the class of the watched `x`, but `z` is not.
+    assert_eq!(interp.interp_().unwrap(), Yield_::Break);
+    let hits2 = interp.take_watch_hits();
+    assert_eq!(hits2.len(), 1);
+    assert!(matches!(hits2[0].change, WatchChange_::Unify{..}));
+    assert_eq!(hits2[0].watch, hits[0].watch);
+    assert!(hits2[0].clk > hits[0].clk);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert!(interp.take_watch_hits().is_empty());
+    let mut interp = _start(src, |interp| {
+      interp.watch_ident("x");
+      interp.clear_watches();
+    });
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert!(interp.take_watch_hits().is_empty());
+  }
+
+  #[test]
+  fn test_query_iter() {
+    fn _sols(interp: &mut FastInterp, q: &str) -> Vec<Vec<(String, String)>> {
+      interp.query(q).unwrap().map(|bindings| {
+        bindings.unwrap().into_iter().map(|(id, v)| (id.as_raw_str().to_string(), v)).collect()
+      }).collect()
+    }
+    let src = "defproc pack(x, *r):\n    x = r\npack(l, 1, 2, 3)\nk = choice(2)\n";
+    let mut interp = _run(src, |_| {});
+    let sols = _sols(&mut interp, "y in l");
+    assert_eq!(sols, ["1", "2", "3"].iter().map(|&v| vec![("y".to_string(), v.to_string())]).collect::<Vec<_>>());
+    assert_eq!(_sols(&mut interp, "2 in l"), vec![Vec::new()]);
+    assert!(_sols(&mut interp, "5 in l").is_empty());
+    // NB: the effects of a query are rolled back upon drop, even before
+    // the iterator is exhausted.
+    {
+      let mut iter = interp.query("y in l").unwrap();
+      assert!(iter.next().unwrap().is_ok());
+    }
+    assert_eq!(_eval(&mut interp, "y"), "y");
+    let mut iter = interp.query("div(1, 0)").unwrap();
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+    drop(iter);
+    assert!(interp.query("a = 1\nb = 2").is_err());
+    // NB: the query does not backtrack into the choice point of `k`.
+    assert_eq!(interp.query("k").unwrap().count(), 1);
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "k"), "1");
+  }
+
+  #[test]
+  fn test_solutions() {
+    let src = "defproc pack(x, *r):\n    x = r\npack(l, 1, 2, 3)\nk = 0\n";
+    let mut interp = _run(src, |_| {});
+    let sols: Vec<_> = interp.solutions("y in l", &["y", "k", "q"], None).unwrap()
+      .iter().map(|v| v.to_string()).collect();
+    assert_eq!(sols, vec![
+      r#"{"k":0,"q":null,"y":1}"#,
+      r#"{"k":0,"q":null,"y":2}"#,
+      r#"{"k":0,"q":null,"y":3}"#,
+    ]);
+    assert_eq!(interp.solutions("y in l", &["y"], Some(2)).unwrap().len(), 2);
+    assert!(interp.solutions("5 in l", &["y"], None).unwrap().is_empty());
+    assert!(interp.solutions("div(1, 0)", &["y"], None).is_err());
+    assert_eq!(_eval(&mut interp, "y"), "y");
+  }
+
+  #[test]
+  fn test_view_at() {
+    use serde_json::{Value as JsonValue};
+    // NB: the idents of the terms in a flat view.
+    fn _flat_idents(flat: &JsonValue) -> Vec<String> {
+      flat["env"]["term"].as_array().unwrap().iter()
+        .filter_map(|e| e["flat_val"]["IdentTerm"]["raw_id"].as_str())
+        .map(|id| id.to_string())
+        .collect()
+    }
+    let src = "x = 10\nk = choice(2)\ny = 20\n";
+    let mut interp = _start(src, |_| {});
+    // NB: the flattened state as of the clock after each step.
+    let mut flats: Vec<(LClk, JsonValue)> = Vec::new();
+    loop {
+      let info = interp.step().unwrap();
+      flats.push((info.clk, serde_json::to_value(&interp.flatten_()).unwrap()));
+      if info.yield_.is_some() {
+        assert_eq!(info.yield_, Some(Yield_::Quiescent));
+        break;
+      }
+    }
+    let now = serde_json::to_value(&interp.flatten_()).unwrap();
+    for &(clk, ref flat) in flats.iter() {
+      let view = serde_json::to_value(&interp.view_at(clk).unwrap()).unwrap();
+      assert_eq!(&view, flat, "clk: {:?}", clk);
+    }
+    assert_eq!(serde_json::to_value(&interp.flatten_()).unwrap(), now);
+    assert_eq!(_flat_idents(&now), vec!["x", "k", "y"]);
+    // NB: `y` is bound in the last stm, so some earlier view has `x`, but
+    // not `y`.
+    assert!(flats.iter().any(|&(_, ref flat)| _flat_idents(flat) == vec!["x", "k"]));
+    // NB: the original interpreter is undisturbed.
+    assert_eq!(_eval(&mut interp, "x"), "10");
+    assert_eq!(_eval(&mut interp, "y"), "20");
+    let last_clk = flats.last().unwrap().0;
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    // NB: the clocks that were backtracked over cannot be viewed.
+    assert!(interp.view_at(last_clk).is_err());
+    assert!(interp.view_at(flats[0].0).is_ok());
+  }
+
+  #[test]
+  fn test_call_goal() {
+    use serde_json::{Value as JsonValue};
+    fn _goal(interp: &mut FastInterp, src: &str) -> TermCodeNum {
+      let mut parser = crate::parse::Parser::new(src);
+      let raw_mod = parser.mod_().unwrap();
+      match &*raw_mod.body[0] {
+        &crate::parse::Stm::Just(_, ref raw_term) => interp._load_raw_term(raw_term).unwrap(),
+        _ => panic!()
+      }
+    }
+    let src = "defproc pack(x, *r):\n    x = r\npack(l, 1, 2, 3)\nk = choice(2)\n";
+    let mut interp = _run(src, |_| {});
+    let mut results = Vec::new();
+    let goal = _goal(&mut interp, "y in l");
+    assert!(interp.call_goal(goal, &mut results).unwrap().is_ok());
+    // NB: the match results of `in` have no value.
+    assert_eq!(results, vec![JsonValue::Null; 3]);
+    let mut results = Vec::new();
+    let goal = _goal(&mut interp, "k");
+    assert!(interp.call_goal(goal, &mut results).unwrap().is_ok());
+    assert_eq!(results, vec![JsonValue::from(0)]);
+    let goal = _goal(&mut interp, "5 in l");
+    assert_eq!(interp._run_scoped_goal(goal).unwrap(), Ok(false));
+    let goal = _goal(&mut interp, "y in l");
+    assert_eq!(interp._run_scoped_goal(goal).unwrap(), Ok(true));
+    assert_eq!(_eval(&mut interp, "y"), "y");
+    // NB: the goal does not backtrack into the choice point of `k`.
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "k"), "1");
+    let mut results = Vec::new();
+    let goal = _goal(&mut interp, "div(1, 0)");
+    assert_eq!(interp.call_goal(goal, &mut results).unwrap(), Err(Yield_::Raise));
+    assert!(results.is_empty());
+  }
+
+  #[test]
+  fn test_split_explore() {
+    use crate::interp::split::{SplitAggregate, split_explore};
+    let src = "k = choice(3)\nj = choice(2)\nif k = 1:\n    failure()\nelse:\n    pass\n";
+    // NB: the solutions are in sequential order, whichever worker
+    // found them.
+    for workers in 1 ..= 3 {
+      let sols = split_explore(src, workers, SplitAggregate::All).unwrap();
+      let pvs: Vec<_> = sols.iter().map(|sol| sol.pv.clone()).collect();
+      assert_eq!(pvs, vec![vec![0, 0], vec![0, 1], vec![2, 0], vec![2, 1]]);
+      assert!(sols.iter().all(|sol| sol.worker == sol.pv[0] % workers));
+    }
+    let sols = split_explore(src, 2, SplitAggregate::First).unwrap();
+    assert_eq!(sols.len(), 1);
+    assert_eq!(sols[0].pv, vec![0, 0]);
+    // NB: w/out a choice point, only the first worker reports.
+    let sols = split_explore("x = 1\n", 2, SplitAggregate::All).unwrap();
+    assert_eq!(sols.len(), 1);
+    assert_eq!(sols[0].worker, 0);
+    // NB: a raise is not a dead end, but an error.
+    assert!(split_explore("k = choice(2)\nx = div(1, 0)\n", 2, SplitAggregate::All).is_err());
+  }
+
+  #[test]
+  fn test_tokenize() {
+    // NB: the spans are of the unquoted str, and each kind is the str
+    // lit of the token variant name.
+    let mut interp = _run("t = tokenize(\"x = (1)\")\n", |_| {});
+    let toks = [("Indent", 0, 0), ("Ident", 0, 1), ("Space", 1, 2), ("Equal", 2, 3), ("Space", 3, 4), ("LParen", 4, 5), ("IntLit", 5, 6), ("RParen", 6, 7)];
+    let toks: Vec<_> = toks.iter().map(|&(kind, start, end)| format!("[{:?}, {}, {}]", kind, start, end)).collect();
+    assert_eq!(_eval(&mut interp, "t"), format!("[{}]", toks.join(", ")));
+    assert_eq!(_eval(&mut interp, "u = tokenize(1)"), "raise: tokenize: arg 1 expects a str");
+  }
+
+  #[test]
+  fn test_quote_eval() {
+    // NB: the quoted stms are only run upon unquoting (in the current
+    // env), and the result is that of the last stm.
+    let mut interp = _run("q = ```quote\ny = 1\n2\n```\nr = ```quote\nz = 3\nfailure()\n```\n", |_| {});
+    assert_eq!(_eval(&mut interp, "y"), "y");
+    assert_eq!(_eval(&mut interp, "x = eval(q)"), "Done");
+    assert_eq!(_eval(&mut interp, "x"), "2");
+    assert_eq!(_eval(&mut interp, "y"), "1");
+    assert_eq!(_eval(&mut interp, "eval(r)"), "Fail");
+    assert_eq!(_eval(&mut interp, "z"), "z");
+  }
+
+  #[test]
+  fn test_eval_code() {
+    // NB: a term code SNum (here, bound to an ident from outside the
+    // program) is evaluated in place, in the current env; a failure w/ no
+    // choice point left halts.
+    fn _bind_code(interp: &mut FastInterp, name: &str, src: &str) {
+      let mut parser = crate::parse::Parser::new(src);
+      let raw_mod = parser.mod_().unwrap();
+      let code = match &*raw_mod.body[0] {
+        &crate::parse::Stm::Just(_, ref raw_term) => interp._load_raw_term(raw_term).unwrap(),
+        _ => panic!()
+      };
+      let clk = interp._get_clock();
+      let id = interp._load_raw_ident(&name.into()).unwrap();
+      let scope = interp._bind_target(id).unwrap();
+      interp._bind_ident(clk, scope, id, code.into()).unwrap();
+    }
+    for (src, y) in [
+      ("z = 1\nw = eval(c)\ny = 7\nx = eval(d)\nx = 1\n", Yield_::Quiescent),
+      ("z = 1\nw = eval(c)\ny = 8\n", Yield_::Halt),
+      ("z = 1\nx = eval(d)\nx = 2\n", Yield_::Halt),
+      ("z = 1\neval(e)\n", Yield_::Halt),
+    ] {
+      let mut interp = _start(src, |_| {});
+      _bind_code(&mut interp, "c", "y = 7");
+      _bind_code(&mut interp, "d", "z");
+      _bind_code(&mut interp, "e", "failure()");
+      assert_eq!(interp.interp_().unwrap(), y, "src: {:?}", src);
+    }
+  }
+
+  #[test]
+  fn test_query_tuples() {
+    // NB: the tuple index entries of an undone branch are undone w/ it.
+    fn _len(interp: &mut FastInterp, name: &str) -> usize {
+      let clk = interp._get_clock();
+      let id = interp._load_raw_ident(&name.into()).unwrap();
+      let x = interp.resolve_ident(id).unwrap().1.unwrap();
+      let x = interp.find(clk, x).unwrap();
+      for (_, v) in interp.get_vals(clk, x).unwrap() {
+        if let Val_::Lit(LitVal_::List{buf}) = v {
+          return buf.len();
+        }
+      }
+      panic!("no list val: {}", name)
+    }
+    let src = "edge(1, 2)\nedge(2, 3)\nk = choice(2)\nif k = 0:\n    edge(1, 4)\nelse:\n    pass\nr = query(edge, 1, w)\ns = query(edge, w, 3)\nt = query(edge, 3, w)\n";
+    let mut interp = _run(src, |_| {});
+    assert_eq!(_len(&mut interp, "r"), 2);
+    assert_eq!(_len(&mut interp, "s"), 1);
+    assert_eq!(_len(&mut interp, "t"), 0);
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_len(&mut interp, "r"), 1);
+  }
+
+  #[test]
+  fn test_stream_feed() {
+    // NB: a stm is queued once the start of the next top-level stm is
+    // fed (or upon `finish`).
+    let mut interp = _interp(|_| {});
+    assert_eq!(interp.feed("x = 1\ny = ").unwrap(), 0);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Interrupt);
+    assert_eq!(interp.feed("2\nif x = 1:\n").unwrap(), 2);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Interrupt);
+    assert_eq!(_eval(&mut interp, "y"), "2");
+    // NB: the lines of a quote are not top-level stms.
+    assert_eq!(interp.feed("    z = 3\nelse:\n    z = 4\nq = ```quote\nw = 5\n").unwrap(), 1);
+    assert_eq!(interp.feed("```\nv = eval(q)\n").unwrap(), 1);
+    assert_eq!(interp.finish().unwrap(), 1);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "z"), "3");
+    assert_eq!(_eval(&mut interp, "w"), "5");
+    assert!(interp.feed("u = 6\n").is_err());
+  }
+
+  #[test]
+  fn test_render_value() {
+    // NB: a cyclic value is elided where it recurs, and an ident is only
+    // rendered for a class w/out any other term.
+    let src = "defproc g(x):\n    pass\ns = (1, (2, 3))\nt = (s,)\nc = f(c)\na = \"ab\"\nu = v\nk = 0\n";
+    let mut interp = _run(src, |_| {});
+    for &(line, v) in [
+      ("s", "(1, (2, 3))"), ("t", "((1, (2, 3)),)"), ("c", "(f, ...)"), ("a", "\"ab\""),
+      ("g", "<proc>"), ("print", "<function>"), ("u", "u"), ("None", "None"),
+    ].iter() {
+      assert_eq!(_eval(&mut interp, line), v);
+    }
+    // NB: as JSON, a str is unquoted, and a tuple is an array.
+    let sols: Vec<_> = interp.solutions("k", &["a", "s"], None).unwrap()
+      .iter().map(|v| v.to_string()).collect();
+    assert_eq!(sols, vec![r#"{"a":"ab","s":[1,[2,3]]}"#]);
+  }
+
+  #[test]
+  fn test_load_bytes() {
+    use crate::src::{EncodingSource, ReplacementPolicy, TranscodeConfig};
+    // NB: a byte order mark takes precedence over the detector.
+    let mut interp = _interp(|_| {});
+    interp.load_bytes(b"\xef\xbb\xbfx = 1\n").unwrap();
+    assert_eq!(interp.src_encoding().unwrap().source, EncodingSource::Bom);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "1");
+    // NB: malformed UTF-8 is transcoded per the guessed encoding.
+    let mut interp = _interp(|_| {});
+    interp.load_bytes(b"x = \"caf\xe9 cr\xe8me br\xfbl\xe9e\"\n").unwrap();
+    let enc = interp.src_encoding().unwrap();
+    assert_eq!((enc.name, enc.source, enc.replaced), ("windows-1252", EncodingSource::Detected, false));
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "\"caf\u{e9} cr\u{e8}me br\u{fb}l\u{e9}e\"");
+    // NB: the encoding is appended to a check raised while loading.
+    let mut interp = _interp(|_| {});
+    let e = interp.load_bytes(b"\xef\xbb\xbfx = (\n").unwrap_err();
+    assert!(format!("{:?}", e).contains("(source encoding: UTF-8)"), "check: {:?}", e);
+    let cfg = TranscodeConfig{replacement: ReplacementPolicy::Strict, .. TranscodeConfig::default()};
+    let mut interp = _interp(|_| {});
+    let e = interp.load_bytes_with(b"\xef\xbb\xbfx = \"\xff\"\n", &cfg).unwrap_err();
+    assert!(format!("{:?}", e).contains("transcode error"), "check: {:?}", e);
+  }
+
+  #[test]
+  fn test_load_file() {
+    use std::fs::{remove_file, write};
+    let path = std::env::temp_dir().join(format!("pythia-test-load-file-{}.py", std::process::id()));
+    write(&path, "x = 1\ny = (x, 2)\n").unwrap();
+    let mut interp = _interp(|_| {});
+    interp.load_file(&path).unwrap();
+    assert_eq!(interp.src_path(), Some(path.as_path()));
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "y"), "(1, 2)");
+    // NB: a parse error is attributed to its path:line:col.
+    write(&path, "x = 1\ny = )\n").unwrap();
+    let mut interp = _interp(|_| {});
+    let e = interp.load_file(&path).unwrap_err();
+    let loc = format!("{}:2:", path.display());
+    assert!(format!("{:?}", e).contains(&loc), "check: {:?}", e);
+    let _ = remove_file(&path);
+    let e = interp.load_file(&path).unwrap_err();
+    assert!(format!("{:?}", e).contains("source file error"), "check: {:?}", e);
+  }
+
+  #[test]
+  fn test_load_project() {
+    use std::fs::{remove_file, write};
+    let path_a = std::env::temp_dir().join(format!("pythia-test-load-project-{}-a.py", std::process::id()));
+    let path_b = std::env::temp_dir().join(format!("pythia-test-load-project-{}-b.py", std::process::id()));
+    write(&path_a, "defproc f(x, y):\n    y = (x, x)\n").unwrap();
+    write(&path_b, "a = 1\nf(a, b)\n").unwrap();
+    let mut interp = _interp(|_| {});
+    interp.load_project(&[&path_a, &path_b]).unwrap();
+    assert_eq!(interp.src_path(), None);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "b"), "(1, 1)");
+    // NB: the files are separated by one newline in the combined source.
+    let srcmap = interp.srcmap();
+    assert_eq!(srcmap.files().len(), 2);
+    let base_b = srcmap.files()[1].base;
+    assert_eq!(base_b, srcmap.files()[0].range().end + 1);
+    assert_eq!(srcmap.line_col(base_b + 6), Some((1, 2, 1)));
+    assert_eq!(srcmap.span_loc(&(base_b + 8 .. base_b + 9)), Some(format!("{}:2:3", path_b.display())));
+    // NB: a parse error in a later file is attributed to that file.
+    write(&path_b, "a = 1\nf(a,\n").unwrap();
+    let mut interp = _interp(|_| {});
+    let e = interp.load_project(&[&path_a, &path_b]).unwrap_err();
+    let loc = format!("{}:", path_b.display());
+    assert!(format!("{:?}", e).contains(&loc), "check: {:?}", e);
+    let _ = remove_file(&path_a);
+    let _ = remove_file(&path_b);
+  }
+
+  #[test]
+  fn test_random_seed() {
+    let src = "defproc pack(x, *r):\n    x = r\npack(l, 1, 2, 3)\nx = random_choice(l)\ny = random_int(10, 20)\n";
+    let draw = |conf: &dyn Fn(&mut FastInterp)| {
+      let mut interp = _run(src, |interp| conf(interp));
+      (_eval(&mut interp, "x"), _eval(&mut interp, "y"))
+    };
+    // NB: w/ the same seed, a run draws the same values.
+    let (x, y) = draw(&|interp| interp.set_seed(7));
+    assert_eq!((x.clone(), y.clone()), draw(&|interp| interp.set_seed(7)));
+    assert!(["1", "2", "3"].contains(&x.as_str()), "x: {}", x);
+    assert!((10 ..= 20).contains(&y.parse::<i64>().unwrap()), "y: {}", y);
+    // NB: the replayed draws take precedence over the seed.
+    let mut rng = FastRng_::new(7);
+    let draws = vec![rng._next_u64(), rng._next_u64()];
+    assert_eq!((x, y), draw(&|interp| {
+      interp.set_seed(8);
+      interp.replay_draws(draws.clone());
+    }));
+    // NB: an empty range fails.
+    let mut interp = _start("x = random_int(2, 1)\n", |_| {});
+    assert_eq!(interp.interp_().unwrap(), Yield_::Halt);
+  }
+
+  #[test]
+  fn test_wchoice_order() {
+    let src = "x = wchoice(1, 5, 3)\n";
+    let mut interp = _run(src, |_| {});
+    assert_eq!(_eval(&mut interp, "x"), "1");
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "2");
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "0");
+    let prefs = interp.choice_prefs().clone();
+    assert_eq!(prefs.values().collect::<Vec<_>>(), vec![&vec![1, 1, 1]]);
+    // NB: the learned preferences scale the weights of a later run.
+    let mut prefs = prefs;
+    for p in prefs.values_mut() {
+      p[0] = 9;
+    }
+    let mut interp = _run(src, |interp| { interp.set_choice_prefs(prefs); });
+    assert_eq!(_eval(&mut interp, "x"), "0");
+    // NB: w/ sampled order, a run is reproduced from its seed.
+    let sample = |seed| {
+      let mut interp = _run(src, |interp| {
+        interp.set_weight_order(WeightOrder::Sampled);
+        interp.set_seed(seed);
+      });
+      let mut xs = vec![_eval(&mut interp, "x")];
+      while interp._backtrack().unwrap() {
+        assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+        xs.push(_eval(&mut interp, "x"));
+      }
+      xs
+    };
+    let xs = sample(3);
+    assert_eq!(xs, sample(3));
+    let mut sorted = xs.clone();
+    sorted.sort();
+    assert_eq!(sorted, vec!["0", "1", "2"]);
+  }
+
+  #[test]
+  fn test_explain() {
+    let src = "a = 1\nb = (a, 2)\nc = b\n";
+    let mut interp = _run(src, |interp| interp.set_explain(true));
+    let clk = interp._get_clock();
+    let id = interp._load_raw_ident(&"c".into()).unwrap();
+    let x = interp.resolve_ident(id).unwrap().1.unwrap();
+    let x = interp.find(clk, x).unwrap();
+    let tree = interp.explain(x).unwrap();
+    assert_eq!((tree.term.as_str(), tree.via), ("(1, 2)", "class"));
+    let vias: Vec<_> = tree.premises.iter().map(|p| (p.via, p.loc.as_deref())).collect();
+    assert_eq!(vias, vec![("tuple", Some("2:1")), ("unify", Some("2:1")), ("unify", Some("3:1"))]);
+    // NB: the premises of a tuple are the classes of its children.
+    let tup: Vec<_> = tree.premises[0].premises.iter().map(|p| (p.term.as_str(), p.via)).collect();
+    assert_eq!(tup, vec![("1", "class"), ("2", "class")]);
+    assert!(tree.to_json().starts_with(r#"{"term":"(1, 2)","via":"class""#));
+    let interp = _run(src, |_| {});
+    assert!(interp.explain(x).is_err());
+  }
+
+  #[test]
+  fn test_cons_tuple() {
+    // NB: two data tuples w/ children in the same classes are the same
+    // term, and so `s` and `t` are in the same class.
+    let src = "a = 1\nb = a\ns = (a, 2)\nt = (b, 2)\nu = (2, a)\n";
+    let mut interp = _run(src, |_| {});
+    let clk = interp._get_clock();
+    let mut xs = Vec::new();
+    for name in ["s", "t", "u"] {
+      let id = interp._load_raw_ident(&name.into()).unwrap();
+      let x = interp.resolve_ident(id).unwrap().1.unwrap();
+      xs.push(interp.find(clk, x).unwrap());
+    }
+    assert!(interp.same_cls(clk, xs[0], xs[1]).unwrap());
+    assert!(!interp.same_cls(clk, xs[0], xs[2]).unwrap());
+    let x = interp._cons_tuple(clk, TupleBuf_::from_slice(&[xs[0], xs[2]])).unwrap();
+    assert_eq!(interp._cons_tuple(clk, TupleBuf_::from_slice(&[xs[1], xs[2]])).unwrap(), x);
+    assert_ne!(interp._cons_tuple(clk, TupleBuf_::from_slice(&[xs[2], xs[0]])).unwrap(), x);
+  }
+
+  #[test]
+  fn test_select_branch() {
+    let src = "x = choice(3)\ny = choice(2)\n";
+    let mut interp = _run(src, |_| {});
+    assert_eq!(interp.current_depth(), 2);
+    let cps = interp.choice_points();
+    let info: Vec<_> = cps.iter().map(|cp| (cp.rank, cp.branch, cp.limit, cp.loc.as_deref())).collect();
+    assert_eq!(info, vec![(0, 0, Some(3), Some("1:5")), (0, 0, Some(2), Some("2:5"))]);
+    // NB: selecting a branch of the outer choice point pops the inner one.
+    interp.select_branch(cps[0].clk, 2).unwrap();
+    assert_eq!(interp.current_depth(), 1);
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!((_eval(&mut interp, "x"), _eval(&mut interp, "y")), ("2".into(), "0".into()));
+    assert_eq!(interp.choice_points()[0].rank, 2);
+    // NB: an explored rank may be selected again.
+    interp.select_branch(cps[0].clk, 0).unwrap();
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "0");
+    assert!(interp.select_branch(cps[0].clk, 3).is_err());
+  }
+
+  #[test]
+  fn test_mcts_play_best() {
+    let mut interp = _start("x = choice(3)\ny = choice(2)\n", |_| {});
+    // NB: the reward is 1 only for the rollout that takes `x = 2, y = 1`.
+    let reward = |sim: &FastInterp, y: &Yield_| {
+      let branches: Vec<_> = sim.choice_points().iter().map(|cp| cp.branch).collect();
+      if y == &Yield_::Quiescent && branches == vec![2, 1] { 1.0 } else { 0.0 }
+    };
+    let stats = interp.interp_mcts_(30, reward).unwrap();
+    assert_eq!((stats.iter_ct, stats.best_reward), (30, Some(1.0)));
+    assert_eq!(stats.best_path, vec![2, 1]);
+    // NB: the tree persists between calls from the same state.
+    let stats = interp.interp_mcts_(10, reward).unwrap();
+    assert_eq!(stats.iter_ct, 40);
+    assert_eq!(interp.current_depth(), 0);
+    assert_eq!(interp.mcts_play_best().unwrap(), Yield_::Quiescent);
+    assert_eq!((_eval(&mut interp, "x"), _eval(&mut interp, "y")), ("2".into(), "1".into()));
+  }
+
+  #[test]
+  fn test_export_dot() {
+    use crate::interp::dot::{DotConfig};
+    let mut interp = _run("x = choice(3)\ny = (x, 1)\n", |_| {});
+    let mut buf = Vec::new();
+    interp.export_dot(&mut buf).unwrap();
+    let dot = String::from_utf8(buf).unwrap();
+    assert!(dot.starts_with("digraph interp {\n"), "dot: {}", dot);
+    assert!(dot.ends_with("}\n"), "dot: {}", dot);
+    assert!(dot.contains(" rank=0 branch=0 limit=3\\n1:5\"];\n"), "dot: {}", dot);
+    assert!(dot.contains(" = (0, 1)\\n"), "dot: {}", dot);
+    for cluster in ["cluster_knt", "cluster_trace", "cluster_eclasses"] {
+      assert!(dot.contains(cluster), "dot: {}", dot);
+    }
+    let cfg = DotConfig{knt: false, trace: false, .. DotConfig::default()};
+    let mut buf = Vec::new();
+    interp.export_dot_with(&mut buf, cfg).unwrap();
+    let dot = String::from_utf8(buf).unwrap();
+    assert!(!dot.contains("cluster_knt") && !dot.contains("cluster_trace"), "dot: {}", dot);
+    assert!(dot.contains("cluster_eclasses"), "dot: {}", dot);
+    assert_eq!(_eval(&mut interp, "y"), "(0, 1)");
+  }
+
+  #[test]
+  fn test_clkinval_compact() {
+    let mut interp = _run("x = choice(3)\ny = (x, 1)\n", |_| {});
+    assert_eq!(interp.clkinval_stats().ranges, 0);
+    // NB: each backtrack to the same choice point subsumes the range
+    // undone by the previous one.
+    for _ in 0 .. 2 {
+      assert!(interp._backtrack().unwrap());
+      assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+      let stats = interp.clkinval_stats();
+      assert_eq!(stats.ranges, 1);
+      assert!(stats.clocks > 0);
+    }
+    assert_eq!(_eval(&mut interp, "y"), "(2, 1)");
+    // NB: w/ the choice point live, its undone range is kept.
+    assert_eq!(interp.compact_clkinval(), 0);
+    assert!(!interp._backtrack().unwrap());
+    assert_eq!(interp.current_depth(), 0);
+    assert_eq!(interp.compact_clkinval(), 1);
+    let stats = interp.clkinval_stats();
+    assert_eq!((stats.ranges, stats.clocks, stats.drop_ct), (0, 0, 1));
+  }
+
+  #[test]
+  fn test_parse_cache() {
+    use crate::parse::cache::{ParseCache};
+    use std::fs::{remove_dir_all, write};
+    let dir = std::env::temp_dir().join(format!("pythia-test-parse-cache-{}", std::process::id()));
+    let _ = remove_dir_all(&dir);
+    let (src_a, src_b) = ("x = 1\n", "x = 2\n");
+    let cache = ParseCache::new(&dir);
+    let mut interp = _run(src_b, |interp| { interp.set_parse_cache(Some(cache.clone())); });
+    assert_eq!(_eval(&mut interp, "x"), "2");
+    let mod_b = cache.get(src_b).unwrap();
+    assert!(cache.get(src_a).is_none());
+    // NB: a cache hit is used in place of parsing the source.
+    cache.put(src_a, &mod_b).unwrap();
+    let mut interp = _run(src_a, |interp| { interp.set_parse_cache(Some(cache.clone())); });
+    assert_eq!(_eval(&mut interp, "x"), "2");
+    let mut interp = _run(src_a, |_| {});
+    assert_eq!(_eval(&mut interp, "x"), "1");
+    // NB: an unreadable entry is a cache miss, and is overwritten.
+    write(cache._path(&ParseCache::key(src_a)), "garbage").unwrap();
+    let mut interp = _run(src_a, |interp| { interp.set_parse_cache(Some(cache.clone())); });
+    assert_eq!(_eval(&mut interp, "x"), "1");
+    assert!(cache.get(src_a).is_some());
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_render_error() {
+    let mut interp = _start("x = 1\ny = div(x, 0)\n", |_| {});
+    assert_eq!(interp.interp_().unwrap(), Yield_::Raise);
+    assert_eq!(interp.render_error().unwrap(), "2:1: error: div: division by zero\n  | y = div(x, 0)\n  | ^^^^^^^^^^^^^");
+    // NB: a raise in a proc body is located in the body.
+    let mut interp = _start("defproc f(a):\n    b = div(a, 0)\nf(1)\n", |_| {});
+    assert_eq!(interp.interp_().unwrap(), Yield_::Raise);
+    assert_eq!(interp.render_error().unwrap(), "2:5: error: div: division by zero\n  |     b = div(a, 0)\n  |     ^^^^^^^^^^^^^");
+    let interp = _run("x = 1\n", |_| {});
+    assert_eq!(interp.render_error(), None);
+  }
+
+  #[test]
+  fn test_unifier_iter() {
+    let mut interp = _run("a = b\nb = c\nd = 1\n", |_| {});
+    let clk = interp.step().unwrap().clk;
+    let mut xs = Vec::new();
+    for name in ["a", "b", "c", "d"] {
+      let id = interp._load_raw_ident(&name.into()).unwrap();
+      xs.push(interp.resolve_ident(id).unwrap().1.unwrap());
+    }
+    let unifier = interp.unifier();
+    let stats = interp.unifier_stats();
+    assert_eq!(stats.classes, unifier.class_count());
+    assert_eq!(unifier.iter_roots().count(), stats.classes);
+    // NB: every key is either a root or linked to one, and so is at
+    // some depth of the union tree.
+    assert_eq!(stats.depth_hist[0], stats.classes as u64);
+    assert_eq!(stats.depth_hist.iter().sum::<u64>(), (stats.classes + stats.links) as u64);
+    let member_ct: usize = unifier.iter_roots().map(|root| unifier.iter_class(root).count()).sum();
+    assert_eq!(member_ct, stats.classes + stats.links);
+    let root = interp.find(clk, xs[0]).unwrap()._cls();
+    let members: Vec<_> = unifier.iter_class(root).collect();
+    assert_eq!(members[0], root);
+    for &x in xs[ .. 3].iter() {
+      assert!(members.contains(&x));
+    }
+    assert!(!members.contains(&xs[3]));
+    assert!(interp.unifier_stats().cache_hit_rate().is_some());
+  }
+
+  #[cfg(feature = "flat_import")]
+  #[test]
+  fn test_flat_import() {
+    use crate::interp::flat::{FlatDiff};
+    let interp = _run("x = 1\ny = (x, 2)\n", |_| {});
+    let flat = interp.flatten_();
+    let imported = FlatInterp::from_json(&flat.vectorize()).unwrap();
+    assert_eq!(imported.clk(), flat.clk());
+    assert!(imported.diff(&flat).is_empty());
+    let tables = imported.tables();
+    assert!(!tables.code.is_empty() && !tables.ident.is_empty());
+    // NB: an extra stm is an extra entry of the code table, at least.
+    let other = _run("x = 1\ny = (x, 2)\nz = 3\n", |_| {}).flatten_();
+    let diffs = imported.diff(&other);
+    assert!(diffs.iter().any(|d| matches!(d, &FlatDiff::Clk{..})), "diffs: {:?}", diffs);
+    assert!(diffs.iter().any(|d| matches!(d, &FlatDiff::Extra{table: "code", ..})), "diffs: {:?}", diffs);
+    assert!(other.diff(&imported).iter().any(|d| matches!(d, &FlatDiff::Missing{table: "code", ..})));
+  }
+
+  #[test]
+  fn test_state_hash() {
+    let src = "x = choice(2)\ny = (x, 1)\n";
+    let mut interp = _run(src, |_| {});
+    let h0 = interp.state_hash().unwrap();
+    assert_eq!(_run(src, |_| {}).state_hash().unwrap(), h0);
+    assert!(interp._backtrack().unwrap());
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_ne!(interp.state_hash().unwrap(), h0);
+    // NB: the same values w/ different idents hash differently.
+    assert_ne!(_run("x = choice(2)\nz = (x, 1)\n", |_| {}).state_hash().unwrap(), h0);
+  }
+
+  #[test]
+  fn test_prelude_registry() {
+    use crate::interp::prelude::{FailureFun};
+    use crate::interp::registry::{BuiltinArity};
+    let mut interp = FastInterp::default();
+    interp.prelude_registry_mut().register_function("reject", BuiltinArity::exact(0), "reject(): fails", FailureFun::default()).unwrap();
+    assert!(interp.prelude_registry_mut().register_function("reject", BuiltinArity::exact(0), "", FailureFun::default()).is_err());
+    assert!(interp.registered_builtins().is_empty());
+    interp.pre_init().unwrap();
+    let infos = interp.registered_builtins();
+    let info = |name: &str| infos.iter().find(|info| info.name == name).unwrap();
+    assert!(info("reject").ext);
+    assert!(!info("choice").ext);
+    assert_eq!(info("reject").arity, BuiltinArity::exact(0));
+    assert_eq!(infos.last().unwrap().name, "reject");
+    interp.cold_start("x = choice(2)\nif x = 0:\n    reject()\n").unwrap();
+    assert_eq!(interp.interp_().unwrap(), Yield_::Quiescent);
+    assert_eq!(_eval(&mut interp, "x"), "1");
+  }
+
+  #[test]
+  fn test_builtin_sig_check() {
+    let mut interp = _run("pass\n", |_| {});
+    for &(line, v) in [
+      ("x = random_int(1)", "raise: random_int expects 2 args, got 1"),
+      ("x = print(1, 2)", "raise: print expects 0..1 args, got 2"),
+      ("x = tokenize(1)", "raise: tokenize: arg 1 expects a str"),
+      ("x = add(1, \"a\")", "raise: add: arg 2 expects an int"),
+      ("x = add(1, 2)", "Done"),
+      ("x", "3"),
+    ].iter() {
+      assert_eq!(_eval(&mut interp, line), v);
+    }
+  }
+
+  #[test]
+  fn test_verify_transitions() {
+    let interp = FastInterp::default();
+    let holes = interp.verify_transitions();
+    assert!(holes.is_empty(), "unhandled transitions: {:?}", holes);
+    for &kind in MemKntKind_::ALL.iter() {
+      for &port in KNT_PORTS.iter() {
+        let tr = _knt_transition(port, kind);
+        assert_ne!(tr, KntTransition_::Unhandled,
+            "unhandled transition: port={:?} knt={}", port, kind.name());
+      }
+    }
+  }
+}
@@ -4,16 +4,17 @@ use crate::algo::blake2s::{Blake2s};
 //use crate::algo::hex::{HexFormat};
 use crate::algo::json::{JsonFormat, JsonValue};
 use crate::clock::{Timestamp};
+use crate::interp::{UnifierStats};
 use crate::oracle::{ApproxOracleItem, ApproxOracleKeyItem};
 
 use byteorder::{LittleEndian as LE, ReadBytesExt, WriteBytesExt};
 //use once_cell::sync::{Lazy};
 use serde::{Serialize, Deserialize};
 
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Read, Write, Seek, SeekFrom};
+use std::fs::{File, OpenOptions, create_dir_all, metadata, rename};
+use std::io::{BufRead, BufReader, Read, Write, Seek, SeekFrom, Error as IoError};
 use std::net::{TcpStream};
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 use std::str::{FromStr, from_utf8};
 
 pub const HASH_SIZE: usize = 32;
@@ -535,6 +536,146 @@ impl JournalExt for DevelJournal_ {
   }
 }
 
+pub const RUN_JOURNAL_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum RunStatus {
+  #[serde(rename = "yield")]
+  Yield(String),
+  #[serde(rename = "check")]
+  Check(String),
+}
+
+// The durable metadata of one run of `FastInterp::interp_`. The yields
+// are counted by kind (e.g. "Fail"), since a run may fail and backtrack
+// arbitrarily many times.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RunRecord {
+  pub t_start:  Timestamp,
+  pub t_end:    Timestamp,
+  pub mod_hash: String,
+  pub yields:   BTreeMap<String, u64>,
+  pub status:   RunStatus,
+  pub step_ct:  u64,
+  pub backtrack_ct: u64,
+  // NB: the RNG seed and the raw draws of the run, which may be replayed
+  // (see `FastInterp::replay_draws`); absent in older records.
+  #[serde(default)]
+  pub seed:     u64,
+  #[serde(default)]
+  pub draws:    Vec<u64>,
+  // NB: the max continuation depth of the run, as sampled (see
+  // `InterpRunStats_::max_depth`); absent in older records.
+  #[serde(default)]
+  pub max_depth: usize,
+  // NB: the unifier stats at the end of the run (see
+  // `FastInterp::unifier_stats`); absent in older records.
+  #[serde(default)]
+  pub unifier:  Option<UnifierStats>,
+}
+
+// An append-only journal of `RunRecord`s in the directory `dir`. The
+// records are appended as lines to `runs.jsonl`; once that file reaches
+// `max_bytes`, it is rotated to `runs.<N>.jsonl`, for the next unused
+// N = 1, 2, ..., before the next append.
+pub struct RunJournal {
+  dir:  PathBuf,
+  max_bytes: u64,
+}
+
+fn _run_log_path(dir: &Path) -> PathBuf {
+  let mut path = dir.to_owned();
+  path.push("runs.jsonl");
+  path
+}
+
+fn _run_log_rotated_path(dir: &Path, n: usize) -> PathBuf {
+  let mut path = dir.to_owned();
+  path.push(format!("runs.{}.jsonl", n));
+  path
+}
+
+impl RunJournal {
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<RunJournal, IoError> {
+    let dir = path.as_ref().to_owned();
+    create_dir_all(&dir)?;
+    Ok(RunJournal{
+      dir,
+      max_bytes: RUN_JOURNAL_MAX_BYTES,
+    })
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  pub fn set_max_bytes(&mut self, max_bytes: u64) {
+    self.max_bytes = max_bytes;
+  }
+
+  fn _rotate(&mut self) -> Result<(), IoError> {
+    let mut n = 1;
+    while _run_log_rotated_path(&self.dir, n).exists() {
+      n += 1;
+    }
+    rename(_run_log_path(&self.dir), _run_log_rotated_path(&self.dir, n))
+  }
+
+  pub fn append(&mut self, rec: &RunRecord) -> Result<(), IoError> {
+    let path = _run_log_path(&self.dir);
+    match metadata(&path) {
+      Ok(m) if m.len() >= self.max_bytes => {
+        self._rotate()?;
+      }
+      _ => {}
+    }
+    let s = serde_json::to_string(rec).unwrap();
+    let mut file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&path)?;
+    writeln!(&mut file, "{}", s)?;
+    file.flush()
+  }
+}
+
+// Reads back all the `RunRecord`s in the journal directory `path`, in
+// append order (i.e. the rotated files in order, then `runs.jsonl`).
+pub fn read_runs<P: AsRef<Path>>(path: P) -> Result<Vec<RunRecord>, IoError> {
+  let dir = path.as_ref();
+  let mut paths = Vec::new();
+  let mut n = 1;
+  loop {
+    let path = _run_log_rotated_path(dir, n);
+    if !path.exists() {
+      break;
+    }
+    paths.push(path);
+    n += 1;
+  }
+  let path = _run_log_path(dir);
+  if path.exists() {
+    paths.push(path);
+  }
+  let mut recs = Vec::new();
+  for path in paths.iter() {
+    let file = File::open(path)?;
+    for line in BufReader::new(file).lines() {
+      let line = line?;
+      if line.is_empty() {
+        continue;
+      }
+      // NB: skip a malformed line (e.g. the partial last line of a run
+      // that crashed mid-append).
+      match serde_json::from_str::<RunRecord>(&line) {
+        Err(_) => {}
+        Ok(rec) => recs.push(rec)
+      }
+    }
+  }
+  Ok(recs)
+}
+
 /*pub struct JournalInterface {
   stream: TcpStream,
 }
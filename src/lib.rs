@@ -23,8 +23,12 @@ extern crate time;
 pub mod _extlib;
 pub mod aikido;
 pub mod algo;
+#[cfg(feature = "bench")]
+pub mod bench;
 pub mod build;
 pub mod clock;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
 pub mod interp;
 pub mod interp_test;
 pub mod journal;
@@ -32,9 +36,11 @@ pub mod journal;
 pub mod oracle;
 pub mod panick;
 pub mod parse;
+pub mod prelude;
 pub mod smp;
 pub mod src;
 pub mod sys;
 pub mod tap;
 pub mod test_data;
+pub mod testing;
 pub mod util;
@@ -1,5 +1,6 @@
 use crate::_extlib::{_EXTLIB};
-use crate::algo::{BTreeMap};
+use crate::aikido::{ContentHash};
+use crate::algo::{BTreeMap, BTreeSet};
 use crate::algo::str::{SafeStr};
 use crate::clock::{Timestamp};
 use crate::journal::{JournalExt, JournalEntryExt, JournalEntrySort_};
@@ -9,8 +10,18 @@ use pyo3::{IntoPyObjectExt};
 use serde::{Serialize, Deserialize};
 use serde::de::{Deserializer};
 
+use std::collections::{VecDeque};
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind as IoErrorKind, Write};
+use std::mem::{take};
+use std::path::{Path};
 use std::str::{FromStr};
+use std::sync::mpsc::{
+  Receiver, Sender, SyncSender, TryRecvError, TrySendError,
+  channel, sync_channel,
+};
+use std::thread::{JoinHandle, spawn};
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Default, Debug)]
 //#[derive(Clone, Copy, Serialize, Deserialize, Default, Debug)]
@@ -309,10 +320,15 @@ impl ApproxOracleInterface {
   }
 
   pub fn put(&self, item: ApproxOracleRequest) -> () {
+    self.try_put(item).unwrap();
+  }
+
+  pub fn try_put(&self, item: ApproxOracleRequest) -> PyResult<()> {
     Python::with_gil(|py| -> PyResult<_> {
       self.this
-          .call_method1(py, "put", (item._into_py().into_pyobject(py)?,))
-    }).unwrap();
+          .call_method1(py, "put", (item._into_py().into_pyobject(py)?,))?;
+      Ok(())
+    })
   }
 
   pub fn poll(&self) -> Option<ApproxOracleItem> {
@@ -327,6 +343,28 @@ impl ApproxOracleInterface {
     }).unwrap()
   }
 
+  // NB: unlike `poll`, a worker failure (incl. an item that does not
+  // extract) is returned, rather than panicking.
+  pub fn poll_timeout(&self, timeout: f64) -> PyResult<Option<ApproxOracleItem>> {
+    Python::with_gil(|py| -> PyResult<_> {
+      self.this
+          .call_method1(py, "poll", (timeout,))?
+          .into_bound_py_any(py)?
+          .extract()
+    })
+  }
+
+  // Cancels a submitted request w/ the given query, if one has not yet
+  // started. Returns true if a request was cancelled, in which case its
+  // item will not be polled.
+  pub fn cancel(&self, query: &[ApproxOracleQueryTurn]) -> PyResult<bool> {
+    Python::with_gil(|py| -> PyResult<_> {
+      self.this
+          .call_method1(py, "cancel", (query.to_vec().into_pyobject(py)?,))?
+          .extract::<_>(py)
+    })
+  }
+
   pub fn poll_test(&self) -> Option<ApproxOracleTestItem> {
     Python::with_gil(|py| -> PyResult<_> {
       let item = self.this
@@ -340,6 +378,536 @@ impl ApproxOracleInterface {
   }
 }
 
+// The correlation ID of a request submitted to an `OracleBridge`.
+pub type OracleReqId = u64;
+
+pub const ORACLE_BRIDGE_POLL_TIMEOUT: f64 = 0.05;
+
+#[derive(Debug)]
+pub struct OracleBridgeRequest {
+  pub id:   OracleReqId,
+  pub req:  ApproxOracleRequest,
+}
+
+#[derive(Debug)]
+pub enum OracleBridgeResponse {
+  // The item of the request w/ the given ID, or else the error upon it.
+  Item(OracleReqId, Result<ApproxOracleItem, OracleBridgeError>),
+  // An error that is not tied to a request.
+  Error(OracleBridgeError),
+}
+
+#[derive(Debug)]
+pub enum OracleBridgeError {
+  // The request queue is at capacity; the request is handed back.
+  Full(ApproxOracleRequest),
+  // The bridge thread has exited.
+  Disconnected,
+  // The worker failed (e.g. a python exception), w/ the message.
+  Worker(String),
+  // The worker answered a query that was not in flight.
+  Uncorrelated(Vec<ApproxOracleQueryTurn>),
+}
+
+// A bridge between the interpreter and an `ApproxOracleWorker`.
+//
+// Requests are sent over a bounded MPSC queue to a bridge thread, which
+// owns the `ApproxOracleInterface`; responses are sent back over an
+// unbounded queue, tagged w/ the correlation ID of the request.
+// Submitting never blocks, so it may be done from within
+// `Function::__apply__`; completed responses are collected by `poll`,
+// and then claimed by ID w/ `take`.
+//
+// Requests that are no longer wanted (e.g. those of an abandoned branch)
+// may be withdrawn w/ `cancel`; cancellations are sent over a separate,
+// unbounded queue, so that they never fail on a full request queue.
+//
+// A worker failure is sent back over the response queue as well: either
+// as the response of the failed request, or else (if it is not tied to
+// a request) it is kept until claimed w/ `take_errors`. Upon a failed
+// poll, the bridge thread fails every request in flight, and exits.
+pub struct OracleBridge {
+  req_tx:   Option<SyncSender<OracleBridgeRequest>>,
+  cancel_tx: Sender<OracleReqId>,
+  res_rx:   Receiver<OracleBridgeResponse>,
+  next_id:  OracleReqId,
+  pending:  BTreeSet<OracleReqId>,
+  done:     BTreeMap<OracleReqId, Result<ApproxOracleItem, OracleBridgeError>>,
+  errors:   Vec<OracleBridgeError>,
+  handle:   Option<JoinHandle<()>>,
+}
+
+impl Drop for OracleBridge {
+  fn drop(&mut self) {
+    // NB: closing the request queue shuts down the bridge thread,
+    // after it drains the in-flight requests.
+    self.req_tx = None;
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+  }
+}
+
+// The far ends of the queues of a detached `OracleBridge`.
+pub struct OracleBridgeEnds {
+  pub req_rx:   Receiver<OracleBridgeRequest>,
+  pub cancel_rx: Receiver<OracleReqId>,
+  pub res_tx:   Sender<OracleBridgeResponse>,
+}
+
+impl OracleBridge {
+  pub fn spawn(worker: ApproxOracleWorker, capacity: usize) -> OracleBridge {
+    let (mut bridge, ends) = OracleBridge::detached(capacity);
+    let handle = spawn(move || {
+      let iface = ApproxOracleInterface::init_with_worker(worker);
+      _oracle_bridge_loop(iface, ends.req_rx, ends.cancel_rx, ends.res_tx)
+    });
+    bridge.handle = Some(handle);
+    bridge
+  }
+
+  // A bridge w/out a bridge thread; the caller serves the far ends of
+  // its queues in place of an `ApproxOracleWorker` (e.g. in tests).
+  pub fn detached(capacity: usize) -> (OracleBridge, OracleBridgeEnds) {
+    let (req_tx, req_rx) = sync_channel(capacity);
+    let (cancel_tx, cancel_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let bridge = OracleBridge{
+      req_tx:   Some(req_tx),
+      cancel_tx,
+      res_rx,
+      next_id:  0,
+      pending:  BTreeSet::new(),
+      done:     BTreeMap::new(),
+      errors:   Vec::new(),
+      handle:   None,
+    };
+    (bridge, OracleBridgeEnds{req_rx, cancel_rx, res_tx})
+  }
+
+  pub fn submit(&mut self, req: ApproxOracleRequest) -> Result<OracleReqId, OracleBridgeError> {
+    let req_tx = match self.req_tx.as_ref() {
+      None => return Err(OracleBridgeError::Disconnected),
+      Some(tx) => tx
+    };
+    let id = self.next_id;
+    match req_tx.try_send(OracleBridgeRequest{id, req}) {
+      Err(TrySendError::Full(r)) => {
+        return Err(OracleBridgeError::Full(r.req));
+      }
+      Err(TrySendError::Disconnected(_)) => {
+        return Err(OracleBridgeError::Disconnected);
+      }
+      Ok(_) => {}
+    }
+    self.next_id += 1;
+    self.pending.insert(id);
+    Ok(id)
+  }
+
+  // Collects the completed responses, w/out blocking; returns the number
+  // of newly completed requests.
+  pub fn poll(&mut self) -> usize {
+    let mut n = 0;
+    loop {
+      match self.res_rx.try_recv() {
+        Ok(OracleBridgeResponse::Item(id, item)) => {
+          if self.pending.remove(&id) {
+            self.done.insert(id, item);
+            n += 1;
+          }
+        }
+        Ok(OracleBridgeResponse::Error(e)) => {
+          self.errors.push(e);
+        }
+        Err(_) => break
+      }
+    }
+    n
+  }
+
+  pub fn is_pending(&self, id: OracleReqId) -> bool {
+    self.pending.contains(&id)
+  }
+
+  pub fn is_done(&self, id: OracleReqId) -> bool {
+    self.done.contains_key(&id)
+  }
+
+  pub fn pending_len(&self) -> usize {
+    self.pending.len()
+  }
+
+  pub fn take(&mut self, id: OracleReqId) -> Option<Result<ApproxOracleItem, OracleBridgeError>> {
+    self.done.remove(&id)
+  }
+
+  // Claims the errors that are not tied to a request (see `poll`).
+  pub fn take_errors(&mut self) -> Vec<OracleBridgeError> {
+    take(&mut self.errors)
+  }
+
+  // Withdraws a request; its response (if any) is discarded. Returns
+  // true if the request was still pending, in which case the bridge
+  // thread is asked to cancel it w/ the worker.
+  pub fn cancel(&mut self, id: OracleReqId) -> bool {
+    if !self.pending.remove(&id) {
+      let _ = self.done.remove(&id);
+      return false;
+    }
+    // NB: if the bridge thread has exited, there is nothing to cancel.
+    let _ = self.cancel_tx.send(id);
+    true
+  }
+}
+
+fn _oracle_bridge_loop(iface: ApproxOracleInterface, req_rx: Receiver<OracleBridgeRequest>, cancel_rx: Receiver<OracleReqId>, res_tx: Sender<OracleBridgeResponse>) {
+  // NB: the python side does not round-trip an integer key, so responses
+  // are correlated by their query; identical queries are answered in
+  // submission order.
+  let mut inflight: BTreeMap<Vec<ApproxOracleQueryTurn>, VecDeque<OracleReqId>> = BTreeMap::new();
+  let mut inflight_len = 0;
+  // NB: a cancelled request that the worker has already started is still
+  // answered; `orphan` counts the responses to discard, per query.
+  let mut orphan: BTreeMap<Vec<ApproxOracleQueryTurn>, usize> = BTreeMap::new();
+  // NB: a cancellation may be received before its request, since they
+  // are sent over different queues; `cancel` holds the cancellations of
+  // requests that are not (yet) in flight.
+  let mut cancel: BTreeSet<OracleReqId> = BTreeSet::new();
+  let mut recv_ub: OracleReqId = 0;
+  let mut closed = false;
+  loop {
+    let mut queued = Vec::new();
+    loop {
+      let r = if inflight_len == 0 && !closed {
+        // NB: nothing to poll for, so block on the next request.
+        match req_rx.recv() {
+          Ok(r) => r,
+          Err(_) => {
+            closed = true;
+            break;
+          }
+        }
+      } else {
+        match req_rx.try_recv() {
+          Ok(r) => r,
+          Err(TryRecvError::Empty) => break,
+          Err(TryRecvError::Disconnected) => {
+            closed = true;
+            break;
+          }
+        }
+      };
+      recv_ub = recv_ub.max(r.id + 1);
+      queued.push(r);
+      if inflight_len == 0 {
+        // NB: do not block again w/ a request in hand.
+        break;
+      }
+    }
+    while let Ok(id) = cancel_rx.try_recv() {
+      cancel.insert(id);
+    }
+    for r in queued.into_iter() {
+      if cancel.remove(&r.id) {
+        continue;
+      }
+      let query = r.req.query.clone();
+      if let Err(e) = iface.try_put(r.req) {
+        let e = OracleBridgeError::Worker(format!("put: {}", e));
+        if res_tx.send(OracleBridgeResponse::Item(r.id, Err(e))).is_err() {
+          return;
+        }
+        continue;
+      }
+      inflight.entry(query).or_default().push_back(r.id);
+      inflight_len += 1;
+    }
+    if !cancel.is_empty() {
+      let mut cancel_query = Vec::new();
+      for (query, ids) in inflight.iter_mut() {
+        let n = ids.len();
+        ids.retain(|id| !cancel.remove(id));
+        for _ in ids.len() .. n {
+          cancel_query.push(query.clone());
+        }
+      }
+      for query in cancel_query.into_iter() {
+        if inflight.get(&query).map_or(false, |ids| ids.is_empty()) {
+          inflight.remove(&query);
+        }
+        match iface.cancel(&query) {
+          Ok(true) => {
+            inflight_len -= 1;
+          }
+          Ok(false) => {
+            *orphan.entry(query).or_default() += 1;
+          }
+          Err(e) => {
+            // NB: the request may still be answered, so it is orphaned.
+            *orphan.entry(query).or_default() += 1;
+            let e = OracleBridgeError::Worker(format!("cancel: {}", e));
+            if res_tx.send(OracleBridgeResponse::Error(e)).is_err() {
+              return;
+            }
+          }
+        }
+      }
+      // NB: a remaining cancellation of an already-received request is
+      // of one that was already answered, so it is stale.
+      cancel.retain(|&id| id >= recv_ub);
+    }
+    if inflight_len == 0 {
+      if closed {
+        return;
+      }
+      continue;
+    }
+    let item = match iface.poll_timeout(ORACLE_BRIDGE_POLL_TIMEOUT) {
+      Err(e) => {
+        // NB: the in-flight requests can no longer be answered, so they
+        // are failed; later submits see a disconnected bridge.
+        let msg = format!("poll: {}", e);
+        for (_, ids) in take(&mut inflight).into_iter() {
+          for id in ids.into_iter() {
+            let _ = res_tx.send(OracleBridgeResponse::Item(id, Err(OracleBridgeError::Worker(msg.clone()))));
+          }
+        }
+        return;
+      }
+      Ok(None) => continue,
+      Ok(Some(item)) => item
+    };
+    inflight_len -= 1;
+    let id = match inflight.get_mut(&item.query).and_then(|ids| ids.pop_front()) {
+      None => {
+        match orphan.get_mut(&item.query) {
+          Some(n) if *n > 0 => {
+            *n -= 1;
+            if *n == 0 {
+              orphan.remove(&item.query);
+            }
+          }
+          _ => {
+            let e = OracleBridgeError::Uncorrelated(item.query);
+            if res_tx.send(OracleBridgeResponse::Error(e)).is_err() {
+              return;
+            }
+          }
+        }
+        continue;
+      }
+      Some(id) => id
+    };
+    if inflight.get(&item.query).map_or(false, |ids| ids.is_empty()) {
+      inflight.remove(&item.query);
+    }
+    if res_tx.send(OracleBridgeResponse::Item(id, Ok(item))).is_err() {
+      return;
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OracleCacheStatus {
+  // The result is cached.
+  Done,
+  // An identical request is batched, but not yet submitted.
+  Batched,
+  // An identical request is submitted, but not yet completed.
+  InFlight,
+}
+
+#[derive(Serialize, Deserialize)]
+struct _OracleCacheRecord {
+  hash: ContentHash,
+  item: ApproxOracleItem,
+}
+
+// A cache of oracle results, keyed by the content hash of the (canonical,
+// flattened) term being evaluated; see `FastInterp::pending_eval_keys`.
+//
+// Identical requests are deduplicated: a request is only batched if its
+// key is neither cached nor outstanding. Batched requests are submitted
+// to an `OracleBridge` together w/ `flush`, and completed results are
+// collected w/ `collect`.
+//
+// A request that fails is dropped, so that its key may be requested
+// again; its error is kept until claimed w/ `take_failed`.
+//
+// A cache opened on a path is persistent: every result is appended to
+// the cache file as a JSON line, and the file is replayed upon `open`.
+#[derive(Default)]
+pub struct OracleCache {
+  done:     BTreeMap<ContentHash, ApproxOracleItem>,
+  batch:    Vec<(ContentHash, ApproxOracleRequest)>,
+  inflight: BTreeMap<OracleReqId, ContentHash>,
+  inflight_keys: BTreeSet<ContentHash>,
+  failed:   Vec<(ContentHash, OracleBridgeError)>,
+  log_file: Option<File>,
+}
+
+impl OracleCache {
+  pub fn new() -> OracleCache {
+    OracleCache::default()
+  }
+
+  // NB: a torn last record (e.g. from a crash) is truncated; any other
+  // invalid record is an error.
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<OracleCache, IoError> {
+    let path = path.as_ref();
+    let mut cache = OracleCache::default();
+    let mut valid_len = None;
+    if path.exists() {
+      let mut reader = BufReader::new(File::open(path)?);
+      let mut line = String::new();
+      let mut off = 0;
+      let mut torn = None;
+      loop {
+        line.clear();
+        let n = reader.read_line(&mut line)?;
+        if n == 0 {
+          break;
+        }
+        if let Some(e) = torn.take() {
+          return Err(IoError::new(IoErrorKind::InvalidData, format!("oracle cache: invalid record: {}", e)));
+        }
+        let rec = line.trim_end();
+        if !rec.is_empty() {
+          match serde_json::from_str::<_OracleCacheRecord>(rec) {
+            Err(e) => {
+              torn = Some(e);
+              valid_len = Some(off);
+            }
+            Ok(rec) => {
+              cache.done.insert(rec.hash, rec.item);
+            }
+          }
+        }
+        off += n as u64;
+      }
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    if let Some(len) = valid_len {
+      file.set_len(len)?;
+    }
+    cache.log_file = Some(file);
+    Ok(cache)
+  }
+
+  pub fn len(&self) -> usize {
+    self.done.len()
+  }
+
+  pub fn get(&self, key: &ContentHash) -> Option<&ApproxOracleItem> {
+    self.done.get(key)
+  }
+
+  pub fn status(&self, key: &ContentHash) -> Option<OracleCacheStatus> {
+    if self.done.contains_key(key) {
+      Some(OracleCacheStatus::Done)
+    } else if self.inflight_keys.contains(key) {
+      Some(OracleCacheStatus::InFlight)
+    } else if self.batch.iter().any(|&(ref k, _)| k == key) {
+      Some(OracleCacheStatus::Batched)
+    } else {
+      None
+    }
+  }
+
+  // Batches the request, unless an identical one (by key) is already
+  // cached or outstanding.
+  pub fn request(&mut self, key: ContentHash, req: ApproxOracleRequest) -> OracleCacheStatus {
+    match self.status(&key) {
+      Some(status) => status,
+      None => {
+        self.batch.push((key, req));
+        OracleCacheStatus::Batched
+      }
+    }
+  }
+
+  pub fn batch_len(&self) -> usize {
+    self.batch.len()
+  }
+
+  pub fn inflight_len(&self) -> usize {
+    self.inflight.len()
+  }
+
+  pub fn take_failed(&mut self) -> Vec<(ContentHash, OracleBridgeError)> {
+    take(&mut self.failed)
+  }
+
+  // Submits the batched requests, in batch order, until the bridge
+  // queue is full; returns the number of submitted requests. Upon any
+  // other submit error, the failed request is dropped, and the rest stay
+  // batched.
+  pub fn flush(&mut self, bridge: &mut OracleBridge) -> Result<usize, OracleBridgeError> {
+    let mut n = 0;
+    let mut batch = self.batch.drain(..).collect::<VecDeque<_>>();
+    let mut ret = Ok(());
+    while let Some((key, req)) = batch.pop_front() {
+      match bridge.submit(req) {
+        Ok(id) => {
+          self.inflight.insert(id, key.clone());
+          self.inflight_keys.insert(key);
+          n += 1;
+        }
+        Err(OracleBridgeError::Full(req)) => {
+          batch.push_front((key, req));
+          break;
+        }
+        Err(e) => {
+          ret = Err(e);
+          break;
+        }
+      }
+    }
+    self.batch.extend(batch);
+    ret.map(|_| n)
+  }
+
+  // Collects the completed results from the bridge into the cache;
+  // returns the number of newly cached results. The failed requests are
+  // moved to `take_failed`.
+  pub fn collect(&mut self, bridge: &mut OracleBridge) -> Result<usize, IoError> {
+    bridge.poll();
+    let ids: Vec<_> = self.inflight.keys()
+      .filter(|&&id| bridge.is_done(id))
+      .map(|&id| id)
+      .collect();
+    let mut n = 0;
+    for id in ids.into_iter() {
+      let (key, item) = match (self.inflight.remove(&id), bridge.take(id)) {
+        (Some(key), Some(item)) => (key, item),
+        _ => continue
+      };
+      self.inflight_keys.remove(&key);
+      match item {
+        Err(e) => {
+          self.failed.push((key, e));
+        }
+        Ok(item) => {
+          self.insert(key, item)?;
+          n += 1;
+        }
+      }
+    }
+    Ok(n)
+  }
+
+  pub fn insert(&mut self, key: ContentHash, item: ApproxOracleItem) -> Result<(), IoError> {
+    if let Some(file) = self.log_file.as_mut() {
+      let rec = _OracleCacheRecord{hash: key.clone(), item: item.clone()};
+      writeln!(file, "{}", serde_json::to_string(&rec).unwrap())?;
+      file.flush()?;
+    }
+    self.done.insert(key, item);
+    Ok(())
+  }
+}
+
 /*pub struct ApproxOracleIndex {
   iface: ApproxOracleInterface,
   // FIXME: probably want a better data structure.
@@ -1,22 +1,30 @@
 // TODO: temporarily disabled lint for debugging.
 #![allow(unused_variables)]
 
-use crate::algo::{SmolStr};
+use crate::algo::{BTreeMap, SmolStr};
 use crate::algo::cell::{RefCell};
-use crate::algo::str::{SafeStr, StrParserConfig, StrParser};
+use crate::algo::str::{SafeStr, StrParserConfig, StrParser, SyntaxError as StrSyntaxError};
 use crate::panick::{Loc, loc};
-use crate::tap::{TAPOutput, _debugln};
+use crate::tap::{LogWriter, TAPOutput, _debugln};
 
 use bitflags::{bitflags, bitflags_match};
 use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
 
 //use std::cell::{RefCell};
+use std::cell::{Cell};
 use std::fmt::{Debug, Formatter, Result as FmtResult};
-use std::io::{Write};
 use std::mem::{replace};
 use std::ops::{Range};
 use std::panic::{Location};
 
+pub mod cache;
+pub mod intern;
+pub mod visit;
+
+use self::intern::{SharedInterner, Sym};
+use self::visit::{Rewriter, SpanRebase};
+
 pub type Span = Range<usize>;
 
 pub trait Hull<Rhs> {
@@ -79,22 +87,30 @@ impl<T> From<RegexMapBuilder<T>> for RegexMap<T> {
 }
 
 impl<T> RegexMap<T> {
+  // Returns the longest match at `start`; a tie goes to the pattern that
+  // was pushed first. (So, the push order only matters for patterns that
+  // match the same text, e.g. `^Para` and `^Par`.)
   pub fn match_at(&self, text: &str, start: usize) -> Option<(Span, T)> {
     let haystack = text.get(start .. ).unwrap();
-    let pat_idx = match self.set.matches_at(haystack, 0).into_iter().next() {
-      None => return None,
-      Some(pat_idx) => pat_idx
-    };
-    if self.pats.borrow()[pat_idx].is_none() {
-      let pat_str = &self.set.patterns()[pat_idx];
-      self.pats.borrow_mut()[pat_idx] = Regex::new(pat_str).unwrap().into();
+    let mut best: Option<(usize, Range<usize>)> = None;
+    for pat_idx in self.set.matches_at(haystack, 0).into_iter() {
+      if self.pats.borrow()[pat_idx].is_none() {
+        let pat_str = &self.set.patterns()[pat_idx];
+        self.pats.borrow_mut()[pat_idx] = Regex::new(pat_str).unwrap().into();
+      }
+      let mat = self.pats.borrow()[pat_idx].as_ref().unwrap()
+                .find_at(haystack, 0).unwrap();
+      match &best {
+        Some((_, best_span)) if best_span.end >= mat.end() => {}
+        _ => {
+          best = Some((pat_idx, mat.range()));
+        }
+      }
     }
-    let mat = self.pats.borrow()[pat_idx].as_ref().unwrap()
-              .find_at(haystack, 0).unwrap();
-    let mut span = mat.range();
+    let (pat_idx, mut span) = best?;
+    let val = (self.funs[pat_idx])(haystack.get(span.clone()).unwrap());
     span.start += start;
     span.end += start;
-    let val = (self.funs[pat_idx])(mat.as_str());
     Some((span, val))
   }
 }
@@ -138,6 +154,11 @@ pub enum Token {
   EqEq,
   Equal,
   SlashEq,
+  BangEq,
+  LtEq,
+  Lt,
+  GtEq,
+  Gt,
   Star,
   StarStar,
   TTTickUnquote,
@@ -163,6 +184,7 @@ pub enum Token {
   Par,
   And,
   As,
+  Assert,
   Async,
   Await,
   Break,
@@ -184,6 +206,7 @@ pub enum Token {
   //Eval,
   Except,
   //Exec,
+  Expect,
   Finally,
   For,
   Forall,
@@ -214,15 +237,21 @@ pub enum Token {
   Yield,
   IntLit(SafeStr),
   AtomLit(SafeStr, ()),
-  PlaceIdent(SafeStr),
-  Ident(SafeStr),
-  DotIdent(SafeStr),
+  // NB: the ident tokens are interned (see `Tokenizer::resolve`); the
+  // sym of a dotident excludes the leading '.'.
+  PlaceIdent(Sym),
+  Ident(Sym),
+  DotIdent(Sym),
   // NB: deprecated syntax.
   /*ColonIdent(SafeStr),*/
   //_Utf8Error(Box<[u8]>),
+  // NB: a malformed str lit (see `Tokenizer::_str_error`).
+  _Error(SafeStr, StrParseErrorKind),
   _Eof,
 }
 
+pub type StrParseErrorKind = StrSyntaxError;
+
 #[derive(Clone, Debug)]
 pub struct SpanToken {
   pub span: Span,
@@ -235,6 +264,16 @@ impl From<(Span, Token)> for SpanToken {
   }
 }
 
+// Source text that the parser skips over, but that the formatter
+// preserves; keyed by offset in `Tokenizer::trivia`.
+#[derive(Clone, Debug)]
+pub enum Trivia {
+  Comment(SafeStr),
+  // NB: a trailing comment follows a non-trivia token on the same line.
+  TrailingComment(SafeStr),
+  Blank,
+}
+
 bitflags! {
   #[derive(Clone, Copy, PartialEq, Eq)]
   pub struct TokenizerFlag_: u8 {
@@ -245,6 +284,72 @@ bitflags! {
   }
 }
 
+// The keyword table: a word (i.e. a full ident) that is a keyword is
+// tokenized as the keyword, rather than as an ident (see
+// `Tokenizer::new`). A keyword is never a prefix match, so e.g. `input`
+// and `order` are idents, not `in` or `or` followed by an ident.
+pub fn keyword(s: &str) -> Option<Token> {
+  Some(match s {
+    "None" => Token::None,
+    "True" => Token::True,
+    "False" => Token::False,
+    "Unk" => Token::Unk,
+    "Para" => Token::Par,
+    "Par" => Token::Par,
+    "and" => Token::And,
+    "async" => Token::Async,
+    "as" => Token::As,
+    "assert" => Token::Assert,
+    "await" => Token::Await,
+    "break" => Token::Break,
+    "cases" => Token::Cases,
+    "case" => Token::Case,
+    //"choices" => Token::Choices,
+    //"choice" => Token::Choice,
+    "class" => Token::Class,
+    "continue" => Token::Continue,
+    "defmatch" => Token::Defmatch,
+    "defproc" => Token::Defproc,
+    "defrule" => Token::Defrule,
+    "def" => Token::Def,
+    "del" => Token::Del,
+    "elif" => Token::Elif,
+    "else" => Token::Else,
+    //"enum" => Token::Enum,
+    //"eval" => Token::Eval,
+    "except" => Token::Except,
+    //"exec" => Token::Exec,
+    "expect" => Token::Expect,
+    "finally" => Token::Finally,
+    "for" => Token::For,
+    "fresh" => Token::Fresh,
+    "from" => Token::From,
+    "global" => Token::Global,
+    "if" => Token::If,
+    "import" => Token::Import,
+    "in" => Token::In,
+    "is" => Token::Is,
+    "lambda" => Token::Lambda,
+    "let" => Token::Let,
+    "match" => Token::Match,
+    "nonlocal" => Token::Nonlocal,
+    "not" => Token::Not,
+    "of" => Token::Of,
+    "or" => Token::Or,
+    "pass" => Token::Pass,
+    "quote" => Token::Quote,
+    "raise" => Token::Raise,
+    "return" => Token::Return,
+    "rule" => Token::Rule,
+    "try" => Token::Try,
+    "unquote" => Token::Unquote,
+    "while" => Token::While,
+    "with" => Token::With,
+    "yield" => Token::Yield,
+    _ => return None
+  })
+}
+
 pub struct Tokenizer<S> {
   imap: RegexMap<Token>,
   map:  RegexMap<Token>,
@@ -252,6 +357,12 @@ pub struct Tokenizer<S> {
   pos:  usize,
   ind:  RawIndent,
   flag: TokenizerFlag_,
+  // NB: set in python compat mode (see `ParserMode`).
+  compat: bool,
+  trivia: BTreeMap<usize, Trivia>,
+  // NB: the first malformed str, if any (see `Token::_Error`).
+  err:  Option<(Span, StrParseErrorKind)>,
+  syms: SharedInterner,
   //writer:   RefCell<Box<dyn Write>>,
   //verbose:  i8,
   tap:  TAPOutput,
@@ -259,6 +370,11 @@ pub struct Tokenizer<S> {
 
 impl<S> Tokenizer<S> {
   pub fn new(buf: S) -> Tokenizer<S> {
+    Tokenizer::with_interner(buf, SharedInterner::default())
+  }
+
+  // A tokenizer that interns its ident tokens in `syms`.
+  pub fn with_interner(buf: S, syms: SharedInterner) -> Tokenizer<S> {
     let mut map = RegexMapBuilder::new();
     map.push(r"^[ \t]+", |_| Token::Space);
     //map.push(r"^\n", |_| Token::NL);
@@ -296,6 +412,11 @@ impl<S> Tokenizer<S> {
     map.push(r"^==", |_| Token::EqEq);
     map.push(r"^=",  |_| Token::Equal);
     map.push(r"^/=", |_| Token::SlashEq);
+    map.push(r"^!=", |_| Token::BangEq);
+    map.push(r"^<=", |_| Token::LtEq);
+    map.push(r"^<",  |_| Token::Lt);
+    map.push(r"^>=", |_| Token::GtEq);
+    map.push(r"^>",  |_| Token::Gt);
     map.push(r"^\*\*", |_| Token::StarStar);
     map.push(r"^\*", |_| Token::Star);
     map.push(r"^```unquote", |_| Token::TTTickUnquote);
@@ -314,67 +435,15 @@ impl<S> Tokenizer<S> {
     map.push(r"^\]", |_| Token::RBrack);
     map.push(r"^\{", |_| Token::LCurly);
     map.push(r"^\}", |_| Token::RCurly);
-    map.push(r"^None",  |_| Token::None);
-    map.push(r"^True",  |_| Token::True);
-    map.push(r"^False", |_| Token::False);
-    map.push(r"^Unk",   |_| Token::Unk);
-    map.push(r"^Para",  |_| Token::Par);
-    map.push(r"^Par",   |_| Token::Par);
-    map.push(r"^and", |_| Token::And);
-    map.push(r"^async", |_| Token::Async);
-    map.push(r"^as",  |_| Token::As);
-    map.push(r"^await", |_| Token::Await);
-    map.push(r"^break", |_| Token::Break);
-    map.push(r"^cases", |_| Token::Cases);
-    map.push(r"^case", |_| Token::Case);
-    //map.push(r"^choices", |_| Token::Choices);
-    //map.push(r"^choice", |_| Token::Choice);*/
-    map.push(r"^class", |_| Token::Class);
-    map.push(r"^continue", |_| Token::Continue);
-    map.push(r"^defmatch", |_| Token::Defmatch);
-    map.push(r"^defproc", |_| Token::Defproc);
-    map.push(r"^defrule", |_| Token::Defrule);
-    map.push(r"^def", |_| Token::Def);
-    map.push(r"^del", |_| Token::Del);
-    map.push(r"^elif", |_| Token::Elif);
-    map.push(r"^else", |_| Token::Else);
-    //map.push(r"^enum", |_| Token::Enum);
-    //map.push(r"^eval", |_| Token::Eval);
-    map.push(r"^except", |_| Token::Except);
-    //map.push(r"^exec", |_| Token::Exec);
-    map.push(r"^finally", |_| Token::Finally);
-    map.push(r"^for", |_| Token::For);
-    map.push(r"^fresh", |_| Token::Fresh);
-    map.push(r"^from", |_| Token::From);
-    map.push(r"^global", |_| Token::Global);
-    map.push(r"^if", |_| Token::If);
-    map.push(r"^import", |_| Token::Import);
-    map.push(r"^in", |_| Token::In);
-    map.push(r"^is", |_| Token::Is);
-    map.push(r"^lambda", |_| Token::Lambda);
-    map.push(r"^let", |_| Token::Let);
-    map.push(r"^match", |_| Token::Match);
-    map.push(r"^nonlocal", |_| Token::Nonlocal);
-    map.push(r"^not", |_| Token::Not);
-    map.push(r"^of", |_| Token::Of);
-    map.push(r"^or", |_| Token::Or);
-    map.push(r"^pass", |_| Token::Pass);
-    map.push(r"^quote", |_| Token::Quote);
-    map.push(r"^raise", |_| Token::Raise);
-    map.push(r"^return", |_| Token::Return);
-    map.push(r"^rule", |_| Token::Rule);
-    map.push(r"^try", |_| Token::Try);
-    map.push(r"^unquote", |_| Token::Unquote);
-    map.push(r"^while", |_| Token::While);
-    map.push(r"^with", |_| Token::With);
-    map.push(r"^yield", |_| Token::Yield);
     let mut imap = RegexMapBuilder::new();
     imap.push(r"^[0-9]+", |s| Token::IntLit(s.into()));
     imap.push(r"^\-[0-9]+", |s| Token::IntLit(s.into()));
-    imap.push(r"^\.[a-zA-Z_][a-zA-Z0-9_]*", |s| Token::DotIdent(s.into()));
+    let dot_syms = syms.clone();
+    imap.push(r"^\.[a-zA-Z_][a-zA-Z0-9_]*", move |s| Token::DotIdent(dot_syms.borrow_mut().intern(&s[1 .. ])));
     // NB: deprecated syntax.
     /*imap.push(r"^:[a-zA-Z_][a-zA-Z0-9_]*", |s| Token::ColonIdent(s.into()));*/
-    imap.push(r"^[a-zA-Z_][a-zA-Z0-9_]*", |s| Token::Ident(s.into()));
+    let id_syms = syms.clone();
+    imap.push(r"^[a-zA-Z_][a-zA-Z0-9_]*", move |s| keyword(s).unwrap_or_else(|| Token::Ident(id_syms.borrow_mut().intern(s))));
     let tap = TAPOutput::default();
     Tokenizer{
       imap: imap.into(),
@@ -383,12 +452,25 @@ impl<S> Tokenizer<S> {
       pos:  0,
       ind:  0,
       flag: TokenizerFlag_::BOL,
+      compat: false,
+      trivia: BTreeMap::new(),
+      err:  None,
+      syms,
       //writer:   RefCell::new(Box::new(std::io::stdout())),
       //verbose:  0,
       tap,
     }
   }
 
+  pub fn interner(&self) -> &SharedInterner {
+    &self.syms
+  }
+
+  // The str of an ident token.
+  pub fn resolve(&self, sym: Sym) -> Ident {
+    self.syms.borrow().resolve(sym).clone()
+  }
+
   pub fn bol(&self) -> bool {
     bitflags_match!(self.flag & TokenizerFlag_::BOL, {
       TokenizerFlag_::BOL => true,
@@ -479,12 +561,26 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
       return Some((span, tok).into());
     }
     if c == Some('\n') {
+      // NB: a CRLF line break is recorded at the LF.
+      let end = match self.pos {
+        0 => 0,
+        pos => match self.buf.as_ref().as_bytes()[pos - 1] {
+          b'\r' => pos - 1,
+          _ => pos
+        }
+      };
+      if self._blank_line_at(end) {
+        self.trivia.insert(self.pos, Trivia::Blank);
+      }
       self.set_bol();
       let span = self._advance(1);
       let tok = Token::NL;
       return Some((span, tok).into());
     }
     else if c == Some('\r') {
+      if self.peek_char2() != Some('\n') && self._blank_line_at(self.pos) {
+        self.trivia.insert(self.pos, Trivia::Blank);
+      }
       self.set_bol();
       let span = self._advance(1);
       let tok = Token::CR;
@@ -494,16 +590,20 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
     else if /*self.compat && */c == Some('#') {
       let mut o = 0;
       for c in self.buf.as_ref().get(self.pos .. ).unwrap().chars() {
-        o += 1;
+        o += c.len_utf8();
         match c {
           '\n' | '\r' => {
+            // NB: the comment also ends the line.
+            self.set_bol();
             break;
           }
           _ => {}
         }
       }
+      let trailing = !self._blank_line_at(self.pos);
       let span = self._advance(o);
       let tok = Token::Comment(self.buf.as_ref().get(span.clone()).unwrap().into());
+      self._put_comment(&span, trailing);
       self.flag |= TokenizerFlag_::PYTHON;
       return Some((span, tok).into());
     }
@@ -511,16 +611,20 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
       if let Some('-') = self.peek_char2() {
         let mut o = 0;
         for c in self.buf.as_ref().get(self.pos .. ).unwrap().chars() {
-          o += 1;
+          o += c.len_utf8();
           match c {
             '\n' | '\r' => {
+              // NB: the comment also ends the line.
+              self.set_bol();
               break;
             }
             _ => {}
           }
         }
+        let trailing = !self._blank_line_at(self.pos);
         let span = self._advance(o);
         let tok = Token::Comment(self.buf.as_ref().get(span.clone()).unwrap().into());
+        self._put_comment(&span, trailing);
         self.flag |= TokenizerFlag_::PYTHIA;
         return Some((span, tok).into());
       }
@@ -544,7 +648,8 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
       }
       if !ident {
         let span = self._advance(o);
-        let tok = Token::PlaceIdent(self.buf.as_ref().get(span.clone()).unwrap().into());
+        let sym = self.syms.borrow_mut().intern(self.buf.as_ref().get(span.clone()).unwrap());
+        let tok = Token::PlaceIdent(sym);
         return Some((span, tok).into());
       }
     }
@@ -563,8 +668,8 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
         let mut strparser = strconfig.parser_from_str_at(self.buf.as_ref(), self.pos);
         let s: String = match strparser.parse_str() {
           Err(e) => {
-            _debugln!(self, "DEBUG: Tokenizer::next: unhandled str parse error: {e:?}");
-            panic!("BUG: Tokenizer::next: unhandled str parse error: {e:?}");
+            _debugln!(self, "DEBUG: Tokenizer::next: str parse error: {e:?}");
+            return Some(self._str_error(e, strconfig));
           }
           Ok(s) => s.into()
         };
@@ -579,8 +684,8 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
         let mut strparser = strconfig.parser_from_str_at(self.buf.as_ref(), self.pos);
         let s: String = match strparser.parse_str() {
           Err(e) => {
-            _debugln!(self, "DEBUG: Tokenizer::next: unhandled str parse error: {e:?}");
-            panic!("BUG: Tokenizer::next: unhandled str parse error: {e:?}");
+            _debugln!(self, "DEBUG: Tokenizer::next: str parse error: {e:?}");
+            return Some(self._str_error(e, strconfig));
           }
           Ok(s) => s.into()
         };
@@ -590,6 +695,37 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
       let tok = Token::AtomLit(self.buf.as_ref().get(span.clone()).unwrap().into(), ());
       return Some((span, tok).into());
     }
+    else if self.compat && c == Some('\'') {
+      let c2 = self.peek_char2();
+      let c3 = self.peek_char3();
+      let strconfig = if c2 == Some('\'') && c3 == Some('\'') {
+        StrParserConfig{
+          delim: "'''",
+          lines: true,
+          tabs: true,
+        }
+      } else {
+        StrParserConfig{
+          delim: "'",
+          lines: false,
+          tabs: false,
+        }
+      };
+      let mut strparser = strconfig.parser_from_str_at(self.buf.as_ref(), self.pos);
+      let s: String = match strparser.parse_str() {
+        Err(e) => {
+          _debugln!(self, "DEBUG: Tokenizer::next: str parse error: {e:?}");
+          return Some(self._str_error(e, strconfig));
+        }
+        Ok(s) => s.into()
+      };
+      let span = self._advance(strparser.offset());
+      // NB: a single-quoted str is normalized to the (escaped) double-quoted
+      // lit of the same str, so that downstream it is the same atom.
+      let lit = serde_json::to_string(&s).unwrap();
+      let tok = Token::AtomLit(lit.as_str().into(), ());
+      return Some((span, tok).into());
+    }
     _debugln!(self, "DEBUG: Tokenizer::next: match pos={}", self.pos);
     match (self.imap.match_at(self.buf.as_ref(), self.pos),
            self.map.match_at(self.buf.as_ref(), self.pos))
@@ -616,6 +752,8 @@ impl<S: AsRef<str>> Iterator for Tokenizer<S> {
       }
       (Some((ispan, itok)), Some((span, tok))) => {
         assert_eq!(ispan.start, span.start);
+        // NB: the words (idents and keywords) are all in `imap`, so the
+        // longer match wins, e.g. `-1` over `-` and `u``` over `u`.
         if ispan.end <= span.end {
           _debugln!(self, "DEBUG: Tokenizer::next:   found match: span={:?} tok={:?}", span, tok);
           self.pos = span.end;
@@ -654,6 +792,81 @@ impl<S: AsRef<str>> Tokenizer<S> {
     cs.next()
   }
 
+  pub fn trivia(&self) -> &BTreeMap<usize, Trivia> {
+    &self.trivia
+  }
+
+  pub fn set_compat(&mut self, compat: bool) {
+    self.compat = compat;
+  }
+
+  pub fn err(&self) -> Option<&(Span, StrParseErrorKind)> {
+    self.err.as_ref()
+  }
+
+  // Skips over a malformed str at the current position, as an error
+  // token; the error token ends after the closing delim, or else (for a
+  // single-line str) at the line end, or else at the eof.
+  pub fn _str_error(&mut self, e: StrParseErrorKind, cfg: StrParserConfig) -> SpanToken {
+    let start = self.pos;
+    let end = self._str_error_end(cfg);
+    let span = self._advance(end - start);
+    if self.err.is_none() {
+      self.err = Some((span.clone(), e));
+    }
+    let tok = Token::_Error(self.buf.as_ref().get(span.clone()).unwrap().into(), e);
+    (span, tok).into()
+  }
+
+  pub fn _str_error_end(&self, cfg: StrParserConfig) -> usize {
+    let buf = self.buf.as_ref();
+    let body = (self.pos + cfg.delim.len()).min(buf.len());
+    let mut escape = false;
+    for (o, c) in buf.get(body .. ).unwrap_or("").char_indices() {
+      let pos = body + o;
+      if !cfg.lines && (c == '\n' || c == '\r') {
+        return pos;
+      }
+      if escape {
+        escape = false;
+      } else if c == '\\' {
+        escape = true;
+      } else if buf.get(pos .. ).unwrap().starts_with(cfg.delim) {
+        return pos + cfg.delim.len();
+      }
+    }
+    buf.len()
+  }
+
+  pub fn take_trivia(&mut self) -> BTreeMap<usize, Trivia> {
+    replace(&mut self.trivia, BTreeMap::new())
+  }
+
+  // Whether the line at `end` is only whitespace up to `end`.
+  pub fn _blank_line_at(&self, end: usize) -> bool {
+    let line = self.buf.as_ref().get( .. end).unwrap();
+    for c in line.chars().rev() {
+      match c {
+        ' ' | '\t' => {}
+        '\n' | '\r' => return true,
+        _ => return false
+      }
+    }
+    true
+  }
+
+  pub fn _put_comment(&mut self, span: &Span, trailing: bool) {
+    // NB: the comment span also includes the line break, if any.
+    let text = self.buf.as_ref().get(span.clone()).unwrap().trim_end();
+    let t = if trailing {
+      Trivia::TrailingComment(text.into())
+    } else {
+      Trivia::Comment(text.into())
+    };
+    // NB: re-tokenizing after a seek visits the same comment again.
+    self.trivia.insert(span.start, t);
+  }
+
   pub fn peek_char2(&self) -> Option<char> {
     let mut cs = self.buf.as_ref().get(self.pos .. ).unwrap().chars();
     let _ = cs.next()?;
@@ -718,7 +931,7 @@ pub type Lit = SafeStr;
 pub type TermRef = Box<Term>;
 pub type StmRef = Box<Stm>;
 
-#[derive(Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum Term {
   // TODO TODO
   Ident(Span, Ident),
@@ -736,6 +949,17 @@ pub enum Term {
   Bunch(Span, Vec<TermRef>),
   Query(Span, TermRef),
   Equal(Span, TermRef, TermRef),
+  // NB: a membership `x in xs`; only in `Pythia` mode (in `PythonCompat`
+  // mode, `in` is a comparison, see `ParserMode`).
+  In(Span, TermRef, TermRef),
+  // NB: the connectives `a and b`, `a or b`, and `not a`; only in `Pythia`
+  // mode (in `PythonCompat` mode, these are calls, see `ParserMode`).
+  And(Span, TermRef, TermRef),
+  Or(Span, TermRef, TermRef),
+  Not(Span, TermRef),
+  // NB: an `await t` suspends on an external eval of (the value of) `t`
+  // (see `TermCode_::Await`).
+  Await(Span, TermRef),
   NEqual(Span, TermRef, TermRef),
   QEqual(Span, TermRef, TermRef),
   BindL(Span, TermRef, TermRef),
@@ -744,6 +968,23 @@ pub enum Term {
   RebindL(Span, TermRef, TermRef),
   RebindR(Span, TermRef, TermRef),
   Apply(Span, Vec<TermRef>),
+  // NB: a keyword arg `k=v`, which only occurs as an arg of an `Apply`
+  // (see `ParserMode::PythonCompat`).
+  Kwarg(Span, Ident, TermRef),
+  // NB: a splat `*t`, which only occurs as an arg of an `Apply`, or as
+  // the last param of a `Defproc` (i.e. a rest param).
+  Splat(Span, TermRef),
+  // NB: a lambda `lambda x, y: t` closes over the scope in which it is
+  // evaluated.
+  Lambda(Span, Vec<Option<Ident>>, TermRef),
+  // NB: a block quote as a term (e.g. the rhs of `q = ...`), which is
+  // reified as for a quote stm (see `Stm::Quote`).
+  Quote(Span, Vec<StmRef>),
+  // NB: a list comprehension `[e for x in xs if c]`; the bool is set for
+  // the nondeterministic form `[e for x choice in xs if c]`, which is
+  // the elt `e` for one item `x` of `xs` (i.e. w/ a choice point over the
+  // items), rather than the list of elts.
+  ListComp(Span, TermRef, Option<Ident>, bool, TermRef, Option<TermRef>),
   ApplyBindL(Span, TermRef, Vec<TermRef>),
   ApplyBindR(Span, Vec<TermRef>, TermRef),
   // FIXME: fold the head (middle) term into rterms/rtup.
@@ -760,12 +1001,18 @@ impl Term {
       &Term::NoneLit(ref span, ..) |
       &Term::BoolLit(ref span, ..) |
       &Term::IntLit(ref span, ..) |
+      &Term::FloatLit(ref span, ..) |
       &Term::ListLit(ref span, ..) |
       &Term::Neg(ref span, ..) |
       &Term::Group(ref span, ..) |
       &Term::Bunch(ref span, ..) |
       &Term::Query(ref span, ..) |
       &Term::Equal(ref span, ..) |
+      &Term::In(ref span, ..) |
+      &Term::And(ref span, ..) |
+      &Term::Or(ref span, ..) |
+      &Term::Not(ref span, ..) |
+      &Term::Await(ref span, ..) |
       &Term::NEqual(ref span, ..) |
       &Term::QEqual(ref span, ..) |
       &Term::BindL(ref span, ..) |
@@ -774,16 +1021,60 @@ impl Term {
       &Term::RebindL(ref span, ..) |
       &Term::RebindR(ref span, ..) |
       &Term::Apply(ref span, ..) |
+      &Term::Kwarg(ref span, ..) |
+      &Term::Splat(ref span, ..) |
+      &Term::Lambda(ref span, ..) |
+      &Term::Quote(ref span, ..) |
+      &Term::ListComp(ref span, ..) |
       &Term::ApplyBindL(ref span, ..) |
       &Term::ApplyBindR(ref span, ..) |
       &Term::Effect(ref span, ..)
       => span.clone(),
-      _ => unimplemented!()
+    }
+  }
+
+  pub fn span_mut(&mut self) -> &mut Span {
+    match self {
+      &mut Term::Ident(ref mut span, ..) |
+      &mut Term::QualIdent(ref mut span, ..) |
+      &mut Term::AtomLit(ref mut span, ..) |
+      &mut Term::NoneLit(ref mut span, ..) |
+      &mut Term::BoolLit(ref mut span, ..) |
+      &mut Term::IntLit(ref mut span, ..) |
+      &mut Term::FloatLit(ref mut span, ..) |
+      &mut Term::ListLit(ref mut span, ..) |
+      &mut Term::Neg(ref mut span, ..) |
+      &mut Term::Group(ref mut span, ..) |
+      &mut Term::Bunch(ref mut span, ..) |
+      &mut Term::Query(ref mut span, ..) |
+      &mut Term::Equal(ref mut span, ..) |
+      &mut Term::In(ref mut span, ..) |
+      &mut Term::And(ref mut span, ..) |
+      &mut Term::Or(ref mut span, ..) |
+      &mut Term::Not(ref mut span, ..) |
+      &mut Term::Await(ref mut span, ..) |
+      &mut Term::NEqual(ref mut span, ..) |
+      &mut Term::QEqual(ref mut span, ..) |
+      &mut Term::BindL(ref mut span, ..) |
+      &mut Term::BindR(ref mut span, ..) |
+      &mut Term::Subst(ref mut span, ..) |
+      &mut Term::RebindL(ref mut span, ..) |
+      &mut Term::RebindR(ref mut span, ..) |
+      &mut Term::Apply(ref mut span, ..) |
+      &mut Term::Kwarg(ref mut span, ..) |
+      &mut Term::Splat(ref mut span, ..) |
+      &mut Term::Lambda(ref mut span, ..) |
+      &mut Term::Quote(ref mut span, ..) |
+      &mut Term::ListComp(ref mut span, ..) |
+      &mut Term::ApplyBindL(ref mut span, ..) |
+      &mut Term::ApplyBindR(ref mut span, ..) |
+      &mut Term::Effect(ref mut span, ..)
+      => span,
     }
   }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum Stm {
   // TODO TODO
   Just(Span, TermRef),
@@ -791,25 +1082,113 @@ pub enum Stm {
   Pass(Span),
   Global(Span, Ident),
   Nonlocal(Span, Option<i16>, Ident),
+  // NB: binds each ident to a fresh unbound var in the enclosing scope,
+  // shadowing any outer binding (see `StmCode_::Fresh`).
+  Fresh(Span, Vec<Ident>),
+  // NB: binds each ident to the module of that name (see
+  // `FastInterp::load_many`).
+  Import(Span, Vec<Ident>),
+  // NB: an `assert` or `expect` that fails does not abort; instead, it
+  // records a test failure (see `FastInterp::test_failures`).
+  Assert(Span, TermRef),
+  Expect(Span, TermRef, TermRef),
   With(Span, TermRef, Vec<StmRef>),
-  Try,
+  // NB: the `try`, `while`, and `for` stms are not (yet) produced by
+  // the parser, which rejects them (see `ParseError::Unimpl`).
+  Try(Span),
   If(Span, Vec<(TermRef, Vec<StmRef>)>, Option<Vec<StmRef>>),
-  While,
-  For,
-  Match(Span, (), Vec<StmRef>),
+  While(Span),
+  For(Span),
+  // NB: the cases are tried in order (see `StmCode_::Match`).
+  Match(Span, TermRef, Vec<(TermRef, Vec<StmRef>)>),
   Def(Span, Option<DefPrefix>, (), Vec<StmRef>),
   // FIXME: def-like args (params) are more general than just
   // a list of idents; but this is a stopgap to parse something.
-  Defproc(Span, Option<DefPrefix>, Ident, Vec<Option<Ident>>, Vec<StmRef>),
+  // NB: the rest param (if any) follows the params; the defaults are
+  // keyed by param, in param order.
+  Defproc(Span, Option<DefPrefix>, Ident, Vec<Option<Ident>>, Option<Ident>, Vec<(Ident, TermRef)>, Vec<StmRef>),
   Defmatch(Span, Option<DefPrefix>, Ident, Vec<Option<Ident>>, Vec<StmRef>),
+  // NB: the head args are patterns (not just params), and the body stms
+  // are the goals of the rule (see `StmCode_::Defrule`).
+  Defrule(Span, Ident, Vec<TermRef>, Vec<StmRef>),
   //Enum(Span, (), ),
-  Cases(Span, (), Vec<StmRef>),
-  Class(Span, (), Vec<StmRef>),
+  // NB: each case is an alternative body, tried at a choice point (see
+  // `StmCode_::Cases`).
+  Cases(Span, Vec<Vec<StmRef>>),
+  // NB: the body stms (e.g. the method defprocs) run in the class scope
+  // (see `StmCode_::Class`).
+  Class(Span, Ident, Vec<StmRef>),
   Quote(Span, (), Vec<StmRef>),
   _EndQuote(Span),
+  // NB: a case block is only parsed inside a match stm (w/ a pattern) or
+  // a cases stm (w/out a pattern), which then takes the case as one of
+  // its own.
+  _Case(Span, Option<TermRef>, Vec<StmRef>),
 }
 
-#[derive(Clone, Copy, Debug)]
+impl Stm {
+  pub fn span(&self) -> Span {
+    match self {
+      &Stm::Just(ref span, ..) |
+      &Stm::Comment(ref span, ..) |
+      &Stm::Pass(ref span, ..) |
+      &Stm::Global(ref span, ..) |
+      &Stm::Nonlocal(ref span, ..) |
+      &Stm::Fresh(ref span, ..) |
+      &Stm::Import(ref span, ..) |
+      &Stm::Assert(ref span, ..) |
+      &Stm::Expect(ref span, ..) |
+      &Stm::With(ref span, ..) |
+      &Stm::If(ref span, ..) |
+      &Stm::Match(ref span, ..) |
+      &Stm::Def(ref span, ..) |
+      &Stm::Defproc(ref span, ..) |
+      &Stm::Defmatch(ref span, ..) |
+      &Stm::Defrule(ref span, ..) |
+      &Stm::Cases(ref span, ..) |
+      &Stm::Class(ref span, ..) |
+      &Stm::Quote(ref span, ..) |
+      &Stm::_EndQuote(ref span, ..) |
+      &Stm::_Case(ref span, ..) |
+      &Stm::Try(ref span) |
+      &Stm::While(ref span) |
+      &Stm::For(ref span)
+      => span.clone(),
+    }
+  }
+
+  pub fn span_mut(&mut self) -> Option<&mut Span> {
+    match self {
+      &mut Stm::Just(ref mut span, ..) |
+      &mut Stm::Comment(ref mut span, ..) |
+      &mut Stm::Pass(ref mut span, ..) |
+      &mut Stm::Global(ref mut span, ..) |
+      &mut Stm::Nonlocal(ref mut span, ..) |
+      &mut Stm::Fresh(ref mut span, ..) |
+      &mut Stm::Import(ref mut span, ..) |
+      &mut Stm::Assert(ref mut span, ..) |
+      &mut Stm::Expect(ref mut span, ..) |
+      &mut Stm::With(ref mut span, ..) |
+      &mut Stm::If(ref mut span, ..) |
+      &mut Stm::Match(ref mut span, ..) |
+      &mut Stm::Def(ref mut span, ..) |
+      &mut Stm::Defproc(ref mut span, ..) |
+      &mut Stm::Defmatch(ref mut span, ..) |
+      &mut Stm::Defrule(ref mut span, ..) |
+      &mut Stm::Cases(ref mut span, ..) |
+      &mut Stm::Class(ref mut span, ..) |
+      &mut Stm::Quote(ref mut span, ..) |
+      &mut Stm::_EndQuote(ref mut span, ..) |
+      &mut Stm::_Case(ref mut span, ..) |
+      &mut Stm::Try(ref mut span) |
+      &mut Stm::While(ref mut span) |
+      &mut Stm::For(ref mut span)
+      => Some(span),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 pub enum DefPrefix {
   // TODO
   //Global,
@@ -817,10 +1196,118 @@ pub enum DefPrefix {
   //GlobalRule,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Mod {
   pub span: Span,
   pub body: Vec<StmRef>,
+  // NB: the pragmas of the module, in source order (see `Pragma`).
+  pub pragmas: Vec<Pragma>,
+}
+
+// A module-level pragma, given by a (non-trailing) comment of the form:
+//
+//     -- pragma: KEY [= VAL], ...
+//
+// e.g. `-- pragma: strict, step_limit = 1000`. The parser does not
+// interpret pragmas; the interpreter applies the ones it recognizes
+// when the module is loaded.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Pragma {
+  pub span: Span,
+  pub key:  SafeStr,
+  pub val:  Option<SafeStr>,
+}
+
+impl Pragma {
+  // The pragmas of the comment `text` at `span`, if it is a pragma
+  // comment.
+  pub fn parse_comment(span: &Span, text: &str) -> Vec<Pragma> {
+    let mut pragmas = Vec::new();
+    let rest = match text.strip_prefix("--")
+      .and_then(|rest| rest.trim_start().strip_prefix("pragma:"))
+    {
+      None => return pragmas,
+      Some(rest) => rest
+    };
+    for item in rest.split(',') {
+      let (key, val) = match item.split_once('=') {
+        None => (item.trim(), None),
+        Some((key, val)) => (key.trim(), Some(val.trim()))
+      };
+      if key.is_empty() {
+        continue;
+      }
+      pragmas.push(Pragma{
+        span: span.clone(),
+        key:  key.into(),
+        val:  val.map(|val| val.into()),
+      });
+    }
+    pragmas
+  }
+}
+
+// The version of the JSON interchange format (see `Mod::to_json`). Bump
+// this upon any change to the serialized form of `Mod`, `Stm`, `Term`,
+// or `DefPrefix`.
+pub const MOD_JSON_VERSION: u32 = 16;
+
+#[derive(Deserialize)]
+struct ModJson_ {
+  version:  u32,
+  #[serde(rename = "mod")]
+  mod_:     Mod,
+}
+
+#[derive(Debug)]
+pub enum ModJsonError {
+  Json(String),
+  Version(u32),
+}
+
+impl Mod {
+  // The JSON interchange format of a module is:
+  //
+  //     {"version": VERSION, "mod": {"span": SPAN, "body": [STM, ...],
+  //       "pragmas": [PRAGMA, ...]}}
+  //
+  // where VERSION is `MOD_JSON_VERSION`, and a SPAN is
+  // `{"start": START, "end": END}`, the byte offsets into the source.
+  // A STM or TERM is a single-key object, keyed by the
+  // variant name, whose value is the array of the variant fields, in
+  // declaration order (see `Stm` and `Term`); e.g. the stm `x = 1` is:
+  //
+  //     {"Just": [{"start": 0, "end": 5}, {"Equal": [
+  //       {"start": 0, "end": 5},
+  //       {"Ident": [{"start": 0, "end": 1}, "x"]},
+  //       {"IntLit": [{"start": 4, "end": 5}, "1"]}]}]}
+  //
+  // A variant w/ a single field (e.g. `Try`) has that field as its value,
+  // rather than an array, e.g. `{"Try": {"start": 0, "end": 3}}`; a unit
+  // variant (e.g. the `Rule` of `DefPrefix`) is just the string of its
+  // name; a `()` field is `null`, and an absent `Option` field is `null`.
+  // A PRAGMA is `{"span": SPAN, "key": KEY, "val": VAL}`, where VAL is a
+  // string or `null` (see `Pragma`).
+  pub fn to_json(&self) -> String {
+    let doc = ModJsonRef_{version: MOD_JSON_VERSION, mod_: self};
+    serde_json::to_string(&doc).unwrap()
+  }
+
+  pub fn from_json(s: &str) -> Result<Mod, ModJsonError> {
+    let doc: ModJson_ = serde_json::from_str(s)
+      .map_err(|e| ModJsonError::Json(e.to_string()))?;
+    if doc.version != MOD_JSON_VERSION {
+      return Err(ModJsonError::Version(doc.version));
+    }
+    Ok(doc.mod_)
+  }
+}
+
+#[derive(Serialize)]
+struct ModJsonRef_<'a> {
+  version:  u32,
+  #[serde(rename = "mod")]
+  mod_:     &'a Mod,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -832,6 +1319,10 @@ pub enum StmStage {
   If,
   Elif,
   Else,
+  Case,
+  // NB: the stage of a case block inside a cases stm (cf. `Case`, inside
+  // a match stm).
+  Cases,
 }
 
 impl<'a> From<&'a Token> for StmStage {
@@ -843,6 +1334,7 @@ impl<'a> From<&'a Token> for StmStage {
       &Token::Finally => StmStage::Finally,
       &Token::If => StmStage::If,
       &Token::Try => StmStage::Try,
+      &Token::Case => StmStage::Case,
       _ => panic!("bug")
     }
   }
@@ -954,6 +1446,7 @@ pub enum ParseError {
   ExpectedBunch,
   ExpectedIntLit,
   InvalidIntLit,
+  MalformedString(StrParseErrorKind),
   Unimpl_,
   Unimpl(Token),
   _Bot,
@@ -974,6 +1467,95 @@ impl From<(Span, ParseError)> for ParseSpanError {
   }
 }
 
+// The surface syntax accepted by `FastParser`.
+//
+// In `PythonCompat` mode, the parser additionally accepts the following
+// subset of Python, which it lowers to the existing AST:
+//
+// - `def f(a, b=1):` lowers to a `Stm::Defproc` w/ params `a, b` and
+//   the default `b=1`; a last param `*rest` is the rest param.
+// - A call arg `k=v` is a keyword arg, and lowers to `Term::Kwarg`;
+//   NB: each call arg is delimited by the commas of the call (rather
+//   than bunched), unless it has an infix op looser than `,` (e.g. `?=`).
+// - `return`, `return e`, `break`, and `continue` lower to the
+//   `Stm::Just` calls `__py_return__()`, `__py_return__(e)`,
+//   `__py_break__()`, and `__py_continue__()`.
+// - `while c:` lowers to the `Stm::With` block `with __py_while__(c):`,
+//   and `for x in xs:` to `with __py_for__(x, xs):`; the target `x`
+//   may also be a bunch (e.g. `for k, v in xs:`). Loop `else` clauses
+//   are not accepted.
+// - The comparisons `<`, `<=`, `>`, `>=`, `==`, `!=`, `in`, `not in`,
+//   `is`, and `is not` lower to the calls `__py_lt__(a, b)` (etc., see
+//   `_py_cmp_ident`); a comparison chain `a < b < c` lowers to the
+//   conjunction `__py_and__(__py_lt__(a, b), __py_lt__(b, c))`, where
+//   NB: the inner operand `b` is duplicated, rather than bound once.
+// - `and`, `or`, and `not` lower to `__py_and__`, `__py_or__`, and
+//   `__py_not__`; as in Python, they bind more loosely than comparisons.
+// - Calls bind more tightly than every binary operator.
+// - Single-quoted strs (`'...'` and `'''...'''`) are accepted, and are
+//   normalized to the equivalent double-quoted atom lit; in either kind
+//   of str, the escapes are the JSON escapes, plus `\'`.
+//
+// All other syntax is parsed as in `Pythia` mode; in particular, `=`
+// still lowers to `Term::Equal` (except as a call arg), and arithmetic
+// operators, `**kwargs`, base classes, and annotations are not accepted.
+// The `__py_*__` calls are plain idents to the interpreter, which only
+// defines the ordering comparisons `__py_lt__`, `__py_le__`, `__py_gt__`,
+// and `__py_ge__` (see `interp::prelude::CmpFun`).
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Debug)]
+pub enum ParserMode {
+  #[default]
+  Pythia,
+  PythonCompat,
+}
+
+pub fn _py_call(span: Span, head: &str, args: Vec<Term>) -> Term {
+  let mut tup: Vec<TermRef> = Vec::with_capacity(args.len() + 1);
+  tup.push(Term::Ident(span.clone(), head.into()).into());
+  tup.extend(args.into_iter().map(|arg| arg.into()));
+  Term::Apply(span, tup)
+}
+
+pub fn _py_cmp_tok(tok: &Token) -> bool {
+  match tok {
+    &Token::Lt |
+    &Token::LtEq |
+    &Token::Gt |
+    &Token::GtEq |
+    &Token::EqEq |
+    &Token::BangEq |
+    &Token::In |
+    &Token::Is |
+    &Token::Not => true,
+    _ => false
+  }
+}
+
+// Splits the header `x in xs` of a `for` stm into `(x, xs)`.
+pub fn _py_split_in(header: Term) -> Option<(Term, Term)> {
+  match header {
+    Term::Apply(_, mut tup) => {
+      if tup.len() != 3 {
+        return None;
+      }
+      match &*tup[0] {
+        &Term::Ident(_, ref s) if s.as_raw_str() == "__py_in__" => {}
+        _ => return None
+      }
+      let iter = *tup.pop().unwrap();
+      let target = *tup.pop().unwrap();
+      Some((target, iter))
+    }
+    Term::Bunch(_, mut tup) => {
+      let (last, iter) = _py_split_in(*tup.pop()?)?;
+      tup.push(last.into());
+      let span = tup[0].span().hull(tup[tup.len() - 1].span());
+      Some((Term::Bunch(span, tup), iter))
+    }
+    _ => None
+  }
+}
+
 pub type Parser<S> = FastParser<S>;
 
 pub struct FastParser<S> {
@@ -983,18 +1565,44 @@ pub struct FastParser<S> {
   cur:      Option<SpanToken>,
   peek:     Option<SpanToken>,
   tap:      TAPOutput,
+  mode:     ParserMode,
+  base:     usize,
   //verbose:  i8,
 }
 
 impl<S: AsRef<str>> Parser<S> {
   pub fn new(buf: S) -> Parser<S> {
-    let tokens = Tokenizer::new(buf);
+    Parser::with_interner(buf, SharedInterner::default())
+  }
+
+  // A parser whose tokenizer interns the idents in `syms`, e.g. the
+  // interner of an interpreter (see `FastInterp::interner`).
+  pub fn with_interner(buf: S, syms: SharedInterner) -> Parser<S> {
+    let tokens = Tokenizer::with_interner(buf, syms);
     //let tokens = DebugTokenizer::new(buf);
     let cur = None;
     let peek = None;
     //let verbose = 0;
     let tap = TAPOutput::default();
-    Parser{tokens, cur, peek, tap}
+    let mode = ParserMode::default();
+    let base = 0;
+    Parser{tokens, cur, peek, tap, mode, base}
+  }
+
+  // Sets the offset that is added to every span of the parsed module
+  // (and of a parse error), e.g. the base offset of the source in a
+  // `src::SourceMap`.
+  pub fn set_span_base(&mut self, base: usize) {
+    self.base = base;
+  }
+
+  pub fn set_mode(&mut self, mode: ParserMode) {
+    self.mode = mode;
+    self.tokens.compat = mode == ParserMode::PythonCompat;
+  }
+
+  pub fn mode(&self) -> ParserMode {
+    self.mode
   }
 
   pub fn set_verbose(&mut self, v: i8) {
@@ -1005,7 +1613,49 @@ impl<S: AsRef<str>> Parser<S> {
     self.set_verbose(3);
   }
 
+  // Redirects the debug output of the parser (and its tokenizer) to the
+  // shared log sink `writer`.
+  pub fn set_log_writer(&mut self, writer: LogWriter) {
+    let _ = self.tokens.tap.set_log_writer(writer.clone());
+    let _ = self.tap.set_log_writer(writer);
+  }
+
+  pub fn take_trivia(&mut self) -> BTreeMap<usize, Trivia> {
+    self.tokens.take_trivia()
+  }
+
+  // The first malformed str seen by the tokenizer, if any (see
+  // `ParseError::MalformedString`).
+  pub fn tokenizer_err(&self) -> Option<&(Span, StrParseErrorKind)> {
+    self.tokens.err()
+  }
+
   pub fn lbp(&self, tok: &Token) -> RawBp {
+    if self.mode == ParserMode::PythonCompat {
+      match tok {
+        &Token::Or => {
+          return 900;
+        }
+        &Token::And => {
+          return 910;
+        }
+        &Token::Lt |
+        &Token::LtEq |
+        &Token::Gt |
+        &Token::GtEq |
+        &Token::EqEq |
+        &Token::BangEq |
+        &Token::In |
+        &Token::Is |
+        &Token::Not => {
+          return 1000;
+        }
+        &Token::LParen => {
+          return 1600;
+        }
+        _ => {}
+      }
+    }
     match tok {
       &Token::LDeduct |
       &Token::RDeduct => {
@@ -1013,17 +1663,31 @@ impl<S: AsRef<str>> Parser<S> {
       }
       // FIXME: LWalrus should be R-assoc.
       &Token::LWalrus |
-      &Token::RWalrus => {
+      &Token::RWalrus |
+      &Token::LDotEq |
+      &Token::RDotEq => {
         110
       }
+      // NB: the connectives bind more loosely than a unification (and
+      // `not` binds in between, see `term_nud`).
+      &Token::Or => {
+        114
+      }
+      &Token::And => {
+        116
+      }
       &Token::Equal |
       &Token::SlashEq |
       &Token::LQueryEq => {
         120
       }
+      &Token::In => {
+        200
+      }
       // NB: deprecated syntax.
       /*&Token::ColonIdent(_) |*/
-      &Token::LParen => {
+      &Token::LParen |
+      &Token::LColonParen => {
         400
       }
       &Token::Comma => {
@@ -1068,14 +1732,12 @@ impl<S: AsRef<str>> Parser<S> {
     self.cur.as_ref().map(|cur| cur.tok.clone())
   }
 
-  #[track_caller]
   pub fn cur_span(&self) -> Span {
-    let loc = loc();
     match self.cur.as_ref() {
       None => {
-        panic!("Parser::cur_span: no cursor (due to reset?): {:?}", loc);
-        //println!("DEBUG: Parser::cur_span: no cursor (due to reset?): {:?}", loc);
-        //Span{start: usize::max_value(), end: usize::max_value()}
+        // NB: w/ no cursor (due to reset), the error is located at the
+        // tokenizer position.
+        self.tokens._pos()
       }
       Some(cur) => cur.span.clone()
     }
@@ -1125,6 +1787,25 @@ impl<S: AsRef<str>> Parser<S> {
   }
 
   pub fn mod_(&mut self) -> Result<Mod, ParseSpanError> {
+    let base = self.base;
+    let res = self._mod_();
+    // NB: a malformed str is the first parse error, even where the parse
+    // got past it (e.g. as an unexpected token) or recovered.
+    let res = match self.tokens.err() {
+      Some(&(ref span, e)) => Err((span.clone(), ParseError::MalformedString(e)).into()),
+      None => res
+    };
+    match res {
+      Ok(mod_) if base == 0 => Ok(mod_),
+      Ok(mod_) => Ok(SpanRebase{base}.rewrite_mod(mod_)),
+      Err(mut e) => {
+        e.span = e.span.start + base .. e.span.end + base;
+        Err(e)
+      }
+    }
+  }
+
+  pub fn _mod_(&mut self) -> Result<Mod, ParseSpanError> {
     let mut body = Vec::new();
     loop {
       _debugln!(self, "DEBUG: Parser::mod_: stm...");
@@ -1138,7 +1819,15 @@ impl<S: AsRef<str>> Parser<S> {
     let start = 0;
     let end = self.maybe_cur_span().map(|span| span.end).unwrap_or(0);
     let span = Span{start, end};
-    Ok(Mod{span, body})
+    let mut pragmas = Vec::new();
+    for (&off, t) in self.tokens.trivia().iter() {
+      if let &Trivia::Comment(ref text) = t {
+        let text = text.as_raw_str();
+        let span = off .. off + text.len();
+        pragmas.extend(Pragma::parse_comment(&span, text));
+      }
+    }
+    Ok(Mod{span, body, pragmas})
   }
 
   pub fn stm(&mut self, ctx: StmCtx) -> Result<Option<(Stm, StmCtx)>, ParseSpanError> {
@@ -1174,14 +1863,23 @@ impl<S: AsRef<str>> Parser<S> {
       }
       _ => {}
     };
-    let mut lterm = self.term_nud(ctx.indent, cur.clone())?;
+    let lterm = self.term_nud(ctx.indent, cur.clone())?;
     _debugln!(self, "DEBUG: Parser::term: nud: tok={:?} lterm={:?}", &cur.tok, &lterm);
+    self._term_led(ctx, lterm)
+  }
+
+  // Continues parsing the term `lterm` w/ the infix ops that bind more
+  // tightly than `ctx.bp`.
+  pub fn _term_led(&mut self, ctx: TermCtx, mut lterm: Term) -> Result<Term, ParseSpanError> {
     loop {
+      let save_pos = self.pos();
       match self.maybe_term_spaces(ctx.indent) {
         Ok(_) => {}
         Err(_) => {
-          // FIXME FIXME: right restore point?
-          //self.restore(&cur.span);
+          // NB: the term ends before the dedent, so restore to just after
+          // the last token of the term (i.e. before the line break), from
+          // which the enclosing stm continues.
+          self.restore(&Span{start: save_pos.end, end: save_pos.end});
           break;
         }
       }
@@ -1202,9 +1900,8 @@ impl<S: AsRef<str>> Parser<S> {
         self.restore(&next.span);
         break;
       }
-      cur = next;
-      lterm = self.term_led(ctx.indent, lterm, cur.clone())?;
-      _debugln!(self, "DEBUG: Parser::term: led: tok={:?} lterm={:?}", &cur.tok, &lterm);
+      lterm = self.term_led(ctx.indent, lterm, next.clone())?;
+      _debugln!(self, "DEBUG: Parser::term: led: tok={:?} lterm={:?}", &next.tok, &lterm);
     }
     Ok(lterm)
   }
@@ -1378,6 +2075,94 @@ impl<S: AsRef<str>> Parser<S> {
         let span = cur.span.hull(self.pos());
         return Ok(Some((Stm::Nonlocal(span, static_scope, ident.into()), this_ctx)));
       }
+      &Token::Fresh => {
+        _debugln!(self, "DEBUG: Parser::stm: fresh: tok={:?}", &cur.tok);
+        let mut term_ctx = this_ctx.term();
+        term_ctx.bp = self.lbp(&Token::Comma);
+        let mut idents = Vec::new();
+        loop {
+          // FIXME: spaces are required here.
+          self.maybe_spaces_deprecated();
+          let term = self.term(term_ctx)?;
+          match term {
+            Term::Ident(_, ident) => idents.push(ident),
+            _ => {
+              return Err((self.cur_span(), ParseError::ExpectedIdent).into());
+            }
+          }
+          self.maybe_term_spaces(term_ctx.indent)?;
+          self.next();
+          let cur = self.cur();
+          match &cur.tok {
+            &Token::Comma => {}
+            _ => {
+              self.restore(&cur.span);
+              break;
+            }
+          }
+        }
+        let span = cur.span.hull(self.pos());
+        return Ok(Some((Stm::Fresh(span, idents), this_ctx)));
+      }
+      &Token::Import => {
+        _debugln!(self, "DEBUG: Parser::stm: import: tok={:?}", &cur.tok);
+        let mut term_ctx = this_ctx.term();
+        term_ctx.bp = self.lbp(&Token::Comma);
+        let mut idents = Vec::new();
+        loop {
+          // FIXME: spaces are required here.
+          self.maybe_spaces_deprecated();
+          let term = self.term(term_ctx)?;
+          match term {
+            Term::Ident(_, ident) => idents.push(ident),
+            _ => {
+              return Err((self.cur_span(), ParseError::ExpectedIdent).into());
+            }
+          }
+          self.maybe_term_spaces(term_ctx.indent)?;
+          self.next();
+          let cur = self.cur();
+          match &cur.tok {
+            &Token::Comma => {}
+            _ => {
+              self.restore(&cur.span);
+              break;
+            }
+          }
+        }
+        let span = cur.span.hull(self.pos());
+        return Ok(Some((Stm::Import(span, idents), this_ctx)));
+      }
+      &Token::Assert => {
+        _debugln!(self, "DEBUG: Parser::stm: assert: tok={:?}", &cur.tok);
+        // FIXME: spaces are required here.
+        self.maybe_spaces_deprecated();
+        let term = self.term(this_ctx.term())?;
+        let span = cur.span.hull(term.span());
+        return Ok(Some((Stm::Assert(span, term.into()), this_ctx)));
+      }
+      &Token::Expect => {
+        _debugln!(self, "DEBUG: Parser::stm: expect: tok={:?}", &cur.tok);
+        // NB: the lhs stops short of the `==` (which is not an infix op
+        // in `Pythia` mode).
+        let mut term_ctx = this_ctx.term();
+        term_ctx.bp = self.lbp(&Token::EqEq);
+        // FIXME: spaces are required here.
+        self.maybe_spaces_deprecated();
+        let lterm = self.term(term_ctx)?;
+        self.maybe_term_spaces(term_ctx.indent)?;
+        self.next();
+        let eq = self.cur();
+        match &eq.tok {
+          &Token::EqEq => {}
+          _ => {
+            return Err((eq.span, ParseError::Expected(Token::EqEq)).into());
+          }
+        }
+        let rterm = self.term(this_ctx.term())?;
+        let span = cur.span.hull(rterm.span());
+        return Ok(Some((Stm::Expect(span, lterm.into(), rterm.into()), this_ctx)));
+      }
       &Token::Rule => {
         match prefix {
           StmPrefix::_Nil => {
@@ -1396,37 +2181,16 @@ impl<S: AsRef<str>> Parser<S> {
       &Token::Elif |
       &Token::Else |
       &Token::Except |
-      &Token::Finally |
+      &Token::Finally => {
+        // NB: a stage of a compound stm is only parsed in place (see the
+        // If parsing block below).
+        _debugln!(self, "DEBUG: Parser::stm: stray compound block tok={:?}", &cur.tok);
+        return Err((cur.span, ParseError::Unexpected(cur.tok)).into());
+      }
       &Token::Try => {
-        // FIXME: deprecated this compound-block stm parsing path;
-        // see the If parsing block below.
-        _debugln!(self, "DEBUG: Parser::stm: compound block tok={:?}", &cur.tok);
-        let mut this_ctx = StmCtx{
-          _stage: Some(StmStage::from(&cur.tok)),
-          indent: this_ctx.indent.eq_to_gt(),
-        };
-        let _stm = match self.stm(this_ctx)? {
-          None => {
-            // FIXME: this is a fail.
-            return Err((cur.span, ParseError::EmptyBlock).into());
-          }
-          Some((_, stm_ctx)) => {
-            assert!(stm_ctx.indent.is_eq());
-            this_ctx.indent = stm_ctx.indent;
-          }
-        };
-        // FIXME: stage transitions for compound statements.
-        loop {
-          match self.stm(this_ctx)? {
-            None => {
-              break;
-            }
-            Some((_stm, _)) => {
-              // TODO
-              unimplemented!();
-            }
-          }
-        }
+        // TODO: try/except/finally compound stms.
+        _debugln!(self, "DEBUG: Parser::stm: unimpl: compound block tok={:?}", &cur.tok);
+        return Err((cur.span, ParseError::Unimpl(cur.tok)).into());
       }
       _ => {}
     }
@@ -1519,12 +2283,26 @@ impl<S: AsRef<str>> Parser<S> {
                     return Ok(Some((Stm::If(span, cases, None), this_ctx)));
                   }
                 }
-                _ => panic!("bug")
+                StmIndent::Gt(_) => {
+                  return Err((self.cur_span(), ParseError::Indent).into());
+                }
               }
               self.next();
               self.cur()
             }
-            _ => cur
+            _ => {
+              // NB: the indent may have already been consumed by the
+              // last stm of the body.
+              match this_ctx.indent {
+                StmIndent::Eq(this_ctx_indent) if self.tokens.ind < this_ctx_indent => {
+                  self.restore(&save_pos);
+                  let span = start.hull(save_pos);
+                  return Ok(Some((Stm::If(span, cases, None), this_ctx)));
+                }
+                _ => {}
+              }
+              cur
+            }
           };
           let cond = match &cur.tok {
             // FIXME: this explicit Eof case is a bit kludgy.
@@ -1541,7 +2319,12 @@ impl<S: AsRef<str>> Parser<S> {
             &Token::Else => {
               None
             }
-            _ => return Err((self.cur_span(), ParseError::Expected(Token::Else)).into())
+            _ => {
+              // NB: an if stm w/out a final else case.
+              self.restore(&save_pos);
+              let span = start.hull(save_pos);
+              return Ok(Some((Stm::If(span, cases, None), this_ctx)));
+            }
           };
           self.next();
           let cur = self.cur();
@@ -1583,18 +2366,13 @@ impl<S: AsRef<str>> Parser<S> {
           }
         }
       }
-      &Token::With => {
-        _debugln!(self, "DEBUG: Parser::stm: with ctx: tok={:?}", &cur.tok);
+      &Token::Match => {
+        _debugln!(self, "DEBUG: Parser::stm: match: tok={:?}", &cur.tok);
         let start = cur.span.clone();
+        // FIXME: spaces are required here.
         self.maybe_spaces_deprecated();
-        let head = self.term(this_ctx.term())?;
-        match &head {
-          &Term::Ident(..) => {
-          }
-          &Term::Apply(..) => {
-          }
-          _ => return Err((self.cur_span(), ParseError::ExpectedIdent).into())
-        }
+        let x = self.term(this_ctx.term())?;
+        // FIXME: no spaces should occur here.
         self.maybe_spaces_deprecated();
         self.next();
         let cur = self.cur();
@@ -1603,21 +2381,174 @@ impl<S: AsRef<str>> Parser<S> {
           }
           _ => return Err((self.cur_span(), ParseError::Expected(Token::Colon)).into())
         }
-        // TODO
-        let mut body = Vec::new();
+        // NB: the case blocks are the stms of the match block, parsed
+        // in the `Case` stage (see `Stm::_Case`).
         let mut inner_ctx = StmCtx{
-          _stage: None,
+          _stage: Some(StmStage::Case),
           indent: this_ctx.indent.eq_to_gt(),
         };
-        let stm = match self.stm(inner_ctx)? {
-          None => {
-            return Err((self.cur_span(), ParseError::ExpectedStm).into());
-          }
-          Some((stm, stm_ctx)) => {
-            assert!(stm_ctx.indent.is_eq());
-            inner_ctx.indent = stm_ctx.indent;
-            stm
-          }
+        let mut cases = Vec::new();
+        loop {
+          let stm = match self.stm(inner_ctx)? {
+            None => {
+              break;
+            }
+            Some((stm, stm_ctx)) => {
+              if cases.is_empty() {
+                assert!(stm_ctx.indent.is_eq());
+                inner_ctx.indent = stm_ctx.indent;
+              }
+              stm
+            }
+          };
+          match stm {
+            Stm::_Case(_, Some(pat), body) => {
+              cases.push((pat, body));
+            }
+            stm => {
+              return Err((stm.span(), ParseError::Expected(Token::Case)).into());
+            }
+          }
+        }
+        if cases.is_empty() {
+          return Err((self.cur_span(), ParseError::ExpectedStm).into());
+        }
+        let span = start.hull(self.pos());
+        return Ok(Some((Stm::Match(span, x.into(), cases), this_ctx)));
+      }
+      &Token::Cases => {
+        _debugln!(self, "DEBUG: Parser::stm: cases: tok={:?}", &cur.tok);
+        let start = cur.span.clone();
+        // FIXME: no spaces should occur here.
+        self.maybe_spaces_deprecated();
+        self.next();
+        let cur = self.cur();
+        match &cur.tok {
+          &Token::Colon => {
+          }
+          _ => return Err((self.cur_span(), ParseError::Expected(Token::Colon)).into())
+        }
+        // NB: the case blocks are the stms of the cases block, parsed
+        // in the `Cases` stage (see `Stm::_Case`).
+        let mut inner_ctx = StmCtx{
+          _stage: Some(StmStage::Cases),
+          indent: this_ctx.indent.eq_to_gt(),
+        };
+        let mut cases = Vec::new();
+        loop {
+          let stm = match self.stm(inner_ctx)? {
+            None => {
+              break;
+            }
+            Some((stm, stm_ctx)) => {
+              if cases.is_empty() {
+                assert!(stm_ctx.indent.is_eq());
+                inner_ctx.indent = stm_ctx.indent;
+              }
+              stm
+            }
+          };
+          match stm {
+            Stm::_Case(_, None, body) => {
+              cases.push(body);
+            }
+            stm => {
+              return Err((stm.span(), ParseError::Expected(Token::Case)).into());
+            }
+          }
+        }
+        if cases.is_empty() {
+          return Err((self.cur_span(), ParseError::ExpectedStm).into());
+        }
+        let span = start.hull(self.pos());
+        self.tokens.flag |= TokenizerFlag_::PYTHIA;
+        return Ok(Some((Stm::Cases(span, cases), this_ctx)));
+      }
+      &Token::Case => {
+        _debugln!(self, "DEBUG: Parser::stm: case: tok={:?}", &cur.tok);
+        let start = cur.span.clone();
+        let pat = match this_ctx._stage {
+          Some(StmStage::Case) => {
+            // FIXME: spaces are required here.
+            self.maybe_spaces_deprecated();
+            Some(self.term(this_ctx.term())?)
+          }
+          // NB: a case of a cases stm has no pattern.
+          Some(StmStage::Cases) => None,
+          _ => return Err((cur.span, ParseError::Unexpected(cur.tok.clone())).into())
+        };
+        // FIXME: no spaces should occur here.
+        self.maybe_spaces_deprecated();
+        self.next();
+        let cur = self.cur();
+        match &cur.tok {
+          &Token::Colon => {
+          }
+          _ => return Err((self.cur_span(), ParseError::Expected(Token::Colon)).into())
+        }
+        let mut inner_ctx = StmCtx{
+          _stage: None,
+          indent: this_ctx.indent.eq_to_gt(),
+        };
+        let mut body = Vec::new();
+        let stm = match self.stm(inner_ctx)? {
+          None => {
+            return Err((self.cur_span(), ParseError::ExpectedStm).into());
+          }
+          Some((stm, stm_ctx)) => {
+            assert!(stm_ctx.indent.is_eq());
+            inner_ctx.indent = stm_ctx.indent;
+            stm
+          }
+        };
+        body.push(stm.into());
+        loop {
+          let stm = match self.stm(inner_ctx)? {
+            None => {
+              break;
+            }
+            Some((stm, _)) => stm
+          };
+          body.push(stm.into());
+        }
+        let span = start.hull(self.pos());
+        return Ok(Some((Stm::_Case(span, pat.map(|pat| pat.into()), body), this_ctx)));
+      }
+      &Token::With => {
+        _debugln!(self, "DEBUG: Parser::stm: with ctx: tok={:?}", &cur.tok);
+        let start = cur.span.clone();
+        self.maybe_spaces_deprecated();
+        let head = self.term(this_ctx.term())?;
+        match &head {
+          &Term::Ident(..) => {
+          }
+          &Term::Apply(..) => {
+          }
+          _ => return Err((self.cur_span(), ParseError::ExpectedIdent).into())
+        }
+        self.maybe_spaces_deprecated();
+        self.next();
+        let cur = self.cur();
+        match &cur.tok {
+          &Token::Colon => {
+          }
+          _ => return Err((self.cur_span(), ParseError::Expected(Token::Colon)).into())
+        }
+        // TODO
+        let mut body = Vec::new();
+        let mut inner_ctx = StmCtx{
+          _stage: None,
+          indent: this_ctx.indent.eq_to_gt(),
+        };
+        let stm = match self.stm(inner_ctx)? {
+          None => {
+            return Err((self.cur_span(), ParseError::ExpectedStm).into());
+          }
+          Some((stm, stm_ctx)) => {
+            assert!(stm_ctx.indent.is_eq());
+            inner_ctx.indent = stm_ctx.indent;
+            stm
+          }
         };
         body.push(stm.into());
         loop {
@@ -1633,6 +2564,59 @@ impl<S: AsRef<str>> Parser<S> {
         _debugln!(self, "DEBUG: Parser::stm: ok: with");
         return Ok(Some((Stm::With(span, head.into(), body), this_ctx)));
       }
+      &Token::Class => {
+        _debugln!(self, "DEBUG: Parser::stm: class: tok={:?}", &cur.tok);
+        let start = cur.span.clone();
+        // FIXME: spaces are required here.
+        self.maybe_spaces_deprecated();
+        // NB: base classes are not (yet) supported, but an empty base
+        // list `class C():` is accepted.
+        let head = match self.term(this_ctx.term())? {
+          Term::Ident(_, s) => s,
+          Term::Apply(span, tup) => match (&*tup[0], tup.len()) {
+            (&Term::Ident(_, ref s), 1) => s.clone(),
+            (_, 1) => return Err((span, ParseError::ExpectedIdent).into()),
+            _ => return Err((tup[1].span(), ParseError::Unimpl(Token::Class)).into())
+          },
+          _ => return Err((self.cur_span(), ParseError::ExpectedIdent).into())
+        };
+        self.maybe_spaces_deprecated();
+        self.next();
+        let cur = self.cur();
+        match &cur.tok {
+          &Token::Colon => {
+          }
+          _ => return Err((self.cur_span(), ParseError::Expected(Token::Colon)).into())
+        }
+        let mut inner_ctx = StmCtx{
+          _stage: None,
+          indent: this_ctx.indent.eq_to_gt(),
+        };
+        let mut body = Vec::new();
+        let stm = match self.stm(inner_ctx)? {
+          None => {
+            return Err((self.cur_span(), ParseError::ExpectedStm).into());
+          }
+          Some((stm, stm_ctx)) => {
+            assert!(stm_ctx.indent.is_eq());
+            inner_ctx.indent = stm_ctx.indent;
+            stm
+          }
+        };
+        body.push(stm.into());
+        loop {
+          let stm = match self.stm(inner_ctx)? {
+            None => {
+              break;
+            }
+            Some((stm, _)) => stm
+          };
+          body.push(stm.into());
+        }
+        let span = start.hull(self.pos());
+        _debugln!(self, "DEBUG: Parser::stm: ok: class");
+        return Ok(Some((Stm::Class(span, head, body), this_ctx)));
+      }
       &Token::Defmatch |
       &Token::Defproc |
       &Token::Def => {
@@ -1642,34 +2626,52 @@ impl<S: AsRef<str>> Parser<S> {
         // FIXME: spaces are required here.
         self.maybe_spaces_deprecated();
         // FIXME: failure cases (?).
-        let mut head = None;
         let mut params = Vec::new();
-        match self.term(this_ctx.term())? {
-          Term::Ident(_, s) => {
-            head = Some(s);
-          }
-          Term::Apply(_, tup) => {
-            match &*tup[0] {
-              &Term::Ident(_, ref s) => {
-                head = Some(s.clone());
-              }
-              _ => {}
-            }
-            for elt in tup[1 .. ].iter() {
-              match &**elt {
-                &Term::Ident(_, ref s) => {
-                  params.push(Some(s.clone()));
+        let mut rest = None;
+        let mut defaults = Vec::new();
+        let head = match &org_tok {
+          &Token::Def if self.mode == ParserMode::PythonCompat => {
+            self._py_def_head(this_ctx, &mut params, &mut rest, &mut defaults)?
+          }
+          _ => match self.term(this_ctx.term())? {
+            Term::Ident(_, s) => s,
+            Term::Apply(_, tup) => {
+              let head = match &*tup[0] {
+                &Term::Ident(_, ref s) => s.clone(),
+                _ => return Err((tup[0].span(), ParseError::ExpectedIdent).into())
+              };
+              for elt in tup[1 .. ].iter() {
+                if rest.is_some() {
+                  return Err((elt.span(), ParseError::Expected(Token::RParen)).into());
                 }
-                _ => {
-                  // FIXME
-                  params.push(None);
+                match &**elt {
+                  &Term::Ident(_, ref s) => {
+                    params.push(Some(s.clone()));
+                  }
+                  &Term::Splat(_, ref t) => match &**t {
+                    &Term::Ident(_, ref s) => {
+                      rest = Some(s.clone());
+                    }
+                    _ => return Err((t.span(), ParseError::ExpectedIdent).into())
+                  },
+                  _ => {
+                    // FIXME
+                    params.push(None);
+                  }
                 }
               }
+              head
             }
+            _ => return Err((self.cur_span(), ParseError::ExpectedIdent).into())
           }
-          _ => return Err((self.cur_span(), ParseError::ExpectedIdent).into())
+        };
+        match (&org_tok, rest.as_ref()) {
+          // NB: a defmatch does not (yet) take a rest param.
+          (&Token::Defmatch, Some(_)) => {
+            return Err((start, ParseError::Unimpl(Token::Star)).into());
+          }
+          _ => {}
         }
-        let head = head.unwrap();
         //let head = head.ok_or_else(|| (cur.span, ParseError::Unimpl(cur.tok.clone())).into())?;
         self.maybe_spaces_deprecated();
         self.next();
@@ -1684,6 +2686,7 @@ impl<S: AsRef<str>> Parser<S> {
           _stage: None,
           indent: this_ctx.indent.eq_to_gt(),
         };
+        let defaults: Vec<_> = defaults.into_iter().map(|(param, default)| (param, default.into())).collect();
         let mut body = Vec::new();
         let stm = match self.stm(inner_ctx)? {
           None => {
@@ -1715,16 +2718,87 @@ impl<S: AsRef<str>> Parser<S> {
           &Token::Defproc => {
             _debugln!(self, "DEBUG: Parser::stm: ok: defproc");
             self.tokens.flag |= TokenizerFlag_::PYTHIA;
-            return Ok(Some((Stm::Defproc(span, prefix.into_def(), head, params, body), this_ctx)));
+            return Ok(Some((Stm::Defproc(span, prefix.into_def(), head, params, rest, defaults, body), this_ctx)));
           }
           &Token::Def => {
             _debugln!(self, "DEBUG: Parser::stm: ok: def");
             self.tokens.flag |= TokenizerFlag_::PYTHON;
-            return Ok(Some((Stm::Defproc(span, prefix.into_def(), head, params, body), this_ctx)));
+            return Ok(Some((Stm::Defproc(span, prefix.into_def(), head, params, rest, defaults, body), this_ctx)));
           }
           _ => {}
         }
       }
+      &Token::Defrule => {
+        _debugln!(self, "DEBUG: Parser::stm: defrule: tok={:?}", &cur.tok);
+        let start = cur.span.clone();
+        match prefix {
+          // NB: a defrule is already a rule.
+          StmPrefix::Rule => {
+            return Err((start, ParseError::Unimpl(Token::Defrule)).into());
+          }
+          _ => {}
+        }
+        // FIXME: spaces are required here.
+        self.maybe_spaces_deprecated();
+        let (head, args) = match self.term(this_ctx.term())? {
+          Term::Ident(_, s) => (s, Vec::new()),
+          Term::Apply(_, mut tup) => {
+            let head = match &*tup[0] {
+              &Term::Ident(_, ref s) => s.clone(),
+              _ => return Err((tup[0].span(), ParseError::ExpectedIdent).into())
+            };
+            let args: Vec<_> = tup.drain(1 .. ).collect();
+            for arg in args.iter() {
+              match &**arg {
+                &Term::Splat(..) |
+                &Term::Kwarg(..) => {
+                  return Err((arg.span(), ParseError::Unimpl(Token::Defrule)).into());
+                }
+                _ => {}
+              }
+            }
+            (head, args)
+          }
+          _ => return Err((self.cur_span(), ParseError::ExpectedIdent).into())
+        };
+        self.maybe_spaces_deprecated();
+        self.next();
+        let cur = self.cur();
+        match &cur.tok {
+          &Token::Colon => {
+          }
+          _ => return Err((self.cur_span(), ParseError::Expected(Token::Colon)).into())
+        }
+        let mut inner_ctx = StmCtx{
+          _stage: None,
+          indent: this_ctx.indent.eq_to_gt(),
+        };
+        let mut body = Vec::new();
+        let stm = match self.stm(inner_ctx)? {
+          None => {
+            return Err((self.cur_span(), ParseError::ExpectedStm).into());
+          }
+          Some((stm, stm_ctx)) => {
+            assert!(stm_ctx.indent.is_eq());
+            inner_ctx.indent = stm_ctx.indent;
+            stm
+          }
+        };
+        body.push(stm.into());
+        loop {
+          let stm = match self.stm(inner_ctx)? {
+            None => {
+              break;
+            }
+            Some((stm, _)) => stm
+          };
+          body.push(stm.into());
+        }
+        let span = start.hull(self.pos());
+        _debugln!(self, "DEBUG: Parser::stm: ok: defrule");
+        self.tokens.flag |= TokenizerFlag_::PYTHIA;
+        return Ok(Some((Stm::Defrule(span, head, args, body), this_ctx)));
+      }
       &Token::TTTickQuote => {
         _debugln!(self, "DEBUG: Parser::stm: block quote: tok={:?}", &cur.tok);
         let start = cur.span.clone();
@@ -1766,6 +2840,92 @@ impl<S: AsRef<str>> Parser<S> {
       }
       _ => {}
     }
+    if self.mode == ParserMode::PythonCompat {
+      match &cur.tok {
+        &Token::Not |
+        &Token::LParen |
+        &Token::LBrack => {
+          _debugln!(self, "DEBUG: Parser::stm: py: just terms: tok={:?}", &cur.tok);
+          self.restore(&cur.span);
+          let t = self.term(this_ctx.term())?;
+          let span = cur.span.hull(self.pos());
+          return Ok(Some((Stm::Just(span, t.into()), this_ctx)));
+        }
+        &Token::Return |
+        &Token::Break |
+        &Token::Continue => {
+          _debugln!(self, "DEBUG: Parser::stm: py: return/break/continue: tok={:?}", &cur.tok);
+          let head = match &cur.tok {
+            &Token::Return => "__py_return__",
+            &Token::Break => "__py_break__",
+            _ => "__py_continue__"
+          };
+          let mut args = Vec::new();
+          loop {
+            self.next();
+            let next = self.cur();
+            match &next.tok {
+              &Token::Space => {}
+              &Token::NL |
+              &Token::CR |
+              &Token::Comment(_) |
+              &Token::_Eof => {
+                self.restore(&next.span);
+                break;
+              }
+              _ => {
+                self.restore(&next.span);
+                match &cur.tok {
+                  &Token::Return => {
+                    args.push(self.term(this_ctx.term())?);
+                  }
+                  _ => return Err((next.span, ParseError::Unexpected(next.tok.clone())).into())
+                }
+                break;
+              }
+            }
+          }
+          let span = cur.span.hull(self.pos());
+          let term = _py_call(span.clone(), head, args);
+          return Ok(Some((Stm::Just(span, term.into()), this_ctx)));
+        }
+        &Token::While |
+        &Token::For => {
+          _debugln!(self, "DEBUG: Parser::stm: py: while/for: tok={:?}", &cur.tok);
+          let start = cur.span.clone();
+          // FIXME: spaces are required here.
+          self.maybe_spaces_deprecated();
+          let header = self.term(this_ctx.term())?;
+          let hspan = start.hull(header.span());
+          let head = match &cur.tok {
+            &Token::While => {
+              _py_call(hspan, "__py_while__", vec![header])
+            }
+            _ => match _py_split_in(header) {
+              None => {
+                return Err((hspan, ParseError::Expected(Token::In)).into());
+              }
+              Some((target, iter)) => {
+                _py_call(hspan, "__py_for__", vec![target, iter])
+              }
+            }
+          };
+          // FIXME: no spaces should occur here.
+          self.maybe_spaces_deprecated();
+          self.next();
+          let next = self.cur();
+          match &next.tok {
+            &Token::Colon => {}
+            _ => return Err((next.span, ParseError::Expected(Token::Colon)).into())
+          }
+          let body = self._block_body(this_ctx)?;
+          let span = start.hull(self.pos());
+          self.tokens.flag |= TokenizerFlag_::PYTHON;
+          return Ok(Some((Stm::With(span, head.into(), body), this_ctx)));
+        }
+        _ => {}
+      }
+    }
     match &cur.tok {
       &Token::Break |
       &Token::Continue |
@@ -1833,6 +2993,185 @@ impl<S: AsRef<str>> Parser<S> {
     Ok(())
   }
 
+  // Parses the (rest of the) comparison operator that starts at `cur`,
+  // and returns the ident of its lowering (see `ParserMode`).
+  pub fn _py_cmp_op(&mut self, ctx_indent: RawIndent, cur: &SpanToken) -> Result<&'static str, ParseSpanError> {
+    match &cur.tok {
+      &Token::Lt => Ok("__py_lt__"),
+      &Token::LtEq => Ok("__py_le__"),
+      &Token::Gt => Ok("__py_gt__"),
+      &Token::GtEq => Ok("__py_ge__"),
+      &Token::EqEq => Ok("__py_eq__"),
+      &Token::BangEq => Ok("__py_ne__"),
+      &Token::In => Ok("__py_in__"),
+      &Token::Not => {
+        self.maybe_term_spaces(ctx_indent)?;
+        self.next();
+        let next = self.cur();
+        match &next.tok {
+          &Token::In => Ok("__py_not_in__"),
+          _ => Err((next.span, ParseError::Expected(Token::In)).into())
+        }
+      }
+      &Token::Is => {
+        self.maybe_term_spaces(ctx_indent)?;
+        self.next();
+        let next = self.cur();
+        match &next.tok {
+          &Token::Not => Ok("__py_is_not__"),
+          _ => {
+            self.restore(&next.span);
+            Ok("__py_is__")
+          }
+        }
+      }
+      _ => Err((cur.span.clone(), ParseError::Unexpected(cur.tok.clone())).into())
+    }
+  }
+
+  // Parses the head `f(a, b=1, *rest)` of a python-style def, and returns
+  // the ident `f`; the params are pushed onto `params`, the rest param (if
+  // any) is set in `rest`, and the defaults are pushed onto `defaults`.
+  pub fn _py_def_head(&mut self, ctx: StmCtx, params: &mut Vec<Option<Ident>>, rest: &mut Option<Ident>, defaults: &mut Vec<(Ident, Term)>) -> Result<Ident, ParseSpanError> {
+    self.next();
+    let cur = self.cur();
+    let head = match &cur.tok {
+      &Token::Ident(s) => self.tokens.resolve(s),
+      _ => return Err((cur.span, ParseError::ExpectedIdent).into())
+    };
+    self.maybe_spaces_deprecated();
+    self.next();
+    let cur = self.cur();
+    match &cur.tok {
+      &Token::LParen => {}
+      _ => return Err((cur.span, ParseError::Expected(Token::LParen)).into())
+    }
+    loop {
+      self.maybe_spaces_deprecated();
+      self.next();
+      let cur = self.cur();
+      let param = match &cur.tok {
+        // NB: this also accepts a trailing comma.
+        &Token::RParen => break,
+        &Token::Ident(s) => Some(self.tokens.resolve(s)),
+        &Token::PlaceIdent(_) => None,
+        &Token::Star => {
+          // NB: the rest param must be the last param.
+          self.maybe_spaces_deprecated();
+          self.next();
+          let cur = self.cur();
+          match &cur.tok {
+            &Token::Ident(s) => {
+              *rest = Some(self.tokens.resolve(s));
+            }
+            _ => return Err((cur.span, ParseError::ExpectedIdent).into())
+          }
+          self.maybe_spaces_deprecated();
+          self.next();
+          let mut cur = self.cur();
+          if let &Token::Comma = &cur.tok {
+            self.maybe_spaces_deprecated();
+            self.next();
+            cur = self.cur();
+          }
+          match &cur.tok {
+            &Token::RParen => break,
+            _ => return Err((cur.span, ParseError::Expected(Token::RParen)).into())
+          }
+        }
+        _ => return Err((cur.span, ParseError::ExpectedIdent).into())
+      };
+      self.maybe_spaces_deprecated();
+      self.next();
+      let mut cur = self.cur();
+      match &cur.tok {
+        &Token::Equal => {
+          let mut term_ctx = ctx.term();
+          term_ctx.bp = self.lbp(&Token::Comma);
+          let default = self.term(term_ctx)?;
+          match param.as_ref() {
+            None => {
+              return Err((cur.span, ParseError::ExpectedIdent).into());
+            }
+            Some(param) => {
+              defaults.push((param.clone(), default));
+            }
+          }
+          self.maybe_spaces_deprecated();
+          self.next();
+          cur = self.cur();
+        }
+        _ => {}
+      }
+      params.push(param);
+      match &cur.tok {
+        &Token::Comma => {}
+        &Token::RParen => break,
+        _ => return Err((cur.span, ParseError::Expected(Token::RParen)).into())
+      }
+    }
+    Ok(head)
+  }
+
+  // Parses a call arg in `PythonCompat` mode: either a keyword arg `k=v`,
+  // or a positional arg.
+  pub fn _py_call_arg(&mut self, ctx: TermCtx) -> Result<Term, ParseSpanError> {
+    let mut arg_ctx = ctx;
+    arg_ctx.bp = self.lbp(&Token::Comma);
+    let lterm = self.term(arg_ctx)?;
+    self.maybe_term_spaces(ctx.indent)?;
+    self.next();
+    let cur = self.cur();
+    match (&cur.tok, lterm) {
+      (&Token::Equal, Term::Ident(span, k)) => {
+        let rterm = self.term(arg_ctx)?;
+        let span = span.hull(rterm.span());
+        Ok(Term::Kwarg(span, k, rterm.into()))
+      }
+      (&Token::Comma, lterm) |
+      (&Token::RParen, lterm) => {
+        self.restore(&cur.span);
+        Ok(lterm)
+      }
+      (_, lterm) => {
+        // NB: an arg w/ a looser infix op is parsed as in `Pythia` mode.
+        self.restore(&cur.span);
+        self._term_led(ctx, lterm)
+      }
+    }
+  }
+
+  // Parses the (non-empty) indented block of a compound stm, after its
+  // trailing `:`.
+  pub fn _block_body(&mut self, this_ctx: StmCtx) -> Result<Vec<StmRef>, ParseSpanError> {
+    let mut inner_ctx = StmCtx{
+      _stage: None,
+      indent: this_ctx.indent.eq_to_gt(),
+    };
+    let mut body = Vec::new();
+    let stm = match self.stm(inner_ctx)? {
+      None => {
+        return Err((self.cur_span(), ParseError::ExpectedStm).into());
+      }
+      Some((stm, stm_ctx)) => {
+        assert!(stm_ctx.indent.is_eq());
+        inner_ctx.indent = stm_ctx.indent;
+        stm
+      }
+    };
+    body.push(stm.into());
+    loop {
+      let stm = match self.stm(inner_ctx)? {
+        None => {
+          break;
+        }
+        Some((stm, _)) => stm
+      };
+      body.push(stm.into());
+    }
+    Ok(body)
+  }
+
   pub fn term_nud(&mut self, ctx_indent: RawIndent, cur: SpanToken) -> Result<Term, ParseSpanError> {
     // TODO TODO
     let this_ctx = TermCtx{
@@ -1841,19 +3180,20 @@ impl<S: AsRef<str>> Parser<S> {
       //bp: self.lbp(&tok),
     };
     match &cur.tok {
-      &Token::Indent(0) => {
-        panic!("bug: Parser::term_nud: unexpected Indent(0)");
-      }
       &Token::Indent(_) |
       &Token::Space |
       &Token::NL |
       &Token::CR |
       &Token::Comment(_) => {
-        panic!("bug: Parser::term_nud: tok={:?}", &cur.tok);
+        _debugln!(self, "DEBUG: Parser::term_nud: unexpected: tok={:?}", &cur.tok);
+        return Err((cur.span, ParseError::Unexpected(cur.tok)).into());
       }
       &Token::_Eof => {
         return Err((cur.span, ParseError::Eof).into());
       }
+      &Token::_Error(_, e) => {
+        return Err((cur.span, ParseError::MalformedString(e)).into());
+      }
       &Token::None => {
         return Ok(Term::NoneLit(cur.span, "None".into()));
       }
@@ -1871,20 +3211,116 @@ impl<S: AsRef<str>> Parser<S> {
         //return Ok(Term::StrLit(cur.span, s.clone()));
         return Ok(Term::AtomLit(cur.span, s.clone()));
       }
-      &Token::PlaceIdent(ref s) => {
+      &Token::TTTickQuote => {
+        // NB: the block is parsed as for a quote stm (at the indent of
+        // the term), up to and incl. the closing "```".
+        self.restore(&cur.span);
+        let ctx = StmCtx{
+          _stage: None,
+          indent: StmIndent::Eq(ctx_indent),
+        };
+        match self._stm(ctx, StmPrefix::default())? {
+          Some((Stm::Quote(span, _, body), _)) => {
+            return Ok(Term::Quote(span, body));
+          }
+          _ => return Err((cur.span, ParseError::ExpectedStm).into())
+        }
+      }
+      &Token::PlaceIdent(s) => {
         // FIXME: place ident term?
-        return Ok(Term::Ident(cur.span, s.clone()));
+        return Ok(Term::Ident(cur.span, self.tokens.resolve(s)));
         //return Ok(Term::PlaceIdent(cur.span, ()));
       }
-      &Token::Ident(ref s) => {
+      &Token::Ident(s) => {
         // FIXME
-        return Ok(Term::Ident(cur.span, s.clone()));
+        return Ok(Term::Ident(cur.span, self.tokens.resolve(s)));
       }
       &Token::DashSlash => {
         self.maybe_term_spaces(ctx_indent)?;
         let rterm = self.term(this_ctx)?;
         return Ok(Term::Neg(cur.span, rterm.into()));
       }
+      &Token::Lambda => {
+        let start = cur.span.clone();
+        let mut params = Vec::new();
+        loop {
+          self.maybe_term_spaces(ctx_indent)?;
+          self.next();
+          let cur = self.cur();
+          match &cur.tok {
+            // NB: this also accepts a trailing comma.
+            &Token::Colon => break,
+            &Token::Ident(s) => params.push(Some(self.tokens.resolve(s))),
+            &Token::PlaceIdent(_) => params.push(None),
+            _ => return Err((cur.span, ParseError::ExpectedIdent).into())
+          }
+          self.maybe_term_spaces(ctx_indent)?;
+          self.next();
+          let cur = self.cur();
+          match &cur.tok {
+            &Token::Comma => {}
+            &Token::Colon => break,
+            _ => return Err((cur.span, ParseError::Expected(Token::Colon)).into())
+          }
+        }
+        // NB: in `PythonCompat` mode, the body binds more tightly than a
+        // comma (as in Python); in `Pythia` mode, where a comma binds more
+        // tightly than a call, the body extends up to an `=`.
+        let mut this_ctx = this_ctx;
+        this_ctx.bp = if self.mode == ParserMode::PythonCompat {
+          self.lbp(&Token::Comma)
+        } else {
+          self.lbp(&Token::Equal)
+        };
+        self.maybe_term_spaces(ctx_indent)?;
+        let body = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::Lambda(span, params, body.into()));
+      }
+      &Token::Star => {
+        // NB: a splat `*t` binds more tightly than a comma, so that it
+        // is one arg of an arg list.
+        let start = cur.span.clone();
+        let mut this_ctx = this_ctx;
+        this_ctx.bp = self.lbp(&Token::Comma);
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::Splat(span, rterm.into()));
+      }
+      &Token::Not if self.mode == ParserMode::PythonCompat => {
+        let start = cur.span.clone();
+        let mut this_ctx = this_ctx;
+        // NB: `not` binds more loosely than a comparison, but more
+        // tightly than `and`.
+        this_ctx.bp = self.lbp(&Token::And) + 10;
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(_py_call(span, "__py_not__", vec![rterm]));
+      }
+      &Token::Not => {
+        let start = cur.span.clone();
+        let mut this_ctx = this_ctx;
+        // NB: `not` binds more loosely than a unification, but more
+        // tightly than `and`.
+        this_ctx.bp = self.lbp(&Token::And) + 1;
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::Not(span, rterm.into()));
+      }
+      &Token::Await => {
+        let start = cur.span.clone();
+        let mut this_ctx = this_ctx;
+        // NB: `await` binds more loosely than a call (or a bunch), but
+        // more tightly than a unification.
+        this_ctx.bp = self.lbp(&Token::Equal);
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::Await(span, rterm.into()));
+      }
       &Token::LParen => {
         let start = cur.span.clone();
         self.maybe_term_spaces(ctx_indent)?;
@@ -1915,6 +3351,12 @@ impl<S: AsRef<str>> Parser<S> {
         }
         self.restore(&cur.span);
         let term = self.term(this_ctx)?;
+        self.next();
+        let next = self.cur();
+        if let &Token::For = &next.tok {
+          return self._list_comp(ctx_indent, start, term);
+        }
+        self.restore(&next.span);
         match term {
           Term::Bunch(_, tup) => {
             self.next();
@@ -1935,8 +3377,61 @@ impl<S: AsRef<str>> Parser<S> {
       }
       _ => {}
     }
-    _debugln!(self, "DEBUG: Parser::term_nud: unimpl: span={:?} tok={:?}", cur.span, &cur.tok);
-    unimplemented!();
+    _debugln!(self, "DEBUG: Parser::term_nud: unexpected: span={:?} tok={:?}", cur.span, &cur.tok);
+    Err((cur.span, ParseError::Unexpected(cur.tok)).into())
+  }
+
+  // Parses the rest `x in xs if c]` of a list comprehension w/ the elt
+  // `elt`, after the `for` (see `Term::ListComp`).
+  pub fn _list_comp(&mut self, ctx_indent: RawIndent, start: Span, elt: Term) -> Result<Term, ParseSpanError> {
+    let ctx = TermCtx{
+      indent: ctx_indent,
+      bp: 0,
+    };
+    self.maybe_term_spaces(ctx_indent)?;
+    self.next();
+    let cur = self.cur();
+    // NB: the target is not parsed as a term, b/c `x in xs` is itself a
+    // term in `PythonCompat` mode.
+    let target = match &cur.tok {
+      &Token::Ident(s) => Some(self.tokens.resolve(s)),
+      &Token::PlaceIdent(_) => None,
+      _ => return Err((cur.span, ParseError::ExpectedIdent).into())
+    };
+    self.maybe_term_spaces(ctx_indent)?;
+    self.next();
+    let mut cur = self.cur();
+    let mut choice = false;
+    match &cur.tok {
+      &Token::Ident(s) if self.tokens.resolve(s).as_raw_str() == "choice" => {
+        choice = true;
+        self.maybe_term_spaces(ctx_indent)?;
+        self.next();
+        cur = self.cur();
+      }
+      _ => {}
+    }
+    match &cur.tok {
+      &Token::In => {}
+      _ => return Err((cur.span, ParseError::Expected(Token::In)).into())
+    }
+    self.maybe_term_spaces(ctx_indent)?;
+    let iter = self.term(ctx)?;
+    self.next();
+    let mut cur = self.cur();
+    let mut cond = None;
+    if let &Token::If = &cur.tok {
+      self.maybe_term_spaces(ctx_indent)?;
+      cond = Some(self.term(ctx)?.into());
+      self.next();
+      cur = self.cur();
+    }
+    match &cur.tok {
+      &Token::RBrack => {}
+      _ => return Err((cur.span, ParseError::Expected(Token::RBrack)).into())
+    }
+    let span = start.hull(self.pos());
+    Ok(Term::ListComp(span, elt.into(), target, choice, iter.into(), cond))
   }
 
   pub fn term_led(&mut self, ctx_indent: RawIndent, mut lterm: Term, cur: SpanToken) -> Result<Term, ParseSpanError> {
@@ -1950,7 +3445,7 @@ impl<S: AsRef<str>> Parser<S> {
       &Token::NL |
       &Token::CR |
       &Token::Comment(_) => {
-        panic!("bug");
+        return Err((cur.span, ParseError::Unexpected(cur.tok)).into());
       }
       &Token::_Eof => {
         return Err((cur.span, ParseError::Eof).into());
@@ -1967,6 +3462,84 @@ impl<S: AsRef<str>> Parser<S> {
         let span = lterm.span();
         return Ok(Term::Query(span, lterm.into()));
       }
+      &Token::And |
+      &Token::Or if self.mode == ParserMode::PythonCompat => {
+        let start = lterm.span();
+        let head = match &cur.tok {
+          &Token::And => "__py_and__",
+          _ => "__py_or__"
+        };
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(_py_call(span, head, vec![lterm, rterm]));
+      }
+      &Token::And => {
+        let start = lterm.span();
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::And(span, lterm.into(), rterm.into()));
+      }
+      &Token::Or => {
+        let start = lterm.span();
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::Or(span, lterm.into(), rterm.into()));
+      }
+      tok if self.mode == ParserMode::PythonCompat && _py_cmp_tok(tok) => {
+        let start = lterm.span();
+        let mut ops = vec![self._py_cmp_op(ctx_indent, &cur)?];
+        let mut operands = vec![lterm];
+        loop {
+          self.maybe_term_spaces(ctx_indent)?;
+          let rterm = self.term(this_ctx)?;
+          operands.push(rterm);
+          // NB: the rest of a comparison chain is parsed here, rather
+          // than by the caller, so that the chain is not confused w/ a
+          // (parenthesized) comparison on the left.
+          match self.maybe_term_spaces(ctx_indent) {
+            Ok(_) => {}
+            Err(_) => break
+          }
+          self.next();
+          let mut next = self.cur();
+          match &next.tok {
+            &Token::Indent(0) => {
+              self.next();
+              next = self.cur();
+            }
+            _ => {}
+          };
+          if !_py_cmp_tok(&next.tok) {
+            self.restore(&next.span);
+            break;
+          }
+          ops.push(self._py_cmp_op(ctx_indent, &next)?);
+        }
+        let mut cmps = Vec::with_capacity(ops.len());
+        for (i, op) in ops.into_iter().enumerate() {
+          let lterm = operands[i].clone();
+          let rterm = operands[i + 1].clone();
+          let span = lterm.span().hull(rterm.span());
+          cmps.push(_py_call(span, op, vec![lterm, rterm]));
+        }
+        let mut cmps = cmps.into_iter();
+        let mut term = cmps.next().unwrap();
+        for cmp in cmps {
+          let span = start.hull(cmp.span());
+          term = _py_call(span, "__py_and__", vec![term, cmp]);
+        }
+        return Ok(term);
+      }
+      &Token::In => {
+        let start = lterm.span();
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::In(span, lterm.into(), rterm.into()));
+      }
       &Token::Equal => {
         //_traceln!(self, "DEBUG: Parser::term_led: Equal: tok={:?}", &cur.tok);
         let start = lterm.span();
@@ -2022,9 +3595,36 @@ impl<S: AsRef<str>> Parser<S> {
         let span = start.hull(self.pos());
         return Ok(Term::BindR(span, lterm.into(), rterm.into()));
       }
+      &Token::LDotEq => {
+        let mut this_ctx = this_ctx;
+        this_ctx.bp -= 1;
+        let start = lterm.span();
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::RebindL(span, lterm.into(), rterm.into()));
+      }
+      &Token::RDotEq => {
+        let start = lterm.span();
+        self.maybe_term_spaces(ctx_indent)?;
+        let rterm = self.term(this_ctx)?;
+        let span = start.hull(self.pos());
+        return Ok(Term::RebindR(span, lterm.into(), rterm.into()));
+      }
       &Token::Comma => {
         let start = lterm.span();
         self.maybe_term_spaces(ctx_indent)?;
+        // NB: a trailing comma before a `)`, as in the 1-tuple `(x,)`.
+        self.next();
+        let next = self.cur();
+        self.restore(&next.span);
+        if let &Token::RParen = &next.tok {
+          if let &Term::Bunch(..) = &lterm {
+            return Ok(lterm);
+          }
+          let span = start.hull(self.pos());
+          return Ok(Term::Bunch(span, vec![lterm.into()]));
+        }
         let rterm = self.term(this_ctx)?;
         match &mut lterm {
           &mut Term::Bunch(ref mut span, ref mut tup) => {
@@ -2037,17 +3637,25 @@ impl<S: AsRef<str>> Parser<S> {
         let span = start.hull(self.pos());
         return Ok(Term::Bunch(span, vec![lterm.into(), rterm.into()]));
       }
-      &Token::DotIdent(_) => {
+      &Token::DotIdent(s) => {
         let start = lterm.span();
-        self.maybe_term_spaces(ctx_indent)?;
+        let dot_span = cur.span.clone();
+        // NB: the dotident (sans the leading '.') is the head of rtup.
+        let head = Term::Ident(cur.span, self.tokens.resolve(s));
+        // NB: the lookahead for an arg tuple only skips spaces, and does
+        // not cross a newline, which may be followed by the dedent at the
+        // end of a block (e.g. after an attribute as the last stm).
         self.next();
-        let cur = self.cur();
+        let mut cur = self.cur();
+        while let &Token::Space = &cur.tok {
+          self.next();
+          cur = self.cur();
+        }
         match &cur.tok {
           &Token::LParen => {
             let mut this_ctx = this_ctx;
             this_ctx.bp = 0;
-            // FIXME: rtup needs the dotident (above) as head.
-            let mut rtup = Vec::new();
+            let mut rtup: Vec<TermRef> = vec![head.into()];
             let mut rterm = self.term(this_ctx)?;
             match &mut rterm {
               &mut Term::Bunch(_, ref mut tup_) => {
@@ -2059,7 +3667,7 @@ impl<S: AsRef<str>> Parser<S> {
                 match &cur.tok {
                   &Token::RParen => {}
                   _ => {
-                    // FIXME: err.
+                    return Err((cur.span, ParseError::Expected(Token::RParen)).into());
                   }
                 }
                 let span = start.hull(self.pos());
@@ -2074,7 +3682,7 @@ impl<S: AsRef<str>> Parser<S> {
             match &cur.tok {
               &Token::RParen => {}
               _ => {
-                // FIXME: err.
+                return Err((cur.span, ParseError::Expected(Token::RParen)).into());
               }
             }
             let span = start.hull(self.pos());
@@ -2082,7 +3690,11 @@ impl<S: AsRef<str>> Parser<S> {
           }
           _ => {}
         }
-        unimplemented!();
+        // NB: a dotident w/out an arg tuple is an attribute (see
+        // `TermCode_::QualIdent`).
+        self.restore(&cur.span);
+        let span = start.hull(dot_span);
+        return Ok(Term::QualIdent(span, lterm.into(), self.tokens.resolve(s)));
       }
       &Token::LParen => {
         let mut this_ctx = this_ctx;
@@ -2112,7 +3724,11 @@ impl<S: AsRef<str>> Parser<S> {
             _ => {}
           }
           self.restore(&cur.span);
-          let rterm = self.term(this_ctx)?;
+          let rterm = if self.mode == ParserMode::PythonCompat {
+            self._py_call_arg(this_ctx)?
+          } else {
+            self.term(this_ctx)?
+          };
           tup.push(rterm.into());
           self.maybe_term_spaces(ctx_indent)?;
           self.next();
@@ -2162,31 +3778,518 @@ impl<S: AsRef<str>> Parser<S> {
         self.maybe_term_spaces(ctx_indent)?;
         let rterm = self.term(this_ctx)?;
         self.maybe_term_spaces(ctx_indent)?;
+        self.next();
+        let cur = self.cur();
         match &cur.tok {
           &Token::RParen => {}
           _ => {
-            return Err((cur.span, ParseError::_Bot).into());
+            return Err((cur.span, ParseError::_Bot).into());
+          }
+        }
+        let span = start.hull(self.pos());
+        return Ok(Term::Subst(span, lterm.into(), rterm.into()));
+      }
+      _ => {
+        _debugln!(self, "DEBUG: Parser::term_led: unexpected: span={:?} tok={:?}", cur.span, &cur.tok);
+        return Err((cur.span, ParseError::Unexpected(cur.tok)).into());
+      }
+    }
+  }
+}
+
+//#[derive(Default)]
+pub struct Printer<S> {
+  buf:  S,
+  indent: RawIndent,
+  // NB: trivia (see `Trivia`) are sorted by offset; `trivia_ct` is
+  // the number of trivia already formatted.
+  trivia: Vec<(usize, Trivia)>,
+  trivia_ct: Cell<usize>,
+}
+
+impl<S> Printer<S> {
+  pub fn new(buf: S) -> Printer<S> {
+    Printer{buf, indent: 0, trivia: Vec::new(), trivia_ct: Cell::new(0)}
+  }
+
+  pub fn set_indent(&mut self, indent: RawIndent) {
+    self.indent = indent;
+  }
+
+  pub fn set_trivia(&mut self, trivia: BTreeMap<usize, Trivia>) {
+    self.trivia = trivia.into_iter().collect();
+    self.trivia_ct.set(0);
+  }
+
+  pub fn _indent(&self) -> RawIndent {
+    match self.indent {
+      0 => 4,
+      x => x
+    }
+  }
+}
+
+impl<S: AsRef<str>> Printer<S> {
+  // The format mode: unlike `pretty_print`, this emits canonical source
+  // (w/out spans) that re-parses to an equivalent AST.
+  pub fn format(&self, mod_: &Mod) -> Result<String, ParseSpanError> {
+    let mut out = String::new();
+    self.trivia_ct.set(0);
+    for stm in mod_.body.iter() {
+      self._format_stm(stm, 0, &mut out)?;
+    }
+    self._format_leading_trivia(usize::max_value(), 0, &mut out);
+    Ok(out)
+  }
+
+  // Format the trivia before the offset `end`, each on its own line.
+  pub fn _format_leading_trivia(&self, end: usize, level: RawIndent, out: &mut String) {
+    let indent = self._indent();
+    let mut ct = self.trivia_ct.get();
+    while ct < self.trivia.len() && self.trivia[ct].0 < end {
+      match &self.trivia[ct].1 {
+        &Trivia::Blank => {
+          out.push('\n');
+        }
+        &Trivia::Comment(ref text) |
+        &Trivia::TrailingComment(ref text) => {
+          for _ in 0 .. level * indent {
+            out.push(' ');
+          }
+          out.push_str(text.as_raw_str());
+          out.push('\n');
+        }
+      }
+      ct += 1;
+    }
+    self.trivia_ct.set(ct);
+  }
+
+  // Format the next trivia if it is a trailing comment on the same line
+  // as the offset `start`.
+  pub fn _format_trailing_trivia(&self, start: usize, out: &mut String) {
+    let ct = self.trivia_ct.get();
+    if ct >= self.trivia.len() {
+      return;
+    }
+    match &self.trivia[ct] {
+      &(off, Trivia::TrailingComment(ref text)) if off > start => {
+        let line = self.buf.as_ref().get(start .. off).unwrap();
+        if line.contains(|c| c == '\n' || c == '\r') {
+          return;
+        }
+        out.push_str("  ");
+        out.push_str(text.as_raw_str());
+        self.trivia_ct.set(ct + 1);
+      }
+      _ => {}
+    }
+  }
+
+  pub fn _format_stm(&self, stm: &Stm, level: RawIndent, out: &mut String) -> Result<(), ParseSpanError> {
+    let indent = self._indent();
+    let start = stm.span().start;
+    self._format_leading_trivia(start, level, out);
+    for _ in 0 .. level * indent {
+      out.push(' ');
+    }
+    match stm {
+      &Stm::Just(_, ref term) => {
+        self._format_term(term, out)?;
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Pass(_) => {
+        out.push_str("pass");
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Global(_, ref ident) => {
+        out.push_str(&format!("global {}", ident));
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Nonlocal(_, None, ref ident) => {
+        out.push_str(&format!("nonlocal {}", ident));
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Nonlocal(_, Some(static_scope), ref ident) => {
+        out.push_str(&format!("nonlocal [{}] {}", static_scope, ident));
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Assert(_, ref term) => {
+        out.push_str("assert ");
+        self._format_term(term, out)?;
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Expect(_, ref lterm, ref rterm) => {
+        out.push_str("expect ");
+        self._format_term(lterm, out)?;
+        out.push_str(" == ");
+        self._format_term(rterm, out)?;
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Fresh(_, ref idents) => {
+        out.push_str("fresh ");
+        for (i, ident) in idents.iter().enumerate() {
+          if i > 0 {
+            out.push_str(", ");
+          }
+          out.push_str(ident.as_raw_str());
+        }
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::Import(_, ref idents) => {
+        out.push_str("import ");
+        for (i, ident) in idents.iter().enumerate() {
+          if i > 0 {
+            out.push_str(", ");
+          }
+          out.push_str(ident.as_raw_str());
+        }
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+      }
+      &Stm::With(_, ref head, ref body) => {
+        out.push_str("with ");
+        self._format_term(head, out)?;
+        out.push(':');
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+        self._format_body(body, level + 1, out)?;
+      }
+      &Stm::If(_, ref cases, ref final_) => {
+        for (idx, &(ref cond, ref body)) in cases.iter().enumerate() {
+          if idx > 0 {
+            for _ in 0 .. level * indent {
+              out.push(' ');
+            }
+            out.push_str("elif ");
+          } else {
+            out.push_str("if ");
+          }
+          self._format_term(cond, out)?;
+          out.push(':');
+          if idx == 0 {
+            self._format_trailing_trivia(start, out);
+          }
+          out.push('\n');
+          self._format_body(body, level + 1, out)?;
+        }
+        if let Some(body) = final_.as_ref() {
+          for _ in 0 .. level * indent {
+            out.push(' ');
+          }
+          out.push_str("else:\n");
+          self._format_body(body, level + 1, out)?;
+        }
+      }
+      &Stm::Class(_, ref head, ref body) => {
+        out.push_str(&format!("class {}:", head));
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+        self._format_body(body, level + 1, out)?;
+      }
+      &Stm::Match(_, ref x, ref cases) => {
+        out.push_str("match ");
+        self._format_term(x, out)?;
+        out.push(':');
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+        for &(ref pat, ref body) in cases.iter() {
+          for _ in 0 .. (level + 1) * indent {
+            out.push(' ');
+          }
+          out.push_str("case ");
+          self._format_term(pat, out)?;
+          out.push_str(":\n");
+          self._format_body(body, level + 2, out)?;
+        }
+      }
+      &Stm::Cases(_, ref cases) => {
+        out.push_str("cases:");
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+        for body in cases.iter() {
+          for _ in 0 .. (level + 1) * indent {
+            out.push(' ');
+          }
+          out.push_str("case:\n");
+          self._format_body(body, level + 2, out)?;
+        }
+      }
+      &Stm::Defmatch(_, prefix, ref head, ref params, ref body) |
+      &Stm::Defproc(_, prefix, ref head, ref params, _, _, ref body) => {
+        let (rest, defaults): (Option<&Ident>, &[(Ident, TermRef)]) = match stm {
+          &Stm::Defproc(.., ref rest, ref defaults, _) => (rest.as_ref(), defaults),
+          _ => (None, &[])
+        };
+        match prefix {
+          None => {}
+          Some(DefPrefix::Rule) => {
+            out.push_str("rule ");
+          }
+        }
+        match stm {
+          &Stm::Defmatch(..) => out.push_str("defmatch "),
+          _ => out.push_str("defproc ")
+        }
+        out.push_str(head.as_raw_str());
+        out.push('(');
+        for (idx, param) in params.iter().enumerate() {
+          match param.as_ref() {
+            None => out.push('_'),
+            Some(param) => {
+              out.push_str(param.as_raw_str());
+              if let Some(&(_, ref default)) = defaults.iter().find(|&&(ref k, _)| k == param) {
+                out.push('=');
+                self._format_term(default, out)?;
+              }
+            }
+          }
+          if idx + 1 < params.len() || rest.is_some() {
+            out.push_str(", ");
+          }
+        }
+        if let Some(rest) = rest {
+          out.push('*');
+          out.push_str(rest.as_raw_str());
+        }
+        out.push_str("):");
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+        self._format_body(body, level + 1, out)?;
+      }
+      &Stm::Defrule(_, ref head, ref args, ref body) => {
+        out.push_str("defrule ");
+        out.push_str(head.as_raw_str());
+        out.push('(');
+        for (idx, arg) in args.iter().enumerate() {
+          self._format_term(arg, out)?;
+          if idx + 1 < args.len() {
+            out.push_str(", ");
+          }
+        }
+        out.push_str("):");
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+        self._format_body(body, level + 1, out)?;
+      }
+      &Stm::Quote(_, .., ref body) => {
+        out.push_str("```quote");
+        self._format_trailing_trivia(start, out);
+        out.push('\n');
+        self._format_body(body, level, out)?;
+        for _ in 0 .. level * indent {
+          out.push(' ');
+        }
+        out.push_str("```\n");
+      }
+      // NB: the remaining stms are not (yet) produced by the parser, so
+      // they are copied verbatim.
+      _ => {
+        out.push_str(self._snippet(&stm.span()).trim_end());
+        out.push('\n');
+      }
+    }
+    Ok(())
+  }
+
+  pub fn _format_body(&self, body: &[StmRef], level: RawIndent, out: &mut String) -> Result<(), ParseSpanError> {
+    for stm in body.iter() {
+      self._format_stm(stm, level, out)?;
+    }
+    Ok(())
+  }
+
+  pub fn _format_terms(&self, terms: &[TermRef], out: &mut String) -> Result<(), ParseSpanError> {
+    for (i, term) in terms.iter().enumerate() {
+      self._format_term(term, out)?;
+      if i + 1 < terms.len() {
+        out.push_str(", ");
+      }
+    }
+    Ok(())
+  }
+
+  pub fn _format_term(&self, term: &Term, out: &mut String) -> Result<(), ParseSpanError> {
+    match term {
+      &Term::Ident(_, ref s) |
+      &Term::AtomLit(_, ref s) |
+      &Term::NoneLit(_, ref s) |
+      &Term::BoolLit(_, ref s) |
+      &Term::IntLit(_, ref s) |
+      &Term::FloatLit(_, ref s) => {
+        // NB: atom lits retain their original quotes.
+        out.push_str(s.as_raw_str());
+      }
+      &Term::QualIdent(_, ref lterm, ref s) => {
+        // NB: the deprecated `x:f` syntax no longer parses (see
+        // `Token::DotIdent`).
+        self._format_term(lterm, out)?;
+        out.push('.');
+        out.push_str(s.as_raw_str());
+      }
+      &Term::ListLit(_, ref terms) => {
+        out.push('[');
+        self._format_terms(terms, out)?;
+        out.push(']');
+      }
+      &Term::Group(_, ref term) => {
+        out.push('(');
+        self._format_term(term, out)?;
+        out.push(')');
+      }
+      &Term::Bunch(_, ref terms) => {
+        self._format_terms(terms, out)?;
+      }
+      &Term::Neg(_, ref term) => {
+        out.push_str("-/");
+        self._format_term(term, out)?;
+      }
+      &Term::Query(_, ref term) => {
+        self._format_term(term, out)?;
+        out.push('?');
+      }
+      &Term::Equal(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " = ", rterm, out)?;
+      }
+      &Term::In(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " in ", rterm, out)?;
+      }
+      &Term::And(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " and ", rterm, out)?;
+      }
+      &Term::Or(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " or ", rterm, out)?;
+      }
+      &Term::Not(_, ref term) => {
+        out.push_str("not ");
+        self._format_term(term, out)?;
+      }
+      &Term::Await(_, ref term) => {
+        out.push_str("await ");
+        self._format_term(term, out)?;
+      }
+      &Term::NEqual(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " /= ", rterm, out)?;
+      }
+      &Term::QEqual(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " ?= ", rterm, out)?;
+      }
+      &Term::BindL(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " := ", rterm, out)?;
+      }
+      &Term::BindR(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " =: ", rterm, out)?;
+      }
+      &Term::RebindL(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " .= ", rterm, out)?;
+      }
+      &Term::RebindR(_, ref lterm, ref rterm) => {
+        self._format_binop(lterm, " =. ", rterm, out)?;
+      }
+      &Term::Subst(_, ref lterm, ref rterm) => {
+        self._format_term(lterm, out)?;
+        out.push_str(":(");
+        self._format_term(rterm, out)?;
+        out.push(')');
+      }
+      &Term::Apply(_, ref tup) => {
+        self._format_apply(tup, out)?;
+      }
+      &Term::Kwarg(_, ref k, ref term) => {
+        out.push_str(k.as_raw_str());
+        out.push('=');
+        self._format_term(term, out)?;
+      }
+      &Term::Splat(_, ref term) => {
+        out.push('*');
+        self._format_term(term, out)?;
+      }
+      &Term::ListComp(_, ref elt, ref target, choice, ref iter, ref cond) => {
+        out.push('[');
+        self._format_term(elt, out)?;
+        out.push_str(" for ");
+        match target.as_ref() {
+          None => out.push('_'),
+          Some(target) => out.push_str(target.as_raw_str())
+        }
+        out.push_str(if choice { " choice in " } else { " in " });
+        self._format_term(iter, out)?;
+        if let Some(cond) = cond.as_ref() {
+          out.push_str(" if ");
+          self._format_term(cond, out)?;
+        }
+        out.push(']');
+      }
+      &Term::Lambda(_, ref params, ref body) => {
+        out.push_str("lambda");
+        for (idx, param) in params.iter().enumerate() {
+          out.push_str(if idx == 0 { " " } else { ", " });
+          match param.as_ref() {
+            None => out.push('_'),
+            Some(param) => out.push_str(param.as_raw_str())
+          }
+        }
+        out.push_str(": ");
+        self._format_term(body, out)?;
+      }
+      &Term::Quote(_, ref body) => {
+        // NB: the quoted stms are formatted at the indent of the line.
+        out.push_str("```quote\n");
+        self._format_body(body, 0, out)?;
+        out.push_str("```");
+      }
+      &Term::ApplyBindL(_, ref lterm, ref tup) => {
+        self._format_term(lterm, out)?;
+        out.push_str(" := ");
+        self._format_apply(tup, out)?;
+      }
+      &Term::ApplyBindR(_, ref tup, ref rterm) => {
+        self._format_apply(tup, out)?;
+        out.push_str(" =: ");
+        self._format_term(rterm, out)?;
+      }
+      &Term::Effect(_, ref lterm, ref rtup) => {
+        self._format_term(lterm, out)?;
+        out.push('.');
+        match rtup.first().map(|t| &**t) {
+          Some(&Term::Ident(_, ref s)) => {
+            out.push_str(s.as_raw_str());
+          }
+          // NB: the parser always heads the rtup of an effect w/ the
+          // dotident, but an AST may be built by other means.
+          _ => {
+            return Err((term.span(), ParseError::ExpectedIdent).into());
           }
         }
-        let span = start.hull(self.pos());
-        return Ok(Term::Subst(span, lterm.into(), rterm.into()));
+        out.push('(');
+        self._format_terms(&rtup[1 .. ], out)?;
+        out.push(')');
       }
-      _ => unimplemented!()
     }
-    #[allow(unreachable_code)]
-    { unreachable!(); }
+    Ok(())
   }
-}
 
-//#[derive(Default)]
-pub struct Printer<S> {
-  buf:  S,
-  indent: RawIndent,
-}
+  pub fn _format_binop(&self, lterm: &Term, op: &str, rterm: &Term, out: &mut String) -> Result<(), ParseSpanError> {
+    self._format_term(lterm, out)?;
+    out.push_str(op);
+    self._format_term(rterm, out)?;
+    Ok(())
+  }
 
-impl<S> Printer<S> {
-  pub fn new(buf: S) -> Printer<S> {
-    Printer{buf, indent: 0}
+  pub fn _format_apply(&self, tup: &[TermRef], out: &mut String) -> Result<(), ParseSpanError> {
+    self._format_term(&tup[0], out)?;
+    out.push('(');
+    self._format_terms(&tup[1 .. ], out)?;
+    out.push(')');
+    Ok(())
   }
 }
 
@@ -2202,10 +4305,7 @@ impl<S: AsRef<str>> Printer<S> {
   }
 
   pub fn _pretty_print_stm(&self, stm: &Stm, level: RawIndent) {
-    let indent = match self.indent {
-      0 => 4,
-      x => x
-    };
+    let indent = self._indent();
     for _ in 0 .. level * indent {
       print!(" ");
     }
@@ -2224,6 +4324,26 @@ impl<S: AsRef<str>> Printer<S> {
         // TODO: static scope (debruijn level/index).
         println!("nonlocal {}", ident);
       }
+      &Stm::Fresh(_, ref idents) => {
+        let idents: Vec<_> = idents.iter().map(|ident| ident.as_raw_str()).collect();
+        println!("fresh {}", idents.join(", "));
+      }
+      &Stm::Import(_, ref idents) => {
+        let idents: Vec<_> = idents.iter().map(|ident| ident.as_raw_str()).collect();
+        println!("import {}", idents.join(", "));
+      }
+      &Stm::Assert(_, ref term) => {
+        print!("assert ");
+        self._pretty_print_term(term, level);
+        println!();
+      }
+      &Stm::Expect(_, ref lterm, ref rterm) => {
+        print!("expect ");
+        self._pretty_print_term(lterm, level);
+        print!(" == ");
+        self._pretty_print_term(rterm, level);
+        println!();
+      }
       &Stm::With(_, ref head, ref body) => {
         print!("with ");
         self._pretty_print_term(head, level);
@@ -2232,6 +4352,40 @@ impl<S: AsRef<str>> Printer<S> {
           self._pretty_print_stm(stm, level + 1);
         }
       }
+      &Stm::Class(_, ref head, ref body) => {
+        println!("class {}:", head);
+        for stm in body.iter() {
+          self._pretty_print_stm(stm, level + 1);
+        }
+      }
+      &Stm::Match(_, ref x, ref cases) => {
+        print!("match ");
+        self._pretty_print_term(x, level);
+        println!(":");
+        for &(ref pat, ref body) in cases.iter() {
+          for _ in 0 .. (level + 1) * indent {
+            print!(" ");
+          }
+          print!("case ");
+          self._pretty_print_term(pat, level + 1);
+          println!(":");
+          for stm in body.iter() {
+            self._pretty_print_stm(stm, level + 2);
+          }
+        }
+      }
+      &Stm::Cases(_, ref cases) => {
+        println!("cases:");
+        for body in cases.iter() {
+          for _ in 0 .. (level + 1) * indent {
+            print!(" ");
+          }
+          println!("case:");
+          for stm in body.iter() {
+            self._pretty_print_stm(stm, level + 2);
+          }
+        }
+      }
       &Stm::Def(_, .., ref body) => {
         println!("def _:");
         for stm in body.iter() {
@@ -2261,7 +4415,7 @@ impl<S: AsRef<str>> Printer<S> {
           self._pretty_print_stm(stm, level + 1);
         }
       }
-      &Stm::Defproc(_, prefix, ref head, ref params, ref body) => {
+      &Stm::Defproc(_, prefix, ref head, ref params, ref rest, ref defaults, ref body) => {
         match prefix {
           None => {}
           Some(DefPrefix::Rule) => {
@@ -2271,11 +4425,32 @@ impl<S: AsRef<str>> Printer<S> {
         print!("defproc {head}(");
         for (idx, param) in params.iter().enumerate() {
           if param.is_some() {
-            print!("{}", param.as_ref().unwrap());
+            let param = param.as_ref().unwrap();
+            print!("{}", param);
+            if let Some(&(_, ref default)) = defaults.iter().find(|&&(ref k, _)| k == param) {
+              print!("=");
+              self._pretty_print_term(default, level);
+            }
           } else {
             print!("_");
           }
-          if idx + 1 < params.len() {
+          if idx + 1 < params.len() || rest.is_some() {
+            print!(", ");
+          }
+        }
+        if let Some(rest) = rest.as_ref() {
+          print!("*{}", rest);
+        }
+        println!("):");
+        for stm in body.iter() {
+          self._pretty_print_stm(stm, level + 1);
+        }
+      }
+      &Stm::Defrule(_, ref head, ref args, ref body) => {
+        print!("defrule {head}(");
+        for (idx, arg) in args.iter().enumerate() {
+          self._pretty_print_term(arg, level);
+          if idx + 1 < args.len() {
             print!(", ");
           }
         }
@@ -2356,6 +4531,29 @@ impl<S: AsRef<str>> Printer<S> {
         print!(" = ");
         self._pretty_print_term(rterm, level);
       }
+      &Term::In(_, ref lterm, ref rterm) => {
+        self._pretty_print_term(lterm, level);
+        print!(" in ");
+        self._pretty_print_term(rterm, level);
+      }
+      &Term::And(_, ref lterm, ref rterm) => {
+        self._pretty_print_term(lterm, level);
+        print!(" and ");
+        self._pretty_print_term(rterm, level);
+      }
+      &Term::Or(_, ref lterm, ref rterm) => {
+        self._pretty_print_term(lterm, level);
+        print!(" or ");
+        self._pretty_print_term(rterm, level);
+      }
+      &Term::Not(_, ref term) => {
+        print!("not ");
+        self._pretty_print_term(term, level);
+      }
+      &Term::Await(_, ref term) => {
+        print!("await ");
+        self._pretty_print_term(term, level);
+      }
       &Term::NEqual(_, ref lterm, ref rterm) => {
         self._pretty_print_term(lterm, level);
         print!(" /= ");
@@ -2378,7 +4576,7 @@ impl<S: AsRef<str>> Printer<S> {
       }
       &Term::Subst(_, ref lterm, ref rterm) => {
         self._pretty_print_term(lterm, level);
-        print!(" .( ");
+        print!(" :( ");
         self._pretty_print_term(rterm, level);
         print!(" )");
       }
@@ -2403,6 +4601,48 @@ impl<S: AsRef<str>> Printer<S> {
         }
         print!(")");
       }
+      &Term::Kwarg(_, ref k, ref term) => {
+        print!("{}=", k);
+        self._pretty_print_term(term, level);
+      }
+      &Term::Splat(_, ref term) => {
+        print!("*");
+        self._pretty_print_term(term, level);
+      }
+      &Term::ListComp(_, ref elt, ref target, choice, ref iter, ref cond) => {
+        print!("[");
+        self._pretty_print_term(elt, level);
+        match target.as_ref() {
+          None => print!(" for _"),
+          Some(target) => print!(" for {}", target)
+        }
+        print!("{}", if choice { " choice in " } else { " in " });
+        self._pretty_print_term(iter, level);
+        if let Some(cond) = cond.as_ref() {
+          print!(" if ");
+          self._pretty_print_term(cond, level);
+        }
+        print!("]");
+      }
+      &Term::Lambda(_, ref params, ref body) => {
+        print!("lambda");
+        for (idx, param) in params.iter().enumerate() {
+          print!("{}", if idx == 0 { " " } else { ", " });
+          match param.as_ref() {
+            None => print!("_"),
+            Some(param) => print!("{}", param)
+          }
+        }
+        print!(": ");
+        self._pretty_print_term(body, level);
+      }
+      &Term::Quote(_, ref body) => {
+        println!("```quote");
+        for stm in body.iter() {
+          self._pretty_print_stm(stm, level);
+        }
+        print!("```");
+      }
       &Term::ApplyBindL(_, ref lterm, ref tup) => {
         self._pretty_print_term(lterm, level);
         print!(" := ");
@@ -2431,8 +4671,17 @@ impl<S: AsRef<str>> Printer<S> {
       }
       &Term::Effect(_, ref lterm, ref rterms) => {
         self._pretty_print_term(lterm, level);
-        print!(".__(");
-        for (i, term) in rterms.iter().enumerate() {
+        print!(".");
+        match rterms.first().map(|t| &**t) {
+          Some(&Term::Ident(_, ref s)) => {
+            print!("{}", s.as_raw_str());
+          }
+          _ => {
+            print!("__");
+          }
+        }
+        print!("(");
+        for (i, term) in rterms.iter().enumerate().skip(1) {
           self._pretty_print_term(term, level);
           if i + 1 < rterms.len() {
             print!(",");
@@ -2446,3 +4695,724 @@ impl<S: AsRef<str>> Printer<S> {
     }
   }
 }
+
+#[derive(Clone, Copy, Debug)]
+pub struct FormatConfig {
+  pub indent: RawIndent,
+}
+
+impl Default for FormatConfig {
+  fn default() -> FormatConfig {
+    FormatConfig{indent: 4}
+  }
+}
+
+// Format the source text `src` as canonical source (see `Printer::format`),
+// preserving the comments and blank lines (see `Trivia`) of `src`.
+pub fn try_format_source(src: &str, cfg: FormatConfig) -> Result<String, ParseSpanError> {
+  let mut parser = Parser::new(src);
+  let mod_ = parser.mod_()?;
+  let mut printer = Printer::new(src);
+  printer.set_indent(cfg.indent);
+  printer.set_trivia(parser.take_trivia());
+  printer.format(&mod_)
+}
+
+// NB: if `src` does not parse, it is returned as is.
+pub fn format_source(src: &str, cfg: FormatConfig) -> String {
+  match try_format_source(src, cfg) {
+    Err(_) => src.to_string(),
+    Ok(s) => s
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // NB: the format is span-free and injective, so a format fixpoint
+  // implies that the re-parsed AST is equivalent to the original.
+  #[test]
+  fn test_format_roundtrip() {
+    let src = "\
+rule defproc f(x, _):
+  y := g(x, [1, 2], \"a\")
+  w = -/(x = None)
+  with h(y):
+    z = y.k(1, 2)
+    w = z.a
+    pass
+defmatch m(x):
+  x ?= True
+k = choice(2)
+if k = 0:
+        failure()
+else:
+  pass
+";
+    let mod_ = Parser::new(src).mod_().unwrap();
+    let fmt = Printer::new(src).format(&mod_).unwrap();
+    let mod2 = Parser::new(fmt.as_str()).mod_().unwrap();
+    let fmt2 = Printer::new(fmt.as_str()).format(&mod2).unwrap();
+    assert_eq!(mod_.body.len(), mod2.body.len());
+    assert_eq!(fmt, fmt2);
+    let mut printer = Printer::new(src);
+    printer.set_indent(2);
+    let fmt3 = printer.format(&mod_).unwrap();
+    let mod3 = Parser::new(fmt3.as_str()).mod_().unwrap();
+    assert_eq!(fmt, Printer::new(fmt3.as_str()).format(&mod3).unwrap());
+    // NB: an attr is formatted w/ a dot, and so re-parses as an attr.
+    assert!(fmt.contains("w = z.a\n"));
+    // NB: an effect whose rtup is not headed by an ident is an error
+    // (rather than a panic).
+    let src = "z = y.k(1)\n";
+    let mod_ = Parser::new(src).mod_().unwrap();
+    let rhs = match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Equal(_, _, ref rhs) => rhs.clone(),
+        _ => panic!()
+      },
+      _ => panic!()
+    };
+    let bad = match *rhs {
+      Term::Effect(span, lterm, rtup) => Term::Effect(span, lterm, rtup[1 .. ].to_vec()),
+      _ => panic!()
+    };
+    let mut out = String::new();
+    assert!(Printer::new(src)._format_term(&bad, &mut out).is_err());
+  }
+
+  #[test]
+  fn test_format_source_trivia() {
+    let src = "\
+# head
+defproc f(x):
+      -- body
+      y  =  x  # trailing
+
+      pass
+
+z=f(1)
+";
+    let fmt = format_source(src, FormatConfig{indent: 2});
+    assert_eq!(fmt, "\
+# head
+defproc f(x):
+  -- body
+  y = x  # trailing
+
+  pass
+
+z = f(1)
+");
+    assert_eq!(format_source(&fmt, FormatConfig{indent: 2}), fmt);
+  }
+
+  fn _call_head(term: &Term) -> &str {
+    match term {
+      &Term::Apply(_, ref tup) => match &*tup[0] {
+        &Term::Ident(_, ref s) => s.as_raw_str(),
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_python_compat() {
+    let src = "\
+def f(a, b=1):
+    while a < b <= 3:
+        if a is not None and not a in b:
+            return 'x\\n'
+        break
+    for k, v in g(a):
+        return
+";
+    let mut parser = Parser::new(src);
+    parser.set_mode(ParserMode::PythonCompat);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 1);
+    let body = match &*mod_.body[0] {
+      &Stm::Defproc(_, _, ref head, ref params, _, ref defaults, ref body) => {
+        assert_eq!(head.as_raw_str(), "f");
+        assert_eq!(params.len(), 2);
+        assert_eq!(defaults.len(), 1);
+        assert_eq!(defaults[0].0.as_raw_str(), "b");
+        assert!(matches!(&*defaults[0].1, &Term::IntLit(..)));
+        body
+      }
+      _ => panic!()
+    };
+    assert_eq!(body.len(), 2);
+    match &*body[0] {
+      &Stm::With(_, ref head, ref body) => {
+        assert_eq!(_call_head(head), "__py_while__");
+        match &**head {
+          &Term::Apply(_, ref tup) => assert_eq!(_call_head(&tup[1]), "__py_and__"),
+          _ => panic!()
+        }
+        assert_eq!(body.len(), 2);
+      }
+      _ => panic!()
+    }
+    match &*body[1] {
+      &Stm::With(_, ref head, _) => {
+        assert_eq!(_call_head(head), "__py_for__");
+        match &**head {
+          &Term::Apply(_, ref tup) => {
+            assert!(matches!(&*tup[1], &Term::Bunch(..)));
+            assert_eq!(_call_head(&tup[2]), "g");
+          }
+          _ => panic!()
+        }
+      }
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_python_compat_kwargs() {
+    let src = "f(x, k=g(y), j=None)\n";
+    let mut parser = Parser::new(src);
+    parser.set_mode(ParserMode::PythonCompat);
+    let mod_ = parser.mod_().unwrap();
+    let tup = match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Apply(_, ref tup) => tup,
+        _ => panic!()
+      },
+      _ => panic!()
+    };
+    assert_eq!(tup.len(), 4);
+    assert!(matches!(&*tup[1], &Term::Ident(..)));
+    match &*tup[2] {
+      &Term::Kwarg(_, ref k, ref term) => {
+        assert_eq!(k.as_raw_str(), "k");
+        assert_eq!(_call_head(term), "g");
+      }
+      _ => panic!()
+    }
+    match &*tup[3] {
+      &Term::Kwarg(_, ref k, ref term) => {
+        assert_eq!(k.as_raw_str(), "j");
+        assert!(matches!(&**term, &Term::NoneLit(..)));
+      }
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_splat() {
+    let src = "defproc f(x, *xs):\n  g(*xs, x)\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    let body = match &*mod_.body[0] {
+      &Stm::Defproc(_, _, _, ref params, ref rest, _, ref body) => {
+        assert_eq!(params.len(), 1);
+        assert_eq!(rest.as_ref().unwrap().as_raw_str(), "xs");
+        body
+      }
+      _ => panic!()
+    };
+    let tup = match &*body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Apply(_, ref tup) => tup,
+        _ => panic!()
+      },
+      _ => panic!()
+    };
+    assert_eq!(tup.len(), 3);
+    assert!(matches!(&*tup[1], &Term::Splat(..)));
+    assert!(matches!(&*tup[2], &Term::Ident(..)));
+    let src = "def f(x, *xs):\n  return g(x, *xs)\n";
+    let mut parser = Parser::new(src);
+    parser.set_mode(ParserMode::PythonCompat);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Defproc(_, _, _, ref params, ref rest, ..) => {
+        assert_eq!(params.len(), 1);
+        assert_eq!(rest.as_ref().unwrap().as_raw_str(), "xs");
+      }
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_lambda() {
+    let src = "g = lambda x, y: f(x, y)\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Equal(_, _, ref rterm) => match &**rterm {
+          &Term::Lambda(_, ref params, ref body) => {
+            assert_eq!(params.len(), 2);
+            assert_eq!(_call_head(body), "f");
+          }
+          _ => panic!()
+        },
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+    let src = "h(lambda: None, 1)\n";
+    let mut parser = Parser::new(src);
+    parser.set_mode(ParserMode::PythonCompat);
+    let mod_ = parser.mod_().unwrap();
+    let tup = match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Apply(_, ref tup) => tup,
+        _ => panic!()
+      },
+      _ => panic!()
+    };
+    assert_eq!(tup.len(), 3);
+    match &*tup[1] {
+      &Term::Lambda(_, ref params, ref body) => {
+        assert!(params.is_empty());
+        assert!(matches!(&**body, &Term::NoneLit(..)));
+      }
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_quote_term() {
+    // NB: the stms after the closing "```" are not quoted.
+    let src = "q = ```quote\ny = 1\nf(y)\n```\nz = 2\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 2);
+    match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Equal(_, _, ref rterm) => match &**rterm {
+          &Term::Quote(_, ref body) => {
+            assert_eq!(body.len(), 2);
+            assert!(matches!(&*body[1], &Stm::Just(..)));
+          }
+          _ => panic!()
+        },
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_list_comp() {
+    let src = "ys = [f(x) for x in xs if p(x)]\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Equal(_, _, ref rterm) => match &**rterm {
+          &Term::ListComp(_, ref elt, ref target, choice, ref iter, ref cond) => {
+            assert_eq!(_call_head(elt), "f");
+            assert_eq!(target.as_ref().unwrap().as_raw_str(), "x");
+            assert!(!choice);
+            assert!(matches!(&**iter, &Term::Ident(..)));
+            assert_eq!(_call_head(cond.as_ref().unwrap()), "p");
+          }
+          _ => panic!()
+        },
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+    let src = "y = [x for x choice in xs]\n";
+    let mut parser = Parser::new(src);
+    parser.set_mode(ParserMode::PythonCompat);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Equal(_, _, ref rterm) => match &**rterm {
+          &Term::ListComp(_, _, _, choice, _, ref cond) => {
+            assert!(choice);
+            assert!(cond.is_none());
+          }
+          _ => panic!()
+        },
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_in() {
+    let src = "if x in f(xs):\n  pass\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::If(_, ref cases, _) => match &*cases[0].0 {
+        &Term::In(_, ref lterm, ref rterm) => {
+          assert!(matches!(&**lterm, &Term::Ident(..)));
+          assert_eq!(_call_head(rterm), "f");
+        }
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_and_or_not() {
+    let src = "if not x = 1 and y in ys or z:\n  pass\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::If(_, ref cases, _) => match &*cases[0].0 {
+        &Term::Or(_, ref lterm, ref rterm) => {
+          match &**lterm {
+            &Term::And(_, ref lterm, ref rterm) => {
+              assert!(matches!(&**lterm, &Term::Not(_, ref term) if matches!(&**term, &Term::Equal(..))));
+              assert!(matches!(&**rterm, &Term::In(..)));
+            }
+            _ => panic!()
+          }
+          assert!(matches!(&**rterm, &Term::Ident(..)));
+        }
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_await() {
+    let src = "x = await f(y)\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Equal(_, ref lterm, ref rterm) => {
+          assert!(matches!(&**lterm, &Term::Ident(..)));
+          match &**rterm {
+            &Term::Await(_, ref term) => {
+              assert_eq!(_call_head(term), "f");
+            }
+            _ => panic!()
+          }
+        }
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_assert_expect() {
+    let src = "assert x = 1\nexpect f(x) == [1, 2]\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 2);
+    match &*mod_.body[0] {
+      &Stm::Assert(_, ref term) => assert!(matches!(&**term, &Term::Equal(..))),
+      _ => panic!()
+    }
+    match &*mod_.body[1] {
+      &Stm::Expect(_, ref lterm, ref rterm) => {
+        assert!(matches!(&**lterm, &Term::Apply(..)));
+        assert!(matches!(&**rterm, &Term::ListLit(..)));
+      }
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_fresh() {
+    let src = "fresh x, y\nx = f(y)\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Fresh(_, ref idents) => {
+        let idents: Vec<_> = idents.iter().map(|ident| ident.as_raw_str()).collect();
+        assert_eq!(idents, ["x", "y"]);
+      }
+      _ => panic!()
+    }
+    assert!(matches!(&*mod_.body[1], &Stm::Just(..)));
+  }
+
+  #[test]
+  fn test_import() {
+    let src = "import a, b\nb.f(a.x)\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Import(_, ref idents) => {
+        let idents: Vec<_> = idents.iter().map(|ident| ident.as_raw_str()).collect();
+        assert_eq!(idents, ["a", "b"]);
+      }
+      _ => panic!()
+    }
+    assert!(matches!(&*mod_.body[1], &Stm::Just(..)));
+  }
+
+  #[test]
+  fn test_match() {
+    let src = "match f(x):\n  case 1:\n    pass\n  case y:\n    g(y)\n    pass\nh()\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 2);
+    match &*mod_.body[0] {
+      &Stm::Match(_, ref x, ref cases) => {
+        assert_eq!(_call_head(x), "f");
+        assert_eq!(cases.len(), 2);
+        assert!(matches!(&*cases[0].0, &Term::IntLit(..)));
+        assert!(matches!(&*cases[1].0, &Term::Ident(..)));
+        assert_eq!(cases[1].1.len(), 2);
+      }
+      _ => panic!()
+    }
+    let src = "case 1:\n  pass\n";
+    let mut parser = Parser::new(src);
+    assert!(parser.mod_().is_err());
+  }
+
+  #[test]
+  fn test_cases() {
+    let src = "cases:\n  case:\n    x = 1\n  case:\n    x = 2\n    pass\nf(x)\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 2);
+    match &*mod_.body[0] {
+      &Stm::Cases(_, ref cases) => {
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].len(), 1);
+        assert_eq!(cases[1].len(), 2);
+      }
+      _ => panic!()
+    }
+    // NB: a case of a cases stm has no pattern, and vice versa for a
+    // case of a match stm.
+    let src = "cases:\n  case 1:\n    pass\n";
+    let mut parser = Parser::new(src);
+    assert!(parser.mod_().is_err());
+    let src = "match x:\n  case:\n    pass\n";
+    let mut parser = Parser::new(src);
+    assert!(parser.mod_().is_err());
+  }
+
+  #[test]
+  fn test_class() {
+    let src = "class Counter:\n  defproc get(self):\n    pass\n  defproc inc(self, k):\n    pass\nc = Counter()\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 2);
+    match &*mod_.body[0] {
+      &Stm::Class(_, ref head, ref body) => {
+        assert_eq!(head.as_raw_str(), "Counter");
+        assert_eq!(body.len(), 2);
+        assert!(matches!(&*body[1], &Stm::Defproc(..)));
+      }
+      _ => panic!()
+    }
+    // NB: an attr term may be the last stm of a nested block, i.e. right
+    // before a dedent; an effect still parses w/ a space before its args.
+    let src = "class Point:\n  defproc get_x(self, r):\n    r = self.x\n  defproc inc(self, k):\n    self.add (k)\np = Point()\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 2);
+    match &*mod_.body[0] {
+      &Stm::Class(_, _, ref body) => {
+        assert_eq!(body.len(), 2);
+        match &*body[0] {
+          &Stm::Defproc(.., ref proc_body) => match &*proc_body[0] {
+            &Stm::Just(_, ref term) => match &**term {
+              &Term::Equal(_, _, ref rhs) => assert!(matches!(&**rhs, &Term::QualIdent(..))),
+              _ => panic!()
+            },
+            _ => panic!()
+          },
+          _ => panic!()
+        }
+      }
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_defrule() {
+    let src = "defrule ancestor(x, [y, z]):\n  parent(x, y)\n  ancestor(y, z)\nf()\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    assert_eq!(mod_.body.len(), 2);
+    match &*mod_.body[0] {
+      &Stm::Defrule(_, ref head, ref args, ref body) => {
+        assert_eq!(head.as_raw_str(), "ancestor");
+        assert_eq!(args.len(), 2);
+        assert!(matches!(&*args[0], &Term::Ident(..)));
+        assert!(matches!(&*args[1], &Term::ListLit(..)));
+        assert_eq!(body.len(), 2);
+      }
+      _ => panic!()
+    }
+    let src = "rule defrule f(x):\n  pass\n";
+    let mut parser = Parser::new(src);
+    assert!(parser.mod_().is_err());
+  }
+
+  #[test]
+  fn test_malformed_stm() {
+    // NB: each src is rejected w/ a parse error (rather than a panic).
+    for src in [
+        "rule():\nassert    pas",
+        "x\nglobal y\nnonlocal [None-1] z1\n",
+        "fresh\"hello\" ",
+        "x := 1\ny := 1 defmatch\nx = y\n",
+        "defproc (f)(x):\n  pass\n",
+        "try:\n  pass\n",
+        "else:\n  pass\n",
+        "foo.baz = >=foo.bar\n",
+        "x = a.f(1 2)\n",
+    ] {
+      let mut parser = Parser::new(src);
+      assert!(parser.mod_().is_err(), "src={:?}", src);
+    }
+    let src = "a.x = b.f(c)\n";
+    let mut parser = Parser::new(src);
+    let mod_ = parser.mod_().unwrap();
+    match &*mod_.body[0] {
+      &Stm::Just(_, ref term) => match &**term {
+        &Term::Equal(_, ref lhs, ref rhs) => {
+          assert!(matches!(&**lhs, &Term::QualIdent(..)));
+          assert!(matches!(&**rhs, &Term::Effect(..)));
+        }
+        _ => panic!()
+      },
+      _ => panic!()
+    }
+  }
+
+  #[test]
+  fn test_malformed_str() {
+    // NB: the last src has a multibyte char inside the closing delim.
+    for src in ["x = \"ab\n", "x = \"\\q\"\n", "x = \"\"\"ab", "x = \"\"\"a\"\u{e9}"] {
+      let mut parser = Parser::new(src);
+      match parser.mod_() {
+        Err(e) => {
+          assert!(matches!(e.err, ParseError::MalformedString(_)));
+          assert_eq!(e.span.start, 4);
+        }
+        _ => panic!()
+      }
+    }
+    // NB: tokenizing continues after the line end, or the closing delim.
+    for (src, end) in [("x = \"a\tb\ny", 10), ("x = \"\\q\" y", 10)] {
+      let mut toks = Vec::new();
+      for t in Tokenizer::new(src) {
+        let eof = matches!(t.tok, Token::_Eof);
+        toks.push(t);
+        if eof {
+          break;
+        }
+      }
+      let err = toks.iter().position(|t| matches!(t.tok, Token::_Error(..))).unwrap();
+      assert_eq!(toks[err].span, 4 .. end - 2);
+      assert!(matches!(toks[toks.len() - 2].tok, Token::Ident(_)));
+    }
+  }
+
+  #[test]
+  fn test_malformed_strs() {
+    // NB: each malformed str is its own error token, w/ its own kind, and
+    // the well-formed str in between is still a lit.
+    let src = "x = \"a\\qb\"\ny = \"a\tb\"\nz = \"ok\"\nw = \"\\u12\"\nv = \"c\nu = 1\n";
+    let mut errs = Vec::new();
+    let mut lits = Vec::new();
+    let mut idents = 0;
+    for t in Tokenizer::new(src) {
+      match t.tok {
+        Token::_Eof => break,
+        Token::_Error(_, e) => errs.push((t.span, e)),
+        Token::AtomLit(s, _) => lits.push(s),
+        Token::Ident(_) => idents += 1,
+        _ => {}
+      }
+    }
+    assert_eq!(errs, vec![
+      (4 .. 10, StrSyntaxError::InvalidEscape),
+      (15 .. 20, StrSyntaxError::ControlCharacterInString(b'\t')),
+      (34 .. 40, StrSyntaxError::InvalidEscape),
+      (45 .. 47, StrSyntaxError::ControlCharacterInString(b'\n')),
+    ]);
+    assert_eq!(lits, vec![SafeStr::from("\"ok\"")]);
+    assert_eq!(idents, 6);
+    // NB: the parse error is the first malformed str.
+    let mut parser = Parser::new(src);
+    match parser.mod_() {
+      Err(e) => {
+        assert!(matches!(e.err, ParseError::MalformedString(StrSyntaxError::InvalidEscape)));
+        assert_eq!(e.span, 4 .. 10);
+      }
+      _ => panic!()
+    }
+    assert_eq!(parser.tokenizer_err(), Some(&(4 .. 10, StrSyntaxError::InvalidEscape)));
+  }
+
+  #[test]
+  fn test_keyword_prefix_idents() {
+    let src = "input order isa asx async Parser Para definite if_ in or";
+    let syms = SharedInterner::default();
+    let mut toks = Vec::new();
+    for t in Tokenizer::with_interner(src, syms.clone()) {
+      match t.tok {
+        Token::_Eof => break,
+        Token::Indent(_) | Token::Space => {}
+        tok => toks.push(tok)
+      }
+    }
+    let kinds: Vec<_> = toks.iter().map(|t| match t {
+      &Token::Ident(s) => syms.borrow().resolve(s).as_raw_str().to_string(),
+      t => format!("{:?}", t)
+    }).collect();
+    assert_eq!(kinds, [
+        "input", "order", "isa", "asx", "Async", "Parser", "Par", "definite",
+        "if_", "In", "Or",
+    ]);
+  }
+
+  #[test]
+  fn test_interned_idents() {
+    let src = "x = f(x, .y, _)\ny = x.y\n";
+    let syms = SharedInterner::default();
+    let mut ids = Vec::new();
+    for t in Tokenizer::with_interner(src, syms.clone()) {
+      match t.tok {
+        Token::_Eof => break,
+        Token::Ident(s) | Token::DotIdent(s) | Token::PlaceIdent(s) => ids.push(s),
+        _ => {}
+      }
+    }
+    let strs: Vec<_> = ids.iter().map(|&s| syms.borrow().resolve(s).as_raw_str().to_string()).collect();
+    assert_eq!(strs, ["x", "f", "x", "y", "_", "y", "x", "y"]);
+    assert_eq!(ids[0], ids[2]);
+    assert_eq!(ids[3], ids[5]);
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(syms.borrow().len(), 4);
+    // NB: a parser w/ the same interner reuses the same syms.
+    let len = syms.borrow().len();
+    let _ = Parser::with_interner("f(y)\n", syms.clone()).mod_().unwrap();
+    assert_eq!(syms.borrow().len(), len);
+  }
+
+  #[test]
+  fn test_pragmas() {
+    let src = "\
+-- pragma: strict, step_limit = 100
+x = 1  -- pragma: trace = 3
+-- not a pragma: occurs_check
+";
+    let mod_ = Parser::new(src).mod_().unwrap();
+    let pragmas: Vec<_> = mod_.pragmas.iter().map(|p| {
+      (p.key.as_raw_str().to_string(), p.val.as_ref().map(|v| v.as_raw_str().to_string()))
+    }).collect();
+    assert_eq!(pragmas, [
+        ("strict".to_string(), None),
+        ("step_limit".to_string(), Some("100".to_string())),
+    ]);
+    assert_eq!(mod_.pragmas[0].span, 0 .. 35);
+  }
+}
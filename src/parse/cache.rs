@@ -0,0 +1,54 @@
+use crate::algo::blake2s::{Blake2s};
+use crate::algo::hex::{HexFormat};
+use crate::parse::*;
+
+use std::fs;
+use std::io::{Error as IoError};
+use std::path::{Path, PathBuf};
+
+// An on-disk cache of parsed modules, keyed by the Blake2s hash of the
+// source text (see `ParseCache::key`). Each entry is the module parsed
+// at span offset 0, in the JSON interchange format (see `Mod::to_json`).
+//
+// NB: a missing, unreadable, or stale (i.e. of a different interchange
+// version) entry is just a cache miss.
+#[derive(Clone, Debug)]
+pub struct ParseCache {
+  dir:  PathBuf,
+}
+
+impl ParseCache {
+  pub fn new<P: Into<PathBuf>>(dir: P) -> ParseCache {
+    ParseCache{dir: dir.into()}
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  pub fn key(src: &str) -> String {
+    let mut h = Blake2s::new_hash();
+    h.hash_bytes(&MOD_JSON_VERSION.to_le_bytes());
+    h.hash_bytes(src.as_bytes());
+    HexFormat::default().to_string(&h.finalize())
+  }
+
+  pub fn _path(&self, key: &str) -> PathBuf {
+    self.dir.join(format!("{}.json", key))
+  }
+
+  pub fn get(&self, src: &str) -> Option<Mod> {
+    let text = fs::read_to_string(self._path(&ParseCache::key(src))).ok()?;
+    Mod::from_json(&text).ok()
+  }
+
+  // NB: the entry is written to a temporary file and then renamed, so
+  // that a concurrent reader never sees a partial entry.
+  pub fn put(&self, src: &str, mod_: &Mod) -> Result<(), IoError> {
+    fs::create_dir_all(&self.dir)?;
+    let key = ParseCache::key(src);
+    let tmp = self.dir.join(format!("{}.json.{}.tmp", key, std::process::id()));
+    fs::write(&tmp, mod_.to_json())?;
+    fs::rename(&tmp, self._path(&key))
+  }
+}
@@ -0,0 +1,75 @@
+use crate::algo::{FxHashMap, SmolStr};
+use crate::algo::cell::{RefCell};
+use crate::algo::rc::{Rc};
+use crate::algo::str::{SafeStr};
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+// A handle of an interned str (see `Interner`); two handles from the same
+// interner are equal iff their strs are equal.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct Sym(pub u32);
+
+impl Debug for Sym {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    write!(f, "Sym({})", self.0)
+  }
+}
+
+impl Sym {
+  pub fn to_usize(self) -> usize {
+    self.0 as usize
+  }
+}
+
+// An append-only str interner. A str is never uninterned, so that a handle
+// stays valid for the lifetime of the interner, in particular across a
+// backtrack (which undoes the env, but not the interner).
+#[derive(Clone, Default)]
+pub struct Interner {
+  buf:  Vec<SafeStr>,
+  index: FxHashMap<SmolStr, Sym>,
+}
+
+impl Debug for Interner {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    write!(f, "Interner{{len: {}}}", self.buf.len())
+  }
+}
+
+impl Interner {
+  pub fn len(&self) -> usize {
+    self.buf.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.buf.is_empty()
+  }
+
+  pub fn intern(&mut self, s: &str) -> Sym {
+    if let Some(&sym) = self.index.get(s) {
+      return sym;
+    }
+    assert!(self.buf.len() < u32::max_value() as usize);
+    let sym = Sym(self.buf.len() as u32);
+    self.buf.push(s.into());
+    self.index.insert(s.into(), sym);
+    sym
+  }
+
+  // The handle of `s`, if `s` has been interned.
+  pub fn get(&self, s: &str) -> Option<Sym> {
+    self.index.get(s).copied()
+  }
+
+  pub fn resolve(&self, sym: Sym) -> &SafeStr {
+    &self.buf[sym.to_usize()]
+  }
+}
+
+// An interner shared between a parser (i.e. its tokenizer) and an
+// interpreter (see `Parser::with_interner`).
+pub type SharedInterner = Rc<RefCell<Interner>>;
@@ -0,0 +1,462 @@
+use crate::parse::*;
+
+// A read-only traversal over the AST. The default methods walk the
+// children (see `walk_mod`, `walk_stm`, `walk_term`); an implementor
+// overrides the methods for the nodes it cares about, and calls the
+// corresponding `walk_*` to continue into the children.
+pub trait Visitor {
+  fn visit_mod(&mut self, mod_: &Mod) {
+    walk_mod(self, mod_);
+  }
+
+  fn visit_stm(&mut self, stm: &Stm) {
+    walk_stm(self, stm);
+  }
+
+  fn visit_term(&mut self, term: &Term) {
+    walk_term(self, term);
+  }
+}
+
+pub fn walk_mod<V: Visitor + ?Sized>(v: &mut V, mod_: &Mod) {
+  for stm in mod_.body.iter() {
+    v.visit_stm(stm);
+  }
+}
+
+pub fn walk_stm<V: Visitor + ?Sized>(v: &mut V, stm: &Stm) {
+  match stm {
+    &Stm::Just(_, ref term) |
+    &Stm::Assert(_, ref term) => {
+      v.visit_term(term);
+    }
+    &Stm::Expect(_, ref lterm, ref rterm) => {
+      v.visit_term(lterm);
+      v.visit_term(rterm);
+    }
+    &Stm::With(_, ref head, ref body) => {
+      v.visit_term(head);
+      for stm in body.iter() {
+        v.visit_stm(stm);
+      }
+    }
+    &Stm::If(_, ref cases, ref final_) => {
+      for &(ref cond, ref body) in cases.iter() {
+        v.visit_term(cond);
+        for stm in body.iter() {
+          v.visit_stm(stm);
+        }
+      }
+      if let Some(body) = final_.as_ref() {
+        for stm in body.iter() {
+          v.visit_stm(stm);
+        }
+      }
+    }
+    &Stm::Defproc(.., ref defaults, ref body) => {
+      for &(_, ref default) in defaults.iter() {
+        v.visit_term(default);
+      }
+      for stm in body.iter() {
+        v.visit_stm(stm);
+      }
+    }
+    &Stm::Match(_, ref x, ref cases) => {
+      v.visit_term(x);
+      for &(ref pat, ref body) in cases.iter() {
+        v.visit_term(pat);
+        for stm in body.iter() {
+          v.visit_stm(stm);
+        }
+      }
+    }
+    &Stm::_Case(_, ref pat, ref body) => {
+      if let Some(pat) = pat.as_ref() {
+        v.visit_term(pat);
+      }
+      for stm in body.iter() {
+        v.visit_stm(stm);
+      }
+    }
+    &Stm::Defrule(_, _, ref args, ref body) => {
+      for arg in args.iter() {
+        v.visit_term(arg);
+      }
+      for stm in body.iter() {
+        v.visit_stm(stm);
+      }
+    }
+    &Stm::Cases(_, ref cases) => {
+      for body in cases.iter() {
+        for stm in body.iter() {
+          v.visit_stm(stm);
+        }
+      }
+    }
+    &Stm::Def(.., ref body) |
+    &Stm::Defmatch(.., ref body) |
+    &Stm::Class(.., ref body) |
+    &Stm::Quote(.., ref body) => {
+      for stm in body.iter() {
+        v.visit_stm(stm);
+      }
+    }
+    &Stm::Comment(..) |
+    &Stm::Pass(..) |
+    &Stm::Global(..) |
+    &Stm::Nonlocal(..) |
+    &Stm::Fresh(..) |
+    &Stm::Import(..) |
+    &Stm::Try(_) |
+    &Stm::While(_) |
+    &Stm::For(_) |
+    &Stm::_EndQuote(..) => {}
+  }
+}
+
+pub fn walk_term<V: Visitor + ?Sized>(v: &mut V, term: &Term) {
+  match term {
+    &Term::Ident(..) |
+    &Term::AtomLit(..) |
+    &Term::NoneLit(..) |
+    &Term::BoolLit(..) |
+    &Term::IntLit(..) |
+    &Term::FloatLit(..) => {}
+    &Term::QualIdent(_, ref term, _) |
+    &Term::Neg(_, ref term) |
+    &Term::Group(_, ref term) |
+    &Term::Query(_, ref term) |
+    &Term::Not(_, ref term) |
+    &Term::Await(_, ref term) |
+    &Term::Kwarg(_, _, ref term) |
+    &Term::Splat(_, ref term) |
+    &Term::Lambda(_, _, ref term) => {
+      v.visit_term(term);
+    }
+    &Term::Quote(_, ref body) => {
+      for stm in body.iter() {
+        v.visit_stm(stm);
+      }
+    }
+    &Term::ListComp(_, ref elt, _, _, ref iter, ref cond) => {
+      v.visit_term(elt);
+      v.visit_term(iter);
+      if let Some(cond) = cond.as_ref() {
+        v.visit_term(cond);
+      }
+    }
+    &Term::ListLit(_, ref terms) |
+    &Term::Bunch(_, ref terms) |
+    &Term::Apply(_, ref terms) => {
+      for term in terms.iter() {
+        v.visit_term(term);
+      }
+    }
+    &Term::Equal(_, ref lterm, ref rterm) |
+    &Term::In(_, ref lterm, ref rterm) |
+    &Term::And(_, ref lterm, ref rterm) |
+    &Term::Or(_, ref lterm, ref rterm) |
+    &Term::NEqual(_, ref lterm, ref rterm) |
+    &Term::QEqual(_, ref lterm, ref rterm) |
+    &Term::BindL(_, ref lterm, ref rterm) |
+    &Term::BindR(_, ref lterm, ref rterm) |
+    &Term::Subst(_, ref lterm, ref rterm) |
+    &Term::RebindL(_, ref lterm, ref rterm) |
+    &Term::RebindR(_, ref lterm, ref rterm) => {
+      v.visit_term(lterm);
+      v.visit_term(rterm);
+    }
+    &Term::ApplyBindL(_, ref lterm, ref tup) |
+    &Term::Effect(_, ref lterm, ref tup) => {
+      v.visit_term(lterm);
+      for term in tup.iter() {
+        v.visit_term(term);
+      }
+    }
+    &Term::ApplyBindR(_, ref tup, ref rterm) => {
+      for term in tup.iter() {
+        v.visit_term(term);
+      }
+      v.visit_term(rterm);
+    }
+  }
+}
+
+// Adds `base` to every span (see `FastParser::set_span_base`).
+pub struct SpanRebase {
+  pub base: usize,
+}
+
+impl SpanRebase {
+  fn _rebase(&self, span: &mut Span) {
+    span.start += self.base;
+    span.end += self.base;
+  }
+}
+
+impl Rewriter for SpanRebase {
+  fn rewrite_mod(&mut self, mod_: Mod) -> Mod {
+    let mut mod_ = walk_rewrite_mod(self, mod_);
+    self._rebase(&mut mod_.span);
+    for pragma in mod_.pragmas.iter_mut() {
+      self._rebase(&mut pragma.span);
+    }
+    mod_
+  }
+
+  fn rewrite_stm(&mut self, stm: Stm) -> Stm {
+    let mut stm = walk_rewrite_stm(self, stm);
+    if let Some(span) = stm.span_mut() {
+      self._rebase(span);
+    }
+    stm
+  }
+
+  fn rewrite_term(&mut self, term: Term) -> Term {
+    let mut term = walk_rewrite_term(self, term);
+    self._rebase(term.span_mut());
+    term
+  }
+}
+
+// A by-value AST transform. The default methods rebuild each node from
+// its rewritten children (see `walk_rewrite_*`), preserving the node's
+// original span.
+pub trait Rewriter {
+  fn rewrite_mod(&mut self, mod_: Mod) -> Mod {
+    walk_rewrite_mod(self, mod_)
+  }
+
+  fn rewrite_stm(&mut self, stm: Stm) -> Stm {
+    walk_rewrite_stm(self, stm)
+  }
+
+  fn rewrite_term(&mut self, term: Term) -> Term {
+    walk_rewrite_term(self, term)
+  }
+}
+
+fn _rewrite_body<R: Rewriter + ?Sized>(r: &mut R, body: Vec<StmRef>) -> Vec<StmRef> {
+  body.into_iter().map(|stm| r.rewrite_stm(*stm).into()).collect()
+}
+
+fn _rewrite_terms<R: Rewriter + ?Sized>(r: &mut R, terms: Vec<TermRef>) -> Vec<TermRef> {
+  terms.into_iter().map(|term| r.rewrite_term(*term).into()).collect()
+}
+
+pub fn walk_rewrite_mod<R: Rewriter + ?Sized>(r: &mut R, mod_: Mod) -> Mod {
+  let Mod{span, body, pragmas} = mod_;
+  let body = _rewrite_body(r, body);
+  Mod{span, body, pragmas}
+}
+
+pub fn walk_rewrite_stm<R: Rewriter + ?Sized>(r: &mut R, stm: Stm) -> Stm {
+  match stm {
+    Stm::Just(span, term) => {
+      Stm::Just(span, r.rewrite_term(*term).into())
+    }
+    Stm::Assert(span, term) => {
+      Stm::Assert(span, r.rewrite_term(*term).into())
+    }
+    Stm::Expect(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Stm::Expect(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Stm::With(span, head, body) => {
+      let head = r.rewrite_term(*head).into();
+      Stm::With(span, head, _rewrite_body(r, body))
+    }
+    Stm::If(span, cases, final_) => {
+      let cases = cases.into_iter().map(|(cond, body)| {
+        let cond = r.rewrite_term(*cond).into();
+        (cond, _rewrite_body(r, body))
+      }).collect();
+      let final_ = final_.map(|body| _rewrite_body(r, body));
+      Stm::If(span, cases, final_)
+    }
+    Stm::Match(span, x, cases) => {
+      let x = r.rewrite_term(*x).into();
+      let cases = cases.into_iter().map(|(pat, body)| {
+        let pat = r.rewrite_term(*pat).into();
+        (pat, _rewrite_body(r, body))
+      }).collect();
+      Stm::Match(span, x, cases)
+    }
+    Stm::_Case(span, pat, body) => {
+      let pat = pat.map(|pat| r.rewrite_term(*pat).into());
+      Stm::_Case(span, pat, _rewrite_body(r, body))
+    }
+    Stm::Def(span, prefix, x, body) => {
+      Stm::Def(span, prefix, x, _rewrite_body(r, body))
+    }
+    Stm::Defproc(span, prefix, head, params, rest, defaults, body) => {
+      let defaults = defaults.into_iter().map(|(param, default)| {
+        (param, r.rewrite_term(*default).into())
+      }).collect();
+      Stm::Defproc(span, prefix, head, params, rest, defaults, _rewrite_body(r, body))
+    }
+    Stm::Defmatch(span, prefix, head, params, body) => {
+      Stm::Defmatch(span, prefix, head, params, _rewrite_body(r, body))
+    }
+    Stm::Defrule(span, head, args, body) => {
+      let args = args.into_iter().map(|arg| r.rewrite_term(*arg).into()).collect();
+      Stm::Defrule(span, head, args, _rewrite_body(r, body))
+    }
+    Stm::Cases(span, cases) => {
+      let cases = cases.into_iter().map(|body| _rewrite_body(r, body)).collect();
+      Stm::Cases(span, cases)
+    }
+    Stm::Class(span, x, body) => {
+      Stm::Class(span, x, _rewrite_body(r, body))
+    }
+    Stm::Quote(span, x, body) => {
+      Stm::Quote(span, x, _rewrite_body(r, body))
+    }
+    stm => stm
+  }
+}
+
+pub fn walk_rewrite_term<R: Rewriter + ?Sized>(r: &mut R, term: Term) -> Term {
+  match term {
+    Term::QualIdent(span, term, s) => {
+      Term::QualIdent(span, r.rewrite_term(*term).into(), s)
+    }
+    Term::Neg(span, term) => {
+      Term::Neg(span, r.rewrite_term(*term).into())
+    }
+    Term::Group(span, term) => {
+      Term::Group(span, r.rewrite_term(*term).into())
+    }
+    Term::Query(span, term) => {
+      Term::Query(span, r.rewrite_term(*term).into())
+    }
+    Term::Not(span, term) => {
+      Term::Not(span, r.rewrite_term(*term).into())
+    }
+    Term::Await(span, term) => {
+      Term::Await(span, r.rewrite_term(*term).into())
+    }
+    Term::ListLit(span, terms) => {
+      Term::ListLit(span, _rewrite_terms(r, terms))
+    }
+    Term::Bunch(span, terms) => {
+      Term::Bunch(span, _rewrite_terms(r, terms))
+    }
+    Term::Apply(span, terms) => {
+      Term::Apply(span, _rewrite_terms(r, terms))
+    }
+    Term::Kwarg(span, k, term) => {
+      Term::Kwarg(span, k, r.rewrite_term(*term).into())
+    }
+    Term::Splat(span, term) => {
+      Term::Splat(span, r.rewrite_term(*term).into())
+    }
+    Term::Lambda(span, params, body) => {
+      Term::Lambda(span, params, r.rewrite_term(*body).into())
+    }
+    Term::Quote(span, body) => {
+      Term::Quote(span, _rewrite_body(r, body))
+    }
+    Term::ListComp(span, elt, target, choice, iter, cond) => {
+      let elt = r.rewrite_term(*elt).into();
+      let iter = r.rewrite_term(*iter).into();
+      let cond = cond.map(|cond| r.rewrite_term(*cond).into());
+      Term::ListComp(span, elt, target, choice, iter, cond)
+    }
+    Term::Equal(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::Equal(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::In(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::In(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::And(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::And(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::Or(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::Or(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::NEqual(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::NEqual(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::QEqual(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::QEqual(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::BindL(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::BindL(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::BindR(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::BindR(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::Subst(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::Subst(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::RebindL(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::RebindL(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::RebindR(span, lterm, rterm) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::RebindR(span, lterm, r.rewrite_term(*rterm).into())
+    }
+    Term::ApplyBindL(span, lterm, tup) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::ApplyBindL(span, lterm, _rewrite_terms(r, tup))
+    }
+    Term::ApplyBindR(span, tup, rterm) => {
+      let tup = _rewrite_terms(r, tup);
+      Term::ApplyBindR(span, tup, r.rewrite_term(*rterm).into())
+    }
+    Term::Effect(span, lterm, rtup) => {
+      let lterm = r.rewrite_term(*lterm).into();
+      Term::Effect(span, lterm, _rewrite_terms(r, rtup))
+    }
+    term => term
+  }
+}
+
+// An example pass: fold int lits into canonical decimal form (e.g.
+// `007` => `7`, `-0` => `0`), and fold groups of a single int lit
+// (e.g. `(1)` => `1`).
+#[derive(Default)]
+pub struct IntLitFold {
+  pub fold_ct: usize,
+}
+
+impl Rewriter for IntLitFold {
+  fn rewrite_term(&mut self, term: Term) -> Term {
+    match walk_rewrite_term(self, term) {
+      Term::IntLit(span, s) => {
+        let v: i64 = match s.as_raw_str().parse() {
+          // NB: an out-of-range lit is left as is.
+          Err(_) => return Term::IntLit(span, s),
+          Ok(v) => v
+        };
+        let canon = v.to_string();
+        if canon.as_str() == s.as_raw_str() {
+          return Term::IntLit(span, s);
+        }
+        self.fold_ct += 1;
+        Term::IntLit(span, canon.into())
+      }
+      Term::Group(span, term) => {
+        match *term {
+          Term::IntLit(_, s) => {
+            self.fold_ct += 1;
+            Term::IntLit(span, s)
+          }
+          term => Term::Group(span, term.into())
+        }
+      }
+      term => term
+    }
+  }
+}
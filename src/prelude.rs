@@ -0,0 +1,42 @@
+// The public surface of the crate, for embedding the interpreter, e.g.:
+//
+//     use pythia::prelude::*;
+//
+//     let mut interp = Interp::builder().build()?;
+//     interp.cold_start(src)?;
+//
+// The aliases below name the stable public items w/out their trailing
+// underscores. Items that are only reachable via `crate::interp` (or the
+// other modules) are internal to the interpreter, and may change.
+
+pub use crate::interp::{
+  FastInterp as Interp,
+  FastInterpBuilder as InterpBuilder,
+  FlatInterp,
+  Function,
+  InterpCheck as Check,
+  LClk,
+  ObjCls,
+  UnifierStats,
+  UnifyMode,
+  Yield_ as Yield,
+};
+pub use crate::interp::debug::{
+  StepInfo,
+  WatchChange_ as WatchChange,
+  WatchHit,
+};
+pub use crate::interp::mem::{MemLimitAction, MemoryReport};
+pub use crate::interp::query::{QueryBindings, QueryIter};
+pub use crate::interp::registry::{BuiltinArity, BuiltinInfo, BuiltinKind, PreludeRegistry};
+pub use crate::interp::repl::{ReplOutcome};
+pub use crate::parse::{
+  Mod,
+  ModJsonError,
+  ParseSpanError as ParseError,
+  Parser,
+  ParserMode,
+  Stm,
+  Term,
+};
+pub use crate::smp::{SmpCacheSizes, SmpInfo, SmpTopology};
@@ -8,17 +8,38 @@ use libc::{_SC_PAGESIZE, sysconf};
 use once_cell::sync::{Lazy};
 #[cfg(feature = "rayon")]
 use rayon::{ThreadPoolBuilder};
+use serde::{Serialize, Deserialize};
 
 #[cfg(target_os = "linux")]
 pub mod linux;
 #[cfg(target_os = "macos")]
 pub mod macos;
+pub mod pool;
 
 pub static ONCE_SMP_INFO: Lazy<SmpInfo> = Lazy::new(|| SmpInfo::new());
 thread_local! {
   pub static TL_SMP_INFO: SmpInfo = SmpInfo::global_clone();
 }
 
+// The per-instance sizes (in bytes) of the CPU caches, where known.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct SmpCacheSizes {
+  pub l1d:  Option<usize>,
+  pub l1i:  Option<usize>,
+  pub l2:   Option<usize>,
+  pub l3:   Option<usize>,
+}
+
+// A summary of the `SmpInfo`, for embedders (see `SmpInfo::topology`).
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct SmpTopology {
+  pub page_sz:  Option<usize>,
+  pub core_ct:  Option<u16>,
+  pub node_ct:  Option<u16>,
+  pub mem_sz:   Option<usize>,
+  pub cache_sz: SmpCacheSizes,
+}
+
 #[derive(Clone)]
 pub struct SmpInfo {
   pub sc_page_sz: Option<usize>,
@@ -80,6 +101,50 @@ impl SmpInfo {
     }
     None
   }
+
+  pub fn numa_node_count(&self) -> Option<u16> {
+    #[cfg(target_os = "linux")]
+    if let Some(lscpu) = self.lscpu.as_ref() {
+      return lscpu.numa_node_count();
+    }
+    #[cfg(target_os = "macos")]
+    if let Some(sysctl) = self.sysctl.as_ref() {
+      return sysctl.numa_node_count();
+    }
+    None
+  }
+
+  pub fn cache_sizes(&self) -> SmpCacheSizes {
+    #[cfg(target_os = "linux")]
+    if let Some(lscpu) = self.lscpu.as_ref() {
+      return lscpu.cache_sizes();
+    }
+    #[cfg(target_os = "macos")]
+    if let Some(sysctl) = self.sysctl.as_ref() {
+      return sysctl.cache_sizes();
+    }
+    SmpCacheSizes::default()
+  }
+
+  // The CPUs to which workers may be pinned, one per physical core (see
+  // `pool::WorkerPool`); empty if unknown, or if pinning is unsupported.
+  pub fn pin_cpus(&self) -> Vec<u16> {
+    #[cfg(target_os = "linux")]
+    if let Some(lscpu) = self.lscpu.as_ref() {
+      return lscpu.core_cpus();
+    }
+    Vec::new()
+  }
+
+  pub fn topology(&self) -> SmpTopology {
+    SmpTopology{
+      page_sz:  self.arch_page_size(),
+      core_ct:  self.physical_core_count(),
+      node_ct:  self.numa_node_count(),
+      mem_sz:   self.physical_memory_size(),
+      cache_sz: self.cache_sizes(),
+    }
+  }
 }
 
 #[cfg(not(feature = "rayon"))]
@@ -100,3 +165,28 @@ pub fn _init_rayon() {
 pub fn init_smp() {
   _init_rayon();
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn test_lscpu_topology() {
+    use self::linux::{LscpuParse};
+    // NB: the output of `lscpu -p`, for 2 cores w/ 2 threads each, on 2
+    // NUMA nodes.
+    let out = "# CPU,Core,Socket,Node,,L1d,L1i,L2,L3\n0,0,0,0,,0,0,0,0\n1,0,0,0,,0,0,0,0\n2,1,0,1,,1,1,1,0\n3,1,0,1,,1,1,1,0\n";
+    let mut lscpu = LscpuParse::parse(out).unwrap();
+    assert_eq!((lscpu.physical_core_count(), lscpu.numa_node_count()), (Some(2), Some(2)));
+    assert_eq!(lscpu.core_cpus(), vec![0, 2]);
+    lscpu.cache_sz = LscpuParse::parse_caches("L1d 49152\nL1i 32768\nL2 1310720\nL3 2621440\n").unwrap();
+    assert_eq!(lscpu.cache_sizes(), SmpCacheSizes{
+      l1d: Some(49152), l1i: Some(32768), l2: Some(1310720), l3: Some(2621440),
+    });
+    let info = SmpInfo{sc_page_sz: None, lscpu: Some(lscpu)};
+    let topo = info.topology();
+    assert_eq!((topo.core_ct, topo.node_ct), (Some(2), Some(2)));
+    assert_eq!(topo.cache_sz.l3, Some(2621440));
+  }
+}
@@ -1,4 +1,5 @@
 use crate::algo::{BTreeSet};
+use crate::smp::{SmpCacheSizes};
 
 use std::io::{Cursor};
 use std::process::{Command, Stdio};
@@ -16,6 +17,8 @@ pub struct LscpuEntry {
 pub struct LscpuParse {
   pub entries: Vec<LscpuEntry>,
   pub core_ct: u16,
+  pub node_ct: u16,
+  pub cache_sz: SmpCacheSizes,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -34,7 +37,54 @@ impl LscpuParse {
     if !out.status.success() {
       return Err(());
     }
-    LscpuParse::parse(out.stdout)
+    let mut info = LscpuParse::parse(out.stdout)?;
+    // NB: the cache sizes are optional, since older lscpu (before
+    // util-linux 2.34) does not support `-C`.
+    if let Ok(cache_sz) = LscpuParse::open_caches() {
+      info.cache_sz = cache_sz;
+    }
+    Ok(info)
+  }
+
+  pub fn open_caches() -> Result<SmpCacheSizes, ()> {
+    let out = Command::new("lscpu")
+        .arg("-C=NAME,ONE-SIZE")
+        .arg("-B")
+        .arg("--noheadings")
+        .stdout(Stdio::piped())
+        .output()
+        .map_err(|_| ())?;
+    if !out.status.success() {
+      return Err(());
+    }
+    LscpuParse::parse_caches(out.stdout)
+  }
+
+  // Parses the output of `lscpu -C=NAME,ONE-SIZE -B --noheadings`, i.e.
+  // lines of the form `L1d 49152`, where the size (in bytes) is per cache
+  // instance.
+  pub fn parse_caches<O: AsRef<[u8]>>(out: O) -> Result<SmpCacheSizes, ()> {
+    let out = from_utf8(out.as_ref()).map_err(|_| ())?;
+    let mut cache_sz = SmpCacheSizes::default();
+    for line in out.lines() {
+      let mut line_parts = line.split_ascii_whitespace();
+      let (name, sz) = match (line_parts.next(), line_parts.next()) {
+        (Some(name), Some(sz)) => (name, sz),
+        _ => continue
+      };
+      let sz: usize = match sz.parse() {
+        Err(_) => continue,
+        Ok(sz) => sz
+      };
+      match name {
+        "L1d" => cache_sz.l1d = Some(sz),
+        "L1i" => cache_sz.l1i = Some(sz),
+        "L2" => cache_sz.l2 = Some(sz),
+        "L3" => cache_sz.l3 = Some(sz),
+        _ => {}
+      }
+    }
+    Ok(cache_sz)
   }
 
   pub fn parse<O: AsRef<[u8]>>(out: O) -> Result<LscpuParse, ()> {
@@ -100,10 +150,15 @@ impl LscpuParse {
     let mut info = LscpuParse{
       entries,
       core_ct: 0,
+      node_ct: 0,
+      cache_sz: SmpCacheSizes::default(),
     };
     if let Some(ct) = info._physical_core_count() {
       info.core_ct = ct;
     }
+    if let Some(ct) = info._numa_node_count() {
+      info.node_ct = ct;
+    }
     Ok(info)
   }
 
@@ -126,4 +181,41 @@ impl LscpuParse {
       Some(self.core_ct)
     }
   }
+
+  // The first logical CPU of each physical core, in CPU order (e.g. for
+  // pinning one worker per core, see `smp::pool::WorkerPool`).
+  pub fn core_cpus(&self) -> Vec<u16> {
+    let mut core = BTreeSet::new();
+    let mut cpus = Vec::new();
+    for e in self.entries.iter() {
+      if core.insert((e.sock, e.core)) {
+        cpus.push(e.cpu);
+      }
+    }
+    cpus.sort();
+    cpus
+  }
+
+  pub fn _numa_node_count(&self) -> Option<u16> {
+    let mut node = BTreeSet::new();
+    for e in self.entries.iter() {
+      node.insert(e.node);
+    }
+    if node.len() == 0 {
+      return None;
+    }
+    node.len().try_into().ok()
+  }
+
+  pub fn numa_node_count(&self) -> Option<u16> {
+    if self.node_ct == 0 {
+      None
+    } else {
+      Some(self.node_ct)
+    }
+  }
+
+  pub fn cache_sizes(&self) -> SmpCacheSizes {
+    self.cache_sz
+  }
 }
@@ -1,3 +1,5 @@
+use crate::smp::{SmpCacheSizes};
+
 use std::io::{BufRead, Cursor};
 use std::process::{Command, Stdio};
 
@@ -5,6 +7,7 @@ use std::process::{Command, Stdio};
 pub struct SysctlParse {
   pub core_ct: u16,
   pub mem_sz: usize,
+  pub cache_sz: SmpCacheSizes,
 }
 
 impl SysctlParse {
@@ -24,6 +27,7 @@ impl SysctlParse {
     let mut info = SysctlParse{
       core_ct: 0,
       mem_sz: 0,
+      cache_sz: SmpCacheSizes::default(),
     };
     let out = out.as_ref();
     for line in Cursor::new(out).lines() {
@@ -41,6 +45,33 @@ impl SysctlParse {
             "hw.memsize_usable:" => {
               info.mem_sz = val.parse().unwrap();
             }
+            // NB: the cache sizes of the performance cores are preferred
+            // over the (legacy) cache sizes of the whole package, if both
+            // are present.
+            "hw.perflevel0.l1dcachesize:" => {
+              info.cache_sz.l1d = val.parse().ok();
+            }
+            "hw.perflevel0.l1icachesize:" => {
+              info.cache_sz.l1i = val.parse().ok();
+            }
+            "hw.perflevel0.l2cachesize:" => {
+              info.cache_sz.l2 = val.parse().ok();
+            }
+            "hw.perflevel0.l3cachesize:" => {
+              info.cache_sz.l3 = val.parse().ok();
+            }
+            "hw.l1dcachesize:" => {
+              info.cache_sz.l1d = info.cache_sz.l1d.or(val.parse().ok());
+            }
+            "hw.l1icachesize:" => {
+              info.cache_sz.l1i = info.cache_sz.l1i.or(val.parse().ok());
+            }
+            "hw.l2cachesize:" => {
+              info.cache_sz.l2 = info.cache_sz.l2.or(val.parse().ok());
+            }
+            "hw.l3cachesize:" => {
+              info.cache_sz.l3 = info.cache_sz.l3.or(val.parse().ok());
+            }
             _ => {}
           }
         }
@@ -63,4 +94,14 @@ impl SysctlParse {
     }
     Some(self.mem_sz)
   }
+
+  pub fn cache_sizes(&self) -> SmpCacheSizes {
+    self.cache_sz
+  }
+
+  // NB: macOS does not expose NUMA nodes; its memory is treated as
+  // uniform, i.e. as one node.
+  pub fn numa_node_count(&self) -> Option<u16> {
+    Some(1)
+  }
 }
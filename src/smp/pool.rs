@@ -0,0 +1,249 @@
+use crate::smp::{SmpInfo};
+
+use std::io::{Error as IoError};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError, channel};
+use std::thread::{Builder as ThreadBuilder, JoinHandle};
+
+pub type WorkerTask = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Debug)]
+pub enum WorkerPoolError {
+  // The pool has been shut down.
+  Shutdown,
+  // There is no worker w/ the given index.
+  NoWorker(usize),
+  // A worker thread failed to spawn.
+  Spawn(IoError),
+  // The task panicked on the given worker.
+  Panicked(usize),
+}
+
+// The result of a task submitted to a `WorkerPool`.
+pub struct WorkerTaskHandle<T> {
+  worker:   usize,
+  res_rx:   Receiver<Result<T, WorkerPoolError>>,
+}
+
+impl<T> WorkerTaskHandle<T> {
+  pub fn worker(&self) -> usize {
+    self.worker
+  }
+
+  // Blocks until the task is done; `WorkerPoolError::Panicked` if the
+  // task panicked.
+  pub fn join(self) -> Result<T, WorkerPoolError> {
+    match self.res_rx.recv() {
+      Ok(res) => res,
+      Err(_) => Err(WorkerPoolError::Shutdown)
+    }
+  }
+
+  // Returns the result if the task is done, w/out blocking; otherwise,
+  // hands back the handle (as the inner `Err`).
+  pub fn try_join(self) -> Result<Result<T, WorkerTaskHandle<T>>, WorkerPoolError> {
+    match self.res_rx.try_recv() {
+      Ok(res) => res.map(Ok),
+      Err(TryRecvError::Empty) => Ok(Err(self)),
+      Err(TryRecvError::Disconnected) => Err(WorkerPoolError::Shutdown),
+    }
+  }
+}
+
+struct Worker_ {
+  task_tx:  Option<Sender<WorkerTask>>,
+  cpu:      Option<u16>,
+  pinned:   bool,
+  handle:   Option<JoinHandle<()>>,
+}
+
+// A pool of dedicated, long-lived worker threads, each of which may be
+// pinned to one CPU (see `SmpInfo::pin_cpus`); unlike a work-stealing
+// pool, a task runs on the worker that it was submitted to, so that e.g.
+// successive tasks on the same (COW-forked) state stay cache-local.
+//
+// Each worker has its own unbounded task queue. `submit` picks a worker
+// round-robin, while `submit_to` picks a given worker. A panicking task
+// does not take down its worker; instead, its handle returns the panic
+// as `WorkerPoolError::Panicked`. Upon `shutdown` (or drop), the queues
+// are closed, and every worker exits after it drains its queue.
+pub struct WorkerPool {
+  workers:  Vec<Worker_>,
+  next:     AtomicUsize,
+}
+
+impl Drop for WorkerPool {
+  fn drop(&mut self) {
+    self.shutdown();
+  }
+}
+
+impl WorkerPool {
+  // A pool of `worker_ct` workers, pinned one per physical core (while
+  // there are enough cores; the remaining workers are not pinned).
+  pub fn new(worker_ct: usize) -> Result<WorkerPool, WorkerPoolError> {
+    let cpus = SmpInfo::tl_clone().pin_cpus();
+    WorkerPool::with_cpus(worker_ct, &cpus)
+  }
+
+  // A pool of `worker_ct` workers, none of which are pinned.
+  pub fn unpinned(worker_ct: usize) -> Result<WorkerPool, WorkerPoolError> {
+    WorkerPool::with_cpus(worker_ct, &[])
+  }
+
+  // A pool of `worker_ct` workers, where worker `w` is pinned to `cpus[w]`
+  // (if any). Pinning is best-effort: a worker that fails to pin (e.g.
+  // under a restricted cpuset) is logged, and stays unpinned.
+  pub fn with_cpus(worker_ct: usize, cpus: &[u16]) -> Result<WorkerPool, WorkerPoolError> {
+    let worker_ct = worker_ct.max(1);
+    let mut workers = Vec::with_capacity(worker_ct);
+    let mut pin_rxs = Vec::with_capacity(worker_ct);
+    for w in 0 .. worker_ct {
+      let cpu = cpus.get(w).copied();
+      let (task_tx, task_rx) = channel::<WorkerTask>();
+      let (pin_tx, pin_rx) = channel();
+      let handle = ThreadBuilder::new()
+        .name(format!("smp-worker-{}", w))
+        .spawn(move || {
+          let pinned = match cpu {
+            None => false,
+            Some(cpu) => _pin_current_thread(cpu)
+          };
+          let _ = pin_tx.send(pinned);
+          _worker_loop(task_rx)
+        })
+        .map_err(WorkerPoolError::Spawn)?;
+      workers.push(Worker_{
+        task_tx:  Some(task_tx),
+        cpu,
+        pinned:   false,
+        handle:   Some(handle),
+      });
+      pin_rxs.push(pin_rx);
+    }
+    for (worker, pin_rx) in workers.iter_mut().zip(pin_rxs.into_iter()) {
+      worker.pinned = pin_rx.recv().unwrap_or(false);
+    }
+    for (w, worker) in workers.iter().enumerate() {
+      if let (Some(cpu), false) = (worker.cpu, worker.pinned) {
+        println!("DEBUG: WorkerPool::with_cpus: warning: worker {} failed to pin to cpu {}", w, cpu);
+      }
+    }
+    Ok(WorkerPool{
+      workers,
+      next: AtomicUsize::new(0),
+    })
+  }
+
+  pub fn worker_count(&self) -> usize {
+    self.workers.len()
+  }
+
+  // The CPU to which worker `w` is pinned, if it is.
+  pub fn worker_cpu(&self, w: usize) -> Option<u16> {
+    self.workers.get(w).filter(|worker| worker.pinned).and_then(|worker| worker.cpu)
+  }
+
+  pub fn submit<T, F>(&self, f: F) -> Result<WorkerTaskHandle<T>, WorkerPoolError>
+  where T: Send + 'static, F: FnOnce() -> T + Send + 'static {
+    let w = self.next.fetch_add(1, Ordering::Relaxed) % self.workers.len();
+    self.submit_to(w, f)
+  }
+
+  pub fn submit_to<T, F>(&self, w: usize, f: F) -> Result<WorkerTaskHandle<T>, WorkerPoolError>
+  where T: Send + 'static, F: FnOnce() -> T + Send + 'static {
+    let worker = match self.workers.get(w) {
+      None => return Err(WorkerPoolError::NoWorker(w)),
+      Some(worker) => worker
+    };
+    let task_tx = match worker.task_tx.as_ref() {
+      None => return Err(WorkerPoolError::Shutdown),
+      Some(tx) => tx
+    };
+    let (res_tx, res_rx) = channel();
+    let task: WorkerTask = Box::new(move || {
+      let res = catch_unwind(AssertUnwindSafe(f)).map_err(|_| WorkerPoolError::Panicked(w));
+      let _ = res_tx.send(res);
+    });
+    task_tx.send(task).map_err(|_| WorkerPoolError::Shutdown)?;
+    Ok(WorkerTaskHandle{worker: w, res_rx})
+  }
+
+  // Closes the task queues, and waits for every worker to drain its queue
+  // and exit. Subsequent submissions fail w/ `WorkerPoolError::Shutdown`.
+  pub fn shutdown(&mut self) {
+    for worker in self.workers.iter_mut() {
+      worker.task_tx = None;
+    }
+    for worker in self.workers.iter_mut() {
+      if let Some(handle) = worker.handle.take() {
+        let _ = handle.join();
+      }
+    }
+  }
+}
+
+fn _worker_loop(task_rx: Receiver<WorkerTask>) {
+  // NB: a task catches its own panic (see `WorkerPool::submit_to`).
+  while let Ok(task) = task_rx.recv() {
+    task();
+  }
+}
+
+#[cfg(target_os = "linux")]
+fn _pin_current_thread(cpu: u16) -> bool {
+  use libc::{CPU_SET, CPU_SETSIZE, CPU_ZERO, cpu_set_t, sched_setaffinity};
+  use std::mem::{size_of, zeroed};
+
+  if cpu as i32 >= CPU_SETSIZE {
+    return false;
+  }
+  unsafe {
+    let mut set: cpu_set_t = zeroed();
+    CPU_ZERO(&mut set);
+    CPU_SET(cpu as usize, &mut set);
+    sched_setaffinity(0, size_of::<cpu_set_t>(), &set) == 0
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn _pin_current_thread(_cpu: u16) -> bool {
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_worker_pool() {
+    let mut pool = WorkerPool::unpinned(2).unwrap();
+    assert_eq!(pool.worker_count(), 2);
+    assert_eq!(pool.worker_cpu(0), None);
+    // NB: `submit` picks the workers round-robin.
+    let handles: Vec<_> = (0 .. 4_u32).map(|i| pool.submit(move || i * i).unwrap()).collect();
+    let workers: Vec<_> = handles.iter().map(|h| h.worker()).collect();
+    assert_eq!(workers, vec![0, 1, 0, 1]);
+    let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert_eq!(results, vec![0, 1, 4, 9]);
+    assert!(matches!(pool.submit_to(2, || ()), Err(WorkerPoolError::NoWorker(2))));
+    // NB: a panicking task does not take down its worker.
+    let h = pool.submit_to(1, || -> u32 { panic!("task panic") }).unwrap();
+    assert!(matches!(h.join(), Err(WorkerPoolError::Panicked(1))));
+    assert_eq!(pool.submit_to(1, || 7).unwrap().join().unwrap(), 7);
+    pool.shutdown();
+    assert!(matches!(pool.submit(|| ()), Err(WorkerPoolError::Shutdown)));
+  }
+
+  #[test]
+  fn test_worker_pool_pin_failure() {
+    // NB: a cpu past the cpu set size always fails to pin, but the worker
+    // still runs its tasks, unpinned.
+    let pool = WorkerPool::with_cpus(2, &[u16::MAX, u16::MAX]).unwrap();
+    assert_eq!(pool.worker_count(), 2);
+    assert_eq!(pool.worker_cpu(0), None);
+    assert_eq!(pool.worker_cpu(1), None);
+    assert_eq!(pool.submit_to(1, || 7).unwrap().join().unwrap(), 7);
+  }
+}
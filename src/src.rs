@@ -38,3 +38,269 @@ _src!(SYS_MMAP, "/src/sys/mmap.rs");
 _src!(TAP, "/src/tap.rs");
 _src!(UTIL, "/src/util.rs");
 //_src!(UTIL_HEX, "/src/util/hex.rs");
+
+use crate::util::encoding::{Encoding, EncodingDetector, UTF_8};
+use crate::util::gzip::{GzipReader};
+
+use std::borrow::{Cow};
+use std::fs::{read};
+use std::io::{Error as IoError, copy as iocopy};
+use std::ops::{Range};
+use std::path::{Path, PathBuf};
+
+// The policy for malformed byte sequences in `transcode`.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ReplacementPolicy {
+  // Replace each malformed sequence w/ U+FFFD.
+  #[default]
+  Replace,
+  // Fail on the first malformed sequence.
+  Strict,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TranscodeConfig {
+  pub replacement: ReplacementPolicy,
+  // A hint to the encoding detector: the top-level domain (e.g. "jp")
+  // that the source bytes came from, if known.
+  pub tld: Option<&'static str>,
+  // Whether the detector may guess UTF-8; NB: valid UTF-8 is accepted
+  // before the detector runs, so this only affects malformed UTF-8.
+  pub allow_utf8: bool,
+}
+
+impl Default for TranscodeConfig {
+  fn default() -> TranscodeConfig {
+    TranscodeConfig{
+      replacement: ReplacementPolicy::default(),
+      tld: None,
+      allow_utf8: true,
+    }
+  }
+}
+
+// How the source bytes were decoded (see `transcode`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EncodingSource {
+  // The bytes were valid UTF-8.
+  Utf8,
+  // The bytes began w/ a byte order mark.
+  Bom,
+  // The encoding was guessed by chardetng.
+  Detected,
+}
+
+#[derive(Clone, Debug)]
+pub struct SourceEncoding {
+  // The (WHATWG) name of the encoding, e.g. "UTF-8" or "Shift_JIS".
+  pub name:     &'static str,
+  pub source:   EncodingSource,
+  // Whether any malformed sequences were replaced.
+  pub replaced: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum TranscodeError {
+  // A malformed sequence under `ReplacementPolicy::Strict`.
+  Malformed(&'static str),
+}
+
+// Decodes the source bytes `buf` to UTF-8: a byte order mark takes
+// precedence, then valid UTF-8, then the guess of chardetng.
+pub fn transcode<'a>(buf: &'a [u8], cfg: &TranscodeConfig) -> Result<(Cow<'a, str>, SourceEncoding), TranscodeError> {
+  let (enc, source, body) = match Encoding::for_bom(buf) {
+    Some((enc, bom_len)) => (enc, EncodingSource::Bom, &buf[bom_len .. ]),
+    None => match std::str::from_utf8(buf) {
+      Ok(s) => {
+        let enc = SourceEncoding{name: UTF_8.name(), source: EncodingSource::Utf8, replaced: false};
+        return Ok((Cow::Borrowed(s), enc));
+      }
+      Err(_) => {
+        let mut detector = EncodingDetector::new();
+        detector.feed(buf, true);
+        let enc = detector.guess(cfg.tld.map(|tld| tld.as_bytes()), cfg.allow_utf8);
+        (enc, EncodingSource::Detected, buf)
+      }
+    }
+  };
+  let (text, replaced) = match cfg.replacement {
+    ReplacementPolicy::Replace => {
+      enc.decode_without_bom_handling(body)
+    }
+    ReplacementPolicy::Strict => {
+      match enc.decode_without_bom_handling_and_without_replacement(body) {
+        None => return Err(TranscodeError::Malformed(enc.name())),
+        Some(text) => (text, false)
+      }
+    }
+  };
+  Ok((text, SourceEncoding{name: enc.name(), source, replaced}))
+}
+
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug)]
+pub enum SourceFileError {
+  Io(IoError),
+  Transcode(TranscodeError),
+}
+
+impl From<IoError> for SourceFileError {
+  fn from(e: IoError) -> SourceFileError {
+    SourceFileError::Io(e)
+  }
+}
+
+impl From<TranscodeError> for SourceFileError {
+  fn from(e: TranscodeError) -> SourceFileError {
+    SourceFileError::Transcode(e)
+  }
+}
+
+// A source file, read (and decompressed, if gzipped) and transcoded to
+// UTF-8. Spans into `text` can be attributed to the origin `path` (see
+// `span_loc`).
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+  pub path: PathBuf,
+  pub gzip: bool,
+  pub enc:  SourceEncoding,
+  pub text: String,
+}
+
+impl SourceFile {
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<SourceFile, SourceFileError> {
+    SourceFile::open_with(path, &TranscodeConfig::default())
+  }
+
+  // NB: a gzipped file is detected by its magic bytes, rather than by its
+  // file extension.
+  pub fn open_with<P: AsRef<Path>>(path: P, cfg: &TranscodeConfig) -> Result<SourceFile, SourceFileError> {
+    let path = path.as_ref().to_owned();
+    let raw = read(&path)?;
+    let (gzip, buf) = if raw.starts_with(&GZIP_MAGIC) {
+      let mut reader = GzipReader::new(&raw[ .. ]);
+      let mut buf = Vec::new();
+      iocopy(&mut reader, &mut buf)?;
+      (true, buf)
+    } else {
+      (false, raw)
+    };
+    let (text, enc) = transcode(&buf, cfg)?;
+    let text = text.into_owned();
+    Ok(SourceFile{path, gzip, enc, text})
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.text
+  }
+
+  // The 1-based line and (char) column of the byte offset `off`.
+  pub fn line_col(&self, off: usize) -> (usize, usize) {
+    let off = off.min(self.text.len());
+    let prefix = self.text.get( .. off).unwrap_or("");
+    let line = prefix.matches('\n').count() + 1;
+    let line_start = prefix.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let col = prefix[line_start .. ].chars().count() + 1;
+    (line, col)
+  }
+
+  // Formats the start of `span` as `path:line:col`.
+  pub fn span_loc(&self, span: &Range<usize>) -> String {
+    let (line, col) = self.line_col(span.start);
+    format!("{}:{}:{}", self.path.display(), line, col)
+  }
+}
+
+pub type FileId = u32;
+
+#[derive(Clone, Debug)]
+pub struct SourceMapFile {
+  pub id:   FileId,
+  // NB: `None` for a source that did not come from a file.
+  pub path: Option<PathBuf>,
+  // The offset of the file in the combined source.
+  pub base: usize,
+  pub len:  usize,
+  // The (file-relative) offsets of the starts of the lines.
+  line_starts: Vec<usize>,
+}
+
+impl SourceMapFile {
+  pub fn range(&self) -> Range<usize> {
+    self.base .. self.base + self.len
+  }
+}
+
+// The map from the offsets of a combined source, i.e. of several files
+// loaded into one interpreter, to the files and their lines.
+//
+// The combined source is the concatenation of the files in the order
+// they were added, w/ one separating `'\n'` between consecutive files;
+// the parser shifts the spans of each file by its base offset (see
+// `FastParser::set_span_base`), so that every span of the loaded module
+// is an offset into the combined source.
+#[derive(Clone, Default, Debug)]
+pub struct SourceMap {
+  files: Vec<SourceMapFile>,
+  end:   usize,
+}
+
+impl SourceMap {
+  pub fn is_empty(&self) -> bool {
+    self.files.is_empty()
+  }
+
+  pub fn files(&self) -> &[SourceMapFile] {
+    &self.files
+  }
+
+  pub fn file(&self, id: FileId) -> Option<&SourceMapFile> {
+    self.files.get(id as usize)
+  }
+
+  // Adds a file w/ the source text `text`, and returns its id and its
+  // base offset.
+  pub fn add_file(&mut self, path: Option<PathBuf>, text: &str) -> (FileId, usize) {
+    let id: FileId = self.files.len().try_into().unwrap();
+    let base = if self.files.is_empty() { 0 } else { self.end + 1 };
+    let mut line_starts = vec![0];
+    line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+    self.files.push(SourceMapFile{id, path, base, len: text.len(), line_starts});
+    self.end = base + text.len();
+    (id, base)
+  }
+
+  // The file that contains the offset `off` (incl. the end of the file).
+  pub fn lookup(&self, off: usize) -> Option<&SourceMapFile> {
+    let idx = self.files.partition_point(|f| f.base <= off);
+    if idx == 0 {
+      return None;
+    }
+    let file = &self.files[idx - 1];
+    if off > file.base + file.len {
+      return None;
+    }
+    Some(file)
+  }
+
+  // The file id, and the 1-based line and (byte) column, of the offset
+  // `off` in the combined source.
+  pub fn line_col(&self, off: usize) -> Option<(FileId, usize, usize)> {
+    let file = self.lookup(off)?;
+    let rel = off - file.base;
+    let line = file.line_starts.partition_point(|&start| start <= rel);
+    let col = rel - file.line_starts[line - 1] + 1;
+    Some((file.id, line, col))
+  }
+
+  // Formats the start of `span` as `path:line:col` (or as `line:col`, if
+  // the file has no path).
+  pub fn span_loc(&self, span: &Range<usize>) -> Option<String> {
+    let (id, line, col) = self.line_col(span.start)?;
+    match self.files[id as usize].path.as_ref() {
+      None => Some(format!("{}:{}", line, col)),
+      Some(path) => Some(format!("{}:{}:{}", path.display(), line, col))
+    }
+  }
+}
@@ -5,10 +5,13 @@ use term_colors::{Colorize};
 
 use std::any::{Any};
 //use std::cell::{RefCell};
+use std::fmt::{Arguments};
 use std::io::{
   BufRead, Read, Write, Error as IoError,
   BufReader, BufWriter, Cursor, stdout
 };
+use std::mem::{replace};
+use std::sync::{Arc, Mutex};
 
 pub trait TAPProver {
   fn prove<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), IoError>;
@@ -106,6 +109,52 @@ impl<W: Write + Any> TAPWriteBuffer for W {
   }
 }
 
+// The subsystems w/ their own log level (see `TAPOutput::verbose`); the
+// levels are: 0 = error, 1 = warning, 2 = info, 3 = debug, 4-6 = more
+// verbose debug, and 7 = trace.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LogSubsys {
+  Parse,
+  Interp,
+}
+
+// A thread-safe log sink. The clones of a `LogWriter` share one writer,
+// and each log line is written whole (see `TAPOutput::_writeln`), so
+// that the parsers and interpreters on different threads may share one
+// sink (e.g. a file, or a buffer).
+#[derive(Clone)]
+pub struct LogWriter {
+  inner: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl LogWriter {
+  pub fn new<W: 'static + Write + Send>(writer: W) -> LogWriter {
+    LogWriter{inner: Arc::new(Mutex::new(Box::new(writer)))}
+  }
+
+  pub fn stdout() -> LogWriter {
+    LogWriter::new(std::io::stdout())
+  }
+}
+
+impl Write for LogWriter {
+  fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+    // NB: a panic while logging does not poison the sink for the others.
+    let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    inner.write(buf)
+  }
+
+  fn write_all(&mut self, buf: &[u8]) -> Result<(), IoError> {
+    let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    inner.write_all(buf)
+  }
+
+  fn flush(&mut self) -> Result<(), IoError> {
+    let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+    inner.flush()
+  }
+}
+
 pub struct TAPOutput {
   pub writer:   RefCell<Box<dyn Write>>,
   pub verbose:  i8,
@@ -128,13 +177,24 @@ impl TAPOutput {
       verbose:  0,
     }
   }
+
+  pub fn set_log_writer(&self, writer: LogWriter) -> Box<dyn Write> {
+    replace(&mut *self.writer.borrow_mut(), Box::new(writer))
+  }
+
+  // Writes one log line, in one write; a log write error is dropped.
+  pub fn _writeln(&self, args: Arguments) {
+    let mut line = args.to_string();
+    line.push('\n');
+    let _ = self.writer.borrow_mut().write_all(line.as_bytes());
+  }
 }
 
 macro_rules! _errorln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 0;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -145,7 +205,7 @@ macro_rules! _warningln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 1;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -156,7 +216,7 @@ macro_rules! _infoln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 2;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -167,7 +227,7 @@ macro_rules! _debugln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 3;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -178,7 +238,7 @@ macro_rules! _vdebugln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 4;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -189,7 +249,7 @@ macro_rules! _vvdebugln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 5;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -200,7 +260,7 @@ macro_rules! _vvvdebugln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 6;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -211,7 +271,7 @@ macro_rules! _traceln {
   ($self:expr, $($arg:tt)*) => {{
     let print = $self.tap.verbose >= 7;
     if print {
-      writeln!($self.tap.writer.borrow_mut(), $($arg)*).unwrap();
+      $self.tap._writeln(format_args!($($arg)*));
     }
     print
   }};
@@ -108,11 +108,16 @@ impl TestDataConfig {
     File::open(&p).ok()
   }*/
 
-  pub fn maybe_get_vector_file(&self, key: &str) -> Option<File> {
+  pub fn vector_path(&self, key: &str) -> PathBuf {
     let mut p = self.root.clone();
     p.push("__v");
     p.push(key);
     p.set_extension("json");
+    p
+  }
+
+  pub fn maybe_get_vector_file(&self, key: &str) -> Option<File> {
+    let p = self.vector_path(key);
     File::open(&p).ok()
   }
 
@@ -0,0 +1,174 @@
+use crate::clock::{Timedelta, Timestamp};
+use crate::interp::*;
+use crate::tap::*;
+
+use term_colors::{Colorize};
+use walkdir::{WalkDir};
+
+use std::fs::{read_to_string};
+use std::io::{Write, Error as IoError};
+use std::path::{Path, PathBuf};
+
+// NB: the test sources are python-syntax, so .py is accepted as well.
+pub const TEST_SOURCE_EXTS: &'static [&'static str] = &["pythia", "py"];
+
+#[derive(Debug)]
+pub enum TapStatus {
+  Ok(Yield_),
+  NotOk(String),
+}
+
+pub struct TapResult {
+  pub path: PathBuf,
+  pub status: TapStatus,
+  pub diag: Vec<String>,
+  pub t_start: Timedelta,
+  pub t_interp: Timedelta,
+}
+
+impl TapResult {
+  pub fn is_ok(&self) -> bool {
+    match &self.status {
+      &TapStatus::Ok(_) => true,
+      &TapStatus::NotOk(_) => false,
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct TapReport {
+  pub results: Vec<TapResult>,
+}
+
+impl TapReport {
+  pub fn ok_ct(&self) -> usize {
+    self.results.iter().filter(|r| r.is_ok()).count()
+  }
+
+  pub fn not_ok_ct(&self) -> usize {
+    self.results.iter().filter(|r| !r.is_ok()).count()
+  }
+
+  pub fn is_ok(&self) -> bool {
+    self.results.iter().all(|r| r.is_ok())
+  }
+}
+
+impl TAPProver for TapReport {
+  fn prove<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), IoError> {
+    writeln!(writer, "1..{}", self.results.len())?;
+    for (idx, r) in self.results.iter().enumerate() {
+      let rank = idx + 1;
+      match &r.status {
+        &TapStatus::Ok(ref yield_) => {
+          writeln!(writer, "{} {} - {}", "ok".green(), rank, r.path.display())?;
+          if yield_ != &Yield_::Quiescent {
+            writeln!(writer, "# yield = {:?}", yield_)?;
+          }
+        }
+        &TapStatus::NotOk(ref check) => {
+          writeln!(writer, "{} {} - {}", "not ok".red().bold(), rank, r.path.display())?;
+          writeln!(writer, "# check = {}", check)?;
+        }
+      }
+      for line in r.diag.iter() {
+        writeln!(writer, "# {}", line)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+// NB: the failed in-language tests (see `FastInterp::test_failures`) are
+// returned along w/ the result of the run.
+fn _run_src(src: &str) -> (Result<Yield_, InterpCheck>, Vec<TestFailure>, Timedelta, Timedelta) {
+  let t0 = Timestamp::fresh();
+  // NB: the interp TAP output is not part of the report.
+  let mut interp = match FastInterp::builder()
+    .trace_writer(wrap_tap_writer(Vec::<u8>::new()))
+    .build()
+  {
+    Err(check) => {
+      let t1 = Timestamp::fresh();
+      return (Err(check), Vec::new(), t1-t0, Timedelta::default());
+    }
+    Ok(interp) => interp
+  };
+  if let Err(check) = interp.cold_start(src) {
+    let t1 = Timestamp::fresh();
+    return (Err(check), interp.take_test_failures(), t1-t0, Timedelta::default());
+  }
+  let t1 = Timestamp::fresh();
+  let result = interp.interp_();
+  let t2 = Timestamp::fresh();
+  (result, interp.take_test_failures(), t1-t0, t2-t1)
+}
+
+pub fn run_file<P: AsRef<Path>>(path: P) -> TapResult {
+  let path = path.as_ref().to_owned();
+  let src = match read_to_string(&path) {
+    Err(e) => {
+      return TapResult{
+        path,
+        status: TapStatus::NotOk(format!("{:?}", e)),
+        diag: Vec::new(),
+        t_start: Timedelta::default(),
+        t_interp: Timedelta::default(),
+      };
+    }
+    Ok(src) => src
+  };
+  let (result, failures, t_start, t_interp) = _run_src(&src);
+  let status = match result {
+    Err(check) => TapStatus::NotOk(format!("{:?}", check)),
+    Ok(_) if !failures.is_empty() => {
+      TapStatus::NotOk(format!("test failure ct = {}", failures.len()))
+    }
+    Ok(yield_) => TapStatus::Ok(yield_)
+  };
+  let diag = failures.iter().map(|fail| fail.render()).collect();
+  TapResult{
+    path,
+    status,
+    diag,
+    t_start,
+    t_interp,
+  }
+}
+
+// Run every test source file (see `TEST_SOURCE_EXTS`) in the directory
+// `path`, recursively, in path order.
+pub fn run_dir<P: AsRef<Path>>(path: P) -> TapReport {
+  let mut paths = Vec::new();
+  for entry in WalkDir::new(path.as_ref()).sort_by_file_name() {
+    let entry = match entry {
+      Err(_) => continue,
+      Ok(entry) => entry
+    };
+    if !entry.file_type().is_file() {
+      continue;
+    }
+    match entry.path().extension().and_then(|ext| ext.to_str()) {
+      Some(ext) if TEST_SOURCE_EXTS.contains(&ext) => {}
+      _ => continue
+    }
+    paths.push(entry.path().to_owned());
+  }
+  let mut report = TapReport::default();
+  for p in paths.iter() {
+    report.results.push(run_file(p));
+  }
+  report
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_run_file_assert_fail() {
+    let r = run_file("data/test_fail/assert_fail_0.py");
+    assert!(!r.is_ok());
+    assert_eq!(r.diag.len(), 2);
+  }
+}
@@ -7,7 +7,7 @@ fn main() {
   let mut frame = Frame::root();
   frame.debug_print_status(&store);
   store.debug_print_digest();
-  let mut frame = frame.fresh(&mut store);
+  let mut frame = frame.fresh(&mut store).unwrap();
   frame.debug_print_status(&store);
   store.debug_print_digest();
   {
@@ -17,7 +17,7 @@ fn main() {
   }
   frame.debug_print_status(&store);
   store.debug_print_digest();
-  frame.commit(&mut store);
+  frame.commit(&mut store).unwrap();
   frame.debug_print_status(&store);
   store.debug_print_digest();
 }
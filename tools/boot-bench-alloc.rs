@@ -0,0 +1,68 @@
+extern crate pythia;
+
+use pythia::clock::{Timestamp};
+use pythia::interp::*;
+use pythia::test_data::*;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Counts the heap allocations (and bytes) of the interp test modules,
+// e.g. to compare tuple layouts (see `TUPLE_INLINE_LEN`).
+struct CountingAlloc;
+
+static ALLOC_CT: AtomicUsize = AtomicUsize::new(0);
+static ALLOC_SZ: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOC_CT.fetch_add(1, Ordering::Relaxed);
+    ALLOC_SZ.fetch_add(layout.size(), Ordering::Relaxed);
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    ALLOC_CT.fetch_add(1, Ordering::Relaxed);
+    ALLOC_SZ.fetch_add(new_size.saturating_sub(layout.size()), Ordering::Relaxed);
+    System.realloc(ptr, layout, new_size)
+  }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn main() {
+  let test_data_cfg = TestDataConfig::interp_tests();
+  println!("DEBUG: boot: test data config = {:?}", test_data_cfg);
+  println!("DEBUG: boot: tuple inline len = {}", TUPLE_INLINE_LEN);
+  let mut total_ct = 0;
+  let mut total_sz = 0;
+  for key in test_data_cfg.keys().iter() {
+    let src = test_data_cfg.get_source(key);
+    let mut interp = FastInterp::default();
+    if let Err(check) = interp.pre_init() {
+      println!("# {:?}: pre_init: check = {:?}", key, check);
+      continue;
+    }
+    if let Err(check) = interp.cold_start(&src) {
+      println!("# {:?}: cold_start: check = {:?}", key, check);
+      continue;
+    }
+    let ct0 = ALLOC_CT.load(Ordering::Relaxed);
+    let sz0 = ALLOC_SZ.load(Ordering::Relaxed);
+    let t0 = Timestamp::fresh();
+    let yield_ = interp.interp_();
+    let t1 = Timestamp::fresh();
+    let ct = ALLOC_CT.load(Ordering::Relaxed) - ct0;
+    let sz = ALLOC_SZ.load(Ordering::Relaxed) - sz0;
+    total_ct += ct;
+    total_sz += sz;
+    println!("{:?}: allocs={} bytes={} elapsed={} yield={:?}",
+        key, ct, sz, t1 - t0, yield_);
+  }
+  println!("total: allocs={} bytes={}", total_ct, total_sz);
+}
@@ -40,6 +40,11 @@ fn _interp(src: &str) -> TestResult {
     Ok(yield_) => yield_
   };
   let t2 = Timestamp::fresh();
+  if yield_ == Yield_::Raise {
+    if let Some(err) = interp.render_error() {
+      println!("{}", err);
+    }
+  }
   return TestResult::OK(t1-t0, t2-t1, yield_);
 }
 
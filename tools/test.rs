@@ -0,0 +1,23 @@
+extern crate pythia;
+
+use pythia::tap::{TAPProver};
+use pythia::testing::{run_dir};
+
+use std::env::{args};
+use std::io::{Write, stdout};
+use std::process::{exit};
+
+fn main() {
+  let argv: Vec<_> = args().collect();
+  if argv.len() != 2 {
+    eprintln!("usage: {} <test-dir>", argv[0]);
+    exit(2);
+  }
+  let report = run_dir(&argv[1]);
+  let mut out = stdout();
+  report.prove(&mut out).unwrap();
+  out.flush().unwrap();
+  if !report.is_ok() {
+    exit(1);
+  }
+}